@@ -26,7 +26,7 @@ use std::time::{Duration, Instant};
 
 use futures::{FutureExt, StreamExt, join};
 use futures_timer::Delay;
-use ironposh_async::{HttpClient, RemoteAsyncPowershellClient};
+use ironposh_async::{HttpClient, RemoteAsyncPowershellClient, SessionHooks};
 use ironposh_client_core::connector::active_session::UserEvent;
 use ironposh_client_core::connector::connection_pool::TrySend;
 use ironposh_client_core::connector::http::HttpResponseTargeted;
@@ -131,11 +131,6 @@ fn extract_command_id(body: &str) -> Option<Uuid> {
     extract_between(body, "CommandId=\"", '"').and_then(|s| s.parse().ok())
 }
 
-fn extract_message_id(body: &str) -> Option<String> {
-    let inner = extract_between(body, "<a:MessageID>", '<')?;
-    Some(inner.trim().to_owned())
-}
-
 // ── Fake server behavior ────────────────────────────────────────────────────
 
 /// Newtype so `HttpClient` can be implemented for a shared server (orphan rule).
@@ -162,13 +157,16 @@ impl HttpClient for SharedServer {
                 st.records.request_count += 1;
             }
 
+            // Every response echoes the request's MessageID as RelatesTo.
+            let relates_to = fake_server::extract_message_id(&body)
+                .expect("every request must carry a MessageID");
             let action = extract_action(&body);
             let xml = match action {
                 "create" => {
                     let rpid = fake_server::extract_shell_id(&body);
                     server.state.lock().unwrap().rpid = Some(rpid);
                     include_str!("../../ironposh-client-core/tests/resources/resource_created.xml")
-                        .to_owned()
+                        .replace("uuid:622d032a-f9b3-498d-b447-1359c3bb14f3", &relates_to)
                 }
                 "command" => {
                     let now = Instant::now();
@@ -192,13 +190,11 @@ impl HttpClient for SharedServer {
                             completed: false,
                         },
                     );
-                    fake_server::command_response_xml(command_id)
+                    fake_server::command_response_xml(&relates_to, command_id)
                 }
                 "signal" => {
                     let now = Instant::now();
                     let command_id = extract_command_id(&body);
-                    let relates_to =
-                        extract_message_id(&body).expect("Signal request must carry a MessageID");
                     let mut st = server.state.lock().unwrap();
                     st.records.signal_arrived_at.push(now);
                     // Killing a live pipeline makes it report Completed on the
@@ -210,8 +206,8 @@ impl HttpClient for SharedServer {
                     }
                     fake_server::signal_response_xml(&relates_to)
                 }
-                "receive" => server.handle_receive(&body).await,
-                _ => fake_server::timeout_fault_xml(),
+                "receive" => server.handle_receive(&body, &relates_to).await,
+                _ => fake_server::timeout_fault_xml(&relates_to),
             };
 
             Ok(fake_server::xml_response(conn_id, xml))
@@ -223,7 +219,7 @@ impl FakeWinRmServer {
     /// Serve a Receive: respond the moment scripted data is due, otherwise
     /// hold the request until its own OperationTimeout and return a
     /// `w:TimedOut` fault — exactly like a real WSMan server.
-    async fn handle_receive(&self, body: &str) -> String {
+    async fn handle_receive(&self, body: &str, relates_to: &str) -> String {
         let op_timeout = extract_operation_timeout(body);
         if std::env::var("BENCH_DEBUG").is_ok() {
             eprintln!(
@@ -254,6 +250,7 @@ impl FakeWinRmServer {
                     .runspace_state(RunspacePoolStateValue::Opened)
                     .build();
                 return fake_server::receive_response_xml(
+                    relates_to,
                     rpid,
                     &[&session_capability, &private_data, &opened],
                 );
@@ -265,7 +262,7 @@ impl FakeWinRmServer {
             Delay::new(op_timeout).await;
             let mut st = self.state.lock().unwrap();
             st.records.timeout_count += 1;
-            return fake_server::timeout_fault_xml();
+            return fake_server::timeout_fault_xml(relates_to);
         };
 
         loop {
@@ -311,6 +308,7 @@ impl FakeWinRmServer {
                     }
 
                     let xml = fake_server::pipeline_receive_response_xml(
+                        relates_to,
                         rpid,
                         command_id,
                         &messages,
@@ -347,7 +345,7 @@ impl FakeWinRmServer {
                         Delay::new(sleep).await;
                         let mut st = self.state.lock().unwrap();
                         st.records.timeout_count += 1;
-                        return fake_server::timeout_fault_xml();
+                        return fake_server::timeout_fault_xml(relates_to);
                     }
                     Delay::new(wake_at.saturating_duration_since(now)).await;
                 }
@@ -427,7 +425,9 @@ async fn run_scenario(
     let server = std::sync::Arc::new(FakeWinRmServer::new(scripts.clone()));
     let (client, host_io, mut session_events, task) = RemoteAsyncPowershellClient::open_task_serial(
         serial_config(),
+        None,
         SharedServer(server.clone()),
+        SessionHooks::default(),
     );
 
     let started = Instant::now();
@@ -0,0 +1,183 @@
+//! Append-only audit trail of invoked pipelines, and the interceptor that can
+//! approve, deny or rewrite them first.
+//!
+//! One [`AuditEntry`] is appended per pipeline once its outcome is known.
+//! Denied pipelines are recorded too.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use ironposh_client_core::connector::WinRmConfig;
+use ironposh_client_core::pipeline::PipelineSpec;
+
+use crate::clock::SystemTime;
+
+/// What an [`InvokeInterceptor`] does with a pipeline about to be invoked.
+#[derive(Debug, Clone)]
+pub enum InvokeDecision {
+    /// Invoke the pipeline as is.
+    Allow,
+    /// Do not invoke it. The caller's stream gets an error record carrying
+    /// `reason`, then ends.
+    Deny(String),
+    /// Invoke this pipeline instead.
+    Modify(PipelineSpec),
+}
+
+/// Command approval hook, consulted before every pipeline is sent.
+///
+/// Runs on the connection task, so `on_before_invoke` must not block.
+pub trait InvokeInterceptor: Send + Sync {
+    fn on_before_invoke(&self, pipeline: &PipelineSpec) -> InvokeDecision;
+}
+
+/// How an audited pipeline ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditStatus {
+    /// Finished without reporting an error record.
+    Completed,
+    /// Finished after reporting at least one error record.
+    Failed,
+    /// Stopped by its `PipelineOptions::timeout`.
+    TimedOut,
+    /// Rejected by the [`InvokeInterceptor`]; never sent.
+    Denied { reason: String },
+}
+
+/// One line of the audit trail.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// When the pipeline was invoked (or denied).
+    pub timestamp: SystemTime,
+    /// The authenticating user, when the configuration names one.
+    pub user: Option<String>,
+    /// The WinRM server, as `host:port`.
+    pub target: String,
+    pub pipeline_id: uuid::Uuid,
+    /// The pipeline as sent, after any interceptor rewrite.
+    pub command: String,
+    pub status: AuditStatus,
+}
+
+/// Destination of the audit trail. Entries are only ever appended.
+pub trait AuditSink: Send + Sync {
+    fn append(&self, entry: AuditEntry);
+}
+
+/// [`AuditSink`] keeping the entries in memory.
+#[derive(Debug, Default)]
+pub struct MemoryAuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl MemoryAuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A snapshot of the entries appended so far, oldest first.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl AuditSink for MemoryAuditLog {
+    fn append(&self, entry: AuditEntry) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(entry);
+    }
+}
+
+struct PendingEntry {
+    timestamp: SystemTime,
+    command: String,
+    failed: bool,
+}
+
+/// Tracks invoked pipelines until their outcome is known, then appends
+/// their entry to the sink.
+pub(crate) struct Auditor {
+    sink: Arc<dyn AuditSink>,
+    user: Option<String>,
+    target: String,
+    pending: Mutex<HashMap<uuid::Uuid, PendingEntry>>,
+}
+
+impl Auditor {
+    pub(crate) fn new(sink: Arc<dyn AuditSink>, config: &WinRmConfig) -> Self {
+        Self {
+            sink,
+            user: config.authentication.user_name().map(str::to_owned),
+            target: format!("{}:{}", config.server.0, config.server.1),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn invoked(&self, pipeline_id: uuid::Uuid, spec: &PipelineSpec) {
+        self.pending().insert(
+            pipeline_id,
+            PendingEntry {
+                timestamp: SystemTime::now(),
+                command: spec.display_text(),
+                failed: false,
+            },
+        );
+    }
+
+    pub(crate) fn denied(&self, pipeline_id: uuid::Uuid, spec: &PipelineSpec, reason: String) {
+        self.append(
+            pipeline_id,
+            PendingEntry {
+                timestamp: SystemTime::now(),
+                command: spec.display_text(),
+                failed: false,
+            },
+            AuditStatus::Denied { reason },
+        );
+    }
+
+    pub(crate) fn error_reported(&self, pipeline_id: uuid::Uuid) {
+        if let Some(entry) = self.pending().get_mut(&pipeline_id) {
+            entry.failed = true;
+        }
+    }
+
+    pub(crate) fn finished(&self, pipeline_id: uuid::Uuid) {
+        let Some(entry) = self.pending().remove(&pipeline_id) else {
+            return;
+        };
+        let status = if entry.failed {
+            AuditStatus::Failed
+        } else {
+            AuditStatus::Completed
+        };
+        self.append(pipeline_id, entry, status);
+    }
+
+    pub(crate) fn timed_out(&self, pipeline_id: uuid::Uuid) {
+        let Some(entry) = self.pending().remove(&pipeline_id) else {
+            return;
+        };
+        self.append(pipeline_id, entry, AuditStatus::TimedOut);
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<uuid::Uuid, PendingEntry>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn append(&self, pipeline_id: uuid::Uuid, entry: PendingEntry, status: AuditStatus) {
+        self.sink.append(AuditEntry {
+            timestamp: entry.timestamp,
+            user: self.user.clone(),
+            target: self.target.clone(),
+            pipeline_id,
+            command: entry.command,
+            status,
+        });
+    }
+}
@@ -1,13 +1,17 @@
+use std::sync::Arc;
+
 use anyhow::Context;
-use futures::SinkExt;
 use futures::channel::mpsc::Receiver;
+use futures::{SinkExt, StreamExt};
+use ironposh_client_core::RemoteError;
 use ironposh_client_core::connector::{WinRmConfig, active_session::UserEvent};
-use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
-use ironposh_client_core::powershell::PipelineHandle;
+use ironposh_client_core::pipeline::{PipelineCommand, PipelineOptions, PipelineSpec};
+use ironposh_client_core::powershell::{PipelineHandle, SignalCode};
+use ironposh_psrp::{PipelineOutput, PsValue};
 use tracing::instrument;
 
 use crate::{
-    HttpClient,
+    AuditSink, HostCallPolicy, HttpClient, InvokeInterceptor,
     connection::{self, ConnectionHandle},
 };
 
@@ -20,6 +24,60 @@ pub struct RemoteAsyncPowershellClient {
     supports_disconnect: bool,
 }
 
+/// How [`RemoteAsyncPowershellClient::stop_pipeline`] stops a running pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopPolicy {
+    /// Send `terminate` right away.
+    Force,
+    /// Send PowerShell's `crtl_c` first, like console PowerShell does, and
+    /// escalate to `terminate` if the pipeline is still running after
+    /// `escalate_after`.
+    Graceful { escalate_after: std::time::Duration },
+}
+
+/// What a pipeline produced, as collected by [`RemoteAsyncPowershellClient::invoke`].
+#[derive(Debug, Default)]
+pub struct InvokeOutput {
+    pub output: Vec<PipelineOutput>,
+    /// Non-terminating errors, in arrival order.
+    pub errors: Vec<RemoteError>,
+}
+
+/// Optional hooks installed on a session when it is opened.
+#[derive(Clone, Default)]
+pub struct SessionHooks {
+    /// Sees every host call before it reaches `host_io`; may deny, answer or
+    /// rewrite it.
+    pub host_policy: Option<Arc<dyn HostCallPolicy>>,
+    /// Approves, denies or rewrites every pipeline before it is sent.
+    pub invoke_interceptor: Option<Arc<dyn InvokeInterceptor>>,
+    /// Receives one entry per invoked (or denied) pipeline.
+    pub audit_log: Option<Arc<dyn AuditSink>>,
+}
+
+impl SessionHooks {
+    #[must_use]
+    pub fn with_host_policy(mut self, policy: impl HostCallPolicy + 'static) -> Self {
+        self.host_policy = Some(Arc::new(policy));
+        self
+    }
+
+    #[must_use]
+    pub fn with_invoke_interceptor(
+        mut self,
+        interceptor: impl InvokeInterceptor + 'static,
+    ) -> Self {
+        self.invoke_interceptor = Some(Arc::new(interceptor));
+        self
+    }
+
+    #[must_use]
+    pub fn with_audit_log(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_log = Some(sink);
+        self
+    }
+}
+
 /// Everything produced by [`RemoteAsyncPowershellClient::open_task`].
 ///
 /// Bundles the client handle, the host I/O and event channels, and the
@@ -48,13 +106,17 @@ impl RemoteAsyncPowershellClient {
     /// When `connect_shell_id` is set, the client attaches to that existing
     /// disconnected runspace pool shell (WSMan Connect / browser-refresh
     /// reattach) instead of creating a new one.
+    ///
+    /// `hooks` install the host call policy, the invoke interceptor and the
+    /// audit log; `SessionHooks::default()` installs none.
     pub fn open_task(
         config: WinRmConfig,
         connect_shell_id: Option<uuid::Uuid>,
         client: impl HttpClient,
+        hooks: SessionHooks,
     ) -> OpenedSession<impl std::future::Future<Output = anyhow::Result<()>>> {
         let (handle, host_io, session_event_rx, lifecycle_event_rx, task) =
-            connection::establish_connection(config, connect_shell_id, client);
+            connection::establish_connection(config, connect_shell_id, client, hooks);
 
         OpenedSession {
             client: Self {
@@ -73,9 +135,14 @@ impl RemoteAsyncPowershellClient {
     /// All WinRM operations are serialized through a single HTTP connection.
     /// Required when the transport only allows one connection per token
     /// (e.g. Devolutions Gateway with jti-based replay detection).
+    ///
+    /// When `connect_shell_id` is set, the client attaches to a shell left by
+    /// [`Self::suspend`] instead of creating a new one.
     pub fn open_task_serial(
         config: WinRmConfig,
+        connect_shell_id: Option<uuid::Uuid>,
         client: impl HttpClient,
+        hooks: SessionHooks,
     ) -> (
         Self,
         crate::HostIo,
@@ -86,7 +153,7 @@ impl RemoteAsyncPowershellClient {
         Self: Sized,
     {
         let (handle, host_io, session_event_rx, task) =
-            connection::establish_connection_serial(config, client);
+            connection::establish_connection_serial(config, connect_shell_id, client, hooks);
 
         (
             Self {
@@ -114,7 +181,12 @@ impl RemoteAsyncPowershellClient {
             .pipeline_input_tx
             .send(connection::PipelineInput::Invoke {
                 uuid: uuid::Uuid::new_v4(),
-                spec: PipelineSpec { commands },
+                spec: PipelineSpec {
+                    commands,
+                    add_to_history: false,
+                },
+                options: PipelineOptions::default(),
+                input: Vec::new(),
                 response_tx: tx,
             })
             .await
@@ -132,6 +204,18 @@ impl RemoteAsyncPowershellClient {
     /// Execute a PowerShell script and return raw PSRP output objects (no Out-String formatting).
     #[instrument(skip(self))]
     pub async fn send_script_raw(&mut self, script: String) -> anyhow::Result<Receiver<UserEvent>> {
+        self.send_script_raw_with_options(script, PipelineOptions::default())
+            .await
+    }
+
+    /// [`Self::send_script_raw`] with per-pipeline options. On timeout the
+    /// pipeline is stopped and the stream ends with `UserEvent::PipelineTimedOut`.
+    #[instrument(skip(self))]
+    pub async fn send_script_raw_with_options(
+        &mut self,
+        script: String,
+        options: PipelineOptions,
+    ) -> anyhow::Result<Receiver<UserEvent>> {
         let commands = vec![PipelineCommand::new_script(script)];
 
         let (tx, rx) = futures::channel::mpsc::channel(10);
@@ -140,7 +224,157 @@ impl RemoteAsyncPowershellClient {
             .pipeline_input_tx
             .send(connection::PipelineInput::Invoke {
                 uuid: uuid::Uuid::new_v4(),
-                spec: PipelineSpec { commands },
+                spec: PipelineSpec {
+                    commands,
+                    add_to_history: false,
+                },
+                options,
+                input: Vec::new(),
+                response_tx: tx,
+            })
+            .await
+            .context("Failed to send CreatePipeline operation")?;
+
+        self.handle
+            .pipeline_input_tx
+            .flush()
+            .await
+            .context("Failed to flush pipeline input")?;
+
+        Ok(rx)
+    }
+
+    /// Run `script` to completion (raw output, like [`Self::send_script_raw`])
+    /// and collect what it produced.
+    ///
+    /// With `options.stop_on_error` the first `ErrorRecord` stops the pipeline
+    /// and is returned as the error, a [`RemoteError`] reachable through
+    /// `downcast_ref`. Hitting `options.timeout` is an error as well.
+    ///
+    /// The whole output is held in memory until the pipeline ends, so
+    /// `ReceiveFlowConfig::max_pending_output_bytes` does not bound it: each
+    /// output is acknowledged as soon as it is collected, since holding the
+    /// acknowledgements back would stop the pipeline before it could finish.
+    /// Stream with [`Self::send_script_raw`] and [`Self::acknowledge_output`]
+    /// to keep memory bounded.
+    #[instrument(skip(self))]
+    pub async fn invoke(
+        &mut self,
+        script: String,
+        options: PipelineOptions,
+    ) -> anyhow::Result<InvokeOutput> {
+        let mut stream = self.send_script_raw_with_options(script, options).await?;
+
+        let mut collected = InvokeOutput::default();
+        while let Some(event) = stream.next().await {
+            match event {
+                UserEvent::PipelineOutput {
+                    pipeline,
+                    output,
+                    data_len,
+                } => {
+                    collected.output.push(output);
+                    self.acknowledge_output(pipeline, data_len).await?;
+                }
+                UserEvent::ErrorRecord { error_record, .. } => {
+                    let error = RemoteError::from(error_record);
+                    if options.stop_on_error {
+                        return Err(error.into());
+                    }
+                    collected.errors.push(error);
+                }
+                UserEvent::PipelineTimedOut { timeout, .. } => {
+                    anyhow::bail!("pipeline timed out after {timeout:?}");
+                }
+                UserEvent::PipelineCreated { .. }
+                | UserEvent::PipelineFinished { .. }
+                | UserEvent::PipelineRecord { .. } => {}
+            }
+        }
+
+        Ok(collected)
+    }
+
+    /// Run a whole multi-line script (functions, here-strings, several
+    /// statements) as a single pipeline, formatted like [`Self::send_script`].
+    #[instrument(skip(self, script))]
+    pub async fn run_script_file(&mut self, script: &str) -> anyhow::Result<Receiver<UserEvent>> {
+        let mut spec = PipelineSpec::script_file(script);
+        spec.commands.push(PipelineCommand::new_output_stream());
+
+        let (tx, rx) = futures::channel::mpsc::channel(10);
+
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Invoke {
+                uuid: uuid::Uuid::new_v4(),
+                spec,
+                options: PipelineOptions::default(),
+                input: Vec::new(),
+                response_tx: tx,
+            })
+            .await
+            .context("Failed to send CreatePipeline operation")?;
+
+        self.handle
+            .pipeline_input_tx
+            .flush()
+            .await
+            .context("Failed to flush pipeline input")?;
+
+        Ok(rx)
+    }
+
+    /// Invoke a caller-built pipeline, e.g. one with
+    /// [`PipelineSpec::add_to_history`] set for an interactive command.
+    #[instrument(skip(self))]
+    pub async fn send_pipeline(
+        &mut self,
+        spec: PipelineSpec,
+        options: PipelineOptions,
+    ) -> anyhow::Result<Receiver<UserEvent>> {
+        let (tx, rx) = futures::channel::mpsc::channel(10);
+
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Invoke {
+                uuid: uuid::Uuid::new_v4(),
+                spec,
+                options,
+                input: Vec::new(),
+                response_tx: tx,
+            })
+            .await
+            .context("Failed to send CreatePipeline operation")?;
+
+        self.handle
+            .pipeline_input_tx
+            .flush()
+            .await
+            .context("Failed to flush pipeline input")?;
+
+        Ok(rx)
+    }
+
+    /// [`Self::send_pipeline`] feeding `input` to the pipeline as PIPELINE_INPUT,
+    /// one Send per object. Paired with [`PipelineSpec::script_from_input`] it
+    /// runs a script too large for a single CreatePipeline envelope.
+    #[instrument(skip(self, input), fields(input_len = input.len()))]
+    pub async fn send_pipeline_with_input(
+        &mut self,
+        spec: PipelineSpec,
+        input: Vec<PsValue>,
+        options: PipelineOptions,
+    ) -> anyhow::Result<Receiver<UserEvent>> {
+        let (tx, rx) = futures::channel::mpsc::channel(10);
+
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Invoke {
+                uuid: uuid::Uuid::new_v4(),
+                spec,
+                options,
+                input,
                 response_tx: tx,
             })
             .await
@@ -155,6 +389,24 @@ impl RemoteAsyncPowershellClient {
         Ok(rx)
     }
 
+    /// Set `variables` in the session's environment (`$env:NAME`), e.g. ones
+    /// forwarded from the local environment when the session starts. Names
+    /// and values travel as a pipeline parameter, so no quoting is needed.
+    /// The stream ends once they are set; a variable that could not be set
+    /// shows up as an `ErrorRecord`.
+    #[instrument(skip(self, variables), fields(count = variables.len()))]
+    pub async fn set_environment(
+        &mut self,
+        variables: &[(String, String)],
+    ) -> anyhow::Result<Receiver<UserEvent>> {
+        let spec = PipelineSpec::set_environment(
+            variables
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        self.send_pipeline(spec, PipelineOptions::default()).await
+    }
+
     #[instrument(skip(self))]
     pub async fn send_command(&mut self, command: String) -> anyhow::Result<Receiver<UserEvent>> {
         let (tx, rx) = futures::channel::mpsc::channel(10);
@@ -165,7 +417,10 @@ impl RemoteAsyncPowershellClient {
                 uuid: uuid::Uuid::new_v4(),
                 spec: PipelineSpec {
                     commands: vec![PipelineCommand::new_command(command)],
+                    add_to_history: false,
                 },
+                options: PipelineOptions::default(),
+                input: Vec::new(),
                 response_tx: tx,
             })
             .await
@@ -184,6 +439,50 @@ impl RemoteAsyncPowershellClient {
         Ok(())
     }
 
+    /// Send a signal (`terminate`, PowerShell's `crtl_c`, or the WinRS
+    /// `ctrl_c`/`ctrl_break`) to a running pipeline.
+    pub async fn signal_pipeline(
+        &mut self,
+        pipeline_handle: PipelineHandle,
+        code: SignalCode,
+    ) -> anyhow::Result<()> {
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Signal {
+                pipeline_handle,
+                code,
+                escalate_after: None,
+            })
+            .await
+            .context("Failed to send SignalPipeline operation")?;
+
+        Ok(())
+    }
+
+    /// Stop a running pipeline according to `policy`.
+    pub async fn stop_pipeline(
+        &mut self,
+        pipeline_handle: PipelineHandle,
+        policy: StopPolicy,
+    ) -> anyhow::Result<()> {
+        match policy {
+            StopPolicy::Force => self.kill_pipeline(pipeline_handle).await,
+            StopPolicy::Graceful { escalate_after } => {
+                self.handle
+                    .pipeline_input_tx
+                    .send(connection::PipelineInput::Signal {
+                        pipeline_handle,
+                        code: SignalCode::PowerShellCtrlC,
+                        escalate_after: Some(escalate_after),
+                    })
+                    .await
+                    .context("Failed to send SignalPipeline operation")?;
+
+                Ok(())
+            }
+        }
+    }
+
     /// Disconnect the runspace pool shell (MS-WSMV Disconnect).
     ///
     /// Completion is reported through the `PoolLifecycleEvent` channel returned
@@ -231,6 +530,85 @@ impl RemoteAsyncPowershellClient {
 
         Ok(())
     }
+
+    /// Suspend the session: disconnect the runspace pool shell and end the
+    /// session, leaving the shell on the server. The session events report
+    /// its id with `SessionEvent::Suspended`; pass it to
+    /// [`Self::open_task_serial`] to attach to the shell again.
+    ///
+    /// Serial session loop only; parallel-mode clients use
+    /// [`Self::disconnect`], which keeps the session for a later reconnect.
+    #[instrument(skip(self))]
+    pub async fn suspend(&mut self) -> anyhow::Result<()> {
+        if self.supports_disconnect {
+            anyhow::bail!("suspend is for the serial session loop; use disconnect instead");
+        }
+
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Suspend)
+            .await
+            .context("Failed to send Suspend operation")?;
+
+        Ok(())
+    }
+
+    /// Power saving, e.g. while the client window is out of focus: the serial
+    /// session loop lets an idle Receive wait on the server for up to five
+    /// seconds instead of one. Output still arrives as soon as it is written;
+    /// only the empty round trips get rarer. The parallel loop already
+    /// long-polls and ignores this.
+    pub async fn set_power_saving(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::SetPowerSaving { enabled })
+            .await
+            .context("Failed to send SetPowerSaving operation")?;
+
+        Ok(())
+    }
+
+    /// Release `bytes` of output the caller has consumed from `pipeline_handle`,
+    /// normally the `data_len` of each `UserEvent::PipelineOutput`.
+    ///
+    /// Only matters when `WinRmConfig::receive_flow` sets
+    /// `max_pending_output_bytes`: a pipeline over that budget is not polled
+    /// again until enough of its output is acknowledged. Flow control only
+    /// bounds memory for callers of the streaming APIs that acknowledge after
+    /// consuming each output; [`Self::invoke`] acknowledges what it buffers by
+    /// itself.
+    pub async fn acknowledge_output(
+        &mut self,
+        pipeline_handle: PipelineHandle,
+        bytes: usize,
+    ) -> anyhow::Result<()> {
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::AcknowledgeOutput {
+                pipeline_handle,
+                bytes,
+            })
+            .await
+            .context("Failed to send AcknowledgeOutput operation")?;
+
+        Ok(())
+    }
+
+    /// Close the session: delete the runspace pool shell (WS-Transfer Delete).
+    ///
+    /// Pipelines still running end with the shell, and the connection task
+    /// resolves once the server has answered. Supported by both session
+    /// loops.
+    #[instrument(skip(self))]
+    pub async fn close(&mut self) -> anyhow::Result<()> {
+        self.handle
+            .pipeline_input_tx
+            .send(connection::PipelineInput::Close)
+            .await
+            .context("Failed to send Close operation")?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
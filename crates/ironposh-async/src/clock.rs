@@ -9,3 +9,19 @@ pub type Instant = web_time::Instant;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub type Instant = std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub type SystemTime = web_time::SystemTime;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type SystemTime = std::time::SystemTime;
+
+/// Local wall-clock time in Unix seconds, for comparing against server
+/// timestamps. A clock set before 1970 reads as 0.
+pub fn unix_now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX)
+        })
+}
@@ -1,18 +1,34 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use futures::{SinkExt, StreamExt, channel::mpsc, join, try_join};
+use futures::{
+    FutureExt, SinkExt, StreamExt,
+    channel::{mpsc, oneshot},
+    future::{self, BoxFuture, Either, Fuse},
+    join,
+    stream::FuturesUnordered,
+    try_join,
+};
+use futures_timer::Delay;
 use ironposh_client_core::{
+    PwshCoreError,
     connector::{
-        Connector, ConnectorStepResult, UserOperation, WinRmConfig, active_session::UserEvent,
+        Connector, ConnectorStepResult, UserOperation, WinRmConfig,
+        active_session::UserEvent,
+        config::{IdleAction, IdlePolicy},
     },
-    pipeline::PipelineSpec,
-    powershell::PipelineHandle,
+    pipeline::{PipelineOptions, PipelineSpec},
+    powershell::{PipelineHandle, SignalCode},
 };
+use ironposh_psrp::PsValue;
 use tracing::{Instrument, Level, debug, info, info_span, span, trace, warn};
 
+use crate::audit::{Auditor, InvokeDecision, InvokeInterceptor};
 use crate::clock::Instant;
-use crate::{HostIo, HostSubmitter, HttpClient, session, session_serial};
+use crate::health::{self, Health, TimedClient};
+use crate::host_policy;
+use crate::recovery::{self, Recovery};
+use crate::{HostIo, HostSubmitter, HttpClient, SessionHooks, session, session_serial};
 
 /// Run the connector handshake loop: step through authentication until Connected.
 /// When `connect_shell_id` is set, the connector attaches to that existing
@@ -111,19 +127,101 @@ async fn run_handshake<C: HttpClient>(
     }
 }
 
+/// How long a session that ended on a broken runspace pool waits for the
+/// multiplexer to hand the failed pipelines their last events.
+const BROKEN_POOL_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The reason, if `e` ended the session because the server broke the runspace pool.
+fn broken_pool_reason(e: &anyhow::Error) -> Option<String> {
+    e.chain()
+        .find_map(|e| e.downcast_ref::<PwshCoreError>())
+        .and_then(|e| match e {
+            PwshCoreError::RunspacePoolBroken { reason } => Some(reason.clone()),
+            _ => None,
+        })
+}
+
+/// Report how the session loop ended. A broken pool first lets the multiplexer
+/// drain the failed pipelines' events: `try_join!` drops it as soon as the
+/// session task errors.
+async fn report_session_end(
+    result: &anyhow::Result<()>,
+    drained_rx: oneshot::Receiver<()>,
+    session_event_tx: &mpsc::UnboundedSender<crate::SessionEvent>,
+) {
+    let event = match result {
+        Ok(()) => crate::SessionEvent::ActiveSessionEnded,
+        Err(e) => match broken_pool_reason(e) {
+            Some(reason) => {
+                futures::select! {
+                    _ = drained_rx.fuse() => {}
+                    () = Delay::new(BROKEN_POOL_DRAIN_TIMEOUT).fuse() => {
+                        warn!("timed out delivering the final events of a broken session");
+                    }
+                }
+                crate::SessionEvent::Broken { reason }
+            }
+            None => crate::SessionEvent::Error(e.to_string()),
+        },
+    };
+    let _ = session_event_tx.unbounded_send(event);
+}
+
+/// Run `session`, sending heartbeats until it ends.
+async fn with_heartbeats<T>(
+    session: impl std::future::Future<Output = T>,
+    heartbeats: impl std::future::Future<Output = std::convert::Infallible>,
+) -> T {
+    futures::pin_mut!(session, heartbeats);
+    match future::select(session, heartbeats).await {
+        Either::Left((res, _)) => res,
+        Either::Right((never, _)) => match never {},
+    }
+}
+
+/// Session-wide policies applied by the pipeline multiplexer.
+#[derive(Default)]
+struct MultiplexerPolicies {
+    idle: Option<IdlePolicy>,
+    interceptor: Option<Arc<dyn InvokeInterceptor>>,
+    auditor: Option<Arc<Auditor>>,
+    recovery: Option<Arc<Recovery>>,
+}
+
 /// Build the pipeline multiplexer task that routes events between user input and server output.
+/// `drained_tx` fires once the session loop is gone and its last events were forwarded.
+/// With an idle policy, the idle action is announced on `session_event_tx` and
+/// issued once no pipeline input arrived for `after` and no pipeline is running.
 fn build_pipeline_multiplexer(
     mut user_input_tx: mpsc::Sender<UserOperation>,
     mut server_output_rx: mpsc::Receiver<UserEvent>,
     mut pipeline_input_rx: mpsc::Receiver<PipelineInput>,
+    drained_tx: oneshot::Sender<()>,
+    policies: MultiplexerPolicies,
+    session_event_tx: mpsc::UnboundedSender<crate::SessionEvent>,
     span_prefix: &'static str,
 ) -> impl std::future::Future<Output = anyhow::Result<()>> {
+    let MultiplexerPolicies {
+        idle: idle_policy,
+        interceptor,
+        auditor,
+        recovery,
+    } = policies;
+    let server_auditor = auditor.clone();
+    let server_recovery = recovery.clone();
     let pipeline_map = Arc::new(futures::lock::Mutex::new(std::collections::HashMap::<
         uuid::Uuid,
         mpsc::Sender<UserEvent>,
     >::new()));
 
     let pipeline_map_clone = Arc::clone(&pipeline_map);
+    // Pipelines invoked with `PipelineOptions::stop_on_error` that have not
+    // reported an error yet.
+    let stop_on_error = Arc::new(futures::lock::Mutex::new(std::collections::HashSet::<
+        uuid::Uuid,
+    >::new()));
+    let stop_on_error_clone = Arc::clone(&stop_on_error);
+    let mut stop_tx = user_input_tx.clone();
 
     let server_span_name = if span_prefix == "Serial" {
         "SerialPipelineServerHandlerLoop"
@@ -141,9 +239,21 @@ fn build_pipeline_multiplexer(
             while let Some(server_output_event) = server_output_rx.next().await {
                 trace!(?server_output_event, "Received server output event");
                 let uuid = server_output_event.pipeline_id();
+                if let Some(recovery) = &server_recovery {
+                    if recovery.absorb_init_event(&server_output_event) {
+                        continue;
+                    }
+                    recovery.answered(&server_output_event);
+                }
                 let mut map = pipeline_map.lock().await;
                 if let Some(sender) = map.get_mut(&uuid) {
+                    if sender.is_closed() {
+                        // Timed out: the stream already ended, drop the stop's aftermath.
+                        trace!(pipeline_id = %uuid, "dropping event for a closed pipeline stream");
+                        continue;
+                    }
                     let close = matches!(server_output_event, UserEvent::PipelineFinished { .. });
+                    let failed = matches!(server_output_event, UserEvent::ErrorRecord { .. });
 
                     if let Err(e) = sender.clone().send(server_output_event).await {
                         warn!(%e, pipeline_id = %uuid, "Failed to forward event to pipeline stream");
@@ -153,30 +263,202 @@ fn build_pipeline_multiplexer(
                         debug!(pipeline_id = %uuid, "Closing stream for finished pipeline");
                         sender.close_channel();
                     }
+                    drop(map);
+
+                    if let Some(auditor) = &server_auditor {
+                        if close {
+                            auditor.finished(uuid);
+                        } else if failed {
+                            auditor.error_reported(uuid);
+                        }
+                    }
+
+                    if close {
+                        stop_on_error.lock().await.remove(&uuid);
+                    } else if failed && stop_on_error.lock().await.remove(&uuid) {
+                        info!(pipeline_id = %uuid, "pipeline reported an error, stopping it (stop_on_error)");
+                        stop_tx
+                            .send(UserOperation::KillPipeline {
+                                pipeline: PipelineHandle::new(uuid),
+                            })
+                            .await
+                            .context("Failed to forward stop-on-error KillPipeline operation")?;
+                    }
                 } else {
                     warn!(pipeline_id = %uuid, "No stream found for pipeline event");
                 }
             }
+            let _ = drained_tx.send(());
 
             Ok::<(), anyhow::Error>(())
         }
         .instrument(span!(Level::INFO, "PipelineServerLoop", prefix = server_span_name));
 
         let pipeline_map = pipeline_map_clone;
+        let stop_on_error = stop_on_error_clone;
         let from_user = async move {
-            while let Some(input) = pipeline_input_rx.next().await {
+            // Pending Ctrl+C → Terminate escalations, resolving to the pipeline id.
+            let mut escalations: FuturesUnordered<BoxFuture<'static, uuid::Uuid>> =
+                FuturesUnordered::new();
+            // Pending `PipelineOptions::timeout` deadlines.
+            let mut deadlines: FuturesUnordered<
+                BoxFuture<'static, (uuid::Uuid, std::time::Duration)>,
+            > = FuturesUnordered::new();
+            let mut idle_timer =
+                idle_policy.map_or_else(Fuse::terminated, |policy| Delay::new(policy.after).fuse());
+            loop {
+                let input = futures::select! {
+                    input = pipeline_input_rx.next() => match input {
+                        Some(input) => input,
+                        None => break,
+                    },
+                    () = idle_timer => {
+                        let Some(policy) = idle_policy else {
+                            continue;
+                        };
+                        let running = pipeline_map
+                            .lock()
+                            .await
+                            .values()
+                            .any(|tx| !tx.is_closed());
+                        if running {
+                            // A long-running pipeline is not inactivity.
+                            trace!("idle timeout elapsed with pipelines running, re-arming");
+                            idle_timer = Delay::new(policy.after).fuse();
+                            continue;
+                        }
+                        info!(action = ?policy.action, after = ?policy.after, "session idle, applying idle policy");
+                        let _ = session_event_tx
+                            .unbounded_send(crate::SessionEvent::Idle { action: policy.action });
+                        match policy.action {
+                            IdleAction::Disconnect => {
+                                user_input_tx
+                                    .send(UserOperation::Disconnect)
+                                    .await
+                                    .context("Failed to forward idle Disconnect operation")?;
+                                continue;
+                            }
+                            IdleAction::Close => {
+                                // The session ends with the shell; stop taking input.
+                                user_input_tx
+                                    .send(UserOperation::Close)
+                                    .await
+                                    .context("Failed to forward idle Close operation")?;
+                                break;
+                            }
+                        }
+                    },
+                    pipeline_id = escalations.select_next_some() => {
+                        // The stream is closed once the pipeline finishes; only a
+                        // pipeline that ignored the graceful stop is escalated.
+                        let still_running = pipeline_map
+                            .lock()
+                            .await
+                            .get(&pipeline_id)
+                            .is_some_and(|tx| !tx.is_closed());
+                        if still_running {
+                            info!(%pipeline_id, "pipeline ignored graceful stop, escalating to terminate");
+                            user_input_tx
+                                .send(UserOperation::KillPipeline {
+                                    pipeline: PipelineHandle::new(pipeline_id),
+                                })
+                                .await
+                                .context("Failed to forward escalated KillPipeline operation")?;
+                        }
+                        continue;
+                    },
+                    (pipeline_id, timeout) = deadlines.select_next_some() => {
+                        let mut map = pipeline_map.lock().await;
+                        let Some(sender) = map.get_mut(&pipeline_id).filter(|tx| !tx.is_closed())
+                        else {
+                            continue;
+                        };
+                        warn!(%pipeline_id, ?timeout, "pipeline timed out, stopping it");
+                        if let Err(e) = sender
+                            .send(UserEvent::PipelineTimedOut {
+                                pipeline: PipelineHandle::new(pipeline_id),
+                                timeout,
+                            })
+                            .await
+                        {
+                            warn!(%e, %pipeline_id, "Failed to report pipeline timeout");
+                        }
+                        sender.close_channel();
+                        drop(map);
+                        stop_on_error.lock().await.remove(&pipeline_id);
+                        if let Some(auditor) = &auditor {
+                            auditor.timed_out(pipeline_id);
+                        }
+
+                        user_input_tx
+                            .send(UserOperation::KillPipeline {
+                                pipeline: PipelineHandle::new(pipeline_id),
+                            })
+                            .await
+                            .context("Failed to forward timed-out KillPipeline operation")?;
+                        continue;
+                    },
+                };
                 debug!(?input, "Received pipeline input");
+                if let Some(policy) = idle_policy {
+                    idle_timer = Delay::new(policy.after).fuse();
+                }
                 match input {
                     PipelineInput::Invoke {
                         uuid,
                         spec,
+                        options,
+                        input: pipeline_input,
                         response_tx,
                     } => {
-                        let op = UserOperation::InvokeWithSpec { uuid, spec };
-                        debug!(?op, "Received pipeline operation");
+                        let spec = match interceptor.as_ref().map(|i| i.on_before_invoke(&spec)) {
+                            None | Some(InvokeDecision::Allow) => spec,
+                            Some(InvokeDecision::Modify(rewritten)) => {
+                                info!(pipeline_id = %uuid, "invoke interceptor rewrote the pipeline");
+                                rewritten
+                            }
+                            Some(InvokeDecision::Deny(reason)) => {
+                                warn!(pipeline_id = %uuid, %reason, "invoke interceptor denied the pipeline");
+                                if let Some(auditor) = &auditor {
+                                    auditor.denied(uuid, &spec, reason.clone());
+                                }
+                                report_denied(uuid, &reason, response_tx).await;
+                                continue;
+                            }
+                        };
+                        if let Some(auditor) = &auditor {
+                            auditor.invoked(uuid, &spec);
+                        }
+                        if let Some(recovery) = &recovery {
+                            recovery.invoked(uuid, &spec, &pipeline_input);
+                        }
+                        let op = if pipeline_input.is_empty() {
+                            UserOperation::InvokeWithSpec { uuid, spec }
+                        } else {
+                            UserOperation::InvokeWithInput {
+                                uuid,
+                                spec,
+                                input: pipeline_input,
+                            }
+                        };
+                        debug!(?op, ?options, "Received pipeline operation");
 
                         let mut map = pipeline_map.lock().await;
                         map.insert(uuid, response_tx);
+                        drop(map);
+                        if options.stop_on_error {
+                            stop_on_error.lock().await.insert(uuid);
+                        }
+
+                        if let Some(timeout) = options.timeout {
+                            deadlines.push(
+                                async move {
+                                    Delay::new(timeout).await;
+                                    (uuid, timeout)
+                                }
+                                .boxed(),
+                            );
+                        }
 
                         user_input_tx
                             .send(op)
@@ -184,6 +466,9 @@ fn build_pipeline_multiplexer(
                             .context("Failed to forward pipeline operation")?;
                     }
                     PipelineInput::Kill { pipeline_handle } => {
+                        if let Some(recovery) = &recovery {
+                            recovery.abandoned(pipeline_handle.id());
+                        }
                         let op = UserOperation::KillPipeline {
                             pipeline: pipeline_handle,
                         };
@@ -194,6 +479,33 @@ fn build_pipeline_multiplexer(
                             .await
                             .context("Failed to forward KillPipeline operation")?;
                     }
+                    PipelineInput::Signal {
+                        pipeline_handle,
+                        code,
+                        escalate_after,
+                    } => {
+                        let pipeline_id = pipeline_handle.id();
+                        let op = UserOperation::SignalPipeline {
+                            pipeline: pipeline_handle,
+                            code,
+                        };
+                        debug!(?op, ?escalate_after, "Received pipeline signal operation");
+
+                        user_input_tx
+                            .send(op)
+                            .await
+                            .context("Failed to forward SignalPipeline operation")?;
+
+                        if let Some(after) = escalate_after {
+                            escalations.push(
+                                async move {
+                                    Delay::new(after).await;
+                                    pipeline_id
+                                }
+                                .boxed(),
+                            );
+                        }
+                    }
                     PipelineInput::Disconnect => {
                         debug!("Received disconnect operation");
                         user_input_tx
@@ -208,6 +520,43 @@ fn build_pipeline_multiplexer(
                             .await
                             .context("Failed to forward Reconnect operation")?;
                     }
+                    PipelineInput::Close => {
+                        debug!("Received close operation");
+                        // The session ends with the shell; stop taking input.
+                        user_input_tx
+                            .send(UserOperation::Close)
+                            .await
+                            .context("Failed to forward Close operation")?;
+                        break;
+                    }
+                    PipelineInput::Suspend => {
+                        debug!("Received suspend operation");
+                        // The session ends once the shell is disconnected.
+                        user_input_tx
+                            .send(UserOperation::Suspend)
+                            .await
+                            .context("Failed to forward Suspend operation")?;
+                        break;
+                    }
+                    PipelineInput::SetPowerSaving { enabled } => {
+                        debug!(enabled, "Received power saving operation");
+                        user_input_tx
+                            .send(UserOperation::SetPowerSaving { enabled })
+                            .await
+                            .context("Failed to forward SetPowerSaving operation")?;
+                    }
+                    PipelineInput::AcknowledgeOutput {
+                        pipeline_handle,
+                        bytes,
+                    } => {
+                        user_input_tx
+                            .send(UserOperation::AcknowledgeOutput {
+                                pipeline: pipeline_handle,
+                                bytes,
+                            })
+                            .await
+                            .context("Failed to forward AcknowledgeOutput operation")?;
+                    }
                 }
             }
 
@@ -224,6 +573,28 @@ fn build_pipeline_multiplexer(
     }
 }
 
+/// End a denied pipeline's stream the way a failed one ends: an error record,
+/// then `PipelineFinished`.
+async fn report_denied(uuid: uuid::Uuid, reason: &str, mut response_tx: mpsc::Sender<UserEvent>) {
+    let error_record = ironposh_psrp::ErrorRecord::builder()
+        .message(format!("The command was denied: {reason}"))
+        .fully_qualified_error_id(Some("CommandDenied".to_owned()))
+        .build();
+    for event in [
+        UserEvent::ErrorRecord {
+            error_record,
+            handle: PipelineHandle::new(uuid),
+        },
+        UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(uuid),
+        },
+    ] {
+        if response_tx.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
 /// Establish connection and return client handle with background task (parallel mode).
 /// `connect_shell_id` switches the handshake into reattach mode (WSMan Connect
 /// to an existing disconnected shell).
@@ -231,6 +602,7 @@ pub fn establish_connection<C>(
     config: WinRmConfig,
     connect_shell_id: Option<uuid::Uuid>,
     client: C,
+    hooks: SessionHooks,
 ) -> (
     ConnectionHandle,
     HostIo,
@@ -248,48 +620,115 @@ where
     let (session_event_tx, session_event_rx) = mpsc::unbounded();
     let session_event_tx_2 = session_event_tx.clone();
     let (lifecycle_tx, lifecycle_rx) = mpsc::unbounded();
+    let (drained_tx, drained_rx) = oneshot::channel();
+    let idle_policy = config.idle_policy;
+    let idle_event_tx = session_event_tx.clone();
+    let health = Health::new();
+    let heartbeats = health::heartbeats(
+        Arc::clone(&health),
+        config.heartbeat_interval,
+        session_event_tx.clone(),
+    );
+    let client = TimedClient::new(client, health);
 
+    let SessionHooks {
+        host_policy,
+        invoke_interceptor,
+        audit_log,
+    } = hooks;
+    let auditor = audit_log.map(|sink| Arc::new(Auditor::new(sink, &config)));
+    let recovery = config
+        .recreate_session
+        .as_ref()
+        .map(|policy| Arc::new(Recovery::new(policy)));
+    let submitter = HostSubmitter(host_resp_tx);
+    let (host_call_rx, host_call_filter) =
+        host_policy::install(host_policy, host_call_rx, submitter.clone());
     let host_io = HostIo {
         host_call_rx,
-        submitter: HostSubmitter(host_resp_tx),
+        submitter,
     };
 
     let user_input_tx_clone = user_input_tx.clone();
+    let session_recovery = recovery.clone();
     let active_session_task = async move {
         let _ = session_event_tx.unbounded_send(crate::SessionEvent::ConnectionStarted);
 
-        let (active_session, next_request) =
-            run_handshake(config, connect_shell_id, &client, &session_event_tx).await?;
+        let mut user_input_rx = user_input_rx;
+        let mut host_resp_rx = host_resp_rx;
+        let mut connect_shell_id = connect_shell_id;
+        // Operations to run first, on a shell opened to replace a dropped one.
+        let mut replay: Option<Vec<UserOperation>> = None;
+        let result = loop {
+            let (active_session, next_request) = run_handshake(
+                config.clone(),
+                connect_shell_id.take(),
+                &client,
+                &session_event_tx,
+            )
+            .await?;
 
-        let _ = session_event_tx.unbounded_send(crate::SessionEvent::ConnectionEstablished);
-        let _ = session_event_tx.unbounded_send(crate::SessionEvent::ActiveSessionStarted);
-        info!("Connection established, entering parallel session loop");
+            let replayed = if let Some(replayed) = replay.take() {
+                let _ = session_event_tx.unbounded_send(crate::SessionEvent::SessionRecreated);
+                info!(
+                    replayed = replayed.len(),
+                    "New shell opened, replaying unanswered operations"
+                );
+                replayed
+            } else {
+                let _ = session_event_tx.unbounded_send(crate::SessionEvent::ConnectionEstablished);
+                let _ = session_event_tx.unbounded_send(crate::SessionEvent::ActiveSessionStarted);
+                info!("Connection established, entering parallel session loop");
+                Vec::new()
+            };
 
-        let result = session::start_active_session_loop(
-            next_request,
-            *active_session,
-            client,
-            user_input_rx,
-            server_output_tx,
-            user_input_tx_clone,
-            host_call_tx,
-            host_resp_rx,
-            lifecycle_tx,
-        )
-        .instrument(info_span!("ActiveSession"))
-        .await;
+            let result = session::start_active_session_loop(
+                next_request,
+                *active_session,
+                &client,
+                futures::stream::iter(replayed).chain(&mut user_input_rx),
+                server_output_tx.clone(),
+                user_input_tx_clone.clone(),
+                host_call_tx.clone(),
+                &mut host_resp_rx,
+                lifecycle_tx.clone(),
+                session_event_tx.clone(),
+            )
+            .instrument(info_span!("ActiveSession"))
+            .await;
 
-        match result {
-            Ok(()) => {
-                info!("Active session loop ended");
-                let _ = session_event_tx.unbounded_send(crate::SessionEvent::ActiveSessionEnded);
-                Ok(())
-            }
-            Err(e) => {
-                let _ = session_event_tx.unbounded_send(crate::SessionEvent::Error(e.to_string()));
-                Err(e)
+            let Some(recovery) = session_recovery
+                .as_ref()
+                .filter(|_| result.as_ref().is_err_and(recovery::is_shell_not_found))
+            else {
+                break result;
+            };
+            let Some(restart) = recovery.restart() else {
+                warn!(
+                    attempts = recovery::MAX_ATTEMPTS,
+                    "the server keeps dropping the shell, giving up"
+                );
+                break result;
+            };
+            warn!(backoff = ?restart.backoff, "the server dropped the shell, opening a new one");
+            let mut lost_tx = server_output_tx.clone();
+            for event in restart.lost_events {
+                lost_tx
+                    .send(event)
+                    .await
+                    .context("Failed to fail a pipeline lost with the shell")?;
             }
+            Delay::new(restart.backoff).await;
+            replay = Some(restart.replay);
+        };
+        // The multiplexer drains once the last sender of server output is gone.
+        drop(server_output_tx);
+
+        if result.is_ok() {
+            info!("Active session loop ended");
         }
+        report_session_end(&result, drained_rx, &session_event_tx).await;
+        result
     }
     .instrument(info_span!("MainTask"));
 
@@ -298,6 +737,14 @@ where
         user_input_tx,
         server_output_rx,
         pipeline_input_rx,
+        drained_tx,
+        MultiplexerPolicies {
+            idle: idle_policy,
+            interceptor: invoke_interceptor,
+            auditor,
+            recovery,
+        },
+        idle_event_tx,
         "Parallel",
     );
 
@@ -306,7 +753,14 @@ where
         // handshake in the active session) instead of waiting for the multiplexer,
         // which would otherwise block forever on its channels and hang the whole
         // connection task — and with it any caller awaiting this future.
-        let res = try_join!(active_session_task, multiplex_pipeline_task);
+        let session = async {
+            try_join!(
+                active_session_task,
+                multiplex_pipeline_task,
+                host_call_filter.map(Ok::<(), anyhow::Error>)
+            )
+        };
+        let res = with_heartbeats(session, heartbeats).await;
         let _ = session_event_tx_2.unbounded_send(crate::SessionEvent::Closed);
         res.map(|_| ())
     };
@@ -324,10 +778,15 @@ where
 ///
 /// All WinRM operations are serialized through a single HTTP connection,
 /// required when the transport (e.g. Devolutions Gateway) only allows a
-/// single WebSocket per token.
+/// single WebSocket per token. `connect_shell_id` attaches to a shell left
+/// by [`PipelineInput::Suspend`], as in [`establish_connection`]. A dropped
+/// shell is not recreated: `WinRmConfig::recreate_session` is ignored and
+/// the session ends with `ShellNotFound`.
 pub fn establish_connection_serial<C>(
     config: WinRmConfig,
+    connect_shell_id: Option<uuid::Uuid>,
     client: C,
+    hooks: SessionHooks,
 ) -> (
     ConnectionHandle,
     HostIo,
@@ -343,17 +802,48 @@ where
     let (host_resp_tx, host_resp_rx) = mpsc::unbounded();
     let (session_event_tx, session_event_rx) = mpsc::unbounded();
     let session_event_tx_2 = session_event_tx.clone();
+    let (drained_tx, drained_rx) = oneshot::channel();
+    // Disconnect needs the parallel session loop (see `SessionCore`), so only
+    // Close can be applied to an idle serial session.
+    let idle_policy = config.idle_policy.filter(|policy| {
+        let supported = policy.action == IdleAction::Close;
+        if !supported {
+            warn!("idle Disconnect is not supported in serial mode, ignoring the idle policy");
+        }
+        supported
+    });
+    if config.recreate_session.is_some() {
+        warn!("session recreation is not supported in serial mode, ignoring it");
+    }
+    let idle_event_tx = session_event_tx.clone();
+    let health = Health::new();
+    let heartbeats = health::heartbeats(
+        Arc::clone(&health),
+        config.heartbeat_interval,
+        session_event_tx.clone(),
+    );
+    let client = TimedClient::new(client, health);
 
+    let SessionHooks {
+        host_policy,
+        invoke_interceptor,
+        audit_log,
+    } = hooks;
+    let auditor = audit_log.map(|sink| Arc::new(Auditor::new(sink, &config)));
+    let submitter = HostSubmitter(host_resp_tx);
+    let (host_call_rx, host_call_filter) =
+        host_policy::install(host_policy, host_call_rx, submitter.clone());
     let host_io = HostIo {
         host_call_rx,
-        submitter: HostSubmitter(host_resp_tx),
+        submitter,
     };
 
+    let heartbeat_interval = config.heartbeat_interval;
     let active_session_task = async move {
         let _ = session_event_tx.unbounded_send(crate::SessionEvent::ConnectionStarted);
 
         let (active_session, next_request) =
-            run_handshake(config, None, &client, &session_event_tx).await?;
+            run_handshake(config, connect_shell_id, &client, &session_event_tx).await?;
 
         let _ = session_event_tx.unbounded_send(crate::SessionEvent::ConnectionEstablished);
         let _ = session_event_tx.unbounded_send(crate::SessionEvent::ActiveSessionStarted);
@@ -367,34 +857,49 @@ where
             server_output_tx,
             host_call_tx,
             host_resp_rx,
+            session_event_tx.clone(),
+            heartbeat_interval,
         )
         .instrument(info_span!("SerialActiveSession"))
         .await;
 
-        match result {
-            Ok(()) => {
-                info!("Serial session loop ended");
-                let _ = session_event_tx.unbounded_send(crate::SessionEvent::ActiveSessionEnded);
-                Ok(())
-            }
-            Err(e) => {
-                let _ = session_event_tx.unbounded_send(crate::SessionEvent::Error(e.to_string()));
-                Err(e)
-            }
+        if result.is_ok() {
+            info!("Serial session loop ended");
         }
+        report_session_end(&result, drained_rx, &session_event_tx).await;
+        result
     }
     .instrument(info_span!("SerialMainTask"));
 
     let (pipeline_input_tx, pipeline_input_rx) = mpsc::channel(100);
-    let multiplex_pipeline_task =
-        build_pipeline_multiplexer(user_input_tx, server_output_rx, pipeline_input_rx, "Serial");
+    let multiplex_pipeline_task = build_pipeline_multiplexer(
+        user_input_tx,
+        server_output_rx,
+        pipeline_input_rx,
+        drained_tx,
+        MultiplexerPolicies {
+            idle: idle_policy,
+            interceptor: invoke_interceptor,
+            auditor,
+            recovery: None,
+        },
+        idle_event_tx,
+        "Serial",
+    );
 
     let joined_task = async move {
         // try_join! short-circuits the moment either task errors (e.g. a failed
         // handshake in the active session) instead of waiting for the multiplexer,
         // which would otherwise block forever on its channels and hang the whole
         // connection task — and with it any caller awaiting this future.
-        let res = try_join!(active_session_task, multiplex_pipeline_task);
+        let session = async {
+            try_join!(
+                active_session_task,
+                multiplex_pipeline_task,
+                host_call_filter.map(Ok::<(), anyhow::Error>)
+            )
+        };
+        let res = with_heartbeats(session, heartbeats).await;
         let _ = session_event_tx_2.unbounded_send(crate::SessionEvent::Closed);
         res.map(|_| ())
     };
@@ -418,13 +923,540 @@ pub enum PipelineInput {
     Invoke {
         uuid: uuid::Uuid,
         spec: PipelineSpec,
+        options: PipelineOptions,
+        /// Objects fed to the pipeline as PIPELINE_INPUT; empty runs it
+        /// without input.
+        input: Vec<PsValue>,
         response_tx: mpsc::Sender<UserEvent>,
     },
     Kill {
         pipeline_handle: PipelineHandle,
     },
+    /// Send a WinRS signal; when `escalate_after` is set and the pipeline is
+    /// still running once it elapses, follow up with a Terminate (kill).
+    Signal {
+        pipeline_handle: PipelineHandle,
+        code: SignalCode,
+        escalate_after: Option<std::time::Duration>,
+    },
     /// Disconnect the runspace pool shell (parallel session loop only).
     Disconnect,
     /// Reconnect a previously disconnected runspace pool shell.
     Reconnect,
+    /// Delete the runspace pool shell, ending the session.
+    Close,
+    /// Disconnect the runspace pool shell and end the session (serial loop),
+    /// leaving the shell for another client to attach to.
+    Suspend,
+    /// Poll the server less often while idle (serial loop only).
+    SetPowerSaving {
+        enabled: bool,
+    },
+    /// Release `bytes` of consumed output of a pipeline throttled by
+    /// `ReceiveFlowConfig::max_pending_output_bytes`.
+    AcknowledgeOutput {
+        pipeline_handle: PipelineHandle,
+        bytes: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timed_out_pipeline_is_stopped_and_its_stream_ends() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (_server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            oneshot::channel().0,
+            MultiplexerPolicies::default(),
+            mpsc::unbounded().0,
+            "Parallel",
+        );
+
+        let (response_tx, mut events) = mpsc::channel(10);
+        let uuid = uuid::Uuid::new_v4();
+        let timeout = Duration::from_millis(10);
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid,
+                    spec: PipelineSpec {
+                        commands: vec![],
+                        add_to_history: false,
+                    },
+                    options: PipelineOptions {
+                        timeout: Some(timeout),
+                        stop_on_error: false,
+                    },
+                    input: Vec::new(),
+                    response_tx,
+                })
+                .await
+                .expect("send Invoke");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::InvokeWithSpec { .. })
+            ));
+
+            match events.next().await {
+                Some(UserEvent::PipelineTimedOut {
+                    pipeline,
+                    timeout: reported,
+                }) => {
+                    assert_eq!(pipeline.id(), uuid);
+                    assert_eq!(reported, timeout);
+                }
+                other => panic!("expected PipelineTimedOut, got {other:?}"),
+            }
+            assert!(events.next().await.is_none(), "the stream must end");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::KillPipeline { pipeline }) if pipeline.id() == uuid
+            ));
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn stop_on_error_pipeline_is_stopped_at_its_first_error_record() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (mut server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            oneshot::channel().0,
+            MultiplexerPolicies::default(),
+            mpsc::unbounded().0,
+            "Parallel",
+        );
+
+        let (response_tx, mut events) = mpsc::channel(10);
+        let uuid = uuid::Uuid::new_v4();
+        let error_record = || UserEvent::ErrorRecord {
+            error_record: ironposh_psrp::ErrorRecord::builder()
+                .message("boom".to_owned())
+                .build(),
+            handle: PipelineHandle::new(uuid),
+        };
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid,
+                    spec: PipelineSpec {
+                        commands: vec![],
+                        add_to_history: false,
+                    },
+                    options: PipelineOptions {
+                        timeout: None,
+                        stop_on_error: true,
+                    },
+                    input: Vec::new(),
+                    response_tx,
+                })
+                .await
+                .expect("send Invoke");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::InvokeWithSpec { .. })
+            ));
+
+            server_output_tx
+                .send(error_record())
+                .await
+                .expect("send ErrorRecord");
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::ErrorRecord { .. })
+            ));
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::KillPipeline { pipeline }) if pipeline.id() == uuid
+            ));
+
+            // Only the first error stops the pipeline.
+            server_output_tx
+                .send(error_record())
+                .await
+                .expect("send second ErrorRecord");
+            server_output_tx
+                .send(UserEvent::PipelineFinished {
+                    pipeline: PipelineHandle::new(uuid),
+                })
+                .await
+                .expect("send PipelineFinished");
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::ErrorRecord { .. })
+            ));
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::PipelineFinished { .. })
+            ));
+            assert!(events.next().await.is_none(), "the stream must end");
+            assert!(
+                user_input_rx.try_next().is_err(),
+                "no further operations expected"
+            );
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn broken_pool_is_reported_after_pipelines_get_their_last_events() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (mut server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let (drained_tx, drained_rx) = oneshot::channel();
+        let (session_event_tx, mut session_event_rx) = mpsc::unbounded();
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            drained_tx,
+            MultiplexerPolicies::default(),
+            mpsc::unbounded().0,
+            "Parallel",
+        );
+
+        let (response_tx, mut events) = mpsc::channel(10);
+        let uuid = uuid::Uuid::new_v4();
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid,
+                    spec: PipelineSpec {
+                        commands: vec![],
+                        add_to_history: false,
+                    },
+                    options: PipelineOptions::default(),
+                    input: Vec::new(),
+                    response_tx,
+                })
+                .await
+                .expect("send Invoke");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::InvokeWithSpec { .. })
+            ));
+
+            // What the session loop emits for a broken pool, right before it ends.
+            server_output_tx
+                .send(UserEvent::ErrorRecord {
+                    error_record: ironposh_psrp::ErrorRecord::builder()
+                        .message("The WinRM service is shutting down.".to_owned())
+                        .build(),
+                    handle: PipelineHandle::new(uuid),
+                })
+                .await
+                .expect("send ErrorRecord");
+            server_output_tx
+                .send(UserEvent::PipelineFinished {
+                    pipeline: PipelineHandle::new(uuid),
+                })
+                .await
+                .expect("send PipelineFinished");
+            drop(server_output_tx);
+
+            let result = Err(PwshCoreError::RunspacePoolBroken {
+                reason: "The WinRM service is shutting down.".to_owned(),
+            }
+            .into());
+            report_session_end(&result, drained_rx, &session_event_tx).await;
+
+            assert!(matches!(
+                session_event_rx.next().await,
+                Some(crate::SessionEvent::Broken { reason })
+                    if reason == "The WinRM service is shutting down."
+            ));
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::ErrorRecord { .. })
+            ));
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::PipelineFinished { .. })
+            ));
+            assert!(events.next().await.is_none(), "the stream must end");
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn idle_policy_closes_the_session_once_no_pipeline_runs() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (mut server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let (session_event_tx, mut session_event_rx) = mpsc::unbounded();
+        let after = Duration::from_millis(10);
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            oneshot::channel().0,
+            MultiplexerPolicies {
+                idle: Some(IdlePolicy {
+                    after,
+                    action: IdleAction::Close,
+                }),
+                ..MultiplexerPolicies::default()
+            },
+            session_event_tx,
+            "Parallel",
+        );
+
+        let (response_tx, mut events) = mpsc::channel(10);
+        let uuid = uuid::Uuid::new_v4();
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid,
+                    spec: PipelineSpec {
+                        commands: vec![],
+                        add_to_history: false,
+                    },
+                    options: PipelineOptions::default(),
+                    input: Vec::new(),
+                    response_tx,
+                })
+                .await
+                .expect("send Invoke");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::InvokeWithSpec { .. })
+            ));
+
+            // A running pipeline keeps the session active past the idle timeout.
+            Delay::new(after * 3).await;
+            assert!(user_input_rx.try_next().is_err(), "no idle action yet");
+
+            server_output_tx
+                .send(UserEvent::PipelineFinished {
+                    pipeline: PipelineHandle::new(uuid),
+                })
+                .await
+                .expect("send PipelineFinished");
+            assert!(matches!(
+                events.next().await,
+                Some(UserEvent::PipelineFinished { .. })
+            ));
+
+            assert!(matches!(
+                session_event_rx.next().await,
+                Some(crate::SessionEvent::Idle {
+                    action: IdleAction::Close
+                })
+            ));
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::Close)
+            ));
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
+
+    struct DenyRemoveItem;
+
+    impl InvokeInterceptor for DenyRemoveItem {
+        fn on_before_invoke(&self, pipeline: &PipelineSpec) -> InvokeDecision {
+            if pipeline.display_text().contains("Remove-Item") {
+                InvokeDecision::Deny("Remove-Item needs approval".to_owned())
+            } else {
+                InvokeDecision::Allow
+            }
+        }
+    }
+
+    #[test]
+    fn denied_pipeline_is_never_sent_and_both_outcomes_are_audited() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (mut server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let audit_log = Arc::new(crate::MemoryAuditLog::new());
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            oneshot::channel().0,
+            MultiplexerPolicies {
+                interceptor: Some(Arc::new(DenyRemoveItem)),
+                auditor: Some(Arc::new(Auditor::new(
+                    audit_log.clone(),
+                    &ironposh_test_support::fake_server::test_config(),
+                ))),
+                ..MultiplexerPolicies::default()
+            },
+            mpsc::unbounded().0,
+            "Parallel",
+        );
+
+        let script = |text: &str| PipelineSpec {
+            commands: vec![ironposh_client_core::pipeline::PipelineCommand::new_script(
+                text.to_owned(),
+            )],
+            add_to_history: false,
+        };
+        let (denied_tx, mut denied_events) = mpsc::channel(10);
+        let (allowed_tx, mut allowed_events) = mpsc::channel(10);
+        let denied = uuid::Uuid::new_v4();
+        let allowed = uuid::Uuid::new_v4();
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid: denied,
+                    spec: script("Remove-Item C:\\data"),
+                    options: PipelineOptions::default(),
+                    input: Vec::new(),
+                    response_tx: denied_tx,
+                })
+                .await
+                .expect("send denied Invoke");
+            assert!(matches!(
+                denied_events.next().await,
+                Some(UserEvent::ErrorRecord { error_record, .. })
+                    if error_record.fully_qualified_error_id.as_deref() == Some("CommandDenied")
+            ));
+            assert!(matches!(
+                denied_events.next().await,
+                Some(UserEvent::PipelineFinished { .. })
+            ));
+
+            pipeline_input_tx
+                .send(PipelineInput::Invoke {
+                    uuid: allowed,
+                    spec: script("Get-Date"),
+                    options: PipelineOptions::default(),
+                    input: Vec::new(),
+                    response_tx: allowed_tx,
+                })
+                .await
+                .expect("send allowed Invoke");
+            // Only the allowed pipeline reaches the session.
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::InvokeWithSpec { uuid, .. }) if uuid == allowed
+            ));
+            server_output_tx
+                .send(UserEvent::PipelineFinished {
+                    pipeline: PipelineHandle::new(allowed),
+                })
+                .await
+                .expect("send PipelineFinished");
+            assert!(matches!(
+                allowed_events.next().await,
+                Some(UserEvent::PipelineFinished { .. })
+            ));
+
+            let entries = audit_log.entries();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].pipeline_id, denied);
+            assert_eq!(
+                entries[0].status,
+                crate::AuditStatus::Denied {
+                    reason: "Remove-Item needs approval".to_owned()
+                }
+            );
+            assert_eq!(entries[1].pipeline_id, allowed);
+            assert_eq!(entries[1].command, "Get-Date");
+            assert_eq!(entries[1].status, crate::AuditStatus::Completed);
+            assert_eq!(entries[1].user.as_deref(), Some("user"));
+            assert_eq!(entries[1].target, "127.0.0.1:5985");
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn close_deletes_the_shell() {
+        let (user_input_tx, mut user_input_rx) = mpsc::channel(10);
+        let (_server_output_tx, server_output_rx) = mpsc::channel(10);
+        let (mut pipeline_input_tx, pipeline_input_rx) = mpsc::channel(10);
+        let multiplexer = build_pipeline_multiplexer(
+            user_input_tx,
+            server_output_rx,
+            pipeline_input_rx,
+            oneshot::channel().0,
+            MultiplexerPolicies::default(),
+            mpsc::unbounded().0,
+            "Serial",
+        );
+
+        let scenario = async {
+            pipeline_input_tx
+                .send(PipelineInput::Close)
+                .await
+                .expect("send Close");
+            assert!(matches!(
+                user_input_rx.next().await,
+                Some(UserOperation::Close)
+            ));
+        };
+
+        let scenario = scenario.fuse();
+        let multiplexer = multiplexer.fuse();
+        futures::pin_mut!(scenario, multiplexer);
+        futures::executor::block_on(async {
+            futures::select! {
+                () = scenario => {}
+                res = multiplexer => panic!("multiplexer ended early: {res:?}"),
+            }
+        });
+    }
 }
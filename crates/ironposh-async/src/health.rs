@@ -0,0 +1,135 @@
+//! Connection health: every HTTP round trip the session makes is timed by
+//! [`TimedClient`], and [`heartbeats`] reports the result as periodic
+//! [`SessionEvent::Heartbeat`]s.
+//!
+//! The parallel loop always has a Receive long-poll going, which the server
+//! answers at least once per operation timeout. The serial loop leaves an
+//! idle session silent, so with heartbeats on it sends a runspace-pool
+//! Receive whenever the server has been quiet for an interval.
+
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures_timer::Delay;
+use ironposh_client_core::connector::{HttpResponseTargeted, TrySend};
+
+use crate::clock::Instant;
+use crate::{HttpClient, SessionEvent};
+
+/// Round trips observed since the last heartbeat.
+pub struct Health {
+    state: Mutex<HealthState>,
+}
+
+struct HealthState {
+    last_response: Instant,
+    fastest: Option<Duration>,
+}
+
+impl Health {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(HealthState {
+                last_response: Instant::now(),
+                fastest: None,
+            }),
+        })
+    }
+
+    fn record(&self, round_trip: Duration) {
+        let mut state = self.state.lock().expect("health lock");
+        state.last_response = Instant::now();
+        state.fastest = Some(state.fastest.map_or(round_trip, |d| d.min(round_trip)));
+    }
+
+    /// The heartbeat for the window that ends now; starts the next one.
+    fn take_heartbeat(&self) -> SessionEvent {
+        let mut state = self.state.lock().expect("health lock");
+        SessionEvent::Heartbeat {
+            latency: state.fastest.take(),
+            last_server_activity: state.last_response.elapsed(),
+        }
+    }
+}
+
+/// An [`HttpClient`] that records the round trip of every answered request.
+pub struct TimedClient<C> {
+    inner: C,
+    health: Arc<Health>,
+}
+
+impl<C> TimedClient<C> {
+    pub const fn new(inner: C, health: Arc<Health>) -> Self {
+        Self { inner, health }
+    }
+}
+
+impl<C: HttpClient> HttpClient for TimedClient<C> {
+    async fn send_request(&self, try_send: TrySend) -> anyhow::Result<HttpResponseTargeted> {
+        let started_at = Instant::now();
+        let response = self.inner.send_request(try_send).await?;
+        self.health.record(started_at.elapsed());
+        Ok(response)
+    }
+}
+
+/// Send a heartbeat every `interval`; never returns, so race it against the
+/// session. Without an interval it just waits.
+pub async fn heartbeats(
+    health: Arc<Health>,
+    interval: Option<Duration>,
+    session_event_tx: mpsc::UnboundedSender<SessionEvent>,
+) -> Infallible {
+    let Some(interval) = interval else {
+        return futures::future::pending().await;
+    };
+    loop {
+        Delay::new(interval).await;
+        let _ = session_event_tx.unbounded_send(health.take_heartbeat());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use futures::future::{Either, select};
+
+    use super::*;
+
+    #[test]
+    fn heartbeat_reports_the_fastest_round_trip_of_its_window() {
+        let health = Health::new();
+        health.record(Duration::from_millis(40));
+        health.record(Duration::from_millis(15));
+        health.record(Duration::from_millis(90));
+
+        let (session_event_tx, mut session_event_rx) = mpsc::unbounded();
+        let ticker = Box::pin(heartbeats(
+            Arc::clone(&health),
+            Some(Duration::from_millis(10)),
+            session_event_tx,
+        ));
+        let (first, second) = futures::executor::block_on(async {
+            let events =
+                Box::pin(async { (session_event_rx.next().await, session_event_rx.next().await) });
+            match select(events, ticker).await {
+                Either::Left((events, _)) => events,
+                Either::Right((never, _)) => match never {},
+            }
+        });
+
+        assert!(matches!(
+            first,
+            Some(SessionEvent::Heartbeat { latency: Some(latency), .. })
+                if latency == Duration::from_millis(15)
+        ));
+        // Nothing was answered in the second window.
+        assert!(matches!(
+            second,
+            Some(SessionEvent::Heartbeat { latency: None, last_server_activity })
+                if last_server_activity >= Duration::from_millis(10)
+        ));
+    }
+}
@@ -0,0 +1,275 @@
+//! Host call policy: a hook between the session and the host handler.
+//!
+//! Every host call passes through the policy before it reaches
+//! [`HostIo`](crate::HostIo). The policy can forward it (possibly rewritten),
+//! deny it, or answer it directly. Each decision is logged under the
+//! `host_audit` target.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use futures::channel::mpsc;
+use ironposh_client_core::host::{HostCall, HostError, Submission};
+use ironposh_psrp::{PipelineHostResponse, RemoteHostMethodId};
+use tracing::{info, warn};
+
+use crate::{HostResponse, HostSubmitter};
+
+/// What a [`HostCallPolicy`] does with a host call.
+#[derive(Debug)]
+pub enum HostCallDecision {
+    /// Hand the call to the host handler, as received or rewritten.
+    Forward(HostCall),
+    /// Fail the call with `error` as the host exception; the handler never
+    /// sees it.
+    Deny(HostError),
+    /// Answer the call without involving the host handler.
+    Respond(Submission),
+}
+
+/// Decides, per host call, whether the host handler gets to see it.
+///
+/// Runs on the connection task, so `decide` must not block.
+pub trait HostCallPolicy: Send + Sync {
+    fn decide(&self, call: HostCall) -> HostCallDecision;
+}
+
+/// Declarative [`HostCallPolicy`] for the common cases: denying methods
+/// (e.g. credential prompts in kiosk deployments) and answering choice
+/// prompts with their default (batch mode). Everything else is forwarded.
+#[derive(Debug, Clone, Default)]
+pub struct HostCallRules {
+    denied: Vec<RemoteHostMethodId>,
+    default_choices: bool,
+}
+
+impl HostCallRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deny every call to `method`.
+    #[must_use]
+    pub fn with_denied(mut self, method: RemoteHostMethodId) -> Self {
+        if !self.denied.contains(&method) {
+            self.denied.push(method);
+        }
+        self
+    }
+
+    /// Deny both `PromptForCredential` overloads.
+    #[must_use]
+    pub fn with_credential_prompts_denied(self) -> Self {
+        self.with_denied(RemoteHostMethodId::PromptForCredential1)
+            .with_denied(RemoteHostMethodId::PromptForCredential2)
+    }
+
+    /// Answer `PromptForChoice` and `PromptForChoiceMultipleSelection` with
+    /// the default choice(s) the script offered.
+    #[must_use]
+    pub fn with_default_choices(mut self) -> Self {
+        self.default_choices = true;
+        self
+    }
+}
+
+impl HostCallPolicy for HostCallRules {
+    fn decide(&self, call: HostCall) -> HostCallDecision {
+        if self.denied.contains(&call.method()) {
+            return HostCallDecision::Deny(HostError::Other(format!(
+                "{} is not allowed by the host call policy",
+                call.method_name()
+            )));
+        }
+
+        if !self.default_choices {
+            return HostCallDecision::Forward(call);
+        }
+
+        match call {
+            HostCall::PromptForChoice { transport } => {
+                let ((_, _, _, default_choice), result) = transport.into_parts();
+                HostCallDecision::Respond(result.accept_result(default_choice))
+            }
+            HostCall::PromptForChoiceMultipleSelection { transport } => {
+                let ((_, _, _, default_choices), result) = transport.into_parts();
+                HostCallDecision::Respond(result.accept_result(default_choices))
+            }
+            call => HostCallDecision::Forward(call),
+        }
+    }
+}
+
+/// Put `policy` in front of the host call channel. Returns the receiver the
+/// host handler reads from and the filter future to drive alongside the
+/// session; without a policy the channel is handed through untouched.
+pub(crate) fn install(
+    policy: Option<Arc<dyn HostCallPolicy>>,
+    calls: mpsc::UnboundedReceiver<HostCall>,
+    submitter: HostSubmitter,
+) -> (
+    mpsc::UnboundedReceiver<HostCall>,
+    impl std::future::Future<Output = ()>,
+) {
+    let Some(policy) = policy else {
+        return (
+            calls,
+            futures::future::Either::Left(futures::future::ready(())),
+        );
+    };
+    let (forward_tx, forward_rx) = mpsc::unbounded();
+    (
+        forward_rx,
+        futures::future::Either::Right(filter_host_calls(policy, calls, forward_tx, submitter)),
+    )
+}
+
+async fn filter_host_calls(
+    policy: Arc<dyn HostCallPolicy>,
+    mut calls: mpsc::UnboundedReceiver<HostCall>,
+    forward_tx: mpsc::UnboundedSender<HostCall>,
+    submitter: HostSubmitter,
+) {
+    while let Some(call) = calls.next().await {
+        let call_id = call.call_id();
+        let scope = call.scope();
+        let method = call.method();
+        let method_name = call.method_name();
+        let answers = call.should_send_response();
+
+        let submission = match policy.decide(call) {
+            HostCallDecision::Forward(call) => {
+                info!(target: "host_audit", call_id, method = method_name, decision = "forward");
+                if forward_tx.unbounded_send(call).is_err() {
+                    warn!(target: "host_audit", "host call handler is gone, stopping the host call policy");
+                    break;
+                }
+                continue;
+            }
+            HostCallDecision::Deny(error) => {
+                info!(target: "host_audit", call_id, method = method_name, decision = "deny", %error);
+                rejection(call_id, method, answers, &error)
+            }
+            HostCallDecision::Respond(submission) => {
+                info!(target: "host_audit", call_id, method = method_name, decision = "respond");
+                submission
+            }
+        };
+
+        if submitter
+            .submit(HostResponse {
+                call_id,
+                scope,
+                submission,
+            })
+            .is_err()
+        {
+            warn!(target: "host_audit", "session is gone, stopping the host call policy");
+            break;
+        }
+    }
+}
+
+/// Fail a call with a host exception. Methods without a return value are
+/// not answered at all.
+fn rejection(
+    call_id: i64,
+    method: RemoteHostMethodId,
+    answers: bool,
+    error: &HostError,
+) -> Submission {
+    if !answers {
+        return Submission::NoSend;
+    }
+    Submission::Send(PipelineHostResponse::exception(
+        call_id,
+        method,
+        error.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironposh_client_core::host::{HostCallScope, Transport};
+    use ironposh_psrp::PsValue;
+
+    fn choice_prompt(default_choice: i32) -> HostCall {
+        HostCall::PromptForChoice {
+            transport: Transport::new(
+                HostCallScope::RunspacePool,
+                7,
+                (
+                    "Confirm".to_owned(),
+                    "Continue?".to_owned(),
+                    vec![],
+                    default_choice,
+                ),
+            ),
+        }
+    }
+
+    #[test]
+    fn rules_deny_credential_prompts() {
+        let rules = HostCallRules::new().with_credential_prompts_denied();
+        let call = HostCall::PromptForCredential1 {
+            transport: Transport::new(
+                HostCallScope::RunspacePool,
+                3,
+                (
+                    "caption".to_owned(),
+                    "message".to_owned(),
+                    "user".to_owned(),
+                    "target".to_owned(),
+                ),
+            ),
+        };
+
+        assert!(matches!(rules.decide(call), HostCallDecision::Deny(_)));
+    }
+
+    #[test]
+    fn rules_answer_choice_prompts_with_the_default() {
+        let rules = HostCallRules::new().with_default_choices();
+
+        let HostCallDecision::Respond(Submission::Send(response)) = rules.decide(choice_prompt(1))
+        else {
+            panic!("expected an answered choice prompt");
+        };
+        assert_eq!(response.call_id, 7);
+        assert_eq!(response.method_result, Some(PsValue::from(1_i32)));
+    }
+
+    #[test]
+    fn rules_forward_everything_else() {
+        let rules = HostCallRules::new();
+        assert!(matches!(
+            rules.decide(choice_prompt(0)),
+            HostCallDecision::Forward(HostCall::PromptForChoice { .. })
+        ));
+    }
+
+    #[test]
+    fn denied_call_is_answered_with_an_exception_and_never_forwarded() {
+        let (calls_tx, calls_rx) = mpsc::unbounded();
+        let (resp_tx, mut resp_rx) = mpsc::unbounded();
+        let policy: Arc<dyn HostCallPolicy> =
+            Arc::new(HostCallRules::new().with_denied(RemoteHostMethodId::PromptForChoice));
+        let (mut forwarded, filter) = install(Some(policy), calls_rx, HostSubmitter(resp_tx));
+
+        calls_tx
+            .unbounded_send(choice_prompt(0))
+            .expect("send host call");
+        drop(calls_tx);
+        futures::executor::block_on(filter);
+
+        let response = resp_rx.try_next().expect("a response").expect("open");
+        assert_eq!(response.call_id, 7);
+        let Submission::Send(response) = response.submission else {
+            panic!("PromptForChoice expects an answer");
+        };
+        assert!(response.method_exception.is_some());
+        assert!(response.method_result.is_none());
+        assert!(matches!(forwarded.try_next(), Ok(None)));
+    }
+}
@@ -1,11 +1,19 @@
 use futures::channel::mpsc;
-use ironposh_client_core::connector::{connection_pool::TrySend, http::HttpResponseTargeted};
+use ironposh_client_core::connector::{
+    HttpResponseTargeted, TrySend, config::IdleAction, http::Interruption,
+    rate_limit::ThrottleStats,
+};
 use ironposh_client_core::host::{HostCall, HostCallScope, Submission};
 use std::future::Future;
+use std::time::Duration;
 
 // Internal modules
+mod audit;
 mod clock;
 mod connection;
+mod health;
+mod host_policy;
+mod recovery;
 mod session;
 mod session_serial;
 
@@ -13,7 +21,11 @@ mod session_serial;
 pub mod client;
 
 // Re-export the main client
-pub use client::RemoteAsyncPowershellClient;
+pub use audit::{
+    AuditEntry, AuditSink, AuditStatus, InvokeDecision, InvokeInterceptor, MemoryAuditLog,
+};
+pub use client::{InvokeOutput, RemoteAsyncPowershellClient, SessionHooks, StopPolicy};
+pub use host_policy::{HostCallDecision, HostCallPolicy, HostCallRules};
 
 /// Session lifecycle events
 #[derive(Debug, Clone)]
@@ -28,6 +40,57 @@ pub enum SessionEvent {
     ActiveSessionEnded,
     /// An error occurred during connection or session
     Error(String),
+    /// The server broke the runspace pool (e.g. service restart, shell closed
+    /// by policy). Terminal: every pipeline has ended with an error record, and
+    /// a new connection is needed to continue.
+    Broken { reason: String },
+    /// The session sat idle past its `IdlePolicy`; `action` is issued right
+    /// after this event.
+    Idle { action: IdleAction },
+    /// The server, or a gateway in front of it, answered a request with a
+    /// status instead of a reply. With `retrying` the session resends the
+    /// request itself; otherwise the request was dropped (and on
+    /// `AuthExpired` the credentials likely need renewing).
+    Interrupted {
+        interruption: Interruption,
+        retrying: bool,
+    },
+    /// The runspace pool shell was disconnected by
+    /// [`RemoteAsyncPowershellClient::suspend`] and left on the server; a new
+    /// client attaches to it with `shell_id`. The session loop ends next.
+    Suspended { shell_id: String },
+    /// Connection health, sent every `WinRmConfig::heartbeat_interval`.
+    /// `latency` is the fastest HTTP round trip since the previous heartbeat,
+    /// `None` if none completed; a quiet session only has Receive long-polls,
+    /// so it is then an upper bound. `last_server_activity` is the time since
+    /// the server last answered: once it nears the operation timeout, the
+    /// connection is likely gone.
+    Heartbeat {
+        latency: Option<Duration>,
+        last_server_activity: Duration,
+    },
+    /// `WinRmConfig::rate_limit` held a request back for `wait`. `stats`
+    /// totals the pacing so far (parallel session loop only).
+    Throttled {
+        wait: Duration,
+        stats: ThrottleStats,
+    },
+    /// The server had dropped the shell (e.g. its idle timeout expired) and
+    /// a new one was opened per `WinRmConfig::recreate_session`. Invocations
+    /// the old shell never answered are sent again on the new one; those it
+    /// was running ended with an error record.
+    SessionRecreated,
+    /// Lenient parsing (`WinRmConfig::parse_mode`) skipped a data-stream
+    /// record the server sent on `stream` that could not be decoded; the
+    /// session goes on.
+    /// `pipeline` is the stream's pipeline, if any; `diagnostics` carries the
+    /// record's bytes when the failure was in PSRP decoding.
+    ProtocolWarning {
+        stream: String,
+        pipeline: Option<uuid::Uuid>,
+        message: String,
+        diagnostics: Option<Box<ironposh_psrp::BlobDiagnostics>>,
+    },
     /// Session has been closed
     Closed,
 }
@@ -90,6 +153,9 @@ pub trait AsyncPowershellClient {
     fn send_command(&self, command: String) -> impl Future<Output = anyhow::Result<String>>;
 }
 
+/// The network side of a session. An implementation sends each [`TrySend`]
+/// over real HTTP and answers with the response tagged with its connection;
+/// see [`TrySend`] for what each kind of work expects.
 pub trait HttpClient: Send + Sync + 'static {
     fn send_request(
         &self,
@@ -0,0 +1,338 @@
+//! Reopening a session whose shell the server dropped, e.g. because its idle
+//! timeout expired while the client was away.
+//!
+//! The multiplexer records every invocation until the server first answers
+//! it. When a request then fails with `ShellNotFound`, the connection task
+//! runs the handshake again and replays, in order, the init script and the
+//! invocations the old shell never answered. Their pipeline streams stay
+//! open, so callers only see their output arrive late. Pipelines the old
+//! shell was already running died with it; they are failed and finished.
+//!
+//! Only the parallel session loop recreates shells. A shell that keeps being
+//! dropped is given up on after [`MAX_ATTEMPTS`] recreations in a row, with
+//! a growing delay between them.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use ironposh_client_core::{
+    PwshCoreError,
+    connector::{UserOperation, active_session::UserEvent, config::SessionRecreation},
+    pipeline::PipelineSpec,
+    powershell::PipelineHandle,
+};
+use ironposh_psrp::{ErrorRecord, PsValue};
+use tracing::{debug, warn};
+
+/// Shell recreations in a row, without the new shell answering anything,
+/// before the session is given up on.
+pub(crate) const MAX_ATTEMPTS: u32 = 5;
+
+/// Longest delay between two recreations.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct State {
+    /// Invocations sent but not answered yet, oldest first.
+    unanswered: Vec<(uuid::Uuid, PipelineSpec, Vec<PsValue>)>,
+    /// Pipelines the server answered that have not finished yet.
+    running: HashSet<uuid::Uuid>,
+    /// Pipelines failed on a recreation whose synthetic events are in flight.
+    lost: HashSet<uuid::Uuid>,
+    /// Init script runs; their events have no stream to go to.
+    init_runs: HashSet<uuid::Uuid>,
+    /// Recreations since a shell last answered.
+    attempts: u32,
+}
+
+/// How to carry on after the server dropped the shell.
+pub(crate) struct Restart {
+    /// How long to wait before opening the new shell.
+    pub(crate) backoff: Duration,
+    /// Error record and `PipelineFinished` for each pipeline lost with the
+    /// old shell.
+    pub(crate) lost_events: Vec<UserEvent>,
+    /// The operations to run first on the new shell: the init script, then
+    /// the unanswered invocations in the order they were made.
+    pub(crate) replay: Vec<UserOperation>,
+}
+
+/// What is needed to carry a session over to a new shell.
+pub(crate) struct Recovery {
+    init_script: Option<String>,
+    state: Mutex<State>,
+}
+
+impl Recovery {
+    pub(crate) fn new(policy: &SessionRecreation) -> Self {
+        Self {
+            init_script: policy.init_script.clone(),
+            state: Mutex::default(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn invoked(&self, uuid: uuid::Uuid, spec: &PipelineSpec, input: &[PsValue]) {
+        self.state()
+            .unanswered
+            .push((uuid, spec.clone(), input.to_vec()));
+    }
+
+    /// The server sent `event`; its pipeline is no longer replayed, and is
+    /// lost with the shell until it finishes.
+    pub(crate) fn answered(&self, event: &UserEvent) {
+        let uuid = event.pipeline_id();
+        let finished = matches!(event, UserEvent::PipelineFinished { .. });
+        let mut state = self.state();
+        if state.lost.contains(&uuid) {
+            if finished {
+                state.lost.remove(&uuid);
+            }
+            return;
+        }
+        state.attempts = 0;
+        let before = state.unanswered.len();
+        state.unanswered.retain(|(id, ..)| *id != uuid);
+        if finished {
+            state.running.remove(&uuid);
+        } else if state.unanswered.len() != before {
+            state.running.insert(uuid);
+        }
+    }
+
+    /// The caller stopped the pipeline. It is not replayed, but its stream
+    /// still ends with the shell if no `PipelineFinished` arrives first.
+    pub(crate) fn abandoned(&self, uuid: uuid::Uuid) {
+        let mut state = self.state();
+        let before = state.unanswered.len();
+        state.unanswered.retain(|(id, ..)| *id != uuid);
+        if state.unanswered.len() != before {
+            state.running.insert(uuid);
+        }
+    }
+
+    /// Whether `event` belongs to an init script run, which is consumed here
+    /// rather than forwarded.
+    pub(crate) fn absorb_init_event(&self, event: &UserEvent) -> bool {
+        let pipeline_id = event.pipeline_id();
+        let mut state = self.state();
+        if !state.init_runs.contains(&pipeline_id) {
+            return false;
+        }
+        match event {
+            UserEvent::ErrorRecord { error_record, .. } => {
+                warn!(%pipeline_id, error = %error_record.render_concise(), "session init script reported an error");
+            }
+            UserEvent::PipelineFinished { .. } => {
+                debug!(%pipeline_id, "session init script finished");
+                state.init_runs.remove(&pipeline_id);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// The server dropped the shell: fail the pipelines it was running and
+    /// plan the replay on a new one, or `None` once [`MAX_ATTEMPTS`]
+    /// recreations in a row went unanswered.
+    pub(crate) fn restart(&self) -> Option<Restart> {
+        let mut state = self.state();
+        if state.attempts >= MAX_ATTEMPTS {
+            return None;
+        }
+        state.attempts += 1;
+        let backoff = match state.attempts {
+            1 => Duration::ZERO,
+            n => Duration::from_secs(1 << (n - 2).min(5)).min(MAX_BACKOFF),
+        };
+        // Init runs on the old shell will never finish.
+        state.init_runs.clear();
+
+        let running: Vec<_> = state.running.drain().collect();
+        let mut lost_events = Vec::with_capacity(running.len() * 2);
+        for uuid in running {
+            state.lost.insert(uuid);
+            let error_record = ErrorRecord::builder()
+                .message("The server dropped the shell running this pipeline.".to_owned())
+                .fully_qualified_error_id(Some("PipelineLostWithShell".to_owned()))
+                .build();
+            lost_events.push(UserEvent::ErrorRecord {
+                error_record,
+                handle: PipelineHandle::new(uuid),
+            });
+            lost_events.push(UserEvent::PipelineFinished {
+                pipeline: PipelineHandle::new(uuid),
+            });
+        }
+
+        let init = self.init_script.as_ref().map(|script| {
+            let uuid = uuid::Uuid::new_v4();
+            state.init_runs.insert(uuid);
+            UserOperation::RunScriptFile {
+                uuid,
+                script: script.clone(),
+            }
+        });
+        let invocations = state.unanswered.iter().map(|(uuid, spec, input)| {
+            if input.is_empty() {
+                UserOperation::InvokeWithSpec {
+                    uuid: *uuid,
+                    spec: spec.clone(),
+                }
+            } else {
+                UserOperation::InvokeWithInput {
+                    uuid: *uuid,
+                    spec: spec.clone(),
+                    input: input.clone(),
+                }
+            }
+        });
+        let replay = init.into_iter().chain(invocations).collect();
+        Some(Restart {
+            backoff,
+            lost_events,
+            replay,
+        })
+    }
+}
+
+/// Whether `e` ended the session because the server no longer knows the shell.
+pub(crate) fn is_shell_not_found(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|e| e.downcast_ref::<PwshCoreError>())
+        .any(|e| matches!(e, PwshCoreError::ShellNotFound { .. }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(script: &str) -> PipelineSpec {
+        PipelineSpec::script_file(script)
+    }
+
+    #[test]
+    fn replays_init_script_then_unanswered_invocations_in_order() {
+        let recovery = Recovery::new(&SessionRecreation {
+            init_script: Some("Import-Module Foo".to_owned()),
+        });
+        let (first, second, answered) = (
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4(),
+        );
+        recovery.invoked(first, &spec("Get-Date"), &[]);
+        recovery.invoked(answered, &spec("Get-Item ."), &[]);
+        recovery.invoked(second, &spec("$input"), &[PsValue::from("x")]);
+        recovery.answered(&UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(answered),
+        });
+
+        let restart = recovery.restart().expect("first recreation");
+        assert_eq!(restart.backoff, Duration::ZERO);
+        assert!(restart.lost_events.is_empty());
+        let init_uuid = match restart.replay.as_slice() {
+            [
+                UserOperation::RunScriptFile { uuid, script },
+                UserOperation::InvokeWithSpec { uuid: a, .. },
+                UserOperation::InvokeWithInput { uuid: b, input, .. },
+            ] => {
+                assert_eq!(script, "Import-Module Foo");
+                assert_eq!((*a, *b), (first, second));
+                assert_eq!(input.len(), 1);
+                *uuid
+            }
+            other => panic!("unexpected replay: {other:?}"),
+        };
+
+        let finished = UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(init_uuid),
+        };
+        assert!(recovery.absorb_init_event(&finished));
+        assert!(
+            !recovery.absorb_init_event(&finished),
+            "the init run is over once it finished"
+        );
+        assert!(!recovery.absorb_init_event(&UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(first),
+        }));
+    }
+
+    #[test]
+    fn running_pipelines_are_failed_and_finished_on_restart() {
+        let recovery = Recovery::new(&SessionRecreation::default());
+        let (running, stopped) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        recovery.invoked(running, &spec("Get-Date"), &[]);
+        recovery.invoked(stopped, &spec("Get-Item ."), &[]);
+        recovery.answered(&UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(uuid::Uuid::new_v4()),
+        });
+        recovery.answered(&UserEvent::ErrorRecord {
+            error_record: ErrorRecord::builder().message("late".to_owned()).build(),
+            handle: PipelineHandle::new(running),
+        });
+        recovery.abandoned(stopped);
+
+        let restart = recovery.restart().expect("first recreation");
+        assert!(restart.replay.is_empty(), "nothing is left unanswered");
+        let mut lost: Vec<_> = restart
+            .lost_events
+            .iter()
+            .filter(|event| matches!(event, UserEvent::PipelineFinished { .. }))
+            .map(UserEvent::pipeline_id)
+            .collect();
+        lost.sort();
+        let mut expected = vec![running, stopped];
+        expected.sort();
+        assert_eq!(lost, expected);
+        assert!(restart.lost_events.iter().any(|event| matches!(
+            event,
+            UserEvent::ErrorRecord { error_record, handle }
+                if handle.id() == running
+                    && error_record.fully_qualified_error_id.as_deref()
+                        == Some("PipelineLostWithShell")
+        )));
+
+        // The synthetic events pass through without counting as an answer.
+        for event in &restart.lost_events {
+            recovery.answered(event);
+        }
+        let second = recovery.restart().expect("second recreation");
+        assert_eq!(second.backoff, Duration::from_secs(1));
+        assert!(second.lost_events.is_empty());
+    }
+
+    #[test]
+    fn recreation_gives_up_after_max_attempts_and_resets_on_an_answer() {
+        let recovery = Recovery::new(&SessionRecreation::default());
+        let backoffs: Vec<_> = (0..MAX_ATTEMPTS)
+            .map(|_| recovery.restart().expect("within the cap").backoff)
+            .collect();
+        assert!(backoffs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(recovery.restart().is_none());
+
+        let uuid = uuid::Uuid::new_v4();
+        recovery.invoked(uuid, &spec("Get-Date"), &[]);
+        recovery.answered(&UserEvent::PipelineFinished {
+            pipeline: PipelineHandle::new(uuid),
+        });
+        assert_eq!(
+            recovery.restart().expect("a new shell answered").backoff,
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn shell_not_found_is_found_behind_context() {
+        let e = anyhow::Error::from(PwshCoreError::ShellNotFound {
+            reason: "gone".to_owned(),
+        })
+        .context("Failed to accept server response");
+        assert!(is_shell_not_found(&e));
+        assert!(!is_shell_not_found(&anyhow::anyhow!("HTTP error")));
+    }
+}
@@ -3,7 +3,11 @@ use std::time::Duration;
 use anyhow::Context;
 use futures::channel::mpsc;
 use futures::future::Either;
-use futures::{SinkExt, StreamExt, stream::FuturesUnordered};
+use futures::{
+    SinkExt, Stream, StreamExt,
+    stream::{FusedStream, FuturesUnordered},
+};
+use ironposh_client_core::PwshCoreError;
 use ironposh_client_core::connector::active_session::{TransportErrorDisposition, UserEvent};
 use ironposh_client_core::connector::{
     ActiveSessionOutput, UserOperation,
@@ -12,6 +16,7 @@ use ironposh_client_core::connector::{
 };
 use tracing::{debug, error, info, instrument, trace, warn};
 
+use crate::clock::Instant;
 use crate::{HostResponse, HttpClient};
 
 /// Resolve deferred send variants into concrete `SendBack` requests.
@@ -43,13 +48,59 @@ fn resolve_deferred_sends(
     }
 }
 
+/// Dispatch operations queued behind a Disconnect/Reconnect that was aborted by a
+/// transport error, splitting the result into requests to launch and the remaining
+/// outputs for [`process_session_outputs`].
+fn take_queued_operations(
+    active_session: &mut ironposh_client_core::connector::active_session::ActiveSession,
+) -> anyhow::Result<(Vec<TrySend>, Vec<ActiveSessionOutput>)> {
+    let mut sends = Vec::new();
+    let mut outputs = Vec::new();
+    let dispatched = active_session
+        .dispatch_queued_operations()
+        .context("Failed to dispatch queued operations")?;
+    for out in dispatched {
+        match resolve_deferred_sends(out, active_session)? {
+            ActiveSessionOutput::SendBack(reqs) => sends.extend(reqs),
+            other => outputs.push(other),
+        }
+    }
+    Ok((sends, outputs))
+}
+
+/// Send `try_send`, after `delay` when set (an interrupted Receive's retry).
 fn launch<C: HttpClient>(
     client: &C,
     try_send: TrySend,
+    delay: Option<Duration>,
 ) -> impl core::future::Future<Output = (ConnectionId, anyhow::Result<HttpResponseTargeted>)> {
     let conn_id = try_send.get_connection_id();
     let response = client.send_request(try_send);
-    async move { (conn_id, response.await) }
+    async move {
+        if let Some(delay) = delay {
+            futures_timer::Delay::new(delay).await;
+        }
+        (conn_id, response.await)
+    }
+}
+
+/// `delay` plus the wait `WinRmConfig::rate_limit` puts on `try_send`; a
+/// wait is reported as `SessionEvent::Throttled`.
+fn paced_delay(
+    active_session: &mut ironposh_client_core::connector::active_session::ActiveSession,
+    started: Instant,
+    try_send: &TrySend,
+    delay: Option<Duration>,
+    session_event_tx: &mpsc::UnboundedSender<crate::SessionEvent>,
+) -> Option<Duration> {
+    let Some(wait) = active_session.pace(try_send.connection_id(), started.elapsed()) else {
+        return delay;
+    };
+    let _ = session_event_tx.unbounded_send(crate::SessionEvent::Throttled {
+        wait,
+        stats: active_session.throttle_stats(),
+    });
+    Some(delay.map_or(wait, |delay| delay.max(wait)))
 }
 
 /// Emit a `PoolLifecycleEvent` when the runspace pool state crossed a
@@ -109,23 +160,37 @@ fn emit_pool_lifecycle_transition(
 pub async fn start_active_session_loop(
     runspace_polling_request: TrySend,
     mut active_session: ironposh_client_core::connector::active_session::ActiveSession,
-    client: impl HttpClient,
-    mut user_input_rx: mpsc::Receiver<UserOperation>,
+    client: &impl HttpClient,
+    mut user_input_rx: impl Stream<Item = UserOperation> + FusedStream + Unpin,
     mut user_output_tx: mpsc::Sender<UserEvent>,
     mut user_input_tx: mpsc::Sender<UserOperation>,
     host_call_tx: mpsc::UnboundedSender<ironposh_client_core::host::HostCall>,
-    mut host_resp_rx: mpsc::UnboundedReceiver<HostResponse>,
+    host_resp_rx: &mut mpsc::UnboundedReceiver<HostResponse>,
     lifecycle_tx: mpsc::UnboundedSender<crate::PoolLifecycleEvent>,
+    session_event_tx: mpsc::UnboundedSender<crate::SessionEvent>,
 ) -> anyhow::Result<()> {
     use ironposh_client_core::connector::active_session::ActiveSessionOutput;
 
     // pending HTTP requests
     let mut inflight: FuturesUnordered<_> = FuturesUnordered::new();
 
+    // Origin of the times the rate limit is paced against.
+    let started = Instant::now();
+
     // The initial poll is a Receive; record its connection so a later Disconnect retires
     // exactly the long-poll Receive (ActiveSession tracks subsequent Receives itself).
     active_session.note_receive_sent(runspace_polling_request.get_connection_id());
-    inflight.push(launch(&client, runspace_polling_request));
+    // Requests run concurrently here, each on its own connection: park the
+    // long-poll on the first one and keep user-driven requests off it.
+    active_session.dedicate_receive_connection(runspace_polling_request.get_connection_id());
+    let delay = paced_delay(
+        &mut active_session,
+        started,
+        &runspace_polling_request,
+        None,
+        &session_event_tx,
+    );
+    inflight.push(launch(client, runspace_polling_request, delay));
 
     // Track the pool state to surface disconnect/reconnect transitions.
     let mut pool_state = active_session.runspace_pool_state();
@@ -169,6 +234,10 @@ pub async fn start_active_session_loop(
 
                         // The http_response is already a HttpResponseTargeted from the client
                         let targeted_response = http_response;
+                        active_session.observe_server_clock(
+                            targeted_response.response(),
+                            crate::clock::unix_now_secs(),
+                        );
                         let step_results = active_session
                             .accept_server_response(targeted_response)
                             .map_err(|e| {
@@ -191,7 +260,14 @@ pub async fn start_active_session_loop(
                                 ActiveSessionOutput::SendBack(reqs) => {
                                     trace!(target: "network", request_count = reqs.len(), "launching HTTP requests in parallel");
                                     for r in reqs {
-                                        inflight.push(launch(&client, r));
+                                        let delay = paced_delay(
+                                            &mut active_session,
+                                            started,
+                                            &r,
+                                            None,
+                                            &session_event_tx,
+                                        );
+                                        inflight.push(launch(client, r, delay));
                                     }
                                 }
                                 ActiveSessionOutput::SendBackError(e) => {
@@ -237,7 +313,14 @@ pub async fn start_active_session_loop(
                                     match step_result {
                                         ActiveSessionOutput::SendBack(reqs) => {
                                             for r in reqs {
-                                                inflight.push(launch(&client, r));
+                                                let delay = paced_delay(
+                                                    &mut active_session,
+                                                    started,
+                                                    &r,
+                                                    None,
+                                                    &session_event_tx,
+                                                );
+                                                inflight.push(launch(client, r, delay));
                                             }
                                         }
                                         other => {
@@ -246,7 +329,7 @@ pub async fn start_active_session_loop(
                                                 &mut user_output_tx,
                                                 &mut user_input_tx,
                                                 &host_call_tx,
-                                                &mut host_resp_rx,
+                                                host_resp_rx,
                                             )
                                             .await?;
                                         }
@@ -255,6 +338,50 @@ pub async fn start_active_session_loop(
                                 ActiveSessionOutput::OperationSuccess => {
                                     trace!(target: "session", "operation completed successfully");
                                 }
+                                ActiveSessionOutput::OperationQueued { operation_type }
+                                | ActiveSessionOutput::OperationDispatched { operation_type } => {
+                                    trace!(target: "session", operation_type, "queued operation update");
+                                }
+                                ActiveSessionOutput::ProtocolWarning {
+                                    stream,
+                                    pipeline,
+                                    error,
+                                } => {
+                                    warn!(target: "network", %stream, %error, "skipped malformed server data");
+                                    let _ = session_event_tx.unbounded_send(
+                                        crate::SessionEvent::ProtocolWarning {
+                                            stream,
+                                            pipeline: pipeline.map(|p| p.id()),
+                                            message: error.to_string(),
+                                            diagnostics: error
+                                                .blob_diagnostics()
+                                                .cloned()
+                                                .map(Box::new),
+                                        },
+                                    );
+                                }
+                                ActiveSessionOutput::Interrupted {
+                                    interruption,
+                                    retry,
+                                } => {
+                                    warn!(target: "network", ?interruption, retrying = retry.is_some(), "request interrupted by the server");
+                                    let _ = session_event_tx.unbounded_send(
+                                        crate::SessionEvent::Interrupted {
+                                            interruption,
+                                            retrying: retry.is_some(),
+                                        },
+                                    );
+                                    if let Some((delay, request)) = retry {
+                                        let delay = paced_delay(
+                                            &mut active_session,
+                                            started,
+                                            &request,
+                                            Some(delay),
+                                            &session_event_tx,
+                                        );
+                                        inflight.push(launch(client, request, delay));
+                                    }
+                                }
                                 // INVARIANT: resolve_deferred_sends converts these into
                                 // SendBack before they reach here. Surface a recoverable
                                 // error rather than panicking the session task if that
@@ -267,6 +394,24 @@ pub async fn start_active_session_loop(
                                 }
                             }
                         }
+
+                        // The server broke the pool: every pipeline was failed and
+                        // finished above, so the session ends here.
+                        if let Some(reason) = active_session.broken_reason() {
+                            error!(target: "session", %reason, "runspace pool broken, ending session");
+                            return Err(PwshCoreError::RunspacePoolBroken {
+                                reason: reason.to_owned(),
+                            }
+                            .into());
+                        }
+
+                        // A Close deleted the shell; nothing is left to poll.
+                        if active_session.runspace_pool_state()
+                            == ironposh_client_core::runspace_pool::RunspacePoolState::Closed
+                        {
+                            info!(target: "session", "runspace pool closed, ending session");
+                            return Ok(());
+                        }
                     }
                     Err(e) => {
                         // A transport-level failure on a dying connection during
@@ -301,7 +446,34 @@ pub async fn start_active_session_loop(
                                 let resume = active_session
                                     .fire_active_receive()
                                     .context("Failed to resume receive after aborted disconnect")?;
-                                inflight.push(launch(&client, resume));
+                                let delay = paced_delay(
+                                    &mut active_session,
+                                    started,
+                                    &resume,
+                                    None,
+                                    &session_event_tx,
+                                );
+                                inflight.push(launch(client, resume, delay));
+                                // Operations queued behind the aborted transition can go now.
+                                let (sends, outputs) = take_queued_operations(&mut active_session)?;
+                                for r in sends {
+                                    let delay = paced_delay(
+                                        &mut active_session,
+                                        started,
+                                        &r,
+                                        None,
+                                        &session_event_tx,
+                                    );
+                                    inflight.push(launch(client, r, delay));
+                                }
+                                process_session_outputs(
+                                    outputs,
+                                    &mut user_output_tx,
+                                    &mut user_input_tx,
+                                    &host_call_tx,
+                                    host_resp_rx,
+                                )
+                                .await?;
                             }
                             TransportErrorDisposition::ReconnectAborted => {
                                 warn!(
@@ -316,6 +488,26 @@ pub async fn start_active_session_loop(
                                     &active_session,
                                     &lifecycle_tx,
                                 );
+                                // Operations queued behind the aborted transition can go now.
+                                let (sends, outputs) = take_queued_operations(&mut active_session)?;
+                                for r in sends {
+                                    let delay = paced_delay(
+                                        &mut active_session,
+                                        started,
+                                        &r,
+                                        None,
+                                        &session_event_tx,
+                                    );
+                                    inflight.push(launch(client, r, delay));
+                                }
+                                process_session_outputs(
+                                    outputs,
+                                    &mut user_output_tx,
+                                    &mut user_input_tx,
+                                    &host_call_tx,
+                                    host_resp_rx,
+                                )
+                                .await?;
                             }
                         }
                     }
@@ -347,7 +539,14 @@ pub async fn start_active_session_loop(
                         ActiveSessionOutput::SendBack(reqs) => {
                             trace!(target: "network", request_count = reqs.len(), "launching HTTP requests from user operation");
                             for r in reqs {
-                                inflight.push(launch(&client, r));
+                                let delay = paced_delay(
+                                    &mut active_session,
+                                    started,
+                                    &r,
+                                    None,
+                                    &session_event_tx,
+                                );
+                                inflight.push(launch(client, r, delay));
                             }
                         }
                         ActiveSessionOutput::UserEvent(event) => {
@@ -386,7 +585,14 @@ pub async fn start_active_session_loop(
                             match step_result {
                                 ActiveSessionOutput::SendBack(reqs) => {
                                     for r in reqs {
-                                        inflight.push(launch(&client, r));
+                                        let delay = paced_delay(
+                                            &mut active_session,
+                                            started,
+                                            &r,
+                                            None,
+                                            &session_event_tx,
+                                        );
+                                        inflight.push(launch(client, r, delay));
                                     }
                                 }
                                 other => {
@@ -395,7 +601,7 @@ pub async fn start_active_session_loop(
                                         &mut user_output_tx,
                                         &mut user_input_tx,
                                         &host_call_tx,
-                                        &mut host_resp_rx,
+                                        host_resp_rx,
                                     )
                                     .await?;
                                 }
@@ -404,11 +610,25 @@ pub async fn start_active_session_loop(
                         ActiveSessionOutput::OperationSuccess => {
                             trace!(target: "session", "operation completed successfully");
                         }
+                        ActiveSessionOutput::OperationQueued { operation_type } => {
+                            debug!(
+                                target: "session",
+                                operation_type,
+                                "operation queued until the pool transition settles"
+                            );
+                        }
+                        ActiveSessionOutput::OperationDispatched { operation_type } => {
+                            trace!(target: "session", operation_type, "queued operation dispatched");
+                        }
                         ActiveSessionOutput::SendBackError(e) => {
                             error!(target: "session", error = %e, "session step failed");
                             return Err(anyhow::anyhow!("Session step failed: {e}"));
                         }
-                        ActiveSessionOutput::Ignore => {}
+                        // Only server responses are interrupted or carry
+                        // protocol warnings.
+                        ActiveSessionOutput::Ignore
+                        | ActiveSessionOutput::Interrupted { .. }
+                        | ActiveSessionOutput::ProtocolWarning { .. } => {}
                         // INVARIANT: resolve_deferred_sends converts these into SendBack
                         // before they reach here. Surface a recoverable error rather than
                         // panicking the session task if that ever stops holding.
@@ -446,10 +666,12 @@ async fn process_session_outputs(
             }
             ActiveSessionOutput::SendBack(_)
             | ActiveSessionOutput::SendAndThenReceive { .. }
-            | ActiveSessionOutput::PendingReceive { .. } => {
+            | ActiveSessionOutput::PendingReceive { .. }
+            | ActiveSessionOutput::Interrupted { .. }
+            | ActiveSessionOutput::ProtocolWarning { .. } => {
                 // This should be handled at the caller level
                 warn!(
-                    "SendBack/SendAndThenReceive/PendingReceive should not reach process_session_outputs"
+                    "SendBack/SendAndThenReceive/PendingReceive/Interrupted/ProtocolWarning should not reach process_session_outputs"
                 );
             }
             ActiveSessionOutput::SendBackError(e) => {
@@ -499,6 +721,10 @@ async fn process_session_outputs(
             ActiveSessionOutput::OperationSuccess => {
                 trace!(target: "session", "operation completed successfully");
             }
+            ActiveSessionOutput::OperationQueued { operation_type }
+            | ActiveSessionOutput::OperationDispatched { operation_type } => {
+                trace!(target: "session", operation_type, "queued operation update");
+            }
         }
     }
     Ok(())
@@ -539,7 +765,7 @@ mod tests {
     use futures::task::noop_waker_ref;
     use ironposh_client_core::connector::{
         Connector, ConnectorStepResult, TransportSecurity, WinRmConfig,
-        config::{AuthenticatorConfig, TlsOptions},
+        config::AuthenticatorConfig,
         connection_pool::{ConnectionId, TrySend},
         http::{HttpBody, HttpRequest, HttpResponse, HttpResponseTargeted, ServerAddress},
     };
@@ -559,6 +785,7 @@ mod tests {
     struct SentRequest {
         kind: RequestKind,
         conn_id: ConnectionId,
+        message_id: String,
         responder: oneshot::Sender<anyhow::Result<HttpResponseTargeted>>,
     }
 
@@ -576,11 +803,13 @@ mod tests {
             async move {
                 let (request, conn_id) = expect_just_send(try_send);
                 let kind = classify_request(&request);
+                let message_id = message_id(&request);
                 let (responder, response_rx) = oneshot::channel();
                 sent_tx
                     .send(SentRequest {
                         kind,
                         conn_id,
+                        message_id,
                         responder,
                     })
                     .expect("test request receiver must stay alive");
@@ -597,19 +826,20 @@ mod tests {
         let (mut user_input_tx, user_input_rx) = mpsc::channel(8);
         let (user_output_tx, _user_output_rx) = mpsc::channel(8);
         let (host_call_tx, _host_call_rx) = mpsc::unbounded();
-        let (_host_resp_tx, host_resp_rx) = mpsc::unbounded();
+        let (_host_resp_tx, mut host_resp_rx) = mpsc::unbounded();
         let (lifecycle_tx, _lifecycle_rx) = mpsc::unbounded();
 
         let session = start_active_session_loop(
             initial_receive,
             active_session,
-            client,
+            &client,
             user_input_rx,
             user_output_tx,
             user_input_tx.clone(),
             host_call_tx,
-            host_resp_rx,
+            &mut host_resp_rx,
             lifecycle_tx,
+            mpsc::unbounded().0,
         );
         futures::pin_mut!(session);
 
@@ -628,7 +858,11 @@ mod tests {
             .responder
             .send(Ok(xml_response(
                 disconnect.conn_id,
-                shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+                shell_op_response_xml(
+                    &disconnect.message_id,
+                    "DisconnectResponse",
+                    "<rsp:DisconnectResponse/>",
+                ),
             )))
             .expect("complete Disconnect request");
         assert_pending(poll_session(session.as_mut()));
@@ -637,7 +871,7 @@ mod tests {
             .responder
             .send(Ok(xml_response(
                 initial_receive.conn_id,
-                FAULT_ENVELOPE.to_owned(),
+                fault_envelope(&initial_receive.message_id),
             )))
             .expect("complete stale receive request");
         assert_pending(poll_session(session.as_mut()));
@@ -653,7 +887,11 @@ mod tests {
             .responder
             .send(Ok(xml_response(
                 reconnect.conn_id,
-                shell_op_response_xml("ReconnectResponse", "<rsp:ReconnectResponse/>"),
+                shell_op_response_xml(
+                    &reconnect.message_id,
+                    "ReconnectResponse",
+                    "<rsp:ReconnectResponse/>",
+                ),
             )))
             .expect("complete Reconnect request");
         assert_pending(poll_session(session.as_mut()));
@@ -669,19 +907,20 @@ mod tests {
         let (mut user_input_tx, user_input_rx) = mpsc::channel(8);
         let (user_output_tx, _user_output_rx) = mpsc::channel(8);
         let (host_call_tx, _host_call_rx) = mpsc::unbounded();
-        let (_host_resp_tx, host_resp_rx) = mpsc::unbounded();
+        let (_host_resp_tx, mut host_resp_rx) = mpsc::unbounded();
         let (lifecycle_tx, mut lifecycle_rx) = mpsc::unbounded();
 
         let session = start_active_session_loop(
             initial_receive,
             active_session,
-            client,
+            &client,
             user_input_rx,
             user_output_tx,
             user_input_tx.clone(),
             host_call_tx,
-            host_resp_rx,
+            &mut host_resp_rx,
             lifecycle_tx,
+            mpsc::unbounded().0,
         );
         futures::pin_mut!(session);
 
@@ -708,7 +947,11 @@ mod tests {
             .responder
             .send(Ok(xml_response(
                 disconnect.conn_id,
-                shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+                shell_op_response_xml(
+                    &disconnect.message_id,
+                    "DisconnectResponse",
+                    "<rsp:DisconnectResponse/>",
+                ),
             )))
             .expect("complete Disconnect request");
         assert_pending(poll_session(session.as_mut()));
@@ -731,19 +974,20 @@ mod tests {
         let (mut user_input_tx, user_input_rx) = mpsc::channel(8);
         let (user_output_tx, _user_output_rx) = mpsc::channel(8);
         let (host_call_tx, _host_call_rx) = mpsc::unbounded();
-        let (_host_resp_tx, host_resp_rx) = mpsc::unbounded();
+        let (_host_resp_tx, mut host_resp_rx) = mpsc::unbounded();
         let (lifecycle_tx, mut lifecycle_rx) = mpsc::unbounded();
 
         let session = start_active_session_loop(
             initial_receive,
             active_session,
-            client,
+            &client,
             user_input_rx,
             user_output_tx,
             user_input_tx.clone(),
             host_call_tx,
-            host_resp_rx,
+            &mut host_resp_rx,
             lifecycle_tx,
+            mpsc::unbounded().0,
         );
         futures::pin_mut!(session);
 
@@ -796,19 +1040,20 @@ mod tests {
         let (mut user_input_tx, user_input_rx) = mpsc::channel(8);
         let (user_output_tx, _user_output_rx) = mpsc::channel(8);
         let (host_call_tx, _host_call_rx) = mpsc::unbounded();
-        let (_host_resp_tx, host_resp_rx) = mpsc::unbounded();
+        let (_host_resp_tx, mut host_resp_rx) = mpsc::unbounded();
         let (lifecycle_tx, mut lifecycle_rx) = mpsc::unbounded();
 
         let session = start_active_session_loop(
             initial_receive,
             active_session,
-            client,
+            &client,
             user_input_rx,
             user_output_tx,
             user_input_tx.clone(),
             host_call_tx,
-            host_resp_rx,
+            &mut host_resp_rx,
             lifecycle_tx,
+            mpsc::unbounded().0,
         );
         futures::pin_mut!(session);
 
@@ -827,7 +1072,11 @@ mod tests {
             .responder
             .send(Ok(xml_response(
                 disconnect.conn_id,
-                shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+                shell_op_response_xml(
+                    &disconnect.message_id,
+                    "DisconnectResponse",
+                    "<rsp:DisconnectResponse/>",
+                ),
             )))
             .expect("complete Disconnect request");
         assert_pending(poll_session(session.as_mut()));
@@ -888,19 +1137,20 @@ mod tests {
         let (user_input_tx, user_input_rx) = mpsc::channel(8);
         let (user_output_tx, _user_output_rx) = mpsc::channel(8);
         let (host_call_tx, _host_call_rx) = mpsc::unbounded();
-        let (_host_resp_tx, host_resp_rx) = mpsc::unbounded();
+        let (_host_resp_tx, mut host_resp_rx) = mpsc::unbounded();
         let (lifecycle_tx, _lifecycle_rx) = mpsc::unbounded();
 
         let session = start_active_session_loop(
             initial_receive,
             active_session,
-            client,
+            &client,
             user_input_rx,
             user_output_tx,
             user_input_tx,
             host_call_tx,
-            host_resp_rx,
+            &mut host_resp_rx,
             lifecycle_tx,
+            mpsc::unbounded().0,
         );
         futures::pin_mut!(session);
 
@@ -955,6 +1205,7 @@ mod tests {
             panic!("expected SendBack for Create");
         };
         let (request, conn_id) = expect_just_send(try_send);
+        let create_message_id = message_id(&request);
         let create_xml = request
             .body
             .expect("create has a body")
@@ -964,14 +1215,18 @@ mod tests {
         let rpid = extract_shell_id(&create_xml);
 
         let create_response =
-            include_str!("../../ironposh-client-core/tests/resources/resource_created.xml");
+            include_str!("../../ironposh-client-core/tests/resources/resource_created.xml")
+                .replace(
+                    "uuid:622d032a-f9b3-498d-b447-1359c3bb14f3",
+                    &create_message_id,
+                );
         let result = connector
-            .step(Some(xml_response(conn_id, create_response.to_owned())))
+            .step(Some(xml_response(conn_id, create_response)))
             .expect("accept CreateResponse");
         let ConnectorStepResult::SendBack { try_send } = result else {
             panic!("expected SendBack for Receive");
         };
-        let (_request, conn_id) = expect_just_send(try_send);
+        let (request, conn_id) = expect_just_send(try_send);
 
         let session_capability = SessionCapability {
             protocol_version: "2.3".to_owned(),
@@ -984,6 +1239,7 @@ mod tests {
             .runspace_state(RunspacePoolStateValue::Opened)
             .build();
         let receive_response = receive_response_xml(
+            &message_id(&request),
             rpid,
             &[&session_capability, &application_private_data, &pool_opened],
         );
@@ -1039,24 +1295,40 @@ mod tests {
             .window_size(size.clone())
             .max_window_size(size.clone())
             .max_physical_window_size(size)
-            .build();
+            .build()
+            .expect("valid host data");
         let host_info = HostInfo::builder()
             .host_default_data(host_data)
             .use_runspace_host(true)
             .build();
 
-        WinRmConfig {
-            server: (ServerAddress::parse("127.0.0.1").unwrap(), 5985),
-            transport: TransportSecurity::HttpInsecure,
-            authentication: AuthenticatorConfig::Basic {
+        WinRmConfig::builder()
+            .server((ServerAddress::parse("127.0.0.1").unwrap(), 5985))
+            .transport(TransportSecurity::HttpInsecure)
+            .authentication(AuthenticatorConfig::Basic {
                 username: "user".into(),
                 password: "pass".into(),
-            },
-            host_info,
-            operation_timeout_secs: Some(1.0),
-            tls: TlsOptions::default(),
-            configuration_name: None,
-        }
+            })
+            .host_info(host_info)
+            .operation_timeout_secs(Some(1.0))
+            .build()
+    }
+
+    /// The request's `a:MessageID`, for the response's `RelatesTo`.
+    fn message_id(request: &HttpRequest) -> String {
+        let body = request
+            .body
+            .as_ref()
+            .expect("test request has a body")
+            .as_str()
+            .expect("test request body is plaintext XML");
+        let marker = "<a:MessageID>";
+        let start = body
+            .find(marker)
+            .map(|idx| idx + marker.len())
+            .expect("request must carry a MessageID");
+        let end = start + body[start..].find('<').expect("MessageID must be closed");
+        body[start..end].trim().to_owned()
     }
 
     fn extract_shell_id(create_xml: &str) -> uuid::Uuid {
@@ -1070,7 +1342,11 @@ mod tests {
             .expect("ShellId must be a UUID")
     }
 
-    fn receive_response_xml(rpid: uuid::Uuid, messages: &[&dyn PsObjectWithType]) -> String {
+    fn receive_response_xml(
+        relates_to: &str,
+        rpid: uuid::Uuid,
+        messages: &[&dyn PsObjectWithType],
+    ) -> String {
         let mut streams = String::new();
         for (index, message) in messages.iter().enumerate() {
             let remoting_message = PowerShellRemotingMessage::new(
@@ -1098,6 +1374,7 @@ mod tests {
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReceiveResponse</a:Action>
         <a:MessageID>uuid:6C334787-EF2C-40E4-992F-DE4599ED2505</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -1120,7 +1397,7 @@ mod tests {
         )
     }
 
-    fn shell_op_response_xml(action: &str, body_element: &str) -> String {
+    fn shell_op_response_xml(relates_to: &str, action: &str, body_element: &str) -> String {
         format!(
             r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
@@ -1129,6 +1406,7 @@ mod tests {
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/{action}</a:Action>
         <a:MessageID>uuid:6C334787-EF2C-40E4-992F-DE4599ED2505</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -1138,7 +1416,9 @@ mod tests {
         )
     }
 
-    const FAULT_ENVELOPE: &str = r#"<s:Envelope xml:lang="en-US"
+    fn fault_envelope(relates_to: &str) -> String {
+        format!(
+            r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
     xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing"
     xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd"
@@ -1146,6 +1426,7 @@ mod tests {
     <s:Header>
         <a:Action>http://schemas.dmtf.org/wbem/wsman/1/wsman/fault</a:Action>
         <a:MessageID>uuid:BB7AF8AE-D64A-422D-B36E-15A04FA17C5C</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -1158,5 +1439,7 @@ mod tests {
             </s:Reason>
         </s:Fault>
     </s:Body>
-</s:Envelope>"#;
+</s:Envelope>"#
+        )
+    }
 }
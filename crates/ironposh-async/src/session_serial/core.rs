@@ -13,13 +13,13 @@ use ironposh_client_core::PwshCoreError;
 use ironposh_client_core::connector::active_session::{
     ActiveSession, TransportErrorDisposition, UserEvent,
 };
-use ironposh_client_core::connector::http::HttpResponseTargeted;
+use ironposh_client_core::connector::http::{HttpResponse, HttpResponseTargeted, Interruption};
 use ironposh_client_core::connector::{
     ActiveSessionOutput, UserOperation,
     connection_pool::{ConnectionId, TrySend},
 };
 use ironposh_client_core::host::{HostCall, HostCallScope};
-use ironposh_client_core::runspace_pool::DesiredStream;
+use ironposh_client_core::runspace_pool::{DesiredStream, RunspacePoolState};
 use tracing::{debug, error, info, trace, warn};
 use uuid::Uuid;
 
@@ -56,6 +56,16 @@ struct Queues {
 
     /// User events accumulated during processing, to be drained by the event loop.
     user_events: Vec<UserEvent>,
+
+    /// An interrupted Receive's resend and the time (ms since `epoch`) it may
+    /// go out. Holds back every other Receive until then.
+    retry: Option<(u64, TrySend)>,
+
+    /// Interruptions to report, with whether the request is being resent.
+    interruptions: Vec<(Interruption, bool)>,
+
+    /// `SessionEvent::ProtocolWarning`s to report.
+    protocol_warnings: Vec<crate::SessionEvent>,
 }
 
 impl Queues {
@@ -69,6 +79,9 @@ impl Queues {
             host_calls: VecDeque::new(),
             user_ops: VecDeque::new(),
             user_events: Vec::new(),
+            retry: None,
+            interruptions: Vec::new(),
+            protocol_warnings: Vec::new(),
         }
     }
 }
@@ -121,6 +134,25 @@ pub(super) trait SessionBackend {
     fn handle_transport_error(&mut self, conn_id: ConnectionId) -> TransportErrorDisposition;
 
     fn active_desired_streams(&self) -> Vec<DesiredStream>;
+
+    /// Sample the server clock from a response before it is accepted.
+    fn observe_server_clock(&mut self, _response: &HttpResponse, _local_unix_secs: i64) {}
+
+    /// Why the server broke the runspace pool, once it has.
+    fn broken_reason(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the runspace pool shell has been deleted (`UserOperation::Close`).
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    /// The shell id, once the runspace pool is disconnected
+    /// (`UserOperation::Suspend`).
+    fn suspended_shell_id(&self) -> Option<String> {
+        None
+    }
 }
 
 impl SessionBackend for ActiveSession {
@@ -153,13 +185,33 @@ impl SessionBackend for ActiveSession {
     fn active_desired_streams(&self) -> Vec<DesiredStream> {
         Self::active_desired_streams(self)
     }
+
+    fn observe_server_clock(&mut self, response: &HttpResponse, local_unix_secs: i64) {
+        Self::observe_server_clock(self, response, local_unix_secs);
+    }
+
+    fn broken_reason(&self) -> Option<&str> {
+        Self::broken_reason(self)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.runspace_pool_state() == RunspacePoolState::Closed
+    }
+
+    fn suspended_shell_id(&self) -> Option<String> {
+        if self.runspace_pool_state() == RunspacePoolState::Disconnected {
+            self.shell_id()
+        } else {
+            None
+        }
+    }
 }
 
 // ── Send priority ─────────────────────────────────────────────────────────
 
 /// Whether a `SendBack` should be pushed to the front or back of the work queue.
 ///
-/// `Front` is used for user-initiated Signals (Ctrl+C / KillPipeline) so they
+/// `Front` is used for user-initiated Signals (KillPipeline / SignalPipeline) so they
 /// get maximum priority in the single-connection serial loop.
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum SendPriority {
@@ -168,10 +220,13 @@ enum SendPriority {
 }
 
 impl SendPriority {
-    /// Compute the priority for a user operation — `Front` for KillPipeline,
-    /// `Normal` for everything else.
+    /// Compute the priority for a user operation — `Front` for KillPipeline and
+    /// SignalPipeline, `Normal` for everything else.
     fn for_user_op(op: &UserOperation) -> Self {
-        if matches!(op, UserOperation::KillPipeline { .. }) {
+        if matches!(
+            op,
+            UserOperation::KillPipeline { .. } | UserOperation::SignalPipeline { .. }
+        ) {
             Self::Front
         } else {
             Self::Normal
@@ -193,6 +248,11 @@ pub(super) struct SessionCore<S: SessionBackend = ActiveSession> {
     queues: Queues,
     host_call_state: HostCallState,
     consecutive_receive_transport_failures: u32,
+    /// With a heartbeat interval, how long the server may stay quiet before
+    /// an idle session probes it with a runspace-pool Receive.
+    probe_interval_ms: Option<u64>,
+    /// When the last response arrived (ms since `epoch`).
+    last_response_ms: u64,
 }
 
 impl SessionCore {
@@ -212,9 +272,18 @@ impl<S: SessionBackend> SessionCore<S> {
             queues: Queues::new(first_receive),
             host_call_state: HostCallState::Idle,
             consecutive_receive_transport_failures: 0,
+            probe_interval_ms: None,
+            last_response_ms: 0,
         }
     }
 
+    /// Probe an idle session at most every `interval`, so heartbeats have
+    /// a round trip to report.
+    pub(super) fn with_probe_interval(mut self, interval: Option<std::time::Duration>) -> Self {
+        self.probe_interval_ms = interval.map(|interval| interval.as_millis() as u64);
+        self
+    }
+
     pub(super) fn now_ms(&self) -> u64 {
         self.epoch.elapsed().as_millis() as u64
     }
@@ -230,7 +299,9 @@ impl<S: SessionBackend> SessionCore<S> {
     ///
     /// Disconnect/Reconnect require the parallel session loop: the serial loop
     /// keeps exactly one request in flight (usually a long-poll Receive), so a
-    /// Disconnect could never be issued concurrently with it. The client API
+    /// Disconnect could never be issued concurrently with it. `Suspend` is
+    /// kept: it disconnects between polls and then ends the loop, so nothing
+    /// has to run alongside it. The client API
     /// rejects these up front ([`crate::RemoteAsyncPowershellClient::disconnect`]);
     /// this is defense-in-depth — drop the op instead of terminating the session.
     ///
@@ -277,7 +348,8 @@ impl<S: SessionBackend> SessionCore<S> {
     ///   1. `work_queue` (Send operations)
     ///   2. `demanded_streams` (server will respond — key exchange, etc.)
     ///   3. pipeline streams from `speculative_streams`
-    ///   4. runspace-pool-only speculative → skip (returns `None`)
+    ///   4. runspace-pool-only speculative → skip (returns `None`), unless
+    ///      a keep-alive probe is due
     pub(super) fn promote_next_request(&mut self) -> anyhow::Result<Option<TrySend>> {
         self.next_wakeup_at_ms = None;
         let now_ms = self.now_ms();
@@ -286,6 +358,20 @@ impl<S: SessionBackend> SessionCore<S> {
             return Ok(Some(req));
         }
 
+        match self.queues.retry.take() {
+            Some((due_ms, req)) if due_ms <= now_ms => {
+                trace!(target: "serial", "promoting interrupted Receive resend");
+                self.in_flight_receive_target = None;
+                return Ok(Some(req));
+            }
+            Some((due_ms, req)) => {
+                self.next_wakeup_at_ms = Some(due_ms);
+                self.queues.retry = Some((due_ms, req));
+                return Ok(None);
+            }
+            None => {}
+        }
+
         // Prefer to hold Receives while a host call awaits its answer — the
         // server blocks them anyway, so polling early just burns round-trips.
         // But the answer can take arbitrarily long (a human at Read-Host) or
@@ -404,8 +490,25 @@ impl<S: SessionBackend> SessionCore<S> {
         }
 
         // Only speculative runspace-pool streams — skip to avoid blocking
-        // for OperationTimeout with no useful data.
+        // for OperationTimeout with no useful data, unless the server has
+        // been quiet for a whole probe interval.
         if !self.queues.speculative_streams.is_empty() {
+            if let Some(interval_ms) = self.probe_interval_ms {
+                let due_ms = self.last_response_ms + interval_ms;
+                if now_ms >= due_ms {
+                    let stream = self.queues.speculative_streams.remove(0);
+                    let target = TargetId::from_stream(&stream);
+                    trace!(target: "serial", "promoting runspace-pool Receive as a keep-alive probe");
+                    let receive = self
+                        .active_session
+                        .fire_receive(vec![stream], Some(self.receive_hold_secs(target, now_ms)))
+                        .context("Failed to build keep-alive Receive")?;
+                    self.in_flight_receive_target = Some(target);
+                    return Ok(Some(receive));
+                }
+                self.next_wakeup_at_ms =
+                    Some(self.next_wakeup_at_ms.map_or(due_ms, |cur| cur.min(due_ms)));
+            }
             diag!(
                 "DIAG promote: skipping speculative runspace-pool-only Receive (would block for OperationTimeout)"
             );
@@ -423,8 +526,11 @@ impl<S: SessionBackend> SessionCore<S> {
         let now_ms = self.now_ms();
         // A response came back — the link is alive; reset the transient-failure tally.
         self.consecutive_receive_transport_failures = 0;
+        self.last_response_ms = now_ms;
         let in_flight_receive = self.in_flight_receive_target.take();
 
+        self.active_session
+            .observe_server_clock(resp.response(), crate::clock::unix_now_secs());
         let outputs = match self.active_session.accept_server_response(resp) {
             Ok(outputs) => outputs,
             Err(e) => {
@@ -447,6 +553,7 @@ impl<S: SessionBackend> SessionCore<S> {
                 matches!(
                     o,
                     ActiveSessionOutput::PendingReceive { .. }
+                        | ActiveSessionOutput::Interrupted { .. }
                         | ActiveSessionOutput::OperationSuccess
                         | ActiveSessionOutput::Ignore
                 )
@@ -475,7 +582,7 @@ impl<S: SessionBackend> SessionCore<S> {
             operation = op.operation_type(),
             "user operation received while idle"
         );
-        if Self::drop_unsupported_op(&op) {
+        if Self::drop_unsupported_op(&op) || self.take_local_op(&op) {
             return Ok(());
         }
         let priority = SendPriority::for_user_op(&op);
@@ -529,8 +636,14 @@ impl<S: SessionBackend> SessionCore<S> {
             operation = op.operation_type(),
             "buffering user operation (HTTP in flight)"
         );
+        if self.take_local_op(&op) {
+            return;
+        }
         self.observe_user_op(&op);
-        if matches!(op, UserOperation::KillPipeline { .. }) {
+        if matches!(
+            op,
+            UserOperation::KillPipeline { .. } | UserOperation::SignalPipeline { .. }
+        ) {
             self.queues.user_ops.push_front(op);
         } else {
             self.queues.user_ops.push_back(op);
@@ -604,6 +717,32 @@ impl<S: SessionBackend> SessionCore<S> {
         std::mem::take(&mut self.queues.user_events)
     }
 
+    /// Drain interruptions to report, with whether each is being resent.
+    pub(super) fn drain_interruptions(&mut self) -> Vec<(Interruption, bool)> {
+        std::mem::take(&mut self.queues.interruptions)
+    }
+
+    /// Drain the protocol warnings to report.
+    pub(super) fn drain_protocol_warnings(&mut self) -> Vec<crate::SessionEvent> {
+        std::mem::take(&mut self.queues.protocol_warnings)
+    }
+
+    /// Set once the server broke the runspace pool; the loop ends after
+    /// dispatching the final user events.
+    pub(super) fn broken_reason(&self) -> Option<&str> {
+        self.active_session.broken_reason()
+    }
+
+    /// Set once a Close deleted the shell; the loop ends cleanly.
+    pub(super) fn is_closed(&self) -> bool {
+        self.active_session.is_closed()
+    }
+
+    /// Set once a Suspend disconnected the shell; the loop ends cleanly.
+    pub(super) fn suspended_shell_id(&self) -> Option<String> {
+        self.active_session.suspended_shell_id()
+    }
+
     /// Whether a HostCall is currently active (event loop uses this for `select!` guard).
     pub(super) fn is_host_call_active(&self) -> bool {
         matches!(self.host_call_state, HostCallState::Waiting { .. })
@@ -799,16 +938,60 @@ impl<S: SessionBackend> SessionCore<S> {
             ActiveSessionOutput::OperationSuccess => {
                 trace!(target: "serial", "enqueue: OperationSuccess (no-op)");
             }
+            ActiveSessionOutput::OperationQueued { operation_type }
+            | ActiveSessionOutput::OperationDispatched { operation_type } => {
+                trace!(target: "serial", operation_type, "enqueue: queued operation update (no-op)");
+            }
+            ActiveSessionOutput::Interrupted {
+                interruption,
+                retry,
+            } => {
+                warn!(target: "serial", ?interruption, retrying = retry.is_some(), "enqueue: Interrupted");
+                self.queues
+                    .interruptions
+                    .push((interruption, retry.is_some()));
+                if let Some((delay, req)) = retry {
+                    let due_ms = self.now_ms() + delay.as_millis() as u64;
+                    self.queues.retry = Some((due_ms, req));
+                }
+            }
+            ActiveSessionOutput::ProtocolWarning {
+                stream,
+                pipeline,
+                error,
+            } => {
+                warn!(target: "serial", %stream, %error, "enqueue: ProtocolWarning");
+                self.queues
+                    .protocol_warnings
+                    .push(crate::SessionEvent::ProtocolWarning {
+                        stream,
+                        pipeline: pipeline.map(|p| p.id()),
+                        message: error.to_string(),
+                        diagnostics: error.blob_diagnostics().cloned().map(Box::new),
+                    });
+            }
             ActiveSessionOutput::Ignore => {}
         }
         Ok(())
     }
 
+    /// Apply an operation that only concerns this loop; `true` when it was one.
+    fn take_local_op(&mut self, op: &UserOperation) -> bool {
+        let UserOperation::SetPowerSaving { enabled } = *op else {
+            return false;
+        };
+        info!(target: "serial", enabled, "scheduler: power saving");
+        self.scheduler.set_power_saving(enabled);
+        true
+    }
+
     fn observe_user_op(&mut self, op: &UserOperation) {
         let now_ms = self.now_ms();
         self.scheduler.note_user_activity(now_ms);
 
-        let UserOperation::KillPipeline { pipeline } = op else {
+        let (UserOperation::KillPipeline { pipeline }
+        | UserOperation::SignalPipeline { pipeline, .. }) = op
+        else {
             return;
         };
 
@@ -858,6 +1041,10 @@ fn output_type_name(o: &ActiveSessionOutput) -> &'static str {
         ActiveSessionOutput::HostCall(_) => "HostCall",
         ActiveSessionOutput::PendingReceive { .. } => "PendingReceive",
         ActiveSessionOutput::OperationSuccess => "OperationSuccess",
+        ActiveSessionOutput::OperationQueued { .. } => "OperationQueued",
+        ActiveSessionOutput::OperationDispatched { .. } => "OperationDispatched",
+        ActiveSessionOutput::Interrupted { .. } => "Interrupted",
+        ActiveSessionOutput::ProtocolWarning { .. } => "ProtocolWarning",
         ActiveSessionOutput::Ignore => "Ignore",
         ActiveSessionOutput::SendBackError(_) => "SendBackError",
     }
@@ -943,6 +1130,7 @@ mod tests {
                 headers: vec![],
                 body: None,
                 cookie: None,
+                connect_to: None,
             },
             conn_id: ConnectionId::test_new(conn_id),
         }
@@ -987,7 +1175,7 @@ mod tests {
         }
     }
 
-    // ── Promotion priority (6 tests) ────────────────────────────────────
+    // ── Promotion priority (7 tests) ────────────────────────────────────
 
     #[test]
     fn work_queue_items_promoted_before_receives() {
@@ -1035,6 +1223,24 @@ mod tests {
         assert!(promoted.is_none());
     }
 
+    #[test]
+    fn keep_alive_probe_promotes_runspace_pool_receive_once_due() {
+        let mut mock = MockBackend::new();
+        mock.receive_results.push_back(dummy_try_send(40));
+        let mut core =
+            core_idle(mock).with_probe_interval(Some(std::time::Duration::from_secs(60)));
+        core.queues.speculative_streams.push(runspace_stream());
+
+        // Not due yet: stay idle, but wake up when it is.
+        assert!(core.promote_next_request().unwrap().is_none());
+        assert_eq!(core.next_wakeup_in_ms(0), Some(60_000));
+
+        core.probe_interval_ms = Some(0);
+        let promoted = core.promote_next_request().unwrap();
+        assert_eq!(promoted.unwrap().get_connection_id().inner(), 40);
+        assert!(core.queues.speculative_streams.is_empty());
+    }
+
     #[test]
     fn speculative_pipeline_stream_promoted() {
         let mut mock = MockBackend::new();
@@ -1260,6 +1466,7 @@ mod tests {
                 commands: vec![ironposh_client_core::pipeline::PipelineCommand::new_script(
                     "test".to_string(),
                 )],
+                add_to_history: false,
             },
         });
 
@@ -1274,6 +1481,33 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn buffer_signal_pipeline_goes_to_front() {
+        let mock = MockBackend::new();
+        let mut core = core_idle(mock);
+
+        core.buffer_user_op(UserOperation::InvokeWithSpec {
+            uuid: Uuid::new_v4(),
+            spec: ironposh_client_core::pipeline::PipelineSpec {
+                commands: vec![ironposh_client_core::pipeline::PipelineCommand::new_script(
+                    "test".to_string(),
+                )],
+                add_to_history: false,
+            },
+        });
+
+        // A graceful Ctrl+C is as urgent as a kill.
+        core.buffer_user_op(UserOperation::SignalPipeline {
+            pipeline: pipeline_handle(Uuid::new_v4()),
+            code: ironposh_client_core::powershell::SignalCode::PowerShellCtrlC,
+        });
+
+        assert!(matches!(
+            core.queues.user_ops.front(),
+            Some(UserOperation::SignalPipeline { .. })
+        ));
+    }
+
     #[test]
     fn process_buffered_op_allows_kill_even_with_work_queue() {
         let mut mock = MockBackend::new();
@@ -1323,6 +1557,7 @@ mod tests {
                     commands: vec![ironposh_client_core::pipeline::PipelineCommand::new_script(
                         "prompt".to_string(),
                     )],
+                    add_to_history: false,
                 },
             });
 
@@ -1391,6 +1626,25 @@ mod tests {
         assert!(core.queues.work.is_empty());
     }
 
+    // ── Suspend (1 test) ────────────────────────────────────────────────
+
+    #[test]
+    fn suspend_reaches_the_backend() {
+        let mut mock = MockBackend::new();
+        mock.op_responses
+            .push_back(ActiveSessionOutput::SendBack(vec![dummy_try_send(9)]));
+        let mut core = core_idle(mock);
+
+        core.accept_user_op(UserOperation::Suspend)
+            .expect("Suspend must be accepted in serial mode");
+
+        assert_eq!(
+            core.queues.work.len(),
+            1,
+            "Suspend must enqueue the Disconnect"
+        );
+    }
+
     // ── Scheduler integration (2 tests) ─────────────────────────────────
 
     #[test]
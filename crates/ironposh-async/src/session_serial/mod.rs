@@ -1,4 +1,7 @@
 //! Serial session loop — single-connection mode for Devolutions Gateway.
+//! With one request in flight at a time, user input waits for a parked
+//! Receive to return; only the parallel loop dedicates a second connection
+//! to it (see `ActiveSession::dedicate_receive_connection`).
 //!
 //! Split into two layers:
 //! - [`core::SessionCore`] — pure synchronous protocol decisions (queues, promotion, routing)
@@ -12,6 +15,7 @@ use futures::channel::mpsc;
 use futures::future::Either;
 use futures::{FutureExt, SinkExt, StreamExt};
 use futures_timer::Delay;
+use ironposh_client_core::PwshCoreError;
 use ironposh_client_core::connector::active_session::{ActiveSession, UserEvent};
 use ironposh_client_core::connector::connection_pool::TrySend;
 use ironposh_client_core::host::HostCall;
@@ -41,7 +45,10 @@ pub(in crate::session_serial) use diag;
 ///
 /// **Core invariant:** At most one HTTP request is in flight at any time.
 /// All protocol decisions (promotion priority, HostCall blocking, speculative
-/// vs demanded Receives) are handled by [`SessionCore`].
+/// vs demanded Receives) are handled by [`SessionCore`]. With a
+/// `heartbeat_interval`, a session that is otherwise idle polls the server
+/// once per interval so heartbeats see it answer.
+#[expect(clippy::too_many_arguments)]
 #[instrument(skip_all)]
 pub async fn start_serial_session_loop(
     first_receive: TrySend,
@@ -51,15 +58,43 @@ pub async fn start_serial_session_loop(
     mut user_output_tx: mpsc::Sender<UserEvent>,
     host_call_tx: mpsc::UnboundedSender<HostCall>,
     mut host_resp_rx: mpsc::UnboundedReceiver<HostResponse>,
+    session_event_tx: mpsc::UnboundedSender<crate::SessionEvent>,
+    heartbeat_interval: Option<Duration>,
 ) -> anyhow::Result<()> {
-    let mut core = SessionCore::new(first_receive, active_session);
+    let mut core =
+        SessionCore::new(first_receive, active_session).with_probe_interval(heartbeat_interval);
 
     info!("Starting serial session loop (flat event loop, single-connection mode)");
     diag!("DIAG serial loop: started (flat event loop)");
 
     loop {
         // Dispatch accumulated effects from the previous iteration.
-        dispatch_effects(&mut core, &mut user_output_tx, &host_call_tx).await?;
+        dispatch_effects(
+            &mut core,
+            &mut user_output_tx,
+            &host_call_tx,
+            &session_event_tx,
+        )
+        .await?;
+
+        if let Some(reason) = core.broken_reason() {
+            warn!(target: "serial", %reason, "runspace pool broken, ending serial session loop");
+            return Err(PwshCoreError::RunspacePoolBroken {
+                reason: reason.to_owned(),
+            }
+            .into());
+        }
+
+        if core.is_closed() {
+            info!(target: "serial", "runspace pool closed, ending serial session loop");
+            return Ok(());
+        }
+
+        if let Some(shell_id) = core.suspended_shell_id() {
+            info!(target: "serial", %shell_id, "runspace pool suspended, ending serial session loop");
+            let _ = session_event_tx.unbounded_send(crate::SessionEvent::Suspended { shell_id });
+            return Ok(());
+        }
 
         // Process one buffered user op if connection is idle.
         core.process_one_buffered_op()?;
@@ -324,7 +359,17 @@ async fn dispatch_effects(
     core: &mut SessionCore,
     user_output_tx: &mut mpsc::Sender<UserEvent>,
     host_call_tx: &mpsc::UnboundedSender<HostCall>,
+    session_event_tx: &mpsc::UnboundedSender<crate::SessionEvent>,
 ) -> anyhow::Result<()> {
+    for (interruption, retrying) in core.drain_interruptions() {
+        let _ = session_event_tx.unbounded_send(crate::SessionEvent::Interrupted {
+            interruption,
+            retrying,
+        });
+    }
+    for warning in core.drain_protocol_warnings() {
+        let _ = session_event_tx.unbounded_send(warning);
+    }
     for event in core.drain_user_events() {
         diag!("DIAG dispatch: UserEvent");
         if user_output_tx.send(event).await.is_err() {
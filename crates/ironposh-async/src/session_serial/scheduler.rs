@@ -29,6 +29,8 @@ pub trait ReceiveScheduler {
     fn note_receive_timeout(&mut self, target: TargetId, now_ms: u64);
     fn note_receive_progress(&mut self, target: TargetId, now_ms: u64);
     fn note_user_activity(&mut self, now_ms: u64);
+    /// While on, idle targets are polled with longer holds (fewer round trips).
+    fn set_power_saving(&mut self, enabled: bool);
 
     fn is_allowed_target(&self, target: TargetId, now_ms: u64) -> bool;
     /// Returns the earliest time this target may be polled, or `None` if the
@@ -45,15 +47,18 @@ pub trait ReceiveScheduler {
 /// - after cancel is requested, cap the hold so we observe the finish quickly
 /// - grow the server-side hold on repeated empty polls so the connection always
 ///   has a Receive parked instead of idling on a client-side backoff sleep
+/// - in power saving, let that hold grow to five seconds instead of one
 #[derive(Debug, Default)]
 pub struct DefaultReceiveScheduler {
     targets: HashMap<TargetId, TargetState>,
     base_hold_ms: u64,
     max_hold_ms: u64,
+    max_hold_power_saving_ms: u64,
     max_hold_after_cancel_ms: u64,
     activity_cap_ms: u64,
     activity_window_ms: u64,
     last_user_activity_ms: Option<u64>,
+    power_saving: bool,
 }
 
 impl DefaultReceiveScheduler {
@@ -62,10 +67,12 @@ impl DefaultReceiveScheduler {
             targets: HashMap::new(),
             base_hold_ms: 250,
             max_hold_ms: 1_000,
+            max_hold_power_saving_ms: 5_000,
             max_hold_after_cancel_ms: 500,
             activity_cap_ms: 250,
             activity_window_ms: 3_000,
             last_user_activity_ms: None,
+            power_saving: false,
         }
     }
 
@@ -114,6 +121,10 @@ impl ReceiveScheduler for DefaultReceiveScheduler {
         self.last_user_activity_ms = Some(now_ms);
     }
 
+    fn set_power_saving(&mut self, enabled: bool) {
+        self.power_saving = enabled;
+    }
+
     fn is_allowed_target(&self, target: TargetId, _now_ms: u64) -> bool {
         self.state(target).is_none_or(|st| !st.finished)
     }
@@ -130,7 +141,12 @@ impl ReceiveScheduler for DefaultReceiveScheduler {
         let streak = st.map_or(0, |s| s.timeout_streak);
         let exp = streak.min(31);
         let pow = 1u64 << exp;
-        let mut hold = self.base_hold_ms.saturating_mul(pow).min(self.max_hold_ms);
+        let max_hold = if self.power_saving {
+            self.max_hold_power_saving_ms
+        } else {
+            self.max_hold_ms
+        };
+        let mut hold = self.base_hold_ms.saturating_mul(pow).min(max_hold);
 
         if st.is_some_and(|s| s.cancel_requested_at_ms.is_some()) {
             hold = hold.min(self.max_hold_after_cancel_ms);
@@ -205,6 +221,23 @@ mod tests {
         assert_eq!(sched.receive_hold_ms(target, 13_001), 1_000);
     }
 
+    #[test]
+    fn power_saving_lets_idle_hold_grow_to_five_seconds() {
+        let mut sched = DefaultReceiveScheduler::new();
+        let target = TargetId::RunspacePool;
+
+        sched.set_power_saving(true);
+        assert_eq!(sched.receive_hold_ms(target, 0), 250, "fresh target: base");
+        for _ in 0..5 {
+            sched.note_receive_timeout(target, 0);
+        }
+        assert_eq!(sched.receive_hold_ms(target, 0), 5_000);
+
+        // Back in focus, the next poll is capped at 1s again.
+        sched.set_power_saving(false);
+        assert_eq!(sched.receive_hold_ms(target, 0), 1_000);
+    }
+
     #[test]
     fn cancel_requested_caps_hold_at_half_second() {
         let mut sched = DefaultReceiveScheduler::new();
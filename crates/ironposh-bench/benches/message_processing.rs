@@ -0,0 +1,305 @@
+//! End-to-end message-processing benchmarks.
+//!
+//! Each group times one stage of the client's hot path in isolation, and
+//! `receive_cycle` chains them the way a ReceiveResponse is processed:
+//!
+//! - `envelope`      — build + render a Receive request; parse a ReceiveResponse
+//! - `fragment`      — PSRP fragment encode (`Fragmenter`) and reassembly (`Defragmenter`)
+//! - `clixml`        — `PsValue` serialize, deserialize and roundtrip
+//! - `receive_cycle` — XML parse → base64 decode → defragment → CLIXML, per response
+//!
+//! Run: `cargo bench -p ironposh-bench`
+//! Compare against the committed baseline: see `crates/ironposh-bench/README.md`.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::hint::black_box;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter, Fragment, Fragmenter};
+use ironposh_psrp::ps_value::{
+    ComplexObject, DeserializationContext, PsObjectWithType, PsPrimitiveValue, PsValue,
+    PsXmlDeserialize,
+};
+use ironposh_psrp::{Destination, PipelineOutput, PowerShellRemotingMessage};
+use ironposh_winrm::cores::{Attribute, DesiredStreamTag, Namespace, Tag, Text};
+use ironposh_winrm::rsp::receive::{ReceiveTag, ReceiveValue};
+use ironposh_winrm::soap::{SoapEnvelope, body::SoapBody};
+use ironposh_winrm::ws_management::{OptionSetValue, SelectorSetValue, WsAction, WsMan};
+use ironposh_xml::builder::SerializationScratch;
+use ironposh_xml::mapping::FromXml;
+use uuid::Uuid;
+
+const RPID: Uuid = Uuid::from_u128(0x6c0c_f55b_ff35_47f2_907b_e762_5272_6109);
+const COMMAND_ID: Uuid = Uuid::from_u128(0x2f1e_8a0b_3c4d_4e5f_8a9b_0c1d_2e3f_4a5b);
+
+/// WsMan's default MaxEnvelopeSize, which the client also uses as its fragment size.
+const MAX_FRAGMENT_SIZE: usize = 512_000;
+
+/// Output objects carried by one ReceiveResponse in the response-shaped fixtures.
+const OUTPUTS_PER_RESPONSE: usize = 32;
+
+/// Note properties per output object — roughly a `Get-Process` row.
+const PROPERTIES_PER_OBJECT: usize = 16;
+
+// ── Fixtures ────────────────────────────────────────────────────────────────
+
+fn process_object(index: usize) -> PsValue {
+    let mut builder = ComplexObject::standard()
+        .type_names([
+            Cow::Borrowed("System.Diagnostics.Process"),
+            Cow::Borrowed("System.ComponentModel.Component"),
+            Cow::Borrowed("System.Object"),
+        ])
+        .to_string_repr(format!("System.Diagnostics.Process (pwsh-{index})"));
+    for property in 0..PROPERTIES_PER_OBJECT {
+        builder = builder.extended(
+            format!("Property{property}"),
+            format!("value-{index}-{property}"),
+        );
+    }
+    builder.build_value()
+}
+
+fn pipeline_outputs(count: usize) -> Vec<PipelineOutput> {
+    (0..count)
+        .map(|index| PipelineOutput {
+            data: process_object(index),
+        })
+        .collect()
+}
+
+fn string_output(len: usize) -> PipelineOutput {
+    PipelineOutput {
+        data: PsValue::Primitive(PsPrimitiveValue::Str("x".repeat(len))),
+    }
+}
+
+/// A pipeline ReceiveResponse carrying `OUTPUTS_PER_RESPONSE` single-fragment streams.
+fn receive_response_xml() -> String {
+    let mut streams = String::new();
+    for (index, output) in pipeline_outputs(OUTPUTS_PER_RESPONSE).iter().enumerate() {
+        let message = PowerShellRemotingMessage::new(
+            Destination::Client,
+            output.message_type(),
+            RPID,
+            Some(COMMAND_ID),
+            &output.to_ps_object(),
+        )
+        .expect("serialize PSRP message");
+        let fragment = Fragment::new(index as u64 + 1, 0, message.pack(), true, true);
+        write!(
+            streams,
+            r#"<rsp:Stream Name="stdout" CommandId="{COMMAND_ID}">{}</rsp:Stream>"#,
+            STANDARD.encode(fragment.pack())
+        )
+        .expect("write stream XML");
+    }
+
+    format!(
+        r#"<s:Envelope xml:lang="en-US"
+    xmlns:s="http://www.w3.org/2003/05/soap-envelope"
+    xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing"
+    xmlns:rsp="http://schemas.microsoft.com/wbem/wsman/1/windows/shell">
+    <s:Header>
+        <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReceiveResponse</a:Action>
+        <a:MessageID>uuid:{COMMAND_ID}</a:MessageID>
+        <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
+    </s:Header>
+    <s:Body>
+        <rsp:ReceiveResponse>{streams}</rsp:ReceiveResponse>
+    </s:Body>
+</s:Envelope>"#
+    )
+}
+
+// ── Stages ──────────────────────────────────────────────────────────────────
+
+/// Build and render a pipeline Receive request, as `WinRunspace::fire_receive` does.
+fn render_receive_request(ws_man: &WsMan, scratch: &mut SerializationScratch) -> String {
+    let desired_stream = Tag::from_name(DesiredStreamTag)
+        .with_value(Text::from("stdout"))
+        .with_attribute(Attribute::CommandId(COMMAND_ID));
+    let receive_tag = Tag::from_name(ReceiveTag)
+        .with_value(
+            ReceiveValue::builder()
+                .desired_streams(vec![desired_stream])
+                .build(),
+        )
+        .with_declaration(Namespace::WsmanShell);
+    let option_set =
+        OptionSetValue::default().add_option("WSMAN_CMDSHELL_OPTION_KEEPALIVE", "true");
+    let selector_set = SelectorSetValue::new().add_selector("ShellId", RPID.to_string());
+
+    ws_man
+        .invoke(
+            scratch,
+            &WsAction::ShellReceive,
+            None,
+            SoapBody::builder().receive(receive_tag).build(),
+            Some(option_set),
+            Some(selector_set),
+        )
+        .expect("render Receive request")
+        .to_owned()
+}
+
+fn parse_receive_response(xml: &str) -> usize {
+    let parsed = ironposh_xml::parser::parse(xml).expect("parse ReceiveResponse");
+    let envelope = SoapEnvelope::from_xml(parsed.root_element()).expect("map ReceiveResponse");
+    envelope
+        .body
+        .as_ref()
+        .receive_response
+        .as_ref()
+        .map_or(0, |response| response.value.streams.len())
+}
+
+/// What `RunspacePool` does with one ReceiveResponse, minus its state machine:
+/// parse the envelope, decode every stream, reassemble and deserialize each message.
+fn process_receive_response(
+    xml: &str,
+    defragmenter: &mut Defragmenter,
+    decoded: &mut Vec<u8>,
+) -> usize {
+    let parsed = ironposh_xml::parser::parse(xml).expect("parse ReceiveResponse");
+    let envelope = SoapEnvelope::from_xml(parsed.root_element()).expect("map ReceiveResponse");
+    let response = envelope
+        .body
+        .as_ref()
+        .receive_response
+        .as_ref()
+        .expect("ReceiveResponse body");
+
+    let mut messages = 0;
+    for stream in &response.value.streams {
+        decoded.clear();
+        STANDARD
+            .decode_vec(stream.value.as_ref(), decoded)
+            .expect("decode stream");
+        if let DefragmentResult::Complete(complete) =
+            defragmenter.defragment(decoded).expect("defragment stream")
+        {
+            for message in complete {
+                black_box(message.parse_ps_message().expect("deserialize message"));
+                messages += 1;
+            }
+        }
+    }
+    messages
+}
+
+fn serialize_clixml(value: &PsValue) -> String {
+    value
+        .to_element_as_root()
+        .expect("build CLIXML")
+        .to_xml_string()
+        .expect("render CLIXML")
+}
+
+fn deserialize_clixml(xml: &str) -> PsValue {
+    let parsed = ironposh_xml::parser::parse(xml).expect("parse CLIXML");
+    PsValue::from_node_with_context(
+        parsed.root_element(),
+        &mut DeserializationContext::default(),
+    )
+    .expect("deserialize CLIXML")
+}
+
+// ── Benchmarks ──────────────────────────────────────────────────────────────
+
+fn envelope(c: &mut Criterion) {
+    let mut group = c.benchmark_group("envelope");
+
+    let ws_man = WsMan::builder()
+        .to("http://127.0.0.1:5985/wsman".to_owned())
+        .build();
+    let mut scratch = SerializationScratch::new();
+    group.bench_function("build_receive", |b| {
+        b.iter(|| render_receive_request(black_box(&ws_man), &mut scratch));
+    });
+
+    let response = receive_response_xml();
+    group.throughput(Throughput::Bytes(response.len() as u64));
+    group.bench_function("parse_receive_response", |b| {
+        b.iter(|| parse_receive_response(black_box(&response)));
+    });
+
+    group.finish();
+}
+
+fn fragment(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fragment");
+
+    for len in [1 << 10, 64 << 10, 1 << 20] {
+        let output = string_output(len);
+        let packet = Fragmenter::new(MAX_FRAGMENT_SIZE)
+            .fragment(&output, RPID, Some(COMMAND_ID), None)
+            .expect("fragment output")
+            .concat();
+        group.throughput(Throughput::Bytes(packet.len() as u64));
+
+        // Includes the CLIXML rendering `Fragmenter::fragment` performs up front.
+        group.bench_with_input(BenchmarkId::new("encode", len), &output, |b, output| {
+            b.iter(|| {
+                Fragmenter::new(MAX_FRAGMENT_SIZE)
+                    .fragment(black_box(output), RPID, Some(COMMAND_ID), None)
+                    .expect("fragment output")
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("decode", len), &packet, |b, packet| {
+            b.iter(|| {
+                Defragmenter::new()
+                    .defragment(black_box(packet))
+                    .expect("defragment packet")
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn clixml(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clixml");
+
+    let value = PsValue::from_array((0..OUTPUTS_PER_RESPONSE).map(process_object).collect());
+    let xml = serialize_clixml(&value);
+    group.throughput(Throughput::Bytes(xml.len() as u64));
+
+    group.bench_function("serialize", |b| {
+        b.iter(|| serialize_clixml(black_box(&value)));
+    });
+    group.bench_function("deserialize", |b| {
+        b.iter(|| deserialize_clixml(black_box(&xml)));
+    });
+    group.bench_function("roundtrip", |b| {
+        b.iter(|| deserialize_clixml(&serialize_clixml(black_box(&value))));
+    });
+
+    group.finish();
+}
+
+fn receive_cycle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receive_cycle");
+
+    let response = receive_response_xml();
+    group.throughput(Throughput::Bytes(response.len() as u64));
+
+    let mut defragmenter = Defragmenter::new();
+    let mut decoded = Vec::new();
+    group.bench_function("pipeline_output", |b| {
+        b.iter(|| {
+            let messages =
+                process_receive_response(black_box(&response), &mut defragmenter, &mut decoded);
+            assert_eq!(messages, OUTPUTS_PER_RESPONSE);
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, envelope, fragment, clixml, receive_cycle);
+criterion_main!(benches);
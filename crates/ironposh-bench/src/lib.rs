@@ -0,0 +1,4 @@
+//! Benchmark-only crate (`publish = false`) for end-to-end message processing.
+//!
+//! The criterion suite lives in `benches/message_processing.rs`; see the
+//! crate README for how to record and compare against the committed baselines.
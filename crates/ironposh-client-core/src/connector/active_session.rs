@@ -1,16 +1,65 @@
+//! The post-connect session as a sans-IO state machine.
+//!
+//! [`SessionStateMachine`] owns the runspace pool and the connection pool once the
+//! [`Connector`](super::Connector) reaches `Connected`. It performs no I/O: an
+//! embedding event loop (a GUI, a service, the bundled tokio/sync clients) feeds
+//! it [`SessionInput`]s and carries out the [`ActiveSessionOutput`]s it returns.
+//!
+//! ```text
+//! loop {
+//!     input  = next user operation | HTTP response | transport error
+//!     output = machine.step(input)?
+//!     for each output:
+//!         SendBack / SendAndThenReceive  -> send the requests (drive AuthNeeded first)
+//!         PendingReceive                 -> machine.fire_receive(streams, hold) and send it
+//!         HostCall                       -> answer with UserOperation::SubmitHostResponse
+//!         UserEvent                      -> hand pipeline events to the application
+//!         Interrupted                    -> report it; send `retry` once its delay passed
+//! }
+//! ```
+//!
+//! The machine's coarse state is the runspace pool state
+//! ([`runspace_pool_state`](SessionStateMachine::runspace_pool_state)):
+//!
+//! | State           | Operations                             | Server responses                      |
+//! |-----------------|----------------------------------------|---------------------------------------|
+//! | `Opened`        | dispatched                             | fed to PSRP                           |
+//! | `Disconnecting` | queued; Disconnect/Reconnect ignored   | only the Disconnect connection counts |
+//! | `Disconnected`  | invokes finish at once; Reconnect sent | dropped                               |
+//! | `Connecting`    | queued                                 | only the Reconnect connection counts  |
+//! | `Closing`       | queued                                 | only the Close connection counts      |
+//! | `Closed`        | invokes finish at once                 | dropped                               |
+//!
+//! Finer state is exposed through [`pipelines`](SessionStateMachine::pipelines),
+//! [`pending_host_calls`](SessionStateMachine::pending_host_calls) and
+//! [`outstanding_requests`](SessionStateMachine::outstanding_requests).
+
 use crate::{
     PwshCoreError,
     connector::{
-        connection_pool::{ConnectionId, ConnectionPool, ConnectionPoolAccept, TrySend},
-        http::HttpResponseTargeted,
+        config::{HostMode, ReceiveFlowConfig},
+        connection_pool::{ConnectionId, ConnectionPool, ConnectionPoolAccept, Lane, TrySend},
+        diagnostics::SessionDiagnostics,
+        flow_control::ReceiveFlowControl,
+        http::{HttpResponse, HttpResponseTargeted, Interruption},
     },
     host::{HostCall, HostCallScope, Submission},
     pipeline::PipelineSpec,
-    powershell::PipelineHandle,
+    powershell::{PipelineHandle, SignalCode},
     runspace_pool::{DesiredStream, RunspacePool, pool::AcceptResponsResult},
 };
 use ironposh_psrp::{ErrorRecord, PipelineOutput, PsPrimitiveValue, PsValue};
-use tracing::{error, info, instrument, warn};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+
+/// Consecutive interrupted Receives resent before the session gives up.
+const MAX_RECEIVE_RETRIES: u32 = 3;
+/// Consecutive quota faults a request is resent after before the error is
+/// surfaced.
+const MAX_QUOTA_RETRIES: u32 = 5;
+/// Upper bound on the wait before a retry, whatever `Retry-After` asks for.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq)]
@@ -24,6 +73,10 @@ pub enum UserEvent {
     PipelineOutput {
         pipeline: PipelineHandle,
         output: PipelineOutput,
+        /// Size of the PSRP message that carried `output`; the amount to
+        /// release with `UserOperation::AcknowledgeOutput` once it is consumed
+        /// (see `ReceiveFlowConfig::max_pending_output_bytes`).
+        data_len: usize,
     },
     ErrorRecord {
         error_record: ErrorRecord,
@@ -33,6 +86,13 @@ pub enum UserEvent {
         pipeline: PipelineHandle,
         record: crate::psrp_record::PsrpRecord,
     },
+    /// The pipeline exceeded `PipelineOptions::timeout` and was stopped.
+    /// Terminal, like `PipelineFinished`; raised by the frontend, never by the
+    /// session itself.
+    PipelineTimedOut {
+        pipeline: PipelineHandle,
+        timeout: std::time::Duration,
+    },
 }
 
 impl UserEvent {
@@ -47,6 +107,10 @@ impl UserEvent {
             | Self::PipelineOutput {
                 pipeline: powershell,
                 ..
+            }
+            | Self::PipelineTimedOut {
+                pipeline: powershell,
+                ..
             } => powershell.id(),
             Self::ErrorRecord { handle, .. } => handle.id(),
             Self::PipelineRecord { pipeline, .. } => pipeline.id(),
@@ -74,6 +138,34 @@ pub enum ActiveSessionOutput {
         desired_streams: Vec<DesiredStream>,
     },
     OperationSuccess,
+    /// A user operation arrived while a Disconnect/Reconnect is in flight and was
+    /// queued; it is dispatched in arrival order once the transition settles.
+    OperationQueued {
+        operation_type: &'static str,
+    },
+    /// A previously queued user operation has now been dispatched; its own
+    /// outputs are emitted alongside this event.
+    OperationDispatched {
+        operation_type: &'static str,
+    },
+    /// A request on an established connection was answered with a status
+    /// instead of a reply, or refused with a quota fault. With `retry`, the
+    /// session resends it (Receives, and requests refused for a quota) and the
+    /// loop sends the request once the delay has
+    /// passed; without, the request was dropped and the application decides
+    /// what to do (e.g. prompt for credentials on `AuthExpired`).
+    Interrupted {
+        interruption: Interruption,
+        retry: Option<(Duration, TrySend)>,
+    },
+    /// Under [`super::config::ParseMode::Lenient`]: a data-stream record on
+    /// `stream` that did not decode was skipped and the rest of the response
+    /// handled. `pipeline` is the stream's pipeline, if any.
+    ProtocolWarning {
+        stream: String,
+        pipeline: Option<PipelineHandle>,
+        error: crate::PwshCoreError,
+    },
     Ignore,
 }
 
@@ -81,10 +173,15 @@ impl ActiveSessionOutput {
     pub fn priority(&self) -> u8 {
         match self {
             Self::HostCall { .. } => 1,
-            Self::SendBack(_) | Self::SendAndThenReceive { .. } | Self::PendingReceive { .. } => 2,
+            Self::SendBack(_)
+            | Self::SendAndThenReceive { .. }
+            | Self::PendingReceive { .. }
+            | Self::Interrupted { .. } => 2,
             Self::SendBackError(_) => 3,
-            Self::UserEvent(_) => 4,
-            Self::OperationSuccess => 5,
+            Self::UserEvent(_) | Self::ProtocolWarning { .. } => 4,
+            Self::OperationSuccess
+            | Self::OperationQueued { .. }
+            | Self::OperationDispatched { .. } => 5,
             Self::Ignore => 6,
         }
     }
@@ -113,9 +210,28 @@ pub enum UserOperation {
         uuid: uuid::Uuid,
         spec: PipelineSpec,
     },
+    /// invoke a pipeline and feed it `input` as PIPELINE_INPUT messages, one
+    /// Send per object once the previous one is acknowledged, then close its
+    /// input; see [`PipelineSpec::script_from_input`]
+    InvokeWithInput {
+        uuid: uuid::Uuid,
+        spec: PipelineSpec,
+        input: Vec<PsValue>,
+    },
+    /// run a whole multi-statement script (functions, here-strings) as one
+    /// pipeline, see [`PipelineSpec::script_file`]
+    RunScriptFile {
+        uuid: uuid::Uuid,
+        script: String,
+    },
     KillPipeline {
         pipeline: PipelineHandle,
     },
+    /// send a WinRS signal (e.g. Ctrl+C for a graceful stop) to a running pipeline
+    SignalPipeline {
+        pipeline: PipelineHandle,
+        code: SignalCode,
+    },
     /// reply to a server-initiated host call
     SubmitHostResponse {
         submission: Submission,
@@ -134,17 +250,42 @@ pub enum UserOperation {
     Disconnect,
     /// reconnect a previously disconnected runspace pool shell (MS-WSMV Reconnect)
     Reconnect,
+    /// disconnect the runspace pool shell like `Disconnect`, to be attached to
+    /// later from another client ([`Connector::new_connect`]); the serial
+    /// session loop ends once the server confirms
+    ///
+    /// [`Connector::new_connect`]: crate::connector::Connector::new_connect
+    Suspend,
+    /// delete the runspace pool shell (WS-Transfer Delete), ending the session
+    Close,
+    /// release `bytes` of consumed pipeline output (see `ReceiveFlowConfig`)
+    AcknowledgeOutput {
+        pipeline: PipelineHandle,
+        bytes: usize,
+    },
+    /// purely local: a serial session loop parks longer Receives while
+    /// `enabled`, e.g. when the client window is out of focus
+    SetPowerSaving {
+        enabled: bool,
+    },
 }
 
 impl UserOperation {
-    pub fn operation_type(&self) -> &str {
+    pub fn operation_type(&self) -> &'static str {
         match self {
             Self::InvokeWithSpec { .. } => "InvokeWithSpec",
+            Self::InvokeWithInput { .. } => "InvokeWithInput",
+            Self::RunScriptFile { .. } => "RunScriptFile",
             Self::KillPipeline { .. } => "KillPipeline",
+            Self::SignalPipeline { .. } => "SignalPipeline",
             Self::SubmitHostResponse { .. } => "SubmitHostResponse",
             Self::CancelHostCall { .. } => "CancelHostCall",
             Self::Disconnect => "Disconnect",
             Self::Reconnect => "Reconnect",
+            Self::Suspend => "Suspend",
+            Self::Close => "Close",
+            Self::AcknowledgeOutput { .. } => "AcknowledgeOutput",
+            Self::SetPowerSaving { .. } => "SetPowerSaving",
         }
     }
 }
@@ -163,9 +304,70 @@ pub enum TransportErrorDisposition {
     ReconnectAborted,
 }
 
-/// Manages post-connect PSRP operations. Produces `TrySend` for the caller to send.
+/// Everything that moves a [`SessionStateMachine`] forward.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum SessionInput {
+    /// A request from the application (invoke, signal, host response, ...).
+    Operation(UserOperation),
+    /// The HTTP response to a request the machine emitted earlier.
+    ServerResponse(HttpResponseTargeted),
+    /// The request sent on this connection failed below HTTP (reset, timeout).
+    TransportError(ConnectionId),
+}
+
+/// Result of one [`SessionStateMachine::step`].
+#[derive(Debug)]
+pub enum SessionOutput {
+    /// Work for the event loop, highest priority first.
+    Outputs(Vec<ActiveSessionOutput>),
+    /// How a transport error was classified. On `Fatal` the session is over;
+    /// on `DisconnectAborted` the loop re-arms polling with
+    /// [`SessionStateMachine::fire_active_receive`]. Either abort also releases
+    /// queued operations through
+    /// [`SessionStateMachine::dispatch_queued_operations`].
+    TransportError(TransportErrorDisposition),
+}
+
+/// A host call handed to the application and not yet answered with
+/// `SubmitHostResponse` or `CancelHostCall`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingHostCall {
+    pub scope: HostCallScope,
+    pub call_id: i64,
+    pub method_name: &'static str,
+}
+
+/// What an in-flight request is for, as far as the session tracks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    /// A long-poll Receive.
+    Receive,
+    /// The Disconnect request.
+    Disconnect,
+    /// The Reconnect request.
+    Reconnect,
+    /// A Receive retired at disconnect; its completion will be ignored.
+    Retired,
+    /// A Command, Send, Signal or host response.
+    Other,
+}
+
+/// A request on the wire, awaiting its response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutstandingRequest {
+    pub conn_id: ConnectionId,
+    pub kind: RequestKind,
+}
+
+/// Former name of [`SessionStateMachine`], kept for existing callers.
+pub type ActiveSession = SessionStateMachine;
+
+/// Post-connect PSRP session: consumes [`SessionInput`]s and produces the
+/// network work and user events the event loop must act on. See the
+/// [module documentation](self) for the states and the loop contract.
 #[derive(Debug)]
-pub struct ActiveSession {
+pub struct SessionStateMachine {
     runspace_pool: RunspacePool,
     connection_pool: ConnectionPool,
     /// Connection carrying an in-flight Disconnect request, so a fault answering
@@ -174,30 +376,140 @@ pub struct ActiveSession {
     /// Connection carrying an in-flight Reconnect request, so late responses from
     /// pre-disconnect traffic are not mistaken for the ReconnectResponse.
     reconnect_conn_id: Option<ConnectionId>,
+    /// Connection carrying an in-flight Close (shell Delete) request.
+    close_conn_id: Option<ConnectionId>,
     /// Connections carrying an in-flight Receive. Only these are retired on Disconnect
     /// (the long-poll Receive is the doomed straggler); in-flight Command/Send/Signal
     /// responses must NOT be discarded.
     outstanding_receive_conns: std::collections::HashSet<ConnectionId>,
+    /// Input not yet sent to pipelines invoked with `InvokeWithInput`. An
+    /// entry lives until END_OF_PIPELINE_INPUT has been queued.
+    pipeline_inputs: HashMap<uuid::Uuid, VecDeque<PsValue>>,
+    /// Connections carrying a pipeline's input Send; the next object goes out
+    /// once it is answered.
+    input_conns: HashMap<ConnectionId, uuid::Uuid>,
+    /// Connections carrying a pipeline's Command. If the request is interrupted
+    /// the server never created the pipeline, so it is failed and finished.
+    command_conns: HashMap<ConnectionId, uuid::Uuid>,
     /// Connections retired at Disconnect time (the dying Receive). Their one straggler
     /// completion/error is ignored regardless of the current pool state — including after a
     /// reconnect returns the pool to Opened — so a late stale response cannot kill the
     /// session.
     retired_conn_ids: std::collections::HashSet<ConnectionId>,
+    /// Per-pipeline output budget and Receive concurrency cap.
+    flow: ReceiveFlowControl,
+    /// User operations received while a Disconnect/Reconnect or an earlier
+    /// operation's Send is in flight, in arrival order. Drained once the pool
+    /// leaves the transitional state and the earlier Send is answered.
+    queued_ops: std::collections::VecDeque<UserOperation>,
+    /// Connections carrying the request of a dispatched shell-bound operation
+    /// (Command, Signal, host response) that the server has not answered yet.
+    operation_conns: std::collections::HashSet<ConnectionId>,
+    /// Host calls emitted to the application and not yet answered, in arrival order.
+    pending_host_calls: Vec<PendingHostCall>,
+    diagnostics: SessionDiagnostics,
+    /// Set once the server moved the runspace pool to `Broken`; the session is
+    /// terminal from then on.
+    broken_reason: Option<String>,
+    /// Interrupted Receives resent since the last one that got a reply.
+    interrupted_receives: u32,
+    /// Requests resent after a quota fault since the last one that got through.
+    quota_retries: u32,
+    host_mode: HostMode,
 }
 
-impl ActiveSession {
-    pub(crate) fn new(runspace_pool: RunspacePool, connection_pool: ConnectionPool) -> Self {
+impl SessionStateMachine {
+    pub(crate) fn new(
+        runspace_pool: RunspacePool,
+        mut connection_pool: ConnectionPool,
+        receive_flow: ReceiveFlowConfig,
+        host_mode: HostMode,
+    ) -> Self {
         info!("ActiveSession: created new session");
+        connection_pool.surface_interruptions();
+        #[cfg(feature = "diagnostics")]
+        if let Some(timeline) = &runspace_pool.timeline {
+            timeline.record(super::timeline::Machine::ActiveSession, "Active");
+        }
         Self {
             runspace_pool,
             connection_pool,
             disconnect_conn_id: None,
             reconnect_conn_id: None,
+            close_conn_id: None,
             outstanding_receive_conns: std::collections::HashSet::new(),
+            pipeline_inputs: HashMap::new(),
+            input_conns: HashMap::new(),
+            command_conns: HashMap::new(),
             retired_conn_ids: std::collections::HashSet::new(),
+            flow: ReceiveFlowControl::new(receive_flow),
+            queued_ops: std::collections::VecDeque::new(),
+            operation_conns: std::collections::HashSet::new(),
+            pending_host_calls: Vec::new(),
+            diagnostics: SessionDiagnostics::default(),
+            broken_reason: None,
+            interrupted_receives: 0,
+            quota_retries: 0,
+            host_mode,
+        }
+    }
+
+    /// Feed one input and collect what the event loop must do next.
+    ///
+    /// Equivalent to calling [`Self::accept_client_operation`],
+    /// [`Self::accept_server_response`] or [`Self::handle_transport_error`]
+    /// directly; those remain available for loops that prefer them.
+    pub fn step(&mut self, input: SessionInput) -> Result<SessionOutput, PwshCoreError> {
+        match input {
+            SessionInput::Operation(operation) => {
+                let output = self.accept_client_operation(operation)?;
+                Ok(SessionOutput::Outputs(vec![output]))
+            }
+            SessionInput::ServerResponse(response) => {
+                let outputs = self.accept_server_response(response)?;
+                Ok(SessionOutput::Outputs(outputs))
+            }
+            SessionInput::TransportError(conn_id) => Ok(SessionOutput::TransportError(
+                self.handle_transport_error(conn_id),
+            )),
         }
     }
 
+    /// Pipelines created and not yet finished, ordered by id.
+    pub fn pipelines(&self) -> Vec<PipelineHandle> {
+        self.runspace_pool.pipeline_handles()
+    }
+
+    /// Host calls the application still has to answer, in arrival order. A
+    /// pipeline's calls are dropped once it finishes or is killed, and all of
+    /// them once the pool is broken or closed.
+    pub fn pending_host_calls(&self) -> &[PendingHostCall] {
+        &self.pending_host_calls
+    }
+
+    /// Requests sent (or being authenticated) whose response has not been
+    /// accepted yet, ordered by connection id.
+    pub fn outstanding_requests(&self) -> Vec<OutstandingRequest> {
+        self.connection_pool
+            .in_flight()
+            .into_iter()
+            .map(|conn_id| {
+                let kind = if self.retired_conn_ids.contains(&conn_id) {
+                    RequestKind::Retired
+                } else if self.disconnect_conn_id == Some(conn_id) {
+                    RequestKind::Disconnect
+                } else if self.reconnect_conn_id == Some(conn_id) {
+                    RequestKind::Reconnect
+                } else if self.outstanding_receive_conns.contains(&conn_id) {
+                    RequestKind::Receive
+                } else {
+                    RequestKind::Other
+                };
+                OutstandingRequest { conn_id, kind }
+            })
+            .collect()
+    }
+
     /// Record that a Receive was dispatched on `conn`. Used to track which connections
     /// carry the long-poll Receive so that, on Disconnect, only those are retired (a
     /// concurrent Command/Send/Signal response must not be discarded). The session loop
@@ -206,6 +518,32 @@ impl ActiveSession {
         self.outstanding_receive_conns.insert(conn);
     }
 
+    /// Keep the Receive long-poll on `receive_conn` and send every other
+    /// request over a second connection, so Commands, Signals and host
+    /// responses don't queue behind a parked Receive. Only for HTTP clients
+    /// that give each `ConnectionId` its own socket. Sequential transports
+    /// (the serial loop over the Gateway WebSocket) carry one request at a
+    /// time and do not use it: there, input still waits for the Receive.
+    pub fn dedicate_receive_connection(&mut self, receive_conn: ConnectionId) {
+        self.connection_pool
+            .dedicate_receive_lane(Some(receive_conn));
+    }
+
+    /// How long to hold back a request for `conn_id` before sending it, per
+    /// `WinRmConfig::rate_limit`; `now` is measured from any fixed origin.
+    pub fn pace(
+        &mut self,
+        conn_id: ConnectionId,
+        now: std::time::Duration,
+    ) -> Option<std::time::Duration> {
+        self.connection_pool.pace(conn_id, now)
+    }
+
+    /// How much the rate limit has held requests back so far.
+    pub fn throttle_stats(&self) -> super::rate_limit::ThrottleStats {
+        self.connection_pool.throttle_stats()
+    }
+
     /// Mark connections as retired: their next completion or transport error is a doomed
     /// straggler and must be ignored in any state. Retained as a direct entry point for
     /// tests; normal operation retires Receive connections via the Disconnect handler.
@@ -213,12 +551,38 @@ impl ActiveSession {
         self.retired_conn_ids.extend(conns);
     }
 
+    /// Sample the server clock from a response's `Date` header.
+    /// `local_unix_secs` is the local wall-clock time the response arrived;
+    /// the session loop supplies it since this crate does no I/O.
+    pub fn observe_server_clock(&mut self, response: &HttpResponse, local_unix_secs: i64) {
+        self.diagnostics
+            .observe_server_date(&response.headers, local_unix_secs);
+    }
+
+    /// Observations collected so far (e.g. server clock skew).
+    pub fn diagnostics(&self) -> SessionDiagnostics {
+        self.diagnostics
+    }
+
+    /// The timeline this session records into, if the connector had one.
+    #[cfg(feature = "diagnostics")]
+    pub fn timeline(&self) -> Option<&super::StateTimeline> {
+        self.runspace_pool.timeline.as_ref()
+    }
+
     /// Current runspace pool state (used by session loops to observe
     /// disconnect/reconnect transitions).
     pub fn runspace_pool_state(&self) -> crate::runspace_pool::RunspacePoolState {
         self.runspace_pool.state
     }
 
+    /// Why the server broke the runspace pool, once it has. Every pipeline was
+    /// already failed and finished; session loops should end with
+    /// [`PwshCoreError::RunspacePoolBroken`] after forwarding the last outputs.
+    pub fn broken_reason(&self) -> Option<&str> {
+        self.broken_reason.as_deref()
+    }
+
     /// Server-assigned shell id of the runspace pool, if the shell was created.
     pub fn shell_id(&self) -> Option<String> {
         self.runspace_pool.shell_id().map(ToOwned::to_owned)
@@ -243,7 +607,7 @@ impl ActiveSession {
         let recv_xml = self
             .runspace_pool
             .fire_receive(desired_streams, hold_secs)?;
-        let ts_send = self.connection_pool.send(&recv_xml)?;
+        let ts_send = self.connection_pool.send_on(Lane::Receive, &recv_xml)?;
         self.outstanding_receive_conns
             .insert(ts_send.get_connection_id());
         Ok(ts_send)
@@ -265,70 +629,137 @@ impl ActiveSession {
     }
 
     /// Client-initiated operation → produce network work (`TrySend`) or a user-level event.
+    ///
+    /// While a Disconnect/Reconnect is in flight, or an earlier operation's
+    /// request has not been answered yet, operations that would target the shell
+    /// are queued (`OperationQueued`) instead of being sent against a shell in
+    /// transition or overtaking the earlier one; see
+    /// [`Self::dispatch_queued_operations`].
     #[instrument(skip_all, fields(operation_type = operation.operation_type()))]
     pub fn accept_client_operation(
         &mut self,
         operation: UserOperation,
     ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
         info!("ActiveSession: processing client operation");
-        match operation {
-            UserOperation::InvokeWithSpec { uuid, spec } => {
-                // A pipeline can only run against an Opened pool. While the pool is
-                // disconnected or in a disconnect/reconnect transition, invoking would
-                // enqueue a command against an unusable shell whose response the routing
-                // then drops. Reject it without sending anything, but emit a terminal
-                // PipelineFinished for this id so the caller's result stream closes
-                // instead of hanging forever (the consumer registered it on send).
-                if self.runspace_pool.state != crate::runspace_pool::RunspacePoolState::Opened {
-                    warn!(
-                        pipeline_uuid = %uuid,
-                        state = ?self.runspace_pool.state,
-                        "rejecting pipeline invocation while the runspace pool is not Opened"
-                    );
-                    return Ok(ActiveSessionOutput::UserEvent(
-                        UserEvent::PipelineFinished {
-                            pipeline: PipelineHandle::new(uuid),
-                        },
-                    ));
-                }
-                info!(pipeline_uuid = %uuid, "invoking pipeline with spec");
+        if let Some(reason) = &self.broken_reason {
+            return Err(PwshCoreError::RunspacePoolBroken {
+                reason: reason.clone(),
+            });
+        }
+        if self.must_queue(&operation) {
+            let operation_type = operation.operation_type();
+            info!(
+                state = ?self.runspace_pool.state,
+                queue_len = self.queued_ops.len(),
+                "queueing operation until the pool transition settles"
+            );
+            self.queued_ops.push_back(operation);
+            return Ok(ActiveSessionOutput::OperationQueued { operation_type });
+        }
+        self.dispatch_tracked(operation)
+    }
 
-                // Single operation: create, populate, and invoke pipeline
-                let invoke_xml = self.runspace_pool.invoke_spec(uuid, spec)?;
-                info!(xml_length = invoke_xml.len(), "built invoke XML request");
-                info!(unencrypted_invoke_xml = %invoke_xml, "outgoing unencrypted invoke SOAP");
+    /// Number of user operations waiting for a Disconnect/Reconnect to settle
+    /// or for an earlier operation's request to be answered.
+    pub fn queued_operation_count(&self) -> usize {
+        self.queued_ops.len()
+    }
 
-                // Send the invoke request
-                let send_invoke = self.connection_pool.send(&invoke_xml)?;
-                info!(invoke_request = ?send_invoke, "queued invoke request");
+    /// Dispatch queued user operations in arrival order, once the pool is no longer
+    /// Disconnecting/Connecting. Dispatching stops again at the first operation that
+    /// puts a request in flight; the rest follow once it is answered. Each dispatched
+    /// operation contributes an `OperationDispatched` event followed by its own
+    /// output. Called internally after every server response; session loops call it
+    /// after a transport error aborted the in-flight Disconnect/Reconnect.
+    pub fn dispatch_queued_operations(
+        &mut self,
+    ) -> Result<Vec<ActiveSessionOutput>, crate::PwshCoreError> {
+        let mut outs = Vec::new();
+        while !self.in_pool_transition() && self.operation_conns.is_empty() {
+            let Some(operation) = self.queued_ops.pop_front() else {
+                break;
+            };
+            let operation_type = operation.operation_type();
+            info!(operation_type, "dispatching queued operation");
+            outs.push(ActiveSessionOutput::OperationDispatched { operation_type });
+            outs.push(self.dispatch_tracked(operation)?);
+        }
+        Ok(outs)
+    }
 
-                Ok(ActiveSessionOutput::SendBack(vec![send_invoke]))
+    /// Dispatch `operation`, remembering the connection of a shell-bound
+    /// operation's request so later operations queue behind it.
+    fn dispatch_tracked(
+        &mut self,
+        operation: UserOperation,
+    ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
+        let shell_bound = is_shell_bound(&operation);
+        let out = self.dispatch_operation(operation)?;
+        if shell_bound {
+            match &out {
+                ActiveSessionOutput::SendBack(sends) => self
+                    .operation_conns
+                    .extend(sends.iter().map(TrySend::get_connection_id)),
+                ActiveSessionOutput::SendAndThenReceive { send_request, .. } => {
+                    self.operation_conns
+                        .insert(send_request.get_connection_id());
+                }
+                _ => {}
             }
+        }
+        Ok(out)
+    }
 
-            UserOperation::KillPipeline { pipeline } => {
-                info!(pipeline_id = %pipeline.id(), "killing pipeline");
+    fn in_pool_transition(&self) -> bool {
+        use crate::runspace_pool::RunspacePoolState;
+        matches!(
+            self.runspace_pool.state,
+            RunspacePoolState::Disconnecting
+                | RunspacePoolState::Connecting
+                | RunspacePoolState::Closing
+        )
+    }
 
-                // 1) Build the Signal request
-                let kill_xml = self.runspace_pool.kill_pipeline(&pipeline);
-                let kill_xml = match kill_xml {
-                    Ok(kill_xml) => kill_xml,
-                    Err(e) => {
-                        error!(error = ?e, "failed to build kill XML");
-                        return Ok(ActiveSessionOutput::Ignore);
-                    }
-                };
+    fn must_queue(&self, operation: &UserOperation) -> bool {
+        is_shell_bound(operation)
+            && (self.in_pool_transition()
+                || !self.queued_ops.is_empty()
+                || !self.operation_conns.is_empty())
+    }
 
-                info!(xml_length = kill_xml.len(), "built kill XML request");
+    fn dispatch_operation(
+        &mut self,
+        operation: UserOperation,
+    ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
+        match operation {
+            UserOperation::InvokeWithSpec { uuid, spec } => self.invoke(uuid, spec, None),
 
-                // 2) Send signal
-                let ts_send = self.connection_pool.send(&kill_xml)?;
-                info!(signal_request = ?ts_send, "queued signal request");
+            UserOperation::InvokeWithInput { uuid, spec, input } => {
+                self.invoke(uuid, spec, Some(input))
+            }
 
-                Ok(ActiveSessionOutput::SendBack(vec![ts_send]))
+            UserOperation::RunScriptFile { uuid, script } => {
+                self.invoke(uuid, PipelineSpec::script_file(&script), None)
+            }
+
+            UserOperation::KillPipeline { pipeline } => {
+                info!(pipeline_id = %pipeline.id(), "killing pipeline");
+                // Also how frontends stop a pipeline that timed out: whoever
+                // would have answered its host calls has stopped listening.
+                self.forget_host_calls(Some(pipeline.id()));
+                self.signal_pipeline(&pipeline, SignalCode::Terminate)
+            }
+
+            UserOperation::SignalPipeline { pipeline, code } => {
+                info!(pipeline_id = %pipeline.id(), signal = ?code, "signalling pipeline");
+                self.signal_pipeline(&pipeline, code)
             }
             UserOperation::SubmitHostResponse {
-                submission, scope, ..
+                submission,
+                scope,
+                call_id,
             } => {
+                self.settle_host_call(&scope, call_id);
                 match submission {
                     Submission::Send(response) => match scope {
                         HostCallScope::Pipeline { command_id } => self.send_pipeline_host_response(
@@ -358,6 +789,7 @@ impl ActiveSession {
                 method,
                 reason: _,
             } => {
+                self.settle_host_call(&scope, call_id);
                 // send an error response back
                 let err = Some(PsValue::Primitive(PsPrimitiveValue::Str(format!(
                     "Host call {call_id} was cancelled"
@@ -372,7 +804,7 @@ impl ActiveSession {
                 }
             }
 
-            UserOperation::Disconnect => {
+            UserOperation::Disconnect | UserOperation::Suspend => {
                 info!("disconnecting runspace pool");
                 let disconnect_xml = match self.runspace_pool.fire_disconnect() {
                     Ok(xml) => xml,
@@ -409,7 +841,130 @@ impl ActiveSession {
                 self.reconnect_conn_id = Some(ts_send.get_connection_id());
                 Ok(ActiveSessionOutput::SendBack(vec![ts_send]))
             }
+
+            UserOperation::Close => {
+                info!("closing runspace pool");
+                let close_xml = match self.runspace_pool.fire_close() {
+                    Ok(xml) => xml,
+                    Err(e @ PwshCoreError::InvalidState(_)) => {
+                        // Mistimed operation (e.g. already closing) — non-fatal.
+                        warn!(error = %e, "ignoring mistimed Close operation");
+                        return Ok(ActiveSessionOutput::Ignore);
+                    }
+                    Err(e) => return Err(e),
+                };
+                let ts_send = self.connection_pool.send(&close_xml)?;
+                self.close_conn_id = Some(ts_send.get_connection_id());
+                // The long-poll Receive dies with the shell; ignore its straggler.
+                let doomed: Vec<ConnectionId> = self.outstanding_receive_conns.drain().collect();
+                self.retire_connections(doomed);
+                Ok(ActiveSessionOutput::SendBack(vec![ts_send]))
+            }
+
+            UserOperation::AcknowledgeOutput { pipeline, bytes } => {
+                if !self.flow.acknowledge(pipeline.id(), bytes) {
+                    return Ok(ActiveSessionOutput::OperationSuccess);
+                }
+                info!(pipeline_id = %pipeline.id(), "pipeline output drained, resuming receive");
+                let desired_streams = self
+                    .flow
+                    .take_ready_for(pipeline.id(), self.outstanding_receive_conns.len());
+                if desired_streams.is_empty() {
+                    return Ok(ActiveSessionOutput::OperationSuccess);
+                }
+                Ok(ActiveSessionOutput::PendingReceive { desired_streams })
+            }
+
+            // The session loop owns the poll timing; nothing changes here.
+            UserOperation::SetPowerSaving { .. } => Ok(ActiveSessionOutput::Ignore),
+        }
+    }
+
+    /// Create and invoke a pipeline; with `input`, it is created expecting
+    /// pipeline input, which [`Self::next_pipeline_input`] sends once the
+    /// CommandResponse arrives.
+    fn invoke(
+        &mut self,
+        uuid: uuid::Uuid,
+        spec: PipelineSpec,
+        input: Option<Vec<PsValue>>,
+    ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
+        // A pipeline can only run against an Opened pool. While the pool is
+        // disconnected or in a disconnect/reconnect transition, invoking would
+        // enqueue a command against an unusable shell whose response the routing
+        // then drops. Reject it without sending anything, but emit a terminal
+        // PipelineFinished for this id so the caller's result stream closes
+        // instead of hanging forever (the consumer registered it on send).
+        if self.runspace_pool.state != crate::runspace_pool::RunspacePoolState::Opened {
+            warn!(
+                pipeline_uuid = %uuid,
+                state = ?self.runspace_pool.state,
+                "rejecting pipeline invocation while the runspace pool is not Opened"
+            );
+            return Ok(ActiveSessionOutput::UserEvent(
+                UserEvent::PipelineFinished {
+                    pipeline: PipelineHandle::new(uuid),
+                },
+            ));
+        }
+        info!(pipeline_uuid = %uuid, "invoking pipeline with spec");
+
+        // Single operation: create, populate, and invoke pipeline
+        let invoke_xml = self
+            .runspace_pool
+            .invoke_spec(uuid, spec, input.is_some())?;
+        info!(xml_length = invoke_xml.len(), "built invoke XML request");
+        info!(unencrypted_invoke_xml = %invoke_xml, "outgoing unencrypted invoke SOAP");
+
+        // Send the invoke request
+        let send_invoke = self.connection_pool.send(&invoke_xml)?;
+        info!(invoke_request = ?send_invoke, "queued invoke request");
+        self.command_conns
+            .insert(send_invoke.get_connection_id(), uuid);
+
+        // The input follows once the server has created the pipeline.
+        if let Some(input) = input {
+            self.pipeline_inputs.insert(uuid, input.into());
         }
+
+        Ok(ActiveSessionOutput::SendBack(vec![send_invoke]))
+    }
+
+    /// Send a pipeline's next input object, or END_OF_PIPELINE_INPUT once
+    /// none is left. The connection is tracked so the object after it waits
+    /// for this one to be acknowledged, keeping the input in order.
+    fn next_pipeline_input(
+        &mut self,
+        pipeline: uuid::Uuid,
+    ) -> Result<Option<ActiveSessionOutput>, crate::PwshCoreError> {
+        let Some(queue) = self.pipeline_inputs.get_mut(&pipeline) else {
+            return Ok(None);
+        };
+        let handle = PipelineHandle::new(pipeline);
+        let xml = match queue.pop_front() {
+            Some(value) => self.runspace_pool.send_pipeline_input(&handle, value)?,
+            None => {
+                self.pipeline_inputs.remove(&pipeline);
+                self.runspace_pool.end_pipeline_input(&handle)?
+            }
+        };
+        let send = self.connection_pool.send(&xml)?;
+        if self.pipeline_inputs.contains_key(&pipeline) {
+            self.input_conns.insert(send.get_connection_id(), pipeline);
+        }
+        debug!(pipeline_id = %pipeline, "queued pipeline input");
+        Ok(Some(ActiveSessionOutput::SendBack(vec![send])))
+    }
+
+    /// An input Send was lost: the pipeline would wait for the rest of its
+    /// input forever, so stop it instead.
+    fn abandon_pipeline_input(
+        &mut self,
+        pipeline: uuid::Uuid,
+    ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
+        warn!(pipeline_id = %pipeline, "pipeline input interrupted; stopping the pipeline");
+        self.pipeline_inputs.remove(&pipeline);
+        self.signal_pipeline(&PipelineHandle::new(pipeline), SignalCode::Terminate)
     }
 
     /// Server response → plaintext XML via pool → PSRP accept → outputs (events / more sends)
@@ -425,10 +980,28 @@ impl ActiveSession {
     ) -> Result<Vec<ActiveSessionOutput>, crate::PwshCoreError> {
         info!("ActiveSession: processing server response");
 
+        let mut outs = self.accept_server_response_inner(response)?;
+        // The response may have settled a Disconnect/Reconnect: release queued operations.
+        if !self.queued_ops.is_empty() {
+            outs.extend(self.dispatch_queued_operations()?);
+            outs.sort();
+        }
+        Ok(outs)
+    }
+
+    fn accept_server_response_inner(
+        &mut self,
+        response: HttpResponseTargeted,
+    ) -> Result<Vec<ActiveSessionOutput>, crate::PwshCoreError> {
         let conn_id = response.connection_id();
         // This connection's in-flight request has completed; note whether it was a Receive
         // so a reauth retry can keep the Receive tracking on its new connection.
         let was_receive = self.outstanding_receive_conns.remove(&conn_id);
+        // Likewise whether it carried pipeline input, whose next object waits on it,
+        // or a user operation that later operations are queued behind.
+        let input_for = self.input_conns.remove(&conn_id);
+        let command_for = self.command_conns.remove(&conn_id);
+        let was_operation = self.operation_conns.remove(&conn_id);
 
         // 0) Drop the one doomed straggler from a connection retired at disconnect time
         //    (e.g. the long-poll Receive that was in flight). This must run in ALL states,
@@ -447,7 +1020,26 @@ impl ActiveSession {
 
         // 1) Decrypt & state-transition inside the pool, get plaintext SOAP
         let xml_body = match self.connection_pool.accept(response)? {
-            ConnectionPoolAccept::Body(xml_body) => xml_body,
+            ConnectionPoolAccept::Body(xml_body) => {
+                if was_receive {
+                    self.interrupted_receives = 0;
+                }
+                xml_body
+            }
+            ConnectionPoolAccept::Interrupted {
+                interruption,
+                queued_xml,
+            } => {
+                let mut outs =
+                    self.accept_interruption(conn_id, was_receive, interruption, &queued_xml)?;
+                if let Some(pipeline) = input_for {
+                    outs.push(self.abandon_pipeline_input(pipeline)?);
+                }
+                if let Some(pipeline) = command_for {
+                    outs.extend(self.fail_unstarted_pipeline(pipeline, interruption));
+                }
+                return Ok(outs);
+            }
             ConnectionPoolAccept::SendBack(reqs) => {
                 use crate::runspace_pool::RunspacePoolState;
                 // A reauth retry (e.g. 401) moves the operation to a fresh connection.
@@ -468,9 +1060,16 @@ impl ActiveSession {
                             self.reconnect_conn_id = Some(retry.get_connection_id());
                         }
                     }
+                    RunspacePoolState::Closing if self.close_conn_id == Some(conn_id) => {
+                        if let Some(retry) = reqs.first() {
+                            self.close_conn_id = Some(retry.get_connection_id());
+                        }
+                    }
                     RunspacePoolState::Disconnecting
                     | RunspacePoolState::Disconnected
-                    | RunspacePoolState::Connecting => {
+                    | RunspacePoolState::Connecting
+                    | RunspacePoolState::Closing
+                    | RunspacePoolState::Closed => {
                         warn!(
                             conn_id = conn_id.inner(),
                             "dropping reauth retry for non-tracked traffic during disconnect/reconnect"
@@ -493,6 +1092,16 @@ impl ActiveSession {
                             .insert(retry.get_connection_id());
                     }
                 }
+                if let (Some(pipeline), Some(retry)) = (input_for, reqs.first()) {
+                    self.input_conns.insert(retry.get_connection_id(), pipeline);
+                }
+                if let (Some(pipeline), Some(retry)) = (command_for, reqs.first()) {
+                    self.command_conns
+                        .insert(retry.get_connection_id(), pipeline);
+                }
+                if let (true, Some(retry)) = (was_operation, reqs.first()) {
+                    self.operation_conns.insert(retry.get_connection_id());
+                }
                 return Ok(vec![ActiveSessionOutput::SendBack(reqs)]);
             }
         };
@@ -529,23 +1138,71 @@ impl ActiveSession {
             crate::runspace_pool::RunspacePoolState::Connecting => {
                 return self.accept_response_while_connecting(&xml_body, conn_id);
             }
+            crate::runspace_pool::RunspacePoolState::Closing => {
+                return self.accept_response_while_closing(&xml_body, conn_id);
+            }
+            crate::runspace_pool::RunspacePoolState::Closed => {
+                warn!(
+                    body_length = xml_body.len(),
+                    "dropping server traffic after the runspace pool was closed"
+                );
+                return Ok(vec![ActiveSessionOutput::Ignore]);
+            }
             _ => {}
         }
 
         // 3) Feed PSRP
-        let results = self.runspace_pool.accept_response(&xml_body).map_err(|e| {
-            error!("RunspacePool.accept_response failed: {:#}", e);
-            e
-        })?;
+        let results = match self.runspace_pool.accept_response(&xml_body) {
+            Ok(results) => {
+                if !was_receive {
+                    self.quota_retries = 0;
+                }
+                results
+            }
+            Err(e @ PwshCoreError::QuotaExceeded { .. }) if !was_receive => {
+                let outs = self.back_off_quota(e)?;
+                for out in &outs {
+                    if let ActiveSessionOutput::Interrupted {
+                        retry: Some((_, retry)),
+                        ..
+                    } = out
+                    {
+                        if let Some(pipeline) = input_for {
+                            self.input_conns.insert(retry.get_connection_id(), pipeline);
+                        }
+                        if let Some(pipeline) = command_for {
+                            self.command_conns
+                                .insert(retry.get_connection_id(), pipeline);
+                        }
+                        if was_operation {
+                            self.operation_conns.insert(retry.get_connection_id());
+                        }
+                    }
+                }
+                return Ok(outs);
+            }
+            Err(e) => {
+                error!("RunspacePool.accept_response failed: {:#}", e);
+                return Err(e);
+            }
+        };
 
         info!(result_count = results.len(), "PSRP processed response");
 
         // 4) Translate PSRP results to outputs
         let mut outs = Vec::new();
+        if let Some(pipeline) = input_for {
+            outs.extend(self.next_pipeline_input(pipeline)?);
+        }
         for (idx, res_accepted) in results.into_iter().enumerate() {
             info!(index = idx, "processing PSRP result");
             match res_accepted {
                 AcceptResponsResult::ReceiveResponse { desired_streams } => {
+                    let in_flight = self.outstanding_receive_conns.len() + queued_receives(&outs);
+                    let desired_streams = self.flow.admit(desired_streams, in_flight);
+                    if desired_streams.is_empty() {
+                        continue;
+                    }
                     info!(streams = ?desired_streams, "deferring receive to session loop");
                     outs.push(ActiveSessionOutput::PendingReceive { desired_streams });
                 }
@@ -558,32 +1215,49 @@ impl ActiveSession {
                         "queued send-then-receive (key exchange / control)"
                     );
                     let ts_send = self.connection_pool.send(&send_xml)?;
-
-                    outs.push(ActiveSessionOutput::SendAndThenReceive {
-                        send_request: ts_send,
-                        then_receive_streams: desired_streams,
-                    });
+                    let queued = queued_receives(&outs);
+                    outs.push(self.send_then_admitted_receive(ts_send, desired_streams, queued));
                 }
                 AcceptResponsResult::PipelineCreated(pipeline) => {
+                    outs.extend(self.next_pipeline_input(pipeline.id())?);
                     outs.push(ActiveSessionOutput::UserEvent(UserEvent::PipelineCreated {
                         pipeline,
                     }));
                 }
                 AcceptResponsResult::PipelineFinished(pipeline) => {
                     info!(pipeline_id= %pipeline.id(),"pipeline finished");
+                    self.flow.forget(pipeline.id());
+                    self.pipeline_inputs.remove(&pipeline.id());
+                    self.forget_host_calls(Some(pipeline.id()));
                     outs.push(ActiveSessionOutput::UserEvent(
                         UserEvent::PipelineFinished { pipeline },
                     ));
                 }
+                AcceptResponsResult::HostCall(host_call)
+                    if self.host_mode == HostMode::Headless =>
+                {
+                    outs.push(self.reject_host_call(&host_call)?);
+                }
                 AcceptResponsResult::HostCall(host_call) => {
                     info!(call_id=host_call.call_id(),method= %host_call.method_name(),"received host call");
+                    self.pending_host_calls.push(PendingHostCall {
+                        scope: host_call.scope(),
+                        call_id: host_call.call_id(),
+                        method_name: host_call.method_name(),
+                    });
                     outs.push(ActiveSessionOutput::HostCall(host_call));
                 }
-                AcceptResponsResult::PipelineOutput { output, handle } => {
+                AcceptResponsResult::PipelineOutput {
+                    output,
+                    handle,
+                    data_len,
+                } => {
                     info!(pipeline_id= %handle.id(),output_type= ?output,"pipeline output received");
+                    self.flow.note_output(handle.id(), data_len);
                     outs.push(ActiveSessionOutput::UserEvent(UserEvent::PipelineOutput {
                         pipeline: handle,
                         output,
+                        data_len,
                     }));
                 }
                 AcceptResponsResult::ErrorRecord {
@@ -602,6 +1276,63 @@ impl ActiveSession {
                         record,
                     }));
                 }
+                AcceptResponsResult::StreamEnded { handle, stream } => {
+                    // Completion is reported by CommandState/Done (PipelineFinished).
+                    debug!(pipeline_id = %handle.id(), %stream, "pipeline stream ended");
+                }
+                AcceptResponsResult::PoolBroken { error_record } => {
+                    error!(reason = %error_record.message, "server broke the runspace pool");
+                    // No pipeline will get its CommandState/Done: fail each one with
+                    // the pool's error record and finish it.
+                    for handle in self.runspace_pool.pipeline_handles() {
+                        self.flow.forget(handle.id());
+                        outs.push(ActiveSessionOutput::UserEvent(UserEvent::ErrorRecord {
+                            error_record: error_record.clone(),
+                            handle: PipelineHandle::new(handle.id()),
+                        }));
+                        outs.push(ActiveSessionOutput::UserEvent(
+                            UserEvent::PipelineFinished { pipeline: handle },
+                        ));
+                    }
+                    #[cfg(feature = "diagnostics")]
+                    if let Some(timeline) = &self.runspace_pool.timeline {
+                        timeline.record(super::timeline::Machine::ActiveSession, "Broken");
+                    }
+                    self.forget_host_calls(None);
+                    self.broken_reason = Some(error_record.message);
+                }
+                AcceptResponsResult::ProtocolWarning {
+                    stream,
+                    handle,
+                    error,
+                } => {
+                    warn!(%stream, pipeline_id = ?handle.as_ref().map(PipelineHandle::id), %error, "skipped malformed PSRP data");
+                    outs.push(ActiveSessionOutput::ProtocolWarning {
+                        stream,
+                        pipeline: handle,
+                        error,
+                    });
+                }
+            }
+        }
+
+        // Nothing more goes to a broken pool; only the final user events (and
+        // what lenient parsing skipped on the way) are left.
+        if self.broken_reason.is_some() {
+            outs.retain(|out| {
+                matches!(
+                    out,
+                    ActiveSessionOutput::UserEvent(_) | ActiveSessionOutput::ProtocolWarning { .. }
+                )
+            });
+            return Ok(outs);
+        }
+
+        // A completed Receive frees a slot: re-issue streams held back by flow control.
+        if was_receive {
+            let in_flight = self.outstanding_receive_conns.len() + queued_receives(&outs);
+            for desired_streams in self.flow.take_ready(in_flight) {
+                outs.push(ActiveSessionOutput::PendingReceive { desired_streams });
             }
         }
 
@@ -622,6 +1353,8 @@ impl ActiveSession {
 
         // The failed request completed; if it was a Receive, drop it from the tracked set.
         self.outstanding_receive_conns.remove(&conn_id);
+        self.operation_conns.remove(&conn_id);
+        self.command_conns.remove(&conn_id);
 
         // A doomed straggler from a connection retired at disconnect time (e.g. the dying
         // long-poll Receive) is tolerated in any state, including after a reconnect has
@@ -662,6 +1395,14 @@ impl ActiveSession {
                 );
                 TransportErrorDisposition::ReconnectAborted
             }
+            state @ RunspacePoolState::Closing if self.close_conn_id != Some(conn_id) => {
+                warn!(
+                    conn_id = conn_id.inner(),
+                    ?state,
+                    "tolerating transport error on dying connection during close"
+                );
+                TransportErrorDisposition::Tolerated
+            }
             state @ RunspacePoolState::Connecting => {
                 // A non-reconnect connection failing while reconnecting is the dying
                 // pre-disconnect Receive (the response path ignores its traffic too);
@@ -691,6 +1432,149 @@ impl ActiveSession {
         disposition
     }
 
+    /// Handle a request answered with an [`Interruption`] instead of a reply.
+    ///
+    /// An interrupted Receive is resent after a delay (`Retry-After` or an
+    /// exponential backoff) unless credentials are at fault or it keeps
+    /// failing; without its Receive the session would hear nothing more, so
+    /// that ends it. Other requests are dropped and reported: the server never
+    /// ran them, and whether running them later is still wanted is up to the
+    /// application. A pipeline whose Command was dropped is failed by the
+    /// caller.
+    fn accept_interruption(
+        &mut self,
+        conn_id: ConnectionId,
+        was_receive: bool,
+        interruption: Interruption,
+        queued_xml: &str,
+    ) -> Result<Vec<ActiveSessionOutput>, PwshCoreError> {
+        use crate::runspace_pool::RunspacePoolState;
+
+        let transition_phase = if self.disconnect_conn_id == Some(conn_id) {
+            Some(crate::Phase::Disconnect)
+        } else if self.reconnect_conn_id == Some(conn_id) {
+            Some(crate::Phase::Reconnect)
+        } else if self.close_conn_id == Some(conn_id) {
+            Some(crate::Phase::Close)
+        } else {
+            None
+        };
+        if let Some(phase) = transition_phase {
+            return Err(interruption.into_error(phase));
+        }
+        if self.runspace_pool.state != RunspacePoolState::Opened {
+            warn!(
+                conn_id = conn_id.inner(),
+                ?interruption,
+                "ignoring interrupted request on a connection outside the pool transition"
+            );
+            return Ok(vec![ActiveSessionOutput::Ignore]);
+        }
+
+        if !was_receive {
+            warn!(
+                conn_id = conn_id.inner(),
+                ?interruption,
+                "request interrupted; reporting it to the application"
+            );
+            return Ok(vec![ActiveSessionOutput::Interrupted {
+                interruption,
+                retry: None,
+            }]);
+        }
+
+        if !interruption.is_transient() || self.interrupted_receives >= MAX_RECEIVE_RETRIES {
+            return Err(interruption.into_error(crate::Phase::Receive));
+        }
+        self.interrupted_receives += 1;
+        let delay = match interruption {
+            Interruption::ServiceUnavailable {
+                retry_after: Some(after),
+            } => after,
+            _ => Duration::from_secs(1 << (self.interrupted_receives - 1)),
+        }
+        .min(MAX_RETRY_DELAY);
+        let retry = self.connection_pool.send_on(Lane::Receive, queued_xml)?;
+        self.outstanding_receive_conns
+            .insert(retry.get_connection_id());
+        warn!(
+            conn_id = conn_id.inner(),
+            retry_conn_id = retry.get_connection_id().inner(),
+            ?interruption,
+            attempt = self.interrupted_receives,
+            delay_ms = delay.as_millis() as u64,
+            "Receive interrupted; resending it"
+        );
+        Ok(vec![ActiveSessionOutput::Interrupted {
+            interruption,
+            retry: Some((delay, retry)),
+        }])
+    }
+
+    /// The Command creating `pipeline` was interrupted, so the server never
+    /// created it and will send nothing for it: fail the pipeline with the
+    /// interruption and finish it, as a broken pool does.
+    fn fail_unstarted_pipeline(
+        &mut self,
+        pipeline: uuid::Uuid,
+        interruption: Interruption,
+    ) -> Vec<ActiveSessionOutput> {
+        if !self.runspace_pool.discard_pipeline(&pipeline) {
+            return Vec::new();
+        }
+        warn!(pipeline_id = %pipeline, ?interruption, "Command interrupted; failing its pipeline");
+        self.flow.forget(pipeline);
+        self.pipeline_inputs.remove(&pipeline);
+        self.forget_host_calls(Some(pipeline));
+        let error_record = ErrorRecord::builder()
+            .message(interruption.into_error(crate::Phase::Command).to_string())
+            .fully_qualified_error_id(Some("CommandInterrupted".to_owned()))
+            .build();
+        vec![
+            ActiveSessionOutput::UserEvent(UserEvent::ErrorRecord {
+                error_record,
+                handle: PipelineHandle::new(pipeline),
+            }),
+            ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished {
+                pipeline: PipelineHandle::new(pipeline),
+            }),
+        ]
+    }
+
+    /// A request refused with a quota fault is resent after an exponential
+    /// backoff, so work queues behind the shells and operations holding the
+    /// quota instead of failing; once it keeps being refused, the fault is
+    /// surfaced.
+    fn back_off_quota(
+        &mut self,
+        err: PwshCoreError,
+    ) -> Result<Vec<ActiveSessionOutput>, PwshCoreError> {
+        let PwshCoreError::QuotaExceeded { which, limit, .. } = err else {
+            return Err(err);
+        };
+        let Some(request) = self.connection_pool.take_faulted_request() else {
+            return Err(err);
+        };
+        if self.quota_retries >= MAX_QUOTA_RETRIES {
+            return Err(err);
+        }
+        self.quota_retries += 1;
+        let delay = Duration::from_secs(1 << self.quota_retries).min(MAX_RETRY_DELAY);
+        let retry = self.connection_pool.send_on(Lane::Control, &request)?;
+        warn!(
+            %which,
+            ?limit,
+            retry_conn_id = retry.get_connection_id().inner(),
+            attempt = self.quota_retries,
+            delay_ms = delay.as_millis() as u64,
+            "WinRM quota exceeded; resending the request after a backoff"
+        );
+        Ok(vec![ActiveSessionOutput::Interrupted {
+            interruption: Interruption::QuotaExceeded { which, limit },
+            retry: Some((delay, retry)),
+        }])
+    }
+
     /// Handle a server response that arrives while a Disconnect is in flight.
     ///
     /// Besides the DisconnectResponse itself, the long-poll Receive that was in
@@ -723,7 +1607,9 @@ impl ActiveSession {
                 self.disconnect_conn_id = None;
                 Ok(vec![ActiveSessionOutput::OperationSuccess])
             }
-            Err(PwshCoreError::InvalidResponse(reason)) => {
+            Err(PwshCoreError::InvalidResponse {
+                message: reason, ..
+            }) => {
                 // The Disconnect request itself received the wrong shape of response:
                 // revert to Opened so the session loop surfaces the failed disconnect
                 // instead of remaining stuck in Disconnecting.
@@ -740,7 +1626,7 @@ impl ActiveSession {
                     desired_streams: self.runspace_pool.compute_active_desired_streams(),
                 }])
             }
-            Err(PwshCoreError::SoapFault { code, reason }) => {
+            Err(PwshCoreError::SoapFault { code, reason, .. }) => {
                 // The Disconnect request faulted: revert the pool to Opened. The session
                 // loop observes the Disconnecting → Opened transition and surfaces the
                 // failure to the user.
@@ -761,6 +1647,43 @@ impl ActiveSession {
         }
     }
 
+    /// Handle a server response that arrives while a Close is in flight.
+    ///
+    /// Only the tracked Close connection completes it; everything else is
+    /// traffic of the shell being deleted. Pipelines still open when the shell
+    /// goes away are finished, since the server sends nothing more for them.
+    fn accept_response_while_closing(
+        &mut self,
+        xml_body: &str,
+        conn_id: ConnectionId,
+    ) -> Result<Vec<ActiveSessionOutput>, crate::PwshCoreError> {
+        if self.close_conn_id != Some(conn_id) {
+            warn!(
+                conn_id = conn_id.inner(),
+                close_conn_id = self.close_conn_id.map(|id| id.inner()),
+                body_length = xml_body.len(),
+                "ignoring non-close traffic while closing"
+            );
+            return Ok(vec![ActiveSessionOutput::Ignore]);
+        }
+
+        self.runspace_pool.accept_close_response(xml_body)?;
+        self.close_conn_id = None;
+        self.forget_host_calls(None);
+
+        let mut outs: Vec<ActiveSessionOutput> = self
+            .runspace_pool
+            .pipeline_handles()
+            .into_iter()
+            .map(|pipeline| {
+                self.flow.forget(pipeline.id());
+                ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished { pipeline })
+            })
+            .collect();
+        outs.push(ActiveSessionOutput::OperationSuccess);
+        Ok(outs)
+    }
+
     /// Handle a server response that arrives while a Reconnect is in flight.
     ///
     /// Late traffic from the pre-disconnect Receive can race ahead of the
@@ -799,7 +1722,9 @@ impl ActiveSession {
                     },
                 ])
             }
-            Err(PwshCoreError::InvalidResponse(reason)) => {
+            Err(PwshCoreError::InvalidResponse {
+                message: reason, ..
+            }) => {
                 // The Reconnect request itself received the wrong shape of response: revert
                 // to Disconnected so the session loop surfaces ReconnectFailed instead of
                 // remaining stuck in Connecting.
@@ -813,7 +1738,7 @@ impl ActiveSession {
                 );
                 Ok(vec![ActiveSessionOutput::Ignore])
             }
-            Err(PwshCoreError::SoapFault { code, reason }) => {
+            Err(PwshCoreError::SoapFault { code, reason, .. }) => {
                 // The Reconnect request faulted (e.g. the shell is gone): revert to
                 // Disconnected. The session loop observes Connecting → Disconnected and
                 // surfaces ReconnectFailed to the user.
@@ -831,6 +1756,83 @@ impl ActiveSession {
         }
     }
 
+    fn settle_host_call(&mut self, scope: &HostCallScope, call_id: i64) {
+        self.pending_host_calls
+            .retain(|call| !(call.call_id == call_id && &call.scope == scope));
+    }
+
+    /// Forget the host calls nobody can answer any more: those of `pipeline`
+    /// once it finished or was killed, or all of them once the pool is gone.
+    fn forget_host_calls(&mut self, pipeline: Option<uuid::Uuid>) {
+        self.pending_host_calls
+            .retain(|call| match (&call.scope, pipeline) {
+                (HostCallScope::Pipeline { command_id }, Some(pipeline)) => *command_id != pipeline,
+                (HostCallScope::RunspacePool, Some(_)) => true,
+                (_, None) => false,
+            });
+    }
+
+    /// Build + send a WinRS Signal for a pipeline. A pipeline that can no longer be
+    /// signalled (unknown or already terminal) is ignored rather than failing the session.
+    fn signal_pipeline(
+        &mut self,
+        pipeline: &PipelineHandle,
+        code: SignalCode,
+    ) -> Result<ActiveSessionOutput, crate::PwshCoreError> {
+        // 1) Build the Signal request
+        let signal_xml = match self.runspace_pool.signal_pipeline(pipeline, code) {
+            Ok(signal_xml) => signal_xml,
+            Err(e) => {
+                error!(error = ?e, "failed to build signal XML");
+                return Ok(ActiveSessionOutput::Ignore);
+            }
+        };
+
+        info!(xml_length = signal_xml.len(), "built signal XML request");
+
+        // 2) Send signal
+        let ts_send = self.connection_pool.send(&signal_xml)?;
+        info!(signal_request = ?ts_send, "queued signal request");
+
+        Ok(ActiveSessionOutput::SendBack(vec![ts_send]))
+    }
+
+    /// A headless session has no host to hand the call to: answer it with an
+    /// exception so the remote command fails with a clear error instead of
+    /// waiting for a reply forever. Void calls need no answer and are dropped.
+    fn reject_host_call(
+        &mut self,
+        host_call: &HostCall,
+    ) -> Result<ActiveSessionOutput, PwshCoreError> {
+        let method_name = host_call.method_name();
+        warn!(
+            call_id = host_call.call_id(),
+            method = %method_name,
+            "host call on a headless session; rejecting it"
+        );
+        if !host_call.should_send_response() {
+            return Ok(ActiveSessionOutput::Ignore);
+        }
+        let error = Some(PsValue::Primitive(PsPrimitiveValue::Str(format!(
+            "The host method {method_name} is not available: the client runs without a host (headless)."
+        ))));
+        match host_call.scope() {
+            HostCallScope::Pipeline { command_id } => self.send_pipeline_host_response(
+                command_id,
+                host_call.call_id(),
+                host_call.method(),
+                None,
+                error,
+            ),
+            HostCallScope::RunspacePool => self.send_runspace_pool_host_response(
+                host_call.call_id(),
+                host_call.method(),
+                None,
+                error,
+            ),
+        }
+    }
+
     /// Build + send a pipeline host response, then queue a receive for that pipeline.
     #[instrument(skip(self, result, error), fields(command_id = %command_id, call_id, method = ?method))]
     fn send_pipeline_host_response(
@@ -877,10 +1879,8 @@ impl ActiveSession {
         let ts_send = self.connection_pool.send(&send_xml)?;
         info!(send_request = ?ts_send, "queued host response send-then-receive");
 
-        Ok(ActiveSessionOutput::SendAndThenReceive {
-            send_request: ts_send,
-            then_receive_streams: DesiredStream::pipeline_streams(command_id),
-        })
+        let streams = DesiredStream::pipeline_streams(command_id);
+        Ok(self.send_then_admitted_receive(ts_send, streams, 0))
     }
 
     /// Build + send a runspace-pool host response, then queue a receive for pool streams.
@@ -931,9 +1931,58 @@ impl ActiveSession {
         let ts_send = self.connection_pool.send(&send_xml)?;
         info!(send_request = ?ts_send, "queued pool host response send-then-receive");
 
-        Ok(ActiveSessionOutput::SendAndThenReceive {
-            send_request: ts_send,
-            then_receive_streams: DesiredStream::runspace_pool_streams(),
-        })
+        let streams = DesiredStream::runspace_pool_streams();
+        Ok(self.send_then_admitted_receive(ts_send, streams, 0))
     }
+
+    /// Pair a send with its follow-up Receive, subject to flow control. When every
+    /// stream is held back, the send goes out alone so it is never starved behind
+    /// Receive traffic; the held-back streams are re-issued once capacity frees up.
+    fn send_then_admitted_receive(
+        &mut self,
+        send_request: TrySend,
+        desired_streams: Vec<DesiredStream>,
+        queued: usize,
+    ) -> ActiveSessionOutput {
+        let in_flight = self.outstanding_receive_conns.len() + queued;
+        let then_receive_streams = self.flow.admit(desired_streams, in_flight);
+        if then_receive_streams.is_empty() {
+            return ActiveSessionOutput::SendBack(vec![send_request]);
+        }
+        ActiveSessionOutput::SendAndThenReceive {
+            send_request,
+            then_receive_streams,
+        }
+    }
+}
+
+/// Disconnect/Suspend/Reconnect/Close keep their mistimed-operation handling,
+/// output acknowledgements and power saving are purely local, and signals
+/// (Ctrl+C, kill) must reach a running pipeline even while its input or a host
+/// response is still being sent, so only the rest is queued and ordered.
+fn is_shell_bound(operation: &UserOperation) -> bool {
+    !matches!(
+        operation,
+        UserOperation::Disconnect
+            | UserOperation::Suspend
+            | UserOperation::Reconnect
+            | UserOperation::Close
+            | UserOperation::AcknowledgeOutput { .. }
+            | UserOperation::SetPowerSaving { .. }
+            | UserOperation::SignalPipeline { .. }
+            | UserOperation::KillPipeline { .. }
+    )
+}
+
+/// Receives already requested by `outs` but not yet dispatched by the session loop.
+fn queued_receives(outs: &[ActiveSessionOutput]) -> usize {
+    outs.iter()
+        .filter(|out| {
+            matches!(
+                out,
+                ActiveSessionOutput::PendingReceive { .. }
+                    | ActiveSessionOutput::SendAndThenReceive { .. }
+            )
+        })
+        .count()
 }
@@ -10,7 +10,7 @@ use crate::{
             SecContextMaybeInit, SecurityContextBuilder, SspiAuthenticator, SspiConfig,
             SspiContext, Token,
         },
-        config::{AuthenticatorConfig, SspiAuthConfig},
+        config::{AuthenticatorConfig, Delegation, SspiAuthConfig, TlsOptions},
         connection_pool::{ConnectionId, TrySend},
         encryption::EncryptionProvider,
         http::{HttpBody, HttpBuilder, HttpRequest, HttpResponse},
@@ -82,19 +82,27 @@ impl SspiAuthContext {
                 identity,
                 kerberos_config,
                 target: target_name,
-            } => SspiContext::new_kerberos(
-                identity,
-                kerberos_config.into(),
-                SspiConfig::with_channel_binding(target_name, channel_binding),
-            )
-            .map(SspiAuthContext::Kerberos),
+            } => {
+                let delegation = kerberos_config.delegation;
+                SspiContext::new_kerberos(
+                    identity,
+                    kerberos_config.into(),
+                    SspiConfig::with_channel_binding(target_name, channel_binding)
+                        .with_delegation(delegation),
+                )
+                .map(SspiAuthContext::Kerberos)
+            }
 
             SspiAuthConfig::Negotiate {
                 identity,
                 kerberos_config,
                 target: target_name,
             } => {
-                let sspi_config = SspiConfig::with_channel_binding(target_name, channel_binding);
+                let delegation = kerberos_config
+                    .as_ref()
+                    .map_or(Delegation::None, |config| config.delegation);
+                let sspi_config = SspiConfig::with_channel_binding(target_name, channel_binding)
+                    .with_delegation(delegation);
 
                 let client_computer_name = whoami::fallible::hostname().map_err(|e| {
                     crate::PwshCoreError::InternalError(format!(
@@ -146,6 +154,7 @@ pub struct SspiAuthSequence {
     context: SspiAuthContext,
     http_builder: HttpBuilder,
     require_encryption: bool,
+    kdc_proxy_tls: Option<TlsOptions>,
 }
 
 pub enum SecCtxInited {
@@ -170,14 +179,32 @@ impl SspiAuthSequence {
         http_builder: HttpBuilder,
         channel_binding: Option<Vec<u8>>,
     ) -> Result<Self, crate::PwshCoreError> {
+        let kdc_proxy_tls = match &sspi_auth_config {
+            SspiAuthConfig::NTLM { .. } => None,
+            SspiAuthConfig::Kerberos {
+                kerberos_config, ..
+            } => Some(kerberos_config),
+            SspiAuthConfig::Negotiate {
+                kerberos_config, ..
+            } => kerberos_config.as_ref(),
+        }
+        .and_then(|config| config.kdc.as_ref()?.proxy_tls().cloned());
+
         let context = SspiAuthContext::new(sspi_auth_config, channel_binding)?;
         Ok(Self {
             context,
             http_builder,
             require_encryption,
+            kdc_proxy_tls,
         })
     }
 
+    /// TLS options for KDC proxy requests, when the Kerberos config names a KDC
+    /// proxy. Clients should fall back to their own TLS options otherwise.
+    pub fn kdc_proxy_tls(&self) -> Option<&TlsOptions> {
+        self.kdc_proxy_tls.as_ref()
+    }
+
     pub fn try_init_sec_context<'ctx, 'builder, 'generator>(
         &'ctx mut self,
         response: Option<&HttpResponse>,
@@ -305,6 +332,7 @@ pub struct PostConAuthSequence {
 pub enum AuthSequence {
     Sspi(SspiAuthSequence),
     Basic(BasicAuthSequence),
+    None(NoAuthSequence),
 }
 
 /// Basic engine (new, zero-round)
@@ -332,6 +360,23 @@ impl BasicAuthSequence {
     }
 }
 
+/// No-auth engine (zero-round): plain requests without an `Authorization`
+/// header, for listeners fronted by an authenticating gateway.
+#[derive(Debug)]
+pub struct NoAuthSequence {
+    http_builder: HttpBuilder,
+}
+
+impl NoAuthSequence {
+    pub fn start(&mut self, xml: &str, connection_id: ConnectionId) -> TrySend {
+        let request = self.http_builder.post(HttpBody::Xml(xml.to_owned()));
+        TrySend::JustSend {
+            request,
+            conn_id: connection_id,
+        }
+    }
+}
+
 impl AuthSequence {
     pub fn new(
         cfg: &AuthSequenceConfig,
@@ -355,6 +400,7 @@ impl AuthSequence {
                     http_builder: http,
                 }))
             }
+            AuthenticatorConfig::None => Ok(Self::None(NoAuthSequence { http_builder: http })),
         }
     }
 }
@@ -7,14 +7,15 @@ use sspi::builders::{
 };
 use sspi::generator::{Generator, GeneratorState};
 use sspi::{
-    BufferType, ClientRequestFlags, CredentialUse, Credentials, DataRepresentation,
-    EncryptionFlags, Error, InitializeSecurityContextResult, Kerberos, KerberosConfig, Negotiate,
-    NegotiateConfig, NetworkRequest, Ntlm, SecurityBuffer, SecurityBufferFlags, SecurityBufferRef,
-    SecurityStatus, Sspi, SspiImpl,
+    BufferType, ClientRequestFlags, ClientResponseFlags, CredentialUse, Credentials,
+    DataRepresentation, EncryptionFlags, Error, InitializeSecurityContextResult, Kerberos,
+    KerberosConfig, Negotiate, NegotiateConfig, NetworkRequest, Ntlm, SecurityBuffer,
+    SecurityBufferFlags, SecurityBufferRef, SecurityStatus, Sspi, SspiImpl,
 };
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 use crate::PwshCoreError;
+use crate::connector::config::Delegation;
 use crate::connector::http::HttpResponse;
 use crate::credentials::ClientAuthIdentity;
 
@@ -34,18 +35,28 @@ pub struct SspiConfig {
     /// into every `InitializeSecurityContext` leg as a `ChannelBindings` input
     /// buffer. `None` for plain HTTP or before the server cert is known.
     channel_binding: Option<Vec<u8>>,
+    delegation: Delegation,
 }
 
 impl SspiConfig {
     pub fn with_channel_binding(mut target: String, channel_binding: Option<Vec<u8>>) -> Self {
-        if !target.trim().starts_with("HTTP/") {
+        // A bare host gets the default service class; a full SPN (any
+        // `service/host` form) is used as given.
+        if !target.contains('/') {
             target = format!("HTTP/{target}");
         }
         Self {
             target_name: target,
             channel_binding,
+            delegation: Delegation::None,
         }
     }
+
+    #[must_use]
+    pub fn with_delegation(mut self, delegation: Delegation) -> Self {
+        self.delegation = delegation;
+        self
+    }
 }
 
 /// Caller-owned "Context" the generator borrows.
@@ -80,7 +91,7 @@ impl SspiContext<Negotiate> {
     ) -> Result<Self, PwshCoreError> {
         Self::new_with_credential(
             Negotiate::new_client(config)?,
-            &Credentials::AuthIdentity(id.into_inner()),
+            &id.into_credentials(),
             sspi_config,
         )
     }
@@ -94,7 +105,7 @@ impl SspiContext<Kerberos> {
     ) -> Result<Self, PwshCoreError> {
         Self::new_with_credential(
             Kerberos::new_client_from_config(kerberos_config)?,
-            &Credentials::AuthIdentity(id.into_inner()),
+            &id.into_credentials(),
             sspi_config,
         )
     }
@@ -134,7 +145,7 @@ where
         id: ClientAuthIdentity,
         config: SspiConfig,
     ) -> Result<Self, PwshCoreError> {
-        let id: sspi::AuthIdentity = id.into_inner();
+        let id: sspi::AuthIdentity = id.into_inner()?;
         let acq = provider
             .acquire_credentials_handle()
             .with_credential_use(CredentialUse::Outbound)
@@ -257,6 +268,12 @@ impl SspiAuthenticator {
             debug!("sealing disabled (TLS transport): requesting INTEGRITY only");
             ClientRequestFlags::INTEGRITY
         };
+        let flag = if context.sspi_auth_config.delegation == Delegation::None {
+            flag
+        } else {
+            debug!("requesting credential delegation");
+            flag | ClientRequestFlags::DELEGATE
+        };
 
         // Build the builder; wire inputs/outputs.
         let mut isc: SecurityContextBuilder<P> = context
@@ -355,7 +372,13 @@ impl SspiAuthenticator {
                     "SSPI ContinueNeeded but no token produced",
                 ))?,
             }),
-            SecurityStatus::Ok => Ok(ActionReqired::Done { token }),
+            SecurityStatus::Ok => {
+                check_delegation(
+                    furniture.sspi_auth_config.delegation,
+                    sec_context.init_sec_context_res.flags,
+                )?;
+                Ok(ActionReqired::Done { token })
+            }
             _ => Err(PwshCoreError::Auth(
                 "SSPI InitializeSecurityContext status needs to be handled",
             )),
@@ -436,6 +459,29 @@ impl SspiAuthenticator {
     }
 }
 
+/// Compare the requested delegation with what the established context grants.
+/// The provider only grants it when the service ticket is `ok-as-delegate`.
+fn check_delegation(
+    delegation: Delegation,
+    granted: ClientResponseFlags,
+) -> Result<(), PwshCoreError> {
+    let delegated = granted.contains(ClientResponseFlags::DELEGATE);
+    match delegation {
+        Delegation::None => Ok(()),
+        _ if delegated => {
+            debug!("credentials delegated to the server");
+            Ok(())
+        }
+        Delegation::IfTrusted => {
+            warn!("server is not trusted for delegation; continuing without delegated credentials");
+            Ok(())
+        }
+        Delegation::Required => Err(PwshCoreError::Auth(
+            "credential delegation was required but the server is not trusted for delegation (service ticket lacks ok-as-delegate)",
+        )),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token(pub(crate) String);
 
@@ -494,3 +540,22 @@ fn parse_negotiate_token(headers: &[(String, String)]) -> Option<Vec<u8>> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegation_outcome_follows_policy() {
+        let denied = ClientResponseFlags::empty();
+        let granted = ClientResponseFlags::DELEGATE;
+
+        assert!(check_delegation(Delegation::None, denied).is_ok());
+        assert!(check_delegation(Delegation::IfTrusted, denied).is_ok());
+        assert!(check_delegation(Delegation::Required, granted).is_ok());
+        assert!(matches!(
+            check_delegation(Delegation::Required, denied),
+            Err(PwshCoreError::Auth(_))
+        ));
+    }
+}
@@ -1,23 +1,108 @@
 use url::Url;
 
+pub use ironposh_psrp::DefragmentLimits;
+pub use ironposh_xml::parser::ParseLimits;
+
+/// Where Kerberos messages are sent.
+#[derive(Debug, Clone)]
+pub enum KdcEndpoint {
+    /// Talk to the KDC directly, e.g. `tcp://dc.example.com:88`.
+    Direct(Url),
+    /// Tunnel KDC messages through an MS-KKDCP proxy over HTTP(S), for networks
+    /// where port 88 is blocked. `tls` applies to the proxy connection only.
+    KdcProxy { url: Url, tls: TlsOptions },
+}
+
+impl KdcEndpoint {
+    /// Pick the variant from the URL scheme: `http`/`https` URLs are KDC proxies.
+    pub fn from_url(url: Url, tls: TlsOptions) -> Self {
+        match url.scheme() {
+            "http" | "https" => Self::KdcProxy { url, tls },
+            _ => Self::Direct(url),
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        match self {
+            Self::Direct(url) | Self::KdcProxy { url, .. } => url,
+        }
+    }
+
+    /// TLS options for the KDC proxy connection, if this is a proxy.
+    pub fn proxy_tls(&self) -> Option<&TlsOptions> {
+        match self {
+            Self::Direct(_) => None,
+            Self::KdcProxy { tls, .. } => Some(tls),
+        }
+    }
+}
+
+/// Kerberos credential delegation, for second-hop scenarios (the remote
+/// session authenticating onwards as the user). Off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Delegation {
+    #[default]
+    None,
+    /// Request delegation (`GSS_C_DELEG_FLAG`); if the KDC did not mark the
+    /// service ticket `ok-as-delegate`, continue without it.
+    IfTrusted,
+    /// Request delegation and fail authentication if the server is not trusted
+    /// for delegation.
+    Required,
+}
+
 #[derive(Debug, Clone)]
 pub struct KerberosConfig {
-    /// Optional KDC URL. If not set, the KDC will be discovered via DNS SRV records.
-    pub kdc_url: Option<Url>,
+    /// Optional KDC endpoint. If not set, the KDC will be discovered via DNS SRV records.
+    pub kdc: Option<KdcEndpoint>,
 
     /// Optional client computer name. If not set, the local computer name will be used.
     pub client_computer_name: String,
+
+    /// Whether to delegate the user's credentials to the server.
+    pub delegation: Delegation,
 }
 
 impl From<KerberosConfig> for sspi::KerberosConfig {
     fn from(val: KerberosConfig) -> Self {
         Self {
-            kdc_url: val.kdc_url,
+            // sspi performs the MS-KKDCP wrapping itself for http(s) KDC URLs.
+            kdc_url: val.kdc.map(|kdc| kdc.url().clone()),
             client_computer_name: Some(val.client_computer_name),
         }
     }
 }
 
+/// Kerberos service principal of the WinRM endpoint, for when it can't be
+/// derived from the connection address (CNAMEs, load balancers, the Devolutions
+/// Gateway).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServicePrincipal {
+    /// Used verbatim, e.g. `HTTP/web01.corp.example.com@CORP.EXAMPLE.COM`.
+    Explicit(String),
+    /// `{host}` is replaced by the connection host, e.g. `HTTP/{host}.corp.example.com`.
+    Template(String),
+}
+
+impl ServicePrincipal {
+    /// A value containing `{host}` is a template, anything else is explicit.
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        if value.contains("{host}") {
+            Self::Template(value)
+        } else {
+            Self::Explicit(value)
+        }
+    }
+
+    pub fn resolve(&self, host: &str) -> String {
+        match self {
+            Self::Explicit(spn) => spn.clone(),
+            Self::Template(template) => template.replace("{host}", host),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SspiAuthConfig {
     NTLM {
@@ -36,6 +121,24 @@ pub enum SspiAuthConfig {
     },
 }
 
+impl SspiAuthConfig {
+    pub fn target_mut(&mut self) -> &mut String {
+        match self {
+            Self::NTLM { target, .. }
+            | Self::Kerberos { target, .. }
+            | Self::Negotiate { target, .. } => target,
+        }
+    }
+
+    pub fn identity(&self) -> &crate::credentials::ClientAuthIdentity {
+        match self {
+            Self::NTLM { identity, .. }
+            | Self::Kerberos { identity, .. }
+            | Self::Negotiate { identity, .. } => identity,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AuthenticatorConfig {
     Basic {
@@ -45,6 +148,22 @@ pub enum AuthenticatorConfig {
     /// SSPI authentication (NTLM, Kerberos, or Negotiate).
     /// Note: SSPI message sealing is now controlled by `TransportSecurity` in `WinRmConfig`.
     Sspi(SspiAuthConfig),
+    /// No authentication: requests carry no `Authorization` header and are
+    /// never sealed. Only for deployments where a Devolutions Gateway or a
+    /// reverse proxy in front of the listener authenticates the user and
+    /// re-injects the identity. **DANGEROUS** against a bare WinRM listener.
+    None,
+}
+
+impl AuthenticatorConfig {
+    /// The user the session authenticates as, when the client knows it.
+    pub fn user_name(&self) -> Option<&str> {
+        match self {
+            Self::Basic { username, .. } => Some(username),
+            Self::Sspi(sspi) => sspi.identity().user_name(),
+            Self::None => None,
+        }
+    }
 }
 
 /// TLS behaviour for HTTPS transports. Honored by `HttpClient` implementations
@@ -61,6 +180,116 @@ pub struct TlsOptions {
     pub extra_ca_pem: Option<Vec<u8>>,
 }
 
+/// Receive-side flow control for the active session.
+///
+/// Both limits default to `None` (unlimited), which keeps the historical
+/// behaviour of re-arming a Receive as soon as the previous one completes.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiveFlowConfig {
+    /// Maximum number of Receive requests in flight at once. Receives that
+    /// would exceed it are deferred until an outstanding one completes, which
+    /// leaves connections free for Send/Signal traffic (e.g. host-call responses).
+    pub max_concurrent_receives: Option<usize>,
+    /// Per-pipeline budget of delivered-but-unacknowledged output bytes. Once a
+    /// pipeline exceeds it, its command stream is dropped from Receive requests
+    /// until the consumer acknowledges output via `UserOperation::AcknowledgeOutput`.
+    /// A consumer that never acknowledges stalls the pipeline at the budget.
+    pub max_pending_output_bytes: Option<usize>,
+}
+
+/// Client-side pacing of requests to the server.
+///
+/// Both limits default to `None` (unpaced). With many sessions fanned out
+/// from one client, a per-host rate keeps each server under its WinRM
+/// throttling limits, and jittered Receives keep the polls of many sessions
+/// from lining up.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// Most requests per second sent to the server; extra ones wait their turn.
+    pub max_requests_per_second: Option<std::num::NonZeroU32>,
+    /// Delay each Receive by a random share of this much.
+    pub receive_jitter: Option<std::time::Duration>,
+}
+
+/// Keeping a session on one node of a load-balanced WinRM farm: a shell only
+/// exists on the node that created it, so follow-up requests must not be
+/// spread across the farm.
+#[derive(Debug, Clone, Default)]
+pub struct AffinityConfig {
+    /// Connect every request to this address instead of resolving the server
+    /// name each time. The name still drives `Host`, TLS SNI and the SPN.
+    pub pinned_address: Option<std::net::IpAddr>,
+    /// Do not send the server's (load balancer's) `Set-Cookie` values back.
+    pub ignore_cookies: bool,
+}
+
+/// What an [`IdlePolicy`] does with a session left idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Disconnect the shell (MS-WSMV Disconnect); the server keeps it, so the
+    /// session can be reconnected later.
+    Disconnect,
+    /// Delete the shell (WS-Transfer Delete), releasing it on the server.
+    Close,
+}
+
+/// Client-side idle handling, for consoles left open in an inactive tab.
+///
+/// Once `after` has passed with no user operation and no running pipeline,
+/// the session loop announces the action (`SessionEvent::Idle`) and then
+/// issues it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdlePolicy {
+    pub after: std::time::Duration,
+    pub action: IdleAction,
+}
+
+/// Reopening a session whose shell the server dropped, e.g. because its idle
+/// timeout expired while the client was away.
+///
+/// When a request fails with `ShellNotFound`, the session runs the handshake
+/// again with the same configuration, runs `init_script` in the new shell and
+/// resends the invocations the old shell never answered. Pipelines the old
+/// shell was running are failed and finished. After five recreations in a
+/// row without an answer from the new shell, with a growing delay between
+/// them, the session ends with `ShellNotFound`.
+///
+/// Only the parallel session loop recreates shells; a serial session (single
+/// connection, e.g. through Devolutions Gateway) ignores this policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionRecreation {
+    /// Rebuilds the state the application set up after connecting (imported
+    /// modules, variables); remote state is otherwise lost with the shell.
+    pub init_script: Option<String>,
+}
+
+/// Whether the client offers the server a host (console UI) at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HostMode {
+    /// Advertise the configured `HostInfo`; host calls reach the application.
+    #[default]
+    Interactive,
+    /// For services and daemons: advertise a null host, raw UI and UI
+    /// (`_isHostNull`, `_isHostUINull`, `_isHostRawUINull`) so the server does
+    /// not call back into one. A host call that still arrives is answered with
+    /// an exception instead of reaching the application.
+    Headless,
+}
+
+/// What a malformed PSRP message in a ReceiveResponse does to the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Fail the response, and with it the session.
+    #[default]
+    Strict,
+    /// Report a malformed data-stream record (output, error, verbose,
+    /// warning, debug, information, progress) as an
+    /// `ActiveSessionOutput::ProtocolWarning` and go on with the rest of the
+    /// response. Malformed state, key exchange or host call messages, and
+    /// streams whose fragments do not reassemble, still fail it.
+    Lenient,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,4 +301,51 @@ mod tests {
         assert!(!tls.accept_invalid_hostnames);
         assert!(tls.extra_ca_pem.is_none());
     }
+
+    #[test]
+    fn kdc_endpoint_from_url_detects_proxies() {
+        let tls = TlsOptions {
+            accept_invalid_certs: true,
+            ..TlsOptions::default()
+        };
+        let proxy = KdcEndpoint::from_url(
+            "https://gw.example.com/KdcProxy".parse().unwrap(),
+            tls.clone(),
+        );
+        assert!(
+            proxy
+                .proxy_tls()
+                .is_some_and(|tls| tls.accept_invalid_certs)
+        );
+
+        let direct = KdcEndpoint::from_url("tcp://dc.example.com:88".parse().unwrap(), tls);
+        assert!(direct.proxy_tls().is_none());
+        assert_eq!(direct.url().as_str(), "tcp://dc.example.com:88");
+    }
+
+    #[test]
+    fn service_principal_templates_substitute_the_host() {
+        let template = ServicePrincipal::new("HTTP/{host}.corp.example.com");
+        assert_eq!(template.resolve("web01"), "HTTP/web01.corp.example.com");
+
+        let explicit = ServicePrincipal::new("HTTP/web01.corp.example.com@CORP.EXAMPLE.COM");
+        assert_eq!(
+            explicit.resolve("lb.example.com"),
+            "HTTP/web01.corp.example.com@CORP.EXAMPLE.COM"
+        );
+    }
+
+    #[test]
+    fn affinity_default_honors_cookies_without_pinning() {
+        let affinity = AffinityConfig::default();
+        assert!(affinity.pinned_address.is_none());
+        assert!(!affinity.ignore_cookies);
+    }
+
+    #[test]
+    fn receive_flow_default_is_unlimited() {
+        let flow = ReceiveFlowConfig::default();
+        assert!(flow.max_concurrent_receives.is_none());
+        assert!(flow.max_pending_output_bytes.is_none());
+    }
 }
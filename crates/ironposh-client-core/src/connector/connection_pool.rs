@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
 use crate::{
     PwshCoreError,
@@ -9,11 +9,14 @@ use crate::{
             AuthSequence, AuthSequenceConfig, Authenticated, PostConAuthSequence,
             SecurityContextBuilderHolder, SspiAuthSequence,
         },
+        config::{AffinityConfig, RateLimitConfig, TlsOptions},
+        correlation::verify_relates_to,
         encryption::{EncryptionOptions, EncryptionProvider},
         http::{
-            HttpBody, HttpBuilder, HttpRequest, HttpRequestAction, HttpResponseTargeted,
-            ServerAddress,
+            HttpBody, HttpBuilder, HttpRequest, HttpRequestAction, HttpResponse,
+            HttpResponseTargeted, Interruption, ServerAddress,
         },
+        rate_limit::{RateLimiter, ThrottleStats},
     },
 };
 
@@ -56,15 +59,43 @@ pub enum ConnectionState {
     Closed,
 }
 
+/// Which kind of request is being sent. Once the receive lane is enabled
+/// ([`ConnectionPool::dedicate_receive_lane`]), the long-poll Receive keeps to
+/// its own connection and everything else goes out on another one, so user
+/// input never waits for the server to release a parked Receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Receive,
+    /// Command, Send, Signal, Disconnect/Reconnect, Delete.
+    Control,
+}
+
 #[derive(Debug)]
 pub enum ConnectionPoolAccept {
     /// Plaintext SOAP envelope (after decrypt / state transition)
     Body(String),
     /// The previous request could not be accepted; caller must send these.
     SendBack(Vec<TrySend>),
+    /// Once the session is established: the request was answered with a
+    /// status and no SOAP reply. `queued_xml` is the unprocessed request; the
+    /// connection is gone.
+    Interrupted {
+        interruption: Interruption,
+        queued_xml: String,
+    },
 }
 
 // =============================== TrySend API ===============================
+/// One unit of outgoing work for an `HttpClient` implementation.
+///
+/// - [`TrySend::JustSend`]: send `request` as-is on the connection
+///   `conn_id` and answer with
+///   `HttpResponseTargeted::new(response, conn_id, None)`. Requests for the
+///   same `conn_id` must go over the same underlying HTTP connection
+///   (keep-alive): WinRM authentication and sealing are per connection.
+/// - [`TrySend::AuthNeeded`]: open a new connection and drive
+///   `auth_sequence` on it until it yields the final request; its response
+///   carries the `AuthenticatedHttpChannel` the sequence hands out.
 #[expect(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum TrySend {
@@ -83,12 +114,35 @@ pub enum TrySend {
 }
 
 impl TrySend {
-    pub fn get_connection_id(&self) -> ConnectionId {
+    /// A ready-to-send request, e.g. to feed a fake server in tests.
+    pub fn just_send(request: HttpRequest, conn_id: ConnectionId) -> Self {
+        Self::JustSend { request, conn_id }
+    }
+
+    /// The connection this work targets.
+    pub fn connection_id(&self) -> ConnectionId {
         match self {
             Self::JustSend { conn_id, .. } => *conn_id,
             Self::AuthNeeded { auth_sequence } => auth_sequence.conn_id,
         }
     }
+
+    pub fn get_connection_id(&self) -> ConnectionId {
+        self.connection_id()
+    }
+
+    /// The request to send, for [`TrySend::JustSend`]; an auth sequence
+    /// produces its requests as it runs.
+    pub fn request(&self) -> Option<&HttpRequest> {
+        match self {
+            Self::JustSend { request, .. } => Some(request),
+            Self::AuthNeeded { .. } => None,
+        }
+    }
+
+    pub fn is_auth_needed(&self) -> bool {
+        matches!(self, Self::AuthNeeded { .. })
+    }
 }
 
 // === Helper: unwrap a TrySend to JustSend during Connected handoff ===
@@ -115,6 +169,8 @@ impl TrySendExt for TrySend {
 pub struct ConnectionPoolConfig {
     server: (ServerAddress, u16),
     scheme: Scheme,
+    affinity: AffinityConfig,
+    rate_limit: RateLimitConfig,
 }
 
 impl From<&WinRmConfig> for ConnectionPoolConfig {
@@ -122,6 +178,8 @@ impl From<&WinRmConfig> for ConnectionPoolConfig {
         Self {
             server: w.server.clone(),
             scheme: w.transport.scheme(),
+            affinity: w.affinity.clone(),
+            rate_limit: w.rate_limit.clone(),
         }
     }
 }
@@ -144,6 +202,24 @@ pub struct ConnectionPool {
     /// auth sequence this pool starts includes it (EPA). `None` over plain HTTP
     /// or before the first challenge.
     channel_binding: Option<Vec<u8>>,
+    affinity: AffinityConfig,
+    /// `Set-Cookie` values seen on responses (load-balancer stickiness), sent
+    /// back on every request so the farm keeps routing to the shell's node.
+    affinity_cookies: Vec<(String, String)>,
+    /// Whether Receives and control requests use separate connections. Off for
+    /// transports that carry every `ConnectionId` over one socket (a second
+    /// auth handshake there would reset the first connection's context).
+    receive_lane_enabled: bool,
+    /// The connection dedicated to the Receive long-poll, once known.
+    receive_conn: Option<ConnectionId>,
+    /// Report 401/419/502/503/504 on established connections as
+    /// [`ConnectionPoolAccept::Interrupted`] instead of failing.
+    surface_interruptions: bool,
+    /// The request behind the last body accepted with an error status, kept
+    /// once the session is up so it can be resent after a quota fault.
+    faulted_request: Option<String>,
+    /// Client-side pacing of the requests sent on this pool's connections.
+    rate_limiter: RateLimiter,
 }
 
 impl ConnectionPool {
@@ -158,29 +234,130 @@ impl ConnectionPool {
             },
             next_id: 1,
             channel_binding: None,
+            affinity: cfg.affinity,
+            affinity_cookies: Vec::new(),
+            receive_lane_enabled: false,
+            receive_conn: None,
+            surface_interruptions: false,
+            faulted_request: None,
+            rate_limiter: RateLimiter::new(&cfg.rate_limit),
+        }
+    }
+
+    /// How long to hold back the request just handed out for `conn_id`
+    /// before sending it, at `now` (from any fixed origin). `None` when it
+    /// can go at once.
+    pub fn pace(
+        &mut self,
+        conn_id: ConnectionId,
+        now: std::time::Duration,
+    ) -> Option<std::time::Duration> {
+        if !self.rate_limiter.is_enabled() {
+            return None;
         }
+        let lane = if self.receive_connection() == Some(conn_id) {
+            Lane::Receive
+        } else {
+            Lane::Control
+        };
+        let wait = self.rate_limiter.acquire(lane, now, rand::random::<f64>());
+        if wait.is_zero() {
+            return None;
+        }
+        debug!(
+            conn_id = conn_id.inner(),
+            ?lane,
+            ?wait,
+            "rate limit holding request back"
+        );
+        Some(wait)
+    }
+
+    /// How much pacing has held requests back so far.
+    pub const fn throttle_stats(&self) -> ThrottleStats {
+        self.rate_limiter.stats()
+    }
+
+    /// Once the session is up, hand interrupting statuses to the caller so it
+    /// can retry or report them; during the handshake they stay errors.
+    pub(crate) fn surface_interruptions(&mut self) {
+        self.surface_interruptions = true;
+    }
+
+    /// Keep Receives on `receive_conn` (the connection carrying the current
+    /// poll, if any) and send everything else over other connections.
+    pub fn dedicate_receive_lane(&mut self, receive_conn: Option<ConnectionId>) {
+        info!(
+            receive_conn = receive_conn.map(|id| id.inner()),
+            "dedicating a connection to the Receive long-poll"
+        );
+        self.receive_lane_enabled = true;
+        self.receive_conn = receive_conn;
+    }
+
+    /// The connection Receives currently go out on, when the lane is enabled.
+    pub fn receive_connection(&self) -> Option<ConnectionId> {
+        self.receive_conn.filter(|_| self.receive_lane_enabled)
     }
 
     fn http_builder(&self) -> HttpBuilder {
-        HttpBuilder::new(
+        let mut builder = HttpBuilder::new(
             self.sever_config.server.clone(),
             self.sever_config.port,
             self.sever_config.scheme,
-        )
+        );
+        if !self.affinity_cookies.is_empty() {
+            builder.with_cookie(
+                self.affinity_cookies
+                    .iter()
+                    .map(|(name, value)| format!("{name}={value}"))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            );
+        }
+        if let Some(address) = self.affinity.pinned_address {
+            builder.with_connect_to(address);
+        }
+        builder
+    }
+
+    /// Remember the cookies of a response; an empty value drops the cookie.
+    fn remember_cookies(&mut self, headers: &[(String, String)]) {
+        if self.affinity.ignore_cookies {
+            return;
+        }
+        let set_cookies = headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .filter_map(|(_, value)| value.split(';').next()?.split_once('='));
+        for (name, value) in set_cookies {
+            let (name, value) = (name.trim(), value.trim());
+            self.affinity_cookies.retain(|(known, _)| known != name);
+            if !value.is_empty() {
+                debug!(cookie = name, "keeping affinity cookie");
+                self.affinity_cookies
+                    .push((name.to_owned(), value.to_owned()));
+            }
+        }
     }
 
     /// Encrypts and builds a request on an Idle connection, or returns
     /// an AuthNeeded with a per-connection auth sequence for a fresh socket.
     #[instrument(skip(self, unencrypted_xml), fields(xml_length = unencrypted_xml.len()))]
     pub fn send(&mut self, unencrypted_xml: &str) -> Result<TrySend, PwshCoreError> {
-        info!("ConnectionPool: processing send request");
+        self.send_on(Lane::Control, unencrypted_xml)
+    }
+
+    pub fn send_on(&mut self, lane: Lane, unencrypted_xml: &str) -> Result<TrySend, PwshCoreError> {
+        info!(?lane, "ConnectionPool: processing send request");
         info!(unencrypted_soap = %unencrypted_xml, "outgoing unencrypted SOAP before encryption");
 
-        if let Some((id, mut enc_opt)) = self.take_idle() {
+        if let Some((id, mut enc_opt)) = self.take_idle(lane) {
             info!(
                 conn_id = id.inner(),
                 "found idle connection, preparing request"
             );
+            self.claim_for_lane(lane, id);
 
             let req = match &mut enc_opt {
                 EncryptionOptions::Sspi {
@@ -208,6 +385,15 @@ impl ConnectionPool {
                         .with_auth_header(header.clone())
                         .post(HttpBody::Xml(unencrypted_xml.to_owned()))
                 }
+                EncryptionOptions::None => {
+                    debug!(
+                        conn_id = id.inner(),
+                        "authentication disabled, sending outgoing XML as-is"
+                    );
+
+                    self.http_builder()
+                        .post(HttpBody::Xml(unencrypted_xml.to_owned()))
+                }
             };
 
             self.connections.insert(
@@ -239,6 +425,7 @@ impl ConnectionPool {
             conn_id = id.inner(),
             "no idle connection, allocated new PreAuth connection for authentication"
         );
+        self.claim_for_lane(lane, id);
 
         // Build an engine (SSPI or Basic) from cfg and a fresh HttpBuilder.
         let seq = AuthSequence::new(
@@ -266,6 +453,15 @@ impl ConnectionPool {
                     queued_xml: unencrypted_xml.to_owned(),
                 };
 
+                (try_send, next_state)
+            }
+            AuthSequence::None(mut no_auth_sequence) => {
+                let try_send = no_auth_sequence.start(unencrypted_xml, id);
+                let next_state = ConnectionState::Pending {
+                    enc: EncryptionOptions::None,
+                    queued_xml: unencrypted_xml.to_owned(),
+                };
+
                 (try_send, next_state)
             }
         };
@@ -293,6 +489,7 @@ impl ConnectionPool {
             connection_id,
             authenticated: encryption,
         } = response;
+        self.remember_cookies(&response.headers);
 
         let Some(state) = self.connections.get_mut(&connection_id) else {
             error!(conn_id = connection_id.inner(), "unknown connection ID");
@@ -364,6 +561,19 @@ impl ConnectionPool {
                             );
                             ts
                         }
+                        crate::connector::auth_sequence::AuthSequence::None(
+                            mut no_auth_sequence,
+                        ) => {
+                            let ts = no_auth_sequence.start(&queued_xml, id);
+                            self.connections.insert(
+                                id,
+                                ConnectionState::Pending {
+                                    enc: EncryptionOptions::None,
+                                    queued_xml,
+                                },
+                            );
+                            ts
+                        }
                     };
 
                     return Ok(ConnectionPoolAccept::SendBack(vec![try_send]));
@@ -386,7 +596,7 @@ impl ConnectionPool {
                         return reject_terminal_401(
                             connection_id,
                             response.status_code,
-                            "server rejected authentication (HTTP 401)",
+                            "server rejected authentication",
                         );
                     }
                     if response.status_code >= 400 {
@@ -403,12 +613,14 @@ impl ConnectionPool {
                             "decrypted PreAuth response, moving to Idle"
                         );
                     }
+                    verify_relates_to(connection_id, &queued_xml, &body)?;
 
                     *state = ConnectionState::Idle {
                         enc: EncryptionOptions::Sspi {
                             encryption_provider,
                         },
                     };
+                    self.keep_faulted_request(response.status_code, queued_xml);
 
                     Ok(ConnectionPoolAccept::Body(body))
                 } else {
@@ -435,13 +647,15 @@ impl ConnectionPool {
                 // If we get challenged again (401) while we believed we had an established
                 // HTTP-SPNEGO-session-encrypted channel, we can no longer trust this
                 // SSPI context for this logical connection. Retry the same queued SOAP on
-                // a fresh authenticated channel.
-                if response.status_code == 401
-                    && response.body.is_empty()
-                    && response
-                        .headers
-                        .iter()
-                        .any(|(k, _)| k.eq_ignore_ascii_case("www-authenticate"))
+                // a fresh authenticated channel. A 419 (authentication timeout, sent by
+                // some front-ends) asks for the same.
+                if response.status_code == 419
+                    || (response.status_code == 401
+                        && response.body.is_empty()
+                        && response
+                            .headers
+                            .iter()
+                            .any(|(k, _)| k.eq_ignore_ascii_case("www-authenticate")))
                 {
                     error!(
                         conn_id = connection_id.inner(),
@@ -485,6 +699,16 @@ impl ConnectionPool {
                             };
                             (try_send, next_state)
                         }
+                        crate::connector::auth_sequence::AuthSequence::None(
+                            mut no_auth_sequence,
+                        ) => {
+                            let try_send = no_auth_sequence.start(&queued_xml, id);
+                            let next_state = ConnectionState::Pending {
+                                enc: EncryptionOptions::None,
+                                queued_xml,
+                            };
+                            (try_send, next_state)
+                        }
                     };
 
                     self.connections.insert(id, next_state);
@@ -492,6 +716,12 @@ impl ConnectionPool {
                     return Ok(ConnectionPoolAccept::SendBack(vec![try_send]));
                 }
 
+                // Proxies and HTTP.sys answer these in plaintext, so check before
+                // decrypting.
+                if let Some(interruption) = self.interruption(&response) {
+                    return Ok(self.interrupted(connection_id, interruption, queued_xml));
+                }
+
                 let body = encryption_provider.decrypt(response.body)?;
                 if response.status_code == 401 {
                     // The recoverable re-challenge case is handled above; a 401 here
@@ -499,7 +729,7 @@ impl ConnectionPool {
                     return reject_terminal_401(
                         connection_id,
                         response.status_code,
-                        "server rejected authentication (HTTP 401)",
+                        "server rejected authentication",
                     );
                 }
                 if response.status_code >= 400 {
@@ -516,28 +746,35 @@ impl ConnectionPool {
                         "decrypted Pending response, moving to Idle"
                     );
                 }
+                verify_relates_to(connection_id, &queued_xml, &body)?;
                 *state = ConnectionState::Idle {
                     enc: EncryptionOptions::Sspi {
                         encryption_provider,
                     },
                 };
+                self.keep_faulted_request(response.status_code, queued_xml);
                 Ok(ConnectionPoolAccept::Body(body))
             }
-            ConnectionState::Pending { enc, queued_xml: _ } => {
+            ConnectionState::Pending { enc, queued_xml } => {
                 info!(
                     conn_id = connection_id.inner(),
-                    "handling Pending response without encryption (Basic auth)"
+                    "handling Pending response without encryption (Basic auth or none)"
                 );
 
+                if let Some(interruption) = self.interruption(&response) {
+                    return Ok(self.interrupted(connection_id, interruption, queued_xml));
+                }
                 if response.status_code == 401 {
-                    // Basic credentials rejected (or Basic disabled on the listener).
+                    // Basic credentials rejected (or Basic disabled on the listener),
+                    // or no gateway authenticated the unauthenticated request.
                     // Terminal — fail fast instead of returning an empty body and
                     // stalling the handshake.
-                    return reject_terminal_401(
-                        connection_id,
-                        response.status_code,
-                        "server rejected Basic authentication (HTTP 401)",
-                    );
+                    let detail = if matches!(enc, EncryptionOptions::None) {
+                        "server requires authentication; no gateway authenticated the request"
+                    } else {
+                        "server rejected Basic authentication"
+                    };
+                    return reject_terminal_401(connection_id, response.status_code, detail);
                 }
                 if response.status_code >= 400 {
                     error!(
@@ -549,9 +786,12 @@ impl ConnectionPool {
                 }
 
                 let string_body = response.body.as_str()?;
+                verify_relates_to(connection_id, &queued_xml, string_body)?;
 
                 *state = ConnectionState::Idle { enc };
-                Ok(ConnectionPoolAccept::Body(string_body.to_owned()))
+                let body = string_body.to_owned();
+                self.keep_faulted_request(response.status_code, queued_xml);
+                Ok(ConnectionPoolAccept::Body(body))
             }
             ConnectionState::Closed => {
                 error!(conn_id = connection_id.inner(), "connection already closed");
@@ -567,7 +807,40 @@ impl ConnectionPool {
         }
     }
 
+    /// The request whose response was the last accepted body, if that came
+    /// with an error status (a SOAP fault).
+    pub(crate) fn take_faulted_request(&mut self) -> Option<String> {
+        self.faulted_request.take()
+    }
+
     // -------- internals --------
+    fn keep_faulted_request(&mut self, status_code: u16, queued_xml: String) {
+        self.faulted_request =
+            (self.surface_interruptions && status_code >= 400).then_some(queued_xml);
+    }
+
+    fn interruption(&self, response: &HttpResponse) -> Option<Interruption> {
+        Interruption::classify(response).filter(|_| self.surface_interruptions)
+    }
+
+    fn interrupted(
+        &mut self,
+        conn_id: ConnectionId,
+        interruption: Interruption,
+        queued_xml: String,
+    ) -> ConnectionPoolAccept {
+        warn!(
+            conn_id = conn_id.inner(),
+            ?interruption,
+            "request interrupted by HTTP status; dropping connection"
+        );
+        self.discard(conn_id);
+        ConnectionPoolAccept::Interrupted {
+            interruption,
+            queued_xml,
+        }
+    }
+
     fn alloc_new(&mut self) -> ConnectionId {
         let id = ConnectionId::new(self.next_id);
         self.next_id += 1;
@@ -592,6 +865,9 @@ impl ConnectionPool {
     /// [`Self::accept`], so without this its pool entry would stay `Pending` forever and leak
     /// across repeated disconnect/reconnect cycles.
     pub(crate) fn discard(&mut self, conn_id: ConnectionId) {
+        if self.receive_conn == Some(conn_id) {
+            self.receive_conn = None;
+        }
         if self.connections.remove(&conn_id).is_some() {
             debug!(
                 conn_id = conn_id.inner(),
@@ -601,17 +877,74 @@ impl ConnectionPool {
         }
     }
 
-    /// Remove one Idle connection from the pool, returning its provider.
-    fn take_idle(&mut self) -> Option<(ConnectionId, EncryptionOptions)> {
-        let key = self
+    /// Connections with a request on the wire (authenticating, or awaiting the
+    /// response), ordered by id.
+    pub(crate) fn in_flight(&self) -> Vec<ConnectionId> {
+        let mut ids: Vec<ConnectionId> = self
             .connections
             .iter()
-            .find_map(|(id, st)| matches!(st, ConnectionState::Idle { .. }).then_some(*id))?;
+            .filter(|(_, state)| {
+                matches!(
+                    state,
+                    ConnectionState::PreAuth { .. } | ConnectionState::Pending { .. }
+                )
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable_by_key(ConnectionId::inner);
+        ids
+    }
+
+    /// A Receive adopts `id` as the receive connection unless the current one
+    /// is still usable (it may just be busy with an earlier Receive).
+    fn claim_for_lane(&mut self, lane: Lane, id: ConnectionId) {
+        if !self.receive_lane_enabled || lane != Lane::Receive {
+            return;
+        }
+        let current_alive = self.receive_conn.is_some_and(|conn| {
+            matches!(
+                self.connections.get(&conn),
+                Some(
+                    ConnectionState::Idle { .. }
+                        | ConnectionState::Pending { .. }
+                        | ConnectionState::PreAuth { .. }
+                )
+            )
+        });
+        if !current_alive {
+            debug!(
+                conn_id = id.inner(),
+                "connection now carries the Receive long-poll"
+            );
+            self.receive_conn = Some(id);
+        }
+    }
+
+    /// Remove one Idle connection from the pool for `lane`, returning its
+    /// provider. With the receive lane enabled, Receives prefer the receive
+    /// connection and control requests never take it.
+    fn take_idle(&mut self, lane: Lane) -> Option<(ConnectionId, EncryptionOptions)> {
+        let is_idle = |st: &ConnectionState| matches!(st, ConnectionState::Idle { .. });
+        let reserved = self.receive_connection();
+        let key = match lane {
+            Lane::Receive => reserved
+                .filter(|id| self.connections.get(id).is_some_and(is_idle))
+                .or_else(|| {
+                    self.connections
+                        .iter()
+                        .find_map(|(id, st)| is_idle(st).then_some(*id))
+                }),
+            Lane::Control => self
+                .connections
+                .iter()
+                .find_map(|(id, st)| (is_idle(st) && Some(*id) != reserved).then_some(*id)),
+        }?;
 
         match self.connections.remove(&key) {
             Some(ConnectionState::Idle { enc }) => {
                 info!(
                     conn_id = key.inner(),
+                    ?lane,
                     remaining_connections = self.connections.len(),
                     ?enc,
                     "took idle connection from pool"
@@ -640,7 +973,11 @@ fn reject_terminal_401(
         conn_id = conn_id.inner(),
         status_code, "authentication rejected by server (terminal 401)"
     );
-    Err(PwshCoreError::Auth(detail))
+    Err(PwshCoreError::HttpStatus {
+        phase: crate::Phase::Authentication,
+        status: status_code,
+        detail,
+    })
 }
 
 #[derive(Debug)]
@@ -679,6 +1016,11 @@ pub enum SecContextInited {
 }
 
 impl PostConAuthSequence {
+    /// See [`SspiAuthSequence::kdc_proxy_tls`].
+    pub fn kdc_proxy_tls(&self) -> Option<&TlsOptions> {
+        self.auth_sequence.kdc_proxy_tls()
+    }
+
     pub fn prepare<'b>(&mut self) -> (&mut SspiAuthSequence, SecurityContextBuilderHolder<'b>) {
         (&mut self.auth_sequence, SecurityContextBuilderHolder::new())
     }
@@ -0,0 +1,172 @@
+//! WS-Addressing request/response correlation.
+//!
+//! Every WS-Management response carries `a:RelatesTo` echoing the MessageID of
+//! the request it answers. The connection pool checks it against the request
+//! outstanding on the connection the response arrived on, so a response that
+//! belongs to some other request (a proxy mixing up connections, a straggler
+//! from an earlier exchange) is rejected instead of being applied to the wrong
+//! operation.
+
+use ironposh_winrm::{
+    cores::{NamedTag, TagName},
+    soap::{EnvelopeTag, header::Header},
+};
+use ironposh_xml::mapping::{FromXml, NodeExt};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{PwshCoreError, connector::connection_pool::ConnectionId};
+
+/// Check that `response_xml` answers `request_xml`.
+///
+/// Bodies that are not a SOAP envelope (an empty body, an HTML error page)
+/// are let through. A SOAP envelope must carry a `RelatesTo` naming the
+/// request's MessageID; a missing or foreign one is a protocol error.
+pub(super) fn verify_relates_to(
+    conn_id: ConnectionId,
+    request_xml: &str,
+    response_xml: &str,
+) -> Result<(), PwshCoreError> {
+    let Some(message_id) = addressing(request_xml)?.and_then(|request| request.message_id) else {
+        return Ok(());
+    };
+    let Some(response) = addressing(response_xml)? else {
+        return Ok(());
+    };
+
+    match response.relates_to {
+        Some(relates_to) if relates_to == message_id => Ok(()),
+        Some(relates_to) => {
+            error!(
+                conn_id = conn_id.inner(),
+                %message_id, %relates_to, "response RelatesTo does not match the outstanding request"
+            );
+            Err(PwshCoreError::RelatesToMismatch {
+                message_id: format!("uuid:{message_id}"),
+                relates_to: format!("uuid:{relates_to}"),
+            })
+        }
+        None => {
+            error!(
+                conn_id = conn_id.inner(),
+                %message_id, "response carries no RelatesTo header"
+            );
+            Err(PwshCoreError::MissingRelatesTo {
+                message_id: format!("uuid:{message_id}"),
+            })
+        }
+    }
+}
+
+/// The WS-Addressing ids of a SOAP envelope's header.
+#[derive(Debug, Default)]
+struct Addressing {
+    message_id: Option<Uuid>,
+    relates_to: Option<Uuid>,
+}
+
+/// Read the `Header` of `xml` through the SOAP header model. `None` when
+/// `xml` is not a SOAP envelope; the body is parsed but never mapped.
+fn addressing(xml: &str) -> Result<Option<Addressing>, PwshCoreError> {
+    let Ok(document) = ironposh_xml::parser::parse(xml) else {
+        return Ok(None);
+    };
+    let root = document.root_element();
+    if !root.is_element_named(EnvelopeTag::NAMESPACE, EnvelopeTag::TAG_NAME) {
+        return Ok(None);
+    }
+    let Some(header) = root
+        .children()
+        .find(|child| child.is_element_named(Header::NAMESPACE, Header::TAG_NAME))
+    else {
+        return Ok(Some(Addressing::default()));
+    };
+
+    let header = Header::from_xml(header)?;
+    let headers = header.as_ref();
+    Ok(Some(Addressing {
+        message_id: headers.message_id.as_ref().map(|id| id.value.0),
+        relates_to: headers.relates_to.as_ref().map(|id| id.value.0),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENVELOPE_NS: &str = r#"xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing""#;
+
+    fn request() -> String {
+        format!(
+            r#"<s:Envelope {ENVELOPE_NS}><s:Header><a:MessageID>uuid:0b4c1d6e-5e2a-4c0d-9e1f-7a8b9c0d1e2f</a:MessageID></s:Header><s:Body/></s:Envelope>"#
+        )
+    }
+
+    fn response(relates_to: &str) -> String {
+        format!(
+            r#"<s:Envelope {ENVELOPE_NS}><s:Header><a:MessageID>uuid:11111111-1111-4111-8111-111111111111</a:MessageID>{relates_to}</s:Header><s:Body/></s:Envelope>"#
+        )
+    }
+
+    #[test]
+    fn matching_relates_to_is_accepted_in_any_case() {
+        let conn = ConnectionId::test_new(1);
+        verify_relates_to(
+            conn,
+            &request(),
+            &response("<a:RelatesTo>uuid:0B4C1D6E-5E2A-4C0D-9E1F-7A8B9C0D1E2F</a:RelatesTo>"),
+        )
+        .expect("same id, different case");
+    }
+
+    #[test]
+    fn mismatched_relates_to_reports_both_ids() {
+        let conn = ConnectionId::test_new(1);
+        let err = verify_relates_to(
+            conn,
+            &request(),
+            &response("<a:RelatesTo>uuid:22222222-2222-4222-8222-222222222222</a:RelatesTo>"),
+        )
+        .expect_err("foreign RelatesTo must be rejected");
+
+        match err {
+            PwshCoreError::RelatesToMismatch {
+                message_id,
+                relates_to,
+            } => {
+                assert_eq!(message_id, "uuid:0b4c1d6e-5e2a-4c0d-9e1f-7a8b9c0d1e2f");
+                assert_eq!(relates_to, "uuid:22222222-2222-4222-8222-222222222222");
+            }
+            other => panic!("expected RelatesToMismatch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn missing_relates_to_is_rejected() {
+        let conn = ConnectionId::test_new(1);
+        // A RelatesTo in the body is payload, not a header.
+        let body_only = format!(
+            r#"<s:Envelope {ENVELOPE_NS}><s:Header/><s:Body><a:RelatesTo>uuid:0b4c1d6e-5e2a-4c0d-9e1f-7a8b9c0d1e2f</a:RelatesTo></s:Body></s:Envelope>"#
+        );
+        for response in [response(""), body_only] {
+            let err = verify_relates_to(conn, &request(), &response)
+                .expect_err("a SOAP response without RelatesTo must be rejected");
+            assert!(
+                matches!(err, PwshCoreError::MissingRelatesTo { ref message_id } if message_id == "uuid:0b4c1d6e-5e2a-4c0d-9e1f-7a8b9c0d1e2f"),
+                "expected MissingRelatesTo, got {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bodies_that_are_not_envelopes_are_tolerated() {
+        let conn = ConnectionId::test_new(1);
+        verify_relates_to(conn, &request(), "").expect("empty body");
+        verify_relates_to(
+            conn,
+            &request(),
+            "<html><body>502 Bad Gateway</body></html>",
+        )
+        .expect("proxy error page");
+    }
+}
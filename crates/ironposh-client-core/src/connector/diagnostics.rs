@@ -0,0 +1,146 @@
+//! Per-session observations about the server, for troubleshooting.
+//!
+//! Currently the server clock: every WinRM response carries an HTTP `Date`
+//! header, which gives the skew between the server and local clocks. Large
+//! skews break Kerberos (tickets are rejected beyond `MaxClockSkew`) and make
+//! server-side timestamps in records and logs misleading.
+
+use tracing::warn;
+
+/// Kerberos' default `MaxClockSkew`: beyond this, ticket validation fails.
+pub const KERBEROS_MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Diagnostics collected by an [`ActiveSession`](super::ActiveSession).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionDiagnostics {
+    /// Server clock minus local clock, in seconds, from the latest sampled
+    /// response. Positive when the server is ahead. Accurate to about a second:
+    /// the header has one-second resolution and includes network latency.
+    pub clock_skew_secs: Option<i64>,
+    /// Number of responses whose `Date` header was sampled.
+    pub clock_samples: u64,
+}
+
+impl SessionDiagnostics {
+    /// Whether the last sampled skew exceeds what Kerberos tolerates.
+    pub fn is_clock_skewed(&self) -> bool {
+        self.clock_skew_secs
+            .is_some_and(|skew| skew.abs() > KERBEROS_MAX_CLOCK_SKEW_SECS)
+    }
+
+    /// Sample the response `Date` header against the local receive time.
+    /// Responses without a parseable header are ignored.
+    pub(crate) fn observe_server_date(
+        &mut self,
+        headers: &[(String, String)],
+        local_unix_secs: i64,
+    ) {
+        let Some(server_unix_secs) = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("date"))
+            .and_then(|(_, value)| parse_http_date(value))
+        else {
+            return;
+        };
+
+        let was_skewed = self.is_clock_skewed();
+        self.clock_skew_secs = Some(server_unix_secs - local_unix_secs);
+        self.clock_samples += 1;
+
+        if self.is_clock_skewed() && !was_skewed {
+            warn!(
+                skew_secs = server_unix_secs - local_unix_secs,
+                "server clock differs from the local clock by more than Kerberos tolerates"
+            );
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse an RFC 9110 IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`, the only
+/// format servers may generate) into Unix seconds.
+pub fn parse_http_date(value: &str) -> Option<i64> {
+    let mut parts = value.split_ascii_whitespace();
+    let _weekday = parts.next()?.strip_suffix(',')?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTHS.iter().position(|month| *month == month_name)?;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let second: i64 = time.next()?.parse().ok()?;
+    if time.next().is_some() || parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, i64::try_from(month).ok()? + 1, day);
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (H. Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_imf_fixdate() {
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(
+            parse_http_date("Tue, 29 Feb 2028 23:59:59 GMT"),
+            Some(1_835_481_599)
+        );
+    }
+
+    #[test]
+    fn rejects_other_date_formats() {
+        for value in [
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 +0000",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 25:49:37 GMT",
+            "",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn skew_follows_the_latest_date_header() {
+        let mut diagnostics = SessionDiagnostics::default();
+        let date = |value: &str| vec![("date".to_owned(), value.to_owned())];
+
+        diagnostics.observe_server_date(&date("Sun, 06 Nov 1994 08:49:37 GMT"), 784_111_770);
+        assert_eq!(diagnostics.clock_skew_secs, Some(7));
+        assert!(!diagnostics.is_clock_skewed());
+
+        diagnostics.observe_server_date(&date("Sun, 06 Nov 1994 08:49:37 GMT"), 784_112_777);
+        assert_eq!(diagnostics.clock_skew_secs, Some(-1_000));
+        assert!(diagnostics.is_clock_skewed());
+
+        diagnostics.observe_server_date(&date("not a date"), 0);
+        diagnostics.observe_server_date(&[], 0);
+        assert_eq!(diagnostics.clock_skew_secs, Some(-1_000));
+        assert_eq!(diagnostics.clock_samples, 2);
+    }
+}
@@ -20,6 +20,8 @@ pub enum EncryptionOptions {
     Sspi {
         encryption_provider: EncryptionProvider,
     },
+    /// `AuthenticatorConfig::None`: plain XML, no `Authorization` header.
+    None,
 }
 
 #[derive(Debug)]
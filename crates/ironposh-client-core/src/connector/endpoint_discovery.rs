@@ -0,0 +1,332 @@
+//! WinRM endpoint discovery for a domain, sans-IO.
+//!
+//! Frontends look up `_wsman._tcp.<domain>` with [`srv_query`] /
+//! [`parse_srv_response`] (same UDP exchange as
+//! [`kdc_discovery`](super::kdc_discovery)), turn the records into
+//! [`candidates`], send each one's [`identify_request`] and record the
+//! [`Probe`] outcome, then [`rank`] the list.
+
+use std::borrow::Cow;
+
+use super::{
+    Scheme,
+    http::{HttpBody, HttpBuilder, HttpRequest, HttpResponse, ServerAddress},
+    kdc_discovery::{SrvRecord, encode_srv_query, parse_srv_answers},
+};
+use crate::{Phase, PwshCoreError};
+
+pub const HTTP_PORT: u16 = 5985;
+pub const HTTPS_PORT: u16 = 5986;
+
+const WSMAN_IDENTITY_NS: &str = "http://schemas.dmtf.org/wbem/wsman/identity/1/wsmanidentity.xsd";
+
+/// `Identify` needs no addressing headers; with `WSMANIDENTIFY: unauthenticated`
+/// the listener answers before any authentication.
+const IDENTIFY_ENVELOPE: &str = concat!(
+    r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" "#,
+    r#"xmlns:wsmid="http://schemas.dmtf.org/wbem/wsman/identity/1/wsmanidentity.xsd">"#,
+    "<s:Header/><s:Body><wsmid:Identify/></s:Body></s:Envelope>",
+);
+
+/// Build a recursive SRV query for `_wsman._tcp.<domain>`.
+pub fn srv_query(domain: &str, id: u16) -> Result<Vec<u8>, PwshCoreError> {
+    let name = format!("_wsman._tcp.{}", domain.trim_end_matches('.'));
+    encode_srv_query(&name, id).ok_or(PwshCoreError::InvalidServerAddress(
+        "discovery domain is not a valid DNS name",
+    ))
+}
+
+/// Parse the reply to [`srv_query`], ordered by priority then descending weight.
+pub fn parse_srv_response(response: &[u8], id: u16) -> Result<Vec<SrvRecord>, PwshCoreError> {
+    parse_srv_answers(response, id, Phase::EndpointDiscovery)
+}
+
+/// What the listener reports about itself in an `IdentifyResponse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub protocol_version: String,
+    pub product_vendor: Option<String>,
+    pub product_version: Option<String>,
+}
+
+/// Outcome of probing a [`Candidate`], best first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Probe {
+    /// A WS-Management listener answered `Identify`.
+    Identified(Identity),
+    /// Something answered over HTTP but not with an `IdentifyResponse`
+    /// (e.g. a 401 from a listener refusing unauthenticated `Identify`).
+    Responded { status: u16 },
+    /// No connection, TLS failure or timeout.
+    Unreachable,
+    #[default]
+    NotProbed,
+}
+
+impl Probe {
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Identified(_) => 0,
+            Self::Responded { .. } => 1,
+            Self::NotProbed => 2,
+            Self::Unreachable => 3,
+        }
+    }
+
+    /// Classify the reply to [`identify_request`].
+    pub fn from_response(response: &HttpResponse) -> Self {
+        if response.status_code == 200
+            && let Ok(identity) = response.body.as_str().and_then(parse_identify_response)
+        {
+            return Self::Identified(identity);
+        }
+        Self::Responded {
+            status: response.status_code,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub host: String,
+    pub port: u16,
+    pub scheme: Scheme,
+    /// The SRV record that named this host, if any.
+    pub srv: Option<SrvRecord>,
+    pub probe: Probe,
+}
+
+impl Candidate {
+    fn new(host: &str, port: u16, srv: Option<&SrvRecord>) -> Self {
+        Self {
+            host: host.trim_end_matches('.').to_owned(),
+            port,
+            scheme: scheme_for_port(port),
+            srv: srv.cloned(),
+            probe: Probe::NotProbed,
+        }
+    }
+
+    /// `Identify` request for this endpoint.
+    pub fn identify_request(&self) -> Result<HttpRequest, PwshCoreError> {
+        Ok(identify_request(
+            ServerAddress::parse(&self.host)?,
+            self.port,
+            self.scheme,
+        ))
+    }
+}
+
+/// Endpoints worth probing: every SRV target on its advertised port plus the
+/// two default WinRM ports, or `domain` itself when there are no records.
+pub fn candidates(domain: &str, records: &[SrvRecord]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut push = |candidate: Candidate| {
+        if !candidates
+            .iter()
+            .any(|c| c.host.eq_ignore_ascii_case(&candidate.host) && c.port == candidate.port)
+        {
+            candidates.push(candidate);
+        }
+    };
+
+    for record in records {
+        for port in [record.port, HTTPS_PORT, HTTP_PORT] {
+            push(Candidate::new(&record.target, port, Some(record)));
+        }
+    }
+    if records.is_empty() {
+        for port in [HTTPS_PORT, HTTP_PORT] {
+            push(Candidate::new(domain, port, None));
+        }
+    }
+    candidates
+}
+
+/// Order `candidates` best first: by probe outcome, HTTPS before HTTP, then
+/// SRV priority and weight. Ties keep their discovery order.
+pub fn rank(candidates: &mut [Candidate]) {
+    candidates.sort_by(|a, b| {
+        a.probe
+            .rank()
+            .cmp(&b.probe.rank())
+            .then_with(|| (a.scheme == Scheme::Http).cmp(&(b.scheme == Scheme::Http)))
+            .then_with(|| srv_order(a).cmp(&srv_order(b)))
+    });
+}
+
+fn srv_order(candidate: &Candidate) -> (u16, std::cmp::Reverse<u16>) {
+    candidate
+        .srv
+        .as_ref()
+        .map_or((u16::MAX, std::cmp::Reverse(0)), |srv| {
+            (srv.priority, std::cmp::Reverse(srv.weight))
+        })
+}
+
+fn scheme_for_port(port: u16) -> Scheme {
+    match port {
+        HTTPS_PORT | 443 => Scheme::Https,
+        _ => Scheme::Http,
+    }
+}
+
+/// Unauthenticated WS-Management `Identify` POST to `server:port`.
+pub fn identify_request(server: ServerAddress, port: u16, scheme: Scheme) -> HttpRequest {
+    let mut builder = HttpBuilder::new(server, port, scheme);
+    builder
+        .headers
+        .push(("WSMANIDENTIFY".to_owned(), "unauthenticated".to_owned()));
+    builder.post(HttpBody::Xml(IDENTIFY_ENVELOPE.to_owned()))
+}
+
+/// Read an `IdentifyResponse`; anything else is an error.
+pub fn parse_identify_response(xml: &str) -> Result<Identity, PwshCoreError> {
+    let document = ironposh_xml::parser::parse(xml)?;
+    let response = document
+        .descendants()
+        .find(|node| node.has_tag_name((WSMAN_IDENTITY_NS, "IdentifyResponse")))
+        .ok_or_else(|| {
+            PwshCoreError::invalid_response(
+                Phase::EndpointDiscovery,
+                Cow::Borrowed("no IdentifyResponse in reply"),
+            )
+        })?;
+    let field = |name: &str| {
+        response
+            .children()
+            .find(|node| node.has_tag_name((WSMAN_IDENTITY_NS, name)))
+            .and_then(|node| node.text())
+            .map(|text| text.trim().to_owned())
+    };
+
+    Ok(Identity {
+        protocol_version: field("ProtocolVersion").ok_or_else(|| {
+            PwshCoreError::invalid_response(
+                Phase::EndpointDiscovery,
+                Cow::Borrowed("IdentifyResponse without ProtocolVersion"),
+            )
+        })?,
+        product_vendor: field("ProductVendor"),
+        product_version: field("ProductVersion"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTIFY_RESPONSE: &str = concat!(
+        r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" "#,
+        r#"xmlns:wsmid="http://schemas.dmtf.org/wbem/wsman/identity/1/wsmanidentity.xsd">"#,
+        "<s:Header/><s:Body><wsmid:IdentifyResponse>",
+        "<wsmid:ProtocolVersion>http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd</wsmid:ProtocolVersion>",
+        "<wsmid:ProductVendor>Microsoft Corporation</wsmid:ProductVendor>",
+        "<wsmid:ProductVersion>OS: 0.0.0 SP: 0.0 Stack: 3.0</wsmid:ProductVersion>",
+        "</wsmid:IdentifyResponse></s:Body></s:Envelope>",
+    );
+
+    fn srv(priority: u16, weight: u16, port: u16, target: &str) -> SrvRecord {
+        SrvRecord {
+            priority,
+            weight,
+            port,
+            target: target.to_owned(),
+        }
+    }
+
+    #[test]
+    fn query_encodes_the_wsman_srv_name() {
+        let query = srv_query("corp.example.", 9).unwrap();
+        assert_eq!(
+            &query[12..],
+            b"\x06_wsman\x04_tcp\x04corp\x07example\x00\x00\x21\x00\x01"
+        );
+        assert!(srv_query("corp..example", 9).is_err());
+    }
+
+    #[test]
+    fn identify_request_is_unauthenticated_soap() {
+        let request = identify_request(
+            ServerAddress::parse("web01.corp.example").unwrap(),
+            HTTPS_PORT,
+            Scheme::Https,
+        );
+        assert!(
+            request
+                .url
+                .starts_with("https://web01.corp.example:5986/wsman")
+        );
+        assert_eq!(request.header("WSMANIDENTIFY"), Some("unauthenticated"));
+        assert!(request.header("Authorization").is_none());
+        assert_eq!(request.body_bytes(), IDENTIFY_ENVELOPE.as_bytes());
+    }
+
+    #[test]
+    fn parses_identify_response() {
+        let identity = parse_identify_response(IDENTIFY_RESPONSE).unwrap();
+        assert_eq!(
+            identity.product_vendor.as_deref(),
+            Some("Microsoft Corporation")
+        );
+        assert_eq!(
+            identity.protocol_version,
+            "http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd"
+        );
+        assert!(parse_identify_response("<s:Envelope xmlns:s=\"x\"/>").is_err());
+    }
+
+    #[test]
+    fn candidates_cover_srv_targets_and_default_ports() {
+        let records = [
+            srv(0, 10, 5986, "web01.corp.example."),
+            srv(5, 0, 80, "web02.corp.example"),
+        ];
+        let found: Vec<_> = candidates("corp.example", &records)
+            .into_iter()
+            .map(|c| (c.host, c.port))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("web01.corp.example".to_owned(), 5986),
+                ("web01.corp.example".to_owned(), 5985),
+                ("web02.corp.example".to_owned(), 80),
+                ("web02.corp.example".to_owned(), 5986),
+                ("web02.corp.example".to_owned(), 5985),
+            ]
+        );
+
+        let fallback = candidates("corp.example", &[]);
+        assert_eq!(fallback.len(), 2);
+        assert_eq!(fallback[0].scheme, Scheme::Https);
+    }
+
+    #[test]
+    fn rank_prefers_identified_https_and_srv_priority() {
+        let records = [
+            srv(10, 0, 5985, "low.example"),
+            srv(0, 0, 5985, "high.example"),
+        ];
+        let mut list = candidates("example", &records);
+        for candidate in &mut list {
+            candidate.probe = match (candidate.host.as_str(), candidate.port) {
+                ("low.example", HTTPS_PORT) => Probe::Unreachable,
+                ("high.example", HTTPS_PORT) => Probe::Responded { status: 401 },
+                _ => Probe::Identified(parse_identify_response(IDENTIFY_RESPONSE).unwrap()),
+            };
+        }
+        rank(&mut list);
+
+        let order: Vec<_> = list.iter().map(|c| (c.host.as_str(), c.port)).collect();
+        assert_eq!(
+            order,
+            [
+                ("high.example", 5985),
+                ("low.example", 5985),
+                ("high.example", 5986),
+                ("low.example", 5986),
+            ]
+        );
+    }
+}
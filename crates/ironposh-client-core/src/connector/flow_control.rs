@@ -0,0 +1,285 @@
+//! Receive flow control: per-pipeline output budget and Receive concurrency cap.
+//!
+//! Keeps a firehose pipeline from monopolising the connection pool with
+//! back-to-back Receives; held-back streams are re-issued by `ActiveSession`
+//! once a Receive slot frees up or the consumer acknowledges output.
+
+use std::collections::HashMap;
+
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{connector::config::ReceiveFlowConfig, runspace_pool::DesiredStream};
+
+/// Receive-side flow control state owned by `ActiveSession`.
+///
+/// Tracks, per pipeline, how many output bytes have been handed to the consumer
+/// without being acknowledged, and holds back the `DesiredStream`s that could not
+/// be polled yet (pipeline over budget, or too many Receives already in flight).
+/// Shell-level streams (no `CommandId`) are never throttled by the byte budget:
+/// they carry runspace-pool host calls and state changes that must keep flowing.
+#[derive(Debug)]
+pub(super) struct ReceiveFlowControl {
+    config: ReceiveFlowConfig,
+    pending_output_bytes: HashMap<Uuid, usize>,
+    deferred: Vec<DesiredStream>,
+}
+
+impl ReceiveFlowControl {
+    pub(super) fn new(config: ReceiveFlowConfig) -> Self {
+        Self {
+            config,
+            pending_output_bytes: HashMap::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    /// Record `bytes` of output delivered to the consumer for `pipeline`.
+    pub(super) fn note_output(&mut self, pipeline: Uuid, bytes: usize) {
+        if self.config.max_pending_output_bytes.is_none() {
+            return;
+        }
+        let pending = self.pending_output_bytes.entry(pipeline).or_default();
+        *pending = pending.saturating_add(bytes);
+    }
+
+    /// Release `bytes` of previously delivered output. Returns `true` when this
+    /// acknowledgement brought the pipeline back under its budget.
+    pub(super) fn acknowledge(&mut self, pipeline: Uuid, bytes: usize) -> bool {
+        let was_throttled = self.is_throttled(pipeline);
+        if let Some(pending) = self.pending_output_bytes.get_mut(&pipeline) {
+            *pending = pending.saturating_sub(bytes);
+        }
+        was_throttled && !self.is_throttled(pipeline)
+    }
+
+    pub(super) fn is_throttled(&self, pipeline: Uuid) -> bool {
+        let Some(limit) = self.config.max_pending_output_bytes else {
+            return false;
+        };
+        self.pending_output_bytes
+            .get(&pipeline)
+            .is_some_and(|pending| *pending >= limit)
+    }
+
+    fn has_receive_capacity(&self, in_flight: usize) -> bool {
+        self.config
+            .max_concurrent_receives
+            .is_none_or(|max| in_flight < max)
+    }
+
+    /// Filter the streams a Receive is about to be issued for.
+    ///
+    /// Streams of throttled pipelines are held back; when `in_flight` Receives
+    /// already reach the configured cap, every stream is held back. Held-back
+    /// streams are released later by [`Self::take_ready`].
+    pub(super) fn admit(
+        &mut self,
+        desired_streams: Vec<DesiredStream>,
+        in_flight: usize,
+    ) -> Vec<DesiredStream> {
+        if !self.has_receive_capacity(in_flight) {
+            debug!(
+                in_flight,
+                streams = ?desired_streams,
+                "receive capacity reached, deferring streams"
+            );
+            self.defer(desired_streams);
+            return Vec::new();
+        }
+
+        let throttled_stream =
+            |stream: &DesiredStream| stream.command_id().is_some_and(|id| self.is_throttled(*id));
+        let (throttled, admitted): (Vec<_>, Vec<_>) =
+            desired_streams.into_iter().partition(throttled_stream);
+        if !throttled.is_empty() {
+            debug!(streams = ?throttled, "pipeline output budget exceeded, deferring streams");
+            self.defer(throttled);
+        }
+        admitted
+    }
+
+    /// Deferred streams that may now be polled, given `in_flight` outstanding Receives.
+    ///
+    /// A Receive carries the streams of a single command (or of the shell), so
+    /// the ready streams come back grouped: one batch per Receive to issue. Each
+    /// batch takes a Receive slot, so later batches stay deferred once the cap
+    /// is reached.
+    pub(super) fn take_ready(&mut self, mut in_flight: usize) -> Vec<Vec<DesiredStream>> {
+        if self.deferred.is_empty() || !self.has_receive_capacity(in_flight) {
+            return Vec::new();
+        }
+        let mut batches = Vec::new();
+        for batch in group_by_command(std::mem::take(&mut self.deferred)) {
+            let admitted = self.admit(batch, in_flight);
+            if !admitted.is_empty() {
+                in_flight += 1;
+                batches.push(admitted);
+            }
+        }
+        batches
+    }
+
+    /// Deferred streams of `pipeline` that may now be polled, given `in_flight`
+    /// outstanding Receives. Streams of other commands stay deferred.
+    pub(super) fn take_ready_for(
+        &mut self,
+        pipeline: Uuid,
+        in_flight: usize,
+    ) -> Vec<DesiredStream> {
+        if !self.has_receive_capacity(in_flight) {
+            return Vec::new();
+        }
+        let (ready, deferred): (Vec<_>, Vec<_>) = std::mem::take(&mut self.deferred)
+            .into_iter()
+            .partition(|stream| stream.command_id() == Some(&pipeline));
+        self.deferred = deferred;
+        self.admit(ready, in_flight)
+    }
+
+    /// Drop all flow state for a finished pipeline.
+    pub(super) fn forget(&mut self, pipeline: Uuid) {
+        self.pending_output_bytes.remove(&pipeline);
+        self.deferred
+            .retain(|stream| stream.command_id() != Some(&pipeline));
+    }
+
+    fn defer(&mut self, streams: Vec<DesiredStream>) {
+        for stream in streams {
+            if !self.deferred.contains(&stream) {
+                self.deferred.push(stream);
+            }
+        }
+    }
+}
+
+/// Split `streams` by command, keeping the order in which commands first appear.
+fn group_by_command(streams: Vec<DesiredStream>) -> Vec<Vec<DesiredStream>> {
+    let mut order = Vec::new();
+    let mut groups: HashMap<Option<Uuid>, Vec<DesiredStream>> = HashMap::new();
+    for stream in streams {
+        let command = stream.command_id().copied();
+        groups
+            .entry(command)
+            .or_insert_with(|| {
+                order.push(command);
+                Vec::new()
+            })
+            .push(stream);
+    }
+    order
+        .into_iter()
+        .filter_map(|command| groups.remove(&command))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(max_receives: Option<usize>, max_bytes: Option<usize>) -> ReceiveFlowControl {
+        ReceiveFlowControl::new(ReceiveFlowConfig {
+            max_concurrent_receives: max_receives,
+            max_pending_output_bytes: max_bytes,
+        })
+    }
+
+    #[test]
+    fn unlimited_config_admits_everything() {
+        let mut flow = limited(None, None);
+        let id = Uuid::new_v4();
+        flow.note_output(id, usize::MAX);
+        let streams = vec![DesiredStream::stdout_for_command(id)];
+        assert_eq!(flow.admit(streams.clone(), 42), streams);
+    }
+
+    #[test]
+    fn throttled_pipeline_is_deferred_until_acknowledged() {
+        let mut flow = limited(None, Some(100));
+        let busy = Uuid::new_v4();
+        let quiet = Uuid::new_v4();
+        flow.note_output(busy, 150);
+
+        let admitted = flow.admit(
+            vec![
+                DesiredStream::stdout_for_command(busy),
+                DesiredStream::stdout_for_command(quiet),
+            ],
+            0,
+        );
+        assert_eq!(admitted, vec![DesiredStream::stdout_for_command(quiet)]);
+        assert!(flow.take_ready(0).is_empty());
+
+        assert!(flow.acknowledge(busy, 100));
+        assert_eq!(
+            flow.take_ready_for(busy, 0),
+            vec![DesiredStream::stdout_for_command(busy)]
+        );
+        assert!(flow.take_ready(0).is_empty());
+    }
+
+    #[test]
+    fn shell_stream_is_never_throttled_by_bytes() {
+        let mut flow = limited(None, Some(1));
+        let streams = DesiredStream::runspace_pool_streams();
+        assert_eq!(flow.admit(streams.clone(), 0), streams);
+    }
+
+    #[test]
+    fn receive_cap_defers_all_streams() {
+        let mut flow = limited(Some(1), None);
+        let streams = DesiredStream::runspace_pool_streams();
+        assert!(flow.admit(streams.clone(), 1).is_empty());
+        assert!(flow.take_ready(1).is_empty());
+        assert_eq!(flow.take_ready(0), vec![streams]);
+    }
+
+    #[test]
+    fn deferred_streams_are_released_one_receive_per_command() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let streams = || {
+            let mut streams = DesiredStream::runspace_pool_streams();
+            streams.push(DesiredStream::stdout_for_command(first));
+            streams.push(DesiredStream::stdout_for_command(second));
+            streams
+        };
+
+        let mut flow = limited(Some(3), None);
+        assert!(flow.admit(streams(), 3).is_empty());
+        assert_eq!(
+            flow.take_ready(0),
+            vec![
+                DesiredStream::runspace_pool_streams(),
+                vec![DesiredStream::stdout_for_command(first)],
+                vec![DesiredStream::stdout_for_command(second)],
+            ]
+        );
+
+        // A single free slot releases one command; the rest wait for the next.
+        let mut flow = limited(Some(1), None);
+        assert!(flow.admit(streams(), 1).is_empty());
+        assert_eq!(
+            flow.take_ready(0),
+            vec![DesiredStream::runspace_pool_streams()]
+        );
+        assert_eq!(
+            flow.take_ready(0),
+            vec![vec![DesiredStream::stdout_for_command(first)]]
+        );
+    }
+
+    #[test]
+    fn forget_drops_deferred_streams() {
+        let mut flow = limited(None, Some(10));
+        let id = Uuid::new_v4();
+        flow.note_output(id, 10);
+        assert!(
+            flow.admit(vec![DesiredStream::stdout_for_command(id)], 0)
+                .is_empty()
+        );
+        flow.forget(id);
+        assert!(!flow.is_throttled(id));
+        assert!(flow.take_ready(0).is_empty());
+    }
+}
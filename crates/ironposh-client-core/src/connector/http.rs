@@ -1,41 +1,142 @@
-use std::{fmt::Display, net::IpAddr};
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6},
+    time::Duration,
+};
 
 use crate::connector::connection_pool::{AuthenticatedHttpChannel, ConnectionId};
 
 pub const ENCRYPTION_BOUNDARY: &str = "Encrypted Boundary";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ServerAddress {
     Ip(IpAddr),
+    /// Link-local IPv6 with its zone (`fe80::1%eth0`, `fe80::1%3`).
+    ScopedIpv6 {
+        address: Ipv6Addr,
+        zone: String,
+    },
     Domain(String),
 }
 
 impl ServerAddress {
+    /// Accepts host names, IPv4, IPv6 with or without brackets (`[::1]`) and
+    /// scoped IPv6 (`fe80::1%eth0`, or `%25` as in URLs).
     pub fn parse(value: &str) -> Result<Self, crate::PwshCoreError> {
-        value.parse::<IpAddr>().map_or_else(
-            |_| {
-                if value.trim().is_empty() {
-                    Err(crate::PwshCoreError::InvalidServerAddress(
-                        "server address cannot be empty",
-                    ))
-                } else {
-                    Ok(Self::Domain(value.to_string()))
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(crate::PwshCoreError::InvalidServerAddress(
+                "server address cannot be empty",
+            ));
+        }
+
+        let unbracketed = match value.strip_prefix('[') {
+            Some(inner) => {
+                inner
+                    .strip_suffix(']')
+                    .ok_or(crate::PwshCoreError::InvalidServerAddress(
+                        "unterminated IPv6 bracket",
+                    ))?
+            }
+            None => value,
+        };
+
+        if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+            return Ok(Self::Ip(ip));
+        }
+
+        if let Some((address, zone)) = unbracketed.split_once('%') {
+            let zone = zone
+                .strip_prefix("25")
+                .filter(|z| !z.is_empty())
+                .unwrap_or(zone);
+            if let Ok(address) = address.parse::<Ipv6Addr>() {
+                if zone.is_empty() {
+                    return Err(crate::PwshCoreError::InvalidServerAddress(
+                        "IPv6 zone cannot be empty",
+                    ));
                 }
-            },
-            |ip| Ok(Self::Ip(ip)),
-        )
+                return Ok(Self::ScopedIpv6 {
+                    address,
+                    zone: zone.to_owned(),
+                });
+            }
+        }
+
+        if unbracketed.len() != value.len() || unbracketed.contains(':') {
+            return Err(crate::PwshCoreError::InvalidServerAddress(
+                "not a valid IPv6 address",
+            ));
+        }
+
+        Ok(Self::Domain(value.to_owned()))
+    }
+
+    /// The host as it goes in a URL authority or `Host` header: IPv6 is
+    /// bracketed. The zone is left out, it only means something to the local
+    /// stack (and the `url` crate rejects it).
+    pub fn url_host(&self) -> String {
+        match self {
+            Self::Ip(IpAddr::V6(ip)) | Self::ScopedIpv6 { address: ip, .. } => format!("[{ip}]"),
+            Self::Ip(ip) => ip.to_string(),
+            Self::Domain(domain) => domain.clone(),
+        }
+    }
+
+    /// The host as it goes in an SPN (`HTTP/<host>`); bracketed like in URLs
+    /// so the colons of an IPv6 address aren't read as a port separator.
+    pub fn spn_host(&self) -> String {
+        self.url_host()
+    }
+
+    /// The socket to connect to for an IP literal; a numeric zone becomes the
+    /// IPv6 scope id. `None` for host names, which need resolving.
+    pub fn socket_addr(&self, port: u16) -> Option<SocketAddr> {
+        match self {
+            Self::Ip(ip) => Some(SocketAddr::new(*ip, port)),
+            Self::ScopedIpv6 { address, zone } => Some(SocketAddr::V6(SocketAddrV6::new(
+                *address,
+                port,
+                0,
+                zone.parse().unwrap_or(0),
+            ))),
+            Self::Domain(_) => None,
+        }
     }
 }
 
 impl Display for ServerAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::Ip(IpAddr::V6(ip)) => write!(f, "[{ip}]"),
             Self::Ip(ip) => write!(f, "{ip}"),
+            Self::ScopedIpv6 { address, zone } => write!(f, "[{address}%{zone}]"),
             Self::Domain(domain) => write!(f, "{domain}"),
         }
     }
 }
 
+/// Which address family to try first when a host name resolves to both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressPreference {
+    /// Keep the resolver's order.
+    #[default]
+    System,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressPreference {
+    /// Stable-sort `addresses` so the preferred family comes first.
+    pub fn order(self, addresses: &mut [SocketAddr]) {
+        match self {
+            Self::System => {}
+            Self::Ipv4 => addresses.sort_by_key(SocketAddr::is_ipv6),
+            Self::Ipv6 => addresses.sort_by_key(SocketAddr::is_ipv4),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Method {
     Get,
@@ -90,6 +191,15 @@ impl HttpBody {
         }
     }
 
+    /// The raw body bytes, whatever the kind of body.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Xml(content) | Self::Text(content) => content.as_bytes(),
+            Self::Encrypted(content) => content,
+            Self::None => &[],
+        }
+    }
+
     /// Returns the body content as a string reference
     pub fn as_str(&self) -> Result<&str, crate::PwshCoreError> {
         match self {
@@ -115,6 +225,9 @@ pub struct HttpRequest {
     pub headers: Vec<(String, String)>,
     pub body: Option<HttpBody>,
     pub cookie: Option<String>,
+    /// Connect to this address rather than resolving the URL host; the host
+    /// name is kept for `Host`, TLS SNI and certificate checks.
+    pub connect_to: Option<IpAddr>,
 }
 
 impl HttpRequest {
@@ -122,6 +235,19 @@ impl HttpRequest {
         self.headers.extend(headers);
         self
     }
+
+    /// Value of the first header called `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body bytes to put on the wire; empty without a body.
+    pub fn body_bytes(&self) -> &[u8] {
+        self.body.as_ref().map_or(&[], HttpBody::as_bytes)
+    }
 }
 
 pub trait Body {
@@ -183,6 +309,91 @@ pub struct HttpResponse {
     pub peer_cert_der: Option<Vec<u8>>,
 }
 
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// `Retry-After` in delta-seconds; the HTTP-date form is not honored.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.header("Retry-After")?
+            .trim()
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+}
+
+/// A status answering a request on an established session without a SOAP
+/// reply, or a quota fault: the server never processed the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interruption {
+    /// 401 or 419: the credentials or the security context stopped being
+    /// accepted.
+    AuthExpired { status: u16 },
+    /// 503, with the server's `Retry-After` when it sent one.
+    ServiceUnavailable { retry_after: Option<Duration> },
+    /// 502 or 504 from a proxy or gateway in front of the listener.
+    GatewayTimeout { status: u16 },
+    /// A `w:QuotaLimit` fault (HTTP 500): a per-user WinRM quota is
+    /// exhausted until other shells or operations finish.
+    QuotaExceeded {
+        which: crate::Quota,
+        limit: Option<u32>,
+    },
+}
+
+impl Interruption {
+    pub fn classify(response: &HttpResponse) -> Option<Self> {
+        match response.status_code {
+            status @ (401 | 419) => Some(Self::AuthExpired { status }),
+            503 => Some(Self::ServiceUnavailable {
+                retry_after: response.retry_after(),
+            }),
+            status @ (502 | 504) => Some(Self::GatewayTimeout { status }),
+            _ => None,
+        }
+    }
+
+    pub fn status(self) -> u16 {
+        match self {
+            Self::AuthExpired { status } | Self::GatewayTimeout { status } => status,
+            Self::ServiceUnavailable { .. } => 503,
+            Self::QuotaExceeded { .. } => 500,
+        }
+    }
+
+    /// Whether sending the same request again may succeed without new
+    /// credentials.
+    pub fn is_transient(self) -> bool {
+        !matches!(self, Self::AuthExpired { .. })
+    }
+
+    pub fn into_error(self, phase: crate::Phase) -> crate::PwshCoreError {
+        let detail = match self {
+            Self::AuthExpired { .. } => "session authentication expired",
+            Self::ServiceUnavailable { .. } => "service unavailable",
+            Self::GatewayTimeout { .. } => "gateway did not reach the server",
+            Self::QuotaExceeded { which, limit } => {
+                return crate::PwshCoreError::QuotaExceeded {
+                    phase,
+                    which,
+                    limit,
+                    reason: format!("{which} exceeded"),
+                };
+            }
+        };
+        crate::PwshCoreError::HttpStatus {
+            phase,
+            status: self.status(),
+            detail,
+        }
+    }
+}
+
 /// A targeted HTTP response that includes both the response data and the connection it came from.
 /// This struct is opaque and immutable, ensuring type safety for response handling.
 #[derive(Debug)]
@@ -231,6 +442,7 @@ pub struct HttpBuilder {
     // pub(crate) authentication: crate::connector::Authentication,
     pub(crate) cookie: Option<String>,
     pub(crate) headers: Vec<(String, String)>,
+    pub(crate) connect_to: Option<IpAddr>,
 }
 
 impl HttpBuilder {
@@ -241,6 +453,7 @@ impl HttpBuilder {
             scheme,
             headers: vec![],
             cookie: None,
+            connect_to: None,
         }
     }
 
@@ -248,6 +461,10 @@ impl HttpBuilder {
         self.cookie = Some(cookie);
     }
 
+    pub fn with_connect_to(&mut self, address: IpAddr) {
+        self.connect_to = Some(address);
+    }
+
     pub fn with_auth_header(&mut self, header: String) -> &mut Self {
         self.headers.push(("Authorization".to_string(), header));
         self
@@ -259,22 +476,17 @@ impl HttpBuilder {
             crate::connector::Scheme::Https => "https",
         };
 
-        let server_str = match &self.server {
-            ServerAddress::Ip(ip) => ip.to_string(),
-            ServerAddress::Domain(domain) => domain.clone(),
-        };
-
         format!(
             "{}://{}:{}{}?PSVersion=7.4.11",
-            scheme_str, server_str, self.port, "/wsman"
+            scheme_str,
+            self.server.url_host(),
+            self.port,
+            "/wsman"
         )
     }
 
     fn build_host_header(&self) -> String {
-        match &self.server {
-            ServerAddress::Ip(ip) => format!("{}:{}", ip, self.port),
-            ServerAddress::Domain(domain) => format!("{}:{}", domain, self.port),
-        }
+        format!("{}:{}", self.server.url_host(), self.port)
     }
 
     fn build_headers(&mut self, body: Option<&HttpBody>) -> Vec<(String, String)> {
@@ -304,6 +516,121 @@ impl HttpBuilder {
             headers: self.build_headers(Some(&body)),
             body: Some(body),
             cookie: self.cookie.clone(),
+            connect_to: self.connect_to,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_header_and_body_accessors() {
+        let mut builder = HttpBuilder::new(
+            ServerAddress::Domain("winrm.example".to_owned()),
+            5985,
+            crate::connector::Scheme::Http,
+        );
+        let request = builder.post(HttpBody::Xml("<s:Envelope/>".to_owned()));
+
+        assert_eq!(request.header("host"), Some("winrm.example:5985"));
+        assert_eq!(request.header("CONTENT-LENGTH"), Some("13"));
+        assert_eq!(request.header("Cookie"), None);
+        assert_eq!(request.body_bytes(), b"<s:Envelope/>");
+        assert!(HttpBody::None.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn ipv6_servers_are_bracketed_in_urls() {
+        let server = ServerAddress::parse("[2001:db8::5]").unwrap();
+        assert_eq!(server, ServerAddress::parse("2001:db8::5").unwrap());
+
+        let mut builder = HttpBuilder::new(server, 5986, crate::connector::Scheme::Https);
+        let request = builder.post(HttpBody::None);
+        assert_eq!(
+            request.url,
+            "https://[2001:db8::5]:5986/wsman?PSVersion=7.4.11"
+        );
+        assert_eq!(request.header("Host"), Some("[2001:db8::5]:5986"));
+    }
+
+    #[test]
+    fn parses_scoped_ipv6_and_rejects_malformed_literals() {
+        let scoped = ServerAddress::parse("fe80::1%253").unwrap();
+        assert_eq!(
+            scoped,
+            ServerAddress::ScopedIpv6 {
+                address: "fe80::1".parse().unwrap(),
+                zone: "3".to_owned(),
+            }
+        );
+        assert_eq!(scoped.url_host(), "[fe80::1]");
+        assert_eq!(scoped.to_string(), "[fe80::1%3]");
+        match scoped.socket_addr(5985) {
+            Some(SocketAddr::V6(socket)) => assert_eq!(socket.scope_id(), 3),
+            other => panic!("expected a scoped IPv6 socket, got {other:?}"),
+        }
+        assert!(matches!(
+            ServerAddress::parse("fe80::1%eth0").unwrap(),
+            ServerAddress::ScopedIpv6 { zone, .. } if zone == "eth0"
+        ));
+
+        assert!(ServerAddress::parse("[::1").is_err());
+        assert!(ServerAddress::parse("[winrm.example]").is_err());
+        assert!(ServerAddress::parse("2001:db8::zz").is_err());
+        assert_eq!(
+            ServerAddress::parse(" winrm.example ").unwrap().spn_host(),
+            "winrm.example"
+        );
+    }
+
+    #[test]
+    fn address_preference_orders_families() {
+        let v4: SocketAddr = "192.0.2.1:5985".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:5985".parse().unwrap();
+
+        let mut addresses = [v6, v4];
+        AddressPreference::Ipv4.order(&mut addresses);
+        assert_eq!(addresses, [v4, v6]);
+        AddressPreference::Ipv6.order(&mut addresses);
+        assert_eq!(addresses, [v6, v4]);
+        AddressPreference::System.order(&mut addresses);
+        assert_eq!(addresses, [v6, v4]);
+    }
+
+    #[test]
+    fn classifies_interrupting_statuses() {
+        let response = |status_code: u16, headers: &[(&str, &str)]| HttpResponse {
+            status_code,
+            headers: headers
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            body: HttpBody::None,
+            peer_cert_der: None,
+        };
+
+        assert_eq!(
+            Interruption::classify(&response(503, &[("retry-after", " 7")])),
+            Some(Interruption::ServiceUnavailable {
+                retry_after: Some(Duration::from_secs(7))
+            })
+        );
+        assert_eq!(
+            Interruption::classify(&response(
+                503,
+                &[("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT")]
+            )),
+            Some(Interruption::ServiceUnavailable { retry_after: None })
+        );
+        assert_eq!(
+            Interruption::classify(&response(419, &[])),
+            Some(Interruption::AuthExpired { status: 419 })
+        );
+        assert!(
+            Interruption::classify(&response(504, &[])).is_some_and(Interruption::is_transient)
+        );
+        assert_eq!(Interruption::classify(&response(500, &[])), None);
+    }
+}
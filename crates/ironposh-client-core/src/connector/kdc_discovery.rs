@@ -0,0 +1,326 @@
+//! KDC discovery through DNS SRV records (RFC 4120 §7.2.3.2), sans-IO.
+//!
+//! Frontends send [`srv_query`] to one of the system nameservers (see
+//! [`nameservers_from_resolv_conf`]) over UDP, feed the reply to
+//! [`parse_srv_response`] and hand the best record's [`SrvRecord::kdc_url`] to
+//! [`KdcEndpoint::Direct`](super::config::KdcEndpoint::Direct).
+
+use std::{borrow::Cow, net::IpAddr};
+
+use url::Url;
+
+use crate::PwshCoreError;
+
+/// DNS resource record type for SRV (RFC 2782).
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_TRUNCATED: u16 = 0x0200;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+const RCODE_NAME_ERROR: u16 = 3;
+/// Upper bound on compression pointers followed while reading one name.
+const MAX_NAME_JUMPS: usize = 16;
+
+/// Transport advertised by a `_kerberos` SRV record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdcTransport {
+    Tcp,
+    Udp,
+}
+
+impl KdcTransport {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Tcp => "_tcp",
+            Self::Udp => "_udp",
+        }
+    }
+
+    fn scheme(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+impl SrvRecord {
+    /// KDC URL in the form sspi expects, e.g. `tcp://dc01.example.com:88`.
+    pub fn kdc_url(&self, transport: KdcTransport) -> Result<Url, PwshCoreError> {
+        let url = format!("{}://{}:{}", transport.scheme(), self.target, self.port);
+        Url::parse(&url).map_err(|e| {
+            PwshCoreError::invalid_response(
+                crate::Phase::KdcDiscovery,
+                Cow::Owned(format!("invalid KDC SRV target {}: {e}", self.target)),
+            )
+        })
+    }
+}
+
+/// Build a recursive SRV query for `_kerberos._<transport>.<realm>`.
+pub fn srv_query(realm: &str, transport: KdcTransport, id: u16) -> Result<Vec<u8>, PwshCoreError> {
+    let name = format!(
+        "_kerberos.{}.{}",
+        transport.label(),
+        realm.trim_end_matches('.')
+    );
+    encode_srv_query(&name, id).ok_or(PwshCoreError::InvalidState(
+        "Kerberos realm is not a valid DNS name",
+    ))
+}
+
+/// SRV query for an arbitrary owner name; `None` if a label is empty or
+/// longer than 63 bytes.
+pub(super) fn encode_srv_query(name: &str, id: u16) -> Option<Vec<u8>> {
+    let mut query = Vec::with_capacity(18 + name.len());
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&FLAG_RECURSION_DESIRED.to_be_bytes());
+    // QDCOUNT = 1; ANCOUNT, NSCOUNT, ARCOUNT = 0.
+    query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        let len = u8::try_from(label.len())
+            .ok()
+            .filter(|len| (1..=63).contains(len))?;
+        query.push(len);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&TYPE_SRV.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Some(query)
+}
+
+/// Parse the reply to [`srv_query`]. Records come back ordered by priority,
+/// then by descending weight; a name error yields no records.
+pub fn parse_srv_response(response: &[u8], id: u16) -> Result<Vec<SrvRecord>, PwshCoreError> {
+    parse_srv_answers(response, id, crate::Phase::KdcDiscovery)
+}
+
+/// [`parse_srv_response`] with errors attributed to `phase`.
+pub(super) fn parse_srv_answers(
+    response: &[u8],
+    id: u16,
+    phase: crate::Phase,
+) -> Result<Vec<SrvRecord>, PwshCoreError> {
+    let malformed = |what: &str| {
+        PwshCoreError::invalid_response(
+            phase,
+            Cow::Owned(format!("malformed DNS response: {what}")),
+        )
+    };
+
+    if response.len() < 12 {
+        return Err(malformed("short header"));
+    }
+    if read_u16(response, 0) != Some(id) {
+        return Err(malformed("transaction id mismatch"));
+    }
+    let flags = read_u16(response, 2).unwrap_or_default();
+    if flags & FLAG_RESPONSE == 0 {
+        return Err(malformed("not a response"));
+    }
+    if flags & FLAG_TRUNCATED != 0 {
+        return Err(PwshCoreError::invalid_response(
+            phase,
+            Cow::Borrowed("DNS response truncated"),
+        ));
+    }
+    match flags & 0x000F {
+        0 => {}
+        RCODE_NAME_ERROR => return Ok(Vec::new()),
+        rcode => {
+            return Err(PwshCoreError::invalid_response(
+                phase,
+                Cow::Owned(format!("DNS server returned rcode {rcode}")),
+            ));
+        }
+    }
+
+    let question_count = read_u16(response, 4).unwrap_or_default();
+    let answer_count = read_u16(response, 6).unwrap_or_default();
+
+    let mut pos = 12;
+    for _ in 0..question_count {
+        let (_, next) = read_name(response, pos).ok_or_else(|| malformed("question name"))?;
+        pos = next + 4;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answer_count {
+        let (_, next) = read_name(response, pos).ok_or_else(|| malformed("answer name"))?;
+        let record_type = read_u16(response, next).ok_or_else(|| malformed("answer type"))?;
+        let data_len =
+            usize::from(read_u16(response, next + 8).ok_or_else(|| malformed("answer length"))?);
+        let data = next + 10;
+        if response.len() < data + data_len {
+            return Err(malformed("answer data"));
+        }
+        pos = data + data_len;
+
+        if record_type != TYPE_SRV {
+            continue;
+        }
+        let (target, _) = read_name(response, data + 6).ok_or_else(|| malformed("SRV target"))?;
+        // A target of "." means the service is explicitly unavailable.
+        if target.is_empty() {
+            continue;
+        }
+        records.push(SrvRecord {
+            priority: read_u16(response, data).ok_or_else(|| malformed("SRV priority"))?,
+            weight: read_u16(response, data + 2).ok_or_else(|| malformed("SRV weight"))?,
+            port: read_u16(response, data + 4).ok_or_else(|| malformed("SRV port"))?,
+            target,
+        });
+    }
+
+    records.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| b.weight.cmp(&a.weight))
+    });
+    Ok(records)
+}
+
+/// Nameservers listed in a `resolv.conf`, in file order.
+pub fn nameservers_from_resolv_conf(contents: &str) -> Vec<IpAddr> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_ascii_whitespace();
+            if fields.next()? != "nameserver" {
+                return None;
+            }
+            // Drop a link-local zone (`fe80::1%eth0`); `IpAddr` cannot carry it.
+            fields.next()?.split('%').next()?.parse().ok()
+        })
+        .collect()
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    let pair = bytes.get(pos..pos + 2)?;
+    Some(u16::from_be_bytes([pair[0], pair[1]]))
+}
+
+/// Read a (possibly compressed) domain name at `pos`. Returns the dotted name
+/// without the trailing dot and the offset just past the name in place.
+fn read_name(message: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *message.get(pos)?;
+        match len {
+            0 => {
+                let end = end.unwrap_or(pos + 1);
+                return Some((labels.join("."), end));
+            }
+            len if len & 0xC0 == 0xC0 => {
+                jumps += 1;
+                if jumps > MAX_NAME_JUMPS {
+                    return None;
+                }
+                let pointer = read_u16(message, pos)? & 0x3FFF;
+                end.get_or_insert(pos + 2);
+                pos = usize::from(pointer);
+            }
+            len if len & 0xC0 == 0 => {
+                let label = message.get(pos + 1..pos + 1 + usize::from(len))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + usize::from(len);
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reply to `srv_query("EXAMPLE.COM", Tcp, 0x1234)` with two SRV answers; the
+    /// second target reuses the first via a compression pointer.
+    fn srv_reply() -> Vec<u8> {
+        let mut reply = srv_query("EXAMPLE.COM", KdcTransport::Tcp, 0x1234).unwrap();
+        // Answer header (pointer, type, class, ttl, rdlength) plus SRV fixed fields.
+        let first_target = reply.len() + 18;
+        reply[2..4].copy_from_slice(&(FLAG_RESPONSE | FLAG_RECURSION_DESIRED).to_be_bytes());
+        reply[6..8].copy_from_slice(&2u16.to_be_bytes());
+
+        let mut answer = |priority: u16, weight: u16, target: &[u8]| {
+            reply.extend_from_slice(&[0xC0, 12]);
+            reply.extend_from_slice(&TYPE_SRV.to_be_bytes());
+            reply.extend_from_slice(&CLASS_IN.to_be_bytes());
+            reply.extend_from_slice(&600u32.to_be_bytes());
+            reply.extend_from_slice(&u16::try_from(6 + target.len()).unwrap().to_be_bytes());
+            reply.extend_from_slice(&priority.to_be_bytes());
+            reply.extend_from_slice(&weight.to_be_bytes());
+            reply.extend_from_slice(&88u16.to_be_bytes());
+            reply.extend_from_slice(target);
+        };
+        answer(10, 0, b"\x04dc02\x07EXAMPLE\x03COM\x00");
+        // "dc01" followed by a pointer to "EXAMPLE.COM" inside the first target.
+        let suffix = u16::try_from(first_target + 5).unwrap() | 0xC000;
+        let mut target = b"\x04dc01".to_vec();
+        target.extend_from_slice(&suffix.to_be_bytes());
+        answer(0, 100, &target);
+        reply
+    }
+
+    #[test]
+    fn query_encodes_the_kerberos_srv_name() {
+        let query = srv_query("example.com.", KdcTransport::Udp, 7).unwrap();
+        assert_eq!(&query[..2], &7u16.to_be_bytes());
+        assert_eq!(
+            &query[12..],
+            b"\x09_kerberos\x04_udp\x07example\x03com\x00\x00\x21\x00\x01"
+        );
+
+        assert!(srv_query("bad..realm", KdcTransport::Tcp, 0).is_err());
+    }
+
+    #[test]
+    fn parses_and_orders_srv_answers() {
+        let records = parse_srv_response(&srv_reply(), 0x1234).unwrap();
+        let targets: Vec<_> = records.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(targets, ["dc01.EXAMPLE.COM", "dc02.EXAMPLE.COM"]);
+        assert_eq!(
+            records[0].kdc_url(KdcTransport::Tcp).unwrap().as_str(),
+            "tcp://dc01.EXAMPLE.COM:88"
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_or_truncated_replies() {
+        let reply = srv_reply();
+        assert!(parse_srv_response(&reply, 0x4321).is_err());
+
+        let mut truncated = reply.clone();
+        truncated[2] |= 0x02;
+        assert!(parse_srv_response(&truncated, 0x1234).is_err());
+
+        let mut name_error = reply;
+        name_error[3] |= 0x03;
+        assert_eq!(parse_srv_response(&name_error, 0x1234).unwrap(), []);
+    }
+
+    #[test]
+    fn reads_nameservers_from_resolv_conf() {
+        let conf = "# generated\nsearch example.com\nnameserver 10.0.0.53\nnameserver fe80::1%eth0\nnameserver bogus\n";
+        assert_eq!(
+            nameservers_from_resolv_conf(conf),
+            [
+                "10.0.0.53".parse::<IpAddr>().unwrap(),
+                "fe80::1".parse().unwrap()
+            ]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use std::{fmt::Debug, sync::Arc};
 
-use ironposh_psrp::HostInfo;
-use ironposh_winrm::ws_management::WsMan;
+use ironposh_psrp::{ApplicationArguments, Defragmenter, HostInfo, TimeZone, ps_value::PsValue};
+use ironposh_winrm::ws_management::{IdGenerator, RandomIds, WsMan};
 
 // I'm lasy for now, just re-export from sspi
 pub use sspi::{generator::NetworkRequest, network_client::NetworkProtocol};
@@ -12,8 +12,8 @@ use crate::{
     connector::{
         auth_sequence::AuthSequenceConfig,
         config::AuthenticatorConfig,
-        connection_pool::{ConnectionPool, ConnectionPoolAccept, ConnectionPoolConfig, TrySend},
-        http::{HttpResponseTargeted, ServerAddress},
+        connection_pool::{ConnectionPool, ConnectionPoolAccept, ConnectionPoolConfig},
+        http::ServerAddress,
     },
     runspace_pool::{
         DesiredStream, ExpectShellConnected, ExpectShellCreated, RunspacePool, RunspacePoolCreator,
@@ -21,14 +21,38 @@ use crate::{
     },
 };
 
-pub use active_session::{ActiveSession, ActiveSessionOutput, UserOperation};
+pub use active_session::{
+    ActiveSession, ActiveSessionOutput, SessionInput, SessionOutput, SessionStateMachine,
+    UserOperation,
+};
+pub use connection_pool::{ConnectionId, TrySend};
+pub use diagnostics::SessionDiagnostics;
+pub use http::{HttpBody, HttpRequest, HttpResponse, HttpResponseTargeted};
+#[cfg(feature = "diagnostics")]
+pub use timeline::StateTimeline;
 pub mod active_session;
 pub mod auth_sequence;
 pub mod authenticator;
 pub mod config;
 pub mod connection_pool;
+mod correlation;
+pub mod diagnostics;
 pub mod encryption;
+#[cfg(feature = "discovery")]
+pub mod endpoint_discovery;
+mod flow_control;
 pub mod http;
+#[cfg(feature = "discovery")]
+pub mod kdc_discovery;
+#[cfg(feature = "out-of-proc")]
+pub mod named_pipe;
+#[cfg(feature = "out-of-proc")]
+pub mod out_of_proc;
+pub mod rate_limit;
+#[cfg(feature = "diagnostics")]
+pub mod timeline;
+#[cfg(feature = "out-of-proc")]
+pub mod transport;
 
 /// Internal scheme type for URL building
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -88,7 +112,12 @@ impl TransportSecurity {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where and how to connect. Build it with [`WinRmConfig::builder`]: only the
+/// server, transport, authentication and host info are required, the rest
+/// defaults. New settings are added as defaulted fields, so the struct is
+/// `#[non_exhaustive]`; its fields stay public to read and adjust.
+#[derive(Debug, Clone, typed_builder::TypedBuilder)]
+#[non_exhaustive]
 pub struct WinRmConfig {
     pub server: (ServerAddress, u16),
     pub transport: TransportSecurity,
@@ -107,25 +136,123 @@ pub struct WinRmConfig {
     ///
     /// Changed from `Option<u32>` to `Option<f64>` to support sub-second timeouts
     /// (required by serial mode's 500 ms Receive slices).
+    #[builder(default)]
     pub operation_timeout_secs: Option<f64>,
     /// TLS behaviour for HTTPS transports. Ignored for plain-HTTP transports.
+    #[builder(default)]
     pub tls: config::TlsOptions,
     /// PowerShell session configuration (JEA endpoint) name.
     /// `None` → `Microsoft.PowerShell`. Becomes the shell resource URI
     /// `http://schemas.microsoft.com/powershell/{name}`.
+    #[builder(default)]
     pub configuration_name: Option<String>,
+    /// Receive flow control (concurrent Receive cap, per-pipeline output budget).
+    #[builder(default)]
+    pub receive_flow: config::ReceiveFlowConfig,
+    /// Client-side pacing (requests per second, Receive jitter).
+    #[builder(default)]
+    pub rate_limit: config::RateLimitConfig,
+    /// SSPI service principal override. `None` → `HTTP/<server>`.
+    #[builder(default)]
+    pub service_principal: Option<config::ServicePrincipal>,
+    /// Sent in `InitRunspacePool`; the server exposes it as
+    /// `$PSSenderInfo.ApplicationArguments`. Empty → `Nil`.
+    #[builder(default = ApplicationArguments::empty())]
+    pub application_arguments: ApplicationArguments,
+    /// Disconnect or close the session after a period of inactivity. `None`
+    /// keeps it open until the application ends it.
+    #[builder(default)]
+    pub idle_policy: Option<config::IdlePolicy>,
+    /// How often the session reports its health (`SessionEvent::Heartbeat`).
+    /// `None` sends no heartbeats.
+    #[builder(default)]
+    pub heartbeat_interval: Option<std::time::Duration>,
+    /// Open a new shell when the server dropped this one. Only the parallel
+    /// session loop honours it. `None` ends the session with `ShellNotFound`
+    /// instead.
+    #[builder(default)]
+    pub recreate_session: Option<config::SessionRecreation>,
+    /// Sticky routing behind a load-balanced farm (cookies, pinned address).
+    #[builder(default)]
+    pub affinity: config::AffinityConfig,
+    /// Whether the server may call back into a host (console UI).
+    #[builder(default)]
+    pub host_mode: config::HostMode,
+    /// Sent in SESSION_CAPABILITY for the server's `[DateTime]` conversions.
+    /// `None` sends no time zone.
+    #[builder(default)]
+    pub time_zone: Option<TimeZone>,
+    /// Whether one malformed message fails the whole ReceiveResponse.
+    #[builder(default)]
+    pub parse_mode: config::ParseMode,
+    /// Size, node, depth and fan-out limits for the XML of every response
+    /// and PSRP message. Checked while parsing, before a DOM is built.
+    #[builder(default)]
+    pub parse_limits: config::ParseLimits,
+    /// Size limits for reassembling PSRP fragments into messages.
+    #[builder(default)]
+    pub defragment_limits: config::DefragmentLimits,
 }
 
 impl WinRmConfig {
+    fn defragmenter(&self) -> Defragmenter {
+        Defragmenter::with_limits(self.defragment_limits)
+    }
+
     pub fn wsman_to(&self, query: Option<&str>) -> String {
         let query = query
             .map(|q| format!("?{}", q.trim_start_matches('?')))
             .unwrap_or_default();
 
+        let host = self.server.0.url_host();
         match self.transport.scheme() {
-            Scheme::Http => format!("http://{}:{}/wsman{}", self.server.0, self.server.1, query),
-            Scheme::Https => format!("https://{}:{}/wsman{}", self.server.0, self.server.1, query),
+            Scheme::Http => format!("http://{}:{}/wsman{}", host, self.server.1, query),
+            Scheme::Https => format!("https://{}:{}/wsman{}", host, self.server.1, query),
+        }
+    }
+
+    /// Set the application arguments from name/value pairs. Values convert from
+    /// common Rust types (`&str`, `String`, `bool`, integers, `Uuid`, bytes)
+    /// or can be given as a ready-made [`PsValue`].
+    #[must_use]
+    pub fn with_application_arguments<K, V>(
+        mut self,
+        arguments: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<PsValue>,
+    {
+        self.application_arguments.additional_arguments.extend(
+            arguments
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        self
+    }
+
+    /// Authentication config with the SSPI target replaced by the
+    /// `service_principal` override, if any.
+    pub fn effective_authentication(&self) -> AuthenticatorConfig {
+        let mut authentication = self.authentication.clone();
+        if let (Some(spn), AuthenticatorConfig::Sspi(sspi)) =
+            (&self.service_principal, &mut authentication)
+        {
+            *sspi.target_mut() = spn.resolve(&self.server.0.spn_host());
         }
+        authentication
+    }
+
+    /// `host_info` with the null-host flags set when the session is
+    /// [`Headless`](config::HostMode::Headless).
+    pub fn effective_host_info(&self) -> HostInfo {
+        let mut host_info = self.host_info.clone();
+        if self.host_mode == config::HostMode::Headless {
+            host_info.is_host_null = true;
+            host_info.is_host_ui_null = true;
+            host_info.is_host_raw_ui_null = true;
+        }
+        host_info
     }
 
     /// Shell resource URI for the configured PowerShell session configuration
@@ -222,6 +349,12 @@ pub struct Connector {
     /// should set it via [`Connector::new_connect_with_runspaces`]. See issue
     /// #12 ("Gap: CONNECT_RUNSPACEPOOL min/max runspaces").
     connect_runspaces: Option<(usize, usize)>,
+    /// Source of envelope ids and the new pool's RPID; see
+    /// [`Connector::with_id_generator`].
+    ids: Arc<dyn IdGenerator>,
+    /// Where state transitions are recorded; see [`Connector::with_timeline`].
+    #[cfg(feature = "diagnostics")]
+    timeline: Option<timeline::StateTimeline>,
 }
 
 impl Connector {
@@ -231,6 +364,9 @@ impl Connector {
             config,
             connect_shell_id: None,
             connect_runspaces: None,
+            ids: Arc::new(RandomIds),
+            #[cfg(feature = "diagnostics")]
+            timeline: None,
         }
     }
 
@@ -246,6 +382,9 @@ impl Connector {
             config,
             connect_shell_id: Some(shell_id),
             connect_runspaces: None,
+            ids: Arc::new(RandomIds),
+            #[cfg(feature = "diagnostics")]
+            timeline: None,
         }
     }
 
@@ -262,11 +401,40 @@ impl Connector {
             config,
             connect_shell_id: Some(shell_id),
             connect_runspaces: Some((min_runspaces, max_runspaces)),
+            ids: Arc::new(RandomIds),
+            #[cfg(feature = "diagnostics")]
+            timeline: None,
         }
     }
 
+    /// Replace the random UUID source, e.g. with
+    /// [`ironposh_winrm::ws_management::SequentialIds`] so a test run produces
+    /// byte-identical envelopes.
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Record every Connector, runspace pool, session and pipeline state
+    /// transition of this session into `timeline`.
+    #[cfg(feature = "diagnostics")]
+    pub fn with_timeline(mut self, timeline: timeline::StateTimeline) -> Self {
+        timeline.record(timeline::Machine::Connector, self.state.state_name());
+        self.timeline = Some(timeline);
+        self
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub fn timeline(&self) -> Option<&timeline::StateTimeline> {
+        self.timeline.as_ref()
+    }
+
     fn set_state(&mut self, state: ConnectorState) {
         info!(state = state.state_name(), "Setting connector state");
+        #[cfg(feature = "diagnostics")]
+        if let Some(timeline) = &self.timeline {
+            timeline.record(timeline::Machine::Connector, state.state_name());
+        }
         self.state = state;
     }
 
@@ -292,8 +460,16 @@ impl Connector {
                 // Create pool with SSPI cfg derived from WinRmConfig
                 let pool_cfg = ConnectionPoolConfig::from(&self.config);
 
-                let authenticator_cfg = self.config.authentication.clone();
+                let authenticator_cfg = self.config.effective_authentication();
                 let require_sspi_sealing = self.config.transport.requires_sspi_sealing();
+                if matches!(authenticator_cfg, AuthenticatorConfig::None) {
+                    warn!(
+                        transport = ?self.config.transport,
+                        "authentication is DISABLED: requests carry no credentials and are \
+                         never sealed; this is only safe behind a gateway or reverse proxy \
+                         that authenticates the user"
+                    );
+                }
 
                 let auth_sequence_config =
                     AuthSequenceConfig::new(authenticator_cfg, require_sspi_sealing);
@@ -306,6 +482,7 @@ impl Connector {
                         .to(self.config.wsman_to(None))
                         .operation_timeout(operation_timeout)
                         .resource_uri(self.config.shell_resource_uri())
+                        .ids(Arc::clone(&self.ids))
                         .build(),
                 );
 
@@ -319,9 +496,16 @@ impl Connector {
                         .id(shell_id)
                         .min_runspaces(min_runspaces)
                         .max_runspaces(max_runspaces)
-                        .host_info(self.config.host_info.clone())
+                        .host_info(self.config.effective_host_info())
+                        .application_arguments(self.config.application_arguments.clone())
+                        .time_zone(self.config.time_zone.clone())
+                        .defragmenter(self.config.defragmenter())
+                        .parse_mode(self.config.parse_mode)
+                        .parse_limits(self.config.parse_limits)
                         .build()
                         .into_connect_runspace_pool(ws_man);
+                    #[cfg(feature = "diagnostics")]
+                    let runspace_pool = runspace_pool.with_timeline(self.timeline.clone());
 
                     let (xml_body, expect_shell_connected) = runspace_pool.connect()?;
                     info!(shell_id = %shell_id, shell_connect_xml = %xml_body, "outgoing unencrypted shell connect SOAP");
@@ -336,9 +520,17 @@ impl Connector {
                     (new_state, ConnectorStepResult::SendBack { try_send })
                 } else {
                     let runspace_pool = RunspacePoolCreator::builder()
-                        .host_info(self.config.host_info.clone())
+                        .id(self.ids.next_id())
+                        .host_info(self.config.effective_host_info())
+                        .application_arguments(self.config.application_arguments.clone())
+                        .time_zone(self.config.time_zone.clone())
+                        .defragmenter(self.config.defragmenter())
+                        .parse_mode(self.config.parse_mode)
+                        .parse_limits(self.config.parse_limits)
                         .build()
                         .into_runspace_pool(ws_man);
+                    #[cfg(feature = "diagnostics")]
+                    let runspace_pool = runspace_pool.with_timeline(self.timeline.clone());
 
                     let (xml_body, expect_shell_created) = runspace_pool.open()?;
                     info!(shell_creation_xml = %xml_body, "outgoing unencrypted shell creation SOAP");
@@ -368,13 +560,18 @@ impl Connector {
                         // (SESSION_CAPABILITY + RUNSPACEPOOL_INIT_DATA): the
                         // pool is Opened right away. Fire the initial Receive
                         // and hand off to the ActiveSession like the normal path.
-                        let runspace_pool = expect_shell_connected.accept(&xml)?;
+                        let mut runspace_pool = expect_shell_connected.accept(&xml)?;
                         let next_receive_xml = runspace_pool
                             .fire_receive(DesiredStream::runspace_pool_streams(), None)?;
                         info!(connect_receive_xml = %next_receive_xml, "outgoing unencrypted post-connect receive SOAP");
                         let next_req = connection_pool.send(&next_receive_xml)?;
 
-                        let active_session = ActiveSession::new(runspace_pool, connection_pool);
+                        let active_session = ActiveSession::new(
+                            runspace_pool,
+                            connection_pool,
+                            self.config.receive_flow.clone(),
+                            self.config.host_mode,
+                        );
                         let new_state = ConnectorState::Connected;
                         (
                             new_state,
@@ -396,6 +593,10 @@ impl Connector {
                         };
                         (new_state, ConnectorStepResult::SendBack { try_send })
                     }
+                    // Only an established session surfaces interruptions.
+                    ConnectionPoolAccept::Interrupted { interruption, .. } => {
+                        return Err(interruption.into_error(crate::Phase::ShellConnect));
+                    }
                 }
             }
             ConnectorState::Connecting {
@@ -410,7 +611,7 @@ impl Connector {
                 match connection_pool.accept(targeted_response)? {
                     ConnectionPoolAccept::Body(xml) => {
                         // Advance runspace handshake
-                        let runspace_pool = expect_shell_created.accept(&xml)?;
+                        let mut runspace_pool = expect_shell_created.accept(&xml)?;
                         let receive_xml = runspace_pool
                             .fire_receive(DesiredStream::runspace_pool_streams(), None)?;
                         info!(connecting_receive_xml = %receive_xml, "outgoing unencrypted connecting receive SOAP");
@@ -435,6 +636,10 @@ impl Connector {
                         };
                         (new_state, ConnectorStepResult::SendBack { try_send })
                     }
+                    // Only an established session surfaces interruptions.
+                    ConnectionPoolAccept::Interrupted { interruption, .. } => {
+                        return Err(interruption.into_error(crate::Phase::ShellCreation));
+                    }
                 }
             }
             ConnectorState::ConnectReceiveCycle {
@@ -448,6 +653,14 @@ impl Connector {
                 match connection_pool.accept(targeted_response)? {
                     ConnectionPoolAccept::Body(xml) => {
                         let results = runspace_pool.accept_response(&xml)?;
+                        if let Some(AcceptResponsResult::PoolBroken { error_record }) = results
+                            .iter()
+                            .find(|r| matches!(r, AcceptResponsResult::PoolBroken { .. }))
+                        {
+                            return Err(crate::PwshCoreError::RunspacePoolBroken {
+                                reason: error_record.message.clone(),
+                            });
+                        }
                         let Some(AcceptResponsResult::ReceiveResponse { desired_streams }) =
                             results
                                 .into_iter()
@@ -471,7 +684,12 @@ impl Connector {
                             let next_receive_xml =
                                 runspace_pool.fire_receive(desired_streams, None)?;
                             let next_req = connection_pool.send(&next_receive_xml)?;
-                            let active_session = ActiveSession::new(runspace_pool, connection_pool);
+                            let active_session = ActiveSession::new(
+                                runspace_pool,
+                                connection_pool,
+                                self.config.receive_flow.clone(),
+                                self.config.host_mode,
+                            );
                             let new_state = ConnectorState::Connected;
                             (
                                 new_state,
@@ -498,6 +716,10 @@ impl Connector {
                         };
                         (new_state, ConnectorStepResult::SendBack { try_send })
                     }
+                    // Only an established session surfaces interruptions.
+                    ConnectionPoolAccept::Interrupted { interruption, .. } => {
+                        return Err(interruption.into_error(crate::Phase::ShellCreation));
+                    }
                 }
             }
         };
@@ -522,7 +744,8 @@ mod tests {
             .window_size(size.clone())
             .max_window_size(size.clone())
             .max_physical_window_size(size)
-            .build();
+            .build()
+            .expect("valid host data");
 
         WinRmConfig {
             server: (ServerAddress::parse("127.0.0.1").unwrap(), 5985),
@@ -535,6 +758,19 @@ mod tests {
             operation_timeout_secs: None,
             tls: config::TlsOptions::default(),
             configuration_name,
+            receive_flow: config::ReceiveFlowConfig::default(),
+            rate_limit: config::RateLimitConfig::default(),
+            service_principal: None,
+            application_arguments: ApplicationArguments::empty(),
+            idle_policy: None,
+            heartbeat_interval: None,
+            recreate_session: None,
+            affinity: config::AffinityConfig::default(),
+            host_mode: config::HostMode::default(),
+            time_zone: None,
+            parse_mode: config::ParseMode::default(),
+            parse_limits: config::ParseLimits::default(),
+            defragment_limits: config::DefragmentLimits::default(),
         }
     }
 
@@ -555,4 +791,35 @@ mod tests {
             "http://schemas.microsoft.com/powershell/MyJEAEndpoint"
         );
     }
+
+    #[test]
+    fn headless_mode_advertises_a_null_host() {
+        let mut config = config_with_configuration_name(None);
+        assert!(!config.effective_host_info().is_host_null);
+
+        config.host_mode = config::HostMode::Headless;
+        let host_info = config.effective_host_info();
+        assert!(host_info.is_host_null);
+        assert!(host_info.is_host_ui_null);
+        assert!(host_info.is_host_raw_ui_null);
+    }
+
+    #[test]
+    fn service_principal_overrides_the_sspi_target() {
+        let mut config = config_with_configuration_name(None);
+        config.server.0 = ServerAddress::parse("lb.example.com").unwrap();
+        config.authentication = AuthenticatorConfig::Sspi(config::SspiAuthConfig::NTLM {
+            target: "lb.example.com".to_owned(),
+            identity: crate::credentials::ClientAuthIdentity::new(
+                crate::credentials::ClientUserName::new("user", None).unwrap(),
+                "pass".to_owned(),
+            ),
+        });
+        config.service_principal = Some(config::ServicePrincipal::new("WSMAN/{host}"));
+
+        let AuthenticatorConfig::Sspi(mut sspi) = config.effective_authentication() else {
+            panic!("expected SSPI authentication");
+        };
+        assert_eq!(sspi.target_mut(), "WSMAN/lb.example.com");
+    }
 }
@@ -0,0 +1,146 @@
+//! Local PowerShell processes (`Enter-PSHostProcess`): every `pwsh` /
+//! `powershell.exe` listens on a named pipe speaking the same OutOfProc
+//! protocol as the SSH subsystem, so attaching to one is
+//! [`OutOfProcTransport`](super::out_of_proc::OutOfProcTransport) over the
+//! pipe instead of over `ssh`.
+//!
+//! On Windows the pipe is `\\.\pipe\PSHost.<start time>.<pid>.<app domain>.<process name>`;
+//! .NET on Linux/macOS backs named pipes with a Unix socket named
+//! `CoreFxPipe_<pipe name>` in the temp directory.
+//!
+//! This module only names the pipes; nothing opens one or runs a session over
+//! it yet (see `docs/plans/2026-10-16-out-of-proc-sessions.md`).
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PIPE_NAME_PREFIX: &str = "PSHost.";
+const DEFAULT_APP_DOMAIN: &str = "DefaultAppDomain";
+const UNIX_SOCKET_PREFIX: &str = "CoreFxPipe_";
+
+/// 100ns intervals between 1601-01-01 (FILETIME epoch) and 1970-01-01.
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// The pipe a local PowerShell process accepts host connections on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PsHostPipe {
+    /// The pipe every PowerShell process opens at startup.
+    Process {
+        /// Process start time as a Windows FILETIME, see [`filetime`].
+        start_time: u64,
+        pid: u32,
+        app_domain: String,
+        process_name: String,
+    },
+    /// `pwsh -CustomPipeName <name>`.
+    Custom(String),
+}
+
+impl PsHostPipe {
+    pub fn process(start_time: u64, pid: u32, process_name: impl Into<String>) -> Self {
+        Self::Process {
+            start_time,
+            pid,
+            app_domain: DEFAULT_APP_DOMAIN.to_owned(),
+            process_name: process_name.into(),
+        }
+    }
+
+    /// Recognize a PowerShell host pipe in a pipe listing (`\\.\pipe\`) or
+    /// a `CoreFxPipe_` socket name; anything else is `None`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.strip_prefix(UNIX_SOCKET_PREFIX).unwrap_or(name);
+        let mut parts = name.strip_prefix(PIPE_NAME_PREFIX)?.splitn(4, '.');
+        let start_time = parts.next()?.parse().ok()?;
+        let pid = parts.next()?.parse().ok()?;
+        let app_domain = parts.next()?.to_owned();
+        let process_name = parts.next()?.to_owned();
+        Some(Self::Process {
+            start_time,
+            pid,
+            app_domain,
+            process_name,
+        })
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            Self::Process { pid, .. } => Some(*pid),
+            Self::Custom(_) => None,
+        }
+    }
+
+    pub fn pipe_name(&self) -> String {
+        match self {
+            Self::Process {
+                start_time,
+                pid,
+                app_domain,
+                process_name,
+            } => format!("{PIPE_NAME_PREFIX}{start_time}.{pid}.{app_domain}.{process_name}"),
+            Self::Custom(name) => name.clone(),
+        }
+    }
+
+    /// `\\.\pipe\...`, for `CreateFile` on Windows.
+    pub fn windows_path(&self) -> String {
+        format!(r"\\.\pipe\{}", self.pipe_name())
+    }
+
+    /// The socket .NET creates for the pipe on Linux/macOS.
+    pub fn unix_socket_path(&self, temp_dir: &Path) -> PathBuf {
+        temp_dir.join(format!("{UNIX_SOCKET_PREFIX}{}", self.pipe_name()))
+    }
+}
+
+/// `time` as a Windows FILETIME, the form process start times take in pipe
+/// names.
+pub fn filetime(time: SystemTime) -> u64 {
+    let since_unix_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    FILETIME_UNIX_EPOCH + (since_unix_epoch.as_nanos() / 100) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_pipe_name_round_trips() {
+        let pipe = PsHostPipe::process(133_500_000_000_000_000, 4242, "pwsh");
+        assert_eq!(
+            pipe.windows_path(),
+            r"\\.\pipe\PSHost.133500000000000000.4242.DefaultAppDomain.pwsh"
+        );
+        assert_eq!(PsHostPipe::parse(&pipe.pipe_name()), Some(pipe));
+    }
+
+    #[test]
+    fn parses_unix_socket_names_and_ignores_other_pipes() {
+        let pipe = PsHostPipe::parse("CoreFxPipe_PSHost.133500000000000000.77.None.pwsh")
+            .expect("a PowerShell host socket");
+        assert_eq!(pipe.pid(), Some(77));
+        assert_eq!(
+            pipe.unix_socket_path(Path::new("/tmp")),
+            Path::new("/tmp/CoreFxPipe_PSHost.133500000000000000.77.None.pwsh")
+        );
+
+        assert_eq!(PsHostPipe::parse("InitShutdown"), None);
+        assert_eq!(PsHostPipe::parse("PSHost.not-a-time.1.x.pwsh"), None);
+    }
+
+    #[test]
+    fn custom_pipe_names_are_used_verbatim() {
+        let pipe = PsHostPipe::Custom("debug-me".to_owned());
+        assert_eq!(pipe.windows_path(), r"\\.\pipe\debug-me");
+        assert_eq!(pipe.pid(), None);
+    }
+
+    #[test]
+    fn filetime_counts_from_1601() {
+        assert_eq!(filetime(UNIX_EPOCH), FILETIME_UNIX_EPOCH);
+        assert_eq!(
+            filetime(UNIX_EPOCH + Duration::from_secs(1)),
+            FILETIME_UNIX_EPOCH + 10_000_000
+        );
+    }
+}
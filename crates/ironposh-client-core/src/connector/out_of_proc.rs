@@ -0,0 +1,425 @@
+//! PowerShell's "OutOfProc" framing, the packet format `pwsh -SSHServerMode`
+//! (the `powershell` SSH subsystem) and local out-of-process runspaces speak.
+//! This module frames and unframes packets only; no session runs over SSH or
+//! a pipe yet (see `docs/plans/2026-10-16-out-of-proc-sessions.md`).
+//!
+//! Every packet is one line of XML:
+//!
+//! ```text
+//! <Data Stream='Default' PSGuid='...'>base64 fragment</Data>
+//! <DataAck PSGuid='...' />
+//! <Command PSGuid='...' />      <CommandAck PSGuid='...' />
+//! <Signal PSGuid='...' />       <SignalAck PSGuid='...' />
+//! <Close PSGuid='...' />        <CloseAck PSGuid='...' />
+//! ```
+//!
+//! `PSGuid` is the pipeline id, or the nil GUID for the runspace pool. The
+//! client sends one fragment per `Data` packet and waits for its `DataAck`
+//! before sending the next one to the same target; a pipeline's data waits
+//! for the `CommandAck` of its `Command` packet.
+
+use std::collections::{HashMap, VecDeque};
+
+use base64::Engine;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{
+    Phase, PwshCoreError,
+    connector::transport::{Accepted, DataStream, PsrpTarget, Transport, TransportEvent},
+};
+
+impl PsrpTarget {
+    fn ps_guid(self) -> Uuid {
+        match self {
+            Self::RunspacePool => Uuid::nil(),
+            Self::Pipeline(id) => id,
+        }
+    }
+
+    fn from_ps_guid(guid: Uuid) -> Self {
+        if guid.is_nil() {
+            Self::RunspacePool
+        } else {
+            Self::Pipeline(guid)
+        }
+    }
+}
+
+impl DataStream {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::PromptResponse => "PromptResponse",
+        }
+    }
+}
+
+/// Outgoing data of one target.
+#[derive(Debug, Default)]
+struct Outbox {
+    /// A `Data` packet (or the pipeline's `Command`) is not acknowledged yet.
+    awaiting_ack: bool,
+    queued: VecDeque<(DataStream, Vec<u8>)>,
+}
+
+/// [`Transport`] speaking the OutOfProc framing; frames are complete lines,
+/// ready to be written to the subsystem's stdin.
+#[derive(Debug, Default)]
+pub struct OutOfProcTransport {
+    outboxes: HashMap<PsrpTarget, Outbox>,
+    /// Bytes of a line whose end has not arrived yet.
+    partial: Vec<u8>,
+}
+
+impl OutOfProcTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn data_packet(target: PsrpTarget, stream: DataStream, fragment: &[u8]) -> String {
+        format!(
+            "<Data Stream='{}' PSGuid='{}'>{}</Data>\n",
+            stream.as_str(),
+            target.ps_guid(),
+            base64::engine::general_purpose::STANDARD.encode(fragment)
+        )
+    }
+
+    fn control_packet(tag: &str, target: PsrpTarget) -> String {
+        format!("<{tag} PSGuid='{}' />\n", target.ps_guid())
+    }
+
+    /// The next queued `Data` packet of `target`, unless one is still
+    /// waiting for its acknowledgement.
+    fn release(&mut self, target: PsrpTarget) -> Option<String> {
+        let outbox = self.outboxes.get_mut(&target)?;
+        if outbox.awaiting_ack {
+            return None;
+        }
+        let (stream, fragment) = outbox.queued.pop_front()?;
+        outbox.awaiting_ack = true;
+        Some(Self::data_packet(target, stream, &fragment))
+    }
+
+    fn acknowledge(&mut self, target: PsrpTarget, send: &mut Vec<String>) {
+        let Some(outbox) = self.outboxes.get_mut(&target) else {
+            warn!(
+                ?target,
+                "acknowledgement for a target with no outgoing data"
+            );
+            return;
+        };
+        outbox.awaiting_ack = false;
+        send.extend(self.release(target));
+    }
+
+    fn accept_line(
+        &mut self,
+        line: &str,
+        events: &mut Vec<TransportEvent>,
+        send: &mut Vec<String>,
+    ) -> Result<(), PwshCoreError> {
+        let document = ironposh_xml::parser::parse(line)?;
+        let packet = document.root_element();
+        let target = packet
+            .attribute("PSGuid")
+            .and_then(|guid| Uuid::parse_str(guid).ok())
+            .map(PsrpTarget::from_ps_guid)
+            .ok_or_else(|| {
+                PwshCoreError::invalid_response(
+                    Phase::Receive,
+                    format!("OutOfProc packet without a valid PSGuid: {line}"),
+                )
+            })?;
+
+        match packet.tag_name().name() {
+            "Data" => {
+                let stream = match packet.attribute("Stream") {
+                    Some("PromptResponse") => DataStream::PromptResponse,
+                    _ => DataStream::Default,
+                };
+                let fragment = base64::engine::general_purpose::STANDARD
+                    .decode(packet.text().unwrap_or_default().trim())
+                    .map_err(|e| {
+                        PwshCoreError::invalid_response(
+                            Phase::Receive,
+                            format!("OutOfProc Data packet is not valid base64: {e}"),
+                        )
+                    })?;
+                events.push(TransportEvent::Data {
+                    target,
+                    stream,
+                    fragment,
+                });
+            }
+            "DataAck" | "CommandAck" => self.acknowledge(target, send),
+            "SignalAck" => {
+                if let PsrpTarget::Pipeline(pipeline_id) = target {
+                    events.push(TransportEvent::SignalAcknowledged { pipeline_id });
+                }
+            }
+            "CloseAck" => {
+                self.outboxes.remove(&target);
+                events.push(TransportEvent::Closed { target });
+            }
+            other => {
+                return Err(PwshCoreError::invalid_response(
+                    Phase::Receive,
+                    format!("unexpected OutOfProc packet <{other}>"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Transport for OutOfProcTransport {
+    type Frame = String;
+
+    fn send(
+        &mut self,
+        target: PsrpTarget,
+        stream: DataStream,
+        fragments: Vec<Vec<u8>>,
+    ) -> Vec<String> {
+        self.outboxes
+            .entry(target)
+            .or_default()
+            .queued
+            .extend(fragments.into_iter().map(|fragment| (stream, fragment)));
+        self.release(target).into_iter().collect()
+    }
+
+    fn create_pipeline(&mut self, pipeline_id: Uuid, fragments: Vec<Vec<u8>>) -> Vec<String> {
+        let target = PsrpTarget::Pipeline(pipeline_id);
+        self.outboxes.insert(
+            target,
+            Outbox {
+                awaiting_ack: true,
+                queued: fragments
+                    .into_iter()
+                    .map(|fragment| (DataStream::Default, fragment))
+                    .collect(),
+            },
+        );
+        vec![Self::control_packet("Command", target)]
+    }
+
+    fn signal(&mut self, pipeline_id: Uuid) -> String {
+        Self::control_packet("Signal", PsrpTarget::Pipeline(pipeline_id))
+    }
+
+    fn close(&mut self, target: PsrpTarget) -> String {
+        Self::control_packet("Close", target)
+    }
+
+    fn accept(&mut self, received: &[u8]) -> Result<Accepted<String>, PwshCoreError> {
+        self.partial.extend_from_slice(received);
+
+        let mut events = Vec::new();
+        let mut send = Vec::new();
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = std::str::from_utf8(&raw).map_err(|e| {
+                PwshCoreError::invalid_response(
+                    Phase::Receive,
+                    format!("OutOfProc packet is not UTF-8: {e}"),
+                )
+            })?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            debug!(packet = %line, "OutOfProc packet received");
+            self.accept_line(line, &mut events, &mut send)?;
+        }
+
+        Ok(Accepted { events, send })
+    }
+}
+
+/// `ssh` invocation reaching the `powershell` subsystem of a PowerShell 7
+/// host (`Subsystem powershell /usr/bin/pwsh -sshs -NoLogo` in its
+/// `sshd_config`). Nothing spawns it yet; a caller that does would carry the
+/// [`OutOfProcTransport`] frames over its stdin/stdout.
+#[derive(Debug, Clone)]
+pub struct SshSubsystemConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+    pub subsystem: String,
+}
+
+impl SshSubsystemConfig {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            user: None,
+            identity_file: None,
+            subsystem: "powershell".to_owned(),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn with_identity_file(mut self, path: impl Into<String>) -> Self {
+        self.identity_file = Some(path.into());
+        self
+    }
+
+    pub fn with_subsystem(mut self, subsystem: impl Into<String>) -> Self {
+        self.subsystem = subsystem.into();
+        self
+    }
+
+    /// Arguments for the `ssh` executable, the way `New-PSSession -HostName`
+    /// starts it.
+    pub fn ssh_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(user) = &self.user {
+            args.extend(["-l".to_owned(), user.clone()]);
+        }
+        if let Some(port) = self.port {
+            args.extend(["-p".to_owned(), port.to_string()]);
+        }
+        if let Some(identity_file) = &self.identity_file {
+            args.extend(["-i".to_owned(), identity_file.clone()]);
+        }
+        args.extend(["-s".to_owned(), self.host.clone(), self.subsystem.clone()]);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPELINE: &str = "5d35e4e4-3bd7-4a4c-9f2f-0f2c5d35e4e4";
+
+    fn pipeline_id() -> Uuid {
+        Uuid::parse_str(PIPELINE).unwrap()
+    }
+
+    #[test]
+    fn pool_data_goes_one_fragment_per_ack() {
+        let mut transport = OutOfProcTransport::new();
+        let frames = transport.send(
+            PsrpTarget::RunspacePool,
+            DataStream::Default,
+            vec![b"first".to_vec(), b"second".to_vec()],
+        );
+        assert_eq!(
+            frames,
+            [
+                "<Data Stream='Default' PSGuid='00000000-0000-0000-0000-000000000000'>Zmlyc3Q=</Data>\n"
+            ]
+        );
+
+        let accepted = transport
+            .accept(b"<DataAck PSGuid='00000000-0000-0000-0000-000000000000' />\n")
+            .unwrap();
+        assert!(accepted.events.is_empty());
+        assert_eq!(
+            accepted.send,
+            [
+                "<Data Stream='Default' PSGuid='00000000-0000-0000-0000-000000000000'>c2Vjb25k</Data>\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn pipeline_data_waits_for_command_ack() {
+        let mut transport = OutOfProcTransport::new();
+        let frames = transport.create_pipeline(pipeline_id(), vec![b"create".to_vec()]);
+        assert_eq!(frames, [format!("<Command PSGuid='{PIPELINE}' />\n")]);
+
+        let accepted = transport
+            .accept(format!("<CommandAck PSGuid='{PIPELINE}' />\r\n").as_bytes())
+            .unwrap();
+        assert_eq!(
+            accepted.send,
+            [format!(
+                "<Data Stream='Default' PSGuid='{PIPELINE}'>Y3JlYXRl</Data>\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn data_is_unframed_across_partial_reads() {
+        let mut transport = OutOfProcTransport::new();
+        let packet = format!("<Data Stream='Default' PSGuid='{PIPELINE}'>aGVsbG8=</Data>\n");
+        let (head, tail) = packet.as_bytes().split_at(20);
+
+        assert!(transport.accept(head).unwrap().events.is_empty());
+        let accepted = transport.accept(tail).unwrap();
+        assert_eq!(
+            accepted.events,
+            [TransportEvent::Data {
+                target: PsrpTarget::Pipeline(pipeline_id()),
+                stream: DataStream::Default,
+                fragment: b"hello".to_vec(),
+            }]
+        );
+    }
+
+    #[test]
+    fn signal_and_close_acks_become_events() {
+        let mut transport = OutOfProcTransport::new();
+        assert_eq!(
+            transport.signal(pipeline_id()),
+            format!("<Signal PSGuid='{PIPELINE}' />\n")
+        );
+
+        let accepted = transport
+            .accept(
+                format!(
+                    "<SignalAck PSGuid='{PIPELINE}' />\n<CloseAck PSGuid='00000000-0000-0000-0000-000000000000' />\n"
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(
+            accepted.events,
+            [
+                TransportEvent::SignalAcknowledged {
+                    pipeline_id: pipeline_id()
+                },
+                TransportEvent::Closed {
+                    target: PsrpTarget::RunspacePool
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_packets_are_rejected() {
+        let mut transport = OutOfProcTransport::new();
+        let error = transport
+            .accept(format!("<Bogus PSGuid='{PIPELINE}' />\n").as_bytes())
+            .unwrap_err();
+        assert!(matches!(error, PwshCoreError::InvalidResponse { .. }));
+    }
+
+    #[test]
+    fn ssh_args_reach_the_powershell_subsystem() {
+        let config = SshSubsystemConfig::new("linux-box")
+            .with_user("admin")
+            .with_port(2222);
+        assert_eq!(
+            config.ssh_args(),
+            ["-l", "admin", "-p", "2222", "-s", "linux-box", "powershell"]
+        );
+    }
+}
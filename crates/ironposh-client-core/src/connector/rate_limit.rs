@@ -0,0 +1,136 @@
+//! Client-side request pacing, so a large fan-out does not trip WinRM
+//! throttling or look like a scan to an IDS.
+//!
+//! The connection pool hands out requests as fast as the session asks for
+//! them; the session loop asks [`RateLimiter`] (through the pool) how long to
+//! hold each one back before putting it on the wire. Time is passed in by the
+//! caller, measured from any fixed origin.
+
+use std::time::Duration;
+
+use crate::connector::{config::RateLimitConfig, connection_pool::Lane};
+
+/// How much pacing has held requests back so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleStats {
+    /// Requests paced, held back or not.
+    pub requests: u64,
+    /// Requests that had to wait.
+    pub throttled: u64,
+    /// Sum of all waits.
+    pub total_wait: Duration,
+    /// Longest single wait.
+    pub longest_wait: Duration,
+}
+
+/// Spaces requests at most `max_requests_per_second` apart and spreads
+/// Receives over `receive_jitter`.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Option<Duration>,
+    receive_jitter: Option<Duration>,
+    /// Earliest time the next request may go out.
+    next_slot: Duration,
+    stats: ThrottleStats,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            interval: config
+                .max_requests_per_second
+                .map(|rate| Duration::from_secs(1) / rate.get()),
+            receive_jitter: config.receive_jitter,
+            next_slot: Duration::ZERO,
+            stats: ThrottleStats::default(),
+        }
+    }
+
+    pub(crate) const fn is_enabled(&self) -> bool {
+        self.interval.is_some() || self.receive_jitter.is_some()
+    }
+
+    /// How long a request on `lane`, asked for at `now`, must wait before it
+    /// is sent. `jitter` in `[0, 1)` picks the share of `receive_jitter` a
+    /// Receive is delayed by.
+    pub(crate) fn acquire(&mut self, lane: Lane, now: Duration, jitter: f64) -> Duration {
+        let mut wait = Duration::ZERO;
+        if let Some(interval) = self.interval {
+            let slot = self.next_slot.max(now);
+            wait = slot - now;
+            self.next_slot = slot + interval;
+        }
+        if let (Lane::Receive, Some(spread)) = (lane, self.receive_jitter) {
+            wait += spread.mul_f64(jitter.clamp(0.0, 1.0));
+        }
+
+        self.stats.requests += 1;
+        if !wait.is_zero() {
+            self.stats.throttled += 1;
+            self.stats.total_wait += wait;
+            self.stats.longest_wait = self.stats.longest_wait.max(wait);
+        }
+        wait
+    }
+
+    pub(crate) const fn stats(&self) -> ThrottleStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use super::*;
+
+    fn limiter(per_second: u32, receive_jitter: Option<Duration>) -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig {
+            max_requests_per_second: NonZeroU32::new(per_second),
+            receive_jitter,
+        })
+    }
+
+    #[test]
+    fn requests_are_spaced_by_the_rate() {
+        let mut limiter = limiter(4, None);
+        let at = Duration::from_millis;
+
+        assert_eq!(limiter.acquire(Lane::Control, at(0), 0.0), at(0));
+        assert_eq!(limiter.acquire(Lane::Control, at(0), 0.0), at(250));
+        assert_eq!(limiter.acquire(Lane::Control, at(100), 0.0), at(400));
+        // After a quiet spell the next request goes out at once.
+        assert_eq!(limiter.acquire(Lane::Control, at(2_000), 0.0), at(0));
+
+        let stats = limiter.stats();
+        assert_eq!((stats.requests, stats.throttled), (4, 2));
+        assert_eq!(stats.total_wait, at(650));
+        assert_eq!(stats.longest_wait, at(400));
+    }
+
+    #[test]
+    fn only_receives_are_jittered() {
+        let mut limiter = limiter(0, Some(Duration::from_millis(200)));
+        assert!(limiter.is_enabled());
+
+        assert_eq!(
+            limiter.acquire(Lane::Control, Duration::ZERO, 0.5),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.acquire(Lane::Receive, Duration::ZERO, 0.5),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn default_config_never_waits() {
+        let mut limiter = RateLimiter::new(&RateLimitConfig::default());
+        assert!(!limiter.is_enabled());
+        assert_eq!(
+            limiter.acquire(Lane::Receive, Duration::ZERO, 0.9),
+            Duration::ZERO
+        );
+        assert_eq!(limiter.stats().throttled, 0);
+    }
+}
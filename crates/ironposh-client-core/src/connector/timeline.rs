@@ -0,0 +1,246 @@
+//! A timeline of every state transition in a session, for diagnosing hangs.
+//!
+//! The [`Connector`](super::Connector), the
+//! [`ActiveSession`](super::ActiveSession), the runspace pool and each
+//! pipeline record their transitions into a shared [`StateTimeline`] (see
+//! [`Connector::with_timeline`](super::Connector::with_timeline)). The
+//! timeline exports as a mermaid sequence diagram or a Graphviz graph: the
+//! last state of each lane is where a stuck session is waiting.
+
+use std::fmt::{self, Write as _};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// The state machines of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Machine {
+    Connector,
+    ActiveSession,
+    RunspacePool,
+    Pipeline(uuid::Uuid),
+}
+
+impl fmt::Display for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connector => f.write_str("Connector"),
+            Self::ActiveSession => f.write_str("ActiveSession"),
+            Self::RunspacePool => f.write_str("RunspacePool"),
+            // The first group of the id tells pipelines apart in a diagram.
+            Self::Pipeline(id) => write!(f, "Pipeline {}", &id.to_string()[..8]),
+        }
+    }
+}
+
+/// One recorded transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    /// Since the timeline was created.
+    pub at: Duration,
+    pub machine: Machine,
+    /// `None` for the first state recorded for `machine`.
+    pub from: Option<String>,
+    pub to: String,
+}
+
+type Clock = dyn Fn() -> Duration + Send + Sync;
+
+/// A shared, append-only record of [`Transition`]s. Clones record into the
+/// same timeline.
+#[derive(Clone)]
+pub struct StateTimeline {
+    clock: Arc<Clock>,
+    transitions: Arc<Mutex<Vec<Transition>>>,
+}
+
+impl fmt::Debug for StateTimeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateTimeline")
+            .field("transitions", &self.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for StateTimeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateTimeline {
+    /// A timeline timed by `std::time::Instant`, which panics on
+    /// `wasm32-unknown-unknown`; give the browser build its own clock with
+    /// [`Self::with_clock`].
+    pub fn new() -> Self {
+        let started = std::time::Instant::now();
+        Self::with_clock(move || started.elapsed())
+    }
+
+    /// A timeline timed by `clock`, the time elapsed since it was created.
+    pub fn with_clock(clock: impl Fn() -> Duration + Send + Sync + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            transitions: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Transition>> {
+        self.transitions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record that `machine` is now in state `to`. Re-entering the current
+    /// state is not a transition and is not recorded.
+    pub(crate) fn record(&self, machine: Machine, to: impl Into<String>) {
+        let to = to.into();
+        let at = (self.clock)();
+        let mut transitions = self.lock();
+        let from = transitions
+            .iter()
+            .rev()
+            .find(|t| t.machine == machine)
+            .map(|t| t.to.clone());
+        if from.as_deref() == Some(to.as_str()) {
+            return;
+        }
+        tracing::debug!(%machine, ?from, %to, ?at, "state transition");
+        transitions.push(Transition {
+            at,
+            machine,
+            from,
+            to,
+        });
+    }
+
+    /// Every transition so far, oldest first.
+    pub fn transitions(&self) -> Vec<Transition> {
+        self.lock().clone()
+    }
+
+    /// The lanes in order of first appearance.
+    fn machines(transitions: &[Transition]) -> Vec<Machine> {
+        let mut machines = Vec::new();
+        for transition in transitions {
+            if !machines.contains(&transition.machine) {
+                machines.push(transition.machine);
+            }
+        }
+        machines
+    }
+
+    /// A mermaid `sequenceDiagram`: one participant per machine, one note
+    /// per transition, in the order they happened.
+    pub fn to_mermaid(&self) -> String {
+        let transitions = self.transitions();
+        let machines = Self::machines(&transitions);
+        let mut out = String::from("sequenceDiagram\n");
+        for (lane, machine) in machines.iter().enumerate() {
+            let _ = writeln!(out, "    participant m{lane} as {machine}");
+        }
+        for transition in &transitions {
+            let lane = lane_of(&machines, transition.machine);
+            let _ = writeln!(
+                out,
+                "    Note over m{lane}: +{} ms {}",
+                transition.at.as_millis(),
+                transition.to
+            );
+        }
+        out
+    }
+
+    /// A Graphviz `digraph`: one row of states per machine, each labelled
+    /// with when it was entered.
+    pub fn to_dot(&self) -> String {
+        let transitions = self.transitions();
+        let machines = Self::machines(&transitions);
+        let mut out = String::from("digraph timeline {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (lane, machine) in machines.iter().enumerate() {
+            let _ = writeln!(out, "    subgraph cluster_{lane} {{");
+            let _ = writeln!(out, "        label=\"{machine}\";");
+            let states: Vec<_> = transitions
+                .iter()
+                .filter(|t| t.machine == *machine)
+                .collect();
+            for (n, transition) in states.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "        m{lane}_{n} [label=\"{}\\n+{} ms\"];",
+                    transition.to.replace('"', "\\\""),
+                    transition.at.as_millis()
+                );
+                if n > 0 {
+                    let _ = writeln!(out, "        m{lane}_{} -> m{lane}_{n};", n - 1);
+                }
+            }
+            out.push_str("    }\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn lane_of(machines: &[Machine], machine: Machine) -> usize {
+    machines
+        .iter()
+        .position(|m| *m == machine)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    /// A clock that advances 5 ms per reading.
+    fn stepping_timeline() -> StateTimeline {
+        let ticks = AtomicU64::new(0);
+        StateTimeline::with_clock(move || {
+            Duration::from_millis(ticks.fetch_add(1, Ordering::Relaxed) * 5)
+        })
+    }
+
+    #[test]
+    fn records_transitions_per_machine_and_skips_repeats() {
+        let timeline = stepping_timeline();
+        timeline.record(Machine::Connector, "Connecting");
+        timeline.record(Machine::RunspacePool, "NegotiationSent");
+        timeline.record(Machine::RunspacePool, "NegotiationSent");
+        timeline.record(Machine::Connector, "Connected");
+
+        let transitions = timeline.transitions();
+        assert_eq!(transitions.len(), 3);
+        assert_eq!(transitions[2].from.as_deref(), Some("Connecting"));
+        assert_eq!(transitions[2].to, "Connected");
+        assert_eq!(transitions[2].at, Duration::from_millis(15));
+        assert_eq!(transitions[1].from, None);
+    }
+
+    #[test]
+    fn exports_mermaid_and_graphviz() {
+        let timeline = stepping_timeline();
+        timeline.record(Machine::Connector, "Connecting");
+        timeline.record(Machine::RunspacePool, "Opened");
+        timeline.record(Machine::Connector, "Connected");
+
+        assert_eq!(
+            timeline.to_mermaid(),
+            "sequenceDiagram\n\
+             \x20   participant m0 as Connector\n\
+             \x20   participant m1 as RunspacePool\n\
+             \x20   Note over m0: +0 ms Connecting\n\
+             \x20   Note over m1: +5 ms Opened\n\
+             \x20   Note over m0: +10 ms Connected\n"
+        );
+        let dot = timeline.to_dot();
+        assert!(dot.contains("label=\"Connector\";"), "{dot}");
+        assert!(
+            dot.contains("m0_1 [label=\"Connected\\n+10 ms\"];"),
+            "{dot}"
+        );
+        assert!(dot.contains("m0_0 -> m0_1;"), "{dot}");
+        assert!(!dot.contains("m1_0 ->"), "{dot}");
+    }
+}
@@ -0,0 +1,85 @@
+//! Carriers for PSRP fragments.
+//!
+//! WSMan over HTTP is driven by [`RunspacePool`](crate::runspace_pool::RunspacePool)
+//! itself (shell create, Command, Send, Receive). Other carriers plug in
+//! through [`Transport`]: they only frame the fragments the pool already
+//! produces and unframe what the server sends back, which is all PSRP
+//! needs from the layer below it. [`OutOfProcTransport`](super::out_of_proc::OutOfProcTransport)
+//! implements PowerShell's OutOfProc packet format. Sessions do not drive a
+//! [`Transport`] yet: this is the framing layer only, with no SSH or named
+//! pipe connection behind it. The remaining work is tracked in
+//! `docs/plans/2026-10-16-out-of-proc-sessions.md`.
+
+use uuid::Uuid;
+
+use crate::PwshCoreError;
+
+/// What a PSRP fragment belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PsrpTarget {
+    RunspacePool,
+    Pipeline(Uuid),
+}
+
+/// Input stream of the target; host responses to `Read-Host`-style prompts
+/// travel on their own stream so they can overtake pipeline input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataStream {
+    #[default]
+    Default,
+    PromptResponse,
+}
+
+/// Something the server sent, unframed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportEvent {
+    /// Raw PSRP fragment bytes, ready for the
+    /// [`Defragmenter`](ironposh_psrp::fragmentation::Defragmenter).
+    Data {
+        target: PsrpTarget,
+        stream: DataStream,
+        fragment: Vec<u8>,
+    },
+    /// The server took the stop signal for this pipeline.
+    SignalAcknowledged { pipeline_id: Uuid },
+    /// The server closed the runspace pool or the pipeline.
+    Closed { target: PsrpTarget },
+}
+
+/// What accepting received bytes produced: events for the session and
+/// frames the transport is now allowed to send (held back by flow control).
+#[derive(Debug)]
+pub struct Accepted<F> {
+    pub events: Vec<TransportEvent>,
+    pub send: Vec<F>,
+}
+
+/// A sans-IO PSRP carrier. The caller writes every returned frame to the
+/// wire in order and feeds everything it reads back to [`Transport::accept`].
+pub trait Transport {
+    /// One unit written to the wire.
+    type Frame;
+
+    /// Frames carrying `fragments` (already produced by the
+    /// [`Fragmenter`](ironposh_psrp::fragmentation::Fragmenter)) to `target`.
+    /// Frames the transport has to hold back are released by a later
+    /// [`Transport::accept`].
+    fn send(
+        &mut self,
+        target: PsrpTarget,
+        stream: DataStream,
+        fragments: Vec<Vec<u8>>,
+    ) -> Vec<Self::Frame>;
+
+    /// Frames creating a pipeline whose `CreatePipeline` message is `fragments`.
+    fn create_pipeline(&mut self, pipeline_id: Uuid, fragments: Vec<Vec<u8>>) -> Vec<Self::Frame>;
+
+    /// Frame asking the server to stop a running pipeline.
+    fn signal(&mut self, pipeline_id: Uuid) -> Self::Frame;
+
+    /// Frame closing a pipeline or the whole runspace pool.
+    fn close(&mut self, target: PsrpTarget) -> Self::Frame;
+
+    /// Unframe `received`. Partial frames are kept until the rest arrives.
+    fn accept(&mut self, received: &[u8]) -> Result<Accepted<Self::Frame>, PwshCoreError>;
+}
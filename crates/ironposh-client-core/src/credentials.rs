@@ -69,22 +69,274 @@ impl ClientUserName {
     }
 }
 
+/// Smart card identity (certificate, reader, PIN) for Kerberos PKINIT.
+#[cfg(feature = "smartcard")]
+pub use sspi::SmartCardIdentity;
+
+/// The cryptographic service provider of most PIV and Windows logon cards.
+#[cfg(feature = "smartcard")]
+pub const DEFAULT_SMART_CARD_CSP: &str = "Microsoft Base Smart Card Crypto Provider";
+
+/// Why NTLM and Basic refuse a smart card: only Kerberos has a certificate
+/// logon (PKINIT).
+pub const SMART_CARD_NEEDS_KERBEROS: &str =
+    "smart card credentials require Kerberos or Negotiate authentication";
+
+/// A smart card logon as the user picked it: which certificate, in which
+/// reader, unlocked with which PIN.
+#[cfg(feature = "smartcard")]
+#[derive(Clone)]
+pub struct SmartCardLogon {
+    /// The user principal name the certificate maps to.
+    pub username: String,
+    /// The card's logon certificate, PEM or DER.
+    pub certificate: Vec<u8>,
+    pub reader_name: String,
+    /// Key container on the card; `None` uses the card's default.
+    pub container_name: Option<String>,
+    pub csp_name: String,
+    pub pin: String,
+}
+
 /// Public wrapper for authentication credentials that hides the sspi AuthIdentity
 #[derive(Debug, Clone)]
 pub struct ClientAuthIdentity {
-    inner: sspi::AuthIdentity,
+    inner: Identity,
+}
+
+#[derive(Debug, Clone)]
+enum Identity {
+    Password(sspi::AuthIdentity),
+    #[cfg(feature = "smartcard")]
+    SmartCard(Box<SmartCardIdentity>),
 }
 
 impl ClientAuthIdentity {
     pub fn new(username: ClientUserName, password: String) -> Self {
-        let inner = sspi::AuthIdentity {
+        let inner = Identity::Password(sspi::AuthIdentity {
             username: username.inner,
             password: password.into(),
-        };
+        });
         Self { inner }
     }
 
-    pub(crate) fn into_inner(self) -> sspi::AuthIdentity {
-        self.inner
+    /// Authenticate with a smart card certificate and PIN, using Kerberos PKINIT
+    /// pre-authentication. Only `SspiAuthConfig::Kerberos` and `Negotiate` accept
+    /// it; NTLM has no certificate logon.
+    #[cfg(feature = "smartcard")]
+    pub fn smart_card(identity: SmartCardIdentity) -> Self {
+        Self {
+            inner: Identity::SmartCard(Box::new(identity)),
+        }
+    }
+
+    /// Build the smart card identity for `logon`. Fails if the certificate
+    /// is neither a PEM nor a DER X.509 certificate.
+    #[cfg(feature = "smartcard")]
+    pub fn from_smart_card_logon(logon: SmartCardLogon) -> Result<Self, PwshCoreError> {
+        fn utf16(value: &str) -> Vec<u8> {
+            value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        }
+
+        let certificate = pem_to_der(&logon.certificate)?;
+        let buffers = sspi::SmartCardIdentityBuffers {
+            username: utf16(&logon.username),
+            certificate,
+            card_name: None,
+            reader_name: utf16(&logon.reader_name),
+            container_name: logon.container_name.as_deref().map(utf16),
+            csp_name: utf16(&logon.csp_name),
+            pin: utf16(&logon.pin).into(),
+            private_key_pem: None,
+            ..Default::default()
+        };
+        Ok(Self::smart_card(SmartCardIdentity::try_from(buffers)?))
+    }
+
+    /// Authenticate with the account's NT hash instead of its password. Only
+    /// NTLM can use it: pair it with `SspiAuthConfig::NTLM`, or `Negotiate`
+    /// without a Kerberos config.
+    #[cfg(feature = "pass-the-hash")]
+    pub fn from_nt_hash(username: ClientUserName, hash: &NtHash) -> Self {
+        Self::new(username, format!("{NTLM_HASH_PREFIX}{hash}"))
+    }
+
+    /// The password identity, for providers that only take one (NTLM).
+    pub(crate) fn into_inner(self) -> Result<sspi::AuthIdentity, PwshCoreError> {
+        match self.inner {
+            Identity::Password(identity) => Ok(identity),
+            #[cfg(feature = "smartcard")]
+            Identity::SmartCard(_) => Err(PwshCoreError::Auth(SMART_CARD_NEEDS_KERBEROS)),
+        }
+    }
+
+    /// The user this identity logs on as, for logs and audit trails. `None`
+    /// for smart cards, whose user comes from the certificate.
+    pub fn user_name(&self) -> Option<&str> {
+        match &self.inner {
+            Identity::Password(identity) => Some(identity.username.inner()),
+            #[cfg(feature = "smartcard")]
+            Identity::SmartCard(_) => None,
+        }
+    }
+
+    pub(crate) fn into_credentials(self) -> sspi::Credentials {
+        match self.inner {
+            Identity::Password(identity) => sspi::Credentials::AuthIdentity(identity),
+            #[cfg(feature = "smartcard")]
+            Identity::SmartCard(identity) => sspi::Credentials::SmartCard(identity),
+        }
+    }
+}
+
+/// The DER body of a PEM certificate; DER input comes back as is.
+#[cfg(feature = "smartcard")]
+fn pem_to_der(certificate: &[u8]) -> Result<Vec<u8>, PwshCoreError> {
+    use base64::Engine as _;
+
+    let Some(pem) = std::str::from_utf8(certificate)
+        .ok()
+        .filter(|text| text.trim_start().starts_with("-----BEGIN"))
+    else {
+        return Ok(certificate.to_vec());
+    };
+    let body: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .map_err(|_| PwshCoreError::Auth("smart card certificate is not valid PEM"))
+}
+
+/// Password prefix sspi's NTLM provider recognizes as "the rest is the hex NT
+/// hash", used in place of hashing a plaintext password.
+#[cfg(feature = "pass-the-hash")]
+const NTLM_HASH_PREFIX: &str = "$NTLM$";
+
+/// An account's NT hash (MD4 of the UTF-16LE password).
+#[cfg(feature = "pass-the-hash")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NtHash([u8; 16]);
+
+#[cfg(feature = "pass-the-hash")]
+impl NtHash {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(feature = "pass-the-hash")]
+impl std::str::FromStr for NtHash {
+    type Err = PwshCoreError;
+
+    /// Parse 32 hex digits, or the `LM:NT` pair secretsdump-style tools print
+    /// (the LM half is ignored).
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value.rsplit(':').next().unwrap_or(value).trim();
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(PwshCoreError::Auth("NT hash must be 32 hexadecimal digits"));
+        }
+
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            let pair = std::str::from_utf8(pair).unwrap_or_default();
+            *byte = u8::from_str_radix(pair, 16)
+                .map_err(|_| PwshCoreError::Auth("NT hash must be 32 hexadecimal digits"))?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(feature = "pass-the-hash")]
+impl std::fmt::Display for NtHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Redacted: the hash is as good as the password.
+#[cfg(feature = "pass-the-hash")]
+impl std::fmt::Debug for NtHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NtHash(..)")
+    }
+}
+
+#[cfg(all(test, feature = "pass-the-hash"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nt_hash_parses_plain_and_lm_nt_forms() {
+        let plain: NtHash = "31D6CFE0D16AE931B73C59D7E0C089C0".parse().unwrap();
+        let pair: NtHash = "aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0"
+            .parse()
+            .unwrap();
+        assert_eq!(plain, pair);
+        assert_eq!(plain.to_string(), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(format!("{plain:?}"), "NtHash(..)");
+
+        assert!("31d6cfe0".parse::<NtHash>().is_err());
+        assert!(
+            "zz d6cfe0d16ae931b73c59d7e0c089c0"
+                .parse::<NtHash>()
+                .is_err()
+        );
+    }
+}
+
+#[cfg(all(test, feature = "smartcard"))]
+mod smart_card_tests {
+    use super::*;
+
+    /// A self-signed logon certificate for `alice@example.com`.
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBpjCCAUygAwIBAgIUNslB6EAOmpnXJtLCL++7l3Ks6tAwCgYIKoZIzj0EAwIw
+EDEOMAwGA1UEAwwFYWxpY2UwIBcNMjYxMDE2MDk0OTQwWhgPMjEyNjA5MjIwOTQ5
+NDBaMBAxDjAMBgNVBAMMBWFsaWNlMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE
+DDZDRgOCTW2/8pI40T6J1nwwUZ9zYrnuA3EXrt9C9DsoeQbcqkLxozFfrCbBr1ys
+pbUWU+ZJh25T9gj4CIDxEKOBgTB/MB0GA1UdDgQWBBR2Rm43fNbLKu3NLmBwEplW
+4IXsSDAfBgNVHSMEGDAWgBR2Rm43fNbLKu3NLmBwEplW4IXsSDAPBgNVHRMBAf8E
+BTADAQH/MCwGA1UdEQQlMCOgIQYKKwYBBAGCNxQCA6ATDBFhbGljZUBleGFtcGxl
+LmNvbTAKBggqhkjOPQQDAgNIADBFAiEA/DCp/TtVrJukX3aKFWrDNF9CPewL9L63
+stvqavWN5qUCICJ1pCPxqbL7xFk6UZ30WBEa/Jqqj7GSvMX5rIL7OejB
+-----END CERTIFICATE-----
+";
+
+    fn smart_card() -> ClientAuthIdentity {
+        ClientAuthIdentity::from_smart_card_logon(SmartCardLogon {
+            username: "alice@example.com".to_owned(),
+            certificate: CERTIFICATE.as_bytes().to_vec(),
+            reader_name: "Virtual Reader 0".to_owned(),
+            container_name: None,
+            csp_name: DEFAULT_SMART_CARD_CSP.to_owned(),
+            pin: "123456".to_owned(),
+        })
+        .expect("smart card identity")
+    }
+
+    #[test]
+    fn kerberos_and_negotiate_get_smart_card_credentials() {
+        assert!(matches!(
+            smart_card().into_credentials(),
+            sspi::Credentials::SmartCard(_)
+        ));
+    }
+
+    #[test]
+    fn ntlm_rejects_a_smart_card() {
+        assert!(matches!(
+            smart_card().into_inner(),
+            Err(PwshCoreError::Auth(SMART_CARD_NEEDS_KERBEROS))
+        ));
+    }
+
+    #[test]
+    fn der_certificates_are_taken_as_is_and_bad_pem_fails() {
+        let der = pem_to_der(CERTIFICATE.as_bytes()).unwrap();
+        assert_eq!(pem_to_der(&der).unwrap(), der);
+        assert!(pem_to_der(b"-----BEGIN CERTIFICATE-----\n!!\n-----END CERTIFICATE-----").is_err());
     }
 }
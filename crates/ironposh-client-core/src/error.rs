@@ -0,0 +1,536 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use ironposh_psrp::{ErrorCategoryInfo, ErrorRecord};
+
+/// Stable classification of a [`PwshCoreError`], for frontends that map errors
+/// to user-facing messages or retry policies without parsing `Display` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// Credentials were rejected or authentication could not complete.
+    AuthenticationFailed,
+    /// The server answered with an unexpected HTTP status.
+    HttpStatus,
+    /// The server answered with a WSMan SOAP fault.
+    WsManFault,
+    /// The server's response was missing expected content or could not be decoded.
+    InvalidResponse,
+    /// A PSRP or WS-Addressing protocol violation.
+    Protocol,
+    /// The operation is not valid in the current session state.
+    InvalidState,
+    /// The client configuration is unusable (e.g. a bad server address).
+    Configuration,
+    /// The local host (UI) failed to service a host call.
+    Host,
+    /// I/O failure below HTTP.
+    Transport,
+    /// The server broke the runspace pool; the session cannot be used anymore.
+    SessionBroken,
+    /// A WinRM per-user quota (shells, concurrent operations) is exhausted.
+    QuotaExceeded,
+    /// A bug or an invariant violation inside the client.
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::AuthenticationFailed => "authentication_failed",
+            Self::HttpStatus => "http_status",
+            Self::WsManFault => "wsman_fault",
+            Self::InvalidResponse => "invalid_response",
+            Self::Protocol => "protocol",
+            Self::InvalidState => "invalid_state",
+            Self::Configuration => "configuration",
+            Self::Host => "host",
+            Self::Transport => "transport",
+            Self::SessionBroken => "session_broken",
+            Self::QuotaExceeded => "quota_exceeded",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The part of the session lifecycle an error happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Phase {
+    Authentication,
+    KdcDiscovery,
+    /// Finding WinRM endpoints (`_wsman._tcp` SRV records, Identify probes).
+    EndpointDiscovery,
+    ShellCreation,
+    /// Attaching to an existing disconnected shell (WSMan Connect).
+    ShellConnect,
+    /// Session-key exchange for `SecureString` transport.
+    KeyExchange,
+    Command,
+    Receive,
+    HostCall,
+    Disconnect,
+    Reconnect,
+    /// Deleting the shell (WS-Transfer Delete).
+    Close,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Authentication => "authentication",
+            Self::KdcDiscovery => "KDC discovery",
+            Self::EndpointDiscovery => "endpoint discovery",
+            Self::ShellCreation => "shell creation",
+            Self::ShellConnect => "shell connect",
+            Self::KeyExchange => "key exchange",
+            Self::Command => "command",
+            Self::Receive => "receive",
+            Self::HostCall => "host call",
+            Self::Disconnect => "disconnect",
+            Self::Reconnect => "reconnect",
+            Self::Close => "close",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The WinRM quota a `w:QuotaLimit` fault reports, named after the
+/// `winrm/config` setting that sets it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Quota {
+    /// `MaxShellsPerUser`.
+    ShellsPerUser,
+    /// `MaxConcurrentOperationsPerUser`.
+    OperationsPerUser,
+    /// `MaxConcurrentUsers`.
+    ConcurrentUsers,
+    /// `MaxConcurrentCommandsPerShell`.
+    CommandsPerShell,
+    /// A quota the fault reason does not name in a known way.
+    Other,
+}
+
+impl Quota {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ShellsPerUser => "MaxShellsPerUser",
+            Self::OperationsPerUser => "MaxConcurrentOperationsPerUser",
+            Self::ConcurrentUsers => "MaxConcurrentUsers",
+            Self::CommandsPerShell => "MaxConcurrentCommandsPerShell",
+            Self::Other => "quota",
+        }
+    }
+
+    /// Which quota a `w:QuotaLimit` fault reason is about, and the limit when
+    /// the reason states it ("This user is allowed a maximum number of 5
+    /// concurrent shells, which has been exceeded.").
+    pub fn from_fault_reason(reason: &str) -> (Self, Option<u32>) {
+        let lower = reason.to_ascii_lowercase();
+        let which = if lower.contains("shells") {
+            Self::ShellsPerUser
+        } else if lower.contains("commands") {
+            Self::CommandsPerShell
+        } else if lower.contains("operations") {
+            Self::OperationsPerUser
+        } else if lower.contains("users") {
+            Self::ConcurrentUsers
+        } else {
+            Self::Other
+        };
+        let limit = reason
+            .split(|c: char| !c.is_ascii_digit())
+            .find_map(|digits| digits.parse().ok());
+        (which, limit)
+    }
+}
+
+impl fmt::Display for Quota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PwshCoreError {
+    #[error("HTTP {status} during {phase}: {detail}")]
+    HttpStatus {
+        phase: Phase,
+        status: u16,
+        detail: &'static str,
+    },
+
+    #[error("Runspace error: {0}")]
+    RunspaceError(String),
+
+    #[error("Hyper error: {0}")]
+    IOError(std::io::Error),
+
+    #[error("Hyper error: {0}")]
+    HyperError(#[from] hyper::http::Error),
+
+    #[error("Invalid state: {0}")]
+    InvalidState(&'static str),
+
+    #[error("Something unlikely happened: {0}")]
+    UnlikelyToHappen(&'static str),
+
+    #[error("Protocol error: {0}")]
+    PowerShellRemotingError(#[from] ironposh_psrp::PowerShellRemotingError),
+
+    #[error("XML parsing error: {0}")]
+    XmlParsingError(#[from] ironposh_xml::XmlError),
+
+    #[error("Invalid response during {phase}: {message}")]
+    InvalidResponse {
+        phase: Phase,
+        message: Cow<'static, str>,
+    },
+
+    #[error("Host error: {0}")]
+    HostError(#[from] crate::host::HostError),
+
+    #[error("SSPI error: {0}")]
+    SspiError(#[from] sspi::Error),
+
+    #[error("SSPI username error: {0}")]
+    UsernameError(&'static str),
+
+    #[error("Authentication error: {0}")]
+    Auth(&'static str),
+
+    #[error("Invalid server address: {0}")]
+    InvalidServerAddress(&'static str),
+
+    #[error("Internal error: {0}")]
+    InternalError(String),
+
+    #[error("SOAP fault during {phase}: {code} - {reason}")]
+    SoapFault {
+        phase: Phase,
+        code: String,
+        reason: String,
+    },
+
+    /// A `w:QuotaLimit` fault: the server refused the request because a
+    /// per-user quota is exhausted. Retrying once other work finished may
+    /// succeed.
+    #[error("WinRM quota {which} exceeded during {phase}: {reason}")]
+    QuotaExceeded {
+        phase: Phase,
+        which: Quota,
+        limit: Option<u32>,
+        reason: String,
+    },
+
+    /// The server moved the runspace pool to `Broken`; terminal for the session.
+    #[error("The runspace pool is broken: {reason}")]
+    RunspacePoolBroken { reason: String },
+
+    /// The server no longer knows the shell (WinRM error 2150858843), e.g.
+    /// because its idle timeout expired while the client was away.
+    #[error("The shell no longer exists on the server: {reason}")]
+    ShellNotFound { reason: String },
+
+    #[error(
+        "WS-Addressing RelatesTo mismatch: request MessageID {message_id}, response RelatesTo {relates_to}"
+    )]
+    RelatesToMismatch {
+        message_id: String,
+        relates_to: String,
+    },
+
+    #[error("WS-Addressing RelatesTo missing from the response to request MessageID {message_id}")]
+    MissingRelatesTo { message_id: String },
+}
+
+impl PwshCoreError {
+    pub(crate) fn invalid_response(phase: Phase, message: impl Into<Cow<'static, str>>) -> Self {
+        Self::InvalidResponse {
+            phase,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::HttpStatus { status, .. } if matches!(status, 401 | 403) => {
+                ErrorCode::AuthenticationFailed
+            }
+            Self::HttpStatus { .. } => ErrorCode::HttpStatus,
+            Self::SspiError(_) | Self::UsernameError(_) | Self::Auth(_) => {
+                ErrorCode::AuthenticationFailed
+            }
+            Self::SoapFault { .. } => ErrorCode::WsManFault,
+            Self::QuotaExceeded { .. } => ErrorCode::QuotaExceeded,
+            Self::InvalidResponse { .. } | Self::XmlParsingError(_) => ErrorCode::InvalidResponse,
+            Self::PowerShellRemotingError(_)
+            | Self::RelatesToMismatch { .. }
+            | Self::MissingRelatesTo { .. } => ErrorCode::Protocol,
+            Self::InvalidState(_) | Self::RunspaceError(_) => ErrorCode::InvalidState,
+            Self::InvalidServerAddress(_) | Self::HyperError(_) => ErrorCode::Configuration,
+            Self::HostError(_) => ErrorCode::Host,
+            Self::IOError(_) => ErrorCode::Transport,
+            Self::RunspacePoolBroken { .. } | Self::ShellNotFound { .. } => {
+                ErrorCode::SessionBroken
+            }
+            Self::InternalError(_) | Self::UnlikelyToHappen(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// The lifecycle phase the error is attributed to, when known.
+    pub fn phase(&self) -> Option<Phase> {
+        match self {
+            Self::HttpStatus { phase, .. }
+            | Self::InvalidResponse { phase, .. }
+            | Self::SoapFault { phase, .. }
+            | Self::QuotaExceeded { phase, .. } => Some(*phase),
+            Self::SspiError(_) | Self::UsernameError(_) | Self::Auth(_) => {
+                Some(Phase::Authentication)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            Self::HttpStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// The WSMan fault `(code, reason)`, if the server answered with one.
+    pub fn wsman_fault(&self) -> Option<(&str, &str)> {
+        match self {
+            Self::SoapFault { code, reason, .. } => Some((code, reason)),
+            _ => None,
+        }
+    }
+
+    /// The PSRP fragment or message that failed to reassemble or decode,
+    /// with its whole blob for the application to save with a bug report.
+    pub fn blob_diagnostics(&self) -> Option<&ironposh_psrp::BlobDiagnostics> {
+        match self {
+            Self::PowerShellRemotingError(e) => e.diagnostics(),
+            _ => None,
+        }
+    }
+
+    /// Whether repeating the same operation later may succeed: transport
+    /// drops, throttling and server-side 5xx, exhausted WinRM quotas and WSMan
+    /// operation timeouts. Authentication failures are never retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::IOError(_) | Self::QuotaExceeded { .. } => true,
+            Self::HttpStatus { status, .. } => matches!(status, 408 | 429 | 500..=599),
+            Self::SoapFault { code, .. } => code.ends_with("TimedOut"),
+            _ => false,
+        }
+    }
+
+    /// Whether the server (or the local security provider) rejected the
+    /// credentials, so prompting the user again is the right response.
+    pub fn is_auth_failure(&self) -> bool {
+        self.code() == ErrorCode::AuthenticationFailed
+    }
+}
+
+/// A remote `ErrorRecord` as a Rust error, for callers that treat the first
+/// non-terminating error as fatal (`$ErrorActionPreference = 'Stop'`).
+///
+/// `Display` is the record's message; the alternate form (`{:#}`) adds the
+/// category line, the fully-qualified error ID and the remote script stack
+/// trace, like PowerShell's `Format-List` view of `$Error[0]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteError {
+    pub message: String,
+    pub category: Option<ErrorCategoryInfo>,
+    pub fully_qualified_error_id: Option<String>,
+    pub target_object: Option<String>,
+    pub script_stack_trace: Option<String>,
+}
+
+impl From<&ErrorRecord> for RemoteError {
+    fn from(record: &ErrorRecord) -> Self {
+        Self {
+            message: record.render_concise(),
+            category: record.error_category.clone(),
+            fully_qualified_error_id: record.fully_qualified_error_id.clone(),
+            target_object: record.target_object.clone(),
+            script_stack_trace: record.script_stack_trace.clone(),
+        }
+    }
+}
+
+impl From<ErrorRecord> for RemoteError {
+    fn from(record: ErrorRecord) -> Self {
+        Self::from(&record)
+    }
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message.trim_end())?;
+        if !f.alternate() {
+            return Ok(());
+        }
+        if let Some(category) = &self.category {
+            write!(f, "\n    + CategoryInfo          : {}", category.line())?;
+        }
+        if let Some(id) = &self.fully_qualified_error_id {
+            write!(f, "\n    + FullyQualifiedErrorId : {id}")?;
+        }
+        if let Some(stack) = &self.script_stack_trace {
+            for frame in stack.lines() {
+                write!(f, "\n{frame}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironposh_psrp::ErrorCategory;
+
+    #[test]
+    fn rejected_credentials_are_auth_failures_and_not_retryable() {
+        let err = PwshCoreError::HttpStatus {
+            phase: Phase::Authentication,
+            status: 401,
+            detail: "server rejected authentication",
+        };
+        assert_eq!(err.code(), ErrorCode::AuthenticationFailed);
+        assert!(err.is_auth_failure());
+        assert!(!err.is_retryable());
+        assert_eq!(err.http_status(), Some(401));
+        assert_eq!(err.phase(), Some(Phase::Authentication));
+    }
+
+    #[test]
+    fn throttling_and_wsman_timeouts_are_retryable() {
+        let throttled = PwshCoreError::HttpStatus {
+            phase: Phase::Command,
+            status: 503,
+            detail: "service unavailable",
+        };
+        assert_eq!(throttled.code(), ErrorCode::HttpStatus);
+        assert!(throttled.is_retryable());
+
+        let timed_out = PwshCoreError::SoapFault {
+            phase: Phase::Receive,
+            code: "w:TimedOut".to_owned(),
+            reason: "The WS-Management service cannot complete the operation".to_owned(),
+        };
+        assert_eq!(timed_out.code(), ErrorCode::WsManFault);
+        assert!(timed_out.is_retryable());
+        assert_eq!(
+            timed_out.wsman_fault().map(|(code, _)| code),
+            Some("w:TimedOut")
+        );
+
+        let access_denied = PwshCoreError::SoapFault {
+            phase: Phase::ShellCreation,
+            code: "w:AccessDenied".to_owned(),
+            reason: "Access is denied.".to_owned(),
+        };
+        assert!(!access_denied.is_retryable());
+    }
+
+    #[test]
+    fn quota_faults_name_the_quota_and_limit() {
+        assert_eq!(
+            Quota::from_fault_reason(
+                "The WS-Management service cannot process the request. This user is allowed a \
+                 maximum number of 5 concurrent shells, which has been exceeded."
+            ),
+            (Quota::ShellsPerUser, Some(5))
+        );
+        assert_eq!(
+            Quota::from_fault_reason(
+                "This user has exceeded the maximum number of concurrent operations (25)."
+            ),
+            (Quota::OperationsPerUser, Some(25))
+        );
+        assert_eq!(
+            Quota::from_fault_reason("The quota was exceeded."),
+            (Quota::Other, None)
+        );
+
+        let err = PwshCoreError::QuotaExceeded {
+            phase: Phase::ShellCreation,
+            which: Quota::ShellsPerUser,
+            limit: Some(5),
+            reason: "too many shells".to_owned(),
+        };
+        assert_eq!(err.code(), ErrorCode::QuotaExceeded);
+        assert_eq!(err.phase(), Some(Phase::ShellCreation));
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn invalid_response_carries_its_phase() {
+        let err = PwshCoreError::invalid_response(Phase::ShellConnect, "No ConnectResponse");
+        assert_eq!(err.code(), ErrorCode::InvalidResponse);
+        assert_eq!(err.phase(), Some(Phase::ShellConnect));
+        assert_eq!(
+            err.to_string(),
+            "Invalid response during shell connect: No ConnectResponse"
+        );
+    }
+
+    #[test]
+    fn remote_error_keeps_id_category_and_stack() {
+        let record = ErrorRecord::builder()
+            .message("Cannot find path 'C:\\nope'.".to_owned())
+            .fully_qualified_error_id(Some(
+                "PathNotFound,Microsoft.PowerShell.Commands.GetItemCommand".to_owned(),
+            ))
+            .target_object(Some("C:\\nope".to_owned()))
+            .error_category(Some(
+                ErrorCategoryInfo::builder()
+                    .category(ErrorCategory::ObjectNotFound)
+                    .message(Some(
+                        "ObjectNotFound: (C:\\nope:String) [Get-Item], ItemNotFoundException"
+                            .to_owned(),
+                    ))
+                    .build(),
+            ))
+            .script_stack_trace(Some(
+                "at Get-Thing, <No file>: line 2\r\nat <ScriptBlock>, <No file>: line 4".to_owned(),
+            ))
+            .build();
+
+        let err = RemoteError::from(&record);
+        assert_eq!(err.target_object.as_deref(), Some("C:\\nope"));
+        assert_eq!(
+            err.category.as_ref().map(|c| c.category),
+            Some(ErrorCategory::ObjectNotFound)
+        );
+        assert_eq!(err.to_string(), "Cannot find path 'C:\\nope'.");
+        assert_eq!(
+            format!("{err:#}"),
+            "Cannot find path 'C:\\nope'.\n\
+             \x20   + CategoryInfo          : ObjectNotFound: (C:\\nope:String) [Get-Item], ItemNotFoundException\n\
+             \x20   + FullyQualifiedErrorId : PathNotFound,Microsoft.PowerShell.Commands.GetItemCommand\n\
+             at Get-Thing, <No file>: line 2\n\
+             at <ScriptBlock>, <No file>: line 4"
+        );
+    }
+}
@@ -9,6 +9,8 @@ mod types;
 
 #[cfg(test)]
 mod test;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod testing;
 
 // Re-export public API
 pub use error::*;
@@ -1,11 +1,8 @@
-use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
 
 use super::{methods, traits::ToPs};
 use ironposh_psrp::ps_value::ToPsValue;
-use ironposh_psrp::{
-    ComplexObject, ComplexObjectContent, Container, Properties, PsPrimitiveValue, PsType, PsValue,
-};
+use ironposh_psrp::{PsDict, PsValue};
 
 /// Return types whose CLIXML is fully macro-derived (`ToPsValue`); `ToPs` here
 /// is just the thin positional-return adapter the host dispatch calls.
@@ -34,23 +31,9 @@ to_ps_via_derive!(
 impl<S: ::std::hash::BuildHasher> ToPs for HashMap<String, PsValue, S> {
     fn to_ps(v: Self) -> Option<PsValue> {
         // A genuinely dynamic dictionary (Prompt result: field names known only
-        // at runtime), so it stays a hand-built Hashtable rather than a derived
+        // at runtime), so it stays a plain Hashtable rather than a derived
         // struct. (Some WS-Man endpoints reject `PSPrimitiveDictionary` for
         // Prompt responses but accept a plain `Hashtable`.)
-        let mut dict = BTreeMap::new();
-        for (k, vv) in v {
-            dict.insert(PsValue::Primitive(PsPrimitiveValue::Str(k)), vv);
-        }
-        Some(PsValue::Object(ComplexObject {
-            type_def: Some(PsType {
-                type_names: vec![
-                    Cow::Borrowed("System.Collections.Hashtable"),
-                    Cow::Borrowed("System.Object"),
-                ],
-            }),
-            to_string: None,
-            content: ComplexObjectContent::Container(Container::Dictionary(dict)),
-            properties: Properties::new(),
-        }))
+        Some(PsDict::from(v).to_hashtable())
     }
 }
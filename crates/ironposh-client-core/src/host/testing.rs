@@ -0,0 +1,870 @@
+//! A scriptable fake host for tests: answers every host call without a
+//! terminal.
+//!
+//! Answers for the interactive calls (ReadLine, Prompt, PromptForChoice,
+//! credentials, ReadKey) are queued up front; a call with nothing queued
+//! fails instead of blocking. RawUI calls act on a virtual 120x30 buffer
+//! that records every change made to it, and text written by the remote
+//! side is drawn into the buffer like a console would.
+
+use std::collections::{HashMap, VecDeque};
+
+use ironposh_psrp::PsValue;
+
+use super::{
+    BufferCell, ChoiceDescription, Coordinates, HostCall, HostError, KeyInfo, PSCredential,
+    ProgressRecord, Rectangle, Size, Submission,
+};
+use crate::connector::UserOperation;
+
+/// Size of the virtual buffer a new [`FakeHost`] starts with.
+pub const DEFAULT_SIZE: Size = Size {
+    width: 120,
+    height: 30,
+};
+
+/// A change the remote side made through the RawUI interface, in call order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawUiChange {
+    ForegroundColor(i32),
+    BackgroundColor(i32),
+    CursorPosition(Coordinates),
+    WindowPosition(Coordinates),
+    CursorSize(i32),
+    BufferSize(Size),
+    WindowSize(Size),
+    WindowTitle(String),
+    BufferContents {
+        rectangle: Rectangle,
+        fill: BufferCell,
+    },
+    ScrollBufferContents {
+        source: Rectangle,
+        destination: Coordinates,
+        clip: Rectangle,
+        fill: BufferCell,
+    },
+    FlushInputBuffer,
+}
+
+/// A line written to one of the host's message streams.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostMessage {
+    Error(String),
+    Warning(String),
+    Verbose(String),
+    Debug(String),
+}
+
+/// The console screen buffer behind the fake host's RawUI.
+#[derive(Debug, Clone)]
+pub struct VirtualBuffer {
+    size: Size,
+    window_size: Size,
+    window_position: Coordinates,
+    cursor: Coordinates,
+    cursor_size: i32,
+    foreground: i32,
+    background: i32,
+    title: String,
+    /// Row-major, `size.width * size.height` cells.
+    cells: Vec<BufferCell>,
+    changes: Vec<RawUiChange>,
+}
+
+impl VirtualBuffer {
+    pub fn new(size: Size) -> Self {
+        let mut buffer = Self {
+            size: Size {
+                width: 0,
+                height: 0,
+            },
+            window_size: size,
+            window_position: Coordinates { x: 0, y: 0 },
+            cursor: Coordinates { x: 0, y: 0 },
+            cursor_size: 25,
+            foreground: 7,
+            background: 0,
+            title: String::new(),
+            cells: Vec::new(),
+            changes: Vec::new(),
+        };
+        buffer.resize(size);
+        buffer
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn cursor(&self) -> Coordinates {
+        self.cursor
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Every RawUI change so far, oldest first.
+    pub fn changes(&self) -> &[RawUiChange] {
+        &self.changes
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> Option<&BufferCell> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    /// The text of row `y` without trailing blanks.
+    pub fn line(&self, y: i32) -> String {
+        let text: String = (0..self.size.width)
+            .filter_map(|x| self.cell(x, y))
+            .map(|cell| cell.character)
+            .collect();
+        text.trim_end().to_owned()
+    }
+
+    /// Draw `text` at the cursor, wrapping at the buffer width and scrolling
+    /// at the bottom.
+    pub fn write(&mut self, text: &str, foreground: i32, background: i32) {
+        for ch in text.chars() {
+            match ch {
+                '\n' => self.new_line(),
+                '\r' => self.cursor.x = 0,
+                _ => {
+                    if let Some(index) = self.index(self.cursor.x, self.cursor.y) {
+                        self.cells[index] = BufferCell {
+                            character: ch,
+                            foreground,
+                            background,
+                            flags: 0,
+                        };
+                    }
+                    self.cursor.x += 1;
+                    if self.cursor.x >= self.size.width {
+                        self.new_line();
+                    }
+                }
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.cursor.x = 0;
+        if self.cursor.y + 1 < self.size.height {
+            self.cursor.y += 1;
+            return;
+        }
+        let width = (self.size.width.max(0) as usize).min(self.cells.len());
+        let blank = self.blank();
+        self.cells.drain(..width);
+        self.cells.extend(std::iter::repeat_n(blank, width));
+    }
+
+    fn resize(&mut self, size: Size) {
+        let blank = self.blank();
+        let mut cells = vec![blank; (size.width.max(0) * size.height.max(0)) as usize];
+        for y in 0..size.height.min(self.size.height) {
+            for x in 0..size.width.min(self.size.width) {
+                if let Some(index) = self.index(x, y) {
+                    cells[(y * size.width + x) as usize] = self.cells[index].clone();
+                }
+            }
+        }
+        self.cells = cells;
+        self.size = size;
+        self.cursor.x = self.cursor.x.clamp(0, (size.width - 1).max(0));
+        self.cursor.y = self.cursor.y.clamp(0, (size.height - 1).max(0));
+    }
+
+    /// The cells of `rectangle`, clipped to the buffer.
+    fn contents(&self, rectangle: Rectangle) -> Vec<Vec<BufferCell>> {
+        let rectangle = self.clip(rectangle);
+        (rectangle.top..=rectangle.bottom)
+            .map(|y| {
+                (rectangle.left..=rectangle.right)
+                    .filter_map(|x| self.cell(x, y).cloned())
+                    .collect()
+            })
+            .filter(|row: &Vec<BufferCell>| !row.is_empty())
+            .collect()
+    }
+
+    /// Fill `rectangle` with `fill`; all -1 means the whole buffer.
+    fn fill(&mut self, rectangle: Rectangle, fill: &BufferCell) {
+        let rectangle = self.clip(rectangle);
+        for y in rectangle.top..=rectangle.bottom {
+            for x in rectangle.left..=rectangle.right {
+                if let Some(index) = self.index(x, y) {
+                    self.cells[index] = fill.clone();
+                }
+            }
+        }
+    }
+
+    /// Move `source` to `destination`, changing only cells inside `clip`;
+    /// the part of `source` left behind is filled with `fill`.
+    fn scroll(
+        &mut self,
+        source: Rectangle,
+        destination: Coordinates,
+        clip: Rectangle,
+        fill: &BufferCell,
+    ) {
+        let source = self.clip(source);
+        let clip = self.clip(clip);
+        let inside = |x: i32, y: i32| {
+            (clip.left..=clip.right).contains(&x) && (clip.top..=clip.bottom).contains(&y)
+        };
+        let before = self.cells.clone();
+        for y in source.top..=source.bottom {
+            for x in source.left..=source.right {
+                if let Some(index) = self.index(x, y).filter(|_| inside(x, y)) {
+                    self.cells[index] = fill.clone();
+                }
+            }
+        }
+        for y in source.top..=source.bottom {
+            for x in source.left..=source.right {
+                let (to_x, to_y) = (
+                    destination.x + x - source.left,
+                    destination.y + y - source.top,
+                );
+                if let (Some(from), Some(to)) = (self.index(x, y), self.index(to_x, to_y))
+                    && inside(to_x, to_y)
+                {
+                    self.cells[to] = before[from].clone();
+                }
+            }
+        }
+    }
+
+    fn clip(&self, rectangle: Rectangle) -> Rectangle {
+        let whole = Rectangle {
+            left: 0,
+            top: 0,
+            right: self.size.width - 1,
+            bottom: self.size.height - 1,
+        };
+        if [
+            rectangle.left,
+            rectangle.top,
+            rectangle.right,
+            rectangle.bottom,
+        ] == [-1; 4]
+        {
+            return whole;
+        }
+        Rectangle {
+            left: rectangle.left.max(whole.left),
+            top: rectangle.top.max(whole.top),
+            right: rectangle.right.min(whole.right),
+            bottom: rectangle.bottom.min(whole.bottom),
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if (0..self.size.width).contains(&x) && (0..self.size.height).contains(&y) {
+            Some((y * self.size.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn blank(&self) -> BufferCell {
+        BufferCell {
+            character: ' ',
+            foreground: self.foreground,
+            background: self.background,
+            flags: 0,
+        }
+    }
+}
+
+/// Answers host calls from queued answers and a [`VirtualBuffer`].
+///
+/// Queue answers with the `with_*` builders, then hand every
+/// `ActiveSessionOutput::HostCall` to [`FakeHost::respond`] and feed the
+/// returned operation back to the session. Interactive calls with nothing
+/// queued are cancelled, the same as a user pressing Ctrl+C.
+#[derive(Debug)]
+pub struct FakeHost {
+    read_lines: VecDeque<String>,
+    secure_strings: VecDeque<Vec<u8>>,
+    prompts: VecDeque<HashMap<String, PsValue>>,
+    choices: VecDeque<i32>,
+    multiple_choices: VecDeque<Vec<i32>>,
+    credentials: VecDeque<PSCredential>,
+    keys: VecDeque<KeyInfo>,
+    buffer: VirtualBuffer,
+    calls: Vec<&'static str>,
+    written: String,
+    messages: Vec<HostMessage>,
+    progress: Vec<ProgressRecord>,
+    /// Labels of the choices offered by each PromptForChoice call.
+    offered_choices: Vec<Vec<String>>,
+    instance_id: uuid::Uuid,
+    should_exit: Option<i32>,
+    nested_prompts: usize,
+    runspaces: Vec<PsValue>,
+}
+
+impl Default for FakeHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeHost {
+    pub fn new() -> Self {
+        Self {
+            read_lines: VecDeque::new(),
+            secure_strings: VecDeque::new(),
+            prompts: VecDeque::new(),
+            choices: VecDeque::new(),
+            multiple_choices: VecDeque::new(),
+            credentials: VecDeque::new(),
+            keys: VecDeque::new(),
+            buffer: VirtualBuffer::new(DEFAULT_SIZE),
+            calls: Vec::new(),
+            written: String::new(),
+            messages: Vec::new(),
+            progress: Vec::new(),
+            offered_choices: Vec::new(),
+            instance_id: uuid::Uuid::nil(),
+            should_exit: None,
+            nested_prompts: 0,
+            runspaces: Vec::new(),
+        }
+    }
+
+    /// Queue the answer to the next ReadLine.
+    pub fn with_read_line(mut self, line: impl Into<String>) -> Self {
+        self.read_lines.push_back(line.into());
+        self
+    }
+
+    /// Queue the answer to the next ReadLineAsSecureString.
+    pub fn with_secure_string(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.secure_strings.push_back(secret.into());
+        self
+    }
+
+    /// Queue the field values returned by the next Prompt.
+    pub fn with_prompt_answer(mut self, fields: HashMap<String, PsValue>) -> Self {
+        self.prompts.push_back(fields);
+        self
+    }
+
+    /// Queue the index returned by the next PromptForChoice.
+    pub fn with_choice(mut self, index: i32) -> Self {
+        self.choices.push_back(index);
+        self
+    }
+
+    /// Queue the indexes returned by the next PromptForChoiceMultipleSelection.
+    pub fn with_choices(mut self, indexes: Vec<i32>) -> Self {
+        self.multiple_choices.push_back(indexes);
+        self
+    }
+
+    /// Queue the credential returned by the next PromptForCredential.
+    pub fn with_credential(mut self, credential: PSCredential) -> Self {
+        self.credentials.push_back(credential);
+        self
+    }
+
+    /// Queue a key for ReadKey; GetKeyAvailable reports whether any is left.
+    pub fn with_key(mut self, key: KeyInfo) -> Self {
+        self.keys.push_back(key);
+        self
+    }
+
+    pub fn with_buffer_size(mut self, size: Size) -> Self {
+        self.buffer = VirtualBuffer::new(size);
+        self
+    }
+
+    pub fn buffer(&self) -> &VirtualBuffer {
+        &self.buffer
+    }
+
+    /// Names of the host methods called so far, in order.
+    pub fn calls(&self) -> &[&'static str] {
+        &self.calls
+    }
+
+    /// Text written through Write and WriteLine.
+    pub fn written(&self) -> &str {
+        &self.written
+    }
+
+    pub fn messages(&self) -> &[HostMessage] {
+        &self.messages
+    }
+
+    pub fn progress(&self) -> &[ProgressRecord] {
+        &self.progress
+    }
+
+    pub fn offered_choices(&self) -> &[Vec<String>] {
+        &self.offered_choices
+    }
+
+    pub fn should_exit(&self) -> Option<i32> {
+        self.should_exit
+    }
+
+    pub fn nested_prompts(&self) -> usize {
+        self.nested_prompts
+    }
+
+    /// Answer `call` and turn the answer into the operation that sends it:
+    /// `SubmitHostResponse`, or `CancelHostCall` when nothing was queued.
+    pub fn respond(&mut self, call: HostCall) -> UserOperation {
+        let (scope, call_id, method) = (call.scope(), call.call_id(), call.method());
+        self.answer(call).map_or_else(
+            |e| UserOperation::CancelHostCall {
+                scope,
+                call_id,
+                method,
+                reason: Some(e.to_string()),
+            },
+            |submission| UserOperation::SubmitHostResponse {
+                submission,
+                scope,
+                call_id,
+            },
+        )
+    }
+
+    /// Answer `call`. Fails for an interactive call with no queued answer.
+    pub fn answer(&mut self, call: HostCall) -> Result<Submission, HostError> {
+        let method = call.method_name();
+        self.calls.push(method);
+        let unanswered = || HostError::Other(format!("no answer queued for {method}"));
+        let (fg, bg) = (self.buffer.foreground, self.buffer.background);
+
+        Ok(match call {
+            HostCall::GetName { transport } => transport
+                .into_parts()
+                .1
+                .accept_result("FakeHost".to_owned()),
+            HostCall::GetVersion { transport } => {
+                transport.into_parts().1.accept_result("1.0.0.0".to_owned())
+            }
+            HostCall::GetInstanceId { transport } => {
+                transport.into_parts().1.accept_result(self.instance_id)
+            }
+            HostCall::GetCurrentCulture { transport } => {
+                transport.into_parts().1.accept_result("en-US".to_owned())
+            }
+            HostCall::GetCurrentUICulture { transport } => {
+                transport.into_parts().1.accept_result("en-US".to_owned())
+            }
+            HostCall::SetShouldExit { transport } => {
+                let ((code,), rt) = transport.into_parts();
+                self.should_exit = Some(code);
+                rt.accept_result(())
+            }
+            HostCall::EnterNestedPrompt { transport } => {
+                self.nested_prompts += 1;
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::ExitNestedPrompt { transport } => {
+                self.nested_prompts = self.nested_prompts.saturating_sub(1);
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::NotifyBeginApplication { transport } => {
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::NotifyEndApplication { transport } => {
+                transport.into_parts().1.accept_result(())
+            }
+
+            HostCall::ReadLine { transport } => {
+                let line = self.read_lines.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(line)
+            }
+            HostCall::ReadLineAsSecureString { transport } => {
+                let secret = self.secure_strings.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(secret)
+            }
+            HostCall::Write1 { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.write(&text, fg, bg);
+                rt.accept_result(())
+            }
+            HostCall::Write2 { transport } => {
+                let ((fg, bg, text), rt) = transport.into_parts();
+                self.write(&text, fg, bg);
+                rt.accept_result(())
+            }
+            HostCall::WriteLine1 { transport } => {
+                self.write("\n", fg, bg);
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::WriteLine2 { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.write(&format!("{text}\n"), fg, bg);
+                rt.accept_result(())
+            }
+            HostCall::WriteLine3 { transport } => {
+                let ((fg, bg, text), rt) = transport.into_parts();
+                self.write(&format!("{text}\n"), fg, bg);
+                rt.accept_result(())
+            }
+            HostCall::WriteErrorLine { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.message(HostMessage::Error(text));
+                rt.accept_result(())
+            }
+            HostCall::WriteDebugLine { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.message(HostMessage::Debug(text));
+                rt.accept_result(())
+            }
+            HostCall::WriteVerboseLine { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.message(HostMessage::Verbose(text));
+                rt.accept_result(())
+            }
+            HostCall::WriteWarningLine { transport } => {
+                let ((text,), rt) = transport.into_parts();
+                self.message(HostMessage::Warning(text));
+                rt.accept_result(())
+            }
+            HostCall::WriteProgress { transport } => {
+                let ((_source_id, record), rt) = transport.into_parts();
+                self.progress.push(record);
+                rt.accept_result(())
+            }
+            HostCall::Prompt { transport } => {
+                let fields = self.prompts.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(fields)
+            }
+            HostCall::PromptForCredential1 { transport } => {
+                let credential = self.credentials.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(credential)
+            }
+            HostCall::PromptForCredential2 { transport } => {
+                let credential = self.credentials.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(credential)
+            }
+            HostCall::PromptForChoice { transport } => {
+                let ((_caption, _message, choices, _default), rt) = transport.into_parts();
+                self.offer(&choices);
+                let index = self.choices.pop_front().ok_or_else(unanswered)?;
+                rt.accept_result(index)
+            }
+            HostCall::PromptForChoiceMultipleSelection { transport } => {
+                let ((_caption, _message, choices, _defaults), rt) = transport.into_parts();
+                self.offer(&choices);
+                let indexes = self.multiple_choices.pop_front().ok_or_else(unanswered)?;
+                rt.accept_result(indexes)
+            }
+
+            HostCall::GetForegroundColor { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.foreground),
+            HostCall::SetForegroundColor { transport } => {
+                let ((color,), rt) = transport.into_parts();
+                self.buffer.foreground = color;
+                self.change(RawUiChange::ForegroundColor(color));
+                rt.accept_result(())
+            }
+            HostCall::GetBackgroundColor { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.background),
+            HostCall::SetBackgroundColor { transport } => {
+                let ((color,), rt) = transport.into_parts();
+                self.buffer.background = color;
+                self.change(RawUiChange::BackgroundColor(color));
+                rt.accept_result(())
+            }
+            HostCall::GetCursorPosition { transport } => {
+                transport.into_parts().1.accept_result(self.buffer.cursor)
+            }
+            HostCall::SetCursorPosition { transport } => {
+                let ((position,), rt) = transport.into_parts();
+                let size = self.buffer.size;
+                self.buffer.cursor = Coordinates {
+                    x: position.x.clamp(0, (size.width - 1).max(0)),
+                    y: position.y.clamp(0, (size.height - 1).max(0)),
+                };
+                self.change(RawUiChange::CursorPosition(position));
+                rt.accept_result(())
+            }
+            HostCall::GetWindowPosition { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.window_position),
+            HostCall::SetWindowPosition { transport } => {
+                let ((position,), rt) = transport.into_parts();
+                self.buffer.window_position = position;
+                self.change(RawUiChange::WindowPosition(position));
+                rt.accept_result(())
+            }
+            HostCall::GetCursorSize { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.cursor_size),
+            HostCall::SetCursorSize { transport } => {
+                let ((cursor_size,), rt) = transport.into_parts();
+                self.buffer.cursor_size = cursor_size;
+                self.change(RawUiChange::CursorSize(cursor_size));
+                rt.accept_result(())
+            }
+            HostCall::GetBufferSize { transport } => {
+                transport.into_parts().1.accept_result(self.buffer.size)
+            }
+            HostCall::SetBufferSize { transport } => {
+                let ((size,), rt) = transport.into_parts();
+                self.buffer.resize(size);
+                self.change(RawUiChange::BufferSize(size));
+                rt.accept_result(())
+            }
+            HostCall::GetWindowSize { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.window_size),
+            HostCall::SetWindowSize { transport } => {
+                let ((size,), rt) = transport.into_parts();
+                self.buffer.window_size = size;
+                self.change(RawUiChange::WindowSize(size));
+                rt.accept_result(())
+            }
+            HostCall::GetWindowTitle { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(self.buffer.title.clone()),
+            HostCall::SetWindowTitle { transport } => {
+                let ((title,), rt) = transport.into_parts();
+                self.buffer.title.clone_from(&title);
+                self.change(RawUiChange::WindowTitle(title));
+                rt.accept_result(())
+            }
+            HostCall::GetMaxWindowSize { transport } => {
+                transport.into_parts().1.accept_result(self.buffer.size)
+            }
+            HostCall::GetMaxPhysicalWindowSize { transport } => {
+                transport.into_parts().1.accept_result(self.buffer.size)
+            }
+            HostCall::GetKeyAvailable { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(!self.keys.is_empty()),
+            HostCall::ReadKey { transport } => {
+                let key = self.keys.pop_front().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(key)
+            }
+            HostCall::FlushInputBuffer { transport } => {
+                self.keys.clear();
+                self.change(RawUiChange::FlushInputBuffer);
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::SetBufferContents1 { transport } => {
+                let ((rectangle, fill), rt) = transport.into_parts();
+                self.buffer.fill(rectangle, &fill);
+                self.change(RawUiChange::BufferContents { rectangle, fill });
+                rt.accept_result(())
+            }
+            HostCall::SetBufferContents2 { transport } => {
+                let ((rectangle, fill), rt) = transport.into_parts();
+                self.buffer.fill(rectangle, &fill);
+                self.change(RawUiChange::BufferContents { rectangle, fill });
+                rt.accept_result(())
+            }
+            HostCall::GetBufferContents { transport } => {
+                let ((rectangle,), rt) = transport.into_parts();
+                rt.accept_result(self.buffer.contents(rectangle))
+            }
+            HostCall::ScrollBufferContents { transport } => {
+                let ((source, destination, clip, fill), rt) = transport.into_parts();
+                self.buffer.scroll(source, destination, clip, &fill);
+                self.change(RawUiChange::ScrollBufferContents {
+                    source,
+                    destination,
+                    clip,
+                    fill,
+                });
+                rt.accept_result(())
+            }
+
+            HostCall::PushRunspace { transport } => {
+                let ((runspace,), rt) = transport.into_parts();
+                self.runspaces.push(runspace);
+                rt.accept_result(())
+            }
+            HostCall::PopRunspace { transport } => {
+                self.runspaces.pop();
+                transport.into_parts().1.accept_result(())
+            }
+            HostCall::GetIsRunspacePushed { transport } => transport
+                .into_parts()
+                .1
+                .accept_result(!self.runspaces.is_empty()),
+            HostCall::GetRunspace { transport } => {
+                let runspace = self.runspaces.last().cloned().ok_or_else(unanswered)?;
+                transport.into_parts().1.accept_result(runspace)
+            }
+        })
+    }
+
+    fn write(&mut self, text: &str, foreground: i32, background: i32) {
+        self.written.push_str(text);
+        self.buffer.write(text, foreground, background);
+    }
+
+    fn message(&mut self, message: HostMessage) {
+        let (HostMessage::Error(text)
+        | HostMessage::Warning(text)
+        | HostMessage::Verbose(text)
+        | HostMessage::Debug(text)) = &message;
+        self.buffer.write(
+            &format!("{text}\n"),
+            self.buffer.foreground,
+            self.buffer.background,
+        );
+        self.messages.push(message);
+    }
+
+    fn offer(&mut self, choices: &[ChoiceDescription]) {
+        self.offered_choices
+            .push(choices.iter().map(|choice| choice.label.clone()).collect());
+    }
+
+    fn change(&mut self, change: RawUiChange) {
+        self.buffer.changes.push(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::HostCallScope;
+    use ironposh_psrp::ps_value::ToPsValue;
+    use ironposh_psrp::{PipelineHostCall, RemoteHostMethodId};
+
+    fn call(method: RemoteHostMethodId, parameters: Vec<PsValue>) -> HostCall {
+        let call = PipelineHostCall {
+            call_id: 1,
+            method,
+            parameters,
+        };
+        HostCall::try_from_pipeline(HostCallScope::RunspacePool, call).expect("valid host call")
+    }
+
+    fn result(submission: Submission) -> Option<PsValue> {
+        match submission {
+            Submission::Send(response) => response.method_result,
+            Submission::NoSend => None,
+        }
+    }
+
+    #[test]
+    fn queued_answers_are_used_in_order_then_run_out() {
+        let mut host = FakeHost::new()
+            .with_read_line("first")
+            .with_read_line("second");
+
+        let answer = |host: &mut FakeHost| host.answer(call(RemoteHostMethodId::ReadLine, vec![]));
+        assert_eq!(
+            result(answer(&mut host).expect("queued")),
+            Some(PsValue::from("first".to_owned()))
+        );
+        assert_eq!(
+            result(answer(&mut host).expect("queued")),
+            Some(PsValue::from("second".to_owned()))
+        );
+        assert!(answer(&mut host).is_err());
+        assert!(matches!(
+            host.respond(call(RemoteHostMethodId::ReadLine, vec![])),
+            UserOperation::CancelHostCall { call_id: 1, .. }
+        ));
+        assert_eq!(host.calls(), ["ReadLine"; 4]);
+    }
+
+    #[test]
+    fn raw_ui_calls_change_the_virtual_buffer_and_are_recorded() {
+        let mut host = FakeHost::new();
+        let at = |x, y| Coordinates { x, y }.to_ps_value();
+
+        host.answer(call(RemoteHostMethodId::SetCursorPosition, vec![at(2, 1)]))
+            .expect("SetCursorPosition");
+        host.answer(call(
+            RemoteHostMethodId::Write1,
+            vec![PsValue::from("hi".to_owned())],
+        ))
+        .expect("Write1");
+        let cursor = host
+            .answer(call(RemoteHostMethodId::GetCursorPosition, vec![]))
+            .expect("GetCursorPosition");
+        assert_eq!(result(cursor), Some(at(4, 1)));
+        assert_eq!(host.buffer().line(1), "  hi");
+
+        let source = Rectangle {
+            left: 0,
+            top: 1,
+            right: 119,
+            bottom: 1,
+        };
+        let fill = BufferCell {
+            character: ' ',
+            foreground: 7,
+            background: 0,
+            flags: 0,
+        };
+        host.answer(call(
+            RemoteHostMethodId::ScrollBufferContents,
+            vec![
+                source.to_ps_value(),
+                at(0, 0),
+                Rectangle {
+                    left: -1,
+                    top: -1,
+                    right: -1,
+                    bottom: -1,
+                }
+                .to_ps_value(),
+                fill.to_ps_value(),
+            ],
+        ))
+        .expect("ScrollBufferContents");
+        assert_eq!(host.buffer().line(0), "  hi");
+        assert_eq!(host.buffer().line(1), "");
+
+        assert_eq!(host.buffer().size(), DEFAULT_SIZE);
+        assert_eq!(
+            host.buffer().changes(),
+            [
+                RawUiChange::CursorPosition(Coordinates { x: 2, y: 1 }),
+                RawUiChange::ScrollBufferContents {
+                    source,
+                    destination: Coordinates { x: 0, y: 0 },
+                    clip: Rectangle {
+                        left: -1,
+                        top: -1,
+                        right: -1,
+                        bottom: -1,
+                    },
+                    fill,
+                },
+            ]
+        );
+        assert_eq!(host.written(), "hi");
+    }
+
+    #[test]
+    fn writes_wrap_and_scroll_at_the_buffer_edges() {
+        let mut buffer = VirtualBuffer::new(Size {
+            width: 4,
+            height: 2,
+        });
+        buffer.write("abcdef\nxy", 7, 0);
+        assert_eq!(buffer.line(0), "ef");
+        assert_eq!(buffer.line(1), "xy");
+        assert_eq!(buffer.cursor(), Coordinates { x: 2, y: 1 });
+    }
+}
@@ -1,4 +1,6 @@
-use ironposh_psrp::{CommandParameter, PsValue};
+use std::fmt::Write as _;
+
+use ironposh_psrp::{CommandParameter, PsDict, PsPrimitiveValue, PsValue};
 
 use crate::runspace_pool::PsInvocationState;
 
@@ -16,20 +18,164 @@ pub struct PipelineCommand {
     pub command_text: String,
     pub is_script: bool,
     pub parameters: Vec<Parameter>,
+    /// `UseLocalScope`: run in a child scope (`true`) rather than the caller's.
+    /// `None` leaves the choice to the server.
+    pub use_local_scope: Option<bool>,
 }
 
 /// Represents a pipeline specification at the API boundary
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PipelineSpec {
     pub commands: Vec<PipelineCommand>,
+    /// Record the pipeline in the remote session's history (`Get-History`),
+    /// as if it had been typed at the console.
+    pub add_to_history: bool,
+}
+
+/// Characters per PIPELINE_INPUT object when a script is sent as input, see
+/// [`PipelineSpec::script_from_input`]. Keeps each Send envelope well below
+/// the server's `MaxEnvelopeSizekb`.
+pub const SCRIPT_INPUT_CHUNK_CHARS: usize = 32 * 1024;
+
+/// Joins the string chunks piped into it and dot-sources the result, so the
+/// script runs in the session scope as if it had been typed.
+const SCRIPT_FROM_INPUT: &str = ". ([scriptblock]::Create(-join @($input)))";
+
+/// Sets each entry of the `Variables` parameter as a process environment
+/// variable. Names and values only ever arrive as parameter values, so
+/// nothing in them is parsed as script.
+const SET_ENVIRONMENT: &str = "param([hashtable]$Variables) \
+foreach ($name in $Variables.Keys) { Set-Item -LiteralPath \"Env:$name\" -Value $Variables[$name] }";
+
+/// Client-side limits for a single pipeline invocation, enforced by the
+/// frontend's timer (the session itself has no clock).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineOptions {
+    /// Stop the pipeline and end its stream with `UserEvent::PipelineTimedOut`
+    /// if it has not finished after this long. `None` waits indefinitely.
+    pub timeout: Option<std::time::Duration>,
+    /// `$ErrorActionPreference = 'Stop'` emulation: stop the pipeline at its
+    /// first `ErrorRecord`. The record is still delivered; the stream then ends
+    /// once the stopped pipeline finishes.
+    pub stop_on_error: bool,
+}
+
+impl PipelineSpec {
+    /// Readable rendering (`Get-Item -Path C:\ | Out-String -Stream`) for logs
+    /// and audit trails. Not meant to be parsed back.
+    pub fn display_text(&self) -> String {
+        self.commands
+            .iter()
+            .map(PipelineCommand::display_text)
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    /// A pipeline running a whole script (functions, here-strings, several
+    /// statements) as a single command, see [`PipelineCommand::new_script_file`].
+    pub fn script_file(script: &str) -> Self {
+        Self {
+            commands: vec![PipelineCommand::new_script_file(script)],
+            add_to_history: false,
+        }
+    }
+
+    /// A pipeline that rebuilds a script from its pipeline input and runs it.
+    /// Invoked with [`script_input_chunks`] as input, a very large script (a
+    /// big paste) travels in several Send envelopes instead of one giant
+    /// CreatePipeline.
+    pub fn script_from_input() -> Self {
+        Self {
+            commands: vec![PipelineCommand::new_script(SCRIPT_FROM_INPUT.to_owned())],
+            add_to_history: false,
+        }
+    }
+
+    /// A pipeline setting `variables` in the session's environment (`$env:`),
+    /// e.g. forwarded from the local one when the session starts. It writes
+    /// no output.
+    pub fn set_environment<K, V>(variables: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let variables: PsDict = variables
+            .into_iter()
+            .map(|(name, value)| (name.into(), PsValue::from(value.into())))
+            .collect();
+        Self {
+            commands: vec![
+                PipelineCommand::new_script(SET_ENVIRONMENT.to_owned()).with_parameter(
+                    Parameter::Named {
+                        name: "Variables".to_owned(),
+                        value: variables.to_hashtable(),
+                    },
+                ),
+            ],
+            add_to_history: false,
+        }
+    }
+}
+
+/// Split `script` into string input objects of at most `max_chars`
+/// characters for [`PipelineSpec::script_from_input`].
+pub fn script_input_chunks(script: &str, max_chars: usize) -> Vec<PsValue> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = script;
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .nth(max_chars)
+            .map_or(rest.len(), |(index, _)| index);
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(PsValue::Primitive(PsPrimitiveValue::Str(chunk.to_owned())));
+        rest = tail;
+    }
+    chunks
 }
 
 impl PipelineCommand {
+    fn display_text(&self) -> String {
+        let mut text = self.command_text.clone();
+        for parameter in &self.parameters {
+            text.push(' ');
+            match parameter {
+                Parameter::Named { name, value } => {
+                    let _ = write!(text, "-{} {value}", name.trim_start_matches('-'));
+                }
+                Parameter::Positional { value } => text.push_str(&value.to_string()),
+                Parameter::Switch { name, value } => {
+                    text.push('-');
+                    text.push_str(name.trim_start_matches('-'));
+                    if !value {
+                        text.push_str(":$false");
+                    }
+                }
+            }
+        }
+        text
+    }
+
     pub fn new_script(script: String) -> Self {
         Self {
             command_text: script,
             is_script: true,
             parameters: Vec::new(),
+            use_local_scope: None,
+        }
+    }
+
+    /// Wrap the contents of a script file as one `IsScript` command in its own
+    /// scope. A leading byte-order mark (which would otherwise be parsed as part
+    /// of the first token) is dropped and line endings are normalized to `\n`.
+    pub fn new_script_file(script: &str) -> Self {
+        let script = script.strip_prefix('\u{feff}').unwrap_or(script);
+        Self {
+            command_text: script.replace("\r\n", "\n").replace('\r', "\n"),
+            is_script: true,
+            parameters: Vec::new(),
+            use_local_scope: Some(true),
         }
     }
 
@@ -38,6 +184,7 @@ impl PipelineCommand {
             command_text: command,
             is_script: false,
             parameters: Vec::new(),
+            use_local_scope: None,
         }
     }
 
@@ -75,7 +222,14 @@ pub struct ExecutionResult {
 pub struct Pipeline {
     state: PsInvocationState,
     pub(crate) commands: Vec<PipelineCommand>,
+    pub(crate) add_to_history: bool,
+    /// Created with `NoInput = false`: the client feeds it PIPELINE_INPUT
+    /// messages and closes them with END_OF_PIPELINE_INPUT.
+    pub(crate) takes_input: bool,
     pub(crate) results: ExecutionResult,
+    /// The pipeline's id and the timeline its transitions go to.
+    #[cfg(feature = "diagnostics")]
+    timeline: Option<(uuid::Uuid, crate::connector::StateTimeline)>,
 }
 
 impl Pipeline {
@@ -83,10 +237,31 @@ impl Pipeline {
         Self {
             state: PsInvocationState::NotStarted,
             commands: Vec::new(),
+            add_to_history: false,
+            takes_input: false,
             results: ExecutionResult::default(),
+            #[cfg(feature = "diagnostics")]
+            timeline: None,
         }
     }
 
+    /// Record the transitions of pipeline `id` into `timeline`.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn with_timeline(
+        mut self,
+        id: uuid::Uuid,
+        timeline: Option<crate::connector::StateTimeline>,
+    ) -> Self {
+        self.timeline = timeline.map(|timeline| {
+            timeline.record(
+                crate::connector::timeline::Machine::Pipeline(id),
+                format!("{:?}", self.state),
+            );
+            (id, timeline)
+        });
+        self
+    }
+
     pub(crate) fn add_information_record(&mut self, record: ironposh_psrp::InformationRecord) {
         self.results.information_records.push(record);
     }
@@ -106,6 +281,13 @@ impl Pipeline {
 
     /// Sets the invocation state of the pipeline.
     pub(crate) fn set_state(&mut self, state: PsInvocationState) {
+        #[cfg(feature = "diagnostics")]
+        if let Some((id, timeline)) = &self.timeline {
+            timeline.record(
+                crate::connector::timeline::Machine::Pipeline(*id),
+                format!("{state:?}"),
+            );
+        }
         self.state = state;
     }
 
@@ -134,6 +316,7 @@ impl Pipeline {
                 ironposh_psrp::Command::builder()
                     .cmd(cmd.command_text.clone())
                     .is_script(cmd.is_script)
+                    .use_local_scope(cmd.use_local_scope)
                     .args(
                         cmd.parameters
                             .iter()
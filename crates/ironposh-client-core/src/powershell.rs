@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub use ironposh_winrm::rsp::signal::SignalCode;
+
 /// A handle to a PowerShell pipeline managed by a `RunspacePool`.
 ///
 /// This struct is a lightweight, copyable identifier for a specific pipeline.
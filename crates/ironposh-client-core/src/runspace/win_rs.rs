@@ -1,29 +1,82 @@
+use std::{collections::BTreeMap, ops::Range};
+
 use base64::Engine;
 use ironposh_winrm::{
     cores::{Attribute, DesiredStreamTag, StreamTag, Tag, Text, Time},
     rsp::{
         commandline::CommandLineValue,
         receive::{CommandStateTag, CommandStateValue, ReceiveTag, ReceiveValue},
-        shell_value::{ShellTag, ShellValue},
+        shell_value::{EnvironmentValue, ShellTag, ShellValue},
+        signal::SignalCode,
     },
     soap::{SoapEnvelope, body::SoapBody},
-    ws_management::{OptionSetValue, SelectorSetValue, WsAction, WsMan},
+    ws_management::{OptionSetValue, SelectorSetValue, WellKnownOption, WsAction, WsMan},
 };
-use ironposh_xml::builder::Element;
-use tracing::{debug, instrument};
+use ironposh_xml::builder::SerializationScratch;
+use tracing::{debug, instrument, warn};
 use uuid::Uuid;
 
+/// Server-side shell settings carried in the `rsp:Shell` body of the Create
+/// request. After the create response is accepted, `idle_time_out` and
+/// `output_streams` hold what the server actually granted.
 #[derive(Debug, Clone, typed_builder::TypedBuilder)]
-pub struct WinRunspace {
-    #[builder(default = "stdin pr".to_string())]
-    input_streams: String,
-    #[builder(default = "stdout".to_string())]
-    output_streams: String,
+pub struct ShellOptions {
+    /// `rsp:IdleTimeOut` in seconds: the server closes the shell after this
+    /// long without a request. `None` leaves it to the server's policy.
     #[builder(default, setter(strip_option))]
-    #[allow(dead_code)]
-    environment: Option<std::collections::HashMap<String, String>>,
+    pub idle_time_out: Option<f64>,
+    /// `rsp:Lifetime` in seconds: upper bound on the shell's total lifetime.
     #[builder(default, setter(strip_option))]
-    idle_time_out: Option<f64>,
+    pub lifetime: Option<f64>,
+    /// `rsp:InputStreams`, space separated.
+    #[builder(default = "stdin pr".to_string(), setter(into))]
+    pub input_streams: String,
+    /// `rsp:OutputStreams`, space separated.
+    #[builder(default = "stdout".to_string(), setter(into))]
+    pub output_streams: String,
+    /// `rsp:Environment` variables for the shell process, sent in name order.
+    #[builder(default)]
+    pub environment: BTreeMap<String, String>,
+    /// `rsp:WorkingDirectory`. Honoured by WinRS (cmd) shells; PowerShell
+    /// endpoints ignore it.
+    #[builder(default, setter(strip_option, into))]
+    pub working_directory: Option<String>,
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// The shell as the server named it in the CreateResponse's
+/// `x:ResourceCreated` endpoint reference. Every later request to the shell
+/// (Receive, Command, Send, Signal, Delete, ...) is addressed with this
+/// resource URI and selector set, sent back verbatim.
+#[derive(Debug, Clone)]
+pub struct ShellReference {
+    pub shell_id: String,
+    pub resource_uri: String,
+    pub selectors: SelectorSetValue,
+}
+
+impl ShellReference {
+    /// A reference to a shell known only by id, as on the Connect path where
+    /// there is no CreateResponse to read it from.
+    pub fn from_shell_id(resource_uri: impl Into<String>, shell_id: impl Into<String>) -> Self {
+        let shell_id = shell_id.into();
+        Self {
+            selectors: SelectorSetValue::new().add_selector("ShellId", shell_id.clone()),
+            shell_id,
+            resource_uri: resource_uri.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, typed_builder::TypedBuilder)]
+pub struct WinRunspace {
+    #[builder(default)]
+    options: ShellOptions,
     #[builder(default, setter(strip_option))]
     name: Option<String>,
 
@@ -39,8 +92,10 @@ pub struct WinRunspace {
     #[builder(default)]
     codepage: Option<u32>,
 
-    #[builder(default)]
-    shell_id: Option<String>,
+    /// Set once the shell exists (create response accepted, or seeded for
+    /// Connect).
+    #[builder(default, setter(strip_option))]
+    reference: Option<ShellReference>,
     #[builder(default)]
     owner: Option<String>,
     #[builder(default)]
@@ -50,23 +105,26 @@ pub struct WinRunspace {
     #[builder(default)]
     shell_inactivity: Option<String>,
 
-    #[builder(default)]
-    selector_set: SelectorSetValue,
-
     #[builder(default)]
     opened: bool,
 
     #[builder(default)]
     signal_messages: std::collections::HashMap<Uuid, Uuid>,
+
+    /// Highest ReceiveResponse `SequenceID` accepted per Receive, keyed by
+    /// CommandId (`None` for the runspace pool's own streams).
+    #[builder(default)]
+    receive_sequence: std::collections::HashMap<Option<Uuid>, u64>,
 }
 
 impl WinRunspace {
-    pub fn open<'a>(
-        &'a self,
-        ws_man: &'a WsMan,
+    pub fn open<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
         option_set: Option<OptionSetValue>,
-        open_content: &'a str,
-    ) -> impl Into<Element<'a>> {
+        open_content: &str,
+    ) -> Result<&'s str, crate::PwshCoreError> {
         let shell = Tag::from_name(ShellTag)
             .with_attribute(ironposh_winrm::cores::Attribute::ShellId(
                 self.id.to_string().into(),
@@ -76,10 +134,22 @@ impl WinRunspace {
             ))
             .with_declaration(ironposh_winrm::cores::Namespace::WsmanShell);
 
+        let environment = (!self.options.environment.is_empty()).then(|| {
+            Tag::new(EnvironmentValue::from_pairs(
+                self.options
+                    .environment
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            ))
+        });
+
         let shell_value = ShellValue::builder()
-            .input_streams(self.input_streams.as_ref())
-            .output_streams(self.output_streams.as_ref())
-            .idle_time_out_opt(self.idle_time_out.map(Time).map(Tag::new))
+            .environment_opt(environment)
+            .working_directory_opt(self.options.working_directory.as_deref().map(Tag::new))
+            .lifetime_opt(self.options.lifetime.map(Time).map(Tag::new))
+            .input_streams(self.options.input_streams.as_ref())
+            .output_streams(self.options.output_streams.as_ref())
+            .idle_time_out_opt(self.options.idle_time_out.map(Time).map(Tag::new))
             .creation_xml(
                 Tag::new(open_content)
                     .with_declaration(ironposh_winrm::cores::Namespace::PowerShellRemoting),
@@ -90,29 +160,31 @@ impl WinRunspace {
 
         let mut option_set = option_set.unwrap_or_default();
 
-        if let Some(profile) = self.no_profile {
-            option_set = option_set.add_option("WINRS_NOPROFILE", profile.to_string());
+        if let Some(no_profile) = self.no_profile {
+            option_set = option_set.add_well_known(WellKnownOption::NoProfile(no_profile));
         }
 
         if let Some(codepage) = self.codepage {
-            option_set = option_set.add_option("WINRS_CODEPAGE", codepage.to_string());
+            option_set = option_set.add_well_known(WellKnownOption::Codepage(codepage));
         }
 
-        ws_man.invoke(
+        Ok(ws_man.invoke(
+            scratch,
             &WsAction::Create,
             None,
             SoapBody::builder().shell(shell).build(),
             Some(option_set),
             None,
-        )
+        )?)
     }
 
-    pub fn fire_receive<'a>(
-        &'a self,
-        ws_man: &'a WsMan,
+    pub fn fire_receive<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
         desired_streams: Vec<crate::runspace_pool::DesiredStream>,
         hold_secs: Option<f64>,
-    ) -> impl Into<Element<'a>> {
+    ) -> Result<&'s str, crate::PwshCoreError> {
         // Group streams by CommandId - streams with the same CommandId go into one DesiredStream element
         let mut grouped_streams: std::collections::BTreeMap<Option<uuid::Uuid>, Vec<String>> =
             std::collections::BTreeMap::new();
@@ -152,28 +224,45 @@ impl WinRunspace {
         let option_set = OptionSetValue::default()
             .add_option("WSMAN_CMDSHELL_OPTION_KEEPALIVE", true.to_string());
 
-        let selector_set = self
-            .shell_id
-            .as_ref()
-            .map(|shell_id| SelectorSetValue::new().add_selector("ShellId", shell_id));
-
-        ws_man.invoke_with_operation_timeout(
+        let request = ws_man.envelope(
             &WsAction::ShellReceive,
-            Some(&self.resource_uri),
+            Some(self.target_resource_uri()),
             SoapBody::builder().receive(receive_tag).build(),
             Some(option_set),
-            selector_set,
+            self.target_selectors(),
             hold_secs,
-        )
+        );
+        Ok(scratch.render(&request.into_element())?)
     }
 
     /// Server-assigned shell id (set when the create response is accepted).
     pub(crate) fn shell_id(&self) -> Option<&str> {
-        self.shell_id.as_deref()
+        self.reference.as_ref().map(|r| r.shell_id.as_str())
+    }
+
+    /// The shell's endpoint reference (set when the create response is accepted).
+    pub(crate) const fn reference(&self) -> Option<&ShellReference> {
+        self.reference.as_ref()
+    }
+
+    /// Resource URI for requests to the existing shell.
+    fn target_resource_uri(&self) -> &str {
+        self.reference
+            .as_ref()
+            .map_or(&self.resource_uri, |r| &r.resource_uri)
+    }
+
+    /// Selectors naming the existing shell.
+    fn target_selectors(&self) -> Option<SelectorSetValue> {
+        self.reference.as_ref().map(|r| r.selectors.clone())
     }
 
     /// Build a Disconnect request targeting this shell (MS-WSMV 3.1.4.13).
-    pub(crate) fn fire_disconnect<'a>(&'a self, ws_man: &'a WsMan) -> impl Into<Element<'a>> {
+    pub(crate) fn fire_disconnect<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
+    ) -> Result<&'s str, crate::PwshCoreError> {
         use ironposh_winrm::{
             cores::Namespace,
             rsp::disconnect::{DisconnectTag, DisconnectValue},
@@ -183,25 +272,44 @@ impl WinRunspace {
             .with_declaration(Namespace::WsmanShell)
             .with_value(DisconnectValue::builder().build());
 
-        ws_man.invoke(
+        Ok(ws_man.invoke(
+            scratch,
             &WsAction::Disconnect,
-            Some(&self.resource_uri),
+            Some(self.target_resource_uri()),
             SoapBody::builder().disconnect(disconnect_tag).build(),
             None,
-            self.selector_set.clone().into(),
-        )
+            self.target_selectors(),
+        )?)
+    }
+
+    /// Build a Delete request for this shell (MS-WSMV 3.1.4.2); the server
+    /// terminates the shell and every command still running in it.
+    pub(crate) fn fire_delete<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
+    ) -> Result<&'s str, crate::PwshCoreError> {
+        Ok(ws_man.invoke(
+            scratch,
+            &WsAction::Delete,
+            Some(self.target_resource_uri()),
+            SoapBody::builder().build(),
+            None,
+            self.target_selectors(),
+        )?)
     }
 
     /// Build a Connect request attaching this client to an existing
     /// disconnected shell (MS-WSMV 3.1.4.15). `connect_payload` is the base64
     /// PSRP payload (SESSION_CAPABILITY + CONNECT_RUNSPACEPOOL) carried in the
     /// `connectXml` element, analogous to `creationXml` on shell create.
-    pub(crate) fn connect<'a>(
-        &'a self,
-        ws_man: &'a WsMan,
+    pub(crate) fn connect<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
         option_set: Option<OptionSetValue>,
-        connect_payload: &'a str,
-    ) -> impl Into<Element<'a>> {
+        connect_payload: &str,
+    ) -> Result<&'s str, crate::PwshCoreError> {
         use ironposh_winrm::{
             cores::Namespace,
             rsp::connect::{ConnectTag, ConnectValue},
@@ -215,53 +323,84 @@ impl WinRunspace {
             .with_declaration(Namespace::WsmanShell)
             .with_value(connect_value);
 
-        ws_man.invoke(
+        Ok(ws_man.invoke(
+            scratch,
             &WsAction::Connect,
-            Some(&self.resource_uri),
+            Some(self.target_resource_uri()),
             SoapBody::builder().connect(connect_tag).build(),
             option_set,
-            self.selector_set.clone().into(),
-        )
+            self.target_selectors(),
+        )?)
     }
 
     /// Build a Reconnect request targeting this shell (MS-WSMV 3.1.4.14).
-    pub(crate) fn fire_reconnect<'a>(&'a self, ws_man: &'a WsMan) -> impl Into<Element<'a>> {
+    pub(crate) fn fire_reconnect<'s>(
+        &self,
+        ws_man: &WsMan,
+        scratch: &'s mut SerializationScratch,
+    ) -> Result<&'s str, crate::PwshCoreError> {
         use ironposh_winrm::cores::{Empty, Namespace, ReconnectTag};
 
         let reconnect_tag = Tag::from_name(ReconnectTag)
             .with_declaration(Namespace::WsmanShell)
             .with_value(Empty);
 
-        ws_man.invoke(
+        Ok(ws_man.invoke(
+            scratch,
             &WsAction::Reconnect,
-            Some(&self.resource_uri),
+            Some(self.target_resource_uri()),
             SoapBody::builder().reconnect(reconnect_tag).build(),
             None,
-            self.selector_set.clone().into(),
-        )
+            self.target_selectors(),
+        )?)
     }
 
+    /// Extract the streams and command state of a ReceiveResponse. Stream
+    /// payloads are base64-decoded straight from the XML text into `decoded`
+    /// (appended, one contiguous region per stream); each [`Stream`] records
+    /// its byte range in that buffer.
+    ///
+    /// Returns `None` for a response whose `SequenceID` is not past the last
+    /// one accepted for the same Receive: a re-delivery (e.g. after an HTTP
+    /// retry) whose streams were already processed. Responses without a
+    /// `SequenceID` are always accepted.
     #[instrument(skip_all)]
     pub(crate) fn accept_receive_response(
+        &mut self,
         soap_envelope: &SoapEnvelope<'_>,
-    ) -> Result<(Vec<Stream>, Option<CommandState>), crate::PwshCoreError> {
+        decoded: &mut Vec<u8>,
+    ) -> Result<Option<(Vec<Stream>, Option<CommandState>)>, crate::PwshCoreError> {
         let receive_response = &soap_envelope
             .body
             .as_ref()
             .receive_response
             .as_ref()
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse("No ReceiveResponse found in response".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
+                    "No ReceiveResponse found in response".into(),
+                )
             })?;
 
+        let sequence_id = receive_response
+            .attributes
+            .iter()
+            .find_map(|attr| match attr {
+                Attribute::SequenceID(id) => Some(*id),
+                _ => None,
+            });
+
         let streams = receive_response
             .value
             .streams
             .iter()
-            .map(Stream::try_from)
+            .map(|stream| Stream::decode_into(stream, decoded))
             .collect::<Result<Vec<_>, _>>()
             .map_err(|_| {
-                crate::PwshCoreError::InvalidResponse("Failed to decode streams".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
+                    "Failed to decode streams".into(),
+                )
             })?;
 
         let command_state = receive_response
@@ -271,9 +410,36 @@ impl WinRunspace {
             .map(CommandState::try_from)
             .transpose()?;
 
+        if let Some(sequence_id) = sequence_id {
+            let key = command_state.as_ref().map_or_else(
+                || {
+                    streams
+                        .iter()
+                        .find_map(|stream| stream.command_id().copied())
+                },
+                |state| Some(state.command_id),
+            );
+            match self.receive_sequence.get(&key) {
+                Some(&last) if sequence_id <= last => {
+                    warn!(
+                        command_id = ?key,
+                        sequence_id,
+                        last,
+                        "dropping re-delivered ReceiveResponse"
+                    );
+                    return Ok(None);
+                }
+                _ => {
+                    // Kept after CommandState/Done too, so a re-delivered
+                    // final chunk cannot replay its output.
+                    self.receive_sequence.insert(key, sequence_id);
+                }
+            }
+        }
+
         debug!(receive_response = ?receive_response,?command_state, "Received streams and command state");
 
-        Ok((streams, command_state))
+        Ok(Some((streams, command_state)))
     }
 
     #[instrument(skip_all)]
@@ -282,9 +448,12 @@ impl WinRunspace {
         soap_envelop: &SoapEnvelope<'_>,
     ) -> Result<(), crate::PwshCoreError> {
         let shell = &soap_envelop.body.as_ref().shell.as_ref().ok_or_else(|| {
-            crate::PwshCoreError::InvalidResponse("No shell found in response".into())
+            crate::PwshCoreError::invalid_response(
+                crate::Phase::ShellCreation,
+                "No shell found in response".into(),
+            )
         })?;
-        let shell_id = shell.as_ref().shell_id.as_ref().map(Tag::clone_value);
+        let shell_id = &shell.as_ref().shell_id;
         let resource_uri = &shell.as_ref().resource_uri;
         let owner = &shell.as_ref().owner;
         let client_ip = &shell.as_ref().client_ip;
@@ -293,19 +462,13 @@ impl WinRunspace {
         let shell_run_time = &shell.as_ref().shell_run_time;
         let shell_inactivity = &shell.as_ref().shell_inactivity;
 
-        self.shell_id = shell_id.map(|s| s.as_ref().to_string());
         self.owner = owner.as_ref().map(|o| o.value.as_ref().to_string());
         self.client_ip = client_ip.as_ref().map(|c| c.value.as_ref().to_string());
-        self.idle_time_out = idle_time_out.as_ref().map(|t| t.value.0);
-        self.output_streams = output_stream
+        self.options.idle_time_out = idle_time_out.as_ref().map(|t| t.value.0);
+        self.options.output_streams = output_stream
             .as_ref()
             .map_or_else(|| "stdout".to_string(), |o| o.value.as_ref().to_string());
 
-        self.resource_uri = resource_uri.as_ref().map_or_else(
-            || self.resource_uri.clone(),
-            |r| r.value.as_ref().to_string(),
-        );
-
         self.shell_run_time = shell_run_time
             .as_ref()
             .map(|t| t.value.as_ref().to_string());
@@ -320,14 +483,42 @@ impl WinRunspace {
             .resource_created
             .as_ref()
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse("No ResourceCreated found in response".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellCreation,
+                    "No ResourceCreated found in response".into(),
+                )
             })?;
 
-        let reference_parameters = resource_created.as_ref().reference_parameters.as_ref();
-
-        let selector_set = &reference_parameters.selector_set;
+        let endpoint = resource_created.as_ref();
+        let selectors = endpoint.selector_set().ok_or_else(|| {
+            crate::PwshCoreError::invalid_response(
+                crate::Phase::ShellCreation,
+                "ResourceCreated carries no SelectorSet".into(),
+            )
+        })?;
 
-        self.selector_set = selector_set.value.clone();
+        // The endpoint reference is authoritative; the rsp:Shell fields only
+        // fill in what it leaves out.
+        let shell_id = selectors
+            .get("ShellId")
+            .map(String::as_str)
+            .or_else(|| shell_id.as_ref().map(|s| s.value.as_ref()))
+            .ok_or_else(|| {
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellCreation,
+                    "Create response names no ShellId".into(),
+                )
+            })?;
+        let resource_uri = endpoint
+            .resource_uri()
+            .or_else(|| resource_uri.as_ref().map(|r| r.value.as_ref()))
+            .unwrap_or(&self.resource_uri);
+
+        self.reference = Some(ShellReference {
+            shell_id: shell_id.to_owned(),
+            resource_uri: resource_uri.to_owned(),
+            selectors: selectors.clone(),
+        });
 
         self.opened = true;
 
@@ -359,22 +550,24 @@ impl WinRunspace {
         )
     }
 
-    pub(crate) fn create_pipeline_request<'a>(
-        &'a self,
-        connection: &'a WsMan,
+    pub(crate) fn create_pipeline_request<'s>(
+        &self,
+        connection: &WsMan,
+        scratch: &'s mut SerializationScratch,
         command_id: uuid::Uuid,
         arguments: Vec<String>,
         executable: Option<String>,
         no_shell: Option<bool>,
-    ) -> impl Into<Element<'a>> {
+    ) -> Result<&'s str, crate::PwshCoreError> {
         let command_line = CommandLineValue {
             command: executable,
             arguments,
         };
 
-        connection.invoke(
+        Ok(connection.invoke(
+            scratch,
             &WsAction::Command,
-            Some(self.resource_uri.as_ref()),
+            Some(self.target_resource_uri()),
             SoapBody::builder()
                 .command_line(
                     Tag::new(command_line).with_attribute(Attribute::CommandId(command_id)),
@@ -384,19 +577,20 @@ impl WinRunspace {
                 "WINRS_SKIP_CMD_SHELL",
                 no_shell.unwrap_or_default().to_string(),
             )),
-            self.selector_set.clone().into(),
-        )
+            self.target_selectors(),
+        )?)
     }
 
     /// Send data to the shell stdin (for host responses)
     /// Each string in data is a base64-encoded PSRP fragment that should be sent
     /// in its own <rsp:Stream> element to respect WinRM envelope size limits
-    pub fn send_data_request<'a>(
-        &'a self,
-        connection: &'a WsMan,
+    pub fn send_data_request<'s>(
+        &self,
+        connection: &WsMan,
+        scratch: &'s mut SerializationScratch,
         command_id: Option<uuid::Uuid>,
-        data: &'a [String],
-    ) -> Result<impl Into<Element<'a>>, crate::PwshCoreError> {
+        data: &[String],
+    ) -> Result<&'s str, crate::PwshCoreError> {
         use ironposh_winrm::{
             cores::{Namespace, StreamTag, Tag},
             rsp::send::{SendTag, SendValue},
@@ -428,18 +622,17 @@ impl WinRunspace {
                 .with_declaration(Namespace::WsmanShell)
         };
 
-        let request = connection.invoke(
+        Ok(connection.invoke(
+            scratch,
             &WsAction::Send,
-            Some(self.resource_uri.as_ref()),
+            Some(self.target_resource_uri()),
             SoapBody::builder().send(send_tag).build(),
             Some(
                 OptionSetValue::default()
                     .add_option("WSMAN_CMDSHELL_OPTION_KEEPALIVE", true.to_string()),
             ),
-            self.selector_set.clone().into(),
-        );
-
-        Ok(request)
+            self.target_selectors(),
+        )?)
     }
 
     pub fn accept_commannd_response(
@@ -452,7 +645,10 @@ impl WinRunspace {
             .command_response
             .as_ref()
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse("No CommandResponse found in response".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Command,
+                    "No CommandResponse found in response".into(),
+                )
             })?
             .as_ref()
             .as_ref();
@@ -460,17 +656,17 @@ impl WinRunspace {
         Ok(command_id.0)
     }
 
-    pub(crate) fn terminal_pipeline_signal<'a>(
-        &'a mut self,
-        connection: &'a WsMan,
+    pub(crate) fn pipeline_signal<'s>(
+        &mut self,
+        connection: &WsMan,
+        scratch: &'s mut SerializationScratch,
         id: Uuid,
-    ) -> Result<impl Into<Element<'a>>, crate::PwshCoreError> {
+        signal_code: SignalCode,
+    ) -> Result<&'s str, crate::PwshCoreError> {
         use ironposh_winrm::cores::{Namespace, SignalCodeTag, SignalTag};
 
-        // Build <rsp:Code>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/ctrl_c</rsp:Code>
-        let code = Tag::from_name(SignalCodeTag).with_value(Text::from(
-            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/terminate",
-        ));
+        // Build <rsp:Code>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/...</rsp:Code>
+        let code = Tag::from_name(SignalCodeTag).with_value(Text::from(signal_code.value()));
 
         // Build <w:Signal CommandId="...">...</w:Signal>
         let signal = Tag::from_name(SignalTag)
@@ -483,16 +679,16 @@ impl WinRunspace {
             .add_option("WSMAN_CMDSHELL_OPTION_KEEPALIVE", true.to_string());
 
         // Reuse the shell's selector set that was captured on create
-        let selector_set = self.selector_set.clone().into();
+        let selector_set = self.target_selectors();
 
         // Ctrl+C should be snappy in a terminal. In serial mode we often use a very
         // short OperationTimeout (e.g. 500ms) for Receives, but that is too small
         // for Signal on some servers and causes WSMan TimedOut faults.
         //
         // Give Signal a larger timeout so we reliably get an acknowledgement.
-        let body = connection.invoke_with_operation_timeout(
+        let body = connection.envelope(
             &WsAction::Signal,
-            Some(self.resource_uri.as_ref()),
+            Some(self.target_resource_uri()),
             SoapBody::builder().signal(signal).build(),
             Some(option_set),
             selector_set,
@@ -510,9 +706,10 @@ impl WinRunspace {
             .value
             .0;
 
+        let xml = scratch.render(&body.into_element())?;
         self.signal_messages.insert(message_id, id);
 
-        Ok(body)
+        Ok(xml)
     }
 }
 
@@ -520,7 +717,10 @@ impl WinRunspace {
 pub(crate) struct Stream {
     name: String,
     command_id: Option<Uuid>,
-    value: Vec<u8>,
+    /// `End="true"`: the server will send nothing more on this stream.
+    end: bool,
+    /// Where this stream's decoded payload lives in the shared decode buffer.
+    range: Range<usize>,
 }
 
 impl Stream {
@@ -532,16 +732,21 @@ impl Stream {
         self.command_id.as_ref()
     }
 
-    pub(crate) fn value(&self) -> &[u8] {
-        &self.value
+    pub(crate) fn is_end(&self) -> bool {
+        self.end
     }
-}
 
-impl<'a> TryFrom<&Tag<'a, Text<'a>, StreamTag>> for Stream {
-    type Error = crate::PwshCoreError;
+    /// This stream's payload within the buffer it was decoded into.
+    pub(crate) fn value<'b>(&self, decoded: &'b [u8]) -> &'b [u8] {
+        &decoded[self.range.clone()]
+    }
 
-    fn try_from(value: &Tag<'a, Text<'a>, StreamTag>) -> Result<Self, Self::Error> {
-        let attributes = &value.attributes;
+    /// Base64-decode `tag`'s text, appending the payload to `decoded`.
+    fn decode_into(
+        tag: &Tag<'_, Text<'_>, StreamTag>,
+        decoded: &mut Vec<u8>,
+    ) -> Result<Self, crate::PwshCoreError> {
+        let attributes = &tag.attributes;
         let name = attributes
             .iter()
             .find_map(|attr| match attr {
@@ -549,7 +754,10 @@ impl<'a> TryFrom<&Tag<'a, Text<'a>, StreamTag>> for Stream {
                 _ => None,
             })
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse("Stream tag missing name attribute".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
+                    "Stream tag missing name attribute".into(),
+                )
             })?;
 
         let command_id = attributes.iter().find_map(|attr| match attr {
@@ -557,17 +765,26 @@ impl<'a> TryFrom<&Tag<'a, Text<'a>, StreamTag>> for Stream {
             _ => None,
         });
 
-        // let value = value.value.as_ref();
-        let value = base64::engine::general_purpose::STANDARD
-            .decode(value.value.as_ref())
+        let end = attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::End(true)));
+
+        let start = decoded.len();
+        base64::engine::general_purpose::STANDARD
+            .decode_vec(tag.value.as_ref(), decoded)
             .map_err(|_| {
-                crate::PwshCoreError::InvalidResponse("Failed to decode stream value".into())
+                decoded.truncate(start);
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
+                    "Failed to decode stream value".into(),
+                )
             })?;
 
         Ok(Self {
             name,
             command_id,
-            value,
+            end,
+            range: start..decoded.len(),
         })
     }
 }
@@ -593,7 +810,8 @@ impl<'a> TryFrom<&Tag<'a, CommandStateValue<'a>, CommandStateTag>> for CommandSt
                 _ => None,
             })
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse(
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
                     "CommandState tag missing command_id attribute".into(),
                 )
             })?;
@@ -606,7 +824,8 @@ impl<'a> TryFrom<&Tag<'a, CommandStateValue<'a>, CommandStateTag>> for CommandSt
                 _ => None,
             })
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse(
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::Receive,
                     "CommandState tag missing state attribute".into(),
                 )
             })?;
@@ -2,11 +2,16 @@ use std::{collections::HashMap, sync::Arc};
 
 use ironposh_psrp::{
     ApartmentState, ApplicationArguments, ApplicationPrivateData, Defragmenter, Fragmenter,
-    HostInfo, PSThreadOptions, SessionCapability,
+    HostInfo, PSThreadOptions, SessionCapability, TimeZone,
 };
 use ironposh_winrm::ws_management::WsMan;
+use ironposh_xml::builder::SerializationScratch;
 
-use crate::{pipeline::Pipeline, runspace::win_rs::WinRunspace};
+use crate::{
+    connector::config::{ParseLimits, ParseMode},
+    pipeline::Pipeline,
+    runspace::win_rs::{ShellOptions, ShellReference, WinRunspace},
+};
 
 use super::{enums::RunspacePoolState, pool::RunspacePool};
 
@@ -22,10 +27,10 @@ pub struct RunspacePoolCreator {
     #[builder(default = 1)]
     max_runspaces: usize,
 
-    #[builder(default = PSThreadOptions::Default)]
+    #[builder(default)]
     thread_options: PSThreadOptions,
 
-    #[builder(default = ApartmentState::Unknown)]
+    #[builder(default)]
     apartment_state: ApartmentState,
 
     host_info: HostInfo,
@@ -36,14 +41,29 @@ pub struct RunspacePoolCreator {
     #[builder(default = Defragmenter::new())]
     defragmenter: Defragmenter,
 
+    #[builder(default)]
+    parse_mode: ParseMode,
+
+    #[builder(default)]
+    parse_limits: ParseLimits,
+
     #[builder(default)]
     application_private_data: Option<ApplicationPrivateData>,
 
     #[builder(default)]
     session_capability: Option<SessionCapability>,
 
+    /// Ours, for the SESSION_CAPABILITY we send.
+    #[builder(default)]
+    time_zone: Option<TimeZone>,
+
     #[builder(default)]
     pipelines: HashMap<uuid::Uuid, Pipeline>,
+
+    /// `rsp:Shell` settings for the Create request. Not sent on the Connect
+    /// path, where the shell already exists.
+    #[builder(default)]
+    shell_options: ShellOptions,
 }
 
 impl RunspacePoolCreator {
@@ -51,6 +71,7 @@ impl RunspacePoolCreator {
         let shell = WinRunspace::builder()
             .id(self.id)
             .resource_uri(connection.resource_uri().to_owned())
+            .options(self.shell_options.clone())
             .build();
 
         self.into_runspace_pool_with_shell(connection, shell)
@@ -64,11 +85,10 @@ impl RunspacePoolCreator {
         let shell = WinRunspace::builder()
             .id(self.id)
             .resource_uri(connection.resource_uri().to_owned())
-            .shell_id(Some(shell_id.clone()))
-            .selector_set(
-                ironposh_winrm::ws_management::SelectorSetValue::new()
-                    .add_selector("ShellId", shell_id),
-            )
+            .reference(ShellReference::from_shell_id(
+                connection.resource_uri(),
+                shell_id,
+            ))
             .build();
 
         self.into_runspace_pool_with_shell(connection, shell)
@@ -92,13 +112,20 @@ impl RunspacePoolCreator {
             connection,
             shell,
             defragmenter: self.defragmenter,
+            parse_mode: self.parse_mode,
+            parse_limits: self.parse_limits,
             application_private_data: self.application_private_data,
             session_capability: self.session_capability,
+            time_zone: self.time_zone,
             pipelines: self.pipelines,
             desired_stream_is_pooling: false,
             key_exchange: None,
             psrp_key_exchange_pending: false,
             pending_host_calls: std::collections::VecDeque::new(),
+            scratch: SerializationScratch::new(),
+            stream_decode_buffer: Vec::new(),
+            #[cfg(feature = "diagnostics")]
+            timeline: None,
         }
     }
 }
@@ -25,7 +25,8 @@ pub(super) fn encrypt_secure_strings_in_value_rec(
     match value {
         PsValue::Primitive(PsPrimitiveValue::SecureString(bytes)) => {
             let Some(session_key) = session_key else {
-                return Err(crate::PwshCoreError::InvalidResponse(
+                return Err(crate::PwshCoreError::invalid_response(
+                    crate::Phase::KeyExchange,
                     "SecureString encountered but PSRP session key is not established".into(),
                 ));
             };
@@ -41,7 +42,8 @@ pub(super) fn encrypt_secure_strings_in_value_rec(
                 ComplexObjectContent::ExtendedPrimitive(p) => {
                     if let PsPrimitiveValue::SecureString(bytes) = p {
                         let Some(session_key) = session_key else {
-                            return Err(crate::PwshCoreError::InvalidResponse(
+                            return Err(crate::PwshCoreError::invalid_response(
+                                crate::Phase::KeyExchange,
                                 "SecureString encountered but PSRP session key is not established"
                                     .into(),
                             ));
@@ -74,7 +76,8 @@ fn encrypt_secure_string_bytes_in_place(
     session_key: &[u8],
 ) -> Result<(), crate::PwshCoreError> {
     if session_key.len() != 32 {
-        return Err(crate::PwshCoreError::InvalidResponse(
+        return Err(crate::PwshCoreError::invalid_response(
+            crate::Phase::KeyExchange,
             format!(
                 "PSRP SecureString encryption requires 32-byte session key; got {}",
                 session_key.len()
@@ -88,7 +91,8 @@ fn encrypt_secure_string_bytes_in_place(
     let iv = [0u8; 16];
 
     let encryptor = cbc::Encryptor::<Aes256>::new_from_slices(session_key, &iv).map_err(|e| {
-        crate::PwshCoreError::InvalidResponse(
+        crate::PwshCoreError::invalid_response(
+            crate::Phase::KeyExchange,
             format!("Failed to initialize AES encryptor: {e}").into(),
         )
     })?;
@@ -101,7 +105,8 @@ fn encrypt_secure_string_bytes_in_place(
     let ciphertext = encryptor
         .encrypt_padded::<Pkcs7>(&mut buf, msg_len)
         .map_err(|e| {
-            crate::PwshCoreError::InvalidResponse(
+            crate::PwshCoreError::invalid_response(
+                crate::Phase::KeyExchange,
                 format!("Failed to encrypt SecureString (padding): {e}").into(),
             )
         })?;
@@ -1,6 +1,6 @@
 use base64::Engine;
 use ironposh_psrp::{MessageType, PsrpMessage, fragmentation};
-use ironposh_winrm::soap::SoapEnvelope;
+use ironposh_winrm::{soap::SoapEnvelope, ws_management::WsAction};
 use ironposh_xml::mapping::FromXml;
 use tracing::{debug, info, trace, warn};
 
@@ -21,12 +21,17 @@ impl ExpectShellConnected {
     pub fn accept(self, response: &str) -> Result<RunspacePool, crate::PwshCoreError> {
         let Self { mut runspace_pool } = self;
 
-        let parsed = ironposh_xml::parser::parse(response)?;
+        let parsed = ironposh_xml::parser::parse_with_limits(response, runspace_pool.parse_limits)?;
 
         let soap_response = SoapEnvelope::from_xml(parsed.root_element())
             .map_err(crate::PwshCoreError::XmlParsingError)?;
 
-        RunspacePool::fault_to_error(&soap_response)?;
+        RunspacePool::fault_to_error(&soap_response, crate::Phase::ShellConnect)?;
+        RunspacePool::check_response_action(
+            &soap_response,
+            &WsAction::ConnectResponse,
+            crate::Phase::ShellConnect,
+        )?;
 
         let connect_response = soap_response
             .body
@@ -34,7 +39,10 @@ impl ExpectShellConnected {
             .connect_response
             .as_ref()
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse("No ConnectResponse found in response".into())
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellConnect,
+                    "No ConnectResponse found in response".into(),
+                )
             })?;
 
         let payload = connect_response
@@ -42,7 +50,8 @@ impl ExpectShellConnected {
             .connect_response_xml
             .as_ref()
             .ok_or_else(|| {
-                crate::PwshCoreError::InvalidResponse(
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellConnect,
                     "ConnectResponse is missing the connectResponseXml payload".into(),
                 )
             })?;
@@ -50,14 +59,16 @@ impl ExpectShellConnected {
         let decoded = base64::engine::general_purpose::STANDARD
             .decode(payload.value.as_ref())
             .map_err(|e| {
-                crate::PwshCoreError::InvalidResponse(
+                crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellConnect,
                     format!("Failed to decode connectResponseXml base64: {e}").into(),
                 )
             })?;
 
         let messages = match runspace_pool.defragmenter.defragment(&decoded)? {
             fragmentation::DefragmentResult::Incomplete => {
-                return Err(crate::PwshCoreError::InvalidResponse(
+                return Err(crate::PwshCoreError::invalid_response(
+                    crate::Phase::ShellConnect,
                     "connectResponseXml carried an incomplete PSRP fragment stream".into(),
                 ));
             }
@@ -66,12 +77,14 @@ impl ExpectShellConnected {
 
         let mut saw_session_capability = false;
         let mut saw_init_data = false;
-        for message in messages {
+        for (index, message) in messages.into_iter().enumerate() {
             // L4 typed stream (issue #12): parse once into a typed PsrpMessage and
             // match on the variant instead of re-deriving the type from the wire
             // header and re-parsing per arm.
             let data_len = message.data.len();
-            match PsrpMessage::parse(&message)? {
+            let parsed = PsrpMessage::parse(&message)
+                .map_err(|e| runspace_pool.defragmenter.decode_error(index, &message, e))?;
+            match parsed {
                 PsrpMessage::SessionCapability(capability) => {
                     debug!(target: "session", ?capability, "received SessionCapability");
                     runspace_pool.session_capability = Some(capability);
@@ -89,17 +102,20 @@ impl ExpectShellConnected {
                     // Reject implausible server values rather than silently coercing them
                     // (a negative or min>max count indicates protocol corruption).
                     let min = usize::try_from(init_data.min_runspaces).map_err(|_| {
-                        crate::PwshCoreError::InvalidResponse(
+                        crate::PwshCoreError::invalid_response(
+                            crate::Phase::ShellConnect,
                             "RunspacePoolInitData MinRunspaces is negative".into(),
                         )
                     })?;
                     let max = usize::try_from(init_data.max_runspaces).map_err(|_| {
-                        crate::PwshCoreError::InvalidResponse(
+                        crate::PwshCoreError::invalid_response(
+                            crate::Phase::ShellConnect,
                             "RunspacePoolInitData MaxRunspaces is negative".into(),
                         )
                     })?;
                     if min > max {
-                        return Err(crate::PwshCoreError::InvalidResponse(
+                        return Err(crate::PwshCoreError::invalid_response(
+                            crate::Phase::ShellConnect,
                             "RunspacePoolInitData MinRunspaces exceeds MaxRunspaces".into(),
                         ));
                     }
@@ -134,12 +150,14 @@ impl ExpectShellConnected {
         // pool sizing (RunspacePoolInitData); without them the pool is not safely usable.
         // ApplicationPrivateData remains optional.
         if !saw_session_capability {
-            return Err(crate::PwshCoreError::InvalidResponse(
+            return Err(crate::PwshCoreError::invalid_response(
+                crate::Phase::ShellConnect,
                 "ConnectResponse missing SessionCapability".into(),
             ));
         }
         if !saw_init_data {
-            return Err(crate::PwshCoreError::InvalidResponse(
+            return Err(crate::PwshCoreError::invalid_response(
+                crate::Phase::ShellConnect,
                 "ConnectResponse missing RunspacePoolInitData".into(),
             ));
         }
@@ -147,7 +165,7 @@ impl ExpectShellConnected {
         // The shell is attached and the pool is usable; the caller fires the
         // initial pool-stream Receive (mirrors the post-create handoff where
         // the pool-stream poll is already accounted for).
-        runspace_pool.state = RunspacePoolState::Opened;
+        runspace_pool.set_state(RunspacePoolState::Opened);
         runspace_pool.desired_stream_is_pooling = true;
         info!(
             runspace_pool_id = %runspace_pool.id,
@@ -1,4 +1,4 @@
-use ironposh_winrm::soap::SoapEnvelope;
+use ironposh_winrm::{soap::SoapEnvelope, ws_management::WsAction};
 use ironposh_xml::mapping::FromXml;
 
 use super::pool::RunspacePool;
@@ -12,11 +12,17 @@ impl ExpectShellCreated {
     pub fn accept(self, response: &str) -> Result<RunspacePool, crate::PwshCoreError> {
         let Self { mut runspace_pool } = self;
 
-        let parsed = ironposh_xml::parser::parse(response)?;
+        let parsed = ironposh_xml::parser::parse_with_limits(response, runspace_pool.parse_limits)?;
 
         let soap_response = SoapEnvelope::from_xml(parsed.root_element())
             .map_err(crate::PwshCoreError::XmlParsingError)?;
 
+        RunspacePool::fault_to_error(&soap_response, crate::Phase::ShellCreation)?;
+        RunspacePool::check_response_action(
+            &soap_response,
+            &WsAction::CreateResponse,
+            crate::Phase::ShellCreation,
+        )?;
         runspace_pool.shell.accept_create_response(&soap_response)?;
 
         Ok(runspace_pool)
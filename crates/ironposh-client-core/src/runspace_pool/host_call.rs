@@ -25,7 +25,8 @@ pub(super) fn pipeline_host_call_from(
     command_id: Option<&Uuid>,
 ) -> Result<HostCall, PwshCoreError> {
     let PsValue::Object(pipeline_host_call) = ps_value else {
-        return Err(PwshCoreError::InvalidResponse(
+        return Err(PwshCoreError::invalid_response(
+            crate::Phase::HostCall,
             "Expected PipelineHostCall as PsValue::Object".into(),
         ));
     };
@@ -43,7 +44,8 @@ pub(super) fn pipeline_host_call_from(
 
     // Question: Can we have a Optional command id here?
     let Some(command_id) = command_id else {
-        return Err(PwshCoreError::InvalidResponse(
+        return Err(PwshCoreError::invalid_response(
+            crate::Phase::HostCall,
             "Expected command_id to be Some".into(),
         ));
     };
@@ -53,7 +55,10 @@ pub(super) fn pipeline_host_call_from(
     };
 
     HostCall::try_from_pipeline(scope, pipeline_host_call).map_err(|e| {
-        PwshCoreError::InvalidResponse(format!("Failed to parse host call: {e}").into())
+        PwshCoreError::invalid_response(
+            crate::Phase::HostCall,
+            format!("Failed to parse host call: {e}").into(),
+        )
     })
 }
 
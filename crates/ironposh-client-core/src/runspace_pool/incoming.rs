@@ -14,23 +14,81 @@ use std::collections::HashSet;
 
 use base64::Engine;
 use ironposh_psrp::{
-    ApplicationPrivateData, ErrorRecord, PipelineOutput, PsValue, RunspacePoolStateMessage,
-    SessionCapability, fragmentation,
+    ApplicationPrivateData, ErrorRecord, PipelineOutput, PowerShellRemotingMessage, PsValue,
+    RunspacePoolStateMessage, SessionCapability, fragmentation, ps_value::ComplexObject,
+};
+use ironposh_winrm::{
+    soap::{SoapEnvelope, fault::SoapFaultValue},
+    ws_management::WsAction,
 };
-use ironposh_winrm::{soap::SoapEnvelope, ws_management::WsAction};
 use ironposh_xml::mapping::FromXml;
 use rsa::pkcs1v15::Pkcs1v15Encrypt;
 use tracing::{debug, error, info, instrument, trace, warn};
 use uuid::Uuid;
 
 use crate::{
-    PwshCoreError, host::HostCall, powershell::PipelineHandle, runspace::win_rs::WinRunspace,
-    runspace_pool::PsInvocationState,
+    PwshCoreError, connector::config::ParseMode, host::HostCall, powershell::PipelineHandle,
+    runspace::win_rs::Stream, runspace_pool::PsInvocationState,
 };
 
 use super::enums::RunspacePoolState;
 use super::pool::{AcceptResponsResult, DesiredStream, RunspacePool};
 
+/// Why a decoded PSRP message could not be handled.
+enum MessageError {
+    /// The payload is not the object its message type announces. Lenient
+    /// parsing skips it if it is a data-stream record.
+    Malformed(PwshCoreError),
+    /// Acting on a well-formed message failed (unknown pipeline, host call,
+    /// key exchange). Always fails the response.
+    Failed(PwshCoreError),
+}
+
+impl From<PwshCoreError> for MessageError {
+    fn from(error: PwshCoreError) -> Self {
+        Self::Failed(error)
+    }
+}
+
+impl std::fmt::Display for MessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(e) | Self::Failed(e) => std::fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+/// Decode the `what` payload of a PSRP message into `T`.
+fn decode_object<T>(ps_value: PsValue, what: &str) -> Result<T, MessageError>
+where
+    T: TryFrom<ComplexObject>,
+    PwshCoreError: From<T::Error>,
+{
+    let PsValue::Object(obj) = ps_value else {
+        return Err(MessageError::Malformed(PwshCoreError::invalid_response(
+            crate::Phase::Receive,
+            format!("Expected {what} as PsValue::Object").into(),
+        )));
+    };
+    T::try_from(obj).map_err(|e| MessageError::Malformed(e.into()))
+}
+
+/// Whether `message_type` only carries data for a pipeline's streams, so
+/// losing one leaves the session and pipeline state intact.
+const fn is_data_record(message_type: ironposh_psrp::MessageType) -> bool {
+    use ironposh_psrp::MessageType;
+    matches!(
+        message_type,
+        MessageType::PipelineOutput
+            | MessageType::ErrorRecord
+            | MessageType::VerboseRecord
+            | MessageType::WarningRecord
+            | MessageType::DebugRecord
+            | MessageType::InformationRecord
+            | MessageType::ProgressRecord
+    )
+}
+
 impl RunspacePool {
     /// Accept the server's DisconnectResponse.
     /// Valid only in `Disconnecting` state; transitions the pool to `Disconnected`.
@@ -45,11 +103,16 @@ impl RunspacePool {
             ));
         }
 
-        let parsed = ironposh_xml::parser::parse(soap_envelope)?;
+        let parsed = ironposh_xml::parser::parse_with_limits(soap_envelope, self.parse_limits)?;
         let soap_envelope = SoapEnvelope::from_xml(parsed.root_element())
             .map_err(crate::PwshCoreError::XmlParsingError)?;
 
-        Self::fault_to_error(&soap_envelope)?;
+        Self::fault_to_error(&soap_envelope, crate::Phase::Disconnect)?;
+        Self::check_response_action(
+            &soap_envelope,
+            &WsAction::DisconnectResponse,
+            crate::Phase::Disconnect,
+        )?;
 
         // Real Windows servers answer shell Disconnect with an empty Body and
         // identify the operation via the `a:Action` header only; the
@@ -57,12 +120,13 @@ impl RunspacePool {
         if soap_envelope.body.as_ref().disconnect_response.is_none()
             && !Self::header_action_is(&soap_envelope, &WsAction::DisconnectResponse)
         {
-            return Err(crate::PwshCoreError::InvalidResponse(
+            return Err(crate::PwshCoreError::invalid_response(
+                crate::Phase::Disconnect,
                 "No DisconnectResponse found in response".into(),
             ));
         }
 
-        self.state = RunspacePoolState::Disconnected;
+        self.set_state(RunspacePoolState::Disconnected);
         info!(runspace_pool_id = %self.id, "runspace pool disconnected");
         Ok(())
     }
@@ -81,11 +145,16 @@ impl RunspacePool {
             ));
         }
 
-        let parsed = ironposh_xml::parser::parse(soap_envelope)?;
+        let parsed = ironposh_xml::parser::parse_with_limits(soap_envelope, self.parse_limits)?;
         let soap_envelope = SoapEnvelope::from_xml(parsed.root_element())
             .map_err(crate::PwshCoreError::XmlParsingError)?;
 
-        Self::fault_to_error(&soap_envelope)?;
+        Self::fault_to_error(&soap_envelope, crate::Phase::Reconnect)?;
+        Self::check_response_action(
+            &soap_envelope,
+            &WsAction::ReconnectResponse,
+            crate::Phase::Reconnect,
+        )?;
 
         // Real Windows servers answer shell Reconnect with an empty Body and
         // identify the operation via the `a:Action` header only; the
@@ -93,12 +162,13 @@ impl RunspacePool {
         if soap_envelope.body.as_ref().reconnect_response.is_none()
             && !Self::header_action_is(&soap_envelope, &WsAction::ReconnectResponse)
         {
-            return Err(crate::PwshCoreError::InvalidResponse(
+            return Err(crate::PwshCoreError::invalid_response(
+                crate::Phase::Reconnect,
                 "No ReconnectResponse found in response".into(),
             ));
         }
 
-        self.state = RunspacePoolState::Opened;
+        self.set_state(RunspacePoolState::Opened);
         // The Receive that was in flight before the disconnect is gone; the caller
         // must fire a fresh pool-stream Receive to resume the receive loop.
         self.desired_stream_is_pooling = false;
@@ -106,6 +176,39 @@ impl RunspacePool {
         Ok(())
     }
 
+    /// Accept the server's DeleteResponse for the shell.
+    /// Valid only in `Closing` state; transitions the pool to `Closed`.
+    #[instrument(skip(self, soap_envelope), fields(envelope_length = soap_envelope.len()))]
+    pub fn accept_close_response(
+        &mut self,
+        soap_envelope: &str,
+    ) -> Result<(), crate::PwshCoreError> {
+        if self.state != RunspacePoolState::Closing {
+            return Err(crate::PwshCoreError::InvalidState(
+                "RunspacePool must be in Closing state to accept a close response",
+            ));
+        }
+
+        let parsed = ironposh_xml::parser::parse_with_limits(soap_envelope, self.parse_limits)?;
+        let soap_envelope = SoapEnvelope::from_xml(parsed.root_element())
+            .map_err(crate::PwshCoreError::XmlParsingError)?;
+
+        Self::fault_to_error(&soap_envelope, crate::Phase::Close)?;
+
+        // WS-Transfer DeleteResponse has an empty Body; only the Action header
+        // identifies it.
+        if !Self::header_action_is(&soap_envelope, &WsAction::DeleteResponse) {
+            return Err(crate::PwshCoreError::invalid_response(
+                crate::Phase::Close,
+                "No DeleteResponse found in response".into(),
+            ));
+        }
+
+        self.set_state(RunspacePoolState::Closed);
+        info!(runspace_pool_id = %self.id, "runspace pool closed");
+        Ok(())
+    }
+
     /// Whether the envelope's `a:Action` header equals the given WSMan action.
     fn header_action_is(soap_envelope: &SoapEnvelope<'_>, action: &WsAction) -> bool {
         soap_envelope
@@ -115,25 +218,81 @@ impl RunspacePool {
             .is_some_and(|tag| tag.as_ref().as_ref() == action.as_str())
     }
 
+    /// Reject a response whose `a:Action` header names another operation than
+    /// `expected`, e.g. a proxy handing back the answer to a different request.
+    /// A missing header and the fault actions pass: faults are surfaced from
+    /// the body.
+    pub(super) fn check_response_action(
+        soap_envelope: &SoapEnvelope<'_>,
+        expected: &WsAction,
+        phase: crate::Phase,
+    ) -> Result<(), crate::PwshCoreError> {
+        let Some(action) = soap_envelope
+            .header
+            .as_ref()
+            .and_then(|header| header.as_ref().action.as_ref())
+        else {
+            return Ok(());
+        };
+        let action = action.as_ref().as_ref().trim();
+        if action == expected.as_str() || WsAction::is_fault(action) {
+            return Ok(());
+        }
+
+        warn!(target: "accept_response", %phase, expected = expected.as_str(), action, "response action does not match the request");
+        Err(crate::PwshCoreError::invalid_response(
+            phase,
+            format!(
+                "expected a response with action {}, got {action}",
+                expected.as_str()
+            ),
+        ))
+    }
+
     /// Surface a WSMan SOAP fault as a `SoapFault` error.
     pub(super) fn fault_to_error(
         soap_envelope: &SoapEnvelope<'_>,
+        phase: crate::Phase,
     ) -> Result<(), crate::PwshCoreError> {
         if let Some(fault_tag) = soap_envelope.body.as_ref().fault.as_ref() {
-            let fault = fault_tag.as_ref();
-            let code = fault
-                .code
-                .as_ref()
-                .and_then(|c| c.as_ref().value.as_ref())
-                .map_or("unknown", |v| <&str>::from(v.as_ref()))
-                .to_string();
-            let reason = fault.reason_text().unwrap_or("unknown").to_string();
-            error!(target: "accept_response", %code, %reason, "received SOAP fault");
-            return Err(PwshCoreError::SoapFault { code, reason });
+            return Err(Self::fault_error(fault_tag.as_ref(), phase));
         }
         Ok(())
     }
 
+    /// The error for a fault the session cannot absorb: `QuotaExceeded` for a
+    /// `w:QuotaLimit` fault, `ShellNotFound` when the shell is gone,
+    /// `SoapFault` otherwise.
+    fn fault_error(fault: &SoapFaultValue<'_>, phase: crate::Phase) -> PwshCoreError {
+        let reason = fault.reason_text().unwrap_or("unknown").to_string();
+        if fault.is_shell_not_found() {
+            warn!(target: "accept_response", %phase, %reason, "shell no longer exists on the server");
+            return PwshCoreError::ShellNotFound { reason };
+        }
+        if fault.is_quota_limit() {
+            let (which, limit) = crate::Quota::from_fault_reason(&reason);
+            warn!(target: "accept_response", %phase, %which, ?limit, %reason, "WinRM quota exceeded");
+            return PwshCoreError::QuotaExceeded {
+                phase,
+                which,
+                limit,
+                reason,
+            };
+        }
+        let code = fault
+            .code
+            .as_ref()
+            .and_then(|c| c.as_ref().value.as_ref())
+            .map_or("unknown", |v| <&str>::from(v.as_ref()))
+            .to_string();
+        error!(target: "accept_response", %phase, %code, %reason, "received SOAP fault");
+        PwshCoreError::SoapFault {
+            phase,
+            code,
+            reason,
+        }
+    }
+
     #[expect(clippy::too_many_lines)]
     #[instrument(skip(self, soap_envelope), fields(envelope_length = soap_envelope.len()))]
     pub(crate) fn accept_response(
@@ -142,26 +301,54 @@ impl RunspacePool {
     ) -> Result<Vec<AcceptResponsResult>, crate::PwshCoreError> {
         debug!(target: "soap", "parsing SOAP envelope");
 
-        let parsed = ironposh_xml::parser::parse(soap_envelope).map_err(|e| {
-            error!(target: "xml", error = %e, xml = soap_envelope, "failed to parse XML");
-            e
-        })?;
-
-        let soap_envelope = SoapEnvelope::from_xml(parsed.root_element()).map_err(|e| {
-            error!(target: "soap", error = %e, "failed to parse SOAP envelope");
+        let mut document = None;
+        let soap_envelope = ironposh_winrm::soap::parse_envelope(
+            soap_envelope,
+            self.parse_limits,
+            &mut document,
+        )
+        .map_err(|e| {
+            error!(target: "soap", error = %e, xml = soap_envelope, "failed to parse SOAP envelope");
             crate::PwshCoreError::XmlParsingError(e)
         })?;
 
+        // The body decides how the response is handled below; make sure the
+        // Action header agrees with it.
+        let body = soap_envelope.body.as_ref();
+        let expected = if body.receive_response.is_some() {
+            Some((WsAction::ReceiveResponse, crate::Phase::Receive))
+        } else if body.command_response.is_some() {
+            Some((WsAction::CommandResponse, crate::Phase::Command))
+        } else if body.signal_response.is_some() {
+            Some((WsAction::SignalResponse, crate::Phase::Command))
+        } else {
+            None
+        };
+        if let Some((action, phase)) = expected {
+            Self::check_response_action(&soap_envelope, &action, phase)?;
+        }
+
         let mut result = Vec::new();
 
         if soap_envelope.body.as_ref().receive_response.is_some() {
             debug!(target: "receive", "processing receive response");
 
-            let (streams, command_state) = WinRunspace::accept_receive_response(&soap_envelope)
+            // Reuse the pool's decode buffer; it only goes back on success, an
+            // error path simply starts the next response with a fresh one.
+            let mut decoded = std::mem::take(&mut self.stream_decode_buffer);
+            decoded.clear();
+            let Some((streams, command_state)) = self
+                .shell
+                .accept_receive_response(&soap_envelope, &mut decoded)
                 .map_err(|e| {
                     error!(target: "receive", error = %e, "failed to accept receive response");
                     e
-                })?;
+                })?
+            else {
+                // Already processed, and the next Receive was scheduled then.
+                self.recycle_stream_decode_buffer(decoded);
+                return Ok(result);
+            };
 
             let streams_ids = streams
                 .iter()
@@ -186,10 +373,24 @@ impl RunspacePool {
                 "processing streams"
             );
 
-            let handle_results = self.handle_pwsh_responses(streams).map_err(|e| {
+            let ended_streams = streams
+                .iter()
+                .filter(|stream| stream.is_end())
+                .filter_map(|stream| {
+                    stream
+                        .command_id()
+                        .map(|id| AcceptResponsResult::StreamEnded {
+                            handle: PipelineHandle { id: *id },
+                            stream: stream.name().to_owned(),
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            let handle_results = self.handle_pwsh_responses(streams, &decoded).map_err(|e| {
                 error!(target: "pwsh", error = %e, "failed to handle PowerShell responses");
                 e
             })?;
+            self.recycle_stream_decode_buffer(decoded);
 
             let already_scheduled_receive = handle_results
                 .iter()
@@ -203,6 +404,7 @@ impl RunspacePool {
             );
 
             result.extend(handle_results);
+            result.extend(ended_streams);
 
             if let Some(command_state) = command_state
                 && command_state.is_done()
@@ -258,7 +460,8 @@ impl RunspacePool {
             self.pipelines
                 .get_mut(&pipeline_id)
                 .ok_or_else(|| {
-                    crate::PwshCoreError::InvalidResponse(
+                    crate::PwshCoreError::invalid_response(
+                        crate::Phase::Receive,
                         "Pipeline not found for command response".into(),
                     )
                 })?
@@ -307,6 +510,10 @@ impl RunspacePool {
                 if !desired_streams.is_empty() {
                     result.push(AcceptResponsResult::ReceiveResponse { desired_streams });
                 }
+            } else if fault.is_shell_not_found() {
+                // The shell itself is gone (idle timeout, server restart), not
+                // just one command: nothing sent on it can succeed any more.
+                return Err(Self::fault_error(fault, crate::Phase::Receive));
             } else if fault.is_invalid_selectors() {
                 // Common cancel race: we had a Receive(CommandId=...) in flight while the
                 // server already tore down the command. Treat this as non-fatal and
@@ -353,20 +560,7 @@ impl RunspacePool {
                 }
             } else {
                 // Real fault - propagate as error
-                let code = fault
-                    .code
-                    .as_ref()
-                    .and_then(|c| c.as_ref().value.as_ref())
-                    .map_or("unknown", |v| <&str>::from(v.as_ref()))
-                    .to_string();
-                let reason = fault.reason_text().unwrap_or("unknown").to_string();
-                error!(
-                    target: "accept_response",
-                    %code,
-                    %reason,
-                    "received non-timeout SOAP fault"
-                );
-                return Err(PwshCoreError::SoapFault { code, reason });
+                return Err(Self::fault_error(fault, crate::Phase::Receive));
             }
         }
 
@@ -394,12 +588,21 @@ impl RunspacePool {
         (!stopping.is_empty()).then_some(stopping)
     }
 
+    /// Keep the decode buffer's allocation for the next ReceiveResponse,
+    /// unless one unusually large response inflated it.
+    fn recycle_stream_decode_buffer(&mut self, mut decoded: Vec<u8>) {
+        const MAX_RETAINED_CAPACITY: usize = 4 * 1024 * 1024;
+        decoded.clear();
+        decoded.shrink_to(MAX_RETAINED_CAPACITY);
+        self.stream_decode_buffer = decoded;
+    }
+
     /// Fire create pipeline for a specific pipeline handle (used by service API)
-    #[expect(clippy::too_many_lines)]
-    #[instrument(skip(self, responses))]
+    #[instrument(skip(self, responses, decoded))]
     fn handle_pwsh_responses(
         &mut self,
-        responses: Vec<crate::runspace::win_rs::Stream>,
+        responses: Vec<Stream>,
+        decoded: &[u8],
     ) -> Result<Vec<AcceptResponsResult>, crate::PwshCoreError> {
         let mut result = Vec::new();
 
@@ -412,15 +615,12 @@ impl RunspacePool {
                 "processing stream"
             );
 
-            let messages = match self.defragmenter.defragment(stream.value()).map_err(|e| {
-                error!(target: "defragment", stream_index, error = %e, "failed to defragment stream");
-                e
-            })? {
-                fragmentation::DefragmentResult::Incomplete => {
+            let messages = match self.defragmenter.defragment(stream.value(decoded)) {
+                Ok(fragmentation::DefragmentResult::Incomplete) => {
                     debug!(target: "defragment", stream_index, "stream incomplete, continuing");
                     continue;
                 }
-                fragmentation::DefragmentResult::Complete(power_shell_remoting_messages) => {
+                Ok(fragmentation::DefragmentResult::Complete(power_shell_remoting_messages)) => {
                     debug!(
                         target: "defragment",
                         stream_index,
@@ -429,20 +629,29 @@ impl RunspacePool {
                     );
                     power_shell_remoting_messages
                 }
+                Err(e) => {
+                    // The stream may have carried state messages; never skipped.
+                    error!(target: "defragment", stream_index, error = %e, "failed to defragment stream");
+                    return Err(e.into());
+                }
             };
 
             for (msg_index, message) in messages.into_iter().enumerate() {
-                let ps_value = message.parse_ps_message().map_err(|e| {
-                    error!(
-                        target: "ps_message",
-                        stream_index,
-                        ?message,
-                        error = %e,
-                        "failed to parse PS message"
-                    );
-                    e
-                })?;
-
+                let ps_value = match message.parse_ps_message_with_limits(self.parse_limits) {
+                    Ok(ps_value) => ps_value,
+                    Err(e) => {
+                        let e = self.defragmenter.decode_error(msg_index, &message, e);
+                        error!(
+                            target: "ps_message",
+                            stream_index,
+                            message_type = ?message.message_type,
+                            error = %e,
+                            "failed to parse PS message"
+                        );
+                        self.skip_or_fail(&stream, message.message_type, e.into(), &mut result)?;
+                        continue;
+                    }
+                };
                 info!(
                     target: "ps_message",
                     message_type = ?message.message_type,
@@ -450,447 +659,492 @@ impl RunspacePool {
                     msg_index,
                     "parsed PS message"
                 );
+                match self.handle_pwsh_message(&stream, &message, ps_value, &mut result) {
+                    Ok(()) => {}
+                    Err(MessageError::Malformed(e)) => {
+                        self.skip_or_fail(&stream, message.message_type, e, &mut result)?;
+                    }
+                    Err(MessageError::Failed(e)) => return Err(e),
+                }
+            }
+        }
 
-                match message.message_type {
-                    ironposh_psrp::MessageType::PublicKeyRequest => {
-                        debug!(
-                            target: "key_exchange",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling PublicKeyRequest message"
-                        );
+        info!(
+            target: "pwsh_responses",
+            result_count = result.len(),
+            "processed PowerShell responses"
+        );
+        Ok(result)
+    }
 
-                        // Validate the payload (best-effort).
-                        if let Err(e) = ironposh_psrp::PublicKeyRequest::try_from(ps_value.clone())
-                        {
-                            warn!(
-                                target: "key_exchange",
-                                error = %e,
-                                payload = ?ps_value,
-                                "unexpected PublicKeyRequest payload"
-                            );
-                        }
+    /// Under [`ParseMode::Lenient`], record `error` on a data-stream record
+    /// (output, error, verbose, warning, debug, information, progress) as a
+    /// [`AcceptResponsResult::ProtocolWarning`] so the rest of the response is
+    /// still handled; otherwise return it. Only for data that does not decode;
+    /// errors acting on a decoded message never come here.
+    fn skip_or_fail(
+        &self,
+        stream: &Stream,
+        message_type: ironposh_psrp::MessageType,
+        error: PwshCoreError,
+        result: &mut Vec<AcceptResponsResult>,
+    ) -> Result<(), PwshCoreError> {
+        if self.parse_mode == ParseMode::Strict || !is_data_record(message_type) {
+            return Err(error);
+        }
+        warn!(
+            target: "ps_message",
+            stream = %stream.name(),
+            command_id = ?stream.command_id(),
+            error = %error,
+            "skipping malformed PSRP data (lenient parsing)"
+        );
+        result.push(AcceptResponsResult::ProtocolWarning {
+            stream: stream.name().to_owned(),
+            handle: stream.command_id().map(|&id| PipelineHandle { id }),
+            error,
+        });
+        Ok(())
+    }
 
-                        let public_key_b64 = self.build_public_key_blob_base64()?;
-                        let public_key_msg = ironposh_psrp::PublicKey {
-                            public_key: public_key_b64,
-                        };
-                        let send_xml = self.send_runspace_pool_message(&public_key_msg)?;
+    /// Act on one decoded PSRP message from `stream`, appending what the
+    /// session has to do about it to `result`.
+    #[expect(clippy::too_many_lines)]
+    fn handle_pwsh_message(
+        &mut self,
+        stream: &Stream,
+        message: &PowerShellRemotingMessage,
+        ps_value: PsValue,
+        result: &mut Vec<AcceptResponsResult>,
+    ) -> Result<(), MessageError> {
+        match message.message_type {
+            ironposh_psrp::MessageType::PublicKeyRequest => {
+                debug!(
+                    target: "key_exchange",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling PublicKeyRequest message"
+                );
 
-                        result.push(AcceptResponsResult::SendThenReceive {
-                            send_xml,
-                            desired_streams: DesiredStream::runspace_pool_streams(),
-                        });
-                    }
-                    ironposh_psrp::MessageType::EncryptedSessionKey => {
-                        debug!(
-                            target: "key_exchange",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling EncryptedSessionKey message"
-                        );
+                // Validate the payload (best-effort).
+                if let Err(e) = ironposh_psrp::PublicKeyRequest::try_from(ps_value.clone()) {
+                    warn!(
+                        target: "key_exchange",
+                        error = %e,
+                        payload = ?ps_value,
+                        "unexpected PublicKeyRequest payload"
+                    );
+                }
 
-                        let PsValue::Object(obj) = ps_value else {
-                            return Err(crate::PwshCoreError::InvalidResponse(
-                                "Expected EncryptedSessionKey as PsValue::Object".into(),
-                            ));
-                        };
+                let public_key_b64 = self.build_public_key_blob_base64()?;
+                let public_key_msg = ironposh_psrp::PublicKey {
+                    public_key: public_key_b64,
+                };
+                let send_xml = self.send_runspace_pool_message(&public_key_msg)?;
 
-                        let encrypted = ironposh_psrp::EncryptedSessionKey::try_from(obj)?;
-                        let decoded = base64::engine::general_purpose::STANDARD
-                            .decode(encrypted.encrypted_session_key)
-                            .map_err(|e| {
-                                crate::PwshCoreError::InvalidResponse(
-                                    format!("Invalid base64 EncryptedSessionKey: {e}").into(),
-                                )
-                            })?;
-
-                        if decoded.len() < 12 + 256 {
-                            return Err(crate::PwshCoreError::InvalidResponse(
-                                format!(
-                                    "EncryptedSessionKey blob too short: {} bytes",
-                                    decoded.len()
-                                )
-                                .into(),
-                            ));
-                        }
-
-                        let encrypted_bytes = &decoded[12..12 + 256];
-                        let state = self.ensure_key_exchange_state()?;
-
-                        let decrypted = state
-                            .private_key
-                            .decrypt(Pkcs1v15Encrypt, encrypted_bytes)
-                            .or_else(|e| {
-                                // Some stacks may provide a representation that requires reversing.
-                                // Try best-effort before failing hard.
-                                let mut reversed = encrypted_bytes.to_vec();
-                                reversed.reverse();
-                                state
-                                    .private_key
-                                    .decrypt(Pkcs1v15Encrypt, &reversed)
-                                    .map_err(|_e2| e)
-                            })
-                            .map_err(|e| {
-                                crate::PwshCoreError::InternalError(format!(
-                                    "failed to decrypt EncryptedSessionKey: {e}"
-                                ))
-                            })?;
-
-                        if decrypted.len() != 32 {
-                            return Err(crate::PwshCoreError::InvalidResponse(
-                                format!(
-                                    "Unexpected decrypted PSRP session key length: {} bytes",
-                                    decrypted.len()
-                                )
-                                .into(),
-                            ));
-                        }
+                result.push(AcceptResponsResult::SendThenReceive {
+                    send_xml,
+                    desired_streams: DesiredStream::runspace_pool_streams(),
+                });
+            }
+            ironposh_psrp::MessageType::EncryptedSessionKey => {
+                debug!(
+                    target: "key_exchange",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling EncryptedSessionKey message"
+                );
 
-                        info!(
-                            target: "key_exchange",
-                            session_key_len = decrypted.len(),
-                            "stored decrypted PSRP session key"
-                        );
-                        state.session_key = Some(decrypted);
-
-                        self.psrp_key_exchange_pending = false;
-                        while let Some(host_call) = self.pending_host_calls.pop_front() {
-                            debug!(
-                                target: "key_exchange",
-                                host_call = ?host_call,
-                                "releasing deferred host call after key exchange"
-                            );
-                            result.push(AcceptResponsResult::HostCall(host_call));
-                        }
-                    }
-                    ironposh_psrp::MessageType::SessionCapability => {
-                        debug!(target: "session", "handling SessionCapability message");
-                        self.handle_session_capability(ps_value).map_err(|e| {
-                            error!(target: "session", error = %e, "failed to handle SessionCapability");
-                            e
-                        })?;
-                    }
-                    ironposh_psrp::MessageType::ApplicationPrivateData => {
-                        debug!(target: "session", "handling ApplicationPrivateData message");
-                        self.handle_application_private_data(ps_value)
-                            .map_err(|e| {
-                                error!(target: "session", error = %e, "failed to handle ApplicationPrivateData");
-                                e
-                            })?;
-                    }
-                    ironposh_psrp::MessageType::RunspacepoolState => {
-                        debug!(target: "runspace", "handling RunspacepoolState message");
-                        self.handle_runspacepool_state(ps_value).map_err(|e| {
-                            error!(target: "runspace", error = %e, "failed to handle RunspacepoolState");
-                            e
-                        })?;
-                    }
-                    ironposh_psrp::MessageType::ProgressRecord => {
-                        debug!(
-                            target: "progress",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling ProgressRecord message"
-                        );
-                        let record =
-                            self.handle_progress_record(ps_value, stream.name(), stream.command_id())
-                                .map_err(|e| {
-                                error!(target: "progress", error = %e, "failed to handle ProgressRecord");
-                                e
-                                })?;
-
-                        let cmd = *stream.command_id().ok_or_else(|| {
-                            crate::PwshCoreError::InvalidResponse(
-                                "ProgressRecord message must have a command_id".into(),
-                            )
-                        })?;
-                        let message_type = message.message_type.clone();
-                        let message_type_value = message_type.value();
-                        result.push(AcceptResponsResult::PipelineRecord {
-                            record: crate::psrp_record::PsrpRecord::Progress {
-                                meta: crate::psrp_record::PsrpRecordMeta {
-                                    message_type,
-                                    message_type_value,
-                                    stream: stream.name().to_string(),
-                                    command_id: Some(cmd),
-                                    data_len: message.data.len(),
-                                },
-                                record,
-                            },
-                            handle: PipelineHandle { id: cmd },
-                        });
-                    }
-                    ironposh_psrp::MessageType::InformationRecord => {
-                        debug!(
-                            target: "information",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling InformationRecord message"
-                        );
-                        let Some(cmd) = stream.command_id().copied() else {
-                            warn!(
-                                target: "ps_message",
-                                message_type = ?message.message_type,
-                                message_type_value = message.message_type.value(),
-                                stream = %stream.name(),
-                                command_id = ?stream.command_id(),
-                                "InformationRecord message missing command_id; ignoring"
-                            );
-                            continue;
-                        };
+                let encrypted: ironposh_psrp::EncryptedSessionKey =
+                    decode_object(ps_value, "EncryptedSessionKey")?;
+                let decoded = base64::engine::general_purpose::STANDARD
+                    .decode(encrypted.encrypted_session_key)
+                    .map_err(|e| {
+                        crate::PwshCoreError::invalid_response(
+                            crate::Phase::Receive,
+                            format!("Invalid base64 EncryptedSessionKey: {e}").into(),
+                        )
+                    })?;
+
+                if decoded.len() < 12 + 256 {
+                    return Err(crate::PwshCoreError::invalid_response(
+                        crate::Phase::Receive,
+                        format!(
+                            "EncryptedSessionKey blob too short: {} bytes",
+                            decoded.len()
+                        )
+                        .into(),
+                    )
+                    .into());
+                }
 
-                        let record = self
-                            .handle_information_record(ps_value, stream.name(), &cmd)
-                            .map_err(|e| {
-                                error!(
-                                    target: "information",
-                                    error = %e,
-                                    "failed to handle InformationRecord"
-                                );
-                                e
-                            })?;
-                        let message_type = message.message_type.clone();
-                        let message_type_value = message_type.value();
-                        result.push(AcceptResponsResult::PipelineRecord {
-                            record: crate::psrp_record::PsrpRecord::Information {
-                                meta: crate::psrp_record::PsrpRecordMeta {
-                                    message_type,
-                                    message_type_value,
-                                    stream: stream.name().to_string(),
-                                    command_id: Some(cmd),
-                                    data_len: message.data.len(),
-                                },
-                                record,
-                            },
-                            handle: PipelineHandle { id: cmd },
-                        });
-                    }
-                    ironposh_psrp::MessageType::DebugRecord
-                    | ironposh_psrp::MessageType::VerboseRecord
-                    | ironposh_psrp::MessageType::WarningRecord => {
-                        let Some(cmd) = stream.command_id().copied() else {
-                            warn!(
-                                target: "ps_message",
-                                message_type = ?message.message_type,
-                                message_type_value = message.message_type.value(),
-                                stream = %stream.name(),
-                                command_id = ?stream.command_id(),
-                                "record message missing command_id; ignoring"
-                            );
-                            continue;
-                        };
+                let encrypted_bytes = &decoded[12..12 + 256];
+                let state = self.ensure_key_exchange_state()?;
+
+                let decrypted = state
+                    .private_key
+                    .decrypt(Pkcs1v15Encrypt, encrypted_bytes)
+                    .or_else(|e| {
+                        // Some stacks may provide a representation that requires reversing.
+                        // Try best-effort before failing hard.
+                        let mut reversed = encrypted_bytes.to_vec();
+                        reversed.reverse();
+                        state
+                            .private_key
+                            .decrypt(Pkcs1v15Encrypt, &reversed)
+                            .map_err(|_e2| e)
+                    })
+                    .map_err(|e| {
+                        crate::PwshCoreError::InternalError(format!(
+                            "failed to decrypt EncryptedSessionKey: {e}"
+                        ))
+                    })?;
+
+                if decrypted.len() != 32 {
+                    return Err(crate::PwshCoreError::invalid_response(
+                        crate::Phase::Receive,
+                        format!(
+                            "Unexpected decrypted PSRP session key length: {} bytes",
+                            decrypted.len()
+                        )
+                        .into(),
+                    )
+                    .into());
+                }
 
-                        let msg = ps_value.as_string().unwrap_or_else(|| ps_value.to_string());
+                info!(
+                    target: "key_exchange",
+                    session_key_len = decrypted.len(),
+                    "stored decrypted PSRP session key"
+                );
+                state.session_key = Some(decrypted);
 
-                        let message_type = message.message_type.clone();
-                        let message_type_value = message_type.value();
-                        let meta = crate::psrp_record::PsrpRecordMeta {
-                            message_type: message_type.clone(),
+                self.psrp_key_exchange_pending = false;
+                while let Some(host_call) = self.pending_host_calls.pop_front() {
+                    debug!(
+                        target: "key_exchange",
+                        host_call = ?host_call,
+                        "releasing deferred host call after key exchange"
+                    );
+                    result.push(AcceptResponsResult::HostCall(host_call));
+                }
+            }
+            ironposh_psrp::MessageType::SessionCapability => {
+                debug!(target: "session", "handling SessionCapability message");
+                self.handle_session_capability(ps_value).map_err(|e| {
+                    error!(target: "session", error = %e, "failed to handle SessionCapability");
+                    e
+                })?;
+            }
+            ironposh_psrp::MessageType::ApplicationPrivateData => {
+                debug!(target: "session", "handling ApplicationPrivateData message");
+                self.handle_application_private_data(ps_value)
+                    .map_err(|e| {
+                        error!(target: "session", error = %e, "failed to handle ApplicationPrivateData");
+                        e
+                    })?;
+            }
+            ironposh_psrp::MessageType::RunspacepoolState => {
+                debug!(target: "runspace", "handling RunspacepoolState message");
+                let broken = self.handle_runspacepool_state(ps_value).map_err(|e| {
+                    error!(target: "runspace", error = %e, "failed to handle RunspacepoolState");
+                    e
+                })?;
+                if let Some(error_record) = broken {
+                    result.push(AcceptResponsResult::PoolBroken { error_record });
+                }
+            }
+            ironposh_psrp::MessageType::ProgressRecord => {
+                debug!(
+                    target: "progress",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling ProgressRecord message"
+                );
+                let record = self
+                    .handle_progress_record(ps_value, stream.name(), stream.command_id())
+                    .map_err(|e| {
+                        error!(target: "progress", error = %e, "failed to handle ProgressRecord");
+                        e
+                    })?;
+
+                let cmd = *stream.command_id().ok_or_else(|| {
+                    crate::PwshCoreError::invalid_response(
+                        crate::Phase::Receive,
+                        "ProgressRecord message must have a command_id".into(),
+                    )
+                })?;
+                let message_type = message.message_type.clone();
+                let message_type_value = message_type.value();
+                result.push(AcceptResponsResult::PipelineRecord {
+                    record: crate::psrp_record::PsrpRecord::Progress {
+                        meta: crate::psrp_record::PsrpRecordMeta {
+                            message_type,
                             message_type_value,
                             stream: stream.name().to_string(),
                             command_id: Some(cmd),
                             data_len: message.data.len(),
-                        };
-
-                        let record = match message_type {
-                            ironposh_psrp::MessageType::DebugRecord => {
-                                crate::psrp_record::PsrpRecord::Debug { meta, message: msg }
-                            }
-                            ironposh_psrp::MessageType::VerboseRecord => {
-                                crate::psrp_record::PsrpRecord::Verbose { meta, message: msg }
-                            }
-                            ironposh_psrp::MessageType::WarningRecord => {
-                                crate::psrp_record::PsrpRecord::Warning { meta, message: msg }
-                            }
-                            _ => unreachable!("guarded by match arm"),
-                        };
+                        },
+                        record,
+                    },
+                    handle: PipelineHandle { id: cmd },
+                });
+            }
+            ironposh_psrp::MessageType::InformationRecord => {
+                debug!(
+                    target: "information",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling InformationRecord message"
+                );
+                let Some(cmd) = stream.command_id().copied() else {
+                    warn!(
+                        target: "ps_message",
+                        message_type = ?message.message_type,
+                        message_type_value = message.message_type.value(),
+                        stream = %stream.name(),
+                        command_id = ?stream.command_id(),
+                        "InformationRecord message missing command_id; ignoring"
+                    );
+                    return Ok(());
+                };
 
-                        result.push(AcceptResponsResult::PipelineRecord {
-                            record,
-                            handle: PipelineHandle { id: cmd },
-                        });
-                    }
-                    ironposh_psrp::MessageType::PipelineState => {
-                        debug!(
-                            target: "pipeline",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling PipelineState message"
+                let record = self
+                    .handle_information_record(ps_value, stream.name(), &cmd)
+                    .map_err(|e| {
+                        error!(
+                            target: "information",
+                            error = %e,
+                            "failed to handle InformationRecord"
                         );
-                        self.handle_pipeline_state(ps_value, stream.name(), stream.command_id())
-                            .map_err(|e| {
-                                error!(target: "pipeline", error = %e, "failed to handle PipelineState");
-                                e
-                            })?;
+                        e
+                    })?;
+                let message_type = message.message_type.clone();
+                let message_type_value = message_type.value();
+                result.push(AcceptResponsResult::PipelineRecord {
+                    record: crate::psrp_record::PsrpRecord::Information {
+                        meta: crate::psrp_record::PsrpRecordMeta {
+                            message_type,
+                            message_type_value,
+                            stream: stream.name().to_string(),
+                            command_id: Some(cmd),
+                            data_len: message.data.len(),
+                        },
+                        record,
+                    },
+                    handle: PipelineHandle { id: cmd },
+                });
+            }
+            ironposh_psrp::MessageType::DebugRecord
+            | ironposh_psrp::MessageType::VerboseRecord
+            | ironposh_psrp::MessageType::WarningRecord => {
+                let Some(cmd) = stream.command_id().copied() else {
+                    warn!(
+                        target: "ps_message",
+                        message_type = ?message.message_type,
+                        message_type_value = message.message_type.value(),
+                        stream = %stream.name(),
+                        command_id = ?stream.command_id(),
+                        "record message missing command_id; ignoring"
+                    );
+                    return Ok(());
+                };
+
+                let msg = ps_value.as_string().unwrap_or_else(|| ps_value.to_string());
+
+                let message_type = message.message_type.clone();
+                let message_type_value = message_type.value();
+                let meta = crate::psrp_record::PsrpRecordMeta {
+                    message_type: message_type.clone(),
+                    message_type_value,
+                    stream: stream.name().to_string(),
+                    command_id: Some(cmd),
+                    data_len: message.data.len(),
+                };
+
+                let record = match message_type {
+                    ironposh_psrp::MessageType::DebugRecord => {
+                        crate::psrp_record::PsrpRecord::Debug { meta, message: msg }
                     }
-                    ironposh_psrp::MessageType::PipelineHostCall => {
-                        debug!(
-                            target: "host_call",
-                            stream_name = ?stream.name(),
-                            pipeline_id = ?stream.command_id(),
-                            "handling PipelineHostCall message"
-                        );
-
-                        let host_call = self
-                            .handle_pipeline_host_call(ps_value, stream.name(), stream.command_id())
-                            .map_err(|e| {
-                                error!(target: "host_call", error = %e, "failed to handle PipelineHostCall");
-                                e
-                            })?;
-                        debug!(target: "host_call", host_call = ?host_call, "successfully created host call");
-
-                        let needs_session_key = super::host_call::needs_session_key(&host_call);
-
-                        let has_session_key = self
-                            .key_exchange
-                            .as_ref()
-                            .and_then(|s| s.session_key.as_ref())
-                            .is_some();
-
-                        if needs_session_key && !has_session_key {
-                            info!(
-                                target: "key_exchange",
-                                host_call_method = host_call.method_name(),
-                                "deferring host call until PSRP session key is established"
-                            );
-                            self.pending_host_calls.push_back(host_call);
-
-                            if !self.psrp_key_exchange_pending {
-                                self.psrp_key_exchange_pending = true;
-
-                                info!(
-                                    target: "key_exchange",
-                                    "starting client-initiated PSRP key exchange"
-                                );
-                                let public_key_b64 = self.build_public_key_blob_base64()?;
-                                let public_key_msg = ironposh_psrp::PublicKey {
-                                    public_key: public_key_b64,
-                                };
-                                let send_xml = self.send_runspace_pool_message(&public_key_msg)?;
-                                result.push(AcceptResponsResult::SendThenReceive {
-                                    send_xml,
-                                    desired_streams: DesiredStream::runspace_pool_streams(),
-                                });
-                            }
-                        } else {
-                            result.push(AcceptResponsResult::HostCall(host_call));
-                        }
+                    ironposh_psrp::MessageType::VerboseRecord => {
+                        crate::psrp_record::PsrpRecord::Verbose { meta, message: msg }
                     }
-                    ironposh_psrp::MessageType::PipelineOutput => {
-                        debug!(
-                            target: "pipeline_output",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling PipelineOutput message"
-                        );
-
-                        let output = self.handle_pipeline_output(ps_value)?;
-
-                        debug!(target: "pipeline_output", output = ?output, "successfully handled PipelineOutput");
-                        result.push(AcceptResponsResult::PipelineOutput {
-                            output,
-                            handle: PipelineHandle {
-                                id: *stream.command_id().ok_or_else(|| {
-                                    crate::PwshCoreError::InvalidResponse(
-                                        "PipelineOutput message must have a command_id".into(),
-                                    )
-                                })?,
-                            },
-                        });
+                    ironposh_psrp::MessageType::WarningRecord => {
+                        crate::psrp_record::PsrpRecord::Warning { meta, message: msg }
                     }
-                    ironposh_psrp::MessageType::ErrorRecord => {
-                        debug!(
-                            target: "error_record",
-                            stream_name = ?stream.name(),
-                            command_id = ?stream.command_id(),
-                            "handling ErrorRecord message"
-                        );
+                    _ => unreachable!("guarded by match arm"),
+                };
 
-                        let PsValue::Object(complex_object) = ps_value else {
-                            return Err(crate::PwshCoreError::InvalidResponse(
-                                "Expected ErrorRecord as PsValue::Object".into(),
-                            ));
-                        };
+                result.push(AcceptResponsResult::PipelineRecord {
+                    record,
+                    handle: PipelineHandle { id: cmd },
+                });
+            }
+            ironposh_psrp::MessageType::PipelineState => {
+                debug!(
+                    target: "pipeline",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling PipelineState message"
+                );
+                self.handle_pipeline_state(ps_value, stream.name(), stream.command_id())
+                    .map_err(|e| {
+                        error!(target: "pipeline", error = %e, "failed to handle PipelineState");
+                        e
+                    })?;
+            }
+            ironposh_psrp::MessageType::PipelineHostCall => {
+                debug!(
+                    target: "host_call",
+                    stream_name = ?stream.name(),
+                    pipeline_id = ?stream.command_id(),
+                    "handling PipelineHostCall message"
+                );
 
-                        let error_record = ErrorRecord::try_from(complex_object).map_err(|e| {
-                            error!(target: "error_record", error = %e, "failed to parse ErrorRecord");
-                            e
-                        })?;
-
-                        debug!(target: "error_record", error_record = ?error_record, "successfully parsed ErrorRecord");
-                        result.push(AcceptResponsResult::ErrorRecord {
-                            error_record,
-                            handle: PipelineHandle {
-                                id: *stream.command_id().ok_or_else(|| {
-                                    crate::PwshCoreError::InvalidResponse(
-                                        "ErrorRecord message must have a command_id".into(),
-                                    )
-                                })?,
-                            },
-                        });
-                    }
-                    _ => {
-                        let data_len = message.data.len();
-                        let data_preview = String::from_utf8_lossy(
-                            &message.data[..std::cmp::min(message.data.len(), 512)],
-                        );
-                        error!(
-                            target: "ps_message",
-                            message_type = ?message.message_type,
-                            message_type_value = message.message_type.value(),
-                            stream = %stream.name(),
-                            command_id = ?stream.command_id(),
-                            data_len,
-                            data_preview = %data_preview,
-                            "received message type but no handler implemented"
-                        );
+                let host_call = self
+                    .handle_pipeline_host_call(ps_value, stream.name(), stream.command_id())
+                    .map_err(|e| {
+                        error!(target: "host_call", error = %e, "failed to handle PipelineHostCall");
+                        e
+                    })?;
+                debug!(target: "host_call", host_call = ?host_call, "successfully created host call");
+
+                let needs_session_key = super::host_call::needs_session_key(&host_call);
+
+                let has_session_key = self
+                    .key_exchange
+                    .as_ref()
+                    .and_then(|s| s.session_key.as_ref())
+                    .is_some();
+
+                if needs_session_key && !has_session_key {
+                    info!(
+                        target: "key_exchange",
+                        host_call_method = host_call.method_name(),
+                        "deferring host call until PSRP session key is established"
+                    );
+                    self.pending_host_calls.push_back(host_call);
+
+                    if !self.psrp_key_exchange_pending {
+                        self.psrp_key_exchange_pending = true;
 
-                        let Some(cmd) = stream.command_id().copied() else {
-                            // No pipeline to attach to; log only (do not crash the session).
-                            continue;
+                        info!(
+                            target: "key_exchange",
+                            "starting client-initiated PSRP key exchange"
+                        );
+                        let public_key_b64 = self.build_public_key_blob_base64()?;
+                        let public_key_msg = ironposh_psrp::PublicKey {
+                            public_key: public_key_b64,
                         };
-                        let message_type = message.message_type.clone();
-                        let message_type_value = message_type.value();
-
-                        result.push(AcceptResponsResult::PipelineRecord {
-                            record: crate::psrp_record::PsrpRecord::Unsupported {
-                                meta: crate::psrp_record::PsrpRecordMeta {
-                                    message_type,
-                                    message_type_value,
-                                    stream: stream.name().to_string(),
-                                    command_id: Some(cmd),
-                                    data_len,
-                                },
-                                data_preview: data_preview.to_string(),
-                            },
-                            handle: PipelineHandle { id: cmd },
+                        let send_xml = self.send_runspace_pool_message(&public_key_msg)?;
+                        result.push(AcceptResponsResult::SendThenReceive {
+                            send_xml,
+                            desired_streams: DesiredStream::runspace_pool_streams(),
                         });
                     }
+                } else {
+                    result.push(AcceptResponsResult::HostCall(host_call));
                 }
             }
-        }
+            ironposh_psrp::MessageType::PipelineOutput => {
+                debug!(
+                    target: "pipeline_output",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling PipelineOutput message"
+                );
 
-        info!(
-            target: "pwsh_responses",
-            result_count = result.len(),
-            "processed PowerShell responses"
-        );
-        Ok(result)
+                let output = self.handle_pipeline_output(ps_value)?;
+
+                debug!(target: "pipeline_output", output = ?output, "successfully handled PipelineOutput");
+                result.push(AcceptResponsResult::PipelineOutput {
+                    output,
+                    data_len: message.data.len(),
+                    handle: PipelineHandle {
+                        id: *stream.command_id().ok_or_else(|| {
+                            crate::PwshCoreError::invalid_response(
+                                crate::Phase::Receive,
+                                "PipelineOutput message must have a command_id".into(),
+                            )
+                        })?,
+                    },
+                });
+            }
+            ironposh_psrp::MessageType::ErrorRecord => {
+                debug!(
+                    target: "error_record",
+                    stream_name = ?stream.name(),
+                    command_id = ?stream.command_id(),
+                    "handling ErrorRecord message"
+                );
+
+                let error_record: ErrorRecord =
+                    decode_object(ps_value, "ErrorRecord").map_err(|e| {
+                        error!(target: "error_record", error = %e, "failed to parse ErrorRecord");
+                        e
+                    })?;
+
+                debug!(target: "error_record", error_record = ?error_record, "successfully parsed ErrorRecord");
+                result.push(AcceptResponsResult::ErrorRecord {
+                    error_record,
+                    handle: PipelineHandle {
+                        id: *stream.command_id().ok_or_else(|| {
+                            crate::PwshCoreError::invalid_response(
+                                crate::Phase::Receive,
+                                "ErrorRecord message must have a command_id".into(),
+                            )
+                        })?,
+                    },
+                });
+            }
+            _ => {
+                let data_len = message.data.len();
+                let data_preview = String::from_utf8_lossy(
+                    &message.data[..std::cmp::min(message.data.len(), 512)],
+                );
+                error!(
+                    target: "ps_message",
+                    message_type = ?message.message_type,
+                    message_type_value = message.message_type.value(),
+                    stream = %stream.name(),
+                    command_id = ?stream.command_id(),
+                    data_len,
+                    data_preview = %data_preview,
+                    "received message type but no handler implemented"
+                );
+
+                let Some(cmd) = stream.command_id().copied() else {
+                    // No pipeline to attach to; log only (do not crash the session).
+                    return Ok(());
+                };
+                let message_type = message.message_type.clone();
+                let message_type_value = message_type.value();
+
+                result.push(AcceptResponsResult::PipelineRecord {
+                    record: crate::psrp_record::PsrpRecord::Unsupported {
+                        meta: crate::psrp_record::PsrpRecordMeta {
+                            message_type,
+                            message_type_value,
+                            stream: stream.name().to_string(),
+                            command_id: Some(cmd),
+                            data_len,
+                        },
+                        data_preview: data_preview.to_string(),
+                    },
+                    handle: PipelineHandle { id: cmd },
+                });
+            }
+        }
+        Ok(())
     }
 
     #[instrument(skip(self, session_capability), fields(protocol_version = tracing::field::Empty, ps_version = tracing::field::Empty))]
-    pub(super) fn handle_session_capability(
+    fn handle_session_capability(
         &mut self,
         session_capability: PsValue,
-    ) -> Result<(), crate::PwshCoreError> {
-        let PsValue::Object(session_capability) = session_capability else {
-            return Err(PwshCoreError::InvalidResponse(
-                "Expected SessionCapability as PsValue::Object".into(),
-            ));
-        };
-
-        let session_capability = SessionCapability::try_from(session_capability)?;
+    ) -> Result<(), MessageError> {
+        let session_capability: SessionCapability =
+            decode_object(session_capability, "SessionCapability")?;
 
         debug!(
             target: "session",
@@ -902,31 +1156,22 @@ impl RunspacePool {
     }
 
     #[instrument(skip(self, app_data))]
-    pub(super) fn handle_application_private_data(
-        &mut self,
-        app_data: PsValue,
-    ) -> Result<(), crate::PwshCoreError> {
-        let PsValue::Object(app_data) = app_data else {
-            return Err(PwshCoreError::InvalidResponse(
-                "Expected ApplicationPrivateData as PsValue::Object".into(),
-            ));
-        };
-
-        let app_data = ApplicationPrivateData::try_from(app_data)?;
+    fn handle_application_private_data(&mut self, app_data: PsValue) -> Result<(), MessageError> {
+        let app_data: ApplicationPrivateData = decode_object(app_data, "ApplicationPrivateData")?;
         trace!(target: "session", app_data = ?app_data, "received ApplicationPrivateData");
         self.application_private_data = Some(app_data);
         Ok(())
     }
 
+    /// Apply a RUNSPACEPOOL_STATE message. Returns the reason as an `ErrorRecord`
+    /// when the server moved the pool to `Broken`.
     #[instrument(skip(self, ps_value), fields(runspace_state = tracing::field::Empty))]
-    fn handle_runspacepool_state(&mut self, ps_value: PsValue) -> Result<(), crate::PwshCoreError> {
-        let PsValue::Object(runspacepool_state) = ps_value else {
-            return Err(PwshCoreError::InvalidResponse(
-                "Expected RunspacepoolState as PsValue::Object".into(),
-            ));
-        };
-
-        let runspacepool_state = RunspacePoolStateMessage::try_from(runspacepool_state)?;
+    fn handle_runspacepool_state(
+        &mut self,
+        ps_value: PsValue,
+    ) -> Result<Option<ErrorRecord>, MessageError> {
+        let runspacepool_state: RunspacePoolStateMessage =
+            decode_object(ps_value, "RunspacepoolState")?;
 
         // Record the state in the span
         let span = tracing::Span::current();
@@ -937,9 +1182,26 @@ impl RunspacePool {
 
         trace!(target: "runspace", state = ?runspacepool_state, "received RunspacePoolState");
 
-        self.state = RunspacePoolState::from(&runspacepool_state.runspace_state);
+        self.set_state(RunspacePoolState::from(&runspacepool_state.runspace_state));
+        if self.state != RunspacePoolState::Broken {
+            return Ok(None);
+        }
 
-        Ok(())
+        // The server attaches the cause (service restart, shell closed by policy, ...)
+        // as ExceptionAsErrorRecord; fall back to a generic record when it does not.
+        let error_record = match runspacepool_state.exception_as_error_record {
+            Some(PsValue::Object(record)) => ErrorRecord::try_from(record).ok(),
+            _ => None,
+        }
+        .unwrap_or_else(|| {
+            ErrorRecord::builder()
+                .message("The server closed the runspace pool.".to_owned())
+                .fully_qualified_error_id(Some("RunspacePoolBroken".to_owned()))
+                .build()
+        });
+        warn!(target: "runspace", reason = %error_record.message, "runspace pool is broken");
+
+        Ok(Some(error_record))
     }
 
     #[instrument(skip(self, ps_value), fields(stream_name, command_id = ?command_id))]
@@ -948,20 +1210,17 @@ impl RunspacePool {
         ps_value: PsValue,
         stream_name: &str,
         command_id: Option<&Uuid>,
-    ) -> Result<ironposh_psrp::ProgressRecord, crate::PwshCoreError> {
-        let PsValue::Object(progress_record) = ps_value else {
-            return Err(PwshCoreError::InvalidResponse(
-                "Expected ProgressRecord as PsValue::Object".into(),
-            ));
-        };
-
-        let progress_record = ironposh_psrp::ProgressRecord::try_from(progress_record)?;
+    ) -> Result<ironposh_psrp::ProgressRecord, MessageError> {
+        let progress_record: ironposh_psrp::ProgressRecord =
+            decode_object(ps_value, "ProgressRecord")?;
 
         // Question: Can we have a Optional command id here?
         let Some(command_id) = command_id else {
-            return Err(PwshCoreError::InvalidResponse(
+            return Err(PwshCoreError::invalid_response(
+                crate::Phase::Receive,
                 "Expected command_id to be Some".into(),
-            ));
+            )
+            .into());
         };
 
         trace!(
@@ -974,7 +1233,10 @@ impl RunspacePool {
 
         // Find the pipeline by command_id
         let pipeline = self.pipelines.get_mut(command_id).ok_or_else(|| {
-            PwshCoreError::InvalidResponse("Pipeline not found for command_id".into())
+            PwshCoreError::invalid_response(
+                crate::Phase::Receive,
+                "Pipeline not found for command_id".into(),
+            )
         })?;
 
         pipeline.add_progress_record(progress_record.clone());
@@ -1047,7 +1309,10 @@ impl RunspacePool {
 
         // Find the pipeline by command_id
         let pipeline = self.pipelines.get_mut(command_id).ok_or_else(|| {
-            PwshCoreError::InvalidResponse("Pipeline not found for command_id".into())
+            PwshCoreError::invalid_response(
+                crate::Phase::Receive,
+                "Pipeline not found for command_id".into(),
+            )
         })?;
 
         pipeline.add_information_record(info_record.clone());
@@ -1061,14 +1326,9 @@ impl RunspacePool {
         ps_value: PsValue,
         stream_name: &str,
         command_id: Option<&Uuid>,
-    ) -> Result<(), crate::PwshCoreError> {
-        let PsValue::Object(pipeline_state) = ps_value else {
-            return Err(PwshCoreError::InvalidResponse(
-                "Expected PipelineState as PsValue::Object".into(),
-            ));
-        };
-
-        let pipeline_state = ironposh_psrp::PipelineStateMessage::try_from(pipeline_state)?;
+    ) -> Result<(), MessageError> {
+        let pipeline_state: ironposh_psrp::PipelineStateMessage =
+            decode_object(ps_value, "PipelineState")?;
         trace!(
             ?pipeline_state,
             stream_name = stream_name,
@@ -1077,14 +1337,19 @@ impl RunspacePool {
         );
         // Question: Can we have a Optional command id here?
         let Some(command_id) = command_id else {
-            return Err(PwshCoreError::InvalidResponse(
+            return Err(PwshCoreError::invalid_response(
+                crate::Phase::Receive,
                 "Expected command_id to be Some".into(),
-            ));
+            )
+            .into());
         };
 
         // Find the pipeline by command_id
         let pipeline = self.pipelines.get_mut(command_id).ok_or_else(|| {
-            PwshCoreError::InvalidResponse("Pipeline not found for command_id".into())
+            PwshCoreError::invalid_response(
+                crate::Phase::Receive,
+                "Pipeline not found for command_id".into(),
+            )
         })?;
         // Update the pipeline state
         pipeline.set_state(PsInvocationState::from(pipeline_state.pipeline_state));
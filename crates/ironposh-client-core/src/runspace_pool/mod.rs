@@ -10,6 +10,7 @@ mod requests;
 pub mod types;
 
 // Re-export public types
+pub use crate::runspace::win_rs::{ShellOptions, ShellReference};
 pub use creator::RunspacePoolCreator;
 pub use enums::{PowerShellState, PsInvocationState, RunspacePoolState};
 pub use expect_shell_connected::ExpectShellConnected;
@@ -9,7 +9,8 @@ use ironposh_psrp::{
     CreatePipeline, Defragmenter, ErrorRecord, HostInfo, InitRunspacePool, PSThreadOptions,
     PipelineOutput, SessionCapability, fragmentation,
 };
-use ironposh_winrm::ws_management::{OptionSetValue, WsMan};
+use ironposh_winrm::ws_management::{OptionSetValue, WellKnownOption, WsMan};
+use ironposh_xml::builder::SerializationScratch;
 use rsa::RsaPrivateKey;
 use tracing::{debug, info, instrument, trace, warn};
 
@@ -20,7 +21,7 @@ use crate::{
     host::HostCall,
     pipeline::{Pipeline, PipelineCommand},
     powershell::PipelineHandle,
-    runspace::win_rs::WinRunspace,
+    runspace::win_rs::{ShellReference, WinRunspace},
     runspace_pool::PsInvocationState,
 };
 
@@ -97,6 +98,8 @@ pub enum AcceptResponsResult {
     PipelineOutput {
         output: PipelineOutput,
         handle: PipelineHandle,
+        /// Size of the raw PSRP message payload, used for receive flow control.
+        data_len: usize,
     },
     ErrorRecord {
         error_record: ErrorRecord,
@@ -106,6 +109,23 @@ pub enum AcceptResponsResult {
         record: crate::psrp_record::PsrpRecord,
         handle: PipelineHandle,
     },
+    /// A pipeline stream carried `End="true"`: the server sends nothing more
+    /// on it.
+    StreamEnded {
+        handle: PipelineHandle,
+        stream: String,
+    },
+    /// The server moved the runspace pool to `Broken` (e.g. the WinRM service
+    /// restarted or a policy closed the shell); `error_record` carries the cause.
+    PoolBroken {
+        error_record: ErrorRecord,
+    },
+    /// Lenient parsing skipped a data-stream record it could not decode.
+    ProtocolWarning {
+        stream: String,
+        handle: Option<PipelineHandle>,
+        error: crate::PwshCoreError,
+    },
 }
 
 #[derive(Debug)]
@@ -121,14 +141,25 @@ pub struct RunspacePool {
     pub(super) shell: WinRunspace,
     pub(super) connection: Arc<WsMan>,
     pub(super) defragmenter: Defragmenter,
+    pub(super) parse_mode: crate::connector::config::ParseMode,
+    pub(super) parse_limits: crate::connector::config::ParseLimits,
     pub(super) application_private_data: Option<ApplicationPrivateData>,
     pub(super) session_capability: Option<SessionCapability>,
+    pub(super) time_zone: Option<ironposh_psrp::TimeZone>,
     pub(super) pipelines: HashMap<uuid::Uuid, Pipeline>,
     pub(super) fragmenter: fragmentation::Fragmenter,
     pub(super) desired_stream_is_pooling: bool,
     pub(super) key_exchange: Option<super::crypto::KeyExchangeState>,
     pub(super) psrp_key_exchange_pending: bool,
     pub(super) pending_host_calls: VecDeque<HostCall>,
+    /// Reused output buffer for rendering every outgoing request envelope.
+    pub(super) scratch: SerializationScratch,
+    /// Reused buffer the `<rsp:Stream>` payloads of each ReceiveResponse are
+    /// base64-decoded into.
+    pub(super) stream_decode_buffer: Vec<u8>,
+    /// Where the pool and its pipelines record their transitions.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) timeline: Option<crate::connector::StateTimeline>,
 }
 
 impl RunspacePool {
@@ -143,6 +174,33 @@ impl RunspacePool {
         super::crypto::encrypt_secure_strings_in_value_rec(value, session_key)
     }
 
+    /// Record this pool's (and its pipelines') transitions into `timeline`.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn with_timeline(
+        mut self,
+        timeline: Option<crate::connector::StateTimeline>,
+    ) -> Self {
+        if let Some(timeline) = &timeline {
+            timeline.record(
+                crate::connector::timeline::Machine::RunspacePool,
+                format!("{:?}", self.state),
+            );
+        }
+        self.timeline = timeline;
+        self
+    }
+
+    pub(crate) fn set_state(&mut self, state: RunspacePoolState) {
+        #[cfg(feature = "diagnostics")]
+        if let Some(timeline) = &self.timeline {
+            timeline.record(
+                crate::connector::timeline::Machine::RunspacePool,
+                format!("{state:?}"),
+            );
+        }
+        self.state = state;
+    }
+
     /// Build the negotiation payload shared by [`Self::open`] and
     /// [`Self::connect`]: SESSION_CAPABILITY plus the path-specific second
     /// message, fragmented into a single base64-encoded request group, with
@@ -155,7 +213,10 @@ impl RunspacePool {
             protocol_version: PROTOCOL_VERSION.to_string(),
             ps_version: PS_VERSION.to_string(),
             serialization_version: SERIALIZATION_VERSION.to_string(),
-            time_zone: None,
+            time_zone: self
+                .time_zone
+                .as_ref()
+                .map(ironposh_psrp::TimeZone::to_bytes),
         };
 
         debug!(
@@ -189,7 +250,9 @@ impl RunspacePool {
             ))
             .map(|bytes| base64::engine::general_purpose::STANDARD.encode(&bytes[..]))?;
 
-        let option_set = OptionSetValue::new().add_option("protocolversion", PROTOCOL_VERSION);
+        let option_set = OptionSetValue::new().add_well_known(WellKnownOption::ProtocolVersion(
+            PROTOCOL_VERSION.to_owned(),
+        ));
 
         Ok((request, option_set))
     }
@@ -223,14 +286,19 @@ impl RunspacePool {
 
         let (request, option_set) = self.negotiation_payload(&init_runspace_pool)?;
 
-        self.state = RunspacePoolState::NegotiationSent;
+        self.set_state(RunspacePoolState::NegotiationSent);
 
-        let result = self
+        let xml = self
             .shell
-            .open(&self.connection, Some(option_set), &request);
-
+            .open(
+                &self.connection,
+                &mut self.scratch,
+                Some(option_set),
+                &request,
+            )?
+            .to_owned();
         Ok((
-            result.into().to_xml_string()?,
+            xml,
             super::expect_shell_created::ExpectShellCreated {
                 runspace_pool: self,
             },
@@ -267,14 +335,19 @@ impl RunspacePool {
 
         let (request, option_set) = self.negotiation_payload(&connect_runspace_pool)?;
 
-        self.state = RunspacePoolState::Connecting;
+        self.set_state(RunspacePoolState::Connecting);
 
-        let result = self
+        let xml = self
             .shell
-            .connect(&self.connection, Some(option_set), &request);
-
+            .connect(
+                &self.connection,
+                &mut self.scratch,
+                Some(option_set),
+                &request,
+            )?
+            .to_owned();
         Ok((
-            result.into().to_xml_string()?,
+            xml,
             super::expect_shell_connected::ExpectShellConnected {
                 runspace_pool: self,
             },
@@ -286,6 +359,13 @@ impl RunspacePool {
         self.shell.shell_id()
     }
 
+    /// The shell's endpoint reference: the resource URI and selectors every
+    /// request to it is addressed with (available after the shell has been
+    /// created).
+    pub fn shell_reference(&self) -> Option<&ShellReference> {
+        self.shell.reference()
+    }
+
     /// Server-supplied ApplicationPrivateData, if delivered during open/connect.
     pub fn application_private_data(&self) -> Option<&ApplicationPrivateData> {
         self.application_private_data.as_ref()
@@ -295,7 +375,7 @@ impl RunspacePool {
     /// Valid only in `Disconnecting` state; reverts the pool to `Opened`.
     pub(crate) fn abort_disconnect(&mut self) {
         if self.state == RunspacePoolState::Disconnecting {
-            self.state = RunspacePoolState::Opened;
+            self.set_state(RunspacePoolState::Opened);
             warn!(runspace_pool_id = %self.id, "disconnect aborted, runspace pool reverted to Opened");
         } else {
             warn!(
@@ -310,7 +390,7 @@ impl RunspacePool {
     /// Valid only in `Connecting` state; reverts the pool to `Disconnected`.
     pub(crate) fn abort_reconnect(&mut self) {
         if self.state == RunspacePoolState::Connecting {
-            self.state = RunspacePoolState::Disconnected;
+            self.set_state(RunspacePoolState::Disconnected);
             warn!(runspace_pool_id = %self.id, "reconnect aborted, runspace pool reverted to Disconnected");
         } else {
             warn!(
@@ -338,6 +418,18 @@ impl RunspacePool {
         streams
     }
 
+    /// Pipelines created and not yet finished, ordered by id.
+    pub(crate) fn pipeline_handles(&self) -> Vec<PipelineHandle> {
+        let mut ids: Vec<Uuid> = self.pipelines.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| PipelineHandle { id }).collect()
+    }
+
+    /// Forget a pipeline whose Command the server never ran.
+    pub(crate) fn discard_pipeline(&mut self, id: &Uuid) -> bool {
+        self.pipelines.remove(id).is_some()
+    }
+
     pub(crate) fn init_pipeline(
         &mut self,
         uuid: Uuid,
@@ -348,7 +440,10 @@ impl RunspacePool {
             ));
         }
 
-        self.pipelines.insert(uuid, Pipeline::new());
+        let pipeline = Pipeline::new();
+        #[cfg(feature = "diagnostics")]
+        let pipeline = pipeline.with_timeline(uuid, self.timeline.clone());
+        self.pipelines.insert(uuid, pipeline);
         Ok(PipelineHandle { id: uuid })
     }
 
@@ -372,6 +467,8 @@ impl RunspacePool {
             .pipeline(protocol_pipeline)
             .host_info(self.host_info.clone())
             .apartment_state(self.apartment_state)
+            .add_to_history(pipeline.add_to_history)
+            .no_input(!pipeline.takes_input)
             .build();
 
         debug!(?create_pipeline);
@@ -385,15 +482,16 @@ impl RunspacePool {
             .map(|bytes| base64::engine::general_purpose::STANDARD.encode(&bytes[..]))
             .collect::<Vec<_>>();
 
-        let request = self.shell.create_pipeline_request(
+        let xml = self.shell.create_pipeline_request(
             &self.connection,
+            &mut self.scratch,
             handle.id(),
             arguments,
             None,
             None,
-        );
+        )?;
 
-        Ok(request.into().to_xml_string()?)
+        Ok(xml.to_owned())
     }
 
     /// Send a runspace pool host response to the server
@@ -434,25 +532,23 @@ impl RunspacePool {
 
         // Create WS-Man Send request (send data to stdin)
         tracing::trace!(stage = "wsman_send_request");
-        let request = self.shell.send_data_request(
-            &self.connection,
-            None, // No command ID for runspace pool
-            &arguments,
-        )?;
-
-        tracing::trace!(stage = "wsman_send_request_built");
-        let element: ironposh_xml::builder::Element<'_> = request.into();
-        tracing::trace!(stage = "serialize_xml");
-        let xml = element.to_xml_string().map_err(|e| {
-            tracing::error!(
-                error = %e,
-                stage = "serialize_xml",
-                "failed to serialize XML"
-            );
-            e
-        })?;
+        let xml = self
+            .shell
+            .send_data_request(
+                &self.connection,
+                &mut self.scratch,
+                None, // No command ID for runspace pool
+                &arguments,
+            )
+            .inspect_err(|e| {
+                tracing::error!(
+                    error = %e,
+                    stage = "serialize_xml",
+                    "failed to serialize XML"
+                );
+            })?;
         tracing::trace!(xml_len = xml.len(), stage = "done");
-        Ok(xml)
+        Ok(xml.to_owned())
     }
 
     pub(super) fn ensure_key_exchange_state(
@@ -501,11 +597,11 @@ mod tests {
     use super::*;
     use crate::runspace_pool::RunspacePoolCreator;
     use ironposh_psrp::{HostDefaultData, Size};
-    use ironposh_winrm::ws_management::SelectorSetValue;
+    use ironposh_winrm::ws_management::WsAction;
 
     const SHELL_ID: &str = "2D6534D0-6B12-40E3-B773-CBA26459CFA8";
 
-    fn test_pool(state: RunspacePoolState) -> RunspacePool {
+    fn test_host_info() -> HostInfo {
         let size = Size {
             width: 80,
             height: 25,
@@ -515,26 +611,36 @@ mod tests {
             .window_size(size.clone())
             .max_window_size(size.clone())
             .max_physical_window_size(size)
-            .build();
-        let host_info = HostInfo::builder()
+            .build()
+            .expect("valid host data");
+        HostInfo::builder()
             .host_default_data(host_data)
             .use_runspace_host(true)
-            .build();
+            .build()
+    }
 
-        let connection = Arc::new(
+    fn test_connection() -> Arc<WsMan> {
+        Arc::new(
             WsMan::builder()
                 .to("http://127.0.0.1:5985/wsman".to_string())
                 .build(),
-        );
+        )
+    }
+
+    fn test_pool(state: RunspacePoolState) -> RunspacePool {
+        let connection = test_connection();
 
         let mut pool = RunspacePoolCreator::builder()
-            .host_info(host_info)
+            .host_info(test_host_info())
             .build()
             .into_runspace_pool(connection);
 
         pool.shell = WinRunspace::builder()
             .id(pool.id)
-            .selector_set(SelectorSetValue::new().add_selector("ShellId", SHELL_ID))
+            .reference(ShellReference::from_shell_id(
+                "http://schemas.microsoft.com/powershell/Microsoft.PowerShell",
+                SHELL_ID,
+            ))
             .build();
         pool.state = state;
         pool
@@ -650,6 +756,41 @@ mod tests {
         assert_eq!(pool.state, RunspacePoolState::Disconnecting);
     }
 
+    #[test]
+    fn create_response_reference_is_reused_verbatim() {
+        let pool = test_pool(RunspacePoolState::Opened);
+        let create_response = response_envelope(
+            "CreateResponse",
+            &format!(
+                r#"<x:ResourceCreated xmlns:x="http://schemas.xmlsoap.org/ws/2004/09/transfer"><a:Address>http://127.0.0.1:5985/wsman</a:Address><a:ReferenceParameters><w:ResourceURI>http://schemas.microsoft.com/powershell/Custom</w:ResourceURI><w:SelectorSet><w:Selector Name="ShellId">{SHELL_ID}</w:Selector><w:Selector Name="Tenant">blue</w:Selector></w:SelectorSet></a:ReferenceParameters></x:ResourceCreated><rsp:Shell><rsp:ShellId>{SHELL_ID}</rsp:ShellId></rsp:Shell>"#
+            ),
+        )
+        // CreateResponse is a WS-Transfer action, not a shell one.
+        .replace(
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/CreateResponse",
+            WsAction::CreateResponse.as_str(),
+        );
+        let mut pool = crate::runspace_pool::ExpectShellCreated {
+            runspace_pool: pool,
+        }
+        .accept(&create_response)
+        .expect("accept create response");
+
+        let reference = pool.shell_reference().expect("shell reference");
+        assert_eq!(reference.shell_id, SHELL_ID);
+        assert_eq!(
+            reference.resource_uri,
+            "http://schemas.microsoft.com/powershell/Custom"
+        );
+
+        let xml = pool.fire_disconnect().expect("fire_disconnect");
+        assert!(
+            xml.contains("http://schemas.microsoft.com/powershell/Custom")
+                && xml.contains(">blue<"),
+            "disconnect must carry the server's resource URI and selectors, got: {xml}"
+        );
+    }
+
     #[test]
     fn disconnect_then_reconnect_roundtrip() {
         let mut pool = test_pool(RunspacePoolState::Opened);
@@ -709,12 +850,34 @@ mod tests {
         let result =
             pool.accept_disconnect_response(&empty_body_response_envelope("ReceiveResponse"));
         assert!(
-            matches!(result, Err(PwshCoreError::InvalidResponse(_))),
+            matches!(result, Err(PwshCoreError::InvalidResponse { .. })),
             "unrelated traffic must stay rejected so the tolerance logic can ignore it, got: {result:?}"
         );
         assert_eq!(pool.state, RunspacePoolState::Disconnecting);
     }
 
+    #[test]
+    fn body_that_disagrees_with_action_is_rejected() {
+        let mut pool = test_pool(RunspacePoolState::Opened);
+        let xml = response_envelope(
+            "CommandResponse",
+            "<rsp:ReceiveResponse><rsp:Stream Name=\"stdout\"></rsp:Stream></rsp:ReceiveResponse>",
+        );
+
+        let result = pool.accept_response(&xml);
+        match result {
+            Err(PwshCoreError::InvalidResponse { phase, message }) => {
+                assert_eq!(phase, crate::Phase::Receive);
+                assert!(
+                    message.contains(WsAction::ReceiveResponse.as_str())
+                        && message.contains(WsAction::CommandResponse.as_str()),
+                    "error must name both actions, got: {message}"
+                );
+            }
+            other => panic!("expected InvalidResponse, got: {other:?}"),
+        }
+    }
+
     #[test]
     fn abort_reconnect_reverts_connecting_to_disconnected() {
         let mut pool = test_pool(RunspacePoolState::Disconnected);
@@ -781,4 +944,124 @@ mod tests {
             "a fault unrelated to a stopping pipeline must still be fatal, got: {result:?}"
         );
     }
+
+    #[test]
+    fn shell_not_found_fault_is_fatal_even_with_pipelines() {
+        let mut pool = test_pool(RunspacePoolState::Opened);
+        pool.pipelines.insert(uuid::Uuid::new_v4(), Pipeline::new());
+        let envelope = FAULT_ENVELOPE
+            .replace("w:SchemaValidationError", "w:InvalidSelectors")
+            .replace(
+                "</s:Reason>",
+                r#"</s:Reason><s:Detail><f:WSManFault xmlns:f="http://schemas.microsoft.com/wbem/wsman/1/wsmanfault" Code="2150858843" Machine="host"/></s:Detail>"#,
+            );
+
+        let result = pool.accept_response(&envelope);
+        assert!(
+            matches!(result, Err(PwshCoreError::ShellNotFound { .. })),
+            "an expired shell must not be mistaken for a cancel race, got: {result:?}"
+        );
+    }
+
+    #[test]
+    fn open_carries_shell_creation_options() {
+        let options = crate::runspace_pool::ShellOptions::builder()
+            .idle_time_out(600.0)
+            .lifetime(3600.0)
+            .working_directory(r"C:\Work")
+            .environment(
+                [("ZED", "last"), ("ALPHA", "first")]
+                    .into_iter()
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect(),
+            )
+            .build();
+        let pool = RunspacePoolCreator::builder()
+            .host_info(test_host_info())
+            .shell_options(options)
+            .build()
+            .into_runspace_pool(test_connection());
+
+        let (xml, _) = pool.open().expect("open");
+
+        let environment = xml.find("<rsp:Environment>").expect("Environment");
+        let alpha = xml
+            .find(r#"<rsp:Variable Name="ALPHA">first</rsp:Variable>"#)
+            .expect("ALPHA variable");
+        let zed = xml
+            .find(r#"<rsp:Variable Name="ZED">last</rsp:Variable>"#)
+            .expect("ZED variable");
+        let working_directory = xml
+            .find(r"<rsp:WorkingDirectory>C:\Work</rsp:WorkingDirectory>")
+            .expect("WorkingDirectory");
+        let lifetime = xml
+            .find("<rsp:Lifetime>PT3600.000S</rsp:Lifetime>")
+            .expect("Lifetime");
+        let idle_time_out = xml
+            .find("<rsp:IdleTimeOut>PT600.000S</rsp:IdleTimeOut>")
+            .expect("IdleTimeOut");
+        let input_streams = xml.find("<rsp:InputStreams>").expect("InputStreams");
+
+        // MS-WSMV Shell schema order; variables sorted by name.
+        assert!(
+            environment < alpha
+                && alpha < zed
+                && zed < working_directory
+                && working_directory < lifetime
+                && lifetime < idle_time_out
+                && idle_time_out < input_streams,
+            "unexpected rsp:Shell layout: {xml}"
+        );
+    }
+
+    #[test]
+    fn open_omits_unset_shell_creation_options() {
+        let pool = RunspacePoolCreator::builder()
+            .host_info(test_host_info())
+            .build()
+            .into_runspace_pool(test_connection());
+
+        let (xml, _) = pool.open().expect("open");
+
+        for element in ["Environment", "WorkingDirectory", "Lifetime", "IdleTimeOut"] {
+            assert!(
+                !xml.contains(&format!("<rsp:{element}")),
+                "{element} must not be sent by default: {xml}"
+            );
+        }
+        assert!(xml.contains("<rsp:InputStreams>stdin pr</rsp:InputStreams>"));
+        assert!(xml.contains("<rsp:OutputStreams>stdout</rsp:OutputStreams>"));
+    }
+
+    fn receive_response_body(sequence_id: u64) -> String {
+        const COMMAND_ID: &str = "9A1F3B5C-2D4E-4F60-8A7B-1C2D3E4F5A6B";
+        format!(
+            r#"<rsp:ReceiveResponse SequenceID="{sequence_id}"><rsp:Stream Name="stdout" CommandId="{COMMAND_ID}" End="true"></rsp:Stream><rsp:CommandState CommandId="{COMMAND_ID}" State="http://schemas.microsoft.com/wbem/wsman/1/windows/shell/CommandState/Running"/></rsp:ReceiveResponse>"#
+        )
+    }
+
+    #[test]
+    fn redelivered_receive_response_is_dropped() {
+        let mut pool = test_pool(RunspacePoolState::Opened);
+        let first = response_envelope("ReceiveResponse", &receive_response_body(3));
+
+        let results = pool.accept_response(&first).expect("first delivery");
+        assert!(
+            results.iter().any(|r| matches!(
+                r,
+                AcceptResponsResult::StreamEnded { stream, .. } if stream == "stdout"
+            )),
+            "End=\"true\" must surface as StreamEnded, got: {results:?}"
+        );
+
+        let results = pool.accept_response(&first).expect("re-delivery");
+        assert!(
+            results.is_empty(),
+            "a re-delivered SequenceID must be ignored, got: {results:?}"
+        );
+
+        let next = response_envelope("ReceiveResponse", &receive_response_body(4));
+        let results = pool.accept_response(&next).expect("next chunk");
+        assert!(!results.is_empty(), "a newer SequenceID must be processed");
+    }
 }
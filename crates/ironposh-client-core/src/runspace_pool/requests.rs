@@ -10,6 +10,7 @@
 //! signature-identical to their previous definitions in `pool.rs`.
 
 use base64::Engine;
+use ironposh_winrm::rsp::signal::SignalCode;
 use rsa::traits::PublicKeyParts;
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -25,16 +26,18 @@ use super::pool::{DesiredStream, RunspacePool};
 impl RunspacePool {
     // We should accept the pipeline id here, but for now let's ignore it
     pub(crate) fn fire_receive(
-        &self,
+        &mut self,
         desired_streams: Vec<DesiredStream>,
         hold_secs: Option<f64>,
     ) -> Result<String, crate::PwshCoreError> {
         debug_assert!(!desired_streams.is_empty(), "At least one desired stream");
-        Ok(self
-            .shell
-            .fire_receive(&self.connection, desired_streams, hold_secs)
-            .into()
-            .to_xml_string()?)
+        let xml = self.shell.fire_receive(
+            &self.connection,
+            &mut self.scratch,
+            desired_streams,
+            hold_secs,
+        )?;
+        Ok(xml.to_owned())
     }
 
     /// Build a Disconnect request for this pool's shell (MS-WSMV 3.1.4.13).
@@ -49,11 +52,10 @@ impl RunspacePool {
 
         let xml = self
             .shell
-            .fire_disconnect(&self.connection)
-            .into()
-            .to_xml_string()?;
+            .fire_disconnect(&self.connection, &mut self.scratch)?
+            .to_owned();
 
-        self.state = RunspacePoolState::Disconnecting;
+        self.set_state(RunspacePoolState::Disconnecting);
         info!(runspace_pool_id = %self.id, "runspace pool disconnect requested");
         Ok(xml)
     }
@@ -70,16 +72,49 @@ impl RunspacePool {
 
         let xml = self
             .shell
-            .fire_reconnect(&self.connection)
-            .into()
-            .to_xml_string()?;
+            .fire_reconnect(&self.connection, &mut self.scratch)?
+            .to_owned();
 
-        self.state = RunspacePoolState::Connecting;
+        self.set_state(RunspacePoolState::Connecting);
         info!(runspace_pool_id = %self.id, "runspace pool reconnect requested");
         Ok(xml)
     }
 
+    /// Build a Delete request for this pool's shell, closing it for good.
+    /// Valid in `Opened` or `Disconnected` state; transitions the pool to `Closing`.
+    #[instrument(skip(self))]
+    pub fn fire_close(&mut self) -> Result<String, crate::PwshCoreError> {
+        if !matches!(
+            self.state,
+            RunspacePoolState::Opened | RunspacePoolState::Disconnected
+        ) {
+            return Err(crate::PwshCoreError::InvalidState(
+                "RunspacePool must be in Opened or Disconnected state to close",
+            ));
+        }
+
+        let xml = self
+            .shell
+            .fire_delete(&self.connection, &mut self.scratch)?
+            .to_owned();
+
+        self.set_state(RunspacePoolState::Closing);
+        info!(runspace_pool_id = %self.id, "runspace pool close requested");
+        Ok(xml)
+    }
+
     pub fn kill_pipeline(&mut self, handle: &PipelineHandle) -> Result<String, PwshCoreError> {
+        self.signal_pipeline(handle, SignalCode::Terminate)
+    }
+
+    /// Build a WinRS Signal request for a running pipeline. `PowerShellCtrlC` asks the
+    /// pipeline to stop gracefully, `Terminate` forces it down; both move the
+    /// pipeline to `Stopping`, so a Ctrl+C may still be escalated to Terminate.
+    pub fn signal_pipeline(
+        &mut self,
+        handle: &PipelineHandle,
+        code: SignalCode,
+    ) -> Result<String, PwshCoreError> {
         let pipeline = self
             .pipelines
             .get_mut(&handle.id())
@@ -92,19 +127,19 @@ impl RunspacePool {
 
         if pipeline.is_terminal() {
             return Err(PwshCoreError::InvalidState(
-                "Cannot kill a pipeline that is already stopped, completed, or failed",
+                "Cannot signal a pipeline that is already stopped, completed, or failed",
             ));
         }
 
         // Set pipeline state to Stopping
         pipeline.set_state(PsInvocationState::Stopping);
-        info!(pipeline_id = %handle.id(), "Killing pipeline");
+        info!(pipeline_id = %handle.id(), signal = ?code, "Signalling pipeline");
 
-        let request = self
-            .shell
-            .terminal_pipeline_signal(&self.connection, handle.id())?;
+        let xml =
+            self.shell
+                .pipeline_signal(&self.connection, &mut self.scratch, handle.id(), code)?;
 
-        Ok(request.into().to_xml_string()?)
+        Ok(xml.to_owned())
     }
 
     /// Send a pipeline host response to the server
@@ -144,19 +179,19 @@ impl RunspacePool {
 
         // Create WS-Man Send request (send data to stdin)
         tracing::trace!(stage = "wsman_send_request");
-        let request =
-            self.shell
-                .send_data_request(&self.connection, Some(command_id), &arguments)?;
-        tracing::trace!(stage = "wsman_send_request_built");
-
-        let element: ironposh_xml::builder::Element<'_> = request.into();
-        tracing::trace!(stage = "serialize_xml");
-        let xml = element.to_xml_string().map_err(|e| {
-            tracing::error!(error = %e, stage = "serialize_xml", "failed to serialize XML");
-            e
-        })?;
+        let xml = self
+            .shell
+            .send_data_request(
+                &self.connection,
+                &mut self.scratch,
+                Some(command_id),
+                &arguments,
+            )
+            .inspect_err(|e| {
+                tracing::error!(error = %e, stage = "serialize_xml", "failed to serialize XML");
+            })?;
         tracing::trace!(xml_len = xml.len(), stage = "done");
-        Ok(xml)
+        Ok(xml.to_owned())
     }
 
     pub(super) fn send_runspace_pool_message(
@@ -180,13 +215,50 @@ impl RunspacePool {
             .collect::<Vec<_>>();
 
         tracing::trace!(stage = "wsman_send_request");
-        let request = self
-            .shell
-            .send_data_request(&self.connection, None, &arguments)?;
+        let xml =
+            self.shell
+                .send_data_request(&self.connection, &mut self.scratch, None, &arguments)?;
+        Ok(xml.to_owned())
+    }
 
-        let element: ironposh_xml::builder::Element<'_> = request.into();
-        let xml = element.to_xml_string()?;
-        Ok(xml)
+    /// Send one PIPELINE_INPUT object to a pipeline created with
+    /// `takes_input`.
+    pub(crate) fn send_pipeline_input(
+        &mut self,
+        handle: &PipelineHandle,
+        value: ironposh_psrp::PsValue,
+    ) -> Result<String, PwshCoreError> {
+        self.send_pipeline_message(handle, &ironposh_psrp::PipelineInput::new(value))
+    }
+
+    /// Close a pipeline's input with END_OF_PIPELINE_INPUT.
+    pub(crate) fn end_pipeline_input(
+        &mut self,
+        handle: &PipelineHandle,
+    ) -> Result<String, PwshCoreError> {
+        self.send_pipeline_message(handle, &ironposh_psrp::EndOfPipelineInput)
+    }
+
+    fn send_pipeline_message(
+        &mut self,
+        handle: &PipelineHandle,
+        message: &dyn ironposh_psrp::PsObjectWithType,
+    ) -> Result<String, PwshCoreError> {
+        let fragmented = self
+            .fragmenter
+            .fragment(message, self.id, Some(handle.id()), None)?;
+        let arguments = fragmented
+            .into_iter()
+            .map(|bytes| base64::engine::general_purpose::STANDARD.encode(&bytes[..]))
+            .collect::<Vec<_>>();
+
+        let xml = self.shell.send_data_request(
+            &self.connection,
+            &mut self.scratch,
+            Some(handle.id()),
+            &arguments,
+        )?;
+        Ok(xml.to_owned())
     }
 
     pub(super) fn build_public_key_blob_base64(&mut self) -> Result<String, PwshCoreError> {
@@ -240,6 +312,7 @@ impl RunspacePool {
         &mut self,
         uuid: Uuid,
         spec: PipelineSpec,
+        takes_input: bool,
     ) -> Result<String, PwshCoreError> {
         // 1) Create the pipeline
         let handle = self.init_pipeline(uuid)?;
@@ -248,6 +321,12 @@ impl RunspacePool {
         for cmd in spec.commands {
             self.add_command(&handle, cmd)?;
         }
+        let pipeline = self
+            .pipelines
+            .get_mut(&handle.id())
+            .ok_or(PwshCoreError::InvalidState("Pipeline handle not found"))?;
+        pipeline.add_to_history = spec.add_to_history;
+        pipeline.takes_input = takes_input;
 
         // 3) Invoke the pipeline using existing logic
         self.invoke_pipeline_request(&handle)
@@ -0,0 +1,305 @@
+//! User-facing rendering of [`PwshCoreError`]s.
+//!
+//! An error maps to a [`UserMessage`]: a stable message ID plus named
+//! parameters. A [`MessageCatalog`] supplies the (possibly localized) template
+//! for each ID, with `{name}` placeholders; [`EnglishCatalog`] is the built-in
+//! fallback. Frontends that localize ship their own catalog, or forward the ID
+//! and parameters to a UI that does (the web client).
+
+use std::error::Error;
+
+use crate::PwshCoreError;
+
+/// A user-facing message, not yet rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserMessage {
+    /// Catalog key, e.g. `auth.rejected`.
+    pub id: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+/// Message templates keyed by [`UserMessage::id`].
+pub trait MessageCatalog {
+    /// The template for `id`, or `None` to fall back to [`EnglishCatalog`].
+    fn template(&self, id: &str) -> Option<&str>;
+}
+
+/// The built-in English templates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnglishCatalog;
+
+impl EnglishCatalog {
+    const TEMPLATES: &'static [(&'static str, &'static str)] = &[
+        (
+            "auth.rejected",
+            "The server rejected your credentials (HTTP {status}).",
+        ),
+        (
+            "auth.sspi",
+            "Authentication failed: {description} ({code}).",
+        ),
+        ("auth.failed", "Authentication failed: {detail}."),
+        (
+            "http.status",
+            "The server returned HTTP {status} during {phase}.",
+        ),
+        (
+            "wsman.fault",
+            "The server reported an error during {phase}: {reason}",
+        ),
+        (
+            "wsman.quota",
+            "The server refused more work during {phase}: its {quota} limit{limit} is reached. Try again once other sessions or commands finish.",
+        ),
+        (
+            "response.invalid",
+            "The server sent an unexpected response during {phase}.",
+        ),
+        (
+            "protocol.error",
+            "The connection failed because of a protocol error: {detail}",
+        ),
+        (
+            "state.invalid",
+            "The operation is not possible right now: {detail}",
+        ),
+        (
+            "config.invalid",
+            "The connection settings are invalid: {detail}",
+        ),
+        (
+            "host.failed",
+            "The console could not handle a request from the server: {detail}",
+        ),
+        (
+            "transport.failed",
+            "The network connection failed: {detail}",
+        ),
+        (
+            "session.broken",
+            "The server closed the session: {reason} Reconnect to continue.",
+        ),
+        (
+            "session.expired",
+            "The session expired on the server: {reason} Reconnect to continue.",
+        ),
+        ("internal.error", "An internal error occurred: {detail}"),
+    ];
+
+    fn lookup(id: &str) -> Option<&'static str> {
+        Self::TEMPLATES
+            .iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, template)| *template)
+    }
+}
+
+impl MessageCatalog for EnglishCatalog {
+    fn template(&self, id: &str) -> Option<&str> {
+        Self::lookup(id)
+    }
+}
+
+impl UserMessage {
+    fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            params: Vec::new(),
+        }
+    }
+
+    fn with(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    /// The message for the first [`PwshCoreError`] in `err`'s source chain, so
+    /// errors wrapped by `anyhow` context or other layers are still found.
+    pub fn find(err: &(dyn Error + 'static)) -> Option<Self> {
+        std::iter::successors(Some(err), |e| e.source())
+            .find_map(|e| e.downcast_ref::<PwshCoreError>())
+            .map(Self::from)
+    }
+
+    /// Fill the template for this message's ID. Unknown placeholders are left
+    /// as-is; an ID missing from `catalog` falls back to [`EnglishCatalog`].
+    pub fn render(&self, catalog: &impl MessageCatalog) -> String {
+        let template = catalog
+            .template(self.id)
+            .or_else(|| EnglishCatalog::lookup(self.id))
+            .unwrap_or(self.id);
+        self.params
+            .iter()
+            .fold(template.to_owned(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+impl From<&PwshCoreError> for UserMessage {
+    fn from(err: &PwshCoreError) -> Self {
+        match err {
+            PwshCoreError::HttpStatus { status, .. } if matches!(status, 401 | 403) => {
+                Self::new("auth.rejected").with("status", status)
+            }
+            PwshCoreError::HttpStatus { phase, status, .. } => Self::new("http.status")
+                .with("status", status)
+                .with("phase", phase),
+            PwshCoreError::SspiError(e) => Self::new("auth.sspi")
+                .with("description", &e.description)
+                .with("code", format!("{:#010X}", e.error_type as u32)),
+            PwshCoreError::Auth(detail) | PwshCoreError::UsernameError(detail) => {
+                Self::new("auth.failed").with("detail", detail)
+            }
+            PwshCoreError::SoapFault { phase, reason, .. } => Self::new("wsman.fault")
+                .with("phase", phase)
+                .with("reason", reason),
+            PwshCoreError::QuotaExceeded {
+                phase,
+                which,
+                limit,
+                ..
+            } => Self::new("wsman.quota")
+                .with("phase", phase)
+                .with("quota", which)
+                .with(
+                    "limit",
+                    limit.map_or_else(String::new, |l| format!(" of {l}")),
+                ),
+            PwshCoreError::InvalidResponse { phase, .. } => {
+                Self::new("response.invalid").with("phase", phase)
+            }
+            PwshCoreError::XmlParsingError(_) => {
+                Self::new("response.invalid").with("phase", "response parsing")
+            }
+            PwshCoreError::PowerShellRemotingError(_)
+            | PwshCoreError::RelatesToMismatch { .. }
+            | PwshCoreError::MissingRelatesTo { .. } => {
+                Self::new("protocol.error").with("detail", err)
+            }
+            PwshCoreError::InvalidState(detail) => {
+                Self::new("state.invalid").with("detail", detail)
+            }
+            PwshCoreError::RunspaceError(detail) => {
+                Self::new("state.invalid").with("detail", detail)
+            }
+            PwshCoreError::InvalidServerAddress(detail) => {
+                Self::new("config.invalid").with("detail", detail)
+            }
+            PwshCoreError::HyperError(e) => Self::new("config.invalid").with("detail", e),
+            PwshCoreError::HostError(e) => Self::new("host.failed").with("detail", e),
+            PwshCoreError::IOError(e) => Self::new("transport.failed").with("detail", e),
+            PwshCoreError::RunspacePoolBroken { reason } => {
+                Self::new("session.broken").with("reason", reason)
+            }
+            PwshCoreError::ShellNotFound { reason } => {
+                Self::new("session.expired").with("reason", reason)
+            }
+            PwshCoreError::InternalError(detail) => {
+                Self::new("internal.error").with("detail", detail)
+            }
+            PwshCoreError::UnlikelyToHappen(detail) => {
+                Self::new("internal.error").with("detail", detail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Phase;
+
+    struct FrenchCatalog;
+
+    impl MessageCatalog for FrenchCatalog {
+        fn template(&self, id: &str) -> Option<&str> {
+            (id == "auth.rejected")
+                .then_some("Le serveur a refusé vos identifiants (HTTP {status}).")
+        }
+    }
+
+    #[test]
+    fn rejected_credentials_render_friendly_message() {
+        let err = PwshCoreError::HttpStatus {
+            phase: Phase::Authentication,
+            status: 401,
+            detail: "server rejected authentication",
+        };
+        let message = UserMessage::from(&err);
+        assert_eq!(message.id, "auth.rejected");
+        assert_eq!(
+            message.render(&EnglishCatalog),
+            "The server rejected your credentials (HTTP 401)."
+        );
+        assert_eq!(
+            message.render(&FrenchCatalog),
+            "Le serveur a refusé vos identifiants (HTTP 401)."
+        );
+    }
+
+    #[test]
+    fn missing_translations_fall_back_to_english() {
+        let err = PwshCoreError::invalid_response(Phase::Receive, "No ReceiveResponse");
+        assert_eq!(
+            UserMessage::from(&err).render(&FrenchCatalog),
+            "The server sent an unexpected response during receive."
+        );
+    }
+
+    #[test]
+    fn find_walks_the_source_chain() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("session failed")]
+        struct Wrapper(#[source] PwshCoreError);
+
+        let err = Wrapper(PwshCoreError::IOError(std::io::Error::other("reset")));
+        let message = UserMessage::find(&err).expect("wrapped core error");
+        assert_eq!(message.id, "transport.failed");
+        assert_eq!(
+            message.render(&EnglishCatalog),
+            "The network connection failed: reset"
+        );
+        assert!(UserMessage::find(&std::io::Error::other("unrelated")).is_none());
+    }
+
+    #[test]
+    fn every_message_id_has_an_english_template() {
+        let errors = [
+            PwshCoreError::HttpStatus {
+                phase: Phase::Command,
+                status: 500,
+                detail: "",
+            },
+            PwshCoreError::Auth(""),
+            PwshCoreError::SoapFault {
+                phase: Phase::ShellCreation,
+                code: String::new(),
+                reason: String::new(),
+            },
+            PwshCoreError::QuotaExceeded {
+                phase: Phase::Command,
+                which: crate::Quota::OperationsPerUser,
+                limit: None,
+                reason: String::new(),
+            },
+            PwshCoreError::InvalidState(""),
+            PwshCoreError::InvalidServerAddress(""),
+            PwshCoreError::HostError(crate::host::HostError::Cancelled),
+            PwshCoreError::RunspacePoolBroken {
+                reason: String::new(),
+            },
+            PwshCoreError::ShellNotFound {
+                reason: String::new(),
+            },
+            PwshCoreError::InternalError(String::new()),
+        ];
+        for err in &errors {
+            let id = UserMessage::from(err).id;
+            assert!(
+                EnglishCatalog.template(id).is_some(),
+                "no template for {id}"
+            );
+        }
+    }
+}
@@ -1,11 +1,17 @@
 //! Fixture-driven Connector handshake tests: a fake server drives `Connector::step`
 //! all the way to `Connected` with zero network.
 
-use ironposh_client_core::connector::{Connector, ConnectorStepResult};
+use std::sync::Arc;
+
+use ironposh_client_core::PwshCoreError;
+use ironposh_client_core::connector::{
+    Connector, ConnectorStepResult, config::AuthenticatorConfig,
+};
 use ironposh_psrp::{
     ApplicationPrivateData, RunspacePoolStateMessage, RunspacePoolStateValue, SessionCapability,
 };
 use ironposh_test_support::fake_server as support;
+use ironposh_winrm::ws_management::SequentialIds;
 
 /// Idle step must emit the shell Create envelope with Basic auth preformatted.
 #[test]
@@ -36,6 +42,32 @@ fn idle_step_emits_shell_create() {
     );
 }
 
+/// `AuthenticatorConfig::None` sends the plain Create envelope with no
+/// Authorization header at all.
+#[test]
+fn no_auth_omits_the_authorization_header() {
+    let mut config = support::test_config();
+    config.authentication = AuthenticatorConfig::None;
+    let mut connector = Connector::new(config);
+
+    let result = connector.step(None).expect("idle step");
+    let ConnectorStepResult::SendBack { try_send } = result else {
+        panic!("expected SendBack");
+    };
+
+    let (request, _conn) = support::expect_just_send(try_send);
+    let body = request.body.expect("create has a body");
+    let xml = body
+        .as_str()
+        .expect("plaintext body without authentication");
+
+    assert!(xml.contains("http://schemas.xmlsoap.org/ws/2004/09/transfer/Create"));
+    assert!(
+        !request.headers.iter().any(|(k, _)| k == "Authorization"),
+        "no-auth requests must not carry an Authorization header"
+    );
+}
+
 /// A JEA `configuration_name` must replace the default shell resource URI.
 #[test]
 fn configuration_name_sets_shell_resource_uri() {
@@ -62,6 +94,150 @@ fn configuration_name_sets_shell_resource_uri() {
     );
 }
 
+/// Configured application arguments must reach the server in the
+/// INIT_RUNSPACEPOOL `ApplicationArguments` dictionary.
+#[test]
+fn application_arguments_are_sent_in_init_runspace_pool() {
+    use base64::Engine;
+    use ironposh_psrp::{
+        MessageType, PsValue,
+        fragmentation::{DefragmentResult, Defragmenter},
+        ps_value::{ComplexObjectContent, Container},
+    };
+
+    let config = support::test_config()
+        .with_application_arguments([("Tenant", PsValue::from("contoso"))])
+        .with_application_arguments([("Retries", 3)]);
+    let mut connector = Connector::new(config);
+
+    let result = connector.step(None).expect("idle step");
+    let ConnectorStepResult::SendBack { try_send } = result else {
+        panic!("expected SendBack");
+    };
+    let (request, _conn) = support::expect_just_send(try_send);
+    let xml = request
+        .body
+        .expect("create has a body")
+        .as_str()
+        .expect("plaintext body in HttpInsecure mode")
+        .to_owned();
+
+    let re = regex::Regex::new(r"<creationXml[^>]*>([^<]+)</creationXml>").unwrap();
+    let payload_b64 = &re
+        .captures(&xml)
+        .expect("Create request must carry a creationXml payload")[1];
+    let payload = base64::engine::general_purpose::STANDARD
+        .decode(payload_b64)
+        .expect("creationXml must be valid base64");
+
+    let mut defragmenter = Defragmenter::new();
+    let DefragmentResult::Complete(messages) = defragmenter
+        .defragment(&payload)
+        .expect("defragment creationXml payload")
+    else {
+        panic!("creationXml payload must defragment to complete messages");
+    };
+    assert_eq!(messages[1].message_type, MessageType::InitRunspacepool);
+
+    let PsValue::Object(init) = messages[1]
+        .parse_ps_message()
+        .expect("parse InitRunspacePool payload")
+    else {
+        panic!("expected InitRunspacePool as PsValue::Object");
+    };
+    let Some(PsValue::Object(arguments)) = init.properties.get("ApplicationArguments") else {
+        panic!("ApplicationArguments must be a dictionary, got: {init:?}");
+    };
+    let ComplexObjectContent::Container(Container::Dictionary(entries)) = &arguments.content else {
+        panic!("ApplicationArguments must be a dictionary, got: {arguments:?}");
+    };
+    assert_eq!(
+        entries.get(&PsValue::from("Tenant")),
+        Some(&PsValue::from("contoso"))
+    );
+    assert_eq!(
+        entries.get(&PsValue::from("Retries")),
+        Some(&PsValue::from(3))
+    );
+}
+
+/// With an injected `SequentialIds`, two connectors emit byte-identical Create
+/// envelopes: no MessageID, OperationID, SessionID or RPID is left to chance.
+#[test]
+fn sequential_ids_make_shell_create_reproducible() {
+    let create_xml = || {
+        let mut connector = Connector::new(support::test_config())
+            .with_id_generator(Arc::new(SequentialIds::new()));
+        let result = connector.step(None).expect("idle step");
+        let ConnectorStepResult::SendBack { try_send } = result else {
+            panic!("expected SendBack");
+        };
+        let (request, _conn) = support::expect_just_send(try_send);
+        let body = request.body.expect("create has a body");
+        body.as_str()
+            .expect("plaintext body in HttpInsecure mode")
+            .to_owned()
+    };
+
+    let first = create_xml();
+    assert_eq!(first, create_xml());
+    assert!(
+        first.contains("00000000-0000-4000-8000-000000000001"),
+        "SessionID must come from the injected generator. xml={first}"
+    );
+}
+
+/// The captured CreateResponse fixture, relating to the request `relates_to`
+/// names instead of the one it was captured for.
+fn create_response(relates_to: &str) -> String {
+    include_str!("resources/resource_created.xml")
+        .replace("uuid:622d032a-f9b3-498d-b447-1359c3bb14f3", relates_to)
+}
+
+/// A response whose `RelatesTo` names another request is rejected with both ids;
+/// one echoing the request's MessageID is accepted.
+#[test]
+fn create_response_must_relate_to_the_create_request() {
+    let send_create = |connector: &mut Connector| {
+        let result = connector.step(None).expect("idle step");
+        let ConnectorStepResult::SendBack { try_send } = result else {
+            panic!("expected SendBack for Create");
+        };
+        let (_request, conn_id) = support::expect_just_send(try_send);
+        (support::relates_to(conn_id), conn_id)
+    };
+
+    let mut connector = Connector::new(support::test_config());
+    let (message_id, conn_id) = send_create(&mut connector);
+    let foreign = "uuid:87d0a667-c08e-4311-8d2d-069367f452d8";
+    let err = connector
+        .step(Some(support::xml_response(
+            conn_id,
+            create_response(foreign),
+        )))
+        .expect_err("a CreateResponse for another request must be rejected");
+    match err {
+        PwshCoreError::RelatesToMismatch {
+            message_id: sent,
+            relates_to,
+        } => {
+            assert_eq!(sent, message_id);
+            assert_eq!(relates_to, foreign);
+        }
+        other => panic!("expected RelatesToMismatch, got {other:?}"),
+    }
+
+    let mut connector = Connector::new(support::test_config());
+    let (message_id, conn_id) = send_create(&mut connector);
+    let result = connector
+        .step(Some(support::xml_response(
+            conn_id,
+            create_response(&message_id.to_uppercase()),
+        )))
+        .expect("a CreateResponse echoing the MessageID is accepted");
+    assert!(matches!(result, ConnectorStepResult::SendBack { .. }));
+}
+
 /// With a JEA `configuration_name`, post-create operations must keep targeting the
 /// JEA resource URI even when the server's CreateResponse omits the ResourceUri echo.
 #[test]
@@ -78,10 +254,11 @@ fn configuration_name_survives_create_response_without_resource_uri_echo() {
     let (_request, conn_id) = support::expect_just_send(try_send);
 
     // 2. Reply with a CreateResponse whose Shell does NOT echo a ResourceUri element.
-    let create_response = include_str!("resources/resource_created.xml");
     let strip_resource_uri =
         regex::Regex::new(r"(?s)<rsp:ResourceUri>.*?</rsp:ResourceUri>").expect("valid regex");
-    let create_response = strip_resource_uri.replace(create_response, "").into_owned();
+    let create_response = strip_resource_uri
+        .replace(&create_response(&support::relates_to(conn_id)), "")
+        .into_owned();
     assert!(
         !create_response.contains("rsp:ResourceUri"),
         "fixture must not echo a shell ResourceUri for this test"
@@ -119,7 +296,27 @@ fn configuration_name_survives_create_response_without_resource_uri_echo() {
 /// Drive the connector to `Connected` against the fake server and return the
 /// ActiveSession (handshake mechanics are asserted by `handshake_reaches_connected`).
 fn establish_active_session() -> ironposh_client_core::connector::active_session::ActiveSession {
-    let mut connector = Connector::new(support::test_config());
+    establish_state_machine().0
+}
+
+/// Like [`establish_active_session`], also returning the runspace pool id and
+/// the connection carrying the handoff Receive.
+fn establish_state_machine() -> (
+    ironposh_client_core::connector::SessionStateMachine,
+    uuid::Uuid,
+    ironposh_client_core::connector::connection_pool::ConnectionId,
+) {
+    establish_state_machine_with(support::test_config())
+}
+
+fn establish_state_machine_with(
+    config: ironposh_client_core::connector::WinRmConfig,
+) -> (
+    ironposh_client_core::connector::SessionStateMachine,
+    uuid::Uuid,
+    ironposh_client_core::connector::connection_pool::ConnectionId,
+) {
+    let mut connector = Connector::new(config);
 
     let result = connector.step(None).expect("idle step");
     let ConnectorStepResult::SendBack { try_send } = result else {
@@ -134,11 +331,10 @@ fn establish_active_session() -> ironposh_client_core::connector::active_session
         .to_owned();
     let rpid = support::extract_shell_id(&create_xml);
 
-    let create_response = include_str!("resources/resource_created.xml");
     let result = connector
         .step(Some(support::xml_response(
             conn_id,
-            create_response.to_owned(),
+            create_response(&support::relates_to(conn_id)),
         )))
         .expect("accept CreateResponse");
     let ConnectorStepResult::SendBack { try_send } = result else {
@@ -158,6 +354,7 @@ fn establish_active_session() -> ironposh_client_core::connector::active_session
         .build();
 
     let receive_response = support::receive_response_xml(
+        &support::relates_to(conn_id),
         rpid,
         &[&session_capability, &application_private_data, &pool_opened],
     );
@@ -167,7 +364,14 @@ fn establish_active_session() -> ironposh_client_core::connector::active_session
         .expect("accept ReceiveResponse");
 
     match result {
-        ConnectorStepResult::Connected { active_session, .. } => *active_session,
+        ConnectorStepResult::Connected {
+            active_session,
+            send_this_one_async_or_you_stuck,
+        } => (
+            *active_session,
+            rpid,
+            support::expect_just_send(send_this_one_async_or_you_stuck).1,
+        ),
         other @ ConnectorStepResult::SendBack { .. } => {
             panic!("expected Connected, got {}", other.name())
         }
@@ -175,8 +379,9 @@ fn establish_active_session() -> ironposh_client_core::connector::active_session
 }
 
 /// Build a minimal response envelope with the given body element (e.g.
-/// `<rsp:DisconnectResponse/>`), mirroring real WinRM response headers.
-fn shell_op_response_xml(action: &str, body_element: &str) -> String {
+/// `<rsp:DisconnectResponse/>`), mirroring real WinRM response headers and
+/// relating to the request `relates_to` names.
+fn shell_op_response_xml(relates_to: &str, action: &str, body_element: &str) -> String {
     format!(
         r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
@@ -187,8 +392,8 @@ fn shell_op_response_xml(action: &str, body_element: &str) -> String {
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/{action}</a:Action>
         <a:MessageID>uuid:6C334787-EF2C-40E4-992F-DE4599ED2505</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
-        <a:RelatesTo>uuid:87d0a667-c08e-4311-8d2d-069367f452d8</a:RelatesTo>
     </s:Header>
     <s:Body>
         {body_element}
@@ -236,70 +441,1421 @@ fn disconnect_reconnect_through_active_session() {
         "outgoing XML must carry the ShellId selector, got: {disconnect_xml}"
     );
     assert_eq!(
-        session.runspace_pool_state(),
-        RunspacePoolState::Disconnecting
+        session.runspace_pool_state(),
+        RunspacePoolState::Disconnecting
+    );
+
+    // 2. DisconnectResponse → pool is Disconnected.
+    let outputs = session
+        .accept_server_response(support::xml_response(
+            conn_id,
+            shell_op_response_xml(
+                &support::relates_to(conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
+        ))
+        .expect("accept DisconnectResponse");
+    assert_eq!(
+        session.runspace_pool_state(),
+        RunspacePoolState::Disconnected
+    );
+    assert!(
+        outputs
+            .iter()
+            .all(|o| matches!(o, ActiveSessionOutput::OperationSuccess)),
+        "DisconnectResponse must yield OperationSuccess only, got: {outputs:?}"
+    );
+
+    // 3. Reconnect operation → outgoing request carries the Reconnect action.
+    let out = session
+        .accept_client_operation(UserOperation::Reconnect)
+        .expect("accept Reconnect operation");
+    let ActiveSessionOutput::SendBack(reqs) = out else {
+        panic!("expected SendBack for Reconnect, got {out:?}");
+    };
+    let (request, conn_id) = support::expect_just_send(reqs.into_iter().next().unwrap());
+    let reconnect_xml = request
+        .body
+        .expect("reconnect has a body")
+        .as_str()
+        .expect("plaintext body")
+        .to_owned();
+    assert!(
+        reconnect_xml.contains("http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Reconnect"),
+        "outgoing XML must carry the Reconnect action, got: {reconnect_xml}"
+    );
+    assert!(
+        reconnect_xml.contains(FIXTURE_SHELL_ID),
+        "outgoing XML must carry the ShellId selector, got: {reconnect_xml}"
+    );
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Connecting);
+
+    // 4. ReconnectResponse → pool is Opened again and the receive loop resumes.
+    let outputs = session
+        .accept_server_response(support::xml_response(
+            conn_id,
+            shell_op_response_xml(
+                &support::relates_to(conn_id),
+                "ReconnectResponse",
+                "<rsp:ReconnectResponse/>",
+            ),
+        ))
+        .expect("accept ReconnectResponse");
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
+    assert!(
+        outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::PendingReceive { .. })),
+        "ReconnectResponse must schedule a Receive to resume the loop, got: {outputs:?}"
+    );
+}
+
+/// Unwrap the outputs of a `step` fed an operation or a server response.
+fn step_outputs(
+    output: ironposh_client_core::connector::SessionOutput,
+) -> Vec<ironposh_client_core::connector::ActiveSessionOutput> {
+    match output {
+        ironposh_client_core::connector::SessionOutput::Outputs(outputs) => outputs,
+        other => panic!("expected Outputs, got {other:?}"),
+    }
+}
+
+/// Unwrap the single request of a `SendBack` output.
+fn single_send(
+    outputs: Vec<ironposh_client_core::connector::ActiveSessionOutput>,
+) -> ironposh_client_core::connector::connection_pool::ConnectionId {
+    use ironposh_client_core::connector::ActiveSessionOutput;
+
+    match <[_; 1]>::try_from(outputs) {
+        Ok([ActiveSessionOutput::SendBack(reqs)]) => {
+            let [req] = <[_; 1]>::try_from(reqs).expect("exactly one request");
+            support::expect_just_send(req).1
+        }
+        other => panic!("expected a single SendBack, got {other:?}"),
+    }
+}
+
+/// Opened → Disconnecting → Disconnected through `step`, with the handoff
+/// Receive reported as outstanding, then retired, then settled.
+#[test]
+fn state_machine_disconnect_transitions_and_outstanding_requests() {
+    use ironposh_client_core::connector::active_session::{
+        OutstandingRequest, RequestKind, TransportErrorDisposition,
+    };
+    use ironposh_client_core::connector::{SessionInput, SessionOutput, UserOperation};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let (mut session, _rpid, receive_conn) = establish_state_machine();
+    session.note_receive_sent(receive_conn);
+    assert_eq!(
+        session.outstanding_requests(),
+        [OutstandingRequest {
+            conn_id: receive_conn,
+            kind: RequestKind::Receive
+        }]
+    );
+
+    let disconnect_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Disconnect))
+            .expect("step Disconnect"),
+    ));
+    assert_eq!(
+        session.runspace_pool_state(),
+        RunspacePoolState::Disconnecting
+    );
+    let outstanding = session.outstanding_requests();
+    assert_eq!(outstanding.len(), 2, "got {outstanding:?}");
+    assert!(outstanding.contains(&OutstandingRequest {
+        conn_id: receive_conn,
+        kind: RequestKind::Retired
+    }));
+    assert!(outstanding.contains(&OutstandingRequest {
+        conn_id: disconnect_conn,
+        kind: RequestKind::Disconnect
+    }));
+
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            disconnect_conn,
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
+        )))
+        .expect("step DisconnectResponse");
+    assert_eq!(
+        session.runspace_pool_state(),
+        RunspacePoolState::Disconnected
+    );
+    assert_eq!(
+        session.outstanding_requests(),
+        [OutstandingRequest {
+            conn_id: receive_conn,
+            kind: RequestKind::Retired
+        }]
+    );
+
+    let out = session
+        .step(SessionInput::TransportError(receive_conn))
+        .expect("step TransportError");
+    assert!(
+        matches!(
+            out,
+            SessionOutput::TransportError(TransportErrorDisposition::Tolerated)
+        ),
+        "the retired Receive failing must be tolerated, got {out:?}"
+    );
+    assert_eq!(session.outstanding_requests(), []);
+}
+
+/// Disconnecting --transport error on the Disconnect--> Opened.
+#[test]
+fn state_machine_transport_error_aborts_disconnect() {
+    use ironposh_client_core::connector::active_session::TransportErrorDisposition;
+    use ironposh_client_core::connector::{SessionInput, SessionOutput, UserOperation};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let disconnect_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Disconnect))
+            .expect("step Disconnect"),
+    ));
+
+    let out = session
+        .step(SessionInput::TransportError(disconnect_conn))
+        .expect("step TransportError");
+    assert!(
+        matches!(
+            out,
+            SessionOutput::TransportError(TransportErrorDisposition::DisconnectAborted)
+        ),
+        "got {out:?}"
+    );
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
+}
+
+/// Connecting --transport error on the Reconnect--> Disconnected, then
+/// Disconnected --Reconnect, ReconnectResponse--> Opened.
+#[test]
+fn state_machine_reconnect_transitions() {
+    use ironposh_client_core::connector::active_session::TransportErrorDisposition;
+    use ironposh_client_core::connector::{
+        ActiveSessionOutput, SessionInput, SessionOutput, UserOperation,
+    };
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let disconnect_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Disconnect))
+            .expect("step Disconnect"),
+    ));
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            disconnect_conn,
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
+        )))
+        .expect("step DisconnectResponse");
+
+    let reconnect_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Reconnect))
+            .expect("step Reconnect"),
+    ));
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Connecting);
+    let out = session
+        .step(SessionInput::TransportError(reconnect_conn))
+        .expect("step TransportError");
+    assert!(
+        matches!(
+            out,
+            SessionOutput::TransportError(TransportErrorDisposition::ReconnectAborted)
+        ),
+        "got {out:?}"
+    );
+    assert_eq!(
+        session.runspace_pool_state(),
+        RunspacePoolState::Disconnected
+    );
+
+    let reconnect_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Reconnect))
+            .expect("step Reconnect again"),
+    ));
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                reconnect_conn,
+                shell_op_response_xml(
+                    &support::relates_to(reconnect_conn),
+                    "ReconnectResponse",
+                    "<rsp:ReconnectResponse/>",
+                ),
+            )))
+            .expect("step ReconnectResponse"),
+    );
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
+    assert!(
+        outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::PendingReceive { .. })),
+        "got {outputs:?}"
+    );
+}
+
+/// Opened --transport error on an untracked request--> Fatal.
+#[test]
+fn state_machine_transport_error_while_opened_is_fatal() {
+    use ironposh_client_core::connector::active_session::TransportErrorDisposition;
+    use ironposh_client_core::connector::{SessionInput, SessionOutput};
+
+    let (mut session, _rpid, receive_conn) = establish_state_machine();
+    let out = session
+        .step(SessionInput::TransportError(receive_conn))
+        .expect("step TransportError");
+    assert!(
+        matches!(
+            out,
+            SessionOutput::TransportError(TransportErrorDisposition::Fatal)
+        ),
+        "got {out:?}"
+    );
+}
+
+/// A server-sent Broken RunspacePoolState fails and finishes every open
+/// pipeline with the server's error record, then makes the session terminal.
+#[test]
+fn broken_runspace_pool_fails_open_pipelines_and_ends_the_session() {
+    use ironposh_client_core::connector::active_session::UserEvent;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+    use ironposh_psrp::{ErrorRecord, PsObjectWithType};
+
+    let (mut session, rpid, receive_conn) = establish_state_machine();
+
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: pipeline_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Start-Sleep 60".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("step InvokeWithSpec"),
+    ));
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            invoke_conn,
+            support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+        )))
+        .expect("step CommandResponse");
+
+    let cause = ErrorRecord::builder()
+        .message("The WinRM service is shutting down.".to_owned())
+        .build();
+    let pool_broken = RunspacePoolStateMessage::builder()
+        .runspace_state(RunspacePoolStateValue::Broken)
+        .exception_as_error_record(Some(cause.to_ps_object()))
+        .build();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    &[&pool_broken],
+                ),
+            )))
+            .expect("step Broken RunspacePoolState"),
+    );
+
+    match outputs.as_slice() {
+        [
+            ActiveSessionOutput::UserEvent(UserEvent::ErrorRecord {
+                error_record,
+                handle,
+            }),
+            ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished { pipeline }),
+        ] => {
+            assert_eq!(error_record.message, "The WinRM service is shutting down.");
+            assert_eq!(handle.id(), pipeline_id);
+            assert_eq!(pipeline.id(), pipeline_id);
+        }
+        other => panic!("expected ErrorRecord then PipelineFinished, got {other:?}"),
+    }
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Broken);
+    assert_eq!(
+        session.broken_reason(),
+        Some("The WinRM service is shutting down.")
+    );
+
+    let err = session
+        .accept_client_operation(UserOperation::InvokeWithSpec {
+            uuid: uuid::Uuid::new_v4(),
+            spec: PipelineSpec {
+                commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
+            },
+        })
+        .expect_err("a broken session accepts no work");
+    assert!(
+        matches!(err, PwshCoreError::RunspacePoolBroken { .. }),
+        "got {err:?}"
+    );
+}
+
+/// A message of the given type whose payload is not an object.
+struct Malformed(ironposh_psrp::MessageType);
+
+impl ironposh_psrp::PsObjectWithType for Malformed {
+    fn message_type(&self) -> ironposh_psrp::MessageType {
+        self.0
+    }
+
+    fn to_ps_object(&self) -> ironposh_psrp::PsValue {
+        ironposh_psrp::PsValue::Primitive(ironposh_psrp::PsPrimitiveValue::Str("Opened".into()))
+    }
+}
+
+/// Lenient parsing reports a malformed data-stream record and still handles
+/// the rest of the response; strict parsing fails the response.
+#[test]
+fn lenient_parsing_skips_a_malformed_message() {
+    use ironposh_client_core::connector::config::ParseMode;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let malformed_record = Malformed(ironposh_psrp::MessageType::ErrorRecord);
+    let pool_broken = RunspacePoolStateMessage::builder()
+        .runspace_state(RunspacePoolStateValue::Broken)
+        .build();
+
+    let (mut session, rpid, receive_conn) = establish_state_machine();
+    let result = session.step(SessionInput::ServerResponse(support::xml_response(
+        receive_conn,
+        support::receive_response_xml(
+            &support::relates_to(receive_conn),
+            rpid,
+            &[&malformed_record, &pool_broken],
+        ),
+    )));
+    assert!(result.is_err(), "strict parsing fails the response");
+
+    let mut config = support::test_config();
+    config.parse_mode = ParseMode::Lenient;
+    let (mut session, rpid, receive_conn) = establish_state_machine_with(config);
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    &[&malformed_record, &pool_broken],
+                ),
+            )))
+            .expect("step malformed ErrorRecord"),
+    );
+
+    match outputs.as_slice() {
+        [
+            ActiveSessionOutput::ProtocolWarning {
+                stream,
+                pipeline: None,
+                error,
+            },
+        ] => {
+            assert_eq!(stream, "stdout");
+            assert!(error.to_string().contains("ErrorRecord"), "got {error}");
+        }
+        other => panic!("expected a single ProtocolWarning, got {other:?}"),
+    }
+    assert_eq!(
+        session.runspace_pool_state(),
+        RunspacePoolState::Broken,
+        "the message after the malformed one was handled"
+    );
+}
+
+/// Lenient parsing never skips a malformed state message: losing one would
+/// leave the pool or a pipeline in the wrong state.
+#[test]
+fn lenient_parsing_fails_on_malformed_state_messages() {
+    use ironposh_client_core::connector::SessionInput;
+    use ironposh_client_core::connector::config::ParseMode;
+    use ironposh_psrp::MessageType;
+
+    for (message_type, name) in [
+        (MessageType::PipelineState, "PipelineState"),
+        (MessageType::RunspacepoolState, "RunspacepoolState"),
+        (MessageType::EncryptedSessionKey, "EncryptedSessionKey"),
+    ] {
+        let mut config = support::test_config();
+        config.parse_mode = ParseMode::Lenient;
+        let (mut session, rpid, receive_conn) = establish_state_machine_with(config);
+        let err = session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    &[&Malformed(message_type)],
+                ),
+            )))
+            .expect_err("a malformed state message fails the response");
+        assert!(err.to_string().contains(name), "got {err}");
+    }
+}
+
+/// Lenient parsing only skips data that does not decode: a well-formed message
+/// the pool cannot act on still fails the response.
+#[test]
+fn lenient_parsing_does_not_skip_handler_errors() {
+    use ironposh_client_core::connector::SessionInput;
+    use ironposh_client_core::connector::config::ParseMode;
+
+    let mut config = support::test_config();
+    config.parse_mode = ParseMode::Lenient;
+    let (mut session, rpid, receive_conn) = establish_state_machine_with(config);
+
+    // PipelineState on the pool stream names no pipeline.
+    let err = session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            receive_conn,
+            support::receive_response_xml(
+                &support::relates_to(receive_conn),
+                rpid,
+                &[&ironposh_psrp::PipelineStateMessage::completed()],
+            ),
+        )))
+        .expect_err("a PipelineState without a pipeline fails the response");
+    assert!(err.to_string().contains("command_id"), "got {err}");
+}
+
+/// `WinRmConfig::parse_limits` and `defragment_limits` reach the parsers the
+/// pool runs on server responses.
+#[test]
+fn configured_limits_reject_oversized_responses() {
+    let mut config = support::test_config();
+    config.parse_limits.max_document_bytes = 64;
+    let mut connector = Connector::new(config);
+    let ConnectorStepResult::SendBack { try_send } = connector.step(None).expect("idle step")
+    else {
+        panic!("expected SendBack for Create");
+    };
+    let (_request, conn_id) = support::expect_just_send(try_send);
+    let err = connector
+        .step(Some(support::xml_response(
+            conn_id,
+            create_response(&support::relates_to(conn_id)),
+        )))
+        .expect_err("CreateResponse is over the document size limit");
+    assert!(err.to_string().contains("document size"), "got {err}");
+
+    let mut config = support::test_config();
+    config.defragment_limits.max_fragment_blob_size = 8;
+    let mut connector = Connector::new(config);
+    let ConnectorStepResult::SendBack { try_send } = connector.step(None).expect("idle step")
+    else {
+        panic!("expected SendBack for Create");
+    };
+    let (request, conn_id) = support::expect_just_send(try_send);
+    let create_xml = request
+        .body
+        .expect("create has a body")
+        .as_str()
+        .expect("plaintext body")
+        .to_owned();
+    let rpid = support::extract_shell_id(&create_xml);
+    let ConnectorStepResult::SendBack { try_send } = connector
+        .step(Some(support::xml_response(
+            conn_id,
+            create_response(&support::relates_to(conn_id)),
+        )))
+        .expect("accept CreateResponse")
+    else {
+        panic!("expected SendBack for Receive");
+    };
+    let (_request, conn_id) = support::expect_just_send(try_send);
+    let pool_opened = RunspacePoolStateMessage::builder()
+        .runspace_state(RunspacePoolStateValue::Opened)
+        .build();
+    let err = connector
+        .step(Some(support::xml_response(
+            conn_id,
+            support::receive_response_xml(&support::relates_to(conn_id), rpid, &[&pool_opened]),
+        )))
+        .expect_err("the fragment is over the blob size limit");
+    assert!(err.to_string().contains("fragment blob size"), "got {err}");
+}
+
+/// An invoked pipeline is listed until it finishes; a host call it raises is
+/// pending until the application answers it.
+#[test]
+fn state_machine_tracks_pipelines_and_host_calls() {
+    use ironposh_client_core::connector::active_session::PendingHostCall;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::host::HostCallScope;
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::powershell::PipelineHandle;
+    use ironposh_psrp::{PipelineHostCall, RemoteHostMethodId};
+
+    let (mut session, rpid, _receive_conn) = establish_state_machine();
+    assert!(session.pipelines().is_empty());
+
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: pipeline_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Read-Host".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("step InvokeWithSpec"),
+    ));
+    let pipelines: Vec<_> = session.pipelines().iter().map(PipelineHandle::id).collect();
+    assert_eq!(pipelines, [pipeline_id]);
+
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            invoke_conn,
+            support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+        )))
+        .expect("step CommandResponse");
+
+    let receive = session
+        .fire_receive(session.active_desired_streams(), None)
+        .expect("fire Receive");
+    let (_request, receive_conn) = support::expect_just_send(receive);
+    let host_call = PipelineHostCall::builder()
+        .call_id(7)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::pipeline_receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    pipeline_id,
+                    &[&host_call],
+                    false,
+                    10,
+                ),
+            )))
+            .expect("step ReceiveResponse"),
+    );
+    assert!(
+        outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::HostCall(_))),
+        "got {outputs:?}"
+    );
+    let scope = HostCallScope::Pipeline {
+        command_id: pipeline_id,
+    };
+    assert_eq!(
+        session.pending_host_calls(),
+        [PendingHostCall {
+            scope: scope.clone(),
+            call_id: 7,
+            method_name: "ReadLine",
+        }]
+    );
+
+    session
+        .step(SessionInput::Operation(UserOperation::CancelHostCall {
+            scope,
+            call_id: 7,
+            method: RemoteHostMethodId::ReadLine,
+            reason: None,
+        }))
+        .expect("step CancelHostCall");
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// A pipeline that raised a `ReadLine` host call the application has not
+/// answered yet. Returns the session, the pool id and the pipeline id.
+fn pipeline_waiting_on_host_call() -> (
+    ironposh_client_core::connector::SessionStateMachine,
+    uuid::Uuid,
+    uuid::Uuid,
+) {
+    use ironposh_client_core::connector::{SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_psrp::{PipelineHostCall, RemoteHostMethodId};
+
+    let (mut session, rpid, _receive_conn) = establish_state_machine();
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: pipeline_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Read-Host".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("step InvokeWithSpec"),
+    ));
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            invoke_conn,
+            support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+        )))
+        .expect("step CommandResponse");
+
+    let host_call = PipelineHostCall::builder()
+        .call_id(7)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+    let receive_conn = fire_pipeline_receive(&mut session);
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            receive_conn,
+            support::pipeline_receive_response_xml(
+                &support::relates_to(receive_conn),
+                rpid,
+                pipeline_id,
+                &[&host_call],
+                false,
+                10,
+            ),
+        )))
+        .expect("step ReceiveResponse");
+    assert_eq!(session.pending_host_calls().len(), 1);
+    (session, rpid, pipeline_id)
+}
+
+fn fire_pipeline_receive(
+    session: &mut ironposh_client_core::connector::SessionStateMachine,
+) -> ironposh_client_core::connector::connection_pool::ConnectionId {
+    let receive = session
+        .fire_receive(session.active_desired_streams(), None)
+        .expect("fire Receive");
+    support::expect_just_send(receive).1
+}
+
+/// The host call of a pipeline that finished can no longer be answered.
+#[test]
+fn finished_pipeline_forgets_its_host_calls() {
+    use ironposh_client_core::connector::active_session::UserEvent;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput};
+
+    let (mut session, rpid, pipeline_id) = pipeline_waiting_on_host_call();
+
+    let receive_conn = fire_pipeline_receive(&mut session);
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::pipeline_receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    pipeline_id,
+                    &[],
+                    true,
+                    20,
+                ),
+            )))
+            .expect("step ReceiveResponse with CommandState Done"),
+    );
+    assert!(
+        outputs.iter().any(|o| matches!(
+            o,
+            ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished { pipeline })
+                if pipeline.id() == pipeline_id
+        )),
+        "got {outputs:?}"
+    );
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// Killing a pipeline (how frontends stop one that timed out) forgets its
+/// host calls at once: nobody is left to answer them.
+#[test]
+fn killed_pipeline_forgets_its_host_calls() {
+    use ironposh_client_core::connector::{SessionInput, UserOperation};
+    use ironposh_client_core::powershell::PipelineHandle;
+
+    let (mut session, _rpid, pipeline_id) = pipeline_waiting_on_host_call();
+
+    single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::KillPipeline {
+                pipeline: PipelineHandle::new(pipeline_id),
+            }))
+            .expect("step KillPipeline"),
+    ));
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// A broken pool forgets every pending host call along with its pipelines.
+#[test]
+fn broken_pool_forgets_pending_host_calls() {
+    use ironposh_client_core::connector::SessionInput;
+    use ironposh_psrp::{ErrorRecord, PsObjectWithType};
+
+    let (mut session, rpid, _pipeline_id) = pipeline_waiting_on_host_call();
+
+    let pool_broken = RunspacePoolStateMessage::builder()
+        .runspace_state(RunspacePoolStateValue::Broken)
+        .exception_as_error_record(Some(
+            ErrorRecord::builder()
+                .message("gone".to_owned())
+                .build()
+                .to_ps_object(),
+        ))
+        .build();
+    let receive_conn = fire_pipeline_receive(&mut session);
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            receive_conn,
+            support::receive_response_xml(
+                &support::relates_to(receive_conn),
+                rpid,
+                &[&pool_broken],
+            ),
+        )))
+        .expect("step Broken RunspacePoolState");
+    assert_eq!(session.broken_reason(), Some("gone"));
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// While Closing, new operations are queued; once the DeleteResponse closes
+/// the pool they are released and finish at once, and the pending host calls
+/// are forgotten.
+#[test]
+fn closing_queues_operations_and_closed_releases_them() {
+    use ironposh_client_core::connector::active_session::UserEvent;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let (mut session, _rpid, _pipeline_id) = pipeline_waiting_on_host_call();
+
+    let close_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::Close))
+            .expect("step Close"),
+    ));
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Closing);
+
+    let queued_id = uuid::Uuid::new_v4();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: queued_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("step InvokeWithSpec while Closing"),
+    );
+    assert!(
+        matches!(
+            outputs.as_slice(),
+            [ActiveSessionOutput::OperationQueued {
+                operation_type: "InvokeWithSpec"
+            }]
+        ),
+        "got {outputs:?}"
+    );
+    assert_eq!(session.queued_operation_count(), 1);
+
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                close_conn,
+                support::delete_response_xml(&support::relates_to(close_conn)),
+            )))
+            .expect("step DeleteResponse"),
+    );
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Closed);
+    assert_eq!(session.queued_operation_count(), 0);
+    assert!(
+        outputs.iter().any(|o| matches!(
+            o,
+            ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished { pipeline })
+                if pipeline.id() == queued_id
+        )),
+        "the queued invoke must finish once the pool is closed, got {outputs:?}"
+    );
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// A headless session answers host calls itself instead of handing them to
+/// the application.
+#[test]
+fn headless_session_rejects_host_calls() {
+    use ironposh_client_core::connector::config::HostMode;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_psrp::{PipelineHostCall, RemoteHostMethodId};
+
+    let mut config = support::test_config();
+    config.host_mode = HostMode::Headless;
+    let (mut session, rpid, _receive_conn) = establish_state_machine_with(config);
+
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: pipeline_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Read-Host".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("step InvokeWithSpec"),
+    ));
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            invoke_conn,
+            support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+        )))
+        .expect("step CommandResponse");
+
+    let receive = session
+        .fire_receive(session.active_desired_streams(), None)
+        .expect("fire Receive");
+    let (_request, receive_conn) = support::expect_just_send(receive);
+    let host_call = PipelineHostCall::builder()
+        .call_id(7)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                receive_conn,
+                support::pipeline_receive_response_xml(
+                    &support::relates_to(receive_conn),
+                    rpid,
+                    pipeline_id,
+                    &[&host_call],
+                    false,
+                    10,
+                ),
+            )))
+            .expect("a host call on a headless session is not fatal"),
+    );
+    assert!(
+        !outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::HostCall(_))),
+        "got {outputs:?}"
+    );
+    assert!(
+        outputs.iter().any(|o| matches!(
+            o,
+            ActiveSessionOutput::SendBack(_) | ActiveSessionOutput::SendAndThenReceive { .. }
+        )),
+        "the host call must be answered, got {outputs:?}"
+    );
+    assert_eq!(session.pending_host_calls(), []);
+}
+
+/// A multi-line script file becomes one local-scope `IsScript` command and a
+/// single tracked pipeline.
+#[test]
+fn run_script_file_invokes_one_pipeline() {
+    use ironposh_client_core::connector::{SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::PipelineSpec;
+    use ironposh_client_core::powershell::PipelineHandle;
+
+    let script =
+        "\u{feff}function Get-Greeting {\r\n    @'\r\nhello\r\n'@\r\n}\r\nGet-Greeting\r\n";
+    let spec = PipelineSpec::script_file(script);
+    let [command] = spec.commands.as_slice() else {
+        panic!("expected a single command, got {spec:?}");
+    };
+    assert!(command.is_script);
+    assert_eq!(command.use_local_scope, Some(true));
+    assert_eq!(
+        command.command_text,
+        "function Get-Greeting {\n    @'\nhello\n'@\n}\nGet-Greeting\n"
+    );
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let pipeline_id = uuid::Uuid::new_v4();
+    single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::RunScriptFile {
+                uuid: pipeline_id,
+                script: script.to_owned(),
+            }))
+            .expect("step RunScriptFile"),
+    ));
+    let pipelines: Vec<_> = session.pipelines().iter().map(PipelineHandle::id).collect();
+    assert_eq!(pipelines, [pipeline_id]);
+}
+
+/// `PipelineSpec::add_to_history` ends up as `AddToHistory` in the
+/// CreatePipeline message.
+#[test]
+fn add_to_history_is_sent_in_create_pipeline() {
+    use base64::Engine;
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_psrp::{
+        MessageType, PsValue,
+        fragmentation::{DefragmentResult, Defragmenter},
+    };
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: uuid::Uuid::new_v4(),
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                    add_to_history: true,
+                },
+            }))
+            .expect("step InvokeWithSpec"),
+    );
+    let Ok([ActiveSessionOutput::SendBack(reqs)]) = <[_; 1]>::try_from(outputs) else {
+        panic!("expected a single SendBack");
+    };
+    let [req] = <[_; 1]>::try_from(reqs).expect("exactly one request");
+    let (request, _conn) = support::expect_just_send(req);
+    let xml = request
+        .body
+        .expect("command has a body")
+        .as_str()
+        .expect("plaintext body in HttpInsecure mode")
+        .to_owned();
+
+    let re = regex::Regex::new(r"<[\w:]*Arguments>([^<]+)</[\w:]*Arguments>").unwrap();
+    let payload = base64::engine::general_purpose::STANDARD
+        .decode(
+            &re.captures(&xml)
+                .expect("Command request must carry Arguments")[1],
+        )
+        .expect("Arguments must be valid base64");
+
+    let mut defragmenter = Defragmenter::new();
+    let DefragmentResult::Complete(messages) = defragmenter
+        .defragment(&payload)
+        .expect("defragment Arguments payload")
+    else {
+        panic!("Arguments payload must defragment to complete messages");
+    };
+    assert_eq!(messages[0].message_type, MessageType::CreatePipeline);
+
+    let PsValue::Object(create) = messages[0]
+        .parse_ps_message()
+        .expect("parse CreatePipeline payload")
+    else {
+        panic!("expected CreatePipeline as PsValue::Object");
+    };
+    assert_eq!(
+        create.properties.get("AddToHistory"),
+        Some(&PsValue::from(true))
+    );
+}
+
+/// `PipelineSpec::set_environment` passes names and values as a parameter,
+/// never as script text.
+#[test]
+fn set_environment_passes_variables_as_a_parameter() {
+    use ironposh_client_core::pipeline::{Parameter, PipelineSpec};
+    use ironposh_psrp::ps_value::{FromPsValue, PsDict};
+
+    let value = "x'; Remove-Item C:\\ -Recurse; '$(whoami)";
+    let spec = PipelineSpec::set_environment([("DEPLOY_TAG", value)]);
+    let [command] = spec.commands.as_slice() else {
+        panic!("expected one command");
+    };
+    assert!(!command.command_text.contains("DEPLOY_TAG"));
+    assert!(!command.command_text.contains(value));
+
+    let [
+        Parameter::Named {
+            name,
+            value: variables,
+        },
+    ] = command.parameters.as_slice()
+    else {
+        panic!("expected one named parameter, got {:?}", command.parameters);
+    };
+    assert_eq!(name, "Variables");
+    let variables = PsDict::from_ps_value(variables).expect("a hashtable");
+    assert_eq!(variables.get_str("DEPLOY_TAG"), Some(value));
+}
+
+/// Decode the single PSRP message a Send request carries on stdin.
+fn sent_message(
+    request: &ironposh_client_core::connector::http::HttpRequest,
+) -> (ironposh_psrp::MessageType, ironposh_psrp::PsValue) {
+    use base64::Engine;
+    use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter};
+
+    let xml = request
+        .body
+        .as_ref()
+        .expect("Send has a body")
+        .as_str()
+        .expect("plaintext body in HttpInsecure mode");
+    let re = regex::Regex::new(r"<[\w:]*Stream[^>]*>([^<]+)</[\w:]*Stream>").unwrap();
+    let payload = base64::engine::general_purpose::STANDARD
+        .decode(&re.captures(xml).expect("Send request must carry a Stream")[1])
+        .expect("Stream must be valid base64");
+    let DefragmentResult::Complete(messages) = Defragmenter::new()
+        .defragment(&payload)
+        .expect("defragment Stream payload")
+    else {
+        panic!("Stream payload must defragment to complete messages");
+    };
+    let [message] = <[_; 1]>::try_from(messages).expect("exactly one message");
+    let value = message.parse_ps_message().expect("parse message payload");
+    (message.message_type, value)
+}
+
+/// `InvokeWithInput` creates the pipeline expecting input, then sends one
+/// PIPELINE_INPUT per acknowledged Send and closes with END_OF_PIPELINE_INPUT.
+#[test]
+fn invoke_with_input_streams_input_one_send_at_a_time() {
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineSpec, script_input_chunks};
+    use ironposh_psrp::{MessageType, PsValue};
+
+    let script = "'a'+'b'";
+    let input = script_input_chunks(script, 3);
+    assert_eq!(input.len(), 3);
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithInput {
+                uuid: pipeline_id,
+                spec: PipelineSpec::script_from_input(),
+                input,
+            }))
+            .expect("step InvokeWithInput"),
+    ));
+
+    let mut outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                invoke_conn,
+                support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+            )))
+            .expect("step CommandResponse"),
+    );
+    let mut sent = Vec::new();
+    loop {
+        let sends: Vec<_> = outputs
+            .into_iter()
+            .filter_map(|output| match output {
+                ActiveSessionOutput::SendBack(reqs) => Some(reqs),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let Ok([send]) = <[_; 1]>::try_from(sends) else {
+            break;
+        };
+        let (request, conn) = support::expect_just_send(send);
+        sent.push(sent_message(&request));
+        outputs = step_outputs(
+            session
+                .step(SessionInput::ServerResponse(support::xml_response(
+                    conn,
+                    shell_op_response_xml(
+                        &support::relates_to(conn),
+                        "SendResponse",
+                        "<rsp:SendResponse/>",
+                    ),
+                )))
+                .expect("step SendResponse"),
+        );
+    }
+
+    let chunk = |s: &str| (MessageType::PipelineInput, PsValue::from(s.to_owned()));
+    assert_eq!(
+        sent,
+        [
+            chunk("'a'"),
+            chunk("+'b"),
+            chunk("'"),
+            (
+                MessageType::EndOfPipelineInput,
+                PsValue::Primitive(ironposh_psrp::PsPrimitiveValue::Nil)
+            ),
+        ]
     );
+}
 
-    // 2. DisconnectResponse → pool is Disconnected.
-    let outputs = session
-        .accept_server_response(support::xml_response(
+/// With the receive lane, user-driven requests never take the connection
+/// reserved for the long-poll, even while it sits idle, and the next Receive
+/// goes back to it.
+#[test]
+fn receive_lane_keeps_commands_off_the_receive_connection() {
+    use ironposh_client_core::connector::{SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+
+    let invoke = || {
+        SessionInput::Operation(UserOperation::InvokeWithSpec {
+            uuid: uuid::Uuid::new_v4(),
+            spec: PipelineSpec {
+                commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
+            },
+        })
+    };
+
+    let (mut session, rpid, receive_conn) = establish_state_machine();
+    session.dedicate_receive_connection(receive_conn);
+
+    let first_command = single_send(step_outputs(session.step(invoke()).expect("first invoke")));
+    assert_ne!(first_command, receive_conn);
+
+    // The parked Receive returns empty: its connection is idle again.
+    session
+        .step(SessionInput::ServerResponse(support::xml_response(
+            receive_conn,
+            support::receive_response_xml(&support::relates_to(receive_conn), rpid, &[]),
+        )))
+        .expect("accept empty ReceiveResponse");
+
+    let second_command = single_send(step_outputs(session.step(invoke()).expect("second invoke")));
+    assert_ne!(second_command, receive_conn);
+
+    let receive = session.fire_active_receive().expect("fire Receive");
+    assert_eq!(receive.get_connection_id(), receive_conn);
+}
+
+/// A gateway status on the long-poll Receive resends it; on a command it is
+/// reported, the command's pipeline is failed and finished, and the session
+/// goes on; expired credentials on the Receive end it.
+#[test]
+fn interrupted_requests_are_resent_or_reported() {
+    use std::time::Duration;
+
+    use ironposh_client_core::connector::active_session::UserEvent;
+    use ironposh_client_core::connector::http::{
+        HttpBody, HttpResponse, HttpResponseTargeted, Interruption,
+    };
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+
+    let status = |conn_id, status_code, headers: &[(&str, &str)]| {
+        SessionInput::ServerResponse(HttpResponseTargeted::new(
+            HttpResponse {
+                status_code,
+                headers: headers
+                    .iter()
+                    .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                    .collect(),
+                body: HttpBody::Text("<html>unavailable</html>".to_owned()),
+                peer_cert_der: None,
+            },
             conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            None,
         ))
-        .expect("accept DisconnectResponse");
+    };
+
+    let (mut session, _rpid, receive_conn) = establish_state_machine();
+    session.note_receive_sent(receive_conn);
+
+    let outputs = step_outputs(
+        session
+            .step(status(receive_conn, 503, &[("Retry-After", "2")]))
+            .expect("503 on the Receive is not fatal"),
+    );
+    let [
+        ActiveSessionOutput::Interrupted {
+            interruption,
+            retry: Some((delay, resend)),
+        },
+    ] = outputs.as_slice()
+    else {
+        panic!("expected a resent Receive, got {outputs:?}");
+    };
     assert_eq!(
-        session.runspace_pool_state(),
-        RunspacePoolState::Disconnected
+        *interruption,
+        Interruption::ServiceUnavailable {
+            retry_after: Some(Duration::from_secs(2))
+        }
     );
-    assert!(
-        outputs
-            .iter()
-            .all(|o| matches!(o, ActiveSessionOutput::OperationSuccess)),
-        "DisconnectResponse must yield OperationSuccess only, got: {outputs:?}"
+    assert_eq!(*delay, Duration::from_secs(2));
+    let resend_conn = resend.get_connection_id();
+    assert_ne!(resend_conn, receive_conn);
+
+    let pipeline_id = uuid::Uuid::new_v4();
+    let command = single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: pipeline_id,
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("invoke"),
+    ));
+    let outputs = step_outputs(
+        session
+            .step(status(command, 504, &[]))
+            .expect("504 on a command is not fatal"),
     );
+    // The server never created the pipeline: it is failed and its stream ends.
+    match outputs.as_slice() {
+        [
+            ActiveSessionOutput::Interrupted {
+                interruption: Interruption::GatewayTimeout { status: 504 },
+                retry: None,
+            },
+            ActiveSessionOutput::UserEvent(UserEvent::ErrorRecord {
+                error_record,
+                handle,
+            }),
+            ActiveSessionOutput::UserEvent(UserEvent::PipelineFinished { pipeline }),
+        ] => {
+            assert_eq!(handle.id(), pipeline_id);
+            assert_eq!(pipeline.id(), pipeline_id);
+            assert!(error_record.message.contains("504"), "got {error_record:?}");
+        }
+        other => panic!("expected a reported interruption ending the pipeline, got {other:?}"),
+    }
+    assert!(session.pipelines().is_empty());
 
-    // 3. Reconnect operation → outgoing request carries the Reconnect action.
-    let out = session
-        .accept_client_operation(UserOperation::Reconnect)
-        .expect("accept Reconnect operation");
-    let ActiveSessionOutput::SendBack(reqs) = out else {
-        panic!("expected SendBack for Reconnect, got {out:?}");
+    let err = session
+        .step(status(resend_conn, 401, &[]))
+        .expect_err("a rejected Receive ends the session");
+    assert_eq!(err.http_status(), Some(401));
+}
+
+/// A `w:QuotaLimit` fault on a command resends it after a growing backoff
+/// instead of failing the session.
+#[test]
+fn quota_faults_back_off_and_resend_the_request() {
+    use std::time::Duration;
+
+    use ironposh_client_core::Quota;
+    use ironposh_client_core::connector::http::{
+        HttpBody, HttpResponse, HttpResponseTargeted, Interruption,
     };
-    let (request, conn_id) = support::expect_just_send(reqs.into_iter().next().unwrap());
-    let reconnect_xml = request
-        .body
-        .expect("reconnect has a body")
-        .as_str()
-        .expect("plaintext body")
-        .to_owned();
-    assert!(
-        reconnect_xml.contains("http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Reconnect"),
-        "outgoing XML must carry the Reconnect action, got: {reconnect_xml}"
-    );
-    assert!(
-        reconnect_xml.contains(FIXTURE_SHELL_ID),
-        "outgoing XML must carry the ShellId selector, got: {reconnect_xml}"
-    );
-    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Connecting);
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
 
-    // 4. ReconnectResponse → pool is Opened again and the receive loop resumes.
-    let outputs = session
-        .accept_server_response(support::xml_response(
+    let quota_fault = |conn_id| {
+        SessionInput::ServerResponse(HttpResponseTargeted::new(
+            HttpResponse {
+                status_code: 500,
+                headers: vec![],
+                body: HttpBody::Xml(quota_fault_envelope(&support::relates_to(conn_id))),
+                peer_cert_der: None,
+            },
             conn_id,
-            shell_op_response_xml("ReconnectResponse", "<rsp:ReconnectResponse/>"),
+            None,
         ))
-        .expect("accept ReconnectResponse");
-    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
-    assert!(
-        outputs
-            .iter()
-            .any(|o| matches!(o, ActiveSessionOutput::PendingReceive { .. })),
-        "ReconnectResponse must schedule a Receive to resume the loop, got: {outputs:?}"
+    };
+    let resent = |outputs: Vec<ActiveSessionOutput>| match <[_; 1]>::try_from(outputs) {
+        Ok(
+            [
+                ActiveSessionOutput::Interrupted {
+                    interruption,
+                    retry: Some((delay, resend)),
+                },
+            ],
+        ) => (interruption, delay, support::expect_just_send(resend)),
+        other => panic!("expected a resent request, got {other:?}"),
+    };
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::InvokeWithSpec {
+                uuid: uuid::Uuid::new_v4(),
+                spec: PipelineSpec {
+                    commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                    add_to_history: false,
+                },
+            }))
+            .expect("invoke"),
+    );
+    let [ActiveSessionOutput::SendBack(reqs)] = <[_; 1]>::try_from(outputs).expect("one output")
+    else {
+        panic!("expected the command to be sent");
+    };
+    let [req] = <[_; 1]>::try_from(reqs).expect("exactly one request");
+    let (command, command_conn) = support::expect_just_send(req);
+
+    let (interruption, delay, (resend, resend_conn)) = resent(step_outputs(
+        session
+            .step(quota_fault(command_conn))
+            .expect("a quota fault on a command is not fatal"),
+    ));
+    assert_eq!(
+        interruption,
+        Interruption::QuotaExceeded {
+            which: Quota::OperationsPerUser,
+            limit: Some(25),
+        }
     );
+    assert_eq!(delay, Duration::from_secs(2));
+    assert_eq!(resend.body_bytes(), command.body_bytes());
+
+    let (_, delay, _) = resent(step_outputs(
+        session
+            .step(quota_fault(resend_conn))
+            .expect("a second quota fault backs off further"),
+    ));
+    assert_eq!(delay, Duration::from_secs(4));
+}
+
+/// `w:QuotaLimit` fault envelope answering the request `relates_to` names.
+fn quota_fault_envelope(relates_to: &str) -> String {
+    format!(
+        r#"<s:Envelope xml:lang="en-US"
+    xmlns:s="http://www.w3.org/2003/05/soap-envelope"
+    xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing"
+    xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd">
+    <s:Header>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
+    </s:Header>
+    <s:Body>
+        <s:Fault>
+            <s:Code>
+                <s:Value>s:Receiver</s:Value>
+                <s:Subcode>
+                    <s:Value>w:QuotaLimit</s:Value>
+                </s:Subcode>
+            </s:Code>
+            <s:Reason>
+                <s:Text xml:lang="en-US">The WS-Management service cannot process the request. This user is allowed a maximum number of 25 concurrent operations, which has been exceeded.</s:Text>
+            </s:Reason>
+        </s:Fault>
+    </s:Body>
+</s:Envelope>""#
+    )
 }
 
-/// Minimal WSMan fault envelope (adapted from ironposh-winrm's error_response fixture).
-const FAULT_ENVELOPE: &str = r#"<s:Envelope xml:lang="en-US"
+/// Minimal WSMan fault envelope (adapted from ironposh-winrm's error_response fixture)
+/// answering the request `relates_to` names.
+fn fault_envelope(relates_to: &str) -> String {
+    format!(
+        r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
     xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing"
     xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd"
@@ -307,8 +1863,8 @@ const FAULT_ENVELOPE: &str = r#"<s:Envelope xml:lang="en-US"
     <s:Header>
         <a:Action>http://schemas.dmtf.org/wbem/wsman/1/wsman/fault</a:Action>
         <a:MessageID>uuid:BB7AF8AE-D64A-422D-B36E-15A04FA17C5C</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
-        <a:RelatesTo>uuid:bead0162-a67d-424d-9e22-4a18b6aefea8</a:RelatesTo>
     </s:Header>
     <s:Body>
         <s:Fault>
@@ -323,7 +1879,9 @@ const FAULT_ENVELOPE: &str = r#"<s:Envelope xml:lang="en-US"
             </s:Reason>
         </s:Fault>
     </s:Body>
-</s:Envelope>"#;
+</s:Envelope>""#
+    )
+}
 
 /// Mistimed Disconnect/Reconnect operations must be ignored, not kill the session.
 #[test]
@@ -360,6 +1918,61 @@ fn mistimed_disconnect_reconnect_is_nonfatal() {
     );
 }
 
+/// Close deletes the shell (WS-Transfer Delete); the DeleteResponse, which
+/// only carries the Action header, ends the pool.
+#[test]
+fn close_deletes_the_shell_and_closes_the_pool() {
+    use ironposh_client_core::connector::{ActiveSessionOutput, UserOperation};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    const FIXTURE_SHELL_ID: &str = "07936B27-7752-4325-8B0D-E7A1E9448320";
+
+    let mut session = establish_active_session();
+
+    let out = session
+        .accept_client_operation(UserOperation::Close)
+        .expect("accept Close operation");
+    let ActiveSessionOutput::SendBack(reqs) = out else {
+        panic!("expected SendBack for Close, got {out:?}");
+    };
+    let (request, conn_id) = support::expect_just_send(reqs.into_iter().next().unwrap());
+    let delete_xml = request
+        .body
+        .expect("delete has a body")
+        .as_str()
+        .expect("plaintext body")
+        .to_owned();
+    assert!(
+        delete_xml.contains("http://schemas.xmlsoap.org/ws/2004/09/transfer/Delete"),
+        "outgoing XML must carry the Delete action, got: {delete_xml}"
+    );
+    assert!(
+        delete_xml.contains(FIXTURE_SHELL_ID),
+        "outgoing XML must carry the ShellId selector, got: {delete_xml}"
+    );
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Closing);
+
+    // A second Close while one is in flight is ignored.
+    let out = session
+        .accept_client_operation(UserOperation::Close)
+        .expect("mistimed Close must be non-fatal");
+    assert!(matches!(out, ActiveSessionOutput::Ignore));
+
+    let outputs = session
+        .accept_server_response(support::xml_response(
+            conn_id,
+            support::delete_response_xml(&support::relates_to(conn_id)),
+        ))
+        .expect("accept DeleteResponse");
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Closed);
+    assert!(
+        outputs
+            .iter()
+            .all(|o| matches!(o, ActiveSessionOutput::OperationSuccess)),
+        "DeleteResponse must yield OperationSuccess only, got: {outputs:?}"
+    );
+}
+
 /// A SOAP fault answering the Disconnect request itself must abort the disconnect:
 /// the pool reverts to Opened instead of staying stuck in Disconnecting forever.
 #[test]
@@ -383,7 +1996,10 @@ fn faulted_disconnect_reverts_pool_to_opened() {
 
     // The server faults the Disconnect request on the same connection.
     let outputs = session
-        .accept_server_response(support::xml_response(conn_id, FAULT_ENVELOPE.to_owned()))
+        .accept_server_response(support::xml_response(
+            conn_id,
+            fault_envelope(&support::relates_to(conn_id)),
+        ))
         .expect("a faulted Disconnect must not kill the session");
     assert_eq!(
         session.runspace_pool_state(),
@@ -414,6 +2030,7 @@ fn fault_on_other_connection_while_disconnecting_is_tolerated() {
             uuid: uuid::Uuid::new_v4(),
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke pipeline");
@@ -437,7 +2054,7 @@ fn fault_on_other_connection_while_disconnecting_is_tolerated() {
     let outputs = session
         .accept_server_response(support::xml_response(
             pipeline_conn_id,
-            FAULT_ENVELOPE.to_owned(),
+            fault_envelope(&support::relates_to(pipeline_conn_id)),
         ))
         .expect("teardown fault must be tolerated while disconnecting");
     assert_eq!(
@@ -456,7 +2073,11 @@ fn fault_on_other_connection_while_disconnecting_is_tolerated() {
     session
         .accept_server_response(support::xml_response(
             disconnect_conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
         ))
         .expect("accept DisconnectResponse");
     assert_eq!(
@@ -490,7 +2111,11 @@ fn invalid_disconnect_response_on_disconnect_connection_reverts_pool_to_opened()
     let outputs = session
         .accept_server_response(support::xml_response(
             disconnect_conn_id,
-            shell_op_response_xml("ReceiveResponse", "<rsp:ReceiveResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "ReceiveResponse",
+                "<rsp:ReceiveResponse/>",
+            ),
         ))
         .expect("invalid Disconnect response must not kill the session");
     assert_eq!(
@@ -531,6 +2156,7 @@ fn reconnect_resumes_active_pipeline_streams() {
             uuid: pipeline_id,
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke pipeline");
@@ -550,7 +2176,11 @@ fn reconnect_resumes_active_pipeline_streams() {
     session
         .accept_server_response(support::xml_response(
             conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
         ))
         .expect("accept DisconnectResponse");
 
@@ -565,7 +2195,11 @@ fn reconnect_resumes_active_pipeline_streams() {
     let outputs = session
         .accept_server_response(support::xml_response(
             conn_id,
-            shell_op_response_xml("ReconnectResponse", "<rsp:ReconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(conn_id),
+                "ReconnectResponse",
+                "<rsp:ReconnectResponse/>",
+            ),
         ))
         .expect("accept ReconnectResponse");
     assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
@@ -603,6 +2237,7 @@ fn reconnect_ignores_stale_traffic_before_real_response() {
             uuid: uuid::Uuid::new_v4(),
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke pipeline");
@@ -624,7 +2259,11 @@ fn reconnect_ignores_stale_traffic_before_real_response() {
     session
         .accept_server_response(support::xml_response(
             disconnect_conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
         ))
         .expect("accept DisconnectResponse");
     assert_eq!(
@@ -645,7 +2284,7 @@ fn reconnect_ignores_stale_traffic_before_real_response() {
     let outputs = session
         .accept_server_response(support::xml_response(
             stale_conn_id,
-            FAULT_ENVELOPE.to_owned(),
+            fault_envelope(&support::relates_to(stale_conn_id)),
         ))
         .expect("stale traffic while reconnecting must be ignored");
     assert_eq!(
@@ -663,7 +2302,11 @@ fn reconnect_ignores_stale_traffic_before_real_response() {
     let outputs = session
         .accept_server_response(support::xml_response(
             reconnect_conn_id,
-            shell_op_response_xml("ReconnectResponse", "<rsp:ReconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(reconnect_conn_id),
+                "ReconnectResponse",
+                "<rsp:ReconnectResponse/>",
+            ),
         ))
         .expect("real ReconnectResponse must complete reconnect");
     assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
@@ -699,7 +2342,11 @@ fn invoke_while_disconnected_emits_terminal_pipeline_finished() {
     session
         .accept_server_response(support::xml_response(
             disconnect_conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
         ))
         .expect("accept DisconnectResponse");
     assert_eq!(
@@ -714,6 +2361,7 @@ fn invoke_while_disconnected_emits_terminal_pipeline_finished() {
             uuid,
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke while disconnected must be non-fatal");
@@ -729,6 +2377,257 @@ fn invoke_while_disconnected_emits_terminal_pipeline_finished() {
     }
 }
 
+/// Operations issued while a Reconnect is in flight are queued rather than sent
+/// against a shell in transition, and dispatched in order once the reconnect lands.
+#[test]
+fn invoke_while_reconnecting_is_queued_then_dispatched() {
+    use ironposh_client_core::connector::{ActiveSessionOutput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::runspace_pool::RunspacePoolState;
+
+    let mut session = establish_active_session();
+
+    let out = session
+        .accept_client_operation(UserOperation::Disconnect)
+        .expect("accept Disconnect operation");
+    let ActiveSessionOutput::SendBack(reqs) = out else {
+        panic!("expected SendBack for Disconnect, got {out:?}");
+    };
+    let (_request, disconnect_conn_id) =
+        support::expect_just_send(reqs.into_iter().next().unwrap());
+    session
+        .accept_server_response(support::xml_response(
+            disconnect_conn_id,
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
+        ))
+        .expect("accept DisconnectResponse");
+
+    let out = session
+        .accept_client_operation(UserOperation::Reconnect)
+        .expect("accept Reconnect operation");
+    let ActiveSessionOutput::SendBack(reqs) = out else {
+        panic!("expected SendBack for Reconnect, got {out:?}");
+    };
+    let (_request, reconnect_conn_id) = support::expect_just_send(reqs.into_iter().next().unwrap());
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Connecting);
+
+    let out = session
+        .accept_client_operation(UserOperation::InvokeWithSpec {
+            uuid: uuid::Uuid::new_v4(),
+            spec: PipelineSpec {
+                commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
+            },
+        })
+        .expect("invoke while reconnecting must be non-fatal");
+    assert!(
+        matches!(
+            out,
+            ActiveSessionOutput::OperationQueued {
+                operation_type: "InvokeWithSpec"
+            }
+        ),
+        "invoke during reconnect must be queued, got: {out:?}"
+    );
+    assert_eq!(session.queued_operation_count(), 1);
+
+    let outputs = session
+        .accept_server_response(support::xml_response(
+            reconnect_conn_id,
+            shell_op_response_xml(
+                &support::relates_to(reconnect_conn_id),
+                "ReconnectResponse",
+                "<rsp:ReconnectResponse/>",
+            ),
+        ))
+        .expect("accept ReconnectResponse");
+    assert_eq!(session.runspace_pool_state(), RunspacePoolState::Opened);
+    assert_eq!(session.queued_operation_count(), 0);
+    assert!(
+        outputs.iter().any(|o| matches!(
+            o,
+            ActiveSessionOutput::OperationDispatched {
+                operation_type: "InvokeWithSpec"
+            }
+        )),
+        "queued invoke must be reported as dispatched, got: {outputs:?}"
+    );
+    assert!(
+        outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::SendBack(_))),
+        "queued invoke must be sent once the pool is Opened, got: {outputs:?}"
+    );
+}
+
+/// Two operations submitted back to back while Opened: the second waits for
+/// the server to answer the first, so the commands reach the shell in order.
+#[test]
+fn back_to_back_operations_are_queued_behind_the_first_send() {
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::powershell::PipelineHandle;
+
+    let invoke = |uuid, script: &str| {
+        SessionInput::Operation(UserOperation::InvokeWithSpec {
+            uuid,
+            spec: PipelineSpec {
+                commands: vec![PipelineCommand::new_script(script.to_owned())],
+                add_to_history: false,
+            },
+        })
+    };
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let first = uuid::Uuid::new_v4();
+    let second = uuid::Uuid::new_v4();
+
+    let first_conn = single_send(step_outputs(
+        session
+            .step(invoke(first, "Get-Date"))
+            .expect("first invoke"),
+    ));
+    let outputs = step_outputs(
+        session
+            .step(invoke(second, "Get-Process"))
+            .expect("second invoke"),
+    );
+    assert!(
+        matches!(
+            outputs.as_slice(),
+            [ActiveSessionOutput::OperationQueued {
+                operation_type: "InvokeWithSpec"
+            }]
+        ),
+        "second invoke must wait for the first, got {outputs:?}"
+    );
+    assert_eq!(session.queued_operation_count(), 1);
+    let pipelines: Vec<_> = session.pipelines().iter().map(PipelineHandle::id).collect();
+    assert_eq!(pipelines, [first]);
+
+    let outputs = step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                first_conn,
+                support::command_response_xml(&support::relates_to(first_conn), first),
+            )))
+            .expect("first CommandResponse"),
+    );
+    assert_eq!(session.queued_operation_count(), 0);
+    assert!(
+        outputs.iter().any(|o| matches!(
+            o,
+            ActiveSessionOutput::OperationDispatched {
+                operation_type: "InvokeWithSpec"
+            }
+        )),
+        "second invoke must be dispatched once the first is answered, got {outputs:?}"
+    );
+    assert!(
+        outputs
+            .iter()
+            .any(|o| matches!(o, ActiveSessionOutput::SendBack(_))),
+        "second invoke must be sent, got {outputs:?}"
+    );
+    let pipelines: Vec<_> = session.pipelines().iter().map(PipelineHandle::id).collect();
+    let mut expected = [first, second];
+    expected.sort_unstable();
+    assert_eq!(pipelines, expected);
+}
+
+/// Signals overtake an outstanding Send: Ctrl+C and kill go out at once while
+/// a host response is still unanswered, and do not hold back what follows.
+#[test]
+fn signals_are_not_queued_behind_an_outstanding_send() {
+    use ironposh_client_core::connector::{ActiveSessionOutput, SessionInput, UserOperation};
+    use ironposh_client_core::host::{HostCallScope, Submission};
+    use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+    use ironposh_client_core::powershell::{PipelineHandle, SignalCode};
+    use ironposh_psrp::{PipelineHostResponse, RemoteHostMethodId};
+
+    let invoke = |uuid| {
+        SessionInput::Operation(UserOperation::InvokeWithSpec {
+            uuid,
+            spec: PipelineSpec {
+                commands: vec![PipelineCommand::new_script("Read-Host".to_owned())],
+                add_to_history: false,
+            },
+        })
+    };
+
+    let (mut session, _rpid, _receive_conn) = establish_state_machine();
+    let pipeline_id = uuid::Uuid::new_v4();
+    let invoke_conn = single_send(step_outputs(
+        session.step(invoke(pipeline_id)).expect("invoke"),
+    ));
+    step_outputs(
+        session
+            .step(SessionInput::ServerResponse(support::xml_response(
+                invoke_conn,
+                support::command_response_xml(&support::relates_to(invoke_conn), pipeline_id),
+            )))
+            .expect("CommandResponse"),
+    );
+
+    single_send(step_outputs(
+        session
+            .step(SessionInput::Operation(UserOperation::SubmitHostResponse {
+                submission: Submission::Send(PipelineHostResponse::exception(
+                    1,
+                    RemoteHostMethodId::ReadLine,
+                    "cancelled",
+                )),
+                scope: HostCallScope::Pipeline {
+                    command_id: pipeline_id,
+                },
+                call_id: 1,
+            }))
+            .expect("host response Send"),
+    ));
+
+    for operation in [
+        UserOperation::SignalPipeline {
+            pipeline: PipelineHandle::new(pipeline_id),
+            code: SignalCode::PowerShellCtrlC,
+        },
+        UserOperation::KillPipeline {
+            pipeline: PipelineHandle::new(pipeline_id),
+        },
+    ] {
+        let operation_type = operation.operation_type();
+        let outputs = step_outputs(
+            session
+                .step(SessionInput::Operation(operation))
+                .expect("signal"),
+        );
+        assert!(
+            matches!(outputs.as_slice(), [ActiveSessionOutput::SendBack(_)]),
+            "{operation_type} must be sent while the Send is outstanding, got {outputs:?}"
+        );
+        assert_eq!(session.queued_operation_count(), 0);
+    }
+
+    // Shell-bound operations still wait for the Send.
+    let outputs = step_outputs(
+        session
+            .step(invoke(uuid::Uuid::new_v4()))
+            .expect("second invoke"),
+    );
+    assert!(
+        matches!(
+            outputs.as_slice(),
+            [ActiveSessionOutput::OperationQueued {
+                operation_type: "InvokeWithSpec"
+            }]
+        ),
+        "invoke must wait for the host response Send, got {outputs:?}"
+    );
+}
+
 /// A straggler (late fault or transport error) from a connection retired at disconnect
 /// time must be ignored in ANY state — including after a reconnect returns the pool to
 /// Opened — so a stale pre-disconnect Receive cannot kill the session.
@@ -747,6 +2646,7 @@ fn straggler_from_retired_connection_is_ignored_even_when_opened() {
             uuid: uuid::Uuid::new_v4(),
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke");
@@ -763,7 +2663,7 @@ fn straggler_from_retired_connection_is_ignored_even_when_opened() {
     let outputs = session
         .accept_server_response(support::xml_response(
             doomed_conn,
-            FAULT_ENVELOPE.to_owned(),
+            fault_envelope(&support::relates_to(doomed_conn)),
         ))
         .expect("straggler must be ignored, not fatal");
     assert!(
@@ -788,6 +2688,7 @@ fn straggler_from_retired_connection_is_ignored_even_when_opened() {
             uuid: fresh,
             spec: PipelineSpec {
                 commands: vec![PipelineCommand::new_script("Get-Date".to_owned())],
+                add_to_history: false,
             },
         })
         .expect("invoke");
@@ -823,7 +2724,11 @@ fn faulted_reconnect_reverts_pool_to_disconnected() {
     session
         .accept_server_response(support::xml_response(
             disconnect_conn_id,
-            shell_op_response_xml("DisconnectResponse", "<rsp:DisconnectResponse/>"),
+            shell_op_response_xml(
+                &support::relates_to(disconnect_conn_id),
+                "DisconnectResponse",
+                "<rsp:DisconnectResponse/>",
+            ),
         ))
         .expect("accept DisconnectResponse");
     assert_eq!(
@@ -844,7 +2749,7 @@ fn faulted_reconnect_reverts_pool_to_disconnected() {
     let outputs = session
         .accept_server_response(support::xml_response(
             reconnect_conn_id,
-            FAULT_ENVELOPE.to_owned(),
+            fault_envelope(&support::relates_to(reconnect_conn_id)),
         ))
         .expect("a faulted Reconnect must not kill the session");
     assert_eq!(
@@ -1030,6 +2935,7 @@ fn connect_mode_reaches_connected() {
     };
     let application_private_data = ApplicationPrivateData::new();
     let connect_response = support::connect_response_xml(
+        &support::relates_to(conn_id),
         shell_id,
         &[&session_capability, &init_data, &application_private_data],
     );
@@ -1107,11 +3013,10 @@ fn handshake_reaches_connected() {
     // Note: the fixture's ShellId differs from the client-generated RPID. The connector
     // currently accepts whatever id the server returns without cross-checking, so the
     // mismatch is harmless here; if id validation is ever added, regenerate the fixture.
-    let create_response = include_str!("resources/resource_created.xml");
     let result = connector
         .step(Some(support::xml_response(
             conn_id,
-            create_response.to_owned(),
+            create_response(&support::relates_to(conn_id)),
         )))
         .expect("accept CreateResponse");
     let ConnectorStepResult::SendBack { try_send } = result else {
@@ -1142,6 +3047,7 @@ fn handshake_reaches_connected() {
         .build();
 
     let receive_response = support::receive_response_xml(
+        &support::relates_to(conn_id),
         rpid,
         &[&session_capability, &application_private_data, &pool_opened],
     );
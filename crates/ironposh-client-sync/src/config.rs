@@ -1,27 +1,20 @@
 use clap::{Parser, ValueEnum};
 use ironposh_client_core::{
     connector::{
-        config::{KerberosConfig, TlsOptions},
+        config::{
+            Delegation, HostMode, KdcEndpoint, KerberosConfig, ParseMode, ServicePrincipal,
+            TlsOptions,
+        },
         http::ServerAddress,
         WinRmConfig,
     },
-    AuthenticatorConfig, SspiAuthConfig, TransportSecurity,
+    credentials::{ClientAuthIdentity, ClientUserName, SMART_CARD_NEEDS_KERBEROS},
+    AuthenticatorConfig, PwshCoreError, SspiAuthConfig, TransportSecurity,
 };
+use ironposh_logging::{LogArgs, LogSettings, Redactions};
 use ironposh_psrp::{HostDefaultData, Size};
-use std::sync::OnceLock;
 use tracing::debug;
 use tracing_log::LogTracer;
-use tracing_subscriber::{fmt, prelude::*, registry::Registry, EnvFilter};
-
-static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
-
-/// Sets up a panic hook to ensure logs are flushed before the program exits.
-fn setup_panic_hook() {
-    std::panic::set_hook(Box::new(|panic_info| {
-        // Log the panic information
-        tracing::error!("A panic occurred: {}", panic_info);
-    }));
-}
 
 /// PowerShell Remoting Client (Synchronous)
 #[derive(Parser)]
@@ -69,10 +62,14 @@ pub struct Args {
     #[arg(short, long, help = "Authentication method", default_value_t = AuthMethod::Basic)]
     pub auth_method: AuthMethod,
 
-    /// Optional KDC URL for Kerberos authentication
+    #[command(flatten)]
+    pub smartcard: SmartCardArgs,
+
+    /// Optional KDC URL for Kerberos authentication. An http(s) URL is used as an
+    /// MS-KKDCP KDC proxy.
     #[arg(
         long,
-        help = "KDC URL for Kerberos authentication (e.g., IT-HELP-DC.ad.it-help.ninja)"
+        help = "KDC URL for Kerberos authentication (e.g., tcp://dc.example.com:88, or https://gw.example.com/KdcProxy)"
     )]
     pub kdc_url: Option<String>,
 
@@ -80,6 +77,22 @@ pub struct Args {
     #[arg(long, help = "Client computer name for Kerberos authentication")]
     pub client_computer_name: Option<String>,
 
+    /// Delegate Kerberos credentials to the server (second hop). Fails unless the
+    /// server is trusted for delegation.
+    #[arg(
+        long,
+        help = "Delegate Kerberos credentials to the server (second hop)"
+    )]
+    pub delegate: bool,
+
+    /// Service principal to authenticate against instead of `HTTP/<server>`, for
+    /// CNAMEs and load balancers. `{host}` is replaced by the server name.
+    #[arg(
+        long,
+        help = "SPN override, e.g. HTTP/web01.corp.example.com or HTTP/{host}.corp.example.com"
+    )]
+    pub spn: Option<String>,
+
     /// Use HTTPS instead of HTTP (TLS provides security, SSPI sealing not needed)
     #[arg(long, help = "Use HTTPS (default: HTTP)")]
     pub https: bool,
@@ -92,9 +105,38 @@ pub struct Args {
     )]
     pub http_insecure: bool,
 
+    /// DANGEROUS: Required with `--auth-method none`. Only for listeners behind a
+    /// Devolutions Gateway or reverse proxy that authenticates the user itself.
+    #[arg(
+        long,
+        help = "DANGEROUS: allow --auth-method none (only behind an authenticating gateway)"
+    )]
+    pub allow_no_auth: bool,
+
+    /// Stop a pipeline that has not finished after this many seconds.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Stop any pipeline running longer than this many seconds"
+    )]
+    pub pipeline_timeout: Option<u64>,
+
+    /// Runspace pool application argument, readable on the server as
+    /// `$PSSenderInfo.ApplicationArguments[NAME]`. Repeatable.
+    #[arg(
+        long = "application-argument",
+        value_name = "NAME=VALUE",
+        value_parser = parse_application_argument,
+        help = "Application argument passed to the session (repeatable)"
+    )]
+    pub application_arguments: Vec<(String, String)>,
+
     /// Verbose logging (can be repeated for more verbosity)
     #[arg(short, long, action = clap::ArgAction::Count, help = "Increase logging verbosity")]
     pub verbose: u8,
+
+    #[command(flatten)]
+    pub log: LogArgs,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -103,6 +145,8 @@ pub enum AuthMethod {
     Ntlm,
     Kerberos,
     Negotiate,
+    /// No credentials at all; requires `--allow-no-auth`.
+    None,
 }
 
 impl std::fmt::Display for AuthMethod {
@@ -112,54 +156,60 @@ impl std::fmt::Display for AuthMethod {
             Self::Ntlm => write!(f, "ntlm"),
             Self::Kerberos => write!(f, "kerberos"),
             Self::Negotiate => write!(f, "negotiate"),
+            Self::None => write!(f, "none"),
         }
     }
 }
 
-/// Initialize logging with file output and proper structured logging
-pub fn init_logging(verbose_level: u8) -> anyhow::Result<()> {
-    // Set up the panic hook to flush logs on panic
-    setup_panic_hook();
+/// Environment variable read for the smart card PIN before prompting.
+pub const SMART_CARD_PIN_ENV: &str = "IRONPOSH_SMARTCARD_PIN";
 
-    // Bridge logs from the `log` crate to `tracing`
-    LogTracer::init().ok();
+/// `--smartcard`: log on with a smart card certificate (Kerberos PKINIT)
+/// instead of the password.
+#[derive(Debug, Default, clap::Args)]
+pub struct SmartCardArgs {
+    /// Log on with a smart card; needs Kerberos or Negotiate.
+    #[arg(long, help = "Log on with a smart card (Kerberos or Negotiate only)")]
+    pub smartcard: bool,
 
-    let file = std::fs::File::create("winrm_client.log")?;
-    let (nb_writer, guard) = tracing_appender::non_blocking(file);
+    /// The card's logon certificate, exported as PEM or DER.
+    #[arg(long, value_name = "FILE", requires = "smartcard")]
+    pub smartcard_certificate: Option<std::path::PathBuf>,
 
-    // Store the guard in a static OnceLock to ensure it lives for the duration of the program.
-    if LOG_GUARD.set(guard).is_err() {
-        // This case should ideally not happen in a single-threaded setup,
-        // but it's good practice to handle it.
-        tracing::warn!("LOG_GUARD was already set. This may indicate a problem in initialization.");
-    }
-
-    // Determine log level based on verbosity using global filters
-    let filter_str = match verbose_level {
-        0 => "info,ureq=error,sspi=error",
-        1 => "debug,ureq=warn,sspi=error",
-        2 => "trace,ureq=info,sspi=error",
-        _ => "trace",
-    };
-
-    let env_filter = EnvFilter::new(filter_str);
+    /// The reader the card is in.
+    #[arg(long, value_name = "NAME", requires = "smartcard")]
+    pub smartcard_reader: Option<String>,
 
-    let subscriber = Registry::default().with(env_filter).with(
-        fmt::layer()
-            .with_writer(nb_writer)
-            .with_target(true)
-            .with_line_number(true)
-            .with_file(true)
-            .with_ansi(false)
-            .compact(),
-    );
+    /// Key container on the card; the card's default when omitted.
+    #[arg(long, value_name = "NAME", requires = "smartcard")]
+    pub smartcard_container: Option<String>,
 
-    tracing::subscriber::set_global_default(subscriber)?;
+    /// Cryptographic service provider of the card.
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "Microsoft Base Smart Card Crypto Provider"
+    )]
+    pub smartcard_csp: String,
+}
 
-    // Immediately log something to confirm initialization.
-    tracing::info!("Logging system initialized.");
+/// Initialize logging as the `--log-*` flags ask; the log file defaults to
+/// `winrm_client.log`.
+pub fn init_logging(args: &Args) -> anyhow::Result<()> {
+    // Bridge logs from the `log` crate to `tracing`
+    LogTracer::init().ok();
 
-    Ok(())
+    ironposh_logging::init(
+        &args.log,
+        LogSettings {
+            verbose: args.verbose,
+            default_file: "winrm_client.log".into(),
+            redactions: Redactions::new()
+                .host(&args.server)
+                .user(&args.username)
+                .domain(&args.domain),
+        },
+    )
 }
 
 /// Create connector configuration from command line arguments
@@ -180,69 +230,98 @@ pub fn create_connector_config(
         TransportSecurity::Http
     };
 
+    // Only Kerberos has a certificate logon (PKINIT); refuse the rest before
+    // prompting for a PIN.
+    if args.smartcard.smartcard
+        && !matches!(
+            args.auth_method,
+            AuthMethod::Kerberos | AuthMethod::Negotiate
+        )
+    {
+        return Err(PwshCoreError::Auth(SMART_CARD_NEEDS_KERBEROS).into());
+    }
+
+    if matches!(args.auth_method, AuthMethod::None) {
+        if !args.allow_no_auth {
+            anyhow::bail!(
+                "--auth-method none sends requests without any credentials or message \
+                 encryption. It is only meant for listeners behind an authenticating \
+                 gateway; pass --allow-no-auth to confirm."
+            );
+        }
+        tracing::warn!(
+            "Authentication is DISABLED - relying on a gateway or reverse proxy to authenticate!"
+        );
+    }
+
     let domain = if args.domain.trim().is_empty() {
         None
     } else {
         Some(args.domain.as_str())
     };
 
+    let delegation = if args.delegate {
+        Delegation::Required
+    } else {
+        Delegation::None
+    };
+
+    let sspi_identity = || -> anyhow::Result<ClientAuthIdentity> {
+        if args.smartcard.smartcard {
+            return smart_card_identity(&args.smartcard, &args.username);
+        }
+        let client_username = ClientUserName::new(&args.username, domain)?;
+        Ok(ClientAuthIdentity::new(
+            client_username,
+            args.password.clone(),
+        ))
+    };
+
     let auth = match args.auth_method {
         AuthMethod::Basic => AuthenticatorConfig::Basic {
             username: args.username.clone(),
             password: args.password.clone(),
         },
         AuthMethod::Ntlm => {
-            let client_username =
-                ironposh_client_core::credentials::ClientUserName::new(&args.username, domain)?;
-            let identity = ironposh_client_core::credentials::ClientAuthIdentity::new(
-                client_username,
-                args.password.clone(),
-            );
+            let client_username = ClientUserName::new(&args.username, domain)?;
+            let identity = ClientAuthIdentity::new(client_username, args.password.clone());
             AuthenticatorConfig::Sspi(SspiAuthConfig::NTLM {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
             })
         }
         AuthMethod::Kerberos => {
-            let client_username =
-                ironposh_client_core::credentials::ClientUserName::new(&args.username, domain)?;
+            let identity = sspi_identity()?;
 
-            let identity = ironposh_client_core::credentials::ClientAuthIdentity::new(
-                client_username,
-                args.password.clone(),
-            );
-
-            let kdc_url = args.kdc_url.as_ref().map(|url| url.parse()).transpose()?;
+            let kdc = resolve_kdc_endpoint(args)?;
 
             AuthenticatorConfig::Sspi(SspiAuthConfig::Kerberos {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
                 kerberos_config: KerberosConfig {
-                    kdc_url,
+                    kdc,
                     client_computer_name: args.client_computer_name.clone().unwrap_or_else(|| {
                         whoami::fallible::hostname().unwrap_or_else(|_| "localhost".to_string())
                     }),
+                    delegation,
                 },
             })
         }
         AuthMethod::Negotiate => {
-            let client_username =
-                ironposh_client_core::credentials::ClientUserName::new(&args.username, domain)?;
-            let identity = ironposh_client_core::credentials::ClientAuthIdentity::new(
-                client_username,
-                args.password.clone(),
-            );
+            let identity = sspi_identity()?;
             AuthenticatorConfig::Sspi(SspiAuthConfig::Negotiate {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
                 kerberos_config: Some(KerberosConfig {
-                    kdc_url: args.kdc_url.as_ref().map(|url| url.parse()).transpose()?,
+                    kdc: resolve_kdc_endpoint(args)?,
                     client_computer_name: args.client_computer_name.clone().unwrap_or_else(|| {
                         whoami::fallible::hostname().unwrap_or_else(|_| "localhost".to_string())
                     }),
+                    delegation,
                 }),
             })
         }
+        AuthMethod::None => AuthenticatorConfig::None,
     };
 
     // Use real terminal size from the terminal instance
@@ -261,19 +340,89 @@ pub fn create_connector_config(
         .window_size(size.clone())
         .max_window_size(size.clone())
         .max_physical_window_size(size)
-        .build();
+        .build()
+        .map_err(|e| anyhow::anyhow!("terminal size {cols}x{rows} is not a usable console: {e}"))?;
 
     let host_info = ironposh_psrp::HostInfo::builder()
         .host_default_data(host_data)
         .build();
 
-    Ok(WinRmConfig {
-        server: (server, args.port),
-        transport,
-        authentication: auth,
-        host_info,
-        operation_timeout_secs: None,
-        tls: TlsOptions::default(),
-        configuration_name: None,
-    })
+    Ok(WinRmConfig::builder()
+        .server((server, args.port))
+        .transport(transport)
+        .authentication(auth)
+        .host_info(host_info)
+        .service_principal(args.spn.clone().map(ServicePrincipal::new))
+        .host_mode(HostMode::Interactive)
+        .parse_mode(ParseMode::Strict)
+        .build()
+        .with_application_arguments(args.application_arguments.clone()))
+}
+
+/// The identity `--smartcard` selects. The PIN comes from
+/// [`SMART_CARD_PIN_ENV`], or is prompted for without echo.
+#[cfg(feature = "smartcard")]
+fn smart_card_identity(args: &SmartCardArgs, username: &str) -> anyhow::Result<ClientAuthIdentity> {
+    use anyhow::Context as _;
+    use ironposh_client_core::credentials::SmartCardLogon;
+
+    let certificate_path = args
+        .smartcard_certificate
+        .as_ref()
+        .context("--smartcard needs --smartcard-certificate")?;
+    let certificate = std::fs::read(certificate_path).with_context(|| {
+        format!(
+            "failed to read smart card certificate {}",
+            certificate_path.display()
+        )
+    })?;
+    let reader_name = args
+        .smartcard_reader
+        .clone()
+        .context("--smartcard needs --smartcard-reader")?;
+    let pin = match std::env::var(SMART_CARD_PIN_ENV) {
+        Ok(pin) => pin,
+        Err(_) => ironposh_terminal::read_secret(&format!("PIN for the card in {reader_name}: "))
+            .context("failed to read the smart card PIN")?,
+    };
+    Ok(ClientAuthIdentity::from_smart_card_logon(SmartCardLogon {
+        username: username.to_owned(),
+        certificate,
+        reader_name,
+        container_name: args.smartcard_container.clone(),
+        csp_name: args.smartcard_csp.clone(),
+        pin,
+    })?)
+}
+
+#[cfg(not(feature = "smartcard"))]
+fn smart_card_identity(_: &SmartCardArgs, _: &str) -> anyhow::Result<ClientAuthIdentity> {
+    anyhow::bail!("--smartcard needs a build with the `smartcard` feature")
+}
+
+fn parse_application_argument(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(format!("expected NAME=VALUE, got `{arg}`")),
+    }
+}
+
+fn resolve_kdc_endpoint(args: &Args) -> anyhow::Result<Option<KdcEndpoint>> {
+    if let Some(url) = &args.kdc_url {
+        return Ok(Some(KdcEndpoint::from_url(
+            url.parse()?,
+            TlsOptions::default(),
+        )));
+    }
+
+    // No explicit KDC: look one up for the realm, falling back to sspi's own
+    // discovery if DNS has nothing.
+    let realm = args.domain.trim();
+    if realm.is_empty() {
+        return Ok(None);
+    }
+    Ok(crate::kerberos::discover_kdc(realm)
+        .inspect_err(|e| tracing::warn!(error = %e, realm, "KDC discovery failed"))
+        .ok()
+        .map(KdcEndpoint::Direct))
 }
@@ -0,0 +1,32 @@
+//! The panic hook: a panic must not leave the user's terminal in raw mode
+//! or the remote shell running with nobody attached to it.
+
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use ironposh_client_core::connector::UserOperation;
+
+/// How long a panic gives the network thread to send the Delete.
+const CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Restore the terminal and queue a Close on the event loop before the
+/// crash report is written. A panic on the event loop thread itself cannot
+/// close the session, so it only restores the terminal.
+pub fn install_panic_hook(user_request_tx: mpsc::Sender<UserOperation>) {
+    let event_loop = thread::current().id();
+    let user_request_tx = Mutex::new(Some(user_request_tx));
+    ironposh_logging::install_panic_hook(move || {
+        ironposh_terminal::restore_host_terminal();
+
+        if thread::current().id() == event_loop {
+            return;
+        }
+        let Some(user_request_tx) = user_request_tx.lock().ok().and_then(|mut tx| tx.take()) else {
+            return;
+        };
+        if user_request_tx.send(UserOperation::Close).is_ok() {
+            thread::sleep(CLOSE_GRACE);
+        }
+    });
+}
@@ -59,6 +59,12 @@ pub struct UreqHttpClient {
     read_timeout: std::time::Duration,
 }
 
+impl Default for UreqHttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UreqHttpClient {
     pub fn new() -> Self {
         info!(
@@ -238,6 +244,9 @@ impl HttpClient for UreqHttpClient {
                     None;
 
                 loop {
+                    // A configured KDC proxy carries its own TLS options.
+                    let kdc_tls = auth_sequence.kdc_proxy_tls().cloned().unwrap_or_default();
+
                     // 1) Initialize security context (may require KDC generator)
                     let (seq, mut holder) = auth_sequence.prepare();
                     let init =
@@ -249,7 +258,7 @@ impl HttpClient for UreqHttpClient {
                             } => {
                                 info!("running generator for KDC communication");
                                 loop {
-                                    let kdc_resp = send_packet(packet).context(
+                                    let kdc_resp = send_packet(packet, &kdc_tls).context(
                                         "failed to send packet to KDC during authentication",
                                     )?;
                                     match SspiAuthSequence::resume(generator_holder, kdc_resp)? {
@@ -1,33 +1,214 @@
 use anyhow::Context;
 use byteorder::{BigEndian, ReadBytesExt};
-use ironposh_client_core::connector::NetworkRequest;
+use ironposh_client_core::connector::{
+    config::TlsOptions,
+    kdc_discovery::{
+        nameservers_from_resolv_conf, parse_srv_response, srv_query, KdcTransport, SrvRecord,
+    },
+    NetworkRequest,
+};
 use std::io::{Read, Write};
-use std::net::TcpStream;
-use tracing::{info, instrument};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::time::Duration;
+use tracing::{info, instrument, warn};
+use url::Url;
+
+/// Upper bound on a KDC response; real replies are a few KiB.
+const MAX_KDC_RESPONSE: u64 = 2 * 1024 * 1024;
+/// Matches sspi-rs' default maximum token length for UDP KDC replies.
+const MAX_UDP_RESPONSE: usize = 0xbb80;
+const KDC_UDP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Per-nameserver wait for a DNS reply.
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Finds the KDC for `realm` through `_kerberos` DNS SRV records, asking the
+/// nameservers listed in `/etc/resolv.conf`. TCP records are preferred, since
+/// tickets with PACs rarely fit in a UDP datagram.
+#[instrument(name = "kerberos.discover_kdc", level = "info", err)]
+pub fn discover_kdc(realm: &str) -> Result<Url, anyhow::Error> {
+    let resolv_conf =
+        std::fs::read_to_string("/etc/resolv.conf").context("failed to read /etc/resolv.conf")?;
+    let nameservers = nameservers_from_resolv_conf(&resolv_conf);
+    if nameservers.is_empty() {
+        anyhow::bail!("no nameservers configured in /etc/resolv.conf");
+    }
+
+    for transport in [KdcTransport::Tcp, KdcTransport::Udp] {
+        for &nameserver in &nameservers {
+            match query_srv(nameserver, realm, transport) {
+                Ok(records) => {
+                    if let Some(record) = records.first() {
+                        let url = record.kdc_url(transport)?;
+                        info!(%url, "discovered KDC via DNS SRV");
+                        return Ok(url);
+                    }
+                }
+                Err(error) => warn!(%nameserver, %error, "DNS SRV query failed"),
+            }
+        }
+    }
+
+    anyhow::bail!("no _kerberos SRV records found for realm {realm}")
+}
+
+fn query_srv(
+    nameserver: IpAddr,
+    realm: &str,
+    transport: KdcTransport,
+) -> Result<Vec<SrvRecord>, anyhow::Error> {
+    let id = {
+        let uuid = uuid::Uuid::new_v4();
+        u16::from_be_bytes([uuid.as_bytes()[0], uuid.as_bytes()[1]])
+    };
+    let query = srv_query(realm, transport, id)?;
+
+    let socket = UdpSocket::bind(unspecified_addr(nameserver))
+        .context("failed to bind UDP socket for DNS query")?;
+    socket.set_read_timeout(Some(DNS_TIMEOUT))?;
+    socket
+        .connect((nameserver, 53))
+        .context("failed to connect UDP socket to nameserver")?;
+    socket.send(&query).context("failed to send DNS query")?;
+
+    let mut response = [0u8; 4096];
+    let len = socket
+        .recv(&mut response)
+        .context("failed to read DNS response")?;
+    Ok(parse_srv_response(&response[..len], id)?)
+}
+
+fn unspecified_addr(remote: IpAddr) -> SocketAddr {
+    match remote {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    }
+}
 
 /// Sends a network packet to the Kerberos KDC and returns the response
 #[instrument(
     name = "kerberos.send_packet",
     level = "info",
-    skip(packet),
-    fields(protocol = ?packet.protocol, url = %packet.url, data_len = packet.data.len()),
+    skip(packet, tls),
+    fields(protocol = ?packet.protocol, host = packet.url.host_str(), data_len = packet.data.len()),
     err
 )]
-pub fn send_packet(packet: NetworkRequest) -> Result<Vec<u8>, anyhow::Error> {
+pub fn send_packet(packet: NetworkRequest, tls: &TlsOptions) -> Result<Vec<u8>, anyhow::Error> {
     info!("sending packet to KDC");
 
     match packet.protocol {
         ironposh_client_core::connector::NetworkProtocol::Tcp => send_tcp_packet(packet),
-        ironposh_client_core::connector::NetworkProtocol::Udp => {
-            todo!("UDP protocol not implemented for Kerberos")
-        }
-        ironposh_client_core::connector::NetworkProtocol::Http => {
-            todo!("HTTP protocol not implemented for Kerberos")
+        ironposh_client_core::connector::NetworkProtocol::Udp => send_udp_packet(packet),
+        ironposh_client_core::connector::NetworkProtocol::Http
+        | ironposh_client_core::connector::NetworkProtocol::Https => send_http_packet(packet, tls),
+    }
+}
+
+/// Sends a packet to an MS-KKDCP KDC proxy. sspi has already wrapped the
+/// Kerberos message in a `KDC-PROXY-MESSAGE`; the body is posted as-is.
+#[instrument(name = "kerberos.http", level = "info", skip(packet, tls), err)]
+fn send_http_packet(packet: NetworkRequest, tls: &TlsOptions) -> Result<Vec<u8>, anyhow::Error> {
+    let mut connector = native_tls::TlsConnector::builder();
+    connector
+        .danger_accept_invalid_certs(tls.accept_invalid_certs)
+        .danger_accept_invalid_hostnames(tls.accept_invalid_hostnames);
+    if let Some(pem) = &tls.extra_ca_pem {
+        let cert = native_tls::Certificate::from_pem(pem)
+            .context("failed to parse extra CA certificate")?;
+        connector.add_root_certificate(cert);
+    }
+    let agent = ureq::AgentBuilder::new()
+        .tls_connector(std::sync::Arc::new(
+            connector.build().context("failed to build TLS connector")?,
+        ))
+        .build();
+
+    // The proxy URL may embed an access token (`/jet/KdcProxy/{token}`), so
+    // errors are reported without it.
+    let response = match agent
+        .post(packet.url.as_str())
+        .set("Content-Type", "application/kerberos")
+        .send_bytes(&packet.data)
+    {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => {
+            anyhow::bail!("KDC HTTP request failed with status {status}")
         }
-        ironposh_client_core::connector::NetworkProtocol::Https => {
-            todo!("HTTPS protocol not implemented for Kerberos")
+        Err(ureq::Error::Transport(transport)) => {
+            anyhow::bail!("failed to send KDC HTTP request: {}", transport.kind())
         }
+    };
+
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_KDC_RESPONSE + 1)
+        .read_to_end(&mut body)
+        .context("failed to read KDC HTTP response body")?;
+    if body.len() as u64 > MAX_KDC_RESPONSE {
+        anyhow::bail!("KDC HTTP response exceeds maximum {MAX_KDC_RESPONSE} bytes");
     }
+
+    info!(response_len = body.len(), "received HTTP response from KDC");
+
+    Ok(body)
+}
+
+/// Sends a packet via UDP to the Kerberos KDC. The reply is returned with a
+/// 4-byte length prefix, the framing sspi expects for every transport.
+#[instrument(
+    name = "kerberos.udp",
+    level = "info",
+    skip(packet),
+    fields(host = packet.url.host_str(), port = packet.url.port()),
+    err
+)]
+fn send_udp_packet(packet: NetworkRequest) -> Result<Vec<u8>, anyhow::Error> {
+    let host = packet
+        .url
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing host in URL"))?;
+    let port = packet
+        .url
+        .port()
+        .ok_or_else(|| anyhow::anyhow!("Missing port in URL"))?;
+
+    let remote_addr = std::net::ToSocketAddrs::to_socket_addrs(&(host, port))
+        .context("failed to resolve KDC UDP address")?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("KDC UDP address resolved to no endpoints"))?;
+
+    info!(%remote_addr, "sending UDP packet to KDC");
+
+    let socket = UdpSocket::bind(unspecified_addr(remote_addr.ip()))
+        .context("failed to bind UDP socket for KDC request")?;
+    socket.set_read_timeout(Some(KDC_UDP_TIMEOUT))?;
+    socket
+        .connect(remote_addr)
+        .context("failed to connect UDP socket to KDC")?;
+    let bytes_sent = socket
+        .send(&packet.data)
+        .context("failed to send UDP packet to KDC")?;
+    if bytes_sent != packet.data.len() {
+        anyhow::bail!(
+            "failed to send full KDC UDP packet: sent {bytes_sent} of {} bytes",
+            packet.data.len()
+        );
+    }
+
+    let mut response_data = vec![0u8; MAX_UDP_RESPONSE];
+    let datagram_len = socket
+        .recv(&mut response_data)
+        .context("failed to read UDP response from KDC")?;
+
+    info!(datagram_len, "received UDP response from KDC");
+
+    let datagram_len_u32 =
+        u32::try_from(datagram_len).context("KDC UDP response length does not fit in u32")?;
+    let mut framed_response = Vec::with_capacity(datagram_len + 4);
+    framed_response.extend_from_slice(&datagram_len_u32.to_be_bytes());
+    framed_response.extend_from_slice(&response_data[..datagram_len]);
+
+    Ok(framed_response)
 }
 
 /// Sends a packet via TCP to the Kerberos KDC
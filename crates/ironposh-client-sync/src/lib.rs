@@ -0,0 +1,23 @@
+//! Library target with the blocking [`PowerShellSession`] facade, for callers
+//! that want script results without driving the sans-io event loop
+//! themselves. The binary in `main.rs` compiles the same modules directly.
+//!
+//! ```no_run
+//! # fn example(config: ironposh_client_sync::WinRmConfig) -> anyhow::Result<()> {
+//! let mut session = ironposh_client_sync::PowerShellSession::connect(config)?;
+//! for value in session.run("Get-Date")? {
+//!     println!("{value}");
+//! }
+//! session.close()?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod connection;
+pub mod http_client;
+pub mod kerberos;
+pub mod network;
+mod session;
+
+pub use ironposh_client_core::connector::WinRmConfig;
+pub use session::{PowerShellSession, ScriptOutput};
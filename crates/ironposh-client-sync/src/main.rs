@@ -1,5 +1,6 @@
 mod config;
 mod connection;
+mod crash;
 mod http_client;
 mod kerberos;
 mod network;
@@ -12,6 +13,9 @@ use ironposh_client_core::connector::connection_pool::TrySend;
 use ironposh_client_core::connector::http::HttpResponseTargeted;
 use ironposh_client_core::connector::ActiveSessionOutput;
 use ironposh_client_core::host::HostCall;
+use ironposh_client_core::pipeline::PipelineOptions;
+use ironposh_client_core::user_message::{EnglishCatalog, UserMessage};
+use ironposh_client_core::PwshCoreError;
 use ironposh_terminal::{Terminal, TerminalOp};
 use std::sync::mpsc;
 use std::thread;
@@ -44,7 +48,7 @@ fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
     // Initialize logging. If it fails, we can't log, so just print and exit.
-    if let Err(e) = init_logging(args.verbose) {
+    if let Err(e) = init_logging(&args) {
         eprintln!("Failed to initialize logging: {e}");
         // Exit with a non-zero status code to indicate failure
         std::process::exit(1);
@@ -54,6 +58,9 @@ fn main() -> anyhow::Result<()> {
     if let Err(e) = run_app(&args) {
         // Log the error before exiting. This is crucial.
         error!("Application failed to run: {:?}", e);
+        if let Some(message) = UserMessage::find(e.as_ref()) {
+            eprintln!("{}", message.render(&EnglishCatalog));
+        }
 
         // The program will now exit, and the log buffer should be flushed upon exit.
         return Err(e);
@@ -82,8 +89,11 @@ fn run_app(args: &Args) -> anyhow::Result<()> {
 
     // Create configuration and establish connection with real terminal dimensions
     let config = create_connector_config(args, cols, rows)?;
-    let (active_session, next_request, http_client) = establish_connection(config)?;
+    let (mut active_session, next_request, http_client) = establish_connection(config)?;
     info!("Runspace pool is now open and ready for operations!");
+    // Every request runs on its own thread and connection: keep the long-poll
+    // on the first one and user input off it.
+    active_session.dedicate_receive_connection(next_request.get_connection_id());
 
     // Set up communication channels
     let (network_request_tx, network_request_rx) = mpsc::channel();
@@ -91,6 +101,10 @@ fn run_app(args: &Args) -> anyhow::Result<()> {
     let (user_request_tx, user_request_rx) = mpsc::channel();
     let (ui_tx, ui_rx) = mpsc::channel::<UIInputEvent>();
 
+    // From here on a panic restores the terminal and deletes the shell before
+    // the process exits. The event loop runs on this thread.
+    crash::install_panic_hook(user_request_tx.clone());
+
     // Spawn network handler
     let network_handler = NetworkHandler::new(network_request_rx, network_response_tx, http_client);
     let network_handle = thread::spawn(move || {
@@ -98,7 +112,11 @@ fn run_app(args: &Args) -> anyhow::Result<()> {
     });
 
     // Spawn user input/UI handler (now takes unified_rx)
-    let user_input_handler = UIHanlder::new(user_request_tx, ui_rx);
+    let pipeline_options = PipelineOptions {
+        timeout: args.pipeline_timeout.map(std::time::Duration::from_secs),
+        stop_on_error: false,
+    };
+    let user_input_handler = UIHanlder::new(user_request_tx, ui_rx, pipeline_options);
     let user_handle = thread::spawn(move || {
         let _ = user_input_handler
             .run(terminal)
@@ -316,7 +334,35 @@ fn run_event_loop(
                 ActiveSessionOutput::OperationSuccess => {
                     info!(target: "session", "operation completed successfully");
                 }
+                ActiveSessionOutput::OperationQueued { operation_type }
+                | ActiveSessionOutput::OperationDispatched { operation_type } => {
+                    info!(target: "session", operation_type, "queued operation update");
+                }
+                ActiveSessionOutput::Interrupted {
+                    interruption,
+                    retry,
+                } => {
+                    warn!(target: "session", ?interruption, retrying = retry.is_some(), "request interrupted by the server");
+                    if let Some((delay, request)) = retry {
+                        let network_request_tx = network_request_tx.clone();
+                        thread::spawn(move || {
+                            thread::sleep(delay);
+                            let _ = network_request_tx.send(request);
+                        });
+                    }
+                }
+                ActiveSessionOutput::ProtocolWarning { stream, error, .. } => {
+                    warn!(target: "session", %stream, %error, "skipped malformed server data");
+                }
+            }
+        }
+
+        if let Some(reason) = active_session.broken_reason() {
+            error!(target: "session", %reason, "runspace pool broken, ending session");
+            return Err(PwshCoreError::RunspacePoolBroken {
+                reason: reason.to_owned(),
             }
+            .into());
         }
     }
 }
@@ -0,0 +1,313 @@
+//! Blocking facade for callers that only want the results of a script.
+//!
+//! `PowerShellSession` drives the same sans-io `ActiveSession` as the binary,
+//! with the network handler on its own thread, and runs one script at a time
+//! to completion.
+
+use anyhow::Context;
+use ironposh_client_core::connector::active_session::{ActiveSession, UserEvent};
+use ironposh_client_core::connector::connection_pool::TrySend;
+use ironposh_client_core::connector::http::HttpResponseTargeted;
+use ironposh_client_core::connector::{ActiveSessionOutput, UserOperation, WinRmConfig};
+use ironposh_client_core::host::{HostCall, Submission};
+use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+use ironposh_client_core::runspace_pool::RunspacePoolState;
+use ironposh_client_core::PwshCoreError;
+use ironposh_psrp::{ErrorRecord, PsValue};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::connection::RemotePowershell;
+use crate::http_client::UreqHttpClient;
+use crate::network::NetworkHandler;
+
+/// What one script wrote to its output and error streams.
+#[derive(Debug, Default)]
+pub struct ScriptOutput {
+    pub output: Vec<PsValue>,
+    pub errors: Vec<ErrorRecord>,
+}
+
+/// How long closing the session waits for the server to answer the Delete.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connected runspace pool that runs scripts and blocks until they finish.
+///
+/// There is no interactive host: host calls that need an answer (ReadLine,
+/// Prompt, credentials, choices) are cancelled, so the script sees them fail.
+/// Output written through the host (Write-Host, progress) is dropped.
+///
+/// Dropping the session deletes the remote shell like [`Self::close`], but
+/// can only log a failure.
+pub struct PowerShellSession {
+    active_session: ActiveSession,
+    network_request_tx: mpsc::Sender<TrySend>,
+    network_response_rx: mpsc::Receiver<HttpResponseTargeted>,
+    closed: bool,
+}
+
+impl PowerShellSession {
+    /// Open the runspace pool described by `config` and start the network
+    /// thread.
+    pub fn connect(config: WinRmConfig) -> anyhow::Result<Self> {
+        let http_client = UreqHttpClient::new();
+        let (mut active_session, next_request) =
+            RemotePowershell::open(config, &http_client)?.into_components();
+        info!("runspace pool is open");
+        // Every request runs on its own thread and connection: keep the
+        // long-poll on the first one and pipeline requests off it.
+        active_session.dedicate_receive_connection(next_request.get_connection_id());
+
+        let (network_request_tx, network_request_rx) = mpsc::channel();
+        let (network_response_tx, network_response_rx) = mpsc::channel();
+        let network_handler =
+            NetworkHandler::new(network_request_rx, network_response_tx, http_client);
+        thread::spawn(move || network_handler.run());
+
+        network_request_tx
+            .send(next_request)
+            .context("Failed to send initial request")?;
+
+        Ok(Self {
+            active_session,
+            network_request_tx,
+            network_response_rx,
+            closed: false,
+        })
+    }
+
+    /// Delete the remote shell and wait, at most [`CLOSE_TIMEOUT`], for the
+    /// server to confirm it.
+    pub fn close(mut self) -> anyhow::Result<()> {
+        self.shutdown()
+    }
+
+    /// Run `script` and return its output objects. Fails if the script wrote
+    /// any error record; use [`Self::invoke`] to get output and errors both.
+    pub fn run(&mut self, script: &str) -> anyhow::Result<Vec<PsValue>> {
+        let ScriptOutput { output, errors } = self.invoke(script)?;
+        if errors.is_empty() {
+            return Ok(output);
+        }
+        let messages: Vec<String> = errors.iter().map(ErrorRecord::render_concise).collect();
+        anyhow::bail!("{}", messages.join("\n"))
+    }
+
+    /// Run `script` and collect its output objects and error records, without
+    /// `Out-String` formatting.
+    pub fn invoke(&mut self, script: &str) -> anyhow::Result<ScriptOutput> {
+        let uuid = uuid::Uuid::new_v4();
+        let spec = PipelineSpec {
+            commands: vec![PipelineCommand::new_script(script.to_owned())],
+            add_to_history: false,
+        };
+        info!(pipeline_uuid = %uuid, "invoking pipeline");
+
+        let mut result = ScriptOutput::default();
+        let first = self
+            .active_session
+            .accept_client_operation(UserOperation::InvokeWithSpec { uuid, spec })
+            .context("Failed to invoke pipeline")?;
+        if self.handle(first, uuid, &mut result)? {
+            return Ok(result);
+        }
+
+        loop {
+            let response = self
+                .network_response_rx
+                .recv()
+                .context("Network response channel disconnected")?;
+            let steps = self
+                .active_session
+                .accept_server_response(response)
+                .context("Failed to accept server response")?;
+            let mut finished = false;
+            for step in steps {
+                finished |= self.handle(step, uuid, &mut result)?;
+            }
+            if let Some(reason) = self.active_session.broken_reason() {
+                return Err(PwshCoreError::RunspacePoolBroken {
+                    reason: reason.to_owned(),
+                }
+                .into());
+            }
+            if finished {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Act on one session output. `true` once pipeline `uuid` has finished.
+    fn handle(
+        &mut self,
+        step: ActiveSessionOutput,
+        uuid: uuid::Uuid,
+        result: &mut ScriptOutput,
+    ) -> anyhow::Result<bool> {
+        match step {
+            ActiveSessionOutput::SendBack(requests) => {
+                for request in requests {
+                    self.send(request)?;
+                }
+            }
+            ActiveSessionOutput::SendAndThenReceive {
+                send_request,
+                then_receive_streams,
+            } => {
+                self.send(send_request)?;
+                let recv = self
+                    .active_session
+                    .fire_receive(then_receive_streams, None)
+                    .context("Failed to build receive after send-then-receive")?;
+                self.send(recv)?;
+            }
+            ActiveSessionOutput::PendingReceive { desired_streams } => {
+                let recv = self
+                    .active_session
+                    .fire_receive(desired_streams, None)
+                    .context("Failed to build deferred receive")?;
+                self.send(recv)?;
+            }
+            ActiveSessionOutput::SendBackError(e) => {
+                return Err(anyhow::anyhow!("Session step failed: {e}"));
+            }
+            ActiveSessionOutput::UserEvent(event) if event.pipeline_id() == uuid => match event {
+                UserEvent::PipelineOutput { output, .. } => result.output.push(output.data),
+                UserEvent::ErrorRecord { error_record, .. } => result.errors.push(error_record),
+                UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => {
+                    return Ok(true);
+                }
+                UserEvent::PipelineCreated { .. } | UserEvent::PipelineRecord { .. } => {}
+            },
+            ActiveSessionOutput::HostCall(host_call) => {
+                let operation = Self::answer_host_call(&host_call);
+                let step = self
+                    .active_session
+                    .accept_client_operation(operation)
+                    .context("Failed to send host call response to active session")?;
+                return self.handle(step, uuid, result);
+            }
+            ActiveSessionOutput::Interrupted {
+                interruption,
+                retry,
+            } => {
+                warn!(
+                    ?interruption,
+                    retrying = retry.is_some(),
+                    "request interrupted by the server"
+                );
+                if let Some((delay, request)) = retry {
+                    let network_request_tx = self.network_request_tx.clone();
+                    thread::spawn(move || {
+                        thread::sleep(delay);
+                        let _ = network_request_tx.send(request);
+                    });
+                }
+            }
+            ActiveSessionOutput::ProtocolWarning { stream, error, .. } => {
+                warn!(%stream, %error, "skipped malformed server data");
+            }
+            ActiveSessionOutput::UserEvent(_)
+            | ActiveSessionOutput::Ignore
+            | ActiveSessionOutput::OperationSuccess
+            | ActiveSessionOutput::OperationQueued { .. }
+            | ActiveSessionOutput::OperationDispatched { .. } => {}
+        }
+        Ok(false)
+    }
+
+    /// Calls without a return value are acknowledged; the rest are cancelled
+    /// since there is nobody to answer them.
+    fn answer_host_call(host_call: &HostCall) -> UserOperation {
+        let (scope, call_id) = (host_call.scope(), host_call.call_id());
+        debug!(
+            host_call = host_call.method_name(),
+            call_id, "answering host call"
+        );
+        if host_call.should_send_response() {
+            UserOperation::CancelHostCall {
+                scope,
+                call_id,
+                method: host_call.method(),
+                reason: Some(format!(
+                    "{} needs an interactive host",
+                    host_call.method_name()
+                )),
+            }
+        } else {
+            UserOperation::SubmitHostResponse {
+                submission: Submission::NoSend,
+                scope,
+                call_id,
+            }
+        }
+    }
+
+    /// Send the Delete and pump responses until the pool is closed. Runs
+    /// once: a second call, or the drop after [`Self::close`], does nothing.
+    fn shutdown(&mut self) -> anyhow::Result<()> {
+        if std::mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        if self.active_session.broken_reason().is_some() {
+            // The server already tore the pool down; there is nothing to delete.
+            return Ok(());
+        }
+        info!("closing runspace pool");
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        let step = self
+            .active_session
+            .accept_client_operation(UserOperation::Close)
+            .context("Failed to close runspace pool")?;
+        if matches!(step, ActiveSessionOutput::Ignore) {
+            // The pool is not open (already closing or closed).
+            return Ok(());
+        }
+        let mut steps = vec![step];
+        loop {
+            for step in steps {
+                if let ActiveSessionOutput::SendBack(requests) = step {
+                    for request in requests {
+                        self.send(request)?;
+                    }
+                }
+            }
+            if self.active_session.runspace_pool_state() == RunspacePoolState::Closed {
+                info!("runspace pool is closed");
+                return Ok(());
+            }
+            let response = match self
+                .network_response_rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(response) => response,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    anyhow::bail!("server did not confirm the Delete within {CLOSE_TIMEOUT:?}")
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    anyhow::bail!("Network response channel disconnected")
+                }
+            };
+            steps = self
+                .active_session
+                .accept_server_response(response)
+                .context("Failed to accept server response while closing")?;
+        }
+    }
+
+    fn send(&self, request: TrySend) -> anyhow::Result<()> {
+        self.network_request_tx
+            .send(request)
+            .context("Failed to send HTTP request")
+    }
+}
+
+impl Drop for PowerShellSession {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            warn!(error = %e, "failed to delete the remote shell");
+        }
+    }
+}
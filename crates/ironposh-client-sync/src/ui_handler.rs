@@ -1,12 +1,13 @@
 use anyhow::Context;
 use ironposh_client_core::connector::active_session::{self};
 use ironposh_client_core::connector::UserOperation;
-use ironposh_client_core::pipeline::{PipelineCommand, PipelineSpec};
+use ironposh_client_core::pipeline::{PipelineCommand, PipelineOptions, PipelineSpec};
 use ironposh_client_core::powershell::PipelineHandle;
 use ironposh_terminal::{ReadOutcome, Terminal};
 use std::io::Write;
 use std::sync::mpsc;
-use tracing::{debug, info};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
 use crate::types::{UIInputEvent, UiOp};
 
@@ -14,16 +15,19 @@ use crate::types::{UIInputEvent, UiOp};
 pub struct UIHanlder {
     user_request_tx: mpsc::Sender<UserOperation>,
     unified_rx: mpsc::Receiver<UIInputEvent>,
+    pipeline_options: PipelineOptions,
 }
 
 impl UIHanlder {
     pub fn new(
         user_request_tx: mpsc::Sender<UserOperation>,
         unified_rx: mpsc::Receiver<UIInputEvent>,
+        pipeline_options: PipelineOptions,
     ) -> Self {
         Self {
             user_request_tx,
             unified_rx,
+            pipeline_options,
         }
     }
 
@@ -31,6 +35,8 @@ impl UIHanlder {
     pub fn run(&self, mut terminal: Terminal) -> anyhow::Result<()> {
         let mut io = terminal.stdio(); // stdio-like wrapper
         let mut current_pipeline: Option<PipelineHandle> = None;
+        // Pipeline being timed, with its deadline (see `PipelineOptions::timeout`).
+        let mut deadline: Option<(uuid::Uuid, Instant)> = None;
 
         let _ui_loop_span = tracing::span!(tracing::Level::INFO, "ui_loop").entered();
         info!("Starting UI loop");
@@ -55,6 +61,7 @@ impl UIHanlder {
                             PipelineCommand::new_script(command.to_string()),
                             PipelineCommand::new_output_stream(),
                         ],
+                        add_to_history: true,
                     };
 
                     let uuid = uuid::Uuid::new_v4();
@@ -63,11 +70,19 @@ impl UIHanlder {
                         .send(UserOperation::InvokeWithSpec { uuid, spec })
                         .context("Failed to send invoke with spec operation")?;
                     debug!("Pipeline request sent successfully");
+                    deadline = self
+                        .pipeline_options
+                        .timeout
+                        .map(|timeout| (uuid, Instant::now() + timeout));
                 }
                 ReadOutcome::Interrupt => {
                     debug!("Interrupt received, reprompting");
                     continue; // reprompt (like shells)
                 }
+                ReadOutcome::Detach => {
+                    writeln!(io, "detaching is not supported by this client")?;
+                    continue;
+                }
                 ReadOutcome::Eof => {
                     info!("EOF received, terminating UI loop");
                     break Ok(());
@@ -75,11 +90,45 @@ impl UIHanlder {
             }
 
             debug!("Entering event receive loop");
-            'receive: while let Ok(event) = self.unified_rx.recv() {
+            'receive: loop {
+                let received = match deadline {
+                    Some((_, at)) => self
+                        .unified_rx
+                        .recv_timeout(at.saturating_duration_since(Instant::now())),
+                    None => self
+                        .unified_rx
+                        .recv()
+                        .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                };
+                let event = match received {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        // Stop it and keep draining until its PipelineFinished,
+                        // like an interrupt.
+                        let Some((pipeline_id, _)) = deadline.take() else {
+                            continue 'receive;
+                        };
+                        warn!(%pipeline_id, "pipeline timed out, stopping it");
+                        let _ = writeln!(
+                            io,
+                            "Pipeline timed out after {}s, stopping it",
+                            self.pipeline_options.timeout.unwrap_or_default().as_secs()
+                        );
+                        let _ = io.render(); // best-effort
+                        self.user_request_tx
+                            .send(UserOperation::KillPipeline {
+                                pipeline: PipelineHandle::new(pipeline_id),
+                            })
+                            .context("Failed to send timeout stop operation")?;
+                        continue 'receive;
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break 'receive,
+                };
                 debug!("Received UI event");
                 let read_outcome = io.try_read_line()?;
                 if matches!(read_outcome, Some(ReadOutcome::Interrupt)) {
                     debug!("Interrupt detected during event processing");
+                    deadline = None;
                     let Some(pipeline) = current_pipeline.take() else {
                         debug!("No active pipeline to interrupt, continuing");
                         continue 'ui;
@@ -119,16 +168,15 @@ impl UIHanlder {
                                 info!(pipeline_id = %pipeline.id(), "Pipeline created, setting as current");
                                 current_pipeline = Some(pipeline);
                             }
-                            active_session::UserEvent::PipelineFinished { pipeline: _ } => {
+                            active_session::UserEvent::PipelineFinished { .. }
+                            | active_session::UserEvent::PipelineTimedOut { .. } => {
                                 info!("Pipeline finished, clearing current pipeline");
                                 current_pipeline = None;
+                                deadline = None;
                                 debug!("Returning to UI input loop");
                                 continue 'ui;
                             }
-                            active_session::UserEvent::PipelineOutput {
-                                output,
-                                pipeline: _,
-                            } => {
+                            active_session::UserEvent::PipelineOutput { output, .. } => {
                                 debug!("Received pipeline output");
                                 match output.format_as_displyable_string() {
                                     Ok(o) => {
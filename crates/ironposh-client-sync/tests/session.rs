@@ -0,0 +1,96 @@
+//! The blocking `PowerShellSession` facade against the in-process mock
+//! WinRM/PSRP server.
+
+use ironposh_client_core::connector::http::ServerAddress;
+use ironposh_client_sync::PowerShellSession;
+use ironposh_psrp::{PipelineHostCall, PsPrimitiveValue, PsValue, RemoteHostMethodId};
+use ironposh_test_server::{CannedPipeline, MockAuth, MockServer, RunningServer};
+use ironposh_test_support::fake_server::test_config;
+
+fn start(runtime: &tokio::runtime::Runtime, pipelines: Vec<CannedPipeline>) -> RunningServer {
+    runtime
+        .block_on(
+            MockServer::builder()
+                .auth(MockAuth::basic("user", "pass"))
+                .pipelines(pipelines)
+                .build()
+                .start(),
+        )
+        .expect("start mock server")
+}
+
+fn connect(server: &RunningServer) -> PowerShellSession {
+    let mut config = test_config();
+    config.server = (
+        ServerAddress::parse("127.0.0.1").expect("loopback address"),
+        server.addr().port(),
+    );
+    PowerShellSession::connect(config).expect("connect")
+}
+
+fn deletes(server: &RunningServer) -> usize {
+    server
+        .requests()
+        .iter()
+        .filter(|request| request.body.contains("/transfer/Delete<"))
+        .count()
+}
+
+#[test]
+fn run_collects_output_and_answers_host_calls() {
+    let write_line = PipelineHostCall::builder()
+        .call_id(1)
+        .method(RemoteHostMethodId::WriteLine2)
+        .parameters(vec![PsValue::Primitive(PsPrimitiveValue::Str(
+            "from the host".to_owned(),
+        ))])
+        .build();
+    let read_line = PipelineHostCall::builder()
+        .call_id(2)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let server = start(
+        &runtime,
+        vec![CannedPipeline::new()
+            .host_call(write_line, false)
+            .string_output("hello")
+            .host_call(read_line, true)
+            .string_output("world")],
+    );
+
+    let mut session = connect(&server);
+    let output = session.run("Write-Output hello, world").expect("run");
+
+    let strings: Vec<&str> = output
+        .iter()
+        .map(|value| match value {
+            PsValue::Primitive(PsPrimitiveValue::Str(s)) => s.as_str(),
+            other => panic!("unexpected output {other:?}"),
+        })
+        .collect();
+    assert_eq!(strings, ["hello", "world"]);
+}
+
+#[test]
+fn close_deletes_the_shell() {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let server = start(&runtime, vec![CannedPipeline::new().string_output("hi")]);
+
+    let mut session = connect(&server);
+    session.run("Write-Output hi").expect("run");
+    session.close().expect("close");
+
+    assert_eq!(deletes(&server), 1, "close sends one Delete, drop none");
+}
+
+#[test]
+fn dropping_the_session_deletes_the_shell() {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let server = start(&runtime, Vec::new());
+
+    drop(connect(&server));
+
+    assert_eq!(deletes(&server), 1);
+}
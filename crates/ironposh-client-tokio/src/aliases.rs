@@ -0,0 +1,201 @@
+//! Client-side aliases: `ll` → `Get-ChildItem -Force | Format-Table`,
+//! expanded locally before a command is sent. Defined in the `[aliases]`
+//! section of the profile or with `%alias` in the REPL.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alias {
+    /// As typed when defined; lookups ignore case.
+    name: String,
+    expansion: String,
+}
+
+/// Aliases by lower-cased name.
+#[derive(Debug, Default)]
+pub struct AliasTable {
+    entries: BTreeMap<String, Alias>,
+}
+
+impl AliasTable {
+    /// Define (or redefine) `name`.
+    pub fn set(&mut self, name: &str, expansion: &str) -> Result<(), String> {
+        validate_name(name)?;
+        if expansion.trim().is_empty() {
+            return Err(format!("alias '{name}' needs an expansion"));
+        }
+        self.entries.insert(
+            name.to_lowercase(),
+            Alias {
+                name: name.to_owned(),
+                expansion: expansion.trim().to_owned(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Define an alias from a `name = expansion` line.
+    pub fn define(&mut self, definition: &str) -> Result<(), String> {
+        let (name, expansion) = definition
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'name = expansion', got '{definition}'"))?;
+        self.set(name.trim(), expansion)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(&name.to_lowercase()).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .get(&name.to_lowercase())
+            .map(|alias| alias.expansion.as_str())
+    }
+
+    /// Replace a leading alias with its expansion, keeping the arguments
+    /// after it. Expansion is not recursive, so an alias may wrap the
+    /// command it is named after.
+    pub fn expand(&self, cmd: &str) -> Option<String> {
+        let end = cmd.find(char::is_whitespace).unwrap_or(cmd.len());
+        let (first, rest) = cmd.split_at(end);
+        let expansion = self.get(first)?;
+        Some(format!("{expansion}{rest}"))
+    }
+
+    /// Run a `%alias` / `%unalias` line and return the text to print, or
+    /// `None` when `line` is not an alias command.
+    ///
+    /// - `%alias` lists every alias, `%alias ll` shows one
+    /// - `%alias ll = Get-ChildItem -Force` defines one for this session
+    /// - `%unalias ll` removes one
+    pub fn run_command(&mut self, line: &str) -> Option<String> {
+        if let Some(args) = strip_command(line, "%unalias") {
+            return Some(if args.is_empty() {
+                "usage: %unalias <name>".to_owned()
+            } else if self.remove(args) {
+                format!("removed alias {args}")
+            } else {
+                format!("alias '{args}' is not defined")
+            });
+        }
+
+        let args = strip_command(line, "%alias")?;
+        Some(if args.is_empty() {
+            self.listing()
+        } else if args.contains('=') {
+            match self.define(args) {
+                Ok(()) => self.describe(args.split('=').next().unwrap_or_default().trim()),
+                Err(e) => e,
+            }
+        } else {
+            self.describe(args)
+        })
+    }
+
+    fn describe(&self, name: &str) -> String {
+        self.entries.get(&name.to_lowercase()).map_or_else(
+            || format!("alias '{name}' is not defined"),
+            |alias| format!("{} = {}", alias.name, alias.expansion),
+        )
+    }
+
+    fn listing(&self) -> String {
+        if self.entries.is_empty() {
+            return "no aliases defined".to_owned();
+        }
+        let width = self
+            .entries
+            .values()
+            .map(|alias| alias.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut out = String::new();
+        for alias in self.entries.values() {
+            let _ = writeln!(out, "{:width$} = {}", alias.name, alias.expansion);
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("invalid alias name '{name}'"));
+    }
+    if name.starts_with(['%', ':']) {
+        return Err(format!(
+            "alias '{name}' would shadow a client command (names starting with % or :)"
+        ));
+    }
+    Ok(())
+}
+
+/// Strip a leading command word (any case) and return its trimmed arguments.
+pub fn strip_command<'a>(line: &'a str, command: &str) -> Option<&'a str> {
+    let rest = line.get(command.len()..)?;
+    let matches = line[..command.len()].eq_ignore_ascii_case(command)
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace));
+    matches.then(|| rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> AliasTable {
+        let mut table = AliasTable::default();
+        table
+            .define("ll = Get-ChildItem -Force | Format-Table")
+            .unwrap();
+        table
+    }
+
+    #[test]
+    fn leading_alias_expands_with_its_arguments() {
+        let table = table();
+        assert_eq!(
+            table.expand("LL C:\\Temp").as_deref(),
+            Some("Get-ChildItem -Force | Format-Table C:\\Temp")
+        );
+        assert_eq!(
+            table.expand("ll").as_deref(),
+            Some("Get-ChildItem -Force | Format-Table")
+        );
+        assert_eq!(table.expand("Get-Item ll"), None);
+        assert_eq!(table.expand("lll"), None);
+    }
+
+    #[test]
+    fn alias_commands_manage_the_table() {
+        let mut table = table();
+        assert_eq!(
+            table.run_command("%alias gs = Get-Service").as_deref(),
+            Some("gs = Get-Service")
+        );
+        assert_eq!(table.get("GS"), Some("Get-Service"));
+        assert_eq!(
+            table.run_command("%alias").as_deref(),
+            Some("gs = Get-Service\nll = Get-ChildItem -Force | Format-Table")
+        );
+        assert_eq!(
+            table.run_command("%unalias gs").as_deref(),
+            Some("removed alias gs")
+        );
+        assert_eq!(
+            table.run_command("%alias gs").as_deref(),
+            Some("alias 'gs' is not defined")
+        );
+        assert_eq!(table.run_command("%aliases"), None);
+        assert_eq!(table.run_command("Get-Alias"), None);
+    }
+
+    #[test]
+    fn bad_definitions_are_rejected() {
+        let mut table = AliasTable::default();
+        assert!(table.define("no equals sign").is_err());
+        assert!(table.define("two words = x").is_err());
+        assert!(table.define("%alias = x").is_err());
+        assert!(table.define("empty =  ").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! `--audit-log`: the session's audit trail as JSON lines appended to a file.
+
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use ironposh_async::{AuditEntry, AuditSink, AuditStatus};
+use tracing::warn;
+
+/// Appends one JSON object per audited pipeline. The file is opened in append
+/// mode and never truncated, so earlier sessions' entries are kept.
+pub struct FileAuditLog {
+    file: Mutex<std::fs::File>,
+}
+
+impl FileAuditLog {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for FileAuditLog {
+    fn append(&self, entry: AuditEntry) {
+        let line = entry_to_json(&entry).to_string();
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Err(e) = writeln!(file, "{line}").and_then(|()| file.flush()) {
+            warn!(error = %e, pipeline_id = %entry.pipeline_id, "failed to append audit log entry");
+        }
+    }
+}
+
+fn entry_to_json(entry: &AuditEntry) -> serde_json::Value {
+    let timestamp_ms = entry
+        .timestamp
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let (status, reason) = match &entry.status {
+        AuditStatus::Completed => ("completed", None),
+        AuditStatus::Failed => ("failed", None),
+        AuditStatus::TimedOut => ("timed_out", None),
+        AuditStatus::Denied { reason } => ("denied", Some(reason.as_str())),
+    };
+    serde_json::json!({
+        "timestamp_ms": timestamp_ms,
+        "user": entry.user,
+        "target": entry.target,
+        "pipeline_id": entry.pipeline_id.to_string(),
+        "command": entry.command,
+        "status": status,
+        "reason": reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_appended_as_json_lines() {
+        let path =
+            std::env::temp_dir().join(format!("ironposh-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let entry = |status| AuditEntry {
+            timestamp: std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1),
+            user: Some("alice".to_owned()),
+            target: "server:5985".to_owned(),
+            pipeline_id: uuid::Uuid::nil(),
+            command: "Get-Date".to_owned(),
+            status,
+        };
+
+        FileAuditLog::open(&path)
+            .expect("open audit log")
+            .append(entry(AuditStatus::Completed));
+        // Reopening appends instead of truncating.
+        FileAuditLog::open(&path)
+            .expect("reopen audit log")
+            .append(entry(AuditStatus::Denied {
+                reason: "not approved".to_owned(),
+            }));
+
+        let contents = std::fs::read_to_string(&path).expect("read audit log");
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON line"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["status"], "completed");
+        assert_eq!(lines[0]["timestamp_ms"], 1000);
+        assert_eq!(lines[0]["user"], "alice");
+        assert_eq!(lines[1]["status"], "denied");
+        assert_eq!(lines[1]["reason"], "not approved");
+    }
+}
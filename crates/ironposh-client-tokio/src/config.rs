@@ -1,20 +1,26 @@
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use ironposh_client_core::{
     connector::{
-        config::{KerberosConfig, TlsOptions},
-        http::ServerAddress,
+        config::{
+            AffinityConfig, Delegation, HostMode, KdcEndpoint, KerberosConfig, ParseMode,
+            ReceiveFlowConfig, ServicePrincipal, TlsOptions,
+        },
+        http::{AddressPreference, ServerAddress},
         WinRmConfig,
     },
-    credentials::{ClientAuthIdentity, ClientUserName},
-    AuthenticatorConfig, SspiAuthConfig, TransportSecurity,
+    credentials::{ClientAuthIdentity, ClientUserName, SMART_CARD_NEEDS_KERBEROS},
+    AuthenticatorConfig, PwshCoreError, SspiAuthConfig, TransportSecurity,
 };
+use ironposh_logging::{LogArgs, LogSettings, Redactions};
 use ironposh_psrp::{
     host_default_data::{HostDefaultData, Size},
-    HostInfo,
+    HostInfo, TimeZone,
 };
+use ironposh_terminal::ClipboardPolicy;
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use tracing_subscriber::{fmt, prelude::*, registry::Registry, EnvFilter};
 use url::Url;
 
 /// PowerShell Remoting Client (Async/Tokio)
@@ -65,6 +71,9 @@ pub struct Args {
     #[arg(short, long, help = "Authentication method", default_value_t = AuthMethod::Basic)]
     pub auth_method: AuthMethod,
 
+    #[command(flatten)]
+    pub smartcard: SmartCardArgs,
+
     /// Use HTTPS instead of HTTP (TLS provides security, SSPI sealing not needed)
     #[arg(long, help = "Use HTTPS (default: HTTP)")]
     pub https: bool,
@@ -77,6 +86,14 @@ pub struct Args {
     )]
     pub http_insecure: bool,
 
+    /// DANGEROUS: Required with `--auth-method none`. Only for listeners behind a
+    /// Devolutions Gateway or reverse proxy that authenticates the user itself.
+    #[arg(
+        long,
+        help = "DANGEROUS: allow --auth-method none (only behind an authenticating gateway)"
+    )]
+    pub allow_no_auth: bool,
+
     /// DANGEROUS: Accept any HTTPS server certificate (self-signed labs).
     /// Only meaningful together with `--https`.
     #[arg(
@@ -93,12 +110,31 @@ pub struct Args {
     pub ca_cert: Option<PathBuf>,
 
     /// Use parallel (multi-connection) session loop instead of the default serial mode.
+    /// Only this loop keeps commands and Ctrl+C off the Receive long-poll's connection.
     #[arg(
         long,
         help = "Use parallel session loop (default: serial/single-connection)"
     )]
     pub parallel: bool,
 
+    /// Cap on Receive requests in flight at once; the rest wait for one to
+    /// complete, leaving connections free for commands and host-call answers.
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Most Receive requests in flight at once (default: unlimited)"
+    )]
+    pub max_concurrent_receives: Option<NonZeroUsize>,
+
+    /// Per-pipeline budget of output received but not yet printed. A pipeline
+    /// over it is not polled until the terminal catches up.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop polling a pipeline while this much of its output is unprinted (default: unlimited)"
+    )]
+    pub max_pending_output_bytes: Option<NonZeroUsize>,
+
     /// Gateway base URL used to mimic the web demo path (for example http://localhost:7272).
     #[arg(long, help = "Use Gateway /jet/fwd/tcp WebSocket transport")]
     pub gateway: Option<String>,
@@ -119,10 +155,68 @@ pub struct Args {
     #[arg(long, help = "Full KDC proxy URL to use for Kerberos/Negotiate")]
     pub kdc_proxy_url: Option<String>,
 
+    /// Delegate Kerberos credentials to the server (second hop). Fails unless the
+    /// server is trusted for delegation.
+    #[arg(
+        long,
+        help = "Delegate Kerberos credentials to the server (second hop)"
+    )]
+    pub delegate: bool,
+
+    /// Service principal to authenticate against instead of `HTTP/<server>`, for
+    /// CNAMEs and load balancers. `{host}` is replaced by the server name.
+    #[arg(
+        long,
+        help = "SPN override, e.g. HTTP/web01.corp.example.com or HTTP/{host}.corp.example.com"
+    )]
+    pub spn: Option<String>,
+
+    /// Connect to this farm node instead of resolving the server name; the
+    /// name is still used for TLS, `Host` and the SPN.
+    #[arg(
+        long,
+        help = "Pin every connection to this server IP (load-balanced farms)"
+    )]
+    pub pin_address: Option<IpAddr>,
+
+    /// Address family to try first when the server name has both A and AAAA records.
+    #[arg(long, help = "Try IPv4 or IPv6 addresses of the server first")]
+    pub prefer_ip: Option<IpFamily>,
+
+    /// List the WinRM endpoints of a domain (`_wsman._tcp` SRV records, probed
+    /// with `Identify`) and exit.
+    #[arg(
+        long,
+        value_name = "DOMAIN",
+        help = "Discover and rank WinRM endpoints of a domain, then exit"
+    )]
+    pub discover: Option<String>,
+
     /// Verbose logging (can be repeated for more verbosity)
     #[arg(short, long, action = clap::ArgAction::Count, help = "Increase logging verbosity")]
     pub verbose: u8,
 
+    #[command(flatten)]
+    pub log: LogArgs,
+
+    /// Save each PSRP fragment or message that fails to decode to a file in
+    /// this directory, to attach to a bug report.
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Save PSRP messages that fail to decode to this directory"
+    )]
+    pub quarantine_dir: Option<PathBuf>,
+
+    /// Skip output and record messages that fail to decode, reporting each
+    /// one, instead of ending the session on the first. Malformed state
+    /// messages still end it.
+    #[arg(
+        long,
+        help = "Skip malformed output/record messages instead of ending the session"
+    )]
+    pub lenient_parsing: bool,
+
     /// PowerShell session configuration (JEA endpoint) name.
     #[arg(
         long,
@@ -130,10 +224,102 @@ pub struct Args {
     )]
     pub configuration_name: Option<String>,
 
+    /// Runspace pool application argument, readable on the server as
+    /// `$PSSenderInfo.ApplicationArguments[NAME]`. Repeatable.
+    #[arg(
+        long = "application-argument",
+        value_name = "NAME=VALUE",
+        value_parser = parse_application_argument,
+        help = "Application argument passed to the session (repeatable)"
+    )]
+    pub application_arguments: Vec<(String, String)>,
+
+    /// Environment variable set in the session (`$env:NAME`) before the
+    /// first command runs. A bare `NAME` forwards the local value. Repeatable.
+    #[arg(
+        long = "env",
+        value_name = "NAME[=VALUE]",
+        value_parser = parse_env_var,
+        help = "Set an environment variable in the session; NAME alone forwards the local one (repeatable)"
+    )]
+    pub env: Vec<(String, String)>,
+
+    /// The time zone the server converts `[DateTime]` values for. Read from
+    /// the system when not given.
+    #[arg(
+        long,
+        value_name = "UTC|+HH:MM",
+        value_parser = parse_time_zone,
+        help = "Time zone to report to the server instead of the local one"
+    )]
+    pub time_zone: Option<TimeZone>,
+
     /// Command to execute (if provided, runs in non-interactive mode)
     #[arg(short = 'c', long, help = "Command to execute")]
     pub command: Option<String>,
 
+    /// Stop the `--command` pipeline if it has not finished after this long;
+    /// output received until then is still printed.
+    #[arg(
+        long,
+        visible_alias = "timeout",
+        value_name = "SECONDS",
+        requires = "command",
+        help = "Stop the command if it runs longer than this many seconds"
+    )]
+    pub command_timeout: Option<u64>,
+
+    /// Stop the `--command` pipeline at its first error and exit with failure,
+    /// like `$ErrorActionPreference = 'Stop'`.
+    #[arg(
+        long,
+        requires = "command",
+        help = "Stop the command at its first error (like $ErrorActionPreference = 'Stop')"
+    )]
+    pub stop_on_error: bool,
+
+    /// Pause interactive output after each screenful, like `| more` (space:
+    /// next page, enter: next line, q: stop the command). `:page <command>`
+    /// flips this for a single command.
+    #[arg(
+        long,
+        conflicts_with = "command",
+        help = "Page interactive command output like | more (toggle per command with :page)"
+    )]
+    pub page: bool,
+
+    /// Show object output of interactive commands in a sortable, filterable
+    /// table (like Out-GridView) instead of printing it. `:grid <command>`
+    /// flips this for a single command.
+    #[arg(
+        long,
+        conflicts_with = "command",
+        help = "Show object output in an interactive table (toggle per command with :grid)"
+    )]
+    pub grid: bool,
+
+    /// Where the interactive prompt comes from. `remote` runs the remote
+    /// `prompt` function like a native remote session, `location` fetches
+    /// only the current directory, and `local` never asks the server.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "remote",
+        conflicts_with = "command",
+        help = "Prompt source: remote prompt function, remote location only, or a fixed local prompt"
+    )]
+    pub prompt: PromptMode,
+
+    /// Local client profile for interactive sessions: an `[aliases]`
+    /// section of `name = expansion` lines expanded before a command is sent,
+    /// and a `[keys]` section with the line editor keymap.
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Client profile with local settings such as [aliases] and [keys]"
+    )]
+    pub profile: Option<PathBuf>,
+
     /// Reattach to an existing disconnected runspace pool shell by ShellId
     /// (printed by `:disconnect`). Requires the parallel session loop.
     #[arg(
@@ -142,6 +328,99 @@ pub struct Args {
         help = "Reattach to a disconnected shell by ShellId (requires --parallel)"
     )]
     pub connect_shell_id: Option<uuid::Uuid>,
+
+    /// Append one JSON line per invoked pipeline (time, user, target, command,
+    /// outcome) to this file.
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append an audit entry per executed command to this file (JSON lines)"
+    )]
+    pub audit_log: Option<PathBuf>,
+
+    /// Also write all pipeline output (and error, warning, verbose, debug
+    /// and information records) to this file while it is rendered. `%tee`
+    /// changes it during an interactive session.
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append a copy of all pipeline output to this file (change with %tee)"
+    )]
+    pub tee: Option<PathBuf>,
+
+    /// Form of the `--tee` file. Defaults to `ndjson` for `.json`, `.jsonl`
+    /// and `.ndjson` paths and `text` otherwise.
+    #[arg(
+        long,
+        value_enum,
+        requires = "tee",
+        help = "Format of the --tee file: text as printed, or one JSON object per line"
+    )]
+    pub tee_format: Option<TeeFormat>,
+
+    /// Record the interactive session as an asciicast v2 file, for
+    /// `replay` or any asciicast player.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "command",
+        help = "Record the interactive session to this .cast file (play it back with `replay`)"
+    )]
+    pub record: Option<PathBuf>,
+
+    /// What to do when the remote sets the clipboard with an OSC 52
+    /// sequence, e.g. `"$([char]27)]52;c;<base64>$([char]7)"`. Off by
+    /// default; `ask` confirms each write at the next prompt.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "off",
+        help = "Let the remote session set the local clipboard (OSC 52): off, ask or on"
+    )]
+    pub clipboard: ClipboardMode,
+
+    /// Keep the bottom row for a status bar: server, session state, how
+    /// long the running command has taken and how the last one ended. The
+    /// remote sees a window one row shorter.
+    #[arg(
+        long,
+        conflicts_with = "command",
+        help = "Show a status bar with the server, session state and command timings"
+    )]
+    pub status_line: bool,
+
+    /// While the terminal window is out of focus, redraw less often and let
+    /// idle server polls wait longer; focus brings both straight back.
+    #[arg(
+        long,
+        conflicts_with = "command",
+        help = "Poll and redraw less while the terminal window is out of focus"
+    )]
+    pub power_saving: bool,
+
+    #[command(subcommand)]
+    pub mode: Option<Mode>,
+}
+
+/// Things the client does instead of connecting.
+#[derive(Debug, Subcommand)]
+pub enum Mode {
+    /// Play back a session recorded with --record.
+    Replay(ReplayArgs),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ReplayArgs {
+    /// The .cast file to play.
+    pub path: PathBuf,
+
+    /// Playback speed; 2 plays twice as fast as recorded.
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Cut pauses longer than this many seconds down to it.
+    #[arg(long, value_name = "SECONDS", value_parser = parse_max_idle)]
+    pub max_idle: Option<f64>,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -150,6 +429,8 @@ pub enum AuthMethod {
     Ntlm,
     Kerberos,
     Negotiate,
+    /// No credentials at all; requires `--allow-no-auth`.
+    None,
 }
 
 impl std::fmt::Display for AuthMethod {
@@ -159,60 +440,126 @@ impl std::fmt::Display for AuthMethod {
             Self::Ntlm => write!(f, "ntlm"),
             Self::Kerberos => write!(f, "kerberos"),
             Self::Negotiate => write!(f, "negotiate"),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Environment variable read for the smart card PIN before prompting.
+pub const SMART_CARD_PIN_ENV: &str = "IRONPOSH_SMARTCARD_PIN";
+
+/// `--smartcard`: log on with a smart card certificate (Kerberos PKINIT)
+/// instead of the password.
+#[derive(Debug, Default, clap::Args)]
+pub struct SmartCardArgs {
+    /// Log on with a smart card; needs Kerberos or Negotiate.
+    #[arg(long, help = "Log on with a smart card (Kerberos or Negotiate only)")]
+    pub smartcard: bool,
+
+    /// The card's logon certificate, exported as PEM or DER.
+    #[arg(long, value_name = "FILE", requires = "smartcard")]
+    pub smartcard_certificate: Option<PathBuf>,
+
+    /// The reader the card is in.
+    #[arg(long, value_name = "NAME", requires = "smartcard")]
+    pub smartcard_reader: Option<String>,
+
+    /// Key container on the card; the card's default when omitted.
+    #[arg(long, value_name = "NAME", requires = "smartcard")]
+    pub smartcard_container: Option<String>,
+
+    /// Cryptographic service provider of the card.
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "Microsoft Base Smart Card Crypto Provider"
+    )]
+    pub smartcard_csp: String,
+}
+
+/// Source of the interactive REPL prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PromptMode {
+    /// The remote `prompt` function, so profile customizations show.
+    Remote,
+    /// `PS <remote location>> `, without running any user code.
+    Location,
+    /// A fixed `PS> `; no round-trip after each command.
+    Local,
+}
+
+/// Handling of clipboard writes from the remote session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClipboardMode {
+    /// Ignore them.
+    Off,
+    /// Ask before each one.
+    Ask,
+    /// Pass them on to the local terminal.
+    On,
+}
+
+impl From<ClipboardMode> for ClipboardPolicy {
+    fn from(mode: ClipboardMode) -> Self {
+        match mode {
+            ClipboardMode::Off => Self::Deny,
+            ClipboardMode::Ask => Self::Ask,
+            ClipboardMode::On => Self::Allow,
         }
     }
 }
 
-/// Initialize logging with file output and proper structured logging
-pub fn init_logging(verbose_level: u8) -> anyhow::Result<()> {
+/// Form of a tee file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TeeFormat {
+    /// The text as printed, without stream prefixes.
+    Text,
+    /// One JSON object per line with the stream, pipeline and text.
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum IpFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl From<IpFamily> for AddressPreference {
+    fn from(family: IpFamily) -> Self {
+        match family {
+            IpFamily::Ipv4 => Self::Ipv4,
+            IpFamily::Ipv6 => Self::Ipv6,
+        }
+    }
+}
+
+/// Initialize logging as the `--log-*` flags ask; the log file defaults to
+/// `ironposh-client-tokio.log`, or `IRONPOSH_TOKIO_LOG_FILE` when set.
+pub fn init_logging(args: &Args) -> anyhow::Result<()> {
     const DEFAULT_LOG_FILE: &str = "ironposh-client-tokio.log";
     const LOG_FILE_ENV: &str = "IRONPOSH_TOKIO_LOG_FILE";
 
-    let log_file_path = std::env::var_os(LOG_FILE_ENV)
+    let default_file = std::env::var_os(LOG_FILE_ENV)
         .filter(|v| !v.is_empty())
         .map_or_else(|| PathBuf::from(DEFAULT_LOG_FILE), PathBuf::from);
 
-    if let Some(parent) = log_file_path.parent() {
-        if !parent.as_os_str().is_empty() {
-            std::fs::create_dir_all(parent)?;
-        }
+    let mut redactions = Redactions::new()
+        .host(&args.server)
+        .user(&args.username)
+        .domain(&args.domain);
+    let gateway = args.gateway.as_deref().and_then(|g| Url::parse(g).ok());
+    if let Some(host) = gateway.as_ref().and_then(Url::host_str) {
+        redactions = redactions.host(host);
     }
 
-    // Append to preserve previous runs (use env override to point elsewhere if needed).
-    let log_file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_file_path)?;
-
-    // Determine log level based on verbosity using structured filters
-    let log_level = match verbose_level {
-        0 => "info,ureq=error,sspi=error",
-        1 => "debug,ureq=warn,sspi=error",
-        2 => "trace,ureq=info,sspi=error",
-        _ => "trace",
-    };
-
-    // Allow overriding filters with `RUST_LOG`.
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
-
-    let subscriber = Registry::default().with(env_filter).with(
-        fmt::layer()
-            .with_writer(log_file)
-            .with_target(true)
-            .with_line_number(true)
-            .with_file(true)
-            .with_ansi(false)
-            .compact(),
-    );
-
-    tracing::subscriber::set_global_default(subscriber)?;
-    tracing::info!(
-        log_file = %log_file_path.display(),
-        log_file_env = LOG_FILE_ENV,
-        "tracing initialized (file output)"
-    );
-    Ok(())
+    ironposh_logging::init(
+        &args.log,
+        LogSettings {
+            verbose: args.verbose,
+            default_file,
+            redactions,
+        },
+    )
 }
 
 /// Validate `--gateway`-specific flag combinations.
@@ -341,6 +688,13 @@ pub fn create_connector_config_with_kdc_url(
         accept_invalid_hostnames: false,
         extra_ca_pem,
     };
+    // The KDC proxy is reached with the same TLS options as the WinRM endpoint.
+    let kdc = kdc_url_override.map(|url| KdcEndpoint::from_url(url, tls.clone()));
+    let delegation = if args.delegate {
+        Delegation::Required
+    } else {
+        Delegation::None
+    };
 
     // Determine transport security from CLI flags
     let transport = if args.https {
@@ -352,6 +706,17 @@ pub fn create_connector_config_with_kdc_url(
         TransportSecurity::Http
     };
 
+    // Only Kerberos has a certificate logon (PKINIT); refuse the rest before
+    // prompting for a PIN.
+    if args.smartcard.smartcard
+        && !matches!(
+            args.auth_method,
+            AuthMethod::Kerberos | AuthMethod::Negotiate
+        )
+    {
+        return Err(PwshCoreError::Auth(SMART_CARD_NEEDS_KERBEROS).into());
+    }
+
     // Basic (and Certificate) auth carry credentials with no message-level
     // encryption, so they are only safe over TLS. WinRM refuses them on a plain
     // HTTP listener unless `AllowUnencrypted` is set; mirror that here. Refuse
@@ -364,12 +729,39 @@ pub fn create_connector_config_with_kdc_url(
         );
     }
 
+    // Without authentication nothing proves who the user is and nothing seals
+    // the traffic; that is only acceptable when a gateway in front of the
+    // listener does both, so demand an explicit opt-in.
+    if matches!(args.auth_method, AuthMethod::None) {
+        if !args.allow_no_auth {
+            anyhow::bail!(
+                "--auth-method none sends requests without any credentials or message \
+                 encryption. It is only meant for listeners behind an authenticating \
+                 gateway; pass --allow-no-auth to confirm."
+            );
+        }
+        tracing::warn!(
+            "Authentication is DISABLED - relying on a gateway or reverse proxy to authenticate!"
+        );
+    }
+
     let domain = if args.domain.trim().is_empty() {
         None
     } else {
         Some(args.domain.as_str())
     };
 
+    let sspi_identity = || -> anyhow::Result<ClientAuthIdentity> {
+        if args.smartcard.smartcard {
+            return smart_card_identity(&args.smartcard, &args.username);
+        }
+        let client_username = ClientUserName::new(&args.username, domain)?;
+        Ok(ClientAuthIdentity::new(
+            client_username,
+            args.password.clone(),
+        ))
+    };
+
     let auth = match args.auth_method {
         AuthMethod::Basic => AuthenticatorConfig::Basic {
             username: args.username.clone(),
@@ -379,36 +771,37 @@ pub fn create_connector_config_with_kdc_url(
             let client_username = ClientUserName::new(&args.username, domain)?;
             let identity = ClientAuthIdentity::new(client_username, args.password.clone());
             AuthenticatorConfig::Sspi(SspiAuthConfig::NTLM {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
             })
         }
         AuthMethod::Kerberos => {
-            let client_username = ClientUserName::new(&args.username, domain)?;
-            let identity = ClientAuthIdentity::new(client_username, args.password.clone());
+            let identity = sspi_identity()?;
             AuthenticatorConfig::Sspi(SspiAuthConfig::Kerberos {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
                 kerberos_config: KerberosConfig {
-                    kdc_url: kdc_url_override,
+                    kdc,
                     client_computer_name: whoami::fallible::hostname()
                         .unwrap_or_else(|_| "localhost".to_string()),
+                    delegation,
                 },
             })
         }
         AuthMethod::Negotiate => {
-            let client_username = ClientUserName::new(&args.username, domain)?;
-            let identity = ClientAuthIdentity::new(client_username, args.password.clone());
+            let identity = sspi_identity()?;
             AuthenticatorConfig::Sspi(SspiAuthConfig::Negotiate {
-                target: args.server.clone(),
+                target: server.spn_host(),
                 identity,
                 kerberos_config: Some(KerberosConfig {
-                    kdc_url: kdc_url_override,
+                    kdc,
                     client_computer_name: whoami::fallible::hostname()
                         .unwrap_or_else(|_| "localhost".to_string()),
+                    delegation,
                 }),
             })
         }
+        AuthMethod::None => AuthenticatorConfig::None,
     };
 
     let size = Size {
@@ -421,7 +814,8 @@ pub fn create_connector_config_with_kdc_url(
         .window_size(size.clone())
         .max_window_size(size.clone())
         .max_physical_window_size(size)
-        .build();
+        .build()
+        .with_context(|| format!("terminal size {cols}x{rows} is not a usable console"))?;
 
     let host_info = HostInfo::builder()
         .host_default_data(host_data)
@@ -433,15 +827,135 @@ pub fn create_connector_config_with_kdc_url(
     // + Ctrl+C responsiveness) under a single in-flight HTTP constraint.
     let operation_timeout_secs = if args.parallel { None } else { Some(0.25) };
 
-    Ok(WinRmConfig {
-        server: (server, args.port),
-        transport,
-        authentication: auth,
-        host_info,
-        operation_timeout_secs,
-        tls,
-        configuration_name: args.configuration_name.clone(),
-    })
+    Ok(WinRmConfig::builder()
+        .server((server, args.port))
+        .transport(transport)
+        .authentication(auth)
+        .host_info(host_info)
+        .operation_timeout_secs(operation_timeout_secs)
+        .tls(tls)
+        .configuration_name(args.configuration_name.clone())
+        .service_principal(args.spn.clone().map(ServicePrincipal::new))
+        .affinity(AffinityConfig {
+            pinned_address: args.pin_address,
+            ignore_cookies: false,
+        })
+        .receive_flow(ReceiveFlowConfig {
+            max_concurrent_receives: args.max_concurrent_receives.map(NonZeroUsize::get),
+            max_pending_output_bytes: args.max_pending_output_bytes.map(NonZeroUsize::get),
+        })
+        .host_mode(HostMode::Interactive)
+        .time_zone(args.time_zone.clone().or_else(crate::timezone::local))
+        .parse_mode(if args.lenient_parsing {
+            ParseMode::Lenient
+        } else {
+            ParseMode::Strict
+        })
+        .build()
+        .with_application_arguments(args.application_arguments.clone()))
+}
+
+/// The identity `--smartcard` selects. The PIN comes from
+/// [`SMART_CARD_PIN_ENV`], or is prompted for without echo.
+#[cfg(feature = "smartcard")]
+fn smart_card_identity(args: &SmartCardArgs, username: &str) -> anyhow::Result<ClientAuthIdentity> {
+    use ironposh_client_core::credentials::SmartCardLogon;
+
+    let certificate_path = args
+        .smartcard_certificate
+        .as_ref()
+        .context("--smartcard needs --smartcard-certificate")?;
+    let certificate = std::fs::read(certificate_path).with_context(|| {
+        format!(
+            "failed to read smart card certificate {}",
+            certificate_path.display()
+        )
+    })?;
+    let reader_name = args
+        .smartcard_reader
+        .clone()
+        .context("--smartcard needs --smartcard-reader")?;
+    let pin = match std::env::var(SMART_CARD_PIN_ENV) {
+        Ok(pin) => pin,
+        Err(_) => ironposh_terminal::read_secret(&format!("PIN for the card in {reader_name}: "))
+            .context("failed to read the smart card PIN")?,
+    };
+    Ok(ClientAuthIdentity::from_smart_card_logon(SmartCardLogon {
+        username: username.to_owned(),
+        certificate,
+        reader_name,
+        container_name: args.smartcard_container.clone(),
+        csp_name: args.smartcard_csp.clone(),
+        pin,
+    })?)
+}
+
+#[cfg(not(feature = "smartcard"))]
+fn smart_card_identity(_: &SmartCardArgs, _: &str) -> anyhow::Result<ClientAuthIdentity> {
+    anyhow::bail!("--smartcard needs a build with the `smartcard` feature")
+}
+
+fn parse_application_argument(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(format!("expected NAME=VALUE, got `{arg}`")),
+    }
+}
+
+/// `NAME=VALUE`, or `NAME` for the local value of `NAME`. Also reads the
+/// profile's `[env]` lines.
+pub(crate) fn parse_env_var(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = match arg.split_once('=') {
+        Some((name, value)) => (name.trim(), Some(value.to_owned())),
+        None => (arg.trim(), None),
+    };
+    if name.is_empty() || name.contains('\0') {
+        return Err(format!("expected NAME or NAME=VALUE, got `{arg}`"));
+    }
+    let value = match value {
+        Some(value) => value,
+        None => std::env::var(name)
+            .map_err(|_| format!("`{name}` is not set in the local environment"))?,
+    };
+    Ok((name.to_owned(), value))
+}
+
+/// `UTC`, or an offset such as `+05:30`, `-0800` or `+2`.
+fn parse_time_zone(arg: &str) -> Result<TimeZone, String> {
+    if arg.eq_ignore_ascii_case("utc") || arg.eq_ignore_ascii_case("z") {
+        return Ok(TimeZone::utc());
+    }
+    let invalid = || format!("expected UTC or an offset such as +05:30, got `{arg}`");
+    let (sign, offset) = match arg.as_bytes().first() {
+        Some(b'+') => (1, &arg[1..]),
+        Some(b'-') => (-1, &arg[1..]),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = match offset.split_once(':') {
+        Some(parts) => parts,
+        None if offset.len() == 4 => offset.split_at(2),
+        None => (offset, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(TimeZone::fixed(sign * (hours * 60 + minutes)))
+}
+
+fn parse_speed(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("expected a positive number, got `{arg}`")),
+    }
+}
+
+fn parse_max_idle(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+        _ => Err(format!("expected a number of seconds, got `{arg}`")),
+    }
 }
 
 pub fn build_reattach_command_prefix(args: &Args) -> String {
@@ -466,6 +980,9 @@ pub fn build_reattach_command_prefix(args: &Args) -> String {
     if args.http_insecure {
         parts.push("--http-insecure".to_string());
     }
+    if args.allow_no_auth {
+        parts.push("--allow-no-auth".to_string());
+    }
     if args.insecure {
         parts.push("--insecure".to_string());
     }
@@ -473,6 +990,24 @@ pub fn build_reattach_command_prefix(args: &Args) -> String {
         parts.push("--ca-cert".to_string());
         parts.push(quote_command_arg(&ca_cert.display().to_string()));
     }
+    let smartcard = &args.smartcard;
+    if smartcard.smartcard {
+        parts.push("--smartcard".to_string());
+        if let Some(certificate) = &smartcard.smartcard_certificate {
+            parts.push("--smartcard-certificate".to_string());
+            parts.push(quote_command_arg(&certificate.display().to_string()));
+        }
+        if let Some(reader) = &smartcard.smartcard_reader {
+            parts.push("--smartcard-reader".to_string());
+            parts.push(quote_command_arg(reader));
+        }
+        if let Some(container) = &smartcard.smartcard_container {
+            parts.push("--smartcard-container".to_string());
+            parts.push(quote_command_arg(container));
+        }
+        parts.push("--smartcard-csp".to_string());
+        parts.push(quote_command_arg(&smartcard.smartcard_csp));
+    }
     if let Some(gateway) = &args.gateway {
         parts.push("--gateway".to_string());
         parts.push(quote_command_arg(gateway));
@@ -489,6 +1024,18 @@ pub fn build_reattach_command_prefix(args: &Args) -> String {
         parts.push("--kdc-proxy-url".to_string());
         parts.push(quote_command_arg(kdc_proxy_url));
     }
+    if args.delegate {
+        parts.push("--delegate".to_string());
+    }
+    if let Some(spn) = &args.spn {
+        parts.push("--spn".to_string());
+        parts.push(quote_command_arg(spn));
+    }
+    // A disconnected shell only lives on the node that created it.
+    if let Some(address) = args.pin_address {
+        parts.push("--pin-address".to_string());
+        parts.push(address.to_string());
+    }
     if let Some(configuration_name) = &args.configuration_name {
         parts.push("--configuration-name".to_string());
         parts.push(quote_command_arg(configuration_name));
@@ -541,8 +1088,10 @@ mod tests {
             password: "pass".to_string(),
             domain: String::new(),
             auth_method: AuthMethod::Basic,
+            smartcard: SmartCardArgs::default(),
             https: false,
             http_insecure: true,
+            allow_no_auth: false,
             insecure: false,
             ca_cert: None,
             parallel: false,
@@ -551,10 +1100,35 @@ mod tests {
             gateway_webapp_password: None,
             kdc_address: None,
             kdc_proxy_url: None,
+            delegate: false,
+            spn: None,
+            pin_address: None,
+            prefer_ip: None,
+            discover: None,
             verbose: 0,
+            log: LogArgs::default(),
+            quarantine_dir: None,
+            lenient_parsing: false,
             configuration_name: None,
+            application_arguments: Vec::new(),
+            env: Vec::new(),
+            time_zone: None,
             command: None,
+            command_timeout: None,
+            stop_on_error: false,
+            page: false,
+            grid: false,
+            prompt: PromptMode::Remote,
+            profile: None,
             connect_shell_id: None,
+            audit_log: None,
+            tee: None,
+            tee_format: None,
+            record: None,
+            clipboard: ClipboardMode::Off,
+            status_line: false,
+            power_saving: false,
+            mode: None,
         };
 
         let cfg = create_connector_config(&args, 120, 30).expect("create config");
@@ -571,8 +1145,10 @@ mod tests {
             password: "pass".to_string(),
             domain: String::new(),
             auth_method: AuthMethod::Basic,
+            smartcard: SmartCardArgs::default(),
             https: false,
             http_insecure: true,
+            allow_no_auth: false,
             insecure: false,
             ca_cert: None,
             parallel: true,
@@ -581,10 +1157,35 @@ mod tests {
             gateway_webapp_password: None,
             kdc_address: None,
             kdc_proxy_url: None,
+            delegate: false,
+            spn: None,
+            pin_address: None,
+            prefer_ip: None,
+            discover: None,
             verbose: 0,
+            log: LogArgs::default(),
+            quarantine_dir: None,
+            lenient_parsing: false,
             configuration_name: None,
+            application_arguments: Vec::new(),
+            env: Vec::new(),
+            time_zone: None,
             command: None,
+            command_timeout: None,
+            stop_on_error: false,
+            page: false,
+            grid: false,
+            prompt: PromptMode::Remote,
+            profile: None,
             connect_shell_id: None,
+            audit_log: None,
+            tee: None,
+            tee_format: None,
+            record: None,
+            clipboard: ClipboardMode::Off,
+            status_line: false,
+            power_saving: false,
+            mode: None,
         };
 
         let cfg = create_connector_config(&args, 120, 30).expect("create config");
@@ -599,8 +1200,10 @@ mod tests {
             password: "pass".to_string(),
             domain: String::new(),
             auth_method: AuthMethod::Basic,
+            smartcard: SmartCardArgs::default(),
             https: true,
             http_insecure: false,
+            allow_no_auth: false,
             insecure: false,
             ca_cert: None,
             parallel: false,
@@ -609,10 +1212,35 @@ mod tests {
             gateway_webapp_password: None,
             kdc_address: None,
             kdc_proxy_url: None,
+            delegate: false,
+            spn: None,
+            pin_address: None,
+            prefer_ip: None,
+            discover: None,
             verbose: 0,
+            log: LogArgs::default(),
+            quarantine_dir: None,
+            lenient_parsing: false,
             configuration_name: None,
+            application_arguments: Vec::new(),
+            env: Vec::new(),
+            time_zone: None,
             command: None,
+            command_timeout: None,
+            stop_on_error: false,
+            page: false,
+            grid: false,
+            prompt: PromptMode::Remote,
+            profile: None,
             connect_shell_id: None,
+            audit_log: None,
+            tee: None,
+            tee_format: None,
+            record: None,
+            clipboard: ClipboardMode::Off,
+            status_line: false,
+            power_saving: false,
+            mode: None,
         }
     }
 
@@ -669,6 +1297,144 @@ mod tests {
         assert_eq!(cfg.configuration_name, None);
     }
 
+    #[test]
+    fn application_argument_flags_map_to_config() {
+        let args = Args::parse_from([
+            "ironposh-client-tokio",
+            "--http-insecure",
+            "--application-argument",
+            "Tenant=contoso",
+            "--application-argument",
+            "Note=a=b",
+        ]);
+
+        let cfg = create_connector_config(&args, 120, 30).expect("create config");
+        let arguments = &cfg.application_arguments.additional_arguments;
+        assert_eq!(arguments.get("Tenant"), Some(&"contoso".into()));
+        assert_eq!(arguments.get("Note"), Some(&"a=b".into()));
+
+        assert!(Args::try_parse_from([
+            "ironposh-client-tokio",
+            "--application-argument",
+            "missing-separator",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn env_flags_set_or_forward_variables() {
+        let args = Args::parse_from([
+            "ironposh-client-tokio",
+            "--env",
+            "DEPLOY_TAG=v1=rc",
+            "--env",
+            "PATH",
+        ]);
+        assert_eq!(
+            args.env,
+            [
+                ("DEPLOY_TAG".to_owned(), "v1=rc".to_owned()),
+                (
+                    "PATH".to_owned(),
+                    std::env::var("PATH").expect("PATH is set")
+                ),
+            ]
+        );
+
+        let err = parse_env_var("IRONPOSH_SURELY_UNSET_VARIABLE").unwrap_err();
+        assert!(err.contains("not set"), "{err}");
+        assert!(parse_env_var("=value").is_err());
+    }
+
+    #[test]
+    fn time_zone_flag_overrides_the_local_zone() {
+        let args = Args::parse_from(["ironposh-client-tokio", "--time-zone", "+05:30"]);
+        assert_eq!(args.time_zone, Some(TimeZone::fixed(330)));
+        let config = create_connector_config(&args, 120, 30).expect("config");
+        assert_eq!(config.time_zone, Some(TimeZone::fixed(330)));
+
+        assert_eq!(parse_time_zone("-0800"), Ok(TimeZone::fixed(-480)));
+        assert_eq!(parse_time_zone("+2"), Ok(TimeZone::fixed(120)));
+        assert_eq!(parse_time_zone("utc"), Ok(TimeZone::utc()));
+        assert!(parse_time_zone("05:30").is_err());
+        assert!(parse_time_zone("+15:00").is_err());
+    }
+
+    #[test]
+    fn smartcard_is_refused_without_kerberos() {
+        for auth in ["basic", "ntlm", "none"] {
+            let args = Args::parse_from([
+                "ironposh-client-tokio",
+                "--https",
+                "--smartcard",
+                "--auth-method",
+                auth,
+            ]);
+            let err = create_connector_config(&args, 120, 30).unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<PwshCoreError>(),
+                    Some(PwshCoreError::Auth(SMART_CARD_NEEDS_KERBEROS))
+                ),
+                "{auth}: {err:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn lenient_parsing_flag_sets_the_parse_mode() {
+        let args = Args::parse_from(["ironposh-client-tokio"]);
+        let config = create_connector_config(&args, 120, 30).expect("config");
+        assert_eq!(config.parse_mode, ParseMode::Strict);
+
+        let args = Args::parse_from(["ironposh-client-tokio", "--lenient-parsing"]);
+        let config = create_connector_config(&args, 120, 30).expect("config");
+        assert_eq!(config.parse_mode, ParseMode::Lenient);
+    }
+
+    #[test]
+    fn flow_flags_set_the_receive_limits() {
+        let args = Args::parse_from(["ironposh-client-tokio"]);
+        let config = create_connector_config(&args, 120, 30).expect("config");
+        assert_eq!(config.receive_flow.max_concurrent_receives, None);
+        assert_eq!(config.receive_flow.max_pending_output_bytes, None);
+
+        let args = Args::parse_from([
+            "ironposh-client-tokio",
+            "--max-concurrent-receives",
+            "2",
+            "--max-pending-output-bytes",
+            "1048576",
+        ]);
+        let config = create_connector_config(&args, 120, 30).expect("config");
+        assert_eq!(config.receive_flow.max_concurrent_receives, Some(2));
+        assert_eq!(
+            config.receive_flow.max_pending_output_bytes,
+            Some(1_048_576)
+        );
+
+        assert!(
+            Args::try_parse_from(["ironposh-client-tokio", "--max-concurrent-receives", "0"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn no_auth_requires_explicit_opt_in() {
+        let args = Args::parse_from(["ironposh-client-tokio", "--https", "--auth-method", "none"]);
+        assert!(create_connector_config(&args, 120, 30).is_err());
+
+        let args = Args::parse_from([
+            "ironposh-client-tokio",
+            "--https",
+            "--auth-method",
+            "none",
+            "--allow-no-auth",
+        ]);
+        let cfg = create_connector_config(&args, 120, 30).expect("create config");
+        assert!(matches!(cfg.authentication, AuthenticatorConfig::None));
+    }
+
     #[test]
     fn connect_shell_id_flag_parses_uuid() {
         let args = Args::parse_from([
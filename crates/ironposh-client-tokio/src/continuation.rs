@@ -0,0 +1,289 @@
+//! Multi-line input: like powershell.exe, a line that leaves a brace, quote,
+//! here-string or comment open, or ends with `|` or a backtick, is continued
+//! at a `>> ` prompt and the lines run as one pipeline.
+
+/// Prompt shown while input is being continued.
+pub const PROMPT: &str = ">> ";
+
+/// Lines typed so far for a command that is not complete yet.
+#[derive(Debug, Default)]
+pub struct PendingInput {
+    lines: Vec<String>,
+}
+
+impl PendingInput {
+    /// Add a typed line; returns the whole command once it is complete. An
+    /// empty line ends continuation whatever the state, as in powershell.exe,
+    /// and the server reports what is still missing.
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        let continuing = !self.lines.is_empty();
+        if continuing && line.trim().is_empty() {
+            return Some(self.take());
+        }
+        self.lines
+            .push(line.trim_end_matches(['\r', '\n']).to_owned());
+        let input = self.lines.join("\n");
+        if is_incomplete(&input) {
+            return None;
+        }
+        self.lines.clear();
+        Some(input)
+    }
+
+    /// Add pasted text, which may span several lines. A paste is one logical
+    /// input: it ends continuation and is submitted as is, even when
+    /// incomplete, rather than prompting `>>` for more.
+    pub fn push_paste(&mut self, text: &str) -> String {
+        self.lines
+            .push(text.trim_end_matches(['\r', '\n']).to_owned());
+        self.take()
+    }
+
+    /// Drop the lines typed so far (Ctrl+C at the `>>` prompt).
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    fn take(&mut self) -> String {
+        std::mem::take(&mut self.lines).join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    /// `(`, `{`, `[` or a `$(` subexpression, waiting for this closer.
+    Group(char),
+    SingleQuoted,
+    DoubleQuoted,
+    /// `@' ... '@` / `@" ... "@`, closed by the quote at a line start.
+    HereString(char),
+    /// `<# ... #>`.
+    BlockComment,
+}
+
+/// Whether `input` needs more lines before it can be parsed: an open group,
+/// string, here-string or block comment, or a trailing `|` or backtick.
+/// Unbalanced closers are left for the server to report.
+pub fn is_incomplete(input: &str) -> bool {
+    let mut scanner = Scanner {
+        chars: input.chars().collect(),
+        i: 0,
+        stack: Vec::new(),
+        last_code: None,
+    };
+    while scanner.i < scanner.chars.len() {
+        match scanner.stack.last().copied() {
+            None | Some(Frame::Group(_)) => {
+                if let Some(incomplete) = scanner.code() {
+                    return incomplete;
+                }
+            }
+            Some(Frame::SingleQuoted) => scanner.single_quoted(),
+            Some(Frame::DoubleQuoted) => scanner.double_quoted(),
+            Some(Frame::HereString(quote)) => scanner.here_string(quote),
+            Some(Frame::BlockComment) => scanner.block_comment(),
+        }
+    }
+    !scanner.stack.is_empty() || scanner.last_code == Some('|')
+}
+
+/// Each step consumes at least one character.
+struct Scanner {
+    chars: Vec<char>,
+    i: usize,
+    stack: Vec<Frame>,
+    /// Last code character outside strings and comments; `|` asks for more.
+    last_code: Option<char>,
+}
+
+impl Scanner {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.i + offset).copied()
+    }
+
+    /// `Some` ends the scan early with the answer.
+    fn code(&mut self) -> Option<bool> {
+        let c = self.chars[self.i];
+        let next = self.peek(1);
+        let token_start = self.i == 0 || starts_token(self.chars[self.i - 1]);
+        self.i += 1;
+        if c.is_whitespace() {
+            return None;
+        }
+        match c {
+            '`' => {
+                // A trailing backtick continues the line.
+                if next.is_none() {
+                    return Some(true);
+                }
+                self.i += 1;
+            }
+            '#' if token_start => {
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.i += 1;
+                }
+                return None;
+            }
+            '<' if token_start && next == Some('#') => {
+                self.stack.push(Frame::BlockComment);
+                self.i += 1;
+                return None;
+            }
+            '@' if matches!(next, Some('\'' | '"')) && self.opens_here_string() => {
+                self.stack.push(Frame::HereString(self.chars[self.i]));
+                self.i += 1;
+            }
+            '\'' => self.stack.push(Frame::SingleQuoted),
+            '"' => self.stack.push(Frame::DoubleQuoted),
+            '(' => self.stack.push(Frame::Group(')')),
+            '{' => self.stack.push(Frame::Group('}')),
+            '[' => self.stack.push(Frame::Group(']')),
+            ')' | '}' | ']' => {
+                if self.stack.last() != Some(&Frame::Group(c)) {
+                    return Some(false);
+                }
+                self.stack.pop();
+            }
+            _ => {}
+        }
+        self.last_code = Some(c);
+        None
+    }
+
+    /// At the quote of `@"`/`@'`: a here-string only when nothing but
+    /// whitespace follows on the line.
+    fn opens_here_string(&self) -> bool {
+        self.chars[self.i + 1..]
+            .iter()
+            .take_while(|&&c| c != '\n')
+            .all(|c| c.is_whitespace())
+    }
+
+    fn single_quoted(&mut self) {
+        let c = self.chars[self.i];
+        self.i += 1;
+        if c == '\'' {
+            if self.peek(0) == Some('\'') {
+                self.i += 1;
+            } else {
+                self.stack.pop();
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) {
+        let c = self.chars[self.i];
+        let next = self.peek(1);
+        self.i += 1;
+        match c {
+            '`' => self.i += 1,
+            '"' if next == Some('"') => self.i += 1,
+            '"' => {
+                self.stack.pop();
+            }
+            '$' if next == Some('(') => {
+                self.stack.push(Frame::Group(')'));
+                self.i += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn here_string(&mut self, quote: char) {
+        let at_line_start = self.i > 0 && self.chars[self.i - 1] == '\n';
+        if at_line_start && self.chars[self.i] == quote && self.peek(1) == Some('@') {
+            self.stack.pop();
+            self.i += 2;
+        } else {
+            self.i += 1;
+        }
+    }
+
+    fn block_comment(&mut self) {
+        if self.chars[self.i] == '#' && self.peek(1) == Some('>') {
+            self.stack.pop();
+            self.i += 2;
+        } else {
+            self.i += 1;
+        }
+    }
+}
+
+/// A `#` or `<#` after one of these starts a comment; elsewhere (`a#b`) it is
+/// part of a word.
+fn starts_token(previous: char) -> bool {
+    previous.is_whitespace() || matches!(previous, '(' | ')' | '{' | '}' | ';' | '|')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_commands_run_at_once() {
+        for input in [
+            "Get-Process",
+            "function f { 'x' }",
+            "'it''s' + \"a `\"b`\"\"",
+            "$a = @{ k = @(1, 2) }",
+            "Write-Output a#b # trailing } comment",
+            "<# block ( #> Get-Date",
+            "\"$(Get-Date | Out-String)\"",
+            "Get-Item )",
+        ] {
+            assert!(!is_incomplete(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn open_constructs_ask_for_more() {
+        for input in [
+            "function f {",
+            "if ($x) { 'a'",
+            "Get-Process |",
+            "Get-Process |   # filter next",
+            "Get-ChildItem `",
+            "'unterminated",
+            "\"$(Get-Date",
+            "@\"",
+            "@'\nline\n '@",
+            "<# comment",
+        ] {
+            assert!(is_incomplete(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn here_strings_close_at_line_start() {
+        assert!(!is_incomplete("@'\n'a' { (\n'@"));
+        assert!(!is_incomplete("@\"\nx\n\"@ | Out-Host"));
+    }
+
+    #[test]
+    fn pending_input_accumulates_until_complete() {
+        let mut pending = PendingInput::default();
+        assert_eq!(pending.push("function f {"), None);
+        assert_eq!(pending.push("  'x'"), None);
+        assert_eq!(pending.push("}").as_deref(), Some("function f {\n  'x'\n}"));
+
+        assert_eq!(pending.push("Get-Process |"), None);
+        assert_eq!(pending.push("").as_deref(), Some("Get-Process |"));
+        assert_eq!(pending.push("").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn pastes_are_submitted_whole() {
+        let mut pending = PendingInput::default();
+        assert_eq!(
+            pending.push_paste("function f {\n  'x'\n}\nf\n"),
+            "function f {\n  'x'\n}\nf"
+        );
+
+        assert_eq!(pending.push("if ($x) {"), None);
+        assert_eq!(
+            pending.push_paste("  'a'\n}").as_str(),
+            "if ($x) {\n  'a'\n}"
+        );
+        assert_eq!(pending.push("Get-Date").as_deref(), Some("Get-Date"));
+    }
+}
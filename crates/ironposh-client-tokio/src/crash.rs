@@ -0,0 +1,43 @@
+//! The panic hook: a panic must not leave the user's terminal in raw mode
+//! or the remote shell running with nobody attached to it.
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use ironposh_async::RemoteAsyncPowershellClient;
+use tracing::{info, warn};
+
+/// How long a panic waits for the server to delete the shell.
+const CLOSE_GRACE: Duration = Duration::from_secs(2);
+
+/// Restore the terminal and delete the shell before the crash report is
+/// written. `connection_done` is signalled (or dropped) when the connection
+/// task ends, which after a Close means the server answered the Delete.
+pub fn install_panic_hook(
+    client: RemoteAsyncPowershellClient,
+    connection_done: mpsc::Receiver<()>,
+) {
+    let client = Mutex::new(Some(client));
+    let connection_done = Mutex::new(connection_done);
+    ironposh_logging::install_panic_hook(move || {
+        ironposh_terminal::restore_host_terminal();
+
+        let Some(mut client) = client.lock().ok().and_then(|mut client| client.take()) else {
+            return;
+        };
+        // The panicking thread may be a runtime worker; queue the Close from a
+        // thread of its own so it cannot wedge on this one.
+        std::thread::spawn(move || futures::executor::block_on(client.close()));
+        let Ok(connection_done) = connection_done.lock() else {
+            return;
+        };
+        match connection_done.recv_timeout(CLOSE_GRACE) {
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                warn!(grace = ?CLOSE_GRACE, "shell not deleted before the crash exit");
+            }
+            Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                info!("session closed after a panic");
+            }
+        }
+    });
+}
@@ -0,0 +1,132 @@
+//! `--discover`: find the WinRM endpoints of a domain through `_wsman._tcp`
+//! SRV records and rank them by probing each with an unauthenticated
+//! WS-Management `Identify`.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use futures::future::join_all;
+use ironposh_client_core::connector::{
+    config::TlsOptions,
+    endpoint_discovery::{self, Candidate, Probe},
+    kdc_discovery::SrvRecord,
+    Scheme,
+};
+use tracing::{info, instrument, warn};
+
+use crate::http_client::ReqwestHttpClient;
+use crate::kerberos::{dns_exchange, dns_query_id, system_nameservers};
+
+/// Per-endpoint wait for the `Identify` reply.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Resolve, probe and rank the WinRM endpoints of `domain`.
+#[instrument(name = "discover_endpoints", level = "info", err)]
+pub async fn discover_endpoints(domain: &str) -> anyhow::Result<Vec<Candidate>> {
+    let records = lookup_wsman_srv(domain).await?;
+    info!(records = records.len(), "resolved _wsman._tcp SRV records");
+
+    let mut candidates = endpoint_discovery::candidates(domain, &records);
+    let probes = join_all(candidates.iter().map(probe)).await;
+    for (candidate, outcome) in candidates.iter_mut().zip(probes) {
+        candidate.probe = outcome;
+    }
+    endpoint_discovery::rank(&mut candidates);
+    Ok(candidates)
+}
+
+/// SRV records from the first nameserver that answers; no records is not an
+/// error, the domain itself is probed instead.
+async fn lookup_wsman_srv(domain: &str) -> anyhow::Result<Vec<SrvRecord>> {
+    let nameservers = system_nameservers().await?;
+    let mut last_error = None;
+    for nameserver in nameservers {
+        let id = dns_query_id();
+        let query = endpoint_discovery::srv_query(domain, id)?;
+        let reply = match dns_exchange(nameserver, &query).await {
+            Ok(reply) => reply,
+            Err(error) => {
+                warn!(%nameserver, %error, "DNS SRV query failed");
+                last_error = Some(error);
+                continue;
+            }
+        };
+        return Ok(endpoint_discovery::parse_srv_response(&reply, id)?);
+    }
+    Err(last_error.map_or_else(
+        || anyhow::anyhow!("no nameserver answered"),
+        |error| error.context("no nameserver answered the _wsman._tcp SRV query"),
+    ))
+}
+
+async fn probe(candidate: &Candidate) -> Probe {
+    let request = match candidate.identify_request() {
+        Ok(request) => request,
+        Err(error) => {
+            warn!(host = %candidate.host, %error, "cannot probe candidate");
+            return Probe::Unreachable;
+        }
+    };
+    // Only reachability and the listener's identity are checked here, so a
+    // self-signed HTTPS listener still counts.
+    let tls = TlsOptions {
+        accept_invalid_certs: true,
+        accept_invalid_hostnames: true,
+        extra_ca_pem: None,
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, ReqwestHttpClient::send_once(request, &tls)).await {
+        Ok(Ok(response)) => Probe::from_response(&response),
+        Ok(Err(error)) => {
+            info!(host = %candidate.host, port = candidate.port, %error, "probe failed");
+            Probe::Unreachable
+        }
+        Err(_) => Probe::Unreachable,
+    }
+}
+
+/// Ranked candidates, one per line, with the flags to connect to each.
+pub fn render(domain: &str, candidates: &[Candidate]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "WinRM endpoints for {domain}:");
+    for candidate in candidates {
+        let status = match &candidate.probe {
+            Probe::Identified(identity) => format!(
+                "WS-Management {}",
+                identity.product_version.as_deref().unwrap_or("listener")
+            ),
+            Probe::Responded { status } => format!("HTTP {status}"),
+            Probe::Unreachable => "unreachable".to_owned(),
+            Probe::NotProbed => "not probed".to_owned(),
+        };
+        let https = if candidate.scheme == Scheme::Https {
+            " --https"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "  --server {} --port {}{https}  ({status})",
+            candidate.host, candidate.port
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_lists_connect_flags_and_probe_outcome() {
+        let mut candidates = endpoint_discovery::candidates("corp.example", &[]);
+        candidates[0].probe = Probe::Responded { status: 401 };
+        candidates[1].probe = Probe::Unreachable;
+
+        assert_eq!(
+            render("corp.example", &candidates),
+            "WinRM endpoints for corp.example:\n  \
+             --server corp.example --port 5986 --https  (HTTP 401)\n  \
+             --server corp.example --port 5985  (unreachable)\n"
+        );
+    }
+}
@@ -15,14 +15,15 @@ use ironposh_client_core::{
 };
 use reqwest::Client;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 use url::Url;
 use uuid::Uuid;
 
-use crate::{config::AuthMethod, http_client::ReqwestHttpClient};
+use crate::{config::AuthMethod, http_client::ReqwestHttpClient, kerberos};
 
 type GatewayWs = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
 
@@ -62,10 +63,54 @@ impl HttpClient for CliHttpClient {
 }
 
 pub struct GatewayHttpViaWsClient {
+    state: Arc<Mutex<GatewayWsState>>,
+    /// Mints a new association token when the WebSocket has to be reopened;
+    /// without it, reconnecting reuses the first (short-lived) token.
+    token_refresh: Option<GatewayTokenConfig>,
+}
+
+struct GatewayWsState {
     websocket_url: Url,
-    websocket: Arc<Mutex<Option<GatewayWs>>>,
+    websocket: Option<GatewayWs>,
+    /// Bumped every time the WebSocket is opened. Each socket reaches the
+    /// server as a new TCP connection, with none of the security contexts
+    /// established over the previous one.
+    generation: u64,
+    /// The socket generation each pool connection last exchanged on.
+    conn_generations: HashMap<ConnectionId, u64>,
+}
+
+/// What a request does when its pool connection last talked to the server
+/// over an older WebSocket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnReopen {
+    /// Send it: it carries Basic credentials, or an unsealed body the
+    /// server answers with a 401 challenge the pool already handles.
+    Send,
+    /// Answer 419 without sending it: the body is sealed with a security
+    /// context the server dropped with the old socket. The pool treats this
+    /// like a connection reset and re-authenticates on a fresh connection
+    /// before sending the request again.
+    Reset,
+    /// Fail: an authentication handshake cannot continue on a new socket.
+    /// (Its first leg is on a new pool connection, which is never stale.)
+    Fail,
+}
+
+impl OnReopen {
+    fn for_request(request: &HttpRequest) -> Self {
+        if request.body.as_ref().is_some_and(HttpBody::is_encrypted) {
+            Self::Reset
+        } else {
+            Self::Send
+        }
+    }
 }
 
+/// Attempts at reopening the Gateway WebSocket before a request fails.
+const RECONNECT_ATTEMPTS: u32 = 3;
+const RECONNECT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
 impl GatewayHttpViaWsClient {
     pub fn new(websocket_url: Url) -> Self {
         let redacted_url = redact_gateway_url(&websocket_url);
@@ -74,8 +119,60 @@ impl GatewayHttpViaWsClient {
             "creating Gateway HTTP-over-WebSocket client"
         );
         Self {
-            websocket_url,
-            websocket: Arc::new(Mutex::new(None)),
+            state: Arc::new(Mutex::new(GatewayWsState {
+                websocket_url,
+                websocket: None,
+                generation: 0,
+                conn_generations: HashMap::new(),
+            })),
+            token_refresh: None,
+        }
+    }
+
+    pub fn with_token_refresh(mut self, config: GatewayTokenConfig) -> Self {
+        self.token_refresh = Some(config);
+        self
+    }
+
+    /// Open the WebSocket, retrying with backoff. Every retry first asks the
+    /// gateway for a fresh association token (when configured): the one in
+    /// the current URL may have expired since the session was prepared.
+    async fn connect(
+        &self,
+        state: &mut GatewayWsState,
+        conn_id: ConnectionId,
+    ) -> Result<GatewayWs> {
+        let mut attempt = 1;
+        loop {
+            let redacted_url = redact_gateway_url(&state.websocket_url);
+            info!(
+                conn_id = conn_id.inner(),
+                attempt,
+                gateway_url = %redacted_url,
+                "opening Gateway WebSocket"
+            );
+            let error = match connect_async(state.websocket_url.as_str()).await {
+                Ok((stream, _)) => {
+                    state.generation += 1;
+                    return Ok(stream);
+                }
+                Err(error) => error,
+            };
+            if attempt >= RECONNECT_ATTEMPTS {
+                return Err(error).context("failed to open Gateway WebSocket");
+            }
+            warn!(attempt, error = %error, "failed to open Gateway WebSocket; retrying");
+
+            tokio::time::sleep(RECONNECT_BACKOFF * attempt).await;
+            if let Some(config) = &self.token_refresh {
+                match create_gateway_session(config).await {
+                    Ok(session) => state.websocket_url = session.websocket_url,
+                    Err(error) => {
+                        warn!(error = %error, "failed to refresh Gateway association token");
+                    }
+                }
+            }
+            attempt += 1;
         }
     }
 
@@ -86,63 +183,101 @@ impl GatewayHttpViaWsClient {
         &self,
         request: HttpRequest,
         conn_id: ConnectionId,
+        on_reopen: OnReopen,
     ) -> Result<HttpResponse> {
         let bytes = serialize_http_request(&request)?;
-        let mut websocket = self.websocket.lock().await;
+        let mut state = self.state.lock().await;
 
-        if websocket.is_none() {
-            let redacted_url = redact_gateway_url(&self.websocket_url);
-            info!(
-                conn_id = conn_id.inner(),
-                gateway_url = %redacted_url,
-                "opening Gateway WebSocket"
-            );
-            let (stream, _) = connect_async(self.websocket_url.as_str())
-                .await
-                .context("failed to open Gateway WebSocket")?;
-            *websocket = Some(stream);
+        let mut stream = match state.websocket.take() {
+            Some(stream) => stream,
+            None => self.connect(&mut state, conn_id).await?,
+        };
+
+        let reopened = state
+            .conn_generations
+            .get(&conn_id)
+            .is_some_and(|generation| *generation != state.generation);
+        if reopened {
+            match on_reopen {
+                OnReopen::Send => {}
+                OnReopen::Reset => {
+                    warn!(
+                        conn_id = conn_id.inner(),
+                        "Gateway WebSocket was reopened; reporting a connection reset"
+                    );
+                    state.conn_generations.remove(&conn_id);
+                    state.websocket = Some(stream);
+                    return Ok(connection_reset_response());
+                }
+                OnReopen::Fail => {
+                    state.websocket = Some(stream);
+                    anyhow::bail!("Gateway WebSocket was reopened during authentication");
+                }
+            }
         }
 
-        let stream = websocket
-            .as_mut()
-            .ok_or_else(|| anyhow::anyhow!("Gateway WebSocket missing after connection"))?;
+        // A socket that failed mid-exchange is dropped rather than put back:
+        // the next request reopens it. The failed request itself is not
+        // replayed since the server may already have acted on it.
+        let response = exchange(&mut stream, bytes, conn_id).await?;
+        let generation = state.generation;
+        state.conn_generations.insert(conn_id, generation);
+        state.websocket = Some(stream);
+        Ok(response)
+    }
+}
+
+/// The response the pool answers by re-authenticating on a new connection
+/// and sending the queued request again (see `OnReopen::Reset`).
+fn connection_reset_response() -> HttpResponse {
+    HttpResponse {
+        status_code: 419,
+        headers: Vec::new(),
+        body: HttpBody::None,
+        peer_cert_der: None,
+    }
+}
 
-        debug!(
-            conn_id = conn_id.inner(),
-            bytes_len = bytes.len(),
-            "sending serialized HTTP request over Gateway WebSocket"
-        );
-        stream
-            .send(Message::Binary(bytes.into()))
+async fn exchange(
+    stream: &mut GatewayWs,
+    bytes: Vec<u8>,
+    conn_id: ConnectionId,
+) -> Result<HttpResponse> {
+    debug!(
+        conn_id = conn_id.inner(),
+        bytes_len = bytes.len(),
+        "sending serialized HTTP request over Gateway WebSocket"
+    );
+    stream
+        .send(Message::Binary(bytes.into()))
+        .await
+        .context("failed to send HTTP request over Gateway WebSocket")?;
+
+    let mut decoder = HttpResponseDecoder::new(16 * 1024 * 1024);
+    loop {
+        let message = stream
+            .next()
             .await
-            .context("failed to send HTTP request over Gateway WebSocket")?;
+            .ok_or_else(|| anyhow::anyhow!("Gateway WebSocket closed"))?
+            .context("Gateway WebSocket read failed")?;
 
-        let mut decoder = HttpResponseDecoder::new(16 * 1024 * 1024);
-        loop {
-            let message = stream
-                .next()
-                .await
-                .ok_or_else(|| anyhow::anyhow!("Gateway WebSocket closed"))?
-                .context("Gateway WebSocket read failed")?;
-
-            match message {
-                Message::Binary(bytes) => {
-                    if let Some(response) = decoder.feed(&bytes)? {
-                        return Ok(response);
-                    }
+        match message {
+            Message::Binary(bytes) => {
+                if let Some(response) = decoder.feed(&bytes)? {
+                    return Ok(response);
                 }
-                Message::Text(text) => {
-                    return Err(anyhow::anyhow!(
-                        "Gateway WebSocket returned text frame: {text}"
-                    ));
-                }
-                Message::Close(frame) => {
-                    return Err(anyhow::anyhow!(
-                        "Gateway WebSocket closed while waiting for response: {frame:?}"
-                    ));
-                }
-                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
             }
+            Message::Text(text) => {
+                return Err(anyhow::anyhow!(
+                    "Gateway WebSocket returned text frame: {text}"
+                ));
+            }
+            Message::Close(frame) => {
+                return Err(anyhow::anyhow!(
+                    "Gateway WebSocket closed while waiting for response: {frame:?}"
+                ));
+            }
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
         }
     }
 }
@@ -152,7 +287,8 @@ impl HttpClient for GatewayHttpViaWsClient {
     async fn send_request(&self, try_send: TrySend) -> Result<HttpResponseTargeted> {
         match try_send {
             TrySend::JustSend { request, conn_id } => {
-                let response = self.send_http_request(request, conn_id).await?;
+                let on_reopen = OnReopen::for_request(&request);
+                let response = self.send_http_request(request, conn_id, on_reopen).await?;
                 Ok(HttpResponseTargeted::new(response, conn_id, None))
             }
             TrySend::AuthNeeded { mut auth_sequence } => {
@@ -173,12 +309,12 @@ impl HttpClient for GatewayHttpViaWsClient {
                             loop {
                                 // Gateway KDC-proxy TLS policy is the gateway deployment's own;
                                 // client TLS flags are rejected with --gateway.
-                                let kdc_response = ReqwestHttpClient::send_kdc_network_request(
-                                    packet,
-                                    &TlsOptions::default(),
-                                )
-                                .await
-                                .context("failed to send KDC request during Gateway auth")?;
+                                let kdc_response =
+                                    kerberos::send_packet(packet, &TlsOptions::default())
+                                        .await
+                                        .context(
+                                            "failed to send KDC request during Gateway auth",
+                                        )?;
 
                                 match SspiAuthSequence::resume(generator_holder, kdc_response)? {
                                     SecContextMaybeInit::Initialized(sec) => break sec,
@@ -204,7 +340,9 @@ impl HttpClient for GatewayHttpViaWsClient {
                                 connection_id,
                                 request,
                             } = request;
-                            let response = self.send_http_request(request, connection_id).await?;
+                            let response = self
+                                .send_http_request(request, connection_id, OnReopen::Fail)
+                                .await?;
                             auth_response = Some(response);
                             auth_sequence = sequence;
                         }
@@ -216,7 +354,9 @@ impl HttpClient for GatewayHttpViaWsClient {
                                 connection_id,
                                 request,
                             } = request;
-                            let response = self.send_http_request(request, connection_id).await?;
+                            let response = self
+                                .send_http_request(request, connection_id, OnReopen::Fail)
+                                .await?;
                             return Ok(HttpResponseTargeted::new(
                                 response,
                                 connection_id,
@@ -312,7 +452,7 @@ pub async fn create_gateway_session(config: &GatewayTokenConfig) -> Result<Gatew
                 )
             }
         }
-        AuthMethod::Basic | AuthMethod::Ntlm => None,
+        AuthMethod::Basic | AuthMethod::Ntlm | AuthMethod::None => None,
     };
 
     Ok(GatewaySession {
@@ -821,4 +961,225 @@ mod tests {
         assert_eq!(response.status_code, 204);
         assert!(matches!(response.body, HttpBody::Text(ref text) if text.is_empty()));
     }
+
+    fn request(body: HttpBody) -> HttpRequest {
+        HttpRequest {
+            method: Method::Post,
+            url: "http://server:5985/wsman".to_string(),
+            headers: Vec::new(),
+            body: Some(body),
+            cookie: None,
+            connect_to: None,
+        }
+    }
+
+    fn http_response(body: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    fn text(response: &HttpResponse) -> &str {
+        match &response.body {
+            HttpBody::Text(text) => text,
+            other => panic!("unexpected body: {other:?}"),
+        }
+    }
+
+    /// Answer every HTTP request carried by `ws` with `<socket>-<request>`,
+    /// closing the socket after `limit` of them.
+    async fn answer(mut ws: WebSocketStream<tokio::net::TcpStream>, socket: usize, limit: usize) {
+        for n in 1..=limit {
+            let Some(Ok(Message::Binary(_))) = ws.next().await else {
+                return;
+            };
+            let reply = http_response(&format!("{socket}-{n}"));
+            if ws.send(Message::Binary(reply.into())).await.is_err() {
+                return;
+            }
+        }
+        let _ = ws.close(None).await;
+    }
+
+    #[tokio::test]
+    async fn a_reopened_socket_resets_sealed_requests() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "ws://{}/jet/fwd/tcp/1?token=t",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move {
+            for (socket, limit) in [(1, 1), (2, usize::MAX)] {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                tokio::spawn(answer(ws, socket, limit));
+            }
+        });
+        let client = GatewayHttpViaWsClient::new(url.parse().unwrap());
+        let sealed = || request(HttpBody::Encrypted(b"sealed".to_vec()));
+        let (first, second) = (ConnectionId::test_new(1), ConnectionId::test_new(2));
+
+        let response = client
+            .send_http_request(sealed(), first, OnReopen::Reset)
+            .await
+            .unwrap();
+        assert_eq!(text(&response), "1-1");
+        // The gateway closed the socket; the request fails and is not replayed.
+        client
+            .send_http_request(sealed(), first, OnReopen::Reset)
+            .await
+            .unwrap_err();
+
+        // The reopened socket has no security context for `first`: the pool
+        // gets a 419 to re-authenticate on, and nothing reaches the server.
+        let response = client
+            .send_http_request(sealed(), first, OnReopen::Reset)
+            .await
+            .unwrap();
+        assert_eq!(response.status_code, 419);
+
+        let response = client
+            .send_http_request(sealed(), second, OnReopen::Reset)
+            .await
+            .unwrap();
+        assert_eq!(text(&response), "2-1");
+    }
+
+    #[tokio::test]
+    async fn an_auth_leg_on_a_reopened_socket_fails() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "ws://{}/jet/fwd/tcp/1?token=t",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move {
+            for (socket, limit) in [(1, 1), (2, usize::MAX)] {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                tokio::spawn(answer(ws, socket, limit));
+            }
+        });
+        let client = GatewayHttpViaWsClient::new(url.parse().unwrap());
+        let leg = || request(HttpBody::Text("negotiate".to_string()));
+        let conn = ConnectionId::test_new(1);
+
+        client
+            .send_http_request(leg(), conn, OnReopen::Fail)
+            .await
+            .unwrap();
+        client
+            .send_http_request(leg(), conn, OnReopen::Fail)
+            .await
+            .unwrap_err();
+
+        let error = client
+            .send_http_request(leg(), conn, OnReopen::Fail)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("reopened during authentication"));
+    }
+
+    /// Read one HTTP request off `tcp` and return its request line.
+    async fn read_http_request(tcp: &mut tokio::net::TcpStream) -> String {
+        use tokio::io::AsyncReadExt as _;
+
+        let mut buffer = Vec::new();
+        let mut chunk = [0; 1024];
+        let end = loop {
+            if let Some(end) = header_end(&buffer) {
+                break end + 4;
+            }
+            let n = tcp.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed mid-request");
+            buffer.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buffer[..end]).into_owned();
+        let length = head
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse::<usize>().unwrap())
+            })
+            .unwrap_or(0);
+        while buffer.len() < end + length {
+            let n = tcp.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed mid-body");
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+        head.lines().next().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn reopening_refreshes_the_association_token() {
+        use tokio::io::AsyncWriteExt as _;
+        use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+        use tokio_tungstenite::tungstenite::http::StatusCode;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut tcp, _) = listener.accept().await.unwrap();
+                let mut start = [0; 4];
+                while tcp.peek(&mut start).await.unwrap() < start.len() {}
+                if &start == b"POST" {
+                    let line = read_http_request(&mut tcp).await;
+                    let token = if line.contains("/jet/webapp/app-token") {
+                        "app"
+                    } else {
+                        "fresh"
+                    };
+                    tcp.write_all(&http_response(token)).await.unwrap();
+                    continue;
+                }
+                // The gateway refuses the expired token the session started with.
+                let check = |request: &Request, response: Response| {
+                    if request.uri().query() == Some("token=fresh") {
+                        Ok(response)
+                    } else {
+                        let mut refusal = ErrorResponse::new(None);
+                        *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+                        Err(refusal)
+                    }
+                };
+                if let Ok(ws) = tokio_tungstenite::accept_hdr_async(tcp, check).await {
+                    tokio::spawn(answer(ws, 1, usize::MAX));
+                }
+            }
+        });
+        let client = GatewayHttpViaWsClient::new(
+            format!("ws://{addr}/jet/fwd/tcp/1?token=expired")
+                .parse()
+                .unwrap(),
+        )
+        .with_token_refresh(GatewayTokenConfig {
+            base_url: format!("http://{addr}"),
+            webapp_username: Some("admin".to_string()),
+            webapp_password: Some("admin".to_string()),
+            server: "server".to_string(),
+            port: 5985,
+            https: false,
+            username: "user".to_string(),
+            domain: String::new(),
+            auth_method: AuthMethod::Basic,
+            kdc_address: None,
+            kdc_proxy_url: None,
+        });
+
+        let response = client
+            .send_http_request(
+                request(HttpBody::Text("hello".to_string())),
+                ConnectionId::test_new(1),
+                OnReopen::Send,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(text(&response), "1-1");
+        let url = client.state.lock().await.websocket_url.clone();
+        assert_eq!(url.query(), Some("token=fresh"));
+    }
 }
@@ -0,0 +1,120 @@
+//! `:grid` / `--grid`: object output of a command collected into a
+//! [`GridView`] instead of being printed.
+
+use ironposh_psrp::PsValue;
+use ironposh_terminal::GridView;
+
+/// Object output collected from one command run in grid mode.
+pub struct GridCapture {
+    command: String,
+    objects: Vec<PsValue>,
+}
+
+impl GridCapture {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            objects: Vec::new(),
+        }
+    }
+
+    /// Keep `value` for the grid when it is an object with properties;
+    /// anything else is handed back to be printed as usual.
+    pub fn push(&mut self, value: PsValue) -> Option<PsValue> {
+        match &value {
+            PsValue::Object(obj) if !obj.properties.is_empty() => {
+                self.objects.push(value);
+                None
+            }
+            _ => Some(value),
+        }
+    }
+
+    /// The grid of everything kept; `None` when the command produced no
+    /// object output. Columns are the union of the objects' properties in
+    /// first-seen order.
+    pub fn into_grid(self) -> Option<GridView> {
+        if self.objects.is_empty() {
+            return None;
+        }
+
+        let mut columns: Vec<String> = Vec::new();
+        for obj in self.objects.iter().filter_map(PsValue::as_object) {
+            for (name, _) in &obj.properties {
+                if !columns.iter().any(|column| column == name) {
+                    columns.push(name.clone());
+                }
+            }
+        }
+
+        let rows = self
+            .objects
+            .iter()
+            .filter_map(PsValue::as_object)
+            .map(|obj| {
+                columns
+                    .iter()
+                    .map(|name| {
+                        obj.properties
+                            .get(name)
+                            .map(|value| single_line(&value.to_string()))
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Some(GridView::new(self.command, columns, rows))
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironposh_psrp::{ComplexObject, Properties};
+
+    fn process(name: &str, id: i32, extra: Option<&str>) -> PsValue {
+        let mut properties = Properties::new();
+        properties.insert_adapted("ProcessName", name);
+        properties.insert_adapted("Id", id);
+        if let Some(extra) = extra {
+            properties.insert_extended("Company", extra);
+        }
+        PsValue::Object(ComplexObject {
+            properties,
+            ..ComplexObject::default()
+        })
+    }
+
+    #[test]
+    fn objects_become_rows_and_the_rest_is_handed_back() {
+        let mut capture = GridCapture::new("Get-Process".to_owned());
+        assert!(capture.push(process("pwsh", 42, None)).is_none());
+        assert!(capture
+            .push(process("explorer", 7, Some("Microsoft")))
+            .is_none());
+        assert_eq!(
+            capture.push(PsValue::from("plain text")),
+            Some(PsValue::from("plain text"))
+        );
+
+        let grid = capture.into_grid().expect("object output");
+        assert_eq!(grid.columns(), ["Id", "ProcessName", "Company"]);
+        assert_eq!(grid.visible_rows(), 2);
+        assert_eq!(
+            grid.row(0),
+            Some(["42".to_owned(), "pwsh".to_owned(), String::new()].as_slice())
+        );
+    }
+
+    #[test]
+    fn no_objects_means_no_grid() {
+        let mut capture = GridCapture::new("'hi'".to_owned());
+        assert!(capture.push(PsValue::from("hi")).is_some());
+        assert!(capture.into_grid().is_none());
+    }
+}
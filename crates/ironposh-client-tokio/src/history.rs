@@ -0,0 +1,161 @@
+//! `history`: the remote session's `Get-History` merged with the commands
+//! typed in this client.
+
+use std::fmt::Write as _;
+
+/// Lists the remote history, one `Id<TAB>CommandLine` string per entry.
+pub const REMOTE_HISTORY_SCRIPT: &str =
+    "Get-History | ForEach-Object { \"$($_.Id)`t$($_.CommandLine)\" }";
+
+/// One entry of the remote `Get-History`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteHistoryEntry {
+    pub id: u64,
+    pub command: String,
+}
+
+/// Parse one line of [`REMOTE_HISTORY_SCRIPT`] output.
+pub fn parse_remote_entry(line: &str) -> Option<RemoteHistoryEntry> {
+    let (id, command) = line.split_once('\t')?;
+    Some(RemoteHistoryEntry {
+        id: id.trim().parse().ok()?,
+        command: command.to_owned(),
+    })
+}
+
+/// A line of the merged history; `id` is `None` for commands that never
+/// made it into the remote history (client commands, commands typed while
+/// disconnected, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryLine {
+    pub id: Option<u64>,
+    pub command: String,
+}
+
+impl From<&RemoteHistoryEntry> for HistoryLine {
+    fn from(entry: &RemoteHistoryEntry) -> Self {
+        Self {
+            id: Some(entry.id),
+            command: entry.command.clone(),
+        }
+    }
+}
+
+/// Merge the remote history with the local one, keeping the order of both.
+///
+/// Each local command is matched with the next remote entry of the same
+/// text; remote entries skipped over on the way (from an earlier client of
+/// a reattached session, for instance) come first.
+pub fn merge(remote: &[RemoteHistoryEntry], local: &[String]) -> Vec<HistoryLine> {
+    let mut merged = Vec::with_capacity(remote.len() + local.len());
+    let mut next = 0;
+
+    for command in local {
+        let matched = remote[next..]
+            .iter()
+            .position(|entry| entry.command.trim() == command.trim());
+        match matched {
+            Some(offset) => {
+                merged.extend(remote[next..=next + offset].iter().map(HistoryLine::from));
+                next += offset + 1;
+            }
+            None => merged.push(HistoryLine {
+                id: None,
+                command: command.clone(),
+            }),
+        }
+    }
+
+    merged.extend(remote[next..].iter().map(HistoryLine::from));
+    merged
+}
+
+/// `Get-History`-like listing; local-only commands are marked with `*`.
+pub fn render(lines: &[HistoryLine]) -> String {
+    let mut out = String::new();
+    for line in lines {
+        let id = line.id.map_or_else(|| "*".to_owned(), |id| id.to_string());
+        let mut command = line.command.lines();
+        let _ = writeln!(out, "{id:>5}  {}", command.next().unwrap_or_default());
+        for continuation in command {
+            let _ = writeln!(out, "{:>5}  {continuation}", "");
+        }
+    }
+    if lines.iter().any(|line| line.id.is_none()) {
+        out.push_str("\n    * not in the remote session's history\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(id: u64, command: &str) -> RemoteHistoryEntry {
+        RemoteHistoryEntry {
+            id,
+            command: command.to_owned(),
+        }
+    }
+
+    fn local(commands: &[&str]) -> Vec<String> {
+        commands.iter().map(|&command| command.to_owned()).collect()
+    }
+
+    #[test]
+    fn parses_id_and_command_line() {
+        assert_eq!(
+            parse_remote_entry("12\tGet-Process | Select -First 1"),
+            Some(remote(12, "Get-Process | Select -First 1"))
+        );
+        assert_eq!(parse_remote_entry("no tab here"), None);
+        assert_eq!(parse_remote_entry("x\tGet-Date"), None);
+    }
+
+    #[test]
+    fn merge_interleaves_local_only_commands() {
+        let merged = merge(
+            &[remote(1, "Get-Date"), remote(2, "dir")],
+            &local(&["Get-Date", ":disconnect", "dir", ":grid"]),
+        );
+        let ids: Vec<_> = merged.iter().map(|line| line.id).collect();
+        assert_eq!(ids, [Some(1), None, Some(2), None]);
+        assert_eq!(merged[1].command, ":disconnect");
+    }
+
+    #[test]
+    fn merge_keeps_remote_entries_typed_elsewhere() {
+        let merged = merge(
+            &[remote(1, "earlier"), remote(2, "ls"), remote(3, "later")],
+            &local(&["ls"]),
+        );
+        let ids: Vec<_> = merged.iter().map(|line| line.id).collect();
+        assert_eq!(ids, [Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn merge_matches_repeated_commands_in_order() {
+        let merged = merge(
+            &[remote(1, "ls"), remote(2, "ls")],
+            &local(&["ls", "ls", "ls"]),
+        );
+        let ids: Vec<_> = merged.iter().map(|line| line.id).collect();
+        assert_eq!(ids, [Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn render_marks_local_only_commands() {
+        let text = render(&[
+            HistoryLine {
+                id: Some(7),
+                command: "Get-Date".to_owned(),
+            },
+            HistoryLine {
+                id: None,
+                command: ":disconnect".to_owned(),
+            },
+        ]);
+        assert!(text.starts_with("    7  Get-Date\n    *  :disconnect\n"));
+        assert!(text.contains("not in the remote session's history"));
+    }
+}
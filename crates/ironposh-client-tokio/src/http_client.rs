@@ -7,14 +7,14 @@ use ironposh_client_core::connector::{
     connection_pool::TrySend,
     connection_pool::{ConnectionId, SecContextInited},
     http::HttpRequestAction,
-    http::{HttpBody, HttpRequest, HttpResponse, HttpResponseTargeted, Method},
+    http::{AddressPreference, HttpBody, HttpRequest, HttpResponse, HttpResponseTargeted, Method},
     NetworkProtocol, NetworkRequest,
 };
 use reqwest::Client;
 use std::{
     collections::HashMap,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
-    sync::Mutex,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -28,6 +28,12 @@ const MAX_KDC_RESPONSE: u32 = 2 * 1024 * 1024;
 
 /// Build a reqwest client honoring the given [`TlsOptions`] (native-tls backend).
 pub fn build_reqwest_client(tls: &TlsOptions) -> anyhow::Result<reqwest::Client> {
+    reqwest_client_builder(tls)?
+        .build()
+        .context("failed to build reqwest client")
+}
+
+fn reqwest_client_builder(tls: &TlsOptions) -> anyhow::Result<reqwest::ClientBuilder> {
     let mut builder = reqwest::Client::builder()
         .use_native_tls()
         // IMPORTANT: keep each logical `ConnectionId` on its own reqwest client to
@@ -46,11 +52,45 @@ pub fn build_reqwest_client(tls: &TlsOptions) -> anyhow::Result<reqwest::Client>
         builder = builder.add_root_certificate(cert);
     }
 
-    builder.build().context("failed to build reqwest client")
+    Ok(builder)
+}
+
+/// The host of `request` mapped to its [`HttpRequest::connect_to`] address, so the
+/// connection goes to that farm node while TLS and `Host` still use the name.
+fn pinned_resolution(request: &HttpRequest) -> anyhow::Result<Option<(String, SocketAddr)>> {
+    let Some(address) = request.connect_to else {
+        return Ok(None);
+    };
+    let url = url::Url::parse(&request.url).context("invalid request URL")?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("missing host in request URL"))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| anyhow::anyhow!("missing port in request URL"))?;
+    Ok(Some((host.to_owned(), SocketAddr::new(address, port))))
+}
+
+/// System resolver with the [`AddressPreference`] family moved to the front,
+/// since reqwest tries addresses in order.
+struct PreferringResolver(AddressPreference);
+
+impl reqwest::dns::Resolve for PreferringResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let preference = self.0;
+        Box::pin(async move {
+            let mut addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            preference.order(&mut addresses);
+            let addresses: reqwest::dns::Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
 }
 
 pub struct ReqwestHttpClient {
     tls: TlsOptions,
+    address_preference: AddressPreference,
     clients_by_conn: Mutex<HashMap<u32, reqwest::Client>>,
 }
 
@@ -76,11 +116,23 @@ impl ReqwestHttpClient {
         );
         Self {
             tls,
+            address_preference: AddressPreference::default(),
             clients_by_conn: Mutex::new(HashMap::new()),
         }
     }
 
-    fn client_for_conn(&self, conn_id: ConnectionId) -> anyhow::Result<Client> {
+    /// Address family to try first when the server name resolves to both.
+    #[must_use]
+    pub fn with_address_preference(mut self, preference: AddressPreference) -> Self {
+        self.address_preference = preference;
+        self
+    }
+
+    fn client_for_conn(
+        &self,
+        conn_id: ConnectionId,
+        request: &HttpRequest,
+    ) -> anyhow::Result<Client> {
         // Fast path: return an existing client, releasing the lock before the (slower)
         // client build so the mutex is never held across `build_reqwest_client`.
         {
@@ -93,7 +145,16 @@ impl ReqwestHttpClient {
             }
         }
 
-        let client = build_reqwest_client(&self.tls)
+        let mut builder = reqwest_client_builder(&self.tls)?;
+        if self.address_preference != AddressPreference::System {
+            builder = builder.dns_resolver(Arc::new(PreferringResolver(self.address_preference)));
+        }
+        if let Some((host, address)) = pinned_resolution(request)? {
+            debug!(host = %host, %address, "pinning connection to server address");
+            builder = builder.resolve(&host, address);
+        }
+        let client = builder
+            .build()
             .context("failed to build reqwest client for connection")?;
 
         let mut clients = self
@@ -316,6 +377,15 @@ impl ReqwestHttpClient {
         Ok(body)
     }
 
+    /// One request on a fresh client, outside any connection's auth state
+    /// (e.g. an unauthenticated `Identify` probe).
+    pub(crate) async fn send_once(
+        request: HttpRequest,
+        tls: &TlsOptions,
+    ) -> anyhow::Result<HttpResponse> {
+        Self::send_with_client(build_reqwest_client(tls)?, request).await
+    }
+
     async fn send_with_client(
         client: Client,
         request: HttpRequest,
@@ -441,7 +511,7 @@ impl HttpClient for ReqwestHttpClient {
             // === Simple path: already have an idle, encrypted channel ===
             TrySend::JustSend { request, conn_id } => {
                 info!(conn_id = conn_id.inner(), "sending on existing connection");
-                let client = self.client_for_conn(conn_id)?;
+                let client = self.client_for_conn(conn_id, &request)?;
                 let resp = Self::send_with_client(client, request).await?;
                 // No provider attached on steady-state sends
                 Ok(HttpResponseTargeted::new(resp, conn_id, None))
@@ -453,36 +523,38 @@ impl HttpClient for ReqwestHttpClient {
                 let mut auth_response: Option<HttpResponse> = None;
 
                 loop {
+                    // A configured KDC proxy carries its own TLS options.
+                    let kdc_tls = auth_sequence.kdc_proxy_tls().unwrap_or(&self.tls).clone();
+
                     // 1) Initialize security context
                     let (seq, mut holder) = auth_sequence.prepare();
-                    let init = match seq
-                        .try_init_sec_context(auth_response.as_ref(), &mut holder)?
-                    {
-                        SecContextMaybeInit::Initialized(sec) => sec,
-                        SecContextMaybeInit::RunGenerator {
-                            mut packet,
-                            mut generator_holder,
-                        } => {
-                            info!("running generator for KDC communication");
-                            loop {
-                                let kdc_resp = Self::send_kdc_network_request(packet, &self.tls)
-                                    .await
-                                    .context(
-                                        "failed to send packet to KDC during authentication",
-                                    )?;
-                                match SspiAuthSequence::resume(generator_holder, kdc_resp)? {
-                                    SecContextMaybeInit::Initialized(sec) => break sec,
-                                    SecContextMaybeInit::RunGenerator {
-                                        packet: next_packet,
-                                        generator_holder: next_holder,
-                                    } => {
-                                        packet = next_packet;
-                                        generator_holder = next_holder;
+                    let init =
+                        match seq.try_init_sec_context(auth_response.as_ref(), &mut holder)? {
+                            SecContextMaybeInit::Initialized(sec) => sec,
+                            SecContextMaybeInit::RunGenerator {
+                                mut packet,
+                                mut generator_holder,
+                            } => {
+                                info!("running generator for KDC communication");
+                                loop {
+                                    let kdc_resp = Self::send_kdc_network_request(packet, &kdc_tls)
+                                        .await
+                                        .context(
+                                            "failed to send packet to KDC during authentication",
+                                        )?;
+                                    match SspiAuthSequence::resume(generator_holder, kdc_resp)? {
+                                        SecContextMaybeInit::Initialized(sec) => break sec,
+                                        SecContextMaybeInit::RunGenerator {
+                                            packet: next_packet,
+                                            generator_holder: next_holder,
+                                        } => {
+                                            packet = next_packet;
+                                            generator_holder = next_holder;
+                                        }
                                     }
                                 }
                             }
-                        }
-                    };
+                        };
 
                     // Capture conn id before the sequence is consumed (needed for
                     // the AlreadyComplete path, which has no outgoing request).
@@ -514,7 +586,7 @@ impl HttpClient for ReqwestHttpClient {
                                 connection_id,
                                 request,
                             } = request;
-                            let client = self.client_for_conn(connection_id)?;
+                            let client = self.client_for_conn(connection_id, &request)?;
                             let resp = Self::send_with_client(client, request).await?;
                             auth_response = Some(resp);
                             auth_sequence = sequence;
@@ -533,7 +605,7 @@ impl HttpClient for ReqwestHttpClient {
                             } = request;
 
                             // Send the final (sealed) request
-                            let client = self.client_for_conn(connection_id)?;
+                            let client = self.client_for_conn(connection_id, &request)?;
                             let resp = Self::send_with_client(client, request).await?;
 
                             // Return targeted response WITH the provider attached
@@ -577,6 +649,28 @@ mod tls_tests {
         };
         assert!(build_reqwest_client(&tls).is_err());
     }
+
+    #[test]
+    fn pinned_resolution_keeps_host_and_default_port() {
+        let mut request = HttpRequest {
+            method: Method::Post,
+            url: "https://farm.example.com/wsman".to_owned(),
+            headers: Vec::new(),
+            body: None,
+            cookie: None,
+            connect_to: None,
+        };
+        assert!(pinned_resolution(&request).unwrap().is_none());
+
+        request.connect_to = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7)));
+        assert_eq!(
+            pinned_resolution(&request).unwrap(),
+            Some((
+                "farm.example.com".to_owned(),
+                "10.0.0.7:443".parse().unwrap()
+            ))
+        );
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,107 @@
+//! Kerberos KDC I/O for the `NetworkRequest`s emitted by the SSPI flow:
+//! DNS SRV discovery of the KDC and the TCP/UDP/KDC-proxy exchange itself.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::Context;
+use ironposh_client_core::connector::{
+    config::TlsOptions,
+    kdc_discovery::{
+        nameservers_from_resolv_conf, parse_srv_response, srv_query, KdcTransport, SrvRecord,
+    },
+    NetworkRequest,
+};
+use tracing::{info, instrument, warn};
+use url::Url;
+
+use crate::http_client::ReqwestHttpClient;
+
+/// Per-nameserver wait for a DNS reply.
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Send one KDC packet over the transport its URL names and return the reply
+/// in the framing sspi expects. `tls` applies to KDC proxy (http/https) URLs.
+pub async fn send_packet(packet: NetworkRequest, tls: &TlsOptions) -> anyhow::Result<Vec<u8>> {
+    ReqwestHttpClient::send_kdc_network_request(packet, tls).await
+}
+
+/// Find the KDC for `realm` through `_kerberos` DNS SRV records, asking the
+/// nameservers listed in `/etc/resolv.conf`. TCP records are preferred, since
+/// tickets with PACs rarely fit in a UDP datagram.
+#[instrument(name = "discover_kdc", level = "info", err)]
+pub async fn discover_kdc(realm: &str) -> anyhow::Result<Url> {
+    let nameservers = system_nameservers().await?;
+
+    for transport in [KdcTransport::Tcp, KdcTransport::Udp] {
+        for &nameserver in &nameservers {
+            match query_srv(nameserver, realm, transport).await {
+                Ok(records) => {
+                    if let Some(record) = records.first() {
+                        let url = record.kdc_url(transport)?;
+                        info!(%url, "discovered KDC via DNS SRV");
+                        return Ok(url);
+                    }
+                }
+                Err(error) => warn!(%nameserver, %error, "DNS SRV query failed"),
+            }
+        }
+    }
+
+    anyhow::bail!("no _kerberos SRV records found for realm {realm}")
+}
+
+/// Nameservers from `/etc/resolv.conf`; an empty list is an error.
+pub(crate) async fn system_nameservers() -> anyhow::Result<Vec<IpAddr>> {
+    let resolv_conf = tokio::fs::read_to_string("/etc/resolv.conf")
+        .await
+        .context("failed to read /etc/resolv.conf")?;
+    let nameservers = nameservers_from_resolv_conf(&resolv_conf);
+    if nameservers.is_empty() {
+        anyhow::bail!("no nameservers configured in /etc/resolv.conf");
+    }
+    Ok(nameservers)
+}
+
+/// Random DNS transaction id.
+pub(crate) fn dns_query_id() -> u16 {
+    let uuid = uuid::Uuid::new_v4();
+    u16::from_be_bytes([uuid.as_bytes()[0], uuid.as_bytes()[1]])
+}
+
+async fn query_srv(
+    nameserver: IpAddr,
+    realm: &str,
+    transport: KdcTransport,
+) -> anyhow::Result<Vec<SrvRecord>> {
+    let id = dns_query_id();
+    let query = srv_query(realm, transport, id)?;
+    let response = dns_exchange(nameserver, &query).await?;
+    Ok(parse_srv_response(&response, id)?)
+}
+
+/// Send one DNS query to `nameserver` over UDP and return the raw reply.
+pub(crate) async fn dns_exchange(nameserver: IpAddr, query: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let local_addr = match nameserver {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    };
+    let socket = tokio::net::UdpSocket::bind(local_addr)
+        .await
+        .context("failed to bind UDP socket for DNS query")?;
+    socket
+        .connect((nameserver, 53))
+        .await
+        .context("failed to connect UDP socket to nameserver")?;
+    socket
+        .send(query)
+        .await
+        .context("failed to send DNS query")?;
+
+    let mut response = [0_u8; 4096];
+    let len = tokio::time::timeout(DNS_TIMEOUT, socket.recv(&mut response))
+        .await
+        .context("timed out waiting for DNS response")?
+        .context("failed to read DNS response")?;
+    Ok(response[..len].to_vec())
+}
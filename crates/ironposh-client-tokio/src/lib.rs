@@ -1,5 +1,6 @@
-//! Library target exposing the HTTP client internals so integration tests
-//! (e.g. `tests/tls_options.rs`) can exercise them. The binary in `main.rs`
-//! compiles the same module directly via `mod http_client;`.
+//! Library target exposing the HTTP client internals and the Kerberos KDC
+//! helpers so integration tests (e.g. `tests/tls_options.rs`) and embedders can
+//! use them. The binary in `main.rs` compiles the same modules directly.
 
 pub mod http_client;
+pub mod kerberos;
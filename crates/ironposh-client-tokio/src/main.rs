@@ -1,20 +1,37 @@
+mod aliases;
+mod audit_log;
 mod config;
+mod continuation;
+mod crash;
+mod discovery;
 mod gateway_http_client;
+mod grid;
+mod history;
 mod hostcall;
 mod http_client;
+mod kerberos;
+mod profile;
+mod quarantine;
 mod repl;
+mod spinner;
+mod tee;
+mod timezone;
 mod types;
 
 use clap::Parser;
 use futures::StreamExt;
-use ironposh_async::RemoteAsyncPowershellClient;
-use ironposh_terminal::Terminal;
+use ironposh_async::{RemoteAsyncPowershellClient, SessionHooks};
+use ironposh_client_core::connector::http::AddressPreference;
+use ironposh_client_core::user_message::{EnglishCatalog, UserMessage};
+use ironposh_client_core::RemoteError;
+use ironposh_terminal::{StatusLine, Terminal};
 use std::sync::Arc;
 use tracing::{debug, error, info, instrument, warn};
 
 use config::{
     build_reattach_command_prefix, build_reattach_credentials_hint, create_connector_config,
-    create_connector_config_with_kdc_url, init_logging, validate_gateway_flags, Args,
+    create_connector_config_with_kdc_url, init_logging, validate_gateway_flags, Args, AuthMethod,
+    Mode, TeeFormat,
 };
 use gateway_http_client::{
     create_gateway_session, redact_gateway_url, CliHttpClient, GatewayHttpViaWsClient,
@@ -22,16 +39,56 @@ use gateway_http_client::{
 };
 use http_client::ReqwestHttpClient;
 
+/// How long past `--command-timeout` to wait for the server to confirm the
+/// pipeline stopped before giving up on it.
+const COMMAND_STOP_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Summarize how the background connection task ended, for surfacing to the user
 /// when it dies before the command completes (e.g. the server rejected auth).
 fn describe_connection_end(joined: Result<anyhow::Result<()>, tokio::task::JoinError>) -> String {
     match joined {
         Ok(Ok(())) => "connection closed unexpectedly".to_string(),
-        Ok(Err(e)) => e.to_string(),
+        Ok(Err(e)) => UserMessage::find(e.as_ref())
+            .map_or_else(|| e.to_string(), |message| message.render(&EnglishCatalog)),
         Err(e) => format!("connection task panicked: {e}"),
     }
 }
 
+/// `--tee` for `--command`: copy a printed line to the tee file. A write
+/// failure closes the file and is reported on stderr, the output goes on.
+fn tee_line(
+    tee: &mut tee::Tee,
+    pipeline: &ironposh_client_core::powershell::PipelineHandle,
+    stream: tee::TeeStream,
+    text: &str,
+) {
+    if let Some(failure) = tee.write(Some(pipeline.id()), stream, text) {
+        warn!(%failure, "tee file closed");
+        eprintln!("{failure}");
+    }
+}
+
+/// Surface what the `--env` setup pipeline could not set; it writes nothing
+/// otherwise.
+async fn report_environment_errors(
+    mut events: futures::channel::mpsc::Receiver<
+        ironposh_client_core::connector::active_session::UserEvent,
+    >,
+) {
+    while let Some(event) = events.next().await {
+        if let ironposh_client_core::connector::active_session::UserEvent::ErrorRecord {
+            error_record,
+            ..
+        } = event
+        {
+            warn!(
+                error = %error_record.render_concise(),
+                "failed to set a session environment variable"
+            );
+        }
+    }
+}
+
 #[tokio::main]
 #[instrument(name = "main", level = "info")]
 async fn main() -> anyhow::Result<()> {
@@ -39,11 +96,33 @@ async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
     // Initialize logging with the specified verbosity level
-    init_logging(args.verbose)?;
+    init_logging(&args)?;
     info!("Starting WinRM PowerShell client (Async/Tokio)");
 
+    if let Some(domain) = &args.discover {
+        let candidates = discovery::discover_endpoints(domain.trim()).await?;
+        print!("{}", discovery::render(domain.trim(), &candidates));
+        return Ok(());
+    }
+
+    if let Some(Mode::Replay(replay)) = &args.mode {
+        let cast = ironposh_terminal::Cast::load(&replay.path)?;
+        let options = ironposh_terminal::ReplayOptions {
+            speed: replay.speed,
+            max_idle: replay.max_idle.map(std::time::Duration::from_secs_f64),
+        };
+        info!(path = %replay.path.display(), events = cast.events.len(), ?options, "replaying recording");
+        return ironposh_terminal::cast::play(&cast, &options);
+    }
+
     let gateway_enabled = args.gateway.is_some();
 
+    // Read the profile before connecting so mistakes in it fail fast.
+    let profile = match args.profile.as_deref() {
+        Some(path) => profile::Profile::load(path)?,
+        None => profile::Profile::default(),
+    };
+
     // Validate gateway-specific flag combinations before any network call to the gateway.
     validate_gateway_flags(&args)?;
 
@@ -64,24 +143,36 @@ async fn main() -> anyhow::Result<()> {
     // Create terminal early to get real dimensions for PowerShell host info
     let scrollback_lines = 2000;
     let mut terminal = Terminal::new(scrollback_lines)?;
-    let (cols, rows) = terminal.size()?;
+    terminal.set_key_bindings(profile.keys);
+    terminal.set_clipboard_policy(args.clipboard.into());
+    if args.status_line {
+        terminal.set_status_line(Some(StatusLine::new(args.server.clone())))?;
+    }
+    if args.power_saving {
+        terminal.set_power_saving(true)?;
+    }
+    if let Some(path) = args.record.as_deref() {
+        terminal.start_recording(path)?;
+    }
+    // The guest screen, not the host: the status bar is not the remote's.
+    let (rows, cols) = terminal.guest_screen_size();
     info!("Terminal created with size: {}x{}", cols, rows);
 
-    let gateway_session = if let Some(gateway) = &args.gateway {
-        let session = create_gateway_session(&GatewayTokenConfig {
-            base_url: gateway.clone(),
-            webapp_username: args.gateway_webapp_username.clone(),
-            webapp_password: args.gateway_webapp_password.clone(),
-            server: args.server.clone(),
-            port: args.port,
-            https: args.https,
-            username: args.username.clone(),
-            domain: args.domain.clone(),
-            auth_method: args.auth_method,
-            kdc_address: args.kdc_address.clone(),
-            kdc_proxy_url: args.kdc_proxy_url.clone(),
-        })
-        .await?;
+    let gateway_token_config = args.gateway.as_ref().map(|gateway| GatewayTokenConfig {
+        base_url: gateway.clone(),
+        webapp_username: args.gateway_webapp_username.clone(),
+        webapp_password: args.gateway_webapp_password.clone(),
+        server: args.server.clone(),
+        port: args.port,
+        https: args.https,
+        username: args.username.clone(),
+        domain: args.domain.clone(),
+        auth_method: args.auth_method,
+        kdc_address: args.kdc_address.clone(),
+        kdc_proxy_url: args.kdc_proxy_url.clone(),
+    });
+    let gateway_session = if let Some(token_config) = &gateway_token_config {
+        let session = create_gateway_session(token_config).await?;
         let redacted_gateway_url = redact_gateway_url(&session.websocket_url);
         info!(
             gateway_url = %redacted_gateway_url,
@@ -96,15 +187,54 @@ async fn main() -> anyhow::Result<()> {
     // Create configuration and HTTP client with real terminal dimensions
     let config = if let Some(session) = gateway_session.as_ref() {
         create_connector_config_with_kdc_url(&args, cols, rows, session.kdc_proxy_url.clone())?
+    } else if matches!(
+        args.auth_method,
+        AuthMethod::Kerberos | AuthMethod::Negotiate
+    ) && !args.domain.trim().is_empty()
+    {
+        // Find the KDC for the realm; if DNS has nothing, sspi falls back to its own discovery.
+        let kdc_url = kerberos::discover_kdc(args.domain.trim())
+            .await
+            .inspect_err(|e| warn!(error = %e, "KDC discovery failed"))
+            .ok();
+        create_connector_config_with_kdc_url(&args, cols, rows, kdc_url)?
     } else {
         create_connector_config(&args, cols, rows)?
     };
     // TLS options apply to direct connections only; the gateway path owns its own transport.
-    let http_client = gateway_session.map_or_else(
-        || CliHttpClient::Direct(ReqwestHttpClient::with_tls_options(config.tls.clone())),
-        |session| CliHttpClient::Gateway(GatewayHttpViaWsClient::new(session.websocket_url)),
+    // The token config lets the gateway client mint a fresh association token
+    // when it has to reopen the WebSocket after the first one expired.
+    let http_client = gateway_session.zip(gateway_token_config).map_or_else(
+        || {
+            CliHttpClient::Direct(
+                ReqwestHttpClient::with_tls_options(config.tls.clone()).with_address_preference(
+                    args.prefer_ip
+                        .map(AddressPreference::from)
+                        .unwrap_or_default(),
+                ),
+            )
+        },
+        |(session, token_config)| {
+            CliHttpClient::Gateway(
+                GatewayHttpViaWsClient::new(session.websocket_url).with_token_refresh(token_config),
+            )
+        },
     );
 
+    let mut hooks = SessionHooks::default();
+    if let Some(path) = args.audit_log.as_deref() {
+        info!(path = %path.display(), "appending pipeline audit entries");
+        hooks = hooks.with_audit_log(Arc::new(audit_log::FileAuditLog::open(path)?));
+    }
+    let tee_sink = match args.tee.as_deref() {
+        Some(path) => {
+            let format = args.tee_format.unwrap_or_else(|| TeeFormat::for_path(path));
+            info!(path = %path.display(), ?format, "teeing pipeline output");
+            Some(tee::TeeSink::open(path, format)?)
+        }
+        None => None,
+    };
+
     // Create the PowerShell client (serial by default, --parallel for multi-connection)
     let (mut client, host_io, session_event_rx, lifecycle_event_rx, connection_task): (
         _,
@@ -123,7 +253,12 @@ async fn main() -> anyhow::Result<()> {
             session_events,
             lifecycle_events,
             connection_task,
-        } = RemoteAsyncPowershellClient::open_task(config, args.connect_shell_id, http_client);
+        } = RemoteAsyncPowershellClient::open_task(
+            config,
+            args.connect_shell_id,
+            http_client,
+            hooks,
+        );
         (
             client,
             host_io,
@@ -140,7 +275,7 @@ async fn main() -> anyhow::Result<()> {
         }
         info!("Using serial (single-connection) session loop");
         let (client, host_io, session_events, task) =
-            RemoteAsyncPowershellClient::open_task_serial(config, http_client);
+            RemoteAsyncPowershellClient::open_task_serial(config, None, http_client, hooks);
         // Serial mode does not support disconnect/reconnect; provide an inert channel.
         let (_inert_lifecycle_tx, lifecycle_events) = futures::channel::mpsc::unbounded();
         (
@@ -152,6 +287,35 @@ async fn main() -> anyhow::Result<()> {
         )
     };
 
+    let session_event_rx = match &args.quarantine_dir {
+        Some(dir) => quarantine::save_from_events(dir.clone(), session_event_rx),
+        None => session_event_rx,
+    };
+
+    // From here on a panic restores the terminal and deletes the shell before
+    // the process exits.
+    let (connection_done_tx, connection_done_rx) = std::sync::mpsc::channel();
+    let quarantine_dir = args.quarantine_dir.clone();
+    let connection_task = async move {
+        let result = connection_task.await;
+        if let (Err(e), Some(dir)) = (&result, &quarantine_dir) {
+            quarantine::save_from_error(dir, e);
+        }
+        let _ = connection_done_tx.send(());
+        result
+    };
+    crash::install_panic_hook(client.clone(), connection_done_rx);
+
+    // Queued ahead of any command, so the first one already sees them;
+    // `--env` wins over the profile for the same name.
+    let mut env = profile.env;
+    env.extend(args.env.iter().cloned());
+    if !env.is_empty() {
+        info!(count = env.len(), "setting session environment variables");
+        let events = client.set_environment(&env).await?;
+        tokio::spawn(report_environment_errors(events));
+    }
+
     // Extract host I/O for handling host calls
     let (host_call_rx, submitter) = host_io.into_parts();
     let (ui_tx, ui_rx) = tokio::sync::mpsc::channel(100); // For future UI integration
@@ -193,10 +357,29 @@ async fn main() -> anyhow::Result<()> {
         // successful run (stream closes *after* the command completed) from a failure
         // (stream closes because the connection task died first).
         let mut command_completed = false;
+        // Set when `--command-timeout` elapsed and the pipeline was stopped.
+        let mut timed_out: Option<std::time::Duration> = None;
+        // With `--stop-on-error`, the error that stopped the pipeline.
+        let mut first_error: Option<RemoteError> = None;
+        let command_timeout = args.command_timeout.map(std::time::Duration::from_secs);
+        let options = ironposh_client_core::pipeline::PipelineOptions {
+            timeout: command_timeout,
+            stop_on_error: args.stop_on_error,
+        };
+        // The session stops the pipeline when `--command-timeout` elapses. If the
+        // server never confirms the stop, give up on our own this long after.
+        let hard_deadline = command_timeout
+            .map(|timeout| tokio::time::Instant::now() + timeout + COMMAND_STOP_GRACE);
+        let mut current_pipeline = None;
+        // Output objects printed so far; reported if the command times out.
+        let mut outputs_received = 0usize;
+        let mut spinner = spinner::Spinner::new(command_timeout);
+        let mut spinner_tick = tokio::time::interval(spinner::TICK);
+        let mut tee = tee::Tee::new(tee_sink);
 
         // Execute command (raw output to inspect PSValue representation)
         let stream_or_dead = tokio::select! {
-            res = client.send_script_raw(command) => Some(res?),
+            res = client.send_script_raw_with_options(command, options) => Some(res?),
             joined = &mut connection_handle => {
                 connection_error = Some(describe_connection_end(joined));
                 None
@@ -218,13 +401,35 @@ async fn main() -> anyhow::Result<()> {
                     connection_error = Some(describe_connection_end(joined));
                     None
                 }
+                _ = spinner_tick.tick(), if spinner.enabled() => {
+                    spinner.draw();
+                    continue;
+                }
+                () = async {
+                    match hard_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => futures::future::pending().await,
+                    }
+                } => {
+                    warn!("pipeline was not stopped by the server after the command timeout");
+                    if let Some(pipeline) = current_pipeline.take() {
+                        if let Err(e) = client.kill_pipeline(pipeline).await {
+                            warn!(error = %e, "failed to stop the timed out pipeline");
+                        }
+                    }
+                    command_completed = true;
+                    timed_out = command_timeout;
+                    break;
+                }
             };
             let Some(event) = event else { break };
+            spinner.clear();
             match event {
                 ironposh_client_core::connector::active_session::UserEvent::PipelineCreated {
                     pipeline,
                 } => {
                     info!(pipeline = ?pipeline, "pipeline created");
+                    current_pipeline = Some(pipeline);
                 }
                 ironposh_client_core::connector::active_session::UserEvent::PipelineFinished {
                     pipeline,
@@ -232,14 +437,31 @@ async fn main() -> anyhow::Result<()> {
                     info!(pipeline = ?pipeline, "pipeline finished");
                     command_completed = true;
                 }
+                ironposh_client_core::connector::active_session::UserEvent::PipelineTimedOut {
+                    pipeline,
+                    timeout,
+                } => {
+                    warn!(pipeline = ?pipeline, ?timeout, "pipeline timed out");
+                    // The session is still alive; only the command failed.
+                    command_completed = true;
+                    timed_out = Some(timeout);
+                }
                 ironposh_client_core::connector::active_session::UserEvent::PipelineOutput {
                     output,
-                    pipeline: _,
+                    pipeline,
+                    data_len,
                 } => {
                     debug!(output = ?output, "pipeline output (raw)");
+                    outputs_received += 1;
+                    let handle =
+                        ironposh_client_core::powershell::PipelineHandle::new(pipeline.id());
+                    if let Err(e) = client.acknowledge_output(handle, data_len).await {
+                        warn!(error = %e, "failed to acknowledge pipeline output");
+                    }
                     match output.format_as_displyable_string() {
                         Ok(text) => {
                             println!("{text}");
+                            tee_line(&mut tee, &pipeline, tee::TeeStream::Output, &text);
                         }
                         Err(e) => {
                             error!(error = %e, "failed to format pipeline output");
@@ -256,11 +478,16 @@ async fn main() -> anyhow::Result<()> {
                         error_record = ?error_record,
                         "received error record"
                     );
-                    println!("{}", error_record.render_concise());
+                    let text = error_record.render_concise();
+                    println!("{text}");
+                    tee_line(&mut tee, &handle, tee::TeeStream::Error, &text);
+                    if args.stop_on_error && first_error.is_none() {
+                        first_error = Some(RemoteError::from(&error_record));
+                    }
                 }
                 ironposh_client_core::connector::active_session::UserEvent::PipelineRecord {
                     record,
-                    pipeline: _,
+                    pipeline,
                 } => {
                     use ironposh_client_core::psrp_record::PsrpRecord;
                     debug!(record = ?record, "pipeline record (raw)");
@@ -268,12 +495,15 @@ async fn main() -> anyhow::Result<()> {
                     match record {
                         PsrpRecord::Debug { message, .. } => {
                             println!("[debug] {message}");
+                            tee_line(&mut tee, &pipeline, tee::TeeStream::Debug, &message);
                         }
                         PsrpRecord::Verbose { message, .. } => {
                             println!("[verbose] {message}");
+                            tee_line(&mut tee, &pipeline, tee::TeeStream::Verbose, &message);
                         }
                         PsrpRecord::Warning { message, .. } => {
                             println!("[warning] {message}");
+                            tee_line(&mut tee, &pipeline, tee::TeeStream::Warning, &message);
                         }
                         PsrpRecord::Information { record, .. } => {
                             let text = match record.message_data {
@@ -284,6 +514,7 @@ async fn main() -> anyhow::Result<()> {
                                 ironposh_psrp::InformationMessageData::Object(v) => v.to_string(),
                             };
                             println!("[information] {text}");
+                            tee_line(&mut tee, &pipeline, tee::TeeStream::Information, &text);
                         }
                         PsrpRecord::Progress { record, .. } => {
                             let status = record.status_description.unwrap_or_default();
@@ -299,6 +530,7 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        drop(spinner);
         // Clean up. If the command completed (we saw PipelineFinished), the session
         // loop is still alive waiting for more input — just stop it; success pays no
         // shutdown delay. If the stream ended WITHOUT the command completing, the
@@ -339,6 +571,15 @@ async fn main() -> anyhow::Result<()> {
                 anyhow::bail!("connection failed: {err}");
             }
         }
+        if let Some(timeout) = timed_out {
+            anyhow::bail!(
+                "command timed out after {}s and was stopped; the {outputs_received} output object(s) received before then are shown above",
+                timeout.as_secs()
+            );
+        }
+        if let Some(err) = first_error {
+            anyhow::bail!("command stopped at its first error:\n{err:#}");
+        }
     } else {
         // Interactive mode: simple REPL
         info!("starting simple interactive mode");
@@ -356,6 +597,11 @@ async fn main() -> anyhow::Result<()> {
             lifecycle_event_rx,
             repl::ReplSessionOptions {
                 disconnect_supported: args.parallel && !gateway_enabled,
+                page_output: args.page,
+                tee: tee_sink,
+                grid_output: args.grid,
+                prompt: args.prompt,
+                aliases: profile.aliases,
                 reattach_command_prefix,
                 reattach_credentials_hint,
             },
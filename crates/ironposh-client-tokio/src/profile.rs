@@ -0,0 +1,153 @@
+//! The client profile (`--profile <PATH>`): local settings for interactive
+//! sessions, in sections of `name = value` lines.
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! [aliases]
+//! ll = Get-ChildItem -Force | Format-Table
+//!
+//! [keys]
+//! mode = vi
+//! ctrl+x = detach-session
+//!
+//! [env]
+//! DEPLOY_TAG = canary
+//! LANG
+//! ```
+
+use anyhow::{bail, Context};
+use std::path::Path;
+
+use ironposh_terminal::KeyBindings;
+
+use crate::aliases::AliasTable;
+use crate::config::parse_env_var;
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    pub aliases: AliasTable,
+    /// Line editor keymap: `mode = emacs|vi` and `<key> = <action>` lines.
+    pub keys: KeyBindings,
+    /// Set in the session when it starts, before any `--env`: `NAME = value`
+    /// lines, or a bare `NAME` to forward the local value.
+    pub env: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Section {
+    Aliases,
+    Keys,
+    Env,
+}
+
+impl Profile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read profile {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("invalid profile {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut profile = Self::default();
+        let mut section = None;
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(match name.trim().to_lowercase().as_str() {
+                    "aliases" => Section::Aliases,
+                    "keys" => Section::Keys,
+                    "env" => Section::Env,
+                    other => bail!("line {number}: unknown section [{other}]"),
+                });
+                continue;
+            }
+            match section {
+                Some(Section::Aliases) => profile
+                    .aliases
+                    .define(line)
+                    .map_err(|e| anyhow::anyhow!("line {number}: {e}"))?,
+                // Actions never contain `=`, so `ctrl+= = ignore` splits right.
+                Some(Section::Keys) => line
+                    .rsplit_once('=')
+                    .ok_or_else(|| format!("expected '<key> = <action>', got '{line}'"))
+                    .and_then(|(key, action)| profile.keys.set(key, action))
+                    .map_err(|e| anyhow::anyhow!("line {number}: {e}"))?,
+                Some(Section::Env) => {
+                    let var = match line.split_once('=') {
+                        Some((name, value)) => format!("{}={}", name.trim(), value.trim()),
+                        None => line.to_owned(),
+                    };
+                    let var =
+                        parse_env_var(&var).map_err(|e| anyhow::anyhow!("line {number}: {e}"))?;
+                    profile.env.push(var);
+                }
+                None => bail!("line {number}: '{line}' is outside a [section]"),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_section_defines_aliases() {
+        let profile = Profile::parse(
+            "# my profile\n\n[Aliases]\nll = Get-ChildItem -Force | Format-Table\n  gs=Get-Service\n",
+        )
+        .expect("valid profile");
+        assert_eq!(
+            profile.aliases.get("ll"),
+            Some("Get-ChildItem -Force | Format-Table")
+        );
+        assert_eq!(profile.aliases.get("gs"), Some("Get-Service"));
+    }
+
+    #[test]
+    fn keys_section_configures_the_line_editor() {
+        let profile =
+            Profile::parse("[keys]\nmode = vi\nctrl+x = detach-session\n").expect("valid profile");
+        assert_eq!(profile.keys.mode(), ironposh_terminal::EditMode::Vi);
+
+        let err = Profile::parse("[keys]\nctrl+x = launch-rockets\n").unwrap_err();
+        assert!(err.to_string().contains("unknown key action"), "{err}");
+    }
+
+    #[test]
+    fn env_section_lists_variables_for_the_session() {
+        let profile =
+            Profile::parse("[env]\nDEPLOY_TAG = canary build\nPATH\n").expect("valid profile");
+        assert_eq!(
+            profile.env,
+            [
+                ("DEPLOY_TAG".to_owned(), "canary build".to_owned()),
+                (
+                    "PATH".to_owned(),
+                    std::env::var("PATH").expect("PATH is set")
+                ),
+            ]
+        );
+
+        let err = Profile::parse("[env]\nIRONPOSH_SURELY_UNSET_VARIABLE\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let err = Profile::parse("[aliases]\nll Get-ChildItem\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+        let err = Profile::parse("ll = dir\n").unwrap_err();
+        assert!(err.to_string().contains("outside a [section]"), "{err}");
+        let err = Profile::parse("[colors]\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown section [colors]"),
+            "{err}"
+        );
+    }
+}
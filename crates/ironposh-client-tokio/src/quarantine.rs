@@ -0,0 +1,104 @@
+//! `--quarantine-dir`: save whole the PSRP fragments and messages that fail
+//! to decode, so they can be attached to a bug report.
+
+use std::path::{Path, PathBuf};
+
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use futures::StreamExt;
+use ironposh_async::SessionEvent;
+use ironposh_client_core::PwshCoreError;
+use ironposh_psrp::{BlobDiagnostics, PowerShellRemotingError};
+use tracing::{info, warn};
+
+/// Write the blob to a new file in `dir`, named after the time and the
+/// message's object and first fragment id.
+pub fn save(dir: &Path, diagnostics: &BlobDiagnostics) -> std::io::Result<PathBuf> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "psrp-{nanos}-{}-{}.bin",
+        diagnostics.origin.object_id,
+        diagnostics.origin.fragment_ids.start()
+    ));
+    std::fs::write(&path, &diagnostics.blob)?;
+    Ok(path)
+}
+
+/// Save the blob carried by the error that ended the session, if any.
+pub fn save_from_error(dir: &Path, error: &anyhow::Error) {
+    let diagnostics = error.chain().find_map(|e| {
+        e.downcast_ref::<PwshCoreError>()
+            .and_then(PwshCoreError::blob_diagnostics)
+            .or_else(|| {
+                e.downcast_ref::<PowerShellRemotingError>()
+                    .and_then(PowerShellRemotingError::diagnostics)
+            })
+    });
+    if let Some(diagnostics) = diagnostics {
+        report(dir, diagnostics);
+    }
+}
+
+/// Pass session events through, saving the blob of each record that lenient
+/// parsing skipped.
+pub fn save_from_events(
+    dir: PathBuf,
+    mut events: UnboundedReceiver<SessionEvent>,
+) -> UnboundedReceiver<SessionEvent> {
+    let (tx, rx) = mpsc::unbounded();
+    tokio::spawn(async move {
+        while let Some(event) = events.next().await {
+            if let SessionEvent::ProtocolWarning {
+                diagnostics: Some(diagnostics),
+                ..
+            } = &event
+            {
+                report(&dir, diagnostics);
+            }
+            // Nobody may be reading the events; keep saving regardless.
+            let _ = tx.unbounded_send(event);
+        }
+    });
+    rx
+}
+
+fn report(dir: &Path, diagnostics: &BlobDiagnostics) {
+    match save(dir, diagnostics) {
+        Ok(path) => {
+            info!(path = %path.display(), "saved undecodable PSRP data");
+            eprintln!("Saved undecodable PSRP data to {}", path.display());
+        }
+        Err(error) => {
+            warn!(%error, dir = %dir.display(), "failed to save undecodable PSRP data");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironposh_psrp::{Defragmenter, Fragment};
+
+    #[test]
+    fn a_rejected_fragment_is_saved_whole() {
+        let payload = vec![0xab; 1000];
+        let fragment = Fragment::new(9, 4, payload.clone(), false, true);
+        let err = Defragmenter::new()
+            .defragment(&fragment.pack())
+            .unwrap_err();
+        let dir = std::env::temp_dir().join(format!("ironposh-quarantine-{}", std::process::id()));
+
+        let path = save(&dir, err.diagnostics().expect("diagnosed")).expect("saved");
+
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .ends_with("-9-4.bin"));
+        assert_eq!(std::fs::read(&path).unwrap(), payload);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
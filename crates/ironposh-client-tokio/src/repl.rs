@@ -3,7 +3,11 @@ use ironposh_async::PoolLifecycleEvent;
 use ironposh_async::RemoteAsyncPowershellClient;
 use ironposh_async::SessionEvent;
 use ironposh_client_core::connector::active_session::UserEvent;
-use ironposh_terminal::Terminal;
+use ironposh_client_core::pipeline::{
+    script_input_chunks, PipelineCommand, PipelineOptions, PipelineSpec, SCRIPT_INPUT_CHUNK_CHARS,
+};
+use ironposh_psrp::PipelineOutput;
+use ironposh_terminal::{CommandStatus, Pager, StdTerm, Terminal};
 use std::collections::VecDeque;
 use std::fmt::Write as _;
 #[cfg(windows)]
@@ -12,8 +16,14 @@ use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::oneshot;
 use tracing::{debug, error, info, warn};
 
-use crate::types::TerminalOperation;
+use crate::aliases::AliasTable;
+use crate::config::{PromptMode, TeeFormat};
+use crate::continuation::{self, PendingInput};
+use crate::grid::GridCapture;
+use crate::history::{self, RemoteHistoryEntry};
+use crate::tee::{split_tee_prefix, Tee, TeeSink, TeeStream};
 use crate::types::{HostUiRequest, HostUiResponse, ReplControl};
+use crate::types::{StatusUpdate, TerminalOperation};
 
 #[cfg(windows)]
 static CONSOLE_CTRL_C_PENDING: AtomicBool = AtomicBool::new(false);
@@ -118,6 +128,10 @@ enum UserInput {
     Cmd(String),
     Interrupt,
     Eof,
+    /// The `detach-session` key: same as typing `:disconnect`.
+    Detach,
+    /// The terminal window gained (`true`) or lost focus, in power saving.
+    Focus(bool),
 }
 
 #[derive(Debug)]
@@ -129,10 +143,32 @@ struct TabCompletionRequest {
 
 pub struct ReplSessionOptions {
     pub disconnect_supported: bool,
+    /// Page every command's output (`--page`); `:page <command>` flips it
+    /// for a single command.
+    pub page_output: bool,
+    /// Show object output in the grid view (`--grid`); `:grid <command>`
+    /// flips it for a single command.
+    pub grid_output: bool,
+    /// Session-wide copy of the output (`--tee`); `%tee` changes it and
+    /// `:tee <path> <command>` adds one for a single command.
+    pub tee: Option<TeeSink>,
+    /// Where the prompt shown after each command comes from (`--prompt`).
+    pub prompt: PromptMode,
+    /// Client-side aliases, expanded before a command is sent.
+    pub aliases: AliasTable,
     pub reattach_command_prefix: String,
     pub reattach_credentials_hint: String,
 }
 
+/// Strip a leading toggle word such as `:page` (any case) from a command
+/// line. Toggles flip a display option for that one command.
+fn strip_toggle_prefix<'a>(cmd: &'a str, toggle: &str) -> Option<&'a str> {
+    let rest = cmd.get(toggle.len()..)?;
+    let is_prefix = cmd[..toggle.len()].eq_ignore_ascii_case(toggle)
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace));
+    is_prefix.then(|| rest.trim_start())
+}
+
 fn escape_ps_single_quoted(input: &str) -> String {
     input.replace('\'', "''")
 }
@@ -201,14 +237,19 @@ async fn tab_complete_line(
 
     while let Some(ev) = stream.next().await {
         match ev {
-            UserEvent::PipelineOutput { output: out, .. } => {
+            UserEvent::PipelineOutput {
+                output: out,
+                pipeline,
+                data_len,
+            } => {
+                client.acknowledge_output(pipeline, data_len).await?;
                 info!(?out.data, "tab completion output");
                 output = Some(out.data);
             }
             UserEvent::ErrorRecord { error_record, .. } => {
                 warn!(error = %error_record.render_concise(), "tab completion error record");
             }
-            UserEvent::PipelineFinished { .. } => break,
+            UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => break,
             UserEvent::PipelineCreated { .. } | UserEvent::PipelineRecord { .. } => {}
         }
     }
@@ -266,6 +307,10 @@ async fn run_script_and_forward_nested(
                 }
             }
             Some(ui_msg) = user_input_rx.recv() => {
+                if let UserInput::Focus(focused) = ui_msg {
+                    client.set_power_saving(!focused).await?;
+                    continue;
+                }
                 if matches!(ui_msg, UserInput::Interrupt) {
                     if let Some(h) = pipeline.take() {
                         info!("Interrupt received; killing pipeline");
@@ -280,10 +325,11 @@ async fn run_script_and_forward_nested(
                     UserEvent::PipelineCreated { pipeline: p } => {
                         pipeline = Some(p);
                     }
-                    UserEvent::PipelineFinished { .. } => {
+                    UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => {
                         break;
                     }
-                    UserEvent::PipelineOutput { output, .. } => {
+                    UserEvent::PipelineOutput { output, pipeline: p, data_len } => {
+                        client.acknowledge_output(p, data_len).await?;
                         let text = output
                             .format_as_displyable_string()
                             .unwrap_or_else(|e| format!("Error formatting output: {e}"));
@@ -416,7 +462,15 @@ async fn run_nested_prompt_loop(
                     UserInput::Interrupt => {
                         // just reprompt
                     }
+                    UserInput::Detach => {
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Print(
+                                "leave the nested prompt before detaching".to_string(),
+                            ))
+                            .await;
+                    }
                     UserInput::Eof => break,
+                    UserInput::Focus(focused) => client.set_power_saving(!focused).await?,
                 }
             }
         }
@@ -440,9 +494,11 @@ fn sanitize_prompt(mut prompt: String) -> String {
     prompt
 }
 
-async fn fetch_remote_prompt(client: &mut RemoteAsyncPowershellClient) -> Option<String> {
-    // Use PowerShell's `prompt` function so user customizations ($PROFILE, etc.) are reflected.
-    let mut stream = match client.send_script("prompt".to_string()).await {
+async fn fetch_remote_prompt(
+    client: &mut RemoteAsyncPowershellClient,
+    script: &str,
+) -> Option<String> {
+    let mut stream = match client.send_script(script.to_string()).await {
         Ok(stream) => stream.boxed(),
         Err(e) => {
             warn!(error = %e, "failed to request remote prompt; falling back");
@@ -454,7 +510,12 @@ async fn fetch_remote_prompt(client: &mut RemoteAsyncPowershellClient) -> Option
 
     while let Some(ev) = stream.next().await {
         match ev {
-            UserEvent::PipelineOutput { output, .. } => {
+            UserEvent::PipelineOutput {
+                output,
+                pipeline,
+                data_len,
+            } => {
+                client.acknowledge_output(pipeline, data_len).await.ok()?;
                 match output.format_as_displyable_string() {
                     Ok(text) => {
                         if !text.trim().is_empty() {
@@ -469,7 +530,7 @@ async fn fetch_remote_prompt(client: &mut RemoteAsyncPowershellClient) -> Option
             UserEvent::ErrorRecord { error_record, .. } => {
                 warn!(error = %error_record.render_concise(), "remote prompt command returned an error");
             }
-            UserEvent::PipelineFinished { .. } => break,
+            UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => break,
             UserEvent::PipelineCreated { .. } | UserEvent::PipelineRecord { .. } => {}
         }
     }
@@ -481,6 +542,58 @@ async fn fetch_remote_prompt(client: &mut RemoteAsyncPowershellClient) -> Option
     prompt
 }
 
+async fn fetch_remote_history(
+    client: &mut RemoteAsyncPowershellClient,
+) -> anyhow::Result<Vec<RemoteHistoryEntry>> {
+    let mut stream = client
+        .send_script_raw(history::REMOTE_HISTORY_SCRIPT.to_string())
+        .await?
+        .boxed();
+
+    let mut entries = Vec::new();
+    while let Some(ev) = stream.next().await {
+        match ev {
+            UserEvent::PipelineOutput {
+                output,
+                pipeline,
+                data_len,
+            } => {
+                client.acknowledge_output(pipeline, data_len).await?;
+                if let Some(entry) = output
+                    .data
+                    .as_string()
+                    .as_deref()
+                    .and_then(history::parse_remote_entry)
+                {
+                    entries.push(entry);
+                }
+            }
+            UserEvent::ErrorRecord { error_record, .. } => {
+                warn!(error = %error_record.render_concise(), "Get-History returned an error");
+            }
+            UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => break,
+            UserEvent::PipelineCreated { .. } | UserEvent::PipelineRecord { .. } => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Copy a printed chunk to the tee files. A file that fails is closed and
+/// the user told; the command keeps running.
+async fn tee_text(
+    tee: &mut Tee,
+    pipeline: &ironposh_client_core::powershell::PipelineHandle,
+    stream: TeeStream,
+    text: &str,
+    terminal_op_tx: &Sender<TerminalOperation>,
+) {
+    if let Some(failure) = tee.write(Some(pipeline.id()), stream, text) {
+        warn!(%failure, "tee file closed");
+        let _ = terminal_op_tx.send(TerminalOperation::Print(failure)).await;
+    }
+}
+
 /// Local prompt shown while the runspace pool is disconnected (no remote
 /// pipeline can run, so the remote `prompt` function must not be used).
 const DISCONNECTED_PROMPT: &str = "(disconnected)> ";
@@ -493,14 +606,75 @@ async fn request_disconnected_prompt(terminal_op_tx: &Sender<TerminalOperation>)
         .await;
 }
 
+/// Prompt for `--prompt local`.
+const LOCAL_PROMPT: &str = "PS> ";
+
+/// Default PowerShell prompt text, built without running the `prompt` function.
+const LOCATION_PROMPT_SCRIPT: &str =
+    "\"PS $($executionContext.SessionState.Path.CurrentLocation)> \"";
+
+/// The prompt for the configured [`PromptMode`], cached between commands. It
+/// is fetched again only after something ran remotely (a command, a nested
+/// prompt, a reconnect); local input such as an empty line or `history`
+/// reuses it.
+struct PromptCache {
+    mode: PromptMode,
+    cached: Option<String>,
+}
+
+impl PromptCache {
+    fn new(mode: PromptMode) -> Self {
+        Self { mode, cached: None }
+    }
+
+    /// Script producing the prompt text, or `None` when no round-trip is needed.
+    fn script(&self) -> Option<&'static str> {
+        match self.mode {
+            // PowerShell's `prompt` function, so user customizations ($PROFILE, etc.) are reflected.
+            PromptMode::Remote => Some("prompt"),
+            PromptMode::Location => Some(LOCATION_PROMPT_SCRIPT),
+            PromptMode::Local => None,
+        }
+    }
+
+    fn get(&self) -> Option<String> {
+        match self.mode {
+            PromptMode::Local => Some(LOCAL_PROMPT.to_string()),
+            PromptMode::Remote | PromptMode::Location => self.cached.clone(),
+        }
+    }
+
+    fn store(&mut self, prompt: &str) {
+        // An empty prompt was drawn by the function itself via `Write-Host`;
+        // only running it again draws it again.
+        self.cached = (!prompt.is_empty()).then(|| prompt.to_string());
+    }
+
+    /// A remote command ran and may have changed the location or prompt.
+    fn invalidate(&mut self) {
+        self.cached = None;
+    }
+}
+
 async fn request_prompt(
     client: &mut RemoteAsyncPowershellClient,
+    prompt_cache: &mut PromptCache,
     terminal_op_tx: &Sender<TerminalOperation>,
 ) {
-    // Important: many customized prompts use `Write-Host` and return an empty
-    // string. In that case, the prompt has already been rendered via HostCalls,
-    // and we should not print any extra local prompt.
-    let prompt = fetch_remote_prompt(client).await.unwrap_or_default();
+    let prompt = match (prompt_cache.get(), prompt_cache.script()) {
+        (Some(prompt), _) => prompt,
+        (None, Some(script)) => {
+            // Important: many customized prompts use `Write-Host` and return an empty
+            // string. In that case, the prompt has already been rendered via HostCalls,
+            // and we should not print any extra local prompt.
+            let prompt = fetch_remote_prompt(client, script)
+                .await
+                .unwrap_or_default();
+            prompt_cache.store(&prompt);
+            prompt
+        }
+        (None, None) => String::new(),
+    };
     let _ = terminal_op_tx
         .send(TerminalOperation::RequestInput { prompt })
         .await;
@@ -588,6 +762,25 @@ fn run_ui_thread(
                 .collect::<Vec<u8>>()
         }
 
+        /// Write through the pager. Quitting the pager stops the command,
+        /// like Ctrl+C. Returns `false` once the REPL is gone.
+        fn write_paged(
+            io: &mut StdTerm<'_>,
+            pager: &mut Pager,
+            text: &str,
+            newline: bool,
+            user_input_tx: &Sender<UserInput>,
+        ) -> std::io::Result<bool> {
+            let (rows, cols) = io.guest_screen_size();
+            pager.resize(rows, cols);
+            let already_quit = pager.has_quit();
+            if io.write_paged(pager, text, newline)? || already_quit {
+                return Ok(true);
+            }
+            info!("user quit the pager, stopping the pipeline");
+            Ok(user_input_tx.blocking_send(UserInput::Interrupt).is_ok())
+        }
+
         fn read_secure_line(
             io: &mut ironposh_terminal::StdTerm<'_>,
             prompt: &str,
@@ -613,7 +806,7 @@ fn run_ui_thread(
                 if let Some(evt) = evt {
                     match evt {
                         crossterm::event::Event::Resize(cols, rows) => {
-                            io.apply_op(TerminalOp::Resize { rows, cols });
+                            io.on_host_resize(cols, rows);
                             io.render().map_err(std::io::Error::other)?;
                         }
                         crossterm::event::Event::Key(crossterm::event::KeyEvent {
@@ -673,9 +866,40 @@ fn run_ui_thread(
             }
         }
 
+        /// Under `--clipboard ask`, confirm the remote's latest clipboard
+        /// write before the next line is read.
+        fn confirm_clipboard(
+            io: &mut StdTerm<'_>,
+            event_queue: &mut VecDeque<crossterm::event::Event>,
+        ) -> std::io::Result<()> {
+            let Some(request) = io.take_clipboard_request() else {
+                return Ok(());
+            };
+            let question = format!(
+                "The remote session wants to set the clipboard ({} bytes). Allow? [y/N] ",
+                request.len()
+            );
+            match io.read_line_queued(&question, event_queue)? {
+                ReadOutcome::Line(answer) if answer.trim().eq_ignore_ascii_case("y") => {
+                    info!(bytes = request.len(), "clipboard write allowed");
+                    io.approve_clipboard(request);
+                    io.render().map_err(std::io::Error::other)?;
+                }
+                _ => info!("clipboard write refused"),
+            }
+            Ok(())
+        }
+
         info!("UI thread starting with unified queue");
+        let focus_tx = user_input_tx.clone();
+        terminal.set_focus_listener(Some(Box::new(move |focused| {
+            // Best effort: a missed change only costs polling speed.
+            let _ = focus_tx.try_send(UserInput::Focus(focused));
+        })));
         let mut io = terminal.stdio();
         let mut event_queue: VecDeque<crossterm::event::Event> = VecDeque::new();
+        // Set by `TerminalOperation::Paginate`, dropped at the next prompt.
+        let mut pager: Option<Pager> = None;
 
         let _ui = tracing::span!(tracing::Level::INFO, "UI Thread").entered();
         // Drain all pending UI ops
@@ -694,6 +918,13 @@ fn run_ui_thread(
                 }
                 TerminalOperation::Print(s) => {
                     debug!(chars = s.len(), "printing output");
+                    if let Some(pager) = pager.as_mut() {
+                        if !write_paged(&mut io, pager, &s, true, &user_input_tx)? {
+                            warn!("failed to send interrupt to REPL - channel closed");
+                            return Ok(());
+                        }
+                        continue;
+                    }
                     if let Err(e) = writeln!(io, "{s}") {
                         error!(error = %e, "failed to write to terminal");
                         return Err(e.into());
@@ -705,6 +936,13 @@ fn run_ui_thread(
                 }
                 TerminalOperation::Write { text, newline } => {
                     debug!(chars = text.len(), newline, "writing output");
+                    if let Some(pager) = pager.as_mut() {
+                        if !write_paged(&mut io, pager, &text, newline, &user_input_tx)? {
+                            warn!("failed to send interrupt to REPL - channel closed");
+                            return Ok(());
+                        }
+                        continue;
+                    }
                     if newline {
                         if let Err(e) = writeln!(io, "{text}") {
                             error!(error = %e, "failed to write line to terminal");
@@ -722,16 +960,16 @@ fn run_ui_thread(
                 }
                 TerminalOperation::SetWindowTitle { title } => {
                     debug!(title = %title, "setting host window title");
-                    // Best-effort: write directly to the host terminal using Crossterm.
-                    // Do not route through the guest terminal emulator.
-                    if let Err(e) =
-                        crossterm::execute!(std::io::stdout(), crossterm::terminal::SetTitle(title))
-                    {
+                    // Goes to the host terminal as is, never onto the guest screen.
+                    io.apply_op(TerminalOp::SetTitle(title));
+                    if let Err(e) = io.render() {
                         warn!(error = %e, "failed to set window title");
                     }
                 }
                 TerminalOperation::RequestInput { prompt } => {
                     debug!(prompt = %prompt, "reading user input");
+                    pager = None;
+                    confirm_clipboard(&mut io, &mut event_queue)?;
                     #[cfg(windows)]
                     if take_console_ctrl_c_pending() {
                         info!("user pressed Ctrl+C (console control event)");
@@ -776,6 +1014,13 @@ fn run_ui_thread(
                                 return Ok(());
                             }
                         }
+                        Ok(ReadOutcome::Detach) => {
+                            info!("user pressed the detach key");
+                            if user_input_tx.blocking_send(UserInput::Detach).is_err() {
+                                warn!("failed to send detach to REPL - channel closed");
+                                return Ok(());
+                            }
+                        }
                         Ok(ReadOutcome::Eof) => {
                             info!("received EOF from user input");
                             let _ = user_input_tx.blocking_send(UserInput::Eof);
@@ -787,6 +1032,18 @@ fn run_ui_thread(
                         }
                     }
                 }
+                TerminalOperation::ShowGrid(mut grid) => {
+                    debug!(rows = grid.visible_rows(), "showing grid view");
+                    if let Err(e) = io.show_grid(&mut grid) {
+                        error!(error = %e, "failed to show grid view");
+                        return Err(e.into());
+                    }
+                }
+                TerminalOperation::Paginate => {
+                    let (rows, cols) = io.guest_screen_size();
+                    debug!(rows, cols, "paging command output");
+                    pager = Some(Pager::new(rows, cols));
+                }
                 TerminalOperation::CheckInterrupt => {
                     #[cfg(windows)]
                     if take_console_ctrl_c_pending() {
@@ -807,6 +1064,24 @@ fn run_ui_thread(
                             return Ok(());
                         }
                     }
+                    // Polled every 50ms while a command runs, which also keeps
+                    // the status bar's running time current.
+                    let _ = io.render();
+                }
+                TerminalOperation::Status(update) => {
+                    let Some(status) = io.status_line_mut() else {
+                        continue;
+                    };
+                    match update {
+                        StatusUpdate::CommandStarted(at) => status.command_started(at),
+                        StatusUpdate::CommandFinished(result, at) => {
+                            status.command_finished(result, at);
+                        }
+                        StatusUpdate::State(state) => status.set_state(state),
+                    }
+                    if let Err(e) = io.render() {
+                        warn!(error = %e, "failed to draw the status line");
+                    }
                 }
                 TerminalOperation::HostRequest {
                     request,
@@ -816,7 +1091,7 @@ fn run_ui_thread(
                         HostUiRequest::ReadLine => {
                             match io.read_line_queued("", &mut event_queue)? {
                                 ReadOutcome::Line(s) => HostUiResponse::Line(s),
-                                ReadOutcome::Interrupt | ReadOutcome::Eof => {
+                                ReadOutcome::Interrupt | ReadOutcome::Eof | ReadOutcome::Detach => {
                                     HostUiResponse::Line(String::new())
                                 }
                             }
@@ -1065,7 +1340,9 @@ fn run_ui_thread(
                                         u
                                     }
                                 }
-                                ReadOutcome::Interrupt | ReadOutcome::Eof => user_name,
+                                ReadOutcome::Interrupt | ReadOutcome::Eof | ReadOutcome::Detach => {
+                                    user_name
+                                }
                             };
 
                             let pw = read_secure_line(&mut io, "Password: ", &mut event_queue)?;
@@ -1110,7 +1387,9 @@ fn run_ui_thread(
                                         u
                                     }
                                 }
-                                ReadOutcome::Interrupt | ReadOutcome::Eof => user_name,
+                                ReadOutcome::Interrupt | ReadOutcome::Eof | ReadOutcome::Detach => {
+                                    user_name
+                                }
                             };
 
                             let pw = read_secure_line(&mut io, "Password: ", &mut event_queue)?;
@@ -1184,7 +1463,7 @@ fn run_ui_thread(
                                 let evt = crossterm::event::read()?;
                                 match evt {
                                     crossterm::event::Event::Resize(cols, rows) => {
-                                        io.apply_op(TerminalOp::Resize { rows, cols });
+                                        io.on_host_resize(cols, rows);
                                         let _ = io.render();
                                     }
                                     other => event_queue.push_back(other),
@@ -1228,18 +1507,40 @@ fn run_ui_thread(
                                     let mut row_vec = Vec::new();
                                     for c in left..=right {
                                         let cell = io.guest_cell(r, c);
-                                        let (ch, fg, bg) = cell.map_or((' ', 7, 0), |cell| {
-                                            let ch = cell.contents().chars().next().unwrap_or(' ');
-                                            let fg = vt_color_to_console(cell.fgcolor(), 7);
-                                            let bg = vt_color_to_console(cell.bgcolor(), 0);
-                                            (ch, fg, bg)
-                                        });
+                                        let (ch, fg, bg, flags) =
+                                            cell.map_or((' ', 7, 0, 0), |cell| {
+                                                // A wide character fills two cells:
+                                                // BufferCellType Leading (1), then
+                                                // Trailing (2) repeating it.
+                                                let (ch, flags) = if cell.is_wide_continuation() {
+                                                    let leading = c
+                                                        .checked_sub(1)
+                                                        .and_then(|prev| io.guest_cell(r, prev));
+                                                    let ch = leading.map_or(' ', |prev| {
+                                                        prev.contents()
+                                                            .chars()
+                                                            .next()
+                                                            .unwrap_or(' ')
+                                                    });
+                                                    (ch, 2)
+                                                } else {
+                                                    let ch = cell
+                                                        .contents()
+                                                        .chars()
+                                                        .next()
+                                                        .unwrap_or(' ');
+                                                    (ch, i32::from(cell.is_wide()))
+                                                };
+                                                let fg = vt_color_to_console(cell.fgcolor(), 7);
+                                                let bg = vt_color_to_console(cell.bgcolor(), 0);
+                                                (ch, fg, bg, flags)
+                                            });
 
                                         row_vec.push(ironposh_client_core::host::BufferCell {
                                             character: ch,
                                             foreground: fg,
                                             background: bg,
-                                            flags: 0,
+                                            flags,
                                         });
                                     }
                                     out.push(row_vec);
@@ -1273,8 +1574,12 @@ async fn run_repl_loop(
 ) -> anyhow::Result<()> {
     info!("Starting unified REPL loop");
 
+    let mut prompt_cache = PromptCache::new(options.prompt);
+    let mut aliases = options.aliases;
+    let mut tee = Tee::new(options.tee);
+
     // Ask for the first prompt
-    request_prompt(client, &terminal_op_tx).await;
+    request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
 
     // Async REPL loop
     let mut current_pipeline = None;
@@ -1282,6 +1587,15 @@ async fn run_repl_loop(
     // Tracked from PoolLifecycleEvents; while true, remote pipelines (commands,
     // remote prompt, tab completion) must not be attempted.
     let mut disconnected = false;
+    // Object output of the running command, when it runs in grid mode.
+    let mut grid_capture: Option<GridCapture> = None;
+    // Every command typed in this client, merged with the remote
+    // `Get-History` by the `history` command.
+    let mut local_history: Vec<String> = Vec::new();
+    // Lines of a command still being typed at the `>>` prompt.
+    let mut pending_input = PendingInput::default();
+    // The running command wrote an error record; shown by the status bar.
+    let mut command_failed = false;
     let mut interrupt_poll = tokio::time::interval(std::time::Duration::from_millis(50));
     interrupt_poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
@@ -1319,7 +1633,8 @@ async fn run_repl_loop(
                             &mut repl_control_rx,
                         )
                         .await?;
-                        request_prompt(client, &terminal_op_tx).await;
+                        prompt_cache.invalidate();
+                        request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                     }
                     ReplControl::ExitNestedPrompt => {
                         debug!("ExitNestedPrompt received while not nested (ignored)");
@@ -1333,32 +1648,146 @@ async fn run_repl_loop(
             // User input from UI thread
             Some(msg) = user_input_rx.recv() => {
                 debug!(?msg, "Received message from UI thread");
+                let msg = match msg {
+                    UserInput::Detach => {
+                        pending_input.clear();
+                        UserInput::Cmd(":disconnect".to_string())
+                    }
+                    other => other,
+                };
                 match msg {
                     UserInput::Eof => {
                         info!("Received EOF, exiting REPL");
                         break;
                     }
+                    UserInput::Detach => unreachable!("detach is turned into :disconnect above"),
+                    UserInput::Focus(focused) => {
+                        debug!(focused, "terminal focus changed");
+                        client.set_power_saving(!focused).await?;
+                    }
                     UserInput::Interrupt => {
+                        pending_input.clear();
                         if let Some(h) = current_pipeline.take() {
                             info!(pipeline = ?h, "Killing active pipeline due to interrupt");
                             client.kill_pipeline(h).await?;
                             current_stream = None;
+                            prompt_cache.invalidate();
+                            let _ = terminal_op_tx
+                                .send(TerminalOperation::Status(StatusUpdate::CommandFinished(
+                                    CommandStatus::Stopped,
+                                    std::time::Instant::now(),
+                                )))
+                                .await;
                         }
+                        grid_capture = None;
+                        tee.end_command();
                         if disconnected {
                             request_disconnected_prompt(&terminal_op_tx).await;
                         } else {
-                            request_prompt(client, &terminal_op_tx).await;
+                            request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                         }
                     }
-                    UserInput::Cmd(cmd) => {
+                    UserInput::Cmd(line) => {
+                        // Only a bracketed paste puts line breaks in a line;
+                        // it runs as one input instead of continuing at `>>`.
+                        let cmd = if line.contains('\n') {
+                            pending_input.push_paste(&line)
+                        } else if let Some(cmd) = pending_input.push(&line) {
+                            cmd
+                        } else {
+                            debug!("incomplete input; continuing at the >> prompt");
+                            let _ = terminal_op_tx
+                                .send(TerminalOperation::RequestInput {
+                                    prompt: continuation::PROMPT.to_string(),
+                                })
+                                .await;
+                            continue;
+                        };
                         let cmd = cmd.trim().to_string();
-                        info!(command = %cmd, "processing command");
+                        // `%alias` / `%unalias` / `%tee` only change local state.
+                        if let Some(message) = aliases
+                            .run_command(&cmd)
+                            .or_else(|| tee.run_command(&cmd))
+                        {
+                            let _ = terminal_op_tx.send(TerminalOperation::Print(message)).await;
+                            local_history.push(cmd);
+                            if disconnected {
+                                request_disconnected_prompt(&terminal_op_tx).await;
+                            } else {
+                                request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
+                            }
+                            continue;
+                        }
+                        let (cmd, command_tee) = match split_tee_prefix(&cmd) {
+                            Some(Ok((path, rest))) => {
+                                match TeeSink::open(&path, TeeFormat::for_path(&path)) {
+                                    Ok(sink) => (rest.to_string(), Some(sink)),
+                                    Err(e) => {
+                                        let message = format!("cannot tee to {}: {e}", path.display());
+                                        let _ = terminal_op_tx.send(TerminalOperation::Print(message)).await;
+                                        // Runs nothing; the empty command gets a new prompt.
+                                        (String::new(), None)
+                                    }
+                                }
+                            }
+                            Some(Err(usage)) => {
+                                let _ = terminal_op_tx.send(TerminalOperation::Print(usage)).await;
+                                (String::new(), None)
+                            }
+                            None => (cmd, None),
+                        };
+                        let (cmd, paged) = match strip_toggle_prefix(&cmd, ":page") {
+                            Some(rest) => (rest.to_string(), !options.page_output),
+                            None => (cmd, options.page_output),
+                        };
+                        let (cmd, grid) = match strip_toggle_prefix(&cmd, ":grid") {
+                            Some(rest) => (rest.to_string(), !options.grid_output),
+                            None => (cmd, options.grid_output),
+                        };
+                        let cmd = match aliases.expand(&cmd) {
+                            Some(expanded) => {
+                                debug!(alias = %cmd, command = %expanded, "expanded client alias");
+                                expanded
+                            }
+                            None => cmd,
+                        };
+                        info!(command = %cmd, paged, grid, "processing command");
 
                         if cmd.eq_ignore_ascii_case("exit") {
                             info!("Exit command received, terminating REPL");
                             break;
                         }
 
+                        if cmd.eq_ignore_ascii_case("history") {
+                            // No remote pipeline can run while disconnected:
+                            // the local history is all there is.
+                            let remote = if disconnected {
+                                Vec::new()
+                            } else {
+                                fetch_remote_history(client).await.unwrap_or_else(|e| {
+                                    warn!(error = %e, "failed to fetch remote history");
+                                    Vec::new()
+                                })
+                            };
+                            let listing = history::render(&history::merge(&remote, &local_history));
+                            if !listing.is_empty() {
+                                let _ = terminal_op_tx
+                                    .send(TerminalOperation::Print(listing.trim_end().to_string()))
+                                    .await;
+                            }
+                            local_history.push(cmd);
+                            if disconnected {
+                                request_disconnected_prompt(&terminal_op_tx).await;
+                            } else {
+                                request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
+                            }
+                            continue;
+                        }
+
+                        if !cmd.is_empty() {
+                            local_history.push(cmd.clone());
+                        }
+
                         if cmd.eq_ignore_ascii_case(":disconnect") || cmd.eq_ignore_ascii_case(":reconnect") {
                             let reconnect = cmd.eq_ignore_ascii_case(":reconnect");
                             if !options.disconnect_supported {
@@ -1368,7 +1797,7 @@ async fn run_repl_loop(
                                             .to_string(),
                                     ))
                                     .await;
-                                request_prompt(client, &terminal_op_tx).await;
+                                request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                             } else if reconnect && !disconnected {
                                 let _ = terminal_op_tx
                                     .send(TerminalOperation::Print(
@@ -1376,7 +1805,7 @@ async fn run_repl_loop(
                                             .to_string(),
                                     ))
                                     .await;
-                                request_prompt(client, &terminal_op_tx).await;
+                                request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                             } else if !reconnect && disconnected {
                                 let _ = terminal_op_tx
                                     .send(TerminalOperation::Print(
@@ -1399,7 +1828,7 @@ async fn run_repl_loop(
                                     if disconnected {
                                         request_disconnected_prompt(&terminal_op_tx).await;
                                     } else {
-                                        request_prompt(client, &terminal_op_tx).await;
+                                        request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                                     }
                                 } else if reconnect {
                                     let _ = terminal_op_tx
@@ -1434,22 +1863,60 @@ async fn run_repl_loop(
 
                         if cmd.is_empty() {
                             debug!("Empty command, requesting new prompt");
-                            request_prompt(client, &terminal_op_tx).await;
+                            request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                             continue;
                         }
 
                         // Start a pipeline
                         info!(command = %cmd, "Sending command to PowerShell");
-                        match client.send_script(cmd).await {
+                        // A script too large for one CreatePipeline envelope
+                        // (a big paste) is streamed in as pipeline input.
+                        let (mut spec, input) = if cmd.len() > SCRIPT_INPUT_CHUNK_CHARS {
+                            let input = script_input_chunks(&cmd, SCRIPT_INPUT_CHUNK_CHARS);
+                            info!(chunks = input.len(), "sending the script as pipeline input");
+                            (PipelineSpec::script_from_input(), input)
+                        } else {
+                            let spec = PipelineSpec {
+                                commands: vec![PipelineCommand::new_script(cmd.clone())],
+                                add_to_history: true,
+                            };
+                            (spec, Vec::new())
+                        };
+                        // Grid mode needs the objects themselves, not their
+                        // `Out-String` rendering.
+                        if grid {
+                            grid_capture = Some(GridCapture::new(cmd));
+                        } else {
+                            grid_capture = None;
+                            spec.commands.push(PipelineCommand::new_output_stream());
+                        }
+                        let sent = if input.is_empty() {
+                            client.send_pipeline(spec, PipelineOptions::default()).await
+                        } else {
+                            client
+                                .send_pipeline_with_input(spec, input, PipelineOptions::default())
+                                .await
+                        };
+                        match sent {
                             Ok(stream) => {
                                 info!("Command sent successfully, waiting for events");
+                                if paged {
+                                    let _ = terminal_op_tx.send(TerminalOperation::Paginate).await;
+                                }
                                 current_stream = Some(stream.boxed());
                                 current_pipeline = None; // will be set on PipelineCreated
+                                command_failed = false;
+                                tee.set_command(command_tee);
+                                let _ = terminal_op_tx
+                                    .send(TerminalOperation::Status(StatusUpdate::CommandStarted(
+                                        std::time::Instant::now(),
+                                    )))
+                                    .await;
                             }
                             Err(e) => {
                                 error!("Failed to send command: {}", e);
                                 let _ = terminal_op_tx.send(TerminalOperation::Print(format!("Error sending command: {e}"))).await;
-                                request_prompt(client, &terminal_op_tx).await;
+                                request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                             }
                         }
                     }
@@ -1463,6 +1930,9 @@ async fn run_repl_loop(
                         let display_id = shell_id.as_deref().unwrap_or("<unknown>");
                         info!(shell_id = %display_id, "runspace pool disconnected");
                         disconnected = true;
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Status(StatusUpdate::State("disconnected")))
+                            .await;
                         let _ = terminal_op_tx
                             .send(TerminalOperation::Print(format!(
                                 "Disconnected from runspace pool (ShellId: {display_id}). Type :reconnect to resume."
@@ -1491,29 +1961,39 @@ async fn run_repl_loop(
                         let shell_id = shell_id.unwrap_or_else(|| "<unknown>".to_string());
                         info!(shell_id = %shell_id, "runspace pool reconnected");
                         disconnected = false;
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Status(StatusUpdate::State("connected")))
+                            .await;
+                        prompt_cache.invalidate();
                         let _ = terminal_op_tx
                             .send(TerminalOperation::Print(format!(
                                 "Reconnected to runspace pool (ShellId: {shell_id})."
                             )))
                             .await;
-                        request_prompt(client, &terminal_op_tx).await;
+                        request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                     }
                     PoolLifecycleEvent::DisconnectFailed { shell_id } => {
                         let shell_id = shell_id.unwrap_or_else(|| "<unknown>".to_string());
                         warn!(shell_id = %shell_id, "disconnect failed; session is still connected");
                         disconnected = false;
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Status(StatusUpdate::State("connected")))
+                            .await;
                         let _ = terminal_op_tx
                             .send(TerminalOperation::Print(
                                 "Disconnect failed (server fault); session is still connected."
                                     .to_string(),
                             ))
                             .await;
-                        request_prompt(client, &terminal_op_tx).await;
+                        request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                     }
                     PoolLifecycleEvent::ReconnectFailed { shell_id } => {
                         let shell_id = shell_id.unwrap_or_else(|| "<unknown>".to_string());
                         warn!(shell_id = %shell_id, "reconnect failed; session is still disconnected");
                         disconnected = true;
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Status(StatusUpdate::State("disconnected")))
+                            .await;
                         let _ = terminal_op_tx
                             .send(TerminalOperation::Print(
                                 "Reconnect failed (connection error); still disconnected. Type :reconnect to retry."
@@ -1539,15 +2019,48 @@ async fn run_repl_loop(
                         info!(pipeline = ?pipeline, "Pipeline created");
                         current_pipeline = Some(pipeline);
                     }
-                    UserEvent::PipelineFinished { .. } => {
+                    finished @ (UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. }) => {
                         info!("Pipeline finished");
                         current_pipeline = None;
                         current_stream = None;
-                        // Request new prompt after pipeline finishes
-                        request_prompt(client, &terminal_op_tx).await;
+                        tee.end_command();
+                        let status = if matches!(finished, UserEvent::PipelineTimedOut { .. }) {
+                            CommandStatus::Stopped
+                        } else if command_failed {
+                            CommandStatus::Failed
+                        } else {
+                            CommandStatus::Succeeded
+                        };
+                        let _ = terminal_op_tx
+                            .send(TerminalOperation::Status(StatusUpdate::CommandFinished(
+                                status,
+                                std::time::Instant::now(),
+                            )))
+                            .await;
+                        if let Some(grid) = grid_capture.take().and_then(GridCapture::into_grid) {
+                            info!(rows = grid.visible_rows(), "showing object output in the grid view");
+                            let _ = terminal_op_tx.send(TerminalOperation::ShowGrid(Box::new(grid))).await;
+                        }
+                        // Request new prompt after pipeline finishes; the
+                        // command may have changed location.
+                        prompt_cache.invalidate();
+                        request_prompt(client, &mut prompt_cache, &terminal_op_tx).await;
                     }
-                    UserEvent::PipelineOutput { output, .. } => {
+                    UserEvent::PipelineOutput {
+                        output, pipeline, data_len
+                    } => {
                         debug!("Received pipeline output");
+                        // Handed to the terminal (or the grid), so the
+                        // server may send more of it.
+                        let handle = ironposh_client_core::powershell::PipelineHandle::new(pipeline.id());
+                        client.acknowledge_output(handle, data_len).await?;
+                        let output = match grid_capture.as_mut() {
+                            Some(capture) => match capture.push(output.data) {
+                                Some(data) => PipelineOutput::from(data),
+                                None => continue,
+                            },
+                            None => output,
+                        };
                         let text = match output.format_as_displyable_string() {
                             Ok(s) => {
                                 debug!("Formatted output: {} chars", s.len());
@@ -1558,27 +2071,33 @@ async fn run_repl_loop(
                                 format!("Error formatting output: {e}")
                             }
                         };
+                        tee_text(&mut tee, &pipeline, TeeStream::Output, &text, &terminal_op_tx).await;
                         let _ = terminal_op_tx.send(TerminalOperation::Print(text)).await;
                     }
-                    UserEvent::ErrorRecord { error_record, .. } => {
+                    UserEvent::ErrorRecord { error_record, handle } => {
                         debug!("Received error record");
+                        command_failed = true;
                         let error_text = error_record.render_concise();
+                        tee_text(&mut tee, &handle, TeeStream::Error, &error_text, &terminal_op_tx).await;
                         let _ = terminal_op_tx.send(TerminalOperation::Print(format!("Error: {error_text}"))).await;
                     }
-                    UserEvent::PipelineRecord { record, .. } => {
+                    UserEvent::PipelineRecord { record, pipeline } => {
                         use ironposh_client_core::psrp_record::PsrpRecord;
                         match record {
                             PsrpRecord::Debug { message, .. } => {
+                                tee_text(&mut tee, &pipeline, TeeStream::Debug, &message, &terminal_op_tx).await;
                                 let _ = terminal_op_tx
                                     .send(TerminalOperation::Print(format!("Debug: {message}")))
                                     .await;
                             }
                             PsrpRecord::Verbose { message, .. } => {
+                                tee_text(&mut tee, &pipeline, TeeStream::Verbose, &message, &terminal_op_tx).await;
                                 let _ = terminal_op_tx
                                     .send(TerminalOperation::Print(format!("Verbose: {message}")))
                                     .await;
                             }
                             PsrpRecord::Warning { message, .. } => {
+                                tee_text(&mut tee, &pipeline, TeeStream::Warning, &message, &terminal_op_tx).await;
                                 let _ = terminal_op_tx
                                     .send(TerminalOperation::Print(format!("Warning: {message}")))
                                     .await;
@@ -1597,6 +2116,7 @@ async fn run_repl_loop(
                                 match &record.message_data {
                                     ironposh_psrp::InformationMessageData::HostInformationMessage(m) => {
                                         let (text, newline) = format_host_information_message(m);
+                                        tee_text(&mut tee, &pipeline, TeeStream::Information, &text, &terminal_op_tx).await;
                                         let _ = terminal_op_tx
                                             .send(TerminalOperation::Write { text, newline })
                                             .await;
@@ -1607,6 +2127,7 @@ async fn run_repl_loop(
                                         } else {
                                             "[information] "
                                         };
+                                        tee_text(&mut tee, &pipeline, TeeStream::Information, &s, &terminal_op_tx).await;
                                         let _ = terminal_op_tx
                                             .send(TerminalOperation::Print(format!("{prefix}{s}")))
                                             .await;
@@ -1617,6 +2138,7 @@ async fn run_repl_loop(
                                         } else {
                                             "[information] "
                                         };
+                                        tee_text(&mut tee, &pipeline, TeeStream::Information, &v.to_string(), &terminal_op_tx).await;
                                         let _ = terminal_op_tx
                                             .send(TerminalOperation::Print(format!(
                                                 "{prefix}{v}"
@@ -1749,4 +2271,35 @@ mod tests {
         let out = apply_command_completion("😀Get-Ser", &completion).expect("should complete");
         assert_eq!(out, "😀Get-Service");
     }
+
+    #[test]
+    fn strip_toggle_prefix_only_matches_the_whole_word() {
+        assert_eq!(
+            strip_toggle_prefix(":page Get-Process", ":page"),
+            Some("Get-Process")
+        );
+        assert_eq!(strip_toggle_prefix(":PAGE  dir", ":page"), Some("dir"));
+        assert_eq!(strip_toggle_prefix(":grid", ":grid"), Some(""));
+        assert_eq!(strip_toggle_prefix(":pager dir", ":page"), None);
+        assert_eq!(strip_toggle_prefix("Get-Process", ":page"), None);
+        assert_eq!(strip_toggle_prefix("ls", ":page"), None);
+    }
+
+    #[test]
+    fn prompt_cache_refetches_only_after_remote_work() {
+        let mut cache = PromptCache::new(PromptMode::Remote);
+        assert_eq!(cache.get(), None);
+        cache.store("PS C:\\> ");
+        assert_eq!(cache.get().as_deref(), Some("PS C:\\> "));
+        cache.invalidate();
+        assert_eq!(cache.get(), None);
+
+        // A `Write-Host` prompt returns nothing and must run again to show.
+        cache.store("");
+        assert_eq!(cache.get(), None);
+
+        let local = PromptCache::new(PromptMode::Local);
+        assert_eq!(local.script(), None);
+        assert_eq!(local.get().as_deref(), Some(LOCAL_PROMPT));
+    }
 }
@@ -0,0 +1,92 @@
+//! Elapsed-time spinner on stderr while a `--command` runs. Drawn only when
+//! stderr is a terminal, so scheduled jobs and redirected output never see it.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often [`Spinner::draw`] should be called.
+pub const TICK: Duration = Duration::from_millis(100);
+
+pub struct Spinner {
+    enabled: bool,
+    started: Instant,
+    timeout: Option<Duration>,
+    frame: usize,
+    drawn: bool,
+}
+
+impl Spinner {
+    /// A spinner for a command limited to `timeout`, drawn only if stderr is
+    /// a terminal.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            enabled: std::io::stderr().is_terminal(),
+            started: Instant::now(),
+            timeout,
+            frame: 0,
+            drawn: false,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn draw(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let line = status_line(self.frame, self.started.elapsed(), self.timeout);
+        self.frame = (self.frame + 1) % FRAMES.len();
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+        self.drawn = true;
+    }
+
+    /// Erase the spinner line so output can be printed; the next
+    /// [`Spinner::draw`] puts it back.
+    pub fn clear(&mut self) {
+        if !self.drawn {
+            return;
+        }
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+        self.drawn = false;
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+fn status_line(frame: usize, elapsed: Duration, timeout: Option<Duration>) -> String {
+    let spinner = FRAMES[frame % FRAMES.len()];
+    let elapsed = elapsed.as_secs();
+    timeout.map_or_else(
+        || format!("{spinner} running {elapsed}s"),
+        |timeout| format!("{spinner} running {elapsed}s / {}s", timeout.as_secs()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_line_shows_elapsed_and_timeout() {
+        assert_eq!(
+            status_line(0, Duration::from_millis(12_400), None),
+            "⠋ running 12s"
+        );
+        assert_eq!(
+            status_line(11, Duration::from_secs(3), Some(Duration::from_secs(90))),
+            "⠙ running 3s / 90s"
+        );
+    }
+}
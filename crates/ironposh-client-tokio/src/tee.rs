@@ -0,0 +1,308 @@
+//! `--tee` / `%tee` / `:tee`: a copy of pipeline output written to a file
+//! while it is still rendered. The sink sees the events in the client loop,
+//! so nothing is added to the remote command.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::aliases::strip_command;
+use crate::config::TeeFormat;
+
+/// Which pipeline stream a teed line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeeStream {
+    Output,
+    Error,
+    Warning,
+    Verbose,
+    Debug,
+    Information,
+}
+
+impl TeeStream {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Output => "output",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Verbose => "verbose",
+            Self::Debug => "debug",
+            Self::Information => "information",
+        }
+    }
+}
+
+impl TeeFormat {
+    /// `.json`, `.jsonl` and `.ndjson` files get JSON lines; anything else
+    /// gets the text as printed.
+    pub fn for_path(path: &Path) -> Self {
+        let is_json = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ["json", "jsonl", "ndjson"]
+                    .iter()
+                    .any(|json| ext.eq_ignore_ascii_case(json))
+            });
+        if is_json {
+            Self::Ndjson
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// One open tee file. It is opened in append mode, so teeing several
+/// commands into the same file keeps all of them.
+pub struct TeeSink {
+    path: PathBuf,
+    format: TeeFormat,
+    file: std::fs::File,
+}
+
+impl TeeSink {
+    pub fn open(path: &Path, format: TeeFormat) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> TeeFormat {
+        self.format
+    }
+
+    /// Write one rendered chunk. Text is written as printed, without the
+    /// stream prefix the terminal shows; NDJSON keeps the stream and
+    /// pipeline as fields.
+    pub fn write(
+        &mut self,
+        pipeline_id: Option<uuid::Uuid>,
+        stream: TeeStream,
+        text: &str,
+    ) -> std::io::Result<()> {
+        match self.format {
+            TeeFormat::Text => writeln!(self.file, "{text}")?,
+            TeeFormat::Ndjson => {
+                let timestamp_ms = std::time::SystemTime::now()
+                    .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_millis());
+                let line = serde_json::json!({
+                    "timestamp_ms": timestamp_ms,
+                    "pipeline_id": pipeline_id.map(|id| id.to_string()),
+                    "stream": stream.as_str(),
+                    "text": text,
+                });
+                writeln!(self.file, "{line}")?;
+            }
+        }
+        self.file.flush()
+    }
+}
+
+/// The tee state of an interactive session: a session-wide sink (`--tee`,
+/// `%tee`) and a sink for the running command only (`:tee`). Both get the
+/// output when both are set.
+#[derive(Default)]
+pub struct Tee {
+    session: Option<TeeSink>,
+    command: Option<TeeSink>,
+}
+
+impl Tee {
+    pub fn new(session: Option<TeeSink>) -> Self {
+        Self {
+            session,
+            command: None,
+        }
+    }
+
+    pub fn set_command(&mut self, sink: Option<TeeSink>) {
+        self.command = sink;
+    }
+
+    /// Called when the running command ends; its `:tee` file is closed.
+    pub fn end_command(&mut self) {
+        self.command = None;
+    }
+
+    /// Tee one chunk to every open sink. A failing sink is closed so a full
+    /// disk does not fail every following line; the returned message says so.
+    pub fn write(
+        &mut self,
+        pipeline_id: Option<uuid::Uuid>,
+        stream: TeeStream,
+        text: &str,
+    ) -> Option<String> {
+        let mut failures = Vec::new();
+        for slot in [&mut self.session, &mut self.command] {
+            if let Some(sink) = slot {
+                if let Err(e) = sink.write(pipeline_id, stream, text) {
+                    failures.push(format!("tee to {} stopped: {e}", sink.path().display()));
+                    *slot = None;
+                }
+            }
+        }
+        (!failures.is_empty()).then(|| failures.join("\n"))
+    }
+
+    /// Run a `%tee` command: `%tee <path> [text|ndjson]` starts teeing the
+    /// session, `%tee off` stops, a bare `%tee` shows the current file.
+    /// `None` when `line` is not a `%tee` command.
+    pub fn run_command(&mut self, line: &str) -> Option<String> {
+        let args = strip_command(line, "%tee")?;
+        if args.is_empty() {
+            return Some(self.session.as_ref().map_or_else(
+                || "output is not being teed".to_owned(),
+                |sink| {
+                    format!(
+                        "teeing output to {} ({})",
+                        sink.path().display(),
+                        format_name(sink.format())
+                    )
+                },
+            ));
+        }
+        if args.eq_ignore_ascii_case("off") {
+            return Some(self.session.take().map_or_else(
+                || "output is not being teed".to_owned(),
+                |sink| format!("stopped teeing output to {}", sink.path().display()),
+            ));
+        }
+
+        let (path, format) = match split_path(args) {
+            Ok(split) => split,
+            Err(usage) => return Some(usage),
+        };
+        let format = match format {
+            None => TeeFormat::for_path(&path),
+            Some(name) if name.eq_ignore_ascii_case("text") => TeeFormat::Text,
+            Some(name) if name.eq_ignore_ascii_case("ndjson") => TeeFormat::Ndjson,
+            Some(_) => return Some(USAGE.to_owned()),
+        };
+        let sink = match TeeSink::open(&path, format) {
+            Ok(sink) => sink,
+            Err(e) => return Some(format!("cannot tee to {}: {e}", path.display())),
+        };
+        self.session = Some(sink);
+        Some(format!(
+            "teeing output to {} ({})",
+            path.display(),
+            format_name(format)
+        ))
+    }
+}
+
+const USAGE: &str = "usage: %tee <path> [text|ndjson] | %tee off";
+
+fn format_name(format: TeeFormat) -> &'static str {
+    match format {
+        TeeFormat::Text => "text",
+        TeeFormat::Ndjson => "ndjson",
+    }
+}
+
+/// Split `:tee <path> <command>` into the path and the command. A path with
+/// spaces is quoted with `'` or `"`.
+pub fn split_tee_prefix(cmd: &str) -> Option<Result<(PathBuf, &str), String>> {
+    let args = strip_command(cmd, ":tee")?;
+    Some(match split_path(args) {
+        Ok((path, Some(command))) => Ok((path, command)),
+        _ => Err("usage: :tee <path> <command>".to_owned()),
+    })
+}
+
+/// The leading path of `args` (unquoted up to whitespace, or quoted) and the
+/// rest, if any.
+fn split_path(args: &str) -> Result<(PathBuf, Option<&str>), String> {
+    let (path, rest) = match args.chars().next() {
+        Some(quote @ ('\'' | '"')) => {
+            let body = &args[1..];
+            let end = body.find(quote).ok_or_else(|| USAGE.to_owned())?;
+            (&body[..end], &body[end + 1..])
+        }
+        Some(_) => args.split_once(char::is_whitespace).unwrap_or((args, "")),
+        None => return Err(USAGE.to_owned()),
+    };
+    if path.is_empty() {
+        return Err(USAGE.to_owned());
+    }
+    let rest = rest.trim();
+    Ok((PathBuf::from(path), (!rest.is_empty()).then_some(rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ironposh-tee-{}.{extension}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn session_and_command_sinks_both_receive_output() {
+        let session_path = temp_path("log");
+        let command_path = temp_path("ndjson");
+        let mut tee = Tee::default();
+        let message = tee
+            .run_command(&format!("%tee {}", session_path.display()))
+            .expect("a %tee command");
+        assert!(message.contains("(text)"), "{message}");
+
+        tee.set_command(Some(
+            TeeSink::open(&command_path, TeeFormat::for_path(&command_path)).expect("open"),
+        ));
+        assert_eq!(
+            tee.write(Some(uuid::Uuid::nil()), TeeStream::Output, "a\nb"),
+            None
+        );
+        tee.end_command();
+        assert_eq!(tee.write(None, TeeStream::Warning, "careful"), None);
+        assert_eq!(
+            tee.run_command("%TEE off").as_deref(),
+            Some(format!("stopped teeing output to {}", session_path.display()).as_str())
+        );
+        assert_eq!(tee.write(None, TeeStream::Output, "dropped"), None);
+
+        let text = std::fs::read_to_string(&session_path).expect("read text tee");
+        let json = std::fs::read_to_string(&command_path).expect("read ndjson tee");
+        let _ = std::fs::remove_file(&session_path);
+        let _ = std::fs::remove_file(&command_path);
+        assert_eq!(text, "a\nb\ncareful\n");
+        let lines: Vec<serde_json::Value> = json
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON line"))
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["stream"], "output");
+        assert_eq!(lines[0]["text"], "a\nb");
+        assert_eq!(lines[0]["pipeline_id"], uuid::Uuid::nil().to_string());
+    }
+
+    #[test]
+    fn tee_prefix_takes_a_plain_or_quoted_path() {
+        assert_eq!(
+            split_tee_prefix(":tee out.txt Get-Process -Name x"),
+            Some(Ok((PathBuf::from("out.txt"), "Get-Process -Name x")))
+        );
+        assert_eq!(
+            split_tee_prefix(":TEE 'my logs/out.json' dir"),
+            Some(Ok((PathBuf::from("my logs/out.json"), "dir")))
+        );
+        assert!(matches!(split_tee_prefix(":tee"), Some(Err(_))));
+        assert!(matches!(split_tee_prefix(":tee out.txt"), Some(Err(_))));
+        assert_eq!(split_tee_prefix(":teex dir"), None);
+        assert_eq!(split_tee_prefix("Get-Process"), None);
+        assert_eq!(TeeFormat::for_path(Path::new("a.JSONL")), TeeFormat::Ndjson);
+        assert_eq!(TeeFormat::for_path(Path::new("a.txt")), TeeFormat::Text);
+    }
+}
@@ -0,0 +1,346 @@
+//! The local time zone, reported to the server in SESSION_CAPABILITY.
+//!
+//! Both platforms describe daylight saving the same way — "the second Sunday
+//! of March at 02:00" — so each reads its zone into a [`Zone`] and the rule
+//! is turned into this year's [`DaylightChanges`] here, the way .NET's
+//! `CurrentSystemTimeZone` caches it.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ironposh_psrp::{DaylightChanges, TimeZone};
+
+const TICKS_PER_SECOND: i64 = 10_000_000;
+/// Days from 0001-01-01 (`DateTime` tick zero) to 1970-01-01.
+const DAYS_BEFORE_EPOCH: i64 = 719_162;
+
+/// The local zone; `None` when it cannot be read.
+pub fn local() -> Option<TimeZone> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    let days = i64::try_from(now.as_secs()).ok()? / 86_400;
+    let (year, _, _) = civil_from_days(days);
+    Some(imp::local()?.to_time_zone(year))
+}
+
+/// A zone's standard offset and, if it has one, its daylight saving rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Zone {
+    /// Local standard time minus UTC, in minutes.
+    offset_minutes: i32,
+    name: String,
+    daylight: Option<Daylight>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Daylight {
+    name: String,
+    /// How far clocks move forward, in minutes.
+    delta_minutes: i32,
+    start: Transition,
+    end: Transition,
+}
+
+/// "Week `week` (5 = last) of `month`, on `weekday` (0 = Sunday), at
+/// `seconds` past local midnight."
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transition {
+    month: u32,
+    week: u32,
+    weekday: u32,
+    seconds: i64,
+}
+
+impl Zone {
+    fn to_time_zone(&self, year: i64) -> TimeZone {
+        let mut zone = TimeZone::fixed(self.offset_minutes);
+        zone.standard_name = Some(self.name.clone());
+        zone.daylight_name = Some(self.name.clone());
+        if let Some(daylight) = &self.daylight {
+            zone.daylight_name = Some(daylight.name.clone());
+            if let Ok(year_i32) = i32::try_from(year) {
+                zone.daylight_changes.push(DaylightChanges {
+                    year: year_i32,
+                    start_ticks: daylight.start.local_ticks(year),
+                    end_ticks: daylight.end.local_ticks(year),
+                    delta_ticks: i64::from(daylight.delta_minutes) * 60 * TICKS_PER_SECOND,
+                });
+            }
+        }
+        zone
+    }
+}
+
+impl Transition {
+    /// Local `DateTime` ticks of this transition in `year`.
+    fn local_ticks(self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        let first_weekday = (first + 4).rem_euclid(7);
+        let mut day = 1
+            + (i64::from(self.weekday) - first_weekday).rem_euclid(7)
+            + (i64::from(self.week.clamp(1, 5)) - 1) * 7;
+        let (next_year, next_month) = if self.month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, self.month + 1)
+        };
+        let days_in_month = days_from_civil(next_year, next_month, 1) - first;
+        while day > days_in_month {
+            day -= 7;
+        }
+        ((first + day - 1 + DAYS_BEFORE_EPOCH) * 86_400 + self.seconds) * TICKS_PER_SECOND
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = u32::try_from(day_of_year - (153 * mp + 2) / 5 + 1).unwrap_or(1);
+    let month = u32::try_from(if mp < 10 { mp + 3 } else { mp - 9 }).unwrap_or(1);
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(windows)]
+mod imp {
+    use windows_sys::Win32::System::Time::{
+        GetTimeZoneInformation, TIME_ZONE_ID_INVALID, TIME_ZONE_INFORMATION,
+    };
+
+    use super::{Daylight, Transition, Zone};
+
+    pub fn local() -> Option<Zone> {
+        // SAFETY: TIME_ZONE_INFORMATION is plain data; all zeroes is valid.
+        let mut info: TIME_ZONE_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: `info` is a valid, writable TIME_ZONE_INFORMATION.
+        if unsafe { GetTimeZoneInformation(&mut info) } == TIME_ZONE_ID_INVALID {
+            return None;
+        }
+        let name = |wide: &[u16]| {
+            let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+            String::from_utf16_lossy(&wide[..len])
+        };
+        // `wDay` is the week of the month (5 = last) when `wYear` is 0.
+        let transition = |date: &windows_sys::Win32::Foundation::SYSTEMTIME| Transition {
+            month: u32::from(date.wMonth),
+            week: u32::from(date.wDay),
+            weekday: u32::from(date.wDayOfWeek),
+            seconds: i64::from(date.wHour) * 3600 + i64::from(date.wMinute) * 60,
+        };
+        let daylight =
+            (info.DaylightDate.wMonth != 0 && info.DaylightDate.wYear == 0).then(|| Daylight {
+                name: name(&info.DaylightName),
+                delta_minutes: info.StandardBias - info.DaylightBias,
+                start: transition(&info.DaylightDate),
+                end: transition(&info.StandardDate),
+            });
+        Some(Zone {
+            offset_minutes: -(info.Bias + info.StandardBias),
+            name: name(&info.StandardName),
+            daylight,
+        })
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::Zone;
+
+    /// From `$TZ`, or else `/etc/localtime`.
+    pub fn local() -> Option<Zone> {
+        match std::env::var("TZ") {
+            Ok(tz) if !tz.is_empty() => {
+                let file = tz.strip_prefix(':').unwrap_or(&tz);
+                let path = if file.starts_with('/') {
+                    file.into()
+                } else {
+                    std::path::Path::new("/usr/share/zoneinfo").join(file)
+                };
+                // Not a zone file: a rule such as `EST5EDT,M3.2.0,M11.1.0`.
+                std::fs::read(path).map_or_else(
+                    |_| super::posix::parse(&tz),
+                    |data| super::posix::from_tzif(&data),
+                )
+            }
+            _ => super::posix::from_tzif(&std::fs::read("/etc/localtime").ok()?),
+        }
+    }
+}
+
+/// POSIX TZ strings, from `$TZ` or the footer of a TZif file.
+#[cfg_attr(windows, allow(dead_code))]
+mod posix {
+    use super::{Daylight, Transition, Zone};
+
+    /// The current rule of a TZif (RFC 8536) version 2+ file: the POSIX TZ string
+    /// on its last line.
+    pub fn from_tzif(data: &[u8]) -> Option<Zone> {
+        if !data.starts_with(b"TZif") || data.get(4).is_none_or(|&version| version < b'2') {
+            return None;
+        }
+        let body = data.strip_suffix(b"\n")?;
+        let footer = &body[body.iter().rposition(|&b| b == b'\n')? + 1..];
+        parse(std::str::from_utf8(footer).ok()?)
+    }
+
+    /// A POSIX TZ string such as `PST8PDT,M3.2.0,M11.1.0` or `<+0530>-5:30`.
+    /// Daylight rules other than the `Mm.w.d` form are left out.
+    pub fn parse(tz: &str) -> Option<Zone> {
+        let mut rest = tz.trim();
+        let name = take_name(&mut rest)?;
+        // POSIX offsets count hours *west* of UTC.
+        let offset = -take_time(&mut rest)?;
+        let mut zone = Zone {
+            offset_minutes: i32::try_from(offset / 60).ok()?,
+            name,
+            daylight: None,
+        };
+        if rest.is_empty() {
+            return Some(zone);
+        }
+        zone.daylight = parse_daylight(rest, offset);
+        Some(zone)
+    }
+
+    /// `dst[offset],start[/time],end[/time]` after the standard part.
+    fn parse_daylight(mut rest: &str, offset: i64) -> Option<Daylight> {
+        let name = take_name(&mut rest)?;
+        let daylight_offset = if rest.starts_with(',') {
+            offset + 3600
+        } else {
+            -take_time(&mut rest)?
+        };
+        let mut rules = rest.strip_prefix(',')?.split(',');
+        Some(Daylight {
+            name,
+            delta_minutes: i32::try_from((daylight_offset - offset) / 60).ok()?,
+            start: parse_rule(rules.next()?)?,
+            end: parse_rule(rules.next()?)?,
+        })
+    }
+
+    /// `Mm.w.d[/time]`; the time defaults to 02:00.
+    fn parse_rule(rule: &str) -> Option<Transition> {
+        let (date, time) = match rule.split_once('/') {
+            Some((date, mut time)) => (date, take_time(&mut time)?),
+            None => (rule, 7200),
+        };
+        let mut parts = date.strip_prefix('M')?.split('.');
+        let mut number = || parts.next()?.parse::<u32>().ok();
+        let (month, week, weekday) = (number()?, number()?, number()?);
+        ((1..=12).contains(&month) && (1..=5).contains(&week) && weekday <= 6).then_some(
+            Transition {
+                month,
+                week,
+                weekday,
+                seconds: time,
+            },
+        )
+    }
+
+    /// `std` or `<+0530>`.
+    fn take_name(rest: &mut &str) -> Option<String> {
+        let (name, tail) = if let Some(quoted) = rest.strip_prefix('<') {
+            let (name, tail) = quoted.split_once('>')?;
+            (name, tail)
+        } else {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            rest.split_at(len)
+        };
+        *rest = tail;
+        (name.len() >= 3).then(|| name.to_owned())
+    }
+
+    /// `[+-]hh[:mm[:ss]]`, in seconds.
+    fn take_time(rest: &mut &str) -> Option<i64> {
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, ':' | '+' | '-')))
+            .unwrap_or(rest.len());
+        let (time, tail) = rest.split_at(len);
+        *rest = tail;
+        let (sign, time) = time.strip_prefix('-').map_or_else(
+            || (1, time.strip_prefix('+').unwrap_or(time)),
+            |time| (-1, time),
+        );
+        let mut seconds = 0;
+        for (index, part) in time.split(':').enumerate() {
+            if index > 2 {
+                return None;
+            }
+            seconds += part.parse::<i64>().ok()? * [3600, 60, 1][index];
+        }
+        Some(sign * seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::posix::{from_tzif, parse as parse_posix};
+    use super::*;
+
+    #[test]
+    fn pacific_rule_gives_the_2009_changes_powershell_sent() {
+        let zone = parse_posix("PST8PDT,M3.2.0,M11.1.0").expect("valid rule");
+        let tz = zone.to_time_zone(2009);
+
+        assert_eq!(tz.utc_offset_minutes, -480);
+        assert_eq!(tz.standard_name.as_deref(), Some("PST"));
+        assert_eq!(tz.daylight_name.as_deref(), Some("PDT"));
+        assert_eq!(
+            tz.daylight_changes,
+            [DaylightChanges {
+                year: 2009,
+                start_ticks: 0x08cb_6da8_b8ea_9000,
+                end_ticks: 0x08cc_28ae_273b_1000,
+                delta_ticks: 3600 * TICKS_PER_SECOND,
+            }]
+        );
+    }
+
+    #[test]
+    fn zones_without_daylight_saving_and_tzif_footers() {
+        let india = parse_posix("<+0530>-5:30").expect("valid rule");
+        assert_eq!(india.offset_minutes, 330);
+        assert_eq!(india.name, "+0530");
+        assert!(india.daylight.is_none());
+
+        // Last Sunday of March at 01:00 UTC, as local times.
+        let berlin = parse_posix("CET-1CEST,M3.5.0,M10.5.0/3").expect("valid rule");
+        let daylight = berlin.daylight.as_ref().expect("has daylight saving");
+        assert_eq!(daylight.delta_minutes, 60);
+        assert_eq!(daylight.end.seconds, 3 * 3600);
+        let start = berlin.to_time_zone(2026).daylight_changes[0].start_ticks;
+        let days = start / TICKS_PER_SECOND / 86_400 - DAYS_BEFORE_EPOCH;
+        assert_eq!(civil_from_days(days), (2026, 3, 29));
+
+        let mut tzif = b"TZif2".to_vec();
+        tzif.extend_from_slice(&[0; 40]);
+        tzif.extend_from_slice(b"\nUTC0\n");
+        assert_eq!(from_tzif(&tzif).expect("footer").offset_minutes, 0);
+        assert!(
+            from_tzif(b"TZif\0rest").is_none(),
+            "version 1 has no footer"
+        );
+
+        let julian = parse_posix("EST5EDT,J60,J300").expect("valid offset");
+        assert_eq!(julian.offset_minutes, -300);
+        assert!(julian.daylight.is_none(), "only Mm.w.d rules are read");
+    }
+}
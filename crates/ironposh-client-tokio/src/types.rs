@@ -1,4 +1,4 @@
-use ironposh_terminal::TerminalOp;
+use ironposh_terminal::{CommandStatus, GridView, TerminalOp};
 use tokio::sync::oneshot;
 
 use ironposh_client_core::host::{
@@ -6,6 +6,7 @@ use ironposh_client_core::host::{
 };
 use ironposh_psrp::PsValue;
 use std::collections::HashMap;
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub enum ReplControl {
@@ -75,6 +76,15 @@ pub enum HostUiResponse {
     BufferContents(Vec<Vec<BufferCell>>),
 }
 
+/// A change for the status bar.
+#[derive(Debug, Clone, Copy)]
+pub enum StatusUpdate {
+    CommandStarted(Instant),
+    CommandFinished(CommandStatus, Instant),
+    /// The session state, e.g. `disconnected`.
+    State(&'static str),
+}
+
 /// UI operations for the async UI handler
 #[derive(Debug)]
 pub enum TerminalOperation {
@@ -90,6 +100,15 @@ pub enum TerminalOperation {
     RequestInput { prompt: String },
     /// Check input for interrupt (Ctrl-C)
     CheckInterrupt,
+    /// Show a table in the grid viewer; blocks the UI until it is closed.
+    ShowGrid(Box<GridView>),
+    /// Page the output printed until the next prompt (`-- More --`). While
+    /// the pager waits for a key, the bounded UI and pipeline channels fill up
+    /// and hold back further output.
+    Paginate,
+    /// Keep the status bar (`--status-line`) up to date; ignored when it is
+    /// not shown.
+    Status(StatusUpdate),
     /// A synchronous UI request that needs a response (used by HostCalls).
     HostRequest {
         request: HostUiRequest,
@@ -1,3 +1,4 @@
 //! Local-only integration tests (no lab server required).
 
+mod mock_server;
 mod tls_options;
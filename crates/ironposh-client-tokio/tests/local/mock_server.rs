@@ -0,0 +1,300 @@
+//! Full client stack against the in-process mock WinRM/PSRP server.
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use ironposh_async::{HostResponse, RemoteAsyncPowershellClient, SessionHooks};
+use ironposh_client_core::connector::active_session::UserEvent;
+use ironposh_client_core::connector::config::SessionRecreation;
+use ironposh_client_core::connector::http::ServerAddress;
+use ironposh_client_core::connector::WinRmConfig;
+use ironposh_client_core::credentials::{ClientAuthIdentity, ClientUserName};
+use ironposh_client_core::host::testing::FakeHost;
+use ironposh_client_core::{AuthenticatorConfig, SspiAuthConfig};
+use ironposh_client_tokio::http_client::ReqwestHttpClient;
+use ironposh_psrp::{PipelineHostCall, PsPrimitiveValue, PsValue, RemoteHostMethodId};
+use ironposh_test_server::{CannedPipeline, MockAuth, MockServer, RunningServer};
+use ironposh_test_support::fake_server::test_config;
+
+const TEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+fn config_for(server: &RunningServer) -> WinRmConfig {
+    let mut config = test_config();
+    config.server = (
+        ServerAddress::parse("127.0.0.1").expect("loopback address"),
+        server.addr().port(),
+    );
+    config
+}
+
+#[tokio::test]
+async fn basic_auth_runs_canned_pipeline() {
+    let server = MockServer::builder()
+        .auth(MockAuth::basic("user", "pass"))
+        .pipelines(vec![CannedPipeline::new()
+            .string_output("hello")
+            .string_output("world")])
+        .build()
+        .start()
+        .await
+        .expect("start mock server");
+
+    let session = RemoteAsyncPowershellClient::open_task(
+        config_for(&server),
+        None,
+        ReqwestHttpClient::new(),
+        SessionHooks::default(),
+    );
+    let mut client = session.client;
+    let connection = tokio::spawn(session.connection_task);
+
+    let outputs = tokio::time::timeout(TEST_TIMEOUT, async {
+        let mut events = client
+            .send_script_raw("Write-Output hello, world".to_owned())
+            .await
+            .expect("invoke pipeline");
+        let mut outputs = Vec::new();
+        while let Some(event) = events.next().await {
+            match event {
+                UserEvent::PipelineOutput { output, .. } => outputs.push(
+                    output
+                        .assume_primitive_string()
+                        .expect("string output")
+                        .clone(),
+                ),
+                UserEvent::PipelineFinished { .. } => break,
+                _ => {}
+            }
+        }
+        outputs
+    })
+    .await
+    .expect("pipeline finishes before the timeout");
+
+    assert_eq!(outputs, ["hello", "world"]);
+    assert!(server
+        .requests()
+        .iter()
+        .all(|request| request.status != 401));
+
+    connection.abort();
+}
+
+#[tokio::test]
+async fn host_call_is_answered_by_fake_host() {
+    let read_line = PipelineHostCall::builder()
+        .call_id(1)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+    let server = MockServer::builder()
+        .auth(MockAuth::basic("user", "pass"))
+        .pipelines(vec![CannedPipeline::new()
+            .host_call(read_line, true)
+            .string_output("done")])
+        .build()
+        .start()
+        .await
+        .expect("start mock server");
+
+    let session = RemoteAsyncPowershellClient::open_task(
+        config_for(&server),
+        None,
+        ReqwestHttpClient::new(),
+        SessionHooks::default(),
+    );
+    let mut client = session.client;
+    let (mut host_calls, submitter) = session.host_io.into_parts();
+    let connection = tokio::spawn(session.connection_task);
+    let answering = tokio::spawn(async move {
+        let mut host = FakeHost::new().with_read_line("yes");
+        let call = host_calls.next().await.expect("a host call");
+        let (call_id, scope) = (call.call_id(), call.scope());
+        let submission = host.answer(call).expect("queued answer");
+        submitter
+            .submit(HostResponse {
+                call_id,
+                scope,
+                submission,
+            })
+            .expect("submit host response");
+        host
+    });
+
+    let outputs = tokio::time::timeout(TEST_TIMEOUT, async {
+        let mut events = client
+            .send_script_raw("Read-Host".to_owned())
+            .await
+            .expect("invoke pipeline");
+        let mut outputs = Vec::new();
+        while let Some(event) = events.next().await {
+            match event {
+                UserEvent::PipelineOutput { output, .. } => outputs.push(
+                    output
+                        .assume_primitive_string()
+                        .expect("string output")
+                        .clone(),
+                ),
+                UserEvent::PipelineFinished { .. } => break,
+                _ => {}
+            }
+        }
+        outputs
+    })
+    .await
+    .expect("pipeline finishes before the timeout");
+
+    let host = answering.await.expect("host task");
+    assert_eq!(host.calls(), ["ReadLine"]);
+    assert_eq!(outputs, ["done"]);
+
+    connection.abort();
+}
+
+/// String outputs of a pipeline stream until it ends, and the
+/// `FullyQualifiedErrorId`s of its error records.
+async fn drain(
+    mut events: impl futures::Stream<Item = UserEvent> + Unpin,
+) -> (Vec<String>, Vec<String>) {
+    let (mut outputs, mut errors) = (Vec::new(), Vec::new());
+    while let Some(event) = events.next().await {
+        match event {
+            UserEvent::PipelineOutput { output, .. } => outputs.push(
+                output
+                    .assume_primitive_string()
+                    .expect("string output")
+                    .clone(),
+            ),
+            UserEvent::ErrorRecord { error_record, .. } => {
+                errors.extend(error_record.fully_qualified_error_id);
+            }
+            _ => {}
+        }
+    }
+    (outputs, errors)
+}
+
+#[tokio::test]
+async fn dropped_shell_is_recreated_and_unanswered_pipeline_replayed() {
+    // The first pipeline keeps running, waiting for a host response that never
+    // comes; the shell is dropped when the second pipeline's Command arrives.
+    let read_line = PipelineHostCall::builder()
+        .call_id(1)
+        .method(RemoteHostMethodId::ReadLine)
+        .build();
+    let server = MockServer::builder()
+        .auth(MockAuth::basic("user", "pass"))
+        .pipelines(vec![
+            CannedPipeline::new()
+                .string_output("running")
+                .host_call(read_line, true),
+            CannedPipeline::new().string_output("replayed"),
+        ])
+        .drop_shell_at_command(1)
+        .build()
+        .start()
+        .await
+        .expect("start mock server");
+
+    let mut config = config_for(&server);
+    config.recreate_session = Some(SessionRecreation::default());
+    let session = RemoteAsyncPowershellClient::open_task(
+        config,
+        None,
+        ReqwestHttpClient::new(),
+        SessionHooks::default(),
+    );
+    let mut client = session.client;
+    // Held so the host call stays pending.
+    let _host_io = session.host_io;
+    let connection = tokio::spawn(session.connection_task);
+
+    let (running, replayed) = tokio::time::timeout(TEST_TIMEOUT, async {
+        let mut running = client
+            .send_script_raw("Read-Host".to_owned())
+            .await
+            .expect("invoke first pipeline");
+        assert!(matches!(
+            running.next().await,
+            Some(UserEvent::PipelineCreated { .. })
+        ));
+        let replayed = client
+            .send_script_raw("Write-Output replayed".to_owned())
+            .await
+            .expect("invoke second pipeline");
+        tokio::join!(drain(running), drain(replayed))
+    })
+    .await
+    .expect("both pipelines end before the timeout");
+
+    assert_eq!(running.1, ["PipelineLostWithShell"]);
+    assert_eq!(replayed.0, ["replayed"]);
+    assert!(replayed.1.is_empty());
+    let creates = server
+        .requests()
+        .iter()
+        .filter(|request| request.body.contains("/transfer/Create<"))
+        .count();
+    assert_eq!(creates, 2, "a second shell is opened");
+
+    connection.abort();
+}
+
+#[tokio::test]
+async fn basic_auth_with_wrong_password_fails() {
+    let server = MockServer::builder()
+        .auth(MockAuth::basic("user", "other"))
+        .build()
+        .start()
+        .await
+        .expect("start mock server");
+
+    let session = RemoteAsyncPowershellClient::open_task(
+        config_for(&server),
+        None,
+        ReqwestHttpClient::new(),
+        SessionHooks::default(),
+    );
+    let result = tokio::time::timeout(TEST_TIMEOUT, session.connection_task)
+        .await
+        .expect("connection task ends before the timeout");
+
+    assert!(result.is_err(), "a rejected login must fail the session");
+    assert!(server
+        .requests()
+        .iter()
+        .any(|request| request.status == 401));
+}
+
+#[tokio::test]
+async fn negotiate_token_is_sent_and_rejection_fails_the_session() {
+    let server = MockServer::builder()
+        .auth(MockAuth::Negotiate)
+        .build()
+        .start()
+        .await
+        .expect("start mock server");
+
+    let mut config = config_for(&server);
+    let username = ClientUserName::new("user", None).expect("username");
+    config.authentication = AuthenticatorConfig::Sspi(SspiAuthConfig::Negotiate {
+        target: "127.0.0.1".to_owned(),
+        identity: ClientAuthIdentity::new(username, "pass".to_owned()),
+        kerberos_config: None,
+    });
+
+    let session = RemoteAsyncPowershellClient::open_task(
+        config,
+        None,
+        ReqwestHttpClient::new(),
+        SessionHooks::default(),
+    );
+    let result = tokio::time::timeout(TEST_TIMEOUT, session.connection_task)
+        .await
+        .expect("connection task ends before the timeout");
+
+    assert!(result.is_err(), "a rejected login must fail the session");
+    assert!(server.requests().iter().any(|request| request
+        .authorization
+        .as_deref()
+        .is_some_and(|value| value.starts_with("Negotiate "))));
+}
@@ -0,0 +1,48 @@
+namespace IronPosh;
+
+/// <summary>How <see cref="PowerShellSession.Connect"/> authenticates.</summary>
+public enum AuthenticationMethod
+{
+    Negotiate,
+    Basic,
+    Ntlm,
+    Kerberos,
+}
+
+/// <summary>Where and how to connect. Serialized as JSON for the native library.</summary>
+public sealed record ConnectionOptions
+{
+    public required string Server { get; init; }
+
+    /// <summary>Defaults to 5985, or 5986 with <see cref="Https"/>.</summary>
+    public ushort? Port { get; init; }
+
+    public bool Https { get; init; }
+
+    /// <summary>Accept any server certificate. Lab use only.</summary>
+    public bool AcceptInvalidCerts { get; init; }
+
+    public AuthenticationMethod Auth { get; init; } = AuthenticationMethod.Negotiate;
+
+    public required string Username { get; init; }
+
+    public required string Password { get; init; }
+
+    public string? Domain { get; init; }
+
+    /// <summary>JEA endpoint; <c>null</c> is <c>Microsoft.PowerShell</c>.</summary>
+    public string? ConfigurationName { get; init; }
+
+    public ushort Columns { get; init; } = 120;
+
+    public ushort Rows { get; init; } = 30;
+
+    /// <summary>Most Receive requests in flight at once; <c>null</c> is unlimited.</summary>
+    public int? MaxConcurrentReceives { get; init; }
+
+    /// <summary>
+    /// Per-pipeline budget, in bytes, of output not yet read from the <see cref="Pipeline"/>.
+    /// A pipeline over it is not polled until it is read; <c>null</c> is unlimited.
+    /// </summary>
+    public long? MaxPendingOutputBytes { get; init; }
+}
@@ -0,0 +1,39 @@
+using System.Collections.Generic;
+using System.Text.Json;
+using System.Text.Json.Nodes;
+using System.Threading.Tasks;
+
+namespace IronPosh;
+
+/// <summary>
+/// A call from the server into the host, e.g. <c>ReadLine</c> for
+/// <c>Read-Host</c> or <c>WriteProgress</c>. <see cref="Parameters"/> holds the
+/// method's arguments for the UI methods (<c>Write*</c>, <c>Prompt*</c>,
+/// <c>WriteProgress</c>) and is empty for the others.
+/// </summary>
+public sealed record HostCall(string Method, long CallId, IReadOnlyList<JsonElement> Parameters)
+{
+    /// <summary>
+    /// Whether the server waits for an answer. Calls without one (<c>Write*</c>,
+    /// progress, RawUI setters) are notifications.
+    /// </summary>
+    public bool ExpectsResponse { get; init; }
+}
+
+/// <summary>
+/// Implements the PowerShell host for a session. Called on a native thread;
+/// answers may be produced asynchronously.
+/// </summary>
+public interface IPowerShellHost
+{
+    /// <summary>
+    /// Handle <paramref name="call"/>. For a call that
+    /// <see cref="HostCall.ExpectsResponse"/>, return its result: a string
+    /// (<c>ReadLine</c>, <c>GetName</c>), a number (<c>PromptForChoice</c>),
+    /// an object of field answers (<c>Prompt</c>),
+    /// <c>{"userName", "password"}</c> (<c>PromptForCredential</c>). Throwing
+    /// raises the exception in the remote script. The result of a notification
+    /// is ignored.
+    /// </summary>
+    Task<JsonNode?> HandleAsync(HostCall call);
+}
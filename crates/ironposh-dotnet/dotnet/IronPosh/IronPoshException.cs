@@ -0,0 +1,12 @@
+using System;
+
+namespace IronPosh;
+
+/// <summary>A session or pipeline operation failed in the native client.</summary>
+public sealed class IronPoshException : Exception
+{
+    public IronPoshException(string message)
+        : base(message)
+    {
+    }
+}
@@ -0,0 +1,95 @@
+using System;
+using System.Runtime.InteropServices;
+
+namespace IronPosh;
+
+internal enum Status
+{
+    Ok = 0,
+    Error = 1,
+    Timeout = 2,
+    Finished = 3,
+    InvalidArgument = 4,
+}
+
+[UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+internal delegate void HostCallback(IntPtr userData, ulong token, IntPtr callJson);
+
+/// <summary>P/Invoke declarations for the <c>ironposh_dotnet</c> native library.</summary>
+internal static class NativeMethods
+{
+    private const string Library = "ironposh_dotnet";
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void ironposh_string_free(IntPtr value);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern Status ironposh_session_connect(
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string configJson,
+        HostCallback? hostCallback,
+        IntPtr userData,
+        out IntPtr session,
+        out IntPtr error);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern Status ironposh_host_respond(
+        IntPtr session,
+        ulong token,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string replyJson,
+        out IntPtr error);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void ironposh_session_free(IntPtr session);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern Status ironposh_pipeline_start(
+        IntPtr session,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string script,
+        out IntPtr pipeline,
+        out IntPtr error);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern Status ironposh_pipeline_next(
+        IntPtr pipeline,
+        long timeoutMs,
+        out IntPtr eventJson,
+        out IntPtr error);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern Status ironposh_pipeline_stop(IntPtr pipeline, out IntPtr error);
+
+    [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void ironposh_pipeline_free(IntPtr pipeline);
+
+    /// <summary>Copy a string returned by the library and free the original.</summary>
+    internal static string TakeString(IntPtr value)
+    {
+        if (value == IntPtr.Zero)
+        {
+            return string.Empty;
+        }
+
+        try
+        {
+            return Marshal.PtrToStringUTF8(value) ?? string.Empty;
+        }
+        finally
+        {
+            ironposh_string_free(value);
+        }
+    }
+
+    /// <summary>Throw for anything but <see cref="Status.Ok"/>.</summary>
+    internal static void Check(Status status, IntPtr error)
+    {
+        switch (status)
+        {
+            case Status.Ok:
+                return;
+            case Status.InvalidArgument:
+                throw new ArgumentException("invalid argument passed to the native library");
+            default:
+                throw new IronPoshException(TakeString(error));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+using System;
+using System.Collections.Generic;
+using System.Runtime.CompilerServices;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace IronPosh;
+
+/// <summary>
+/// A running script. Read its events with <see cref="ReadAllAsync"/>; dispose
+/// it before its session.
+/// </summary>
+public sealed class Pipeline : IDisposable
+{
+    /// <summary>How long one native wait lasts before cancellation is checked again.</summary>
+    private const long PollMilliseconds = 250;
+
+    private IntPtr handle;
+
+    internal Pipeline(IntPtr handle)
+    {
+        this.handle = handle;
+    }
+
+    /// <summary>
+    /// The pipeline's events in order, until it finishes. Each native wait runs
+    /// on the thread pool; cancelling stops reading but not the pipeline, see
+    /// <see cref="Stop"/>.
+    /// </summary>
+    public async IAsyncEnumerable<PipelineEvent> ReadAllAsync(
+        [EnumeratorCancellation] CancellationToken cancellationToken = default)
+    {
+        while (true)
+        {
+            cancellationToken.ThrowIfCancellationRequested();
+            var (status, json) = await Task.Run(Next, cancellationToken).ConfigureAwait(false);
+            switch (status)
+            {
+                case Status.Finished:
+                    yield break;
+                case Status.Timeout:
+                    continue;
+                default:
+                    yield return PipelineEvent.Parse(json);
+                    break;
+            }
+        }
+    }
+
+    /// <summary>Stop the pipeline. The events already produced are still read.</summary>
+    public void Stop()
+    {
+        var status = NativeMethods.ironposh_pipeline_stop(Handle, out var error);
+        NativeMethods.Check(status, error);
+    }
+
+    public void Dispose()
+    {
+        var handle = Interlocked.Exchange(ref this.handle, IntPtr.Zero);
+        NativeMethods.ironposh_pipeline_free(handle);
+    }
+
+    private IntPtr Handle => handle != IntPtr.Zero
+        ? handle
+        : throw new ObjectDisposedException(nameof(Pipeline));
+
+    private (Status Status, string Json) Next()
+    {
+        var status = NativeMethods.ironposh_pipeline_next(Handle, PollMilliseconds, out var eventJson, out var error);
+        switch (status)
+        {
+            case Status.Ok:
+                return (status, NativeMethods.TakeString(eventJson));
+            case Status.Timeout or Status.Finished:
+                return (status, string.Empty);
+            default:
+                NativeMethods.Check(status, error);
+                return (status, string.Empty);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+using System.Text.Json;
+
+namespace IronPosh;
+
+/// <summary>The stream an event came from.</summary>
+public enum PipelineEventKind
+{
+    Output,
+    Error,
+    Debug,
+    Verbose,
+    Warning,
+    Information,
+    Progress,
+    Unsupported,
+}
+
+/// <summary>
+/// One event of a running pipeline. For <see cref="PipelineEventKind.Output"/>,
+/// <see cref="Text"/> is the object as PowerShell would print it and
+/// <see cref="Value"/> the object itself; for the other streams
+/// <see cref="Text"/> is the message.
+/// </summary>
+public sealed record PipelineEvent(PipelineEventKind Kind, string Text, JsonElement? Value)
+{
+    internal static PipelineEvent Parse(string json)
+    {
+        using var document = JsonDocument.Parse(json);
+        var root = document.RootElement;
+        var kind = root.GetProperty("type").GetString() switch
+        {
+            "output" => PipelineEventKind.Output,
+            "error" => PipelineEventKind.Error,
+            "debug" => PipelineEventKind.Debug,
+            "verbose" => PipelineEventKind.Verbose,
+            "warning" => PipelineEventKind.Warning,
+            "information" => PipelineEventKind.Information,
+            "progress" => PipelineEventKind.Progress,
+            _ => PipelineEventKind.Unsupported,
+        };
+        var text = kind switch
+        {
+            PipelineEventKind.Output => root.GetProperty("text").GetString(),
+            PipelineEventKind.Progress => root.GetProperty("activity").GetString(),
+            _ => root.GetProperty("message").GetString(),
+        };
+        JsonElement? value = kind is PipelineEventKind.Output or PipelineEventKind.Progress
+            ? root.Clone()
+            : null;
+        return new PipelineEvent(kind, text ?? string.Empty, value);
+    }
+}
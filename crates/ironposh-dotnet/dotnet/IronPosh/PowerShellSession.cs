@@ -0,0 +1,170 @@
+using System;
+using System.Collections.Generic;
+using System.Runtime.InteropServices;
+using System.Text.Json;
+using System.Text.Json.Nodes;
+using System.Text.Json.Serialization;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace IronPosh;
+
+/// <summary>
+/// A PowerShell remoting session: one runspace pool on the server, driven by
+/// the native client on its own threads.
+/// </summary>
+public sealed class PowerShellSession : IDisposable
+{
+    private static readonly JsonSerializerOptions OptionsJson = new()
+    {
+        PropertyNamingPolicy = JsonNamingPolicy.CamelCase,
+        Converters = { new JsonStringEnumConverter(JsonNamingPolicy.CamelCase) },
+    };
+
+    private readonly IPowerShellHost? host;
+
+    /// <summary>Kept alive for as long as the native side may call it.</summary>
+    private readonly HostCallback? callback;
+
+    /// <summary>Host calls can arrive before <c>Connect</c> has returned the handle.</summary>
+    private readonly TaskCompletionSource<IntPtr> connected = new(TaskCreationOptions.RunContinuationsAsynchronously);
+
+    private GCHandle self;
+    private IntPtr handle;
+
+    private PowerShellSession(IPowerShellHost? host)
+    {
+        this.host = host;
+        if (host is not null)
+        {
+            callback = OnHostCall;
+            self = GCHandle.Alloc(this);
+        }
+    }
+
+    /// <summary>
+    /// Connect and open the runspace pool. Without a <paramref name="host"/>
+    /// the server is offered no host, and scripts that need one
+    /// (<c>Read-Host</c>, <c>Get-Credential</c>) fail.
+    /// </summary>
+    public static PowerShellSession Connect(ConnectionOptions options, IPowerShellHost? host = null)
+    {
+        var session = new PowerShellSession(host);
+        var status = NativeMethods.ironposh_session_connect(
+            JsonSerializer.Serialize(options, OptionsJson),
+            session.callback,
+            session.self.IsAllocated ? GCHandle.ToIntPtr(session.self) : IntPtr.Zero,
+            out var handle,
+            out var error);
+        if (status is not Status.Ok)
+        {
+            session.connected.SetCanceled();
+            session.ReleaseSelf();
+            NativeMethods.Check(status, error);
+        }
+
+        session.handle = handle;
+        session.connected.SetResult(handle);
+        return session;
+    }
+
+    /// <summary><see cref="Connect"/> on the thread pool.</summary>
+    public static Task<PowerShellSession> ConnectAsync(ConnectionOptions options, IPowerShellHost? host = null) =>
+        Task.Run(() => Connect(options, host));
+
+    /// <summary>Start <paramref name="script"/>; its output arrives as objects, not formatted text.</summary>
+    public Pipeline Start(string script)
+    {
+        var status = NativeMethods.ironposh_pipeline_start(Handle, script, out var pipeline, out var error);
+        NativeMethods.Check(status, error);
+        return new Pipeline(pipeline);
+    }
+
+    /// <summary>Run <paramref name="script"/> to completion and collect its events.</summary>
+    public async Task<IReadOnlyList<PipelineEvent>> InvokeAsync(string script, CancellationToken cancellationToken = default)
+    {
+        using var pipeline = Start(script);
+        var events = new List<PipelineEvent>();
+        await foreach (var e in pipeline.ReadAllAsync(cancellationToken).ConfigureAwait(false))
+        {
+            events.Add(e);
+        }
+
+        return events;
+    }
+
+    /// <summary>Close the session. Dispose its pipelines first.</summary>
+    public void Dispose()
+    {
+        var handle = Interlocked.Exchange(ref this.handle, IntPtr.Zero);
+        if (handle != IntPtr.Zero)
+        {
+            NativeMethods.ironposh_session_free(handle);
+            ReleaseSelf();
+        }
+    }
+
+    private IntPtr Handle => handle != IntPtr.Zero
+        ? handle
+        : throw new ObjectDisposedException(nameof(PowerShellSession));
+
+    private void ReleaseSelf()
+    {
+        if (self.IsAllocated)
+        {
+            self.Free();
+        }
+    }
+
+    private static void OnHostCall(IntPtr userData, ulong token, IntPtr callJson)
+    {
+        var session = (PowerShellSession)GCHandle.FromIntPtr(userData).Target!;
+        using var document = JsonDocument.Parse(Marshal.PtrToStringUTF8(callJson) ?? "{}");
+        var root = document.RootElement;
+        var parameters = new List<JsonElement>();
+        foreach (var parameter in root.GetProperty("parameters").EnumerateArray())
+        {
+            parameters.Add(parameter.Clone());
+        }
+
+        var call = new HostCall(root.GetProperty("method").GetString() ?? string.Empty, root.GetProperty("callId").GetInt64(), parameters)
+        {
+            ExpectsResponse = token != 0,
+        };
+
+        // Return to the native thread right away; answers come back through
+        // `ironposh_host_respond`.
+        _ = session.HandleAsync(call, token);
+    }
+
+    private async Task HandleAsync(HostCall call, ulong token)
+    {
+        JsonObject reply;
+        try
+        {
+            reply = new JsonObject { ["result"] = await host!.HandleAsync(call).ConfigureAwait(false) };
+        }
+        catch (Exception e)
+        {
+            reply = new JsonObject { ["error"] = e.Message };
+        }
+
+        if (token == 0)
+        {
+            return;
+        }
+
+        var handle = await connected.Task.ConfigureAwait(false);
+        if (this.handle == IntPtr.Zero)
+        {
+            // Disposed while the host was answering.
+            return;
+        }
+
+        var status = NativeMethods.ironposh_host_respond(handle, token, reply.ToJsonString(), out var error);
+        if (status is not Status.Ok)
+        {
+            NativeMethods.TakeString(error);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! The connection settings a .NET host sends as JSON, mapped onto
+//! [`WinRmConfig`].
+
+use anyhow::Context;
+use ironposh::WinRmConfig;
+use ironposh::auth::{
+    AuthenticatorConfig, ClientAuthIdentity, ClientUserName, Delegation, KerberosConfig,
+    SspiAuthConfig,
+};
+use ironposh::config::{
+    HostDefaultData, HostInfo, HostMode, ParseMode, ReceiveFlowConfig, ServerAddress, Size,
+    TlsOptions, TransportSecurity,
+};
+use serde::Deserialize;
+
+/// How the session authenticates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DotnetAuth {
+    Basic,
+    Ntlm,
+    Kerberos,
+    #[default]
+    Negotiate,
+}
+
+/// `ConnectionOptions` on the .NET side; field names are camelCase in JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DotnetConfig {
+    pub server: String,
+    /// Defaults to 5985, or 5986 with `https`.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub https: bool,
+    /// Accept any server certificate. Lab use only.
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    #[serde(default)]
+    pub auth: DotnetAuth,
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// JEA endpoint; `None` is `Microsoft.PowerShell`.
+    #[serde(default)]
+    pub configuration_name: Option<String>,
+    #[serde(default = "default_columns")]
+    pub columns: u16,
+    #[serde(default = "default_rows")]
+    pub rows: u16,
+    /// Most Receive requests in flight at once; `None` is unlimited.
+    #[serde(default)]
+    pub max_concurrent_receives: Option<usize>,
+    /// Per-pipeline budget of output not yet taken by
+    /// `ironposh_pipeline_next`; `None` is unlimited.
+    #[serde(default)]
+    pub max_pending_output_bytes: Option<usize>,
+}
+
+fn default_columns() -> u16 {
+    120
+}
+
+fn default_rows() -> u16 {
+    30
+}
+
+impl DotnetConfig {
+    /// `interactive` is whether the .NET side registered a host; without one
+    /// the server is offered a null host and does not call back.
+    pub fn into_winrm_config(self, interactive: bool) -> anyhow::Result<WinRmConfig> {
+        let server = ServerAddress::parse(&self.server)
+            .with_context(|| format!("invalid server address {}", self.server))?;
+        let port = self.port.unwrap_or(if self.https { 5986 } else { 5985 });
+
+        let authentication = if self.auth == DotnetAuth::Basic {
+            AuthenticatorConfig::Basic {
+                username: self.username,
+                password: self.password,
+            }
+        } else {
+            let user_name = ClientUserName::new(&self.username, self.domain.as_deref())?;
+            let identity = ClientAuthIdentity::new(user_name, self.password);
+            let target = self.server.clone();
+            let kerberos_config = KerberosConfig {
+                kdc: None,
+                client_computer_name: self.server.clone(),
+                delegation: Delegation::None,
+            };
+            AuthenticatorConfig::Sspi(match self.auth {
+                DotnetAuth::Ntlm => SspiAuthConfig::NTLM { target, identity },
+                DotnetAuth::Kerberos => SspiAuthConfig::Kerberos {
+                    target,
+                    identity,
+                    kerberos_config,
+                },
+                DotnetAuth::Negotiate | DotnetAuth::Basic => SspiAuthConfig::Negotiate {
+                    target,
+                    identity,
+                    kerberos_config: Some(kerberos_config),
+                },
+            })
+        };
+
+        // Basic has no SSPI context to seal plain HTTP with.
+        let transport = match (self.https, &authentication) {
+            (true, _) => TransportSecurity::Https,
+            (false, AuthenticatorConfig::Basic { .. }) => TransportSecurity::HttpInsecure,
+            (false, _) => TransportSecurity::Http,
+        };
+
+        let size = Size {
+            width: i32::from(self.columns),
+            height: i32::from(self.rows),
+        };
+        let host_info = HostInfo::builder()
+            .host_default_data(
+                HostDefaultData::builder()
+                    .buffer_size(size.clone())
+                    .window_size(size.clone())
+                    .max_window_size(size.clone())
+                    .max_physical_window_size(size)
+                    .build()
+                    .context("columns and rows do not describe a usable console")?,
+            )
+            .build();
+
+        Ok(WinRmConfig::builder()
+            .server((server, port))
+            .transport(transport)
+            .authentication(authentication)
+            .host_info(host_info)
+            .tls(TlsOptions {
+                accept_invalid_certs: self.accept_invalid_certs,
+                ..TlsOptions::default()
+            })
+            .configuration_name(self.configuration_name)
+            .receive_flow(ReceiveFlowConfig {
+                max_concurrent_receives: self.max_concurrent_receives,
+                max_pending_output_bytes: self.max_pending_output_bytes,
+            })
+            .host_mode(if interactive {
+                HostMode::Interactive
+            } else {
+                HostMode::Headless
+            })
+            .parse_mode(ParseMode::Strict)
+            .build())
+    }
+}
@@ -0,0 +1,415 @@
+//! Host calls from the server, forwarded to the .NET host as JSON.
+//!
+//! Every call goes to the registered [`HostCallback`] with a token. A call
+//! that returns a value stays pending under that token until the host answers
+//! it with `ironposh_host_respond`; void calls (`Write*`, progress, RawUI
+//! setters) get token 0 and are acknowledged right away.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use futures::StreamExt;
+use futures::channel::mpsc::UnboundedReceiver;
+use ironposh::client::{HostResponse, HostSubmitter};
+use ironposh::host::{
+    Coordinates, HostCall, PSCredential, PipelineHostResponse, RemoteHostMethodId, Size, Submission,
+};
+use ironposh::value::{PsPrimitiveValue, PsValue};
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+
+use crate::into_c_string;
+
+/// Receives host calls. `call_json` is only valid for the duration of the
+/// call; `token` is 0 when no answer is expected.
+pub type HostCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, token: u64, call_json: *const c_char);
+
+/// The host's `user_data`, passed back untouched.
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// SAFETY: the pointer is never dereferenced here; the .NET side hands in a
+// GCHandle that is valid on any thread.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+pub struct HostBridge {
+    callback: HostCallback,
+    user_data: UserData,
+    submitter: HostSubmitter,
+    next_token: AtomicU64,
+    pending: Mutex<HashMap<u64, HostCall>>,
+}
+
+impl HostBridge {
+    pub fn new(callback: HostCallback, user_data: *mut c_void, submitter: HostSubmitter) -> Self {
+        Self {
+            callback,
+            user_data: UserData(user_data),
+            submitter,
+            next_token: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The calls waiting for an answer. A panic while the lock was held
+    /// cannot leave the map half-updated, so a poisoned lock is used as is.
+    fn pending(&self) -> MutexGuard<'_, HashMap<u64, HostCall>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Forward host calls until the session ends.
+    pub async fn run(self: Arc<Self>, mut host_calls: UnboundedReceiver<HostCall>) {
+        while let Some(call) = host_calls.next().await {
+            let json = describe(&call).to_string();
+            debug!(
+                method = call.method_name(),
+                call_id = call.call_id(),
+                "forwarding host call"
+            );
+            if call.should_send_response() {
+                let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+                self.pending().insert(token, call);
+                self.notify(token, json);
+            } else {
+                self.notify(0, json);
+                self.submit(&call, Submission::NoSend);
+            }
+        }
+    }
+
+    /// Answer the call pending under `token` with the host's `reply`:
+    /// `{"result": ...}`, or `{"error": "message"}` to raise an exception in
+    /// the script.
+    pub fn respond(&self, token: u64, reply: &str) -> anyhow::Result<()> {
+        let reply: Value = serde_json::from_str(reply)?;
+        let call = self
+            .pending()
+            .remove(&token)
+            .ok_or_else(|| anyhow::anyhow!("no host call is pending under token {token}"))?;
+
+        let (call_id, method) = (call.call_id(), call.method());
+        let scope = call.scope();
+        let submission = reply
+            .get("error")
+            .map_or_else(
+                || accept(call, reply.get("result").unwrap_or(&Value::Null)),
+                |error| Err(error.as_str().unwrap_or("host call failed").to_owned()),
+            )
+            .unwrap_or_else(|message| exception(call_id, method, message));
+
+        self.submitter.submit(HostResponse {
+            call_id,
+            scope,
+            submission,
+        })
+    }
+
+    fn notify(&self, token: u64, json: String) {
+        let json = into_c_string(json);
+        // SAFETY: the callback contract is documented on `HostCallback`; the
+        // string stays alive until it returns.
+        unsafe { (self.callback)(self.user_data.0, token, json) };
+        drop(unsafe { std::ffi::CString::from_raw(json) });
+    }
+
+    fn submit(&self, call: &HostCall, submission: Submission) {
+        let response = HostResponse {
+            call_id: call.call_id(),
+            scope: call.scope(),
+            submission,
+        };
+        if let Err(e) = self.submitter.submit(response) {
+            warn!(error = %e, "failed to submit host call response");
+        }
+    }
+}
+
+/// `{"method", "callId", "parameters"}`. Parameters are given for the calls a
+/// .NET host is expected to implement; the rest carry an empty array.
+fn describe(call: &HostCall) -> Value {
+    let parameters = match call {
+        HostCall::Write1 { transport } => json!([transport.params.0]),
+        HostCall::Write2 { transport } => {
+            let (foreground, background, text) = &transport.params;
+            json!([foreground, background, text])
+        }
+        HostCall::WriteLine2 { transport } => json!([transport.params.0]),
+        HostCall::WriteLine3 { transport } => {
+            let (foreground, background, text) = &transport.params;
+            json!([foreground, background, text])
+        }
+        HostCall::WriteErrorLine { transport } => json!([transport.params.0]),
+        HostCall::WriteDebugLine { transport } => json!([transport.params.0]),
+        HostCall::WriteVerboseLine { transport } => json!([transport.params.0]),
+        HostCall::WriteWarningLine { transport } => json!([transport.params.0]),
+        HostCall::WriteProgress { transport } => {
+            let (source_id, record) = &transport.params;
+            json!([source_id, {
+                "activity": record.activity,
+                "statusDescription": record.status_description,
+                "currentOperation": record.current_operation,
+                "activityId": record.activity_id,
+                "parentActivityId": record.parent_activity_id,
+                "percentComplete": record.percent_complete,
+            }])
+        }
+        HostCall::SetShouldExit { transport } => json!([transport.params.0]),
+        HostCall::SetWindowTitle { transport } => json!([transport.params.0]),
+        HostCall::Prompt { transport } => {
+            let (caption, message, fields) = &transport.params;
+            let fields: Vec<Value> = fields
+                .iter()
+                .map(|field| {
+                    json!({
+                        "name": field.name,
+                        "label": field.label,
+                        "helpMessage": field.help_message,
+                        "isMandatory": field.is_mandatory,
+                    })
+                })
+                .collect();
+            json!([caption, message, fields])
+        }
+        HostCall::PromptForCredential1 { transport } => {
+            let (caption, message, user_name, target_name) = &transport.params;
+            json!([caption, message, user_name, target_name])
+        }
+        HostCall::PromptForCredential2 { transport } => {
+            let (caption, message, user_name, target_name, ..) = &transport.params;
+            json!([caption, message, user_name, target_name])
+        }
+        HostCall::PromptForChoice { transport } => {
+            let (caption, message, choices, default) = &transport.params;
+            json!([caption, message, choice_labels(choices), default])
+        }
+        HostCall::PromptForChoiceMultipleSelection { transport } => {
+            let (caption, message, choices, defaults) = &transport.params;
+            json!([caption, message, choice_labels(choices), defaults])
+        }
+        _ => json!([]),
+    };
+    json!({
+        "method": call.method_name(),
+        "callId": call.call_id(),
+        "parameters": parameters,
+    })
+}
+
+fn choice_labels(choices: &[ironposh::host::ChoiceDescription]) -> Vec<Value> {
+    choices
+        .iter()
+        .map(|choice| json!({ "label": choice.label, "helpMessage": choice.help_message }))
+        .collect()
+}
+
+/// The host's `result` converted to the return type of `call`.
+fn accept(call: HostCall, result: &Value) -> Result<Submission, String> {
+    Ok(match call {
+        HostCall::GetName { transport } => transport.into_parts().1.accept_result(string(result)?),
+        HostCall::GetVersion { transport } => {
+            transport.into_parts().1.accept_result(string(result)?)
+        }
+        HostCall::GetCurrentCulture { transport } => {
+            transport.into_parts().1.accept_result(string(result)?)
+        }
+        HostCall::GetCurrentUICulture { transport } => {
+            transport.into_parts().1.accept_result(string(result)?)
+        }
+        HostCall::ReadLine { transport } => transport.into_parts().1.accept_result(string(result)?),
+        HostCall::GetWindowTitle { transport } => {
+            transport.into_parts().1.accept_result(string(result)?)
+        }
+        HostCall::GetInstanceId { transport } => {
+            let id = uuid::Uuid::parse_str(&string(result)?).map_err(|e| e.to_string())?;
+            transport.into_parts().1.accept_result(id)
+        }
+        HostCall::ReadLineAsSecureString { transport } => transport
+            .into_parts()
+            .1
+            .accept_result(secure_string(&string(result)?)),
+        HostCall::Prompt { transport } => {
+            let answers = result
+                .as_object()
+                .ok_or("expected an object of field answers")?
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), ps_value(value)?)))
+                .collect::<Result<_, String>>()?;
+            transport.into_parts().1.accept_result(answers)
+        }
+        HostCall::PromptForCredential1 { transport } => {
+            transport.into_parts().1.accept_result(credential(result)?)
+        }
+        HostCall::PromptForCredential2 { transport } => {
+            transport.into_parts().1.accept_result(credential(result)?)
+        }
+        HostCall::PromptForChoice { transport } => {
+            transport.into_parts().1.accept_result(int(result)?)
+        }
+        HostCall::PromptForChoiceMultipleSelection { transport } => {
+            let selected = result
+                .as_array()
+                .ok_or("expected an array of choice indexes")?
+                .iter()
+                .map(int)
+                .collect::<Result<_, String>>()?;
+            transport.into_parts().1.accept_result(selected)
+        }
+        HostCall::GetForegroundColor { transport } => {
+            transport.into_parts().1.accept_result(int(result)?)
+        }
+        HostCall::GetBackgroundColor { transport } => {
+            transport.into_parts().1.accept_result(int(result)?)
+        }
+        HostCall::GetCursorSize { transport } => {
+            transport.into_parts().1.accept_result(int(result)?)
+        }
+        HostCall::GetKeyAvailable { transport } => {
+            transport.into_parts().1.accept_result(boolean(result)?)
+        }
+        HostCall::GetIsRunspacePushed { transport } => {
+            transport.into_parts().1.accept_result(boolean(result)?)
+        }
+        HostCall::GetCursorPosition { transport } => {
+            transport.into_parts().1.accept_result(coordinates(result)?)
+        }
+        HostCall::GetWindowPosition { transport } => {
+            transport.into_parts().1.accept_result(coordinates(result)?)
+        }
+        HostCall::GetBufferSize { transport } => {
+            transport.into_parts().1.accept_result(size(result)?)
+        }
+        HostCall::GetWindowSize { transport } => {
+            transport.into_parts().1.accept_result(size(result)?)
+        }
+        HostCall::GetMaxWindowSize { transport } => {
+            transport.into_parts().1.accept_result(size(result)?)
+        }
+        HostCall::GetMaxPhysicalWindowSize { transport } => {
+            transport.into_parts().1.accept_result(size(result)?)
+        }
+        other => {
+            return Err(format!(
+                "{} is not supported by the .NET host",
+                other.method_name()
+            ));
+        }
+    })
+}
+
+fn exception(call_id: i64, method: RemoteHostMethodId, message: String) -> Submission {
+    Submission::Send(PipelineHostResponse::exception(call_id, method, message))
+}
+
+fn string(value: &Value) -> Result<String, String> {
+    value
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| format!("expected a string, got {value}"))
+}
+
+fn int(value: &Value) -> Result<i32, String> {
+    value
+        .as_i64()
+        .and_then(|n| i32::try_from(n).ok())
+        .ok_or_else(|| format!("expected a 32-bit integer, got {value}"))
+}
+
+fn boolean(value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("expected a boolean, got {value}"))
+}
+
+fn field(value: &Value, name: &str) -> Result<i32, String> {
+    int(value.get(name).unwrap_or(&Value::Null)).map_err(|e| format!("{name}: {e}"))
+}
+
+fn coordinates(value: &Value) -> Result<Coordinates, String> {
+    Ok(Coordinates {
+        x: field(value, "x")?,
+        y: field(value, "y")?,
+    })
+}
+
+fn size(value: &Value) -> Result<Size, String> {
+    Ok(Size {
+        width: field(value, "width")?,
+        height: field(value, "height")?,
+    })
+}
+
+fn credential(value: &Value) -> Result<PSCredential, String> {
+    let text = |name: &str| string(value.get(name).unwrap_or(&Value::Null));
+    Ok(PSCredential {
+        user_name: text("userName")?,
+        password: secure_string(&text("password")?),
+    })
+}
+
+/// SecureString payloads are UTF-16LE.
+fn secure_string(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// A JSON answer to a Prompt field: strings, booleans and integers map to
+/// their PowerShell primitives, `{"secureString": "..."}` to a SecureString.
+fn ps_value(value: &Value) -> Result<PsValue, String> {
+    let primitive = match value {
+        Value::Null => PsPrimitiveValue::Nil,
+        Value::Bool(b) => PsPrimitiveValue::Bool(*b),
+        Value::String(s) => PsPrimitiveValue::Str(s.clone()),
+        Value::Number(n) => n.as_i64().map_or_else(
+            || PsPrimitiveValue::Str(n.to_string()),
+            |n| i32::try_from(n).map_or(PsPrimitiveValue::I64(n), PsPrimitiveValue::I32),
+        ),
+        Value::Object(object) => match object.get("secureString").and_then(Value::as_str) {
+            Some(text) => PsPrimitiveValue::SecureString(secure_string(text)),
+            None => return Err(format!("unsupported prompt answer {value}")),
+        },
+        Value::Array(_) => return Err(format!("unsupported prompt answer {value}")),
+    };
+    Ok(PsValue::Primitive(primitive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_answers_map_to_primitives() {
+        assert_eq!(
+            ps_value(&json!("text")),
+            Ok(PsValue::Primitive(PsPrimitiveValue::Str("text".to_owned())))
+        );
+        assert_eq!(
+            ps_value(&json!(7)),
+            Ok(PsValue::Primitive(PsPrimitiveValue::I32(7)))
+        );
+        assert_eq!(
+            ps_value(&json!(5_000_000_000_i64)),
+            Ok(PsValue::Primitive(PsPrimitiveValue::I64(5_000_000_000)))
+        );
+        assert_eq!(
+            ps_value(&json!({ "secureString": "pw" })),
+            Ok(PsValue::Primitive(PsPrimitiveValue::SecureString(vec![
+                b'p', 0, b'w', 0
+            ])))
+        );
+        assert!(ps_value(&json!([1])).is_err());
+    }
+
+    #[test]
+    fn results_are_checked_against_the_return_type() {
+        assert_eq!(int(&json!(2)), Ok(2));
+        assert!(int(&json!("2")).is_err());
+        assert_eq!(
+            size(&json!({ "width": 120, "height": 30 })).map(|s| (s.width, s.height)),
+            Ok((120, 30))
+        );
+        assert!(coordinates(&json!({ "x": 1 })).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! C ABI over the async client, consumed by the `IronPosh` .NET package in
+//! `dotnet/`.
+//!
+//! Conventions shared by every exported function:
+//!
+//! - Strings in both directions are NUL-terminated UTF-8. Strings returned
+//!   through an `out_*` pointer are owned by the caller and released with
+//!   [`ironposh_string_free`].
+//! - Structured data (configuration, pipeline events, host calls and their
+//!   answers) is JSON.
+//! - The return value is an [`IronPoshStatus`]; on `Error` the message is in
+//!   `out_error`.
+//! - No panic unwinds into .NET: a panic becomes `Error`, with the panic
+//!   message in `out_error`. The `_free` functions log it instead.
+//! - Sessions and pipelines are opaque pointers released with their `_free`
+//!   function. A pipeline must be freed before its session.
+
+mod config;
+mod host;
+mod pipeline;
+mod session;
+
+use std::any::Any;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+pub use config::{DotnetAuth, DotnetConfig};
+pub use host::HostCallback;
+pub use pipeline::Pipeline;
+pub use session::Session;
+
+/// Outcome of an exported function.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronPoshStatus {
+    Ok = 0,
+    /// The call failed; `out_error` holds the message.
+    Error = 1,
+    /// `ironposh_pipeline_next` waited `timeout_ms` without an event.
+    Timeout = 2,
+    /// The pipeline has finished and has no more events.
+    Finished = 3,
+    /// A required pointer was null or a string was not UTF-8.
+    InvalidArgument = 4,
+}
+
+/// Release a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `value` must be null or a string returned by this library that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        log_panic("ironposh_string_free", || {
+            drop(unsafe { CString::from_raw(value) });
+        });
+    }
+}
+
+/// Borrow a caller string; `None` when null or not UTF-8.
+///
+/// # Safety
+///
+/// `value` must be null or point to a NUL-terminated string that outlives
+/// `'a`.
+unsafe fn read_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(value) }.to_str().ok()
+}
+
+/// Hand `value` to the caller. An interior NUL, which C strings cannot
+/// carry, is replaced.
+fn into_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .unwrap_or_else(|e| {
+            let lossy = String::from_utf8_lossy(e.as_bytes()).replace('\0', "\u{fffd}");
+            CString::new(lossy).expect("NULs were replaced")
+        })
+        .into_raw()
+}
+
+/// Store `value` in an out pointer, when the caller passed one.
+///
+/// # Safety
+///
+/// `out` must be null or valid for a write.
+unsafe fn write_out(out: *mut *mut c_char, value: String) {
+    if !out.is_null() {
+        unsafe { out.write(into_c_string(value)) };
+    }
+}
+
+/// Run the body of an exported function. Unwinding across `extern "C"` is
+/// undefined behavior, so a panic is reported through `out_error` as an
+/// [`IronPoshStatus::Error`].
+///
+/// # Safety
+///
+/// `out_error` must be null or valid for a write.
+unsafe fn catch_panic(
+    out_error: *mut *mut c_char,
+    body: impl FnOnce() -> IronPoshStatus,
+) -> IronPoshStatus {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|panic| {
+        let error = anyhow::anyhow!("panic in the native client: {}", panic_message(&*panic));
+        unsafe { fail(out_error, &error) }
+    })
+}
+
+/// Like [`catch_panic`] for the `_free` functions, which have nowhere to
+/// report it.
+fn log_panic(function: &str, body: impl FnOnce()) {
+    if let Err(panic) = catch_unwind(AssertUnwindSafe(body)) {
+        tracing::error!(
+            function,
+            panic = panic_message(&*panic),
+            "panic in the native client"
+        );
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Report `error` through `out_error` and return [`IronPoshStatus::Error`].
+///
+/// # Safety
+///
+/// `out_error` must be null or valid for a write.
+unsafe fn fail(out_error: *mut *mut c_char, error: &anyhow::Error) -> IronPoshStatus {
+    unsafe { write_out(out_error, format!("{error:#}")) };
+    IronPoshStatus::Error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_panic_becomes_an_error_status() {
+        let mut error = std::ptr::null_mut();
+        let status = unsafe { catch_panic(&raw mut error, || panic!("lock poisoned")) };
+        assert_eq!(status, IronPoshStatus::Error);
+        let message = unsafe { CString::from_raw(error) };
+        assert_eq!(
+            message.to_str(),
+            Ok("panic in the native client: lock poisoned")
+        );
+    }
+}
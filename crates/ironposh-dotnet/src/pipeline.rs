@@ -0,0 +1,231 @@
+//! `ironposh_pipeline_*`: one running script, whose events .NET pulls one at
+//! a time.
+
+use std::ffi::c_char;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::channel::mpsc::Receiver;
+use ironposh::client::RemoteAsyncPowershellClient;
+use ironposh::pipeline::{PipelineHandle, PsrpRecord, UserEvent};
+use ironposh::value::InformationMessageData;
+use serde_json::{Value, json};
+use tracing::warn;
+
+use crate::session::Session;
+use crate::{IronPoshStatus, catch_panic, fail, log_panic, read_str, write_out};
+
+/// A running pipeline. Opaque to .NET.
+pub struct Pipeline {
+    runtime: tokio::runtime::Handle,
+    client: RemoteAsyncPowershellClient,
+    events: Receiver<UserEvent>,
+    /// Known once the server has created the pipeline.
+    pipeline_id: Option<uuid::Uuid>,
+    finished: bool,
+}
+
+impl Pipeline {
+    /// The next event for .NET, skipping the ones it has no use for. `None`
+    /// once the pipeline has finished.
+    async fn next_event(&mut self) -> Option<Value> {
+        while !self.finished {
+            let Some(event) = self.events.next().await else {
+                self.finished = true;
+                break;
+            };
+            match event {
+                UserEvent::PipelineCreated { pipeline } => self.pipeline_id = Some(pipeline.id()),
+                UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => {
+                    self.finished = true;
+                }
+                UserEvent::PipelineOutput {
+                    output,
+                    pipeline,
+                    data_len,
+                } => {
+                    // .NET takes it now, so the server may send more.
+                    if let Err(e) = self.client.acknowledge_output(pipeline, data_len).await {
+                        warn!(error = %e, "failed to acknowledge pipeline output");
+                    }
+                    return Some(json!({
+                        "type": "output",
+                        "text": output.data.to_string(),
+                        "value": output.data,
+                    }));
+                }
+                UserEvent::ErrorRecord { error_record, .. } => {
+                    return Some(json!({
+                        "type": "error",
+                        "message": error_record.render_concise(),
+                    }));
+                }
+                UserEvent::PipelineRecord { record, .. } => return Some(record_event(record)),
+            }
+        }
+        None
+    }
+}
+
+fn record_event(record: PsrpRecord) -> Value {
+    match record {
+        PsrpRecord::Debug { message, .. } => json!({ "type": "debug", "message": message }),
+        PsrpRecord::Verbose { message, .. } => json!({ "type": "verbose", "message": message }),
+        PsrpRecord::Warning { message, .. } => json!({ "type": "warning", "message": message }),
+        PsrpRecord::Information { record, .. } => {
+            let message = match record.message_data {
+                InformationMessageData::String(s) => s,
+                InformationMessageData::HostInformationMessage(m) => m.message,
+                InformationMessageData::Object(v) => v.to_string(),
+            };
+            json!({ "type": "information", "message": message })
+        }
+        PsrpRecord::Progress { record, .. } => json!({
+            "type": "progress",
+            "activity": record.activity,
+            "statusDescription": record.status_description,
+            "percentComplete": record.percent_complete,
+        }),
+        PsrpRecord::Unsupported { data_preview, .. } => {
+            json!({ "type": "unsupported", "message": data_preview })
+        }
+    }
+}
+
+/// Start `script` without `Out-String` formatting; output events carry the
+/// objects as JSON.
+///
+/// # Safety
+///
+/// `session` must be a live session, `script` a valid string,
+/// `out_pipeline` valid for a write and `out_error` null or valid for a
+/// write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_pipeline_start(
+    session: *const Session,
+    script: *const c_char,
+    out_pipeline: *mut *mut Pipeline,
+    out_error: *mut *mut c_char,
+) -> IronPoshStatus {
+    unsafe {
+        catch_panic(out_error, || {
+            let (Some(session), Some(script)) =
+                (unsafe { session.as_ref() }, unsafe { read_str(script) })
+            else {
+                return IronPoshStatus::InvalidArgument;
+            };
+            if out_pipeline.is_null() {
+                return IronPoshStatus::InvalidArgument;
+            }
+            let mut client = session.client.clone();
+            let events = match session
+                .runtime
+                .block_on(client.send_script_raw(script.to_owned()))
+            {
+                Ok(events) => events,
+                Err(e) => return unsafe { fail(out_error, &e) },
+            };
+            let pipeline = Pipeline {
+                runtime: session.runtime.handle().clone(),
+                client,
+                events,
+                pipeline_id: None,
+                finished: false,
+            };
+            unsafe { out_pipeline.write(Box::into_raw(Box::new(pipeline))) };
+            IronPoshStatus::Ok
+        })
+    }
+}
+
+/// Wait for the next event, as JSON with a `type` of `output`, `error`,
+/// `debug`, `verbose`, `warning`, `information`, `progress` or
+/// `unsupported`. A negative `timeout_ms` waits indefinitely. Returns
+/// `Finished` once the pipeline is done and `Timeout` when nothing arrived in
+/// time, and `Error` with the message in `out_error` if the client panicked.
+///
+/// # Safety
+///
+/// `pipeline` must be a live pipeline used by one thread at a time,
+/// `out_event` valid for a write and `out_error` null or valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_pipeline_next(
+    pipeline: *mut Pipeline,
+    timeout_ms: i64,
+    out_event: *mut *mut c_char,
+    out_error: *mut *mut c_char,
+) -> IronPoshStatus {
+    unsafe {
+        catch_panic(out_error, || {
+            let Some(pipeline) = (unsafe { pipeline.as_mut() }) else {
+                return IronPoshStatus::InvalidArgument;
+            };
+            if out_event.is_null() {
+                return IronPoshStatus::InvalidArgument;
+            }
+            let runtime = pipeline.runtime.clone();
+            let event = if timeout_ms < 0 {
+                runtime.block_on(pipeline.next_event())
+            } else {
+                let timeout = Duration::from_millis(timeout_ms.unsigned_abs());
+                match runtime
+                    .block_on(async { tokio::time::timeout(timeout, pipeline.next_event()).await })
+                {
+                    Ok(event) => event,
+                    Err(_) => return IronPoshStatus::Timeout,
+                }
+            };
+            let Some(event) = event else {
+                return IronPoshStatus::Finished;
+            };
+            unsafe { write_out(out_event, event.to_string()) };
+            IronPoshStatus::Ok
+        })
+    }
+}
+
+/// Stop the pipeline. Its remaining events still arrive, ending with
+/// `Finished`.
+///
+/// # Safety
+///
+/// `pipeline` must be a live pipeline and `out_error` null or valid for a
+/// write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_pipeline_stop(
+    pipeline: *mut Pipeline,
+    out_error: *mut *mut c_char,
+) -> IronPoshStatus {
+    unsafe {
+        catch_panic(out_error, || {
+            let Some(pipeline) = (unsafe { pipeline.as_mut() }) else {
+                return IronPoshStatus::InvalidArgument;
+            };
+            let Some(pipeline_id) = pipeline.pipeline_id else {
+                // Not created on the server yet.
+                return IronPoshStatus::Ok;
+            };
+            let runtime = pipeline.runtime.clone();
+            let handle = PipelineHandle::new(pipeline_id);
+            if let Err(e) = runtime.block_on(pipeline.client.kill_pipeline(handle)) {
+                return unsafe { fail(out_error, &e) };
+            }
+            IronPoshStatus::Ok
+        })
+    }
+}
+
+/// Release a pipeline. Null is ignored.
+///
+/// # Safety
+///
+/// `pipeline` must be null or a pipeline from `ironposh_pipeline_start` that
+/// has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_pipeline_free(pipeline: *mut Pipeline) {
+    log_panic("ironposh_pipeline_free", || {
+        if !pipeline.is_null() {
+            drop(unsafe { Box::from_raw(pipeline) });
+        }
+    });
+}
@@ -0,0 +1,201 @@
+//! `ironposh_session_*`: a connected runspace pool with its own tokio runtime.
+
+use std::ffi::{c_char, c_void};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use futures::StreamExt;
+use ironposh::client::{
+    OpenedSession, RemoteAsyncPowershellClient, ReqwestHttpClient, SessionEvent, SessionHooks,
+};
+use tracing::{debug, info, warn};
+
+use crate::config::DotnetConfig;
+use crate::host::{HostBridge, HostCallback};
+use crate::{IronPoshStatus, catch_panic, fail, log_panic, read_str};
+
+/// How long freeing a session waits for the server to delete the shell.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connected session. Opaque to .NET.
+pub struct Session {
+    pub(crate) runtime: tokio::runtime::Runtime,
+    pub(crate) client: RemoteAsyncPowershellClient,
+    host: Option<Arc<HostBridge>>,
+    connection: tokio::task::JoinHandle<anyhow::Result<()>>,
+}
+
+impl Session {
+    fn connect(
+        config: DotnetConfig,
+        callback: Option<HostCallback>,
+        user_data: *mut c_void,
+    ) -> anyhow::Result<Self> {
+        let config = config.into_winrm_config(callback.is_some())?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("ironposh")
+            .build()
+            .context("Failed to start the tokio runtime")?;
+        let http_client = ReqwestHttpClient::with_tls_options(config.tls.clone());
+
+        let OpenedSession {
+            client,
+            host_io,
+            mut session_events,
+            lifecycle_events,
+            connection_task,
+        } = RemoteAsyncPowershellClient::open_task(
+            config,
+            None,
+            http_client,
+            SessionHooks::default(),
+        );
+        let connection = runtime.spawn(connection_task);
+        drop(lifecycle_events);
+
+        let (host_calls, submitter) = host_io.into_parts();
+        let host = callback.map(|callback| {
+            let bridge = Arc::new(HostBridge::new(callback, user_data, submitter));
+            runtime.spawn(Arc::clone(&bridge).run(host_calls));
+            bridge
+        });
+
+        runtime.block_on(async {
+            while let Some(event) = session_events.next().await {
+                match event {
+                    SessionEvent::ActiveSessionStarted => return Ok(()),
+                    SessionEvent::Error(e) => anyhow::bail!("Session error: {e}"),
+                    other => debug!(event = ?other, "session event"),
+                }
+            }
+            anyhow::bail!("Session ended before it was established")
+        })?;
+        runtime.spawn(async move {
+            while let Some(event) = session_events.next().await {
+                debug!(event = ?event, "session event");
+            }
+        });
+        info!("session established");
+
+        Ok(Self {
+            runtime,
+            client,
+            host,
+            connection,
+        })
+    }
+}
+
+/// Connect with the JSON `ConnectionOptions` in `config_json`. With a
+/// `host_callback`, host calls are forwarded to it (see `HostCallback`);
+/// without one the server is offered no host.
+///
+/// # Safety
+///
+/// `config_json` must be a valid string, `out_session` valid for a write and
+/// `out_error` null or valid for a write. `host_callback` must stay callable,
+/// from any thread, with `user_data` until the session is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_session_connect(
+    config_json: *const c_char,
+    host_callback: Option<HostCallback>,
+    user_data: *mut c_void,
+    out_session: *mut *mut Session,
+    out_error: *mut *mut c_char,
+) -> IronPoshStatus {
+    unsafe {
+        catch_panic(out_error, || {
+            let Some(config_json) = (unsafe { read_str(config_json) }) else {
+                return IronPoshStatus::InvalidArgument;
+            };
+            if out_session.is_null() {
+                return IronPoshStatus::InvalidArgument;
+            }
+            let session = serde_json::from_str::<DotnetConfig>(config_json)
+                .context("Invalid connection options")
+                .and_then(|config| Session::connect(config, host_callback, user_data));
+            let session = match session {
+                Ok(session) => session,
+                Err(e) => return unsafe { fail(out_error, &e) },
+            };
+            unsafe { out_session.write(Box::into_raw(Box::new(session))) };
+            IronPoshStatus::Ok
+        })
+    }
+}
+
+/// Answer the host call forwarded under `token` with `reply_json`:
+/// `{"result": ...}` or `{"error": "message"}`.
+///
+/// # Safety
+///
+/// `session` must be a live session, `reply_json` a valid string and
+/// `out_error` null or valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_host_respond(
+    session: *const Session,
+    token: u64,
+    reply_json: *const c_char,
+    out_error: *mut *mut c_char,
+) -> IronPoshStatus {
+    unsafe {
+        catch_panic(out_error, || {
+            let (Some(session), Some(reply)) =
+                (unsafe { session.as_ref() }, unsafe { read_str(reply_json) })
+            else {
+                return IronPoshStatus::InvalidArgument;
+            };
+            let Some(host) = &session.host else {
+                return unsafe { fail(out_error, &anyhow::anyhow!("the session has no host")) };
+            };
+            if let Err(e) = host.respond(token, reply) {
+                return unsafe { fail(out_error, &e) };
+            }
+            IronPoshStatus::Ok
+        })
+    }
+}
+
+/// Close the session and release it. Null is ignored.
+///
+/// Blocks until the server has deleted the shell, for at most five seconds.
+///
+/// # Safety
+///
+/// `session` must be null or a session from `ironposh_session_connect` that
+/// has not been freed, with all of its pipelines freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironposh_session_free(session: *mut Session) {
+    log_panic("ironposh_session_free", || {
+        if session.is_null() {
+            return;
+        }
+        let Session {
+            runtime,
+            mut client,
+            mut connection,
+            ..
+        } = *unsafe { Box::from_raw(session) };
+        let closed = runtime.block_on(async {
+            tokio::time::timeout(CLOSE_TIMEOUT, async {
+                client.close().await?;
+                // The connection task ends once the server answered the Delete.
+                (&mut connection)
+                    .await
+                    .context("The connection task panicked")?
+            })
+            .await
+        });
+        match closed {
+            Ok(Ok(())) => info!("session closed"),
+            Ok(Err(e)) => warn!(error = %e, "closing the session failed"),
+            Err(_) => {
+                warn!(timeout = ?CLOSE_TIMEOUT, "shell not deleted before the session was freed")
+            }
+        }
+        connection.abort();
+        runtime.shutdown_background();
+    });
+}
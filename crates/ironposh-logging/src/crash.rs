@@ -0,0 +1,136 @@
+//! The panic hook: cleans up after the client, then writes a crash report
+//! with the panic, its backtrace and the last log records next to the log
+//! file.
+
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Redactions;
+use crate::recent;
+
+/// Where crash reports go and what to scrub from them; set by [`crate::init`].
+pub struct CrashSettings {
+    pub log_file: PathBuf,
+    pub redactions: Option<Redactions>,
+}
+
+pub static SETTINGS: OnceLock<CrashSettings> = OnceLock::new();
+
+/// Held for the whole hook, so a second panic waits for the first one's
+/// cleanup instead of racing it.
+static CRASHING: Mutex<()> = Mutex::new(());
+
+/// Replace the panic hook with one that cleans up and reports the crash.
+///
+/// On a panic, `cleanup` runs first (restore the terminal, close the
+/// session), then the panic is logged, the crash report written, the
+/// previous hook run, and the process exits: with the terminal and the
+/// session torn down there is nothing left to carry on with.
+pub fn install_panic_hook(cleanup: impl Fn() + Send + Sync + 'static) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _crashing = CRASHING.lock().unwrap_or_else(PoisonError::into_inner);
+        cleanup();
+        tracing::error!(panic = %info, "panicked");
+        let written = write_report(info);
+        previous(info);
+        match written {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write a crash report: {e}"),
+        }
+        std::process::exit(101);
+    }));
+}
+
+fn write_report(info: &PanicHookInfo<'_>) -> std::io::Result<PathBuf> {
+    let thread = std::thread::current();
+    let message = info
+        .payload_as_str()
+        .unwrap_or("(non-string panic payload)");
+    let location = info
+        .location()
+        .map_or_else(|| "unknown".to_owned(), ToString::to_string);
+    let report = render_report(
+        thread.name().unwrap_or("unnamed"),
+        message,
+        &location,
+        &Backtrace::force_capture().to_string(),
+        &recent::snapshot(),
+    );
+
+    let (log_file, report) = match SETTINGS.get() {
+        Some(settings) => (
+            settings.log_file.as_path(),
+            match &settings.redactions {
+                Some(redactions) => redactions.apply(&report).into_owned(),
+                None => report,
+            },
+        ),
+        None => (Path::new("ironposh.log"), report),
+    };
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = report_path(log_file, secs);
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// `<dir>/<log file stem>-crash-<unix time>.log`
+fn report_path(log_file: &Path, secs: u64) -> PathBuf {
+    let stem = log_file
+        .file_stem()
+        .map_or_else(|| "ironposh".into(), |s| s.to_string_lossy());
+    log_file.with_file_name(format!("{stem}-crash-{secs}.log"))
+}
+
+fn render_report(
+    thread: &str,
+    message: &str,
+    location: &str,
+    backtrace: &str,
+    records: &[String],
+) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "ironposh crash report");
+    let _ = writeln!(
+        report,
+        "thread '{thread}' panicked at {location}:\n{message}\n"
+    );
+    let _ = writeln!(report, "backtrace:\n{backtrace}");
+    let _ = writeln!(report, "last {} log records:", records.len());
+    for record in records {
+        let _ = writeln!(report, "{record}");
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_has_the_panic_and_the_last_records_next_to_the_log_file() {
+        let records = ["connecting".to_owned(), "pipeline created".to_owned()];
+        let report = render_report(
+            "main",
+            "index out of bounds",
+            "src/repl.rs:10:5",
+            "<backtrace>",
+            &records,
+        );
+
+        assert!(
+            report.contains("thread 'main' panicked at src/repl.rs:10:5:\nindex out of bounds")
+        );
+        assert!(report.ends_with("last 2 log records:\nconnecting\npipeline created\n"));
+        assert_eq!(
+            report_path(Path::new("logs/winrm_client.log"), 42),
+            Path::new("logs/winrm_client-crash-42.log")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Logging for the command-line clients: where records go (`--log-sink`),
+//! how big the log file may grow (`--log-max-size`, `--log-max-files`),
+//! per-module levels (`--log-filter`) and scrubbing of host and user names
+//! (`--log-redact`) for logs attached to support tickets. A panic hook
+//! ([`install_panic_hook`]) writes a crash report with the last records.
+
+mod crash;
+mod recent;
+mod redact;
+mod rotate;
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{EnvFilter, Layer, fmt, prelude::*, registry::Registry};
+
+pub use crash::install_panic_hook;
+pub use redact::Redactions;
+pub use rotate::RotatingFile;
+
+/// Where log records are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogSink {
+    /// Human-readable lines on stderr.
+    Console,
+    /// Human-readable lines in the log file.
+    File,
+    /// One JSON object per record in the log file.
+    Json,
+}
+
+const DEFAULT_MAX_SIZE_MIB: u64 = 10;
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// The logging flags shared by the clients; `#[command(flatten)]` them.
+#[derive(Debug, Clone, clap::Args)]
+pub struct LogArgs {
+    /// Where log records go.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "file",
+        help = "Log sink: console (stderr), file, or json (JSON lines in the log file)"
+    )]
+    pub log_sink: LogSink,
+
+    /// Log file for the `file` and `json` sinks.
+    #[arg(long, value_name = "PATH", help = "Log file (file and json sinks)")]
+    pub log_file: Option<PathBuf>,
+
+    /// Rotate the log file once it would grow past this many MiB.
+    #[arg(
+        long,
+        value_name = "MIB",
+        default_value_t = DEFAULT_MAX_SIZE_MIB,
+        help = "Rotate the log file at this size in MiB (0: never)"
+    )]
+    pub log_max_size: u64,
+
+    /// Rotated files kept next to the log file (`<file>.1` is the newest).
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = DEFAULT_MAX_FILES,
+        help = "Number of rotated log files to keep"
+    )]
+    pub log_max_files: usize,
+
+    /// Level overrides applied on top of `-v`/`RUST_LOG`, e.g.
+    /// `ironposh_async=trace,sspi=debug`.
+    #[arg(
+        long,
+        value_name = "FILTER",
+        help = "Per-module log levels, e.g. ironposh_async=trace,sspi=debug"
+    )]
+    pub log_filter: Option<String>,
+
+    /// Replace the server name and user names in log records with
+    /// placeholders.
+    #[arg(
+        long,
+        help = "Scrub hostnames and usernames from the log (for support tickets)"
+    )]
+    pub log_redact: bool,
+}
+
+impl Default for LogArgs {
+    /// The values of a command line without logging flags.
+    fn default() -> Self {
+        Self {
+            log_sink: LogSink::File,
+            log_file: None,
+            log_max_size: DEFAULT_MAX_SIZE_MIB,
+            log_max_files: DEFAULT_MAX_FILES,
+            log_filter: None,
+            log_redact: false,
+        }
+    }
+}
+
+/// What the client knows about its own logging.
+pub struct LogSettings {
+    /// `-v` count.
+    pub verbose: u8,
+    /// Log file when `--log-file` is not given.
+    pub default_file: PathBuf,
+    /// Scrubbed with `--log-redact`.
+    pub redactions: Redactions,
+}
+
+/// Base filter for a `-v` count.
+fn verbosity_filter(verbose: u8) -> &'static str {
+    match verbose {
+        0 => "info,ureq=error,sspi=error",
+        1 => "debug,ureq=warn,sspi=error",
+        2 => "trace,ureq=info,sspi=error",
+        _ => "trace",
+    }
+}
+
+/// The level filter: `RUST_LOG` if set, else the verbosity, then each
+/// `--log-filter` directive on top (a directive for the same target
+/// replaces the base one).
+fn build_filter(verbose: u8, overrides: Option<&str>) -> anyhow::Result<EnvFilter> {
+    let mut filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity_filter(verbose)));
+    for directive in overrides
+        .into_iter()
+        .flat_map(|s| s.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        filter = filter.add_directive(
+            directive
+                .parse()
+                .with_context(|| format!("invalid --log-filter directive {directive:?}"))?,
+        );
+    }
+    Ok(filter)
+}
+
+/// Install the global subscriber described by `args`.
+pub fn init(args: &LogArgs, settings: LogSettings) -> anyhow::Result<()> {
+    let filter = build_filter(settings.verbose, args.log_filter.as_deref())?;
+
+    let log_file = args.log_file.clone().unwrap_or(settings.default_file);
+    let writer = if args.log_sink == LogSink::Console {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        let max_size = args.log_max_size.saturating_mul(1024 * 1024);
+        let file = RotatingFile::open(&log_file, max_size, args.log_max_files)
+            .with_context(|| format!("failed to open log file {}", log_file.display()))?;
+        BoxMakeWriter::new(file)
+    };
+    // The crash report gets the same records, scrubbed the same way.
+    let recent = BoxMakeWriter::new(recent::Recent::global());
+    let (writer, recent) = if args.log_redact {
+        (
+            BoxMakeWriter::new(settings.redactions.clone().wrap(writer)),
+            BoxMakeWriter::new(settings.redactions.clone().wrap(recent)),
+        )
+    } else {
+        (writer, recent)
+    };
+    let _ = crash::SETTINGS.set(crash::CrashSettings {
+        log_file: log_file.clone(),
+        redactions: args.log_redact.then_some(settings.redactions),
+    });
+
+    let layer = match args.log_sink {
+        LogSink::Json => fmt::layer()
+            .json()
+            .with_writer(writer)
+            .with_current_span(true)
+            .with_file(true)
+            .with_line_number(true)
+            .boxed(),
+        LogSink::Console | LogSink::File => fmt::layer()
+            .with_writer(writer)
+            .with_target(true)
+            .with_line_number(true)
+            .with_file(true)
+            .with_ansi(false)
+            .compact()
+            .boxed(),
+    };
+    let recent = fmt::layer()
+        .with_writer(recent)
+        .with_target(true)
+        .with_ansi(false)
+        .compact();
+
+    tracing::subscriber::set_global_default(
+        Registry::default().with(filter).with(layer).with(recent),
+    )?;
+    tracing::info!(
+        sink = ?args.log_sink,
+        log_file = %log_file.display(),
+        redact = args.log_redact,
+        "logging initialized"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_filter_overrides_the_verbosity_level_per_module() {
+        let filter = build_filter(0, Some("ironposh_async=trace, sspi=debug")).unwrap();
+        let rendered = filter.to_string();
+        assert!(rendered.contains("ironposh_async=trace"), "{rendered}");
+        assert!(rendered.contains("sspi=debug"), "{rendered}");
+        assert!(!rendered.contains("sspi=error"), "{rendered}");
+    }
+
+    #[test]
+    fn invalid_log_filter_is_rejected() {
+        assert!(build_filter(0, Some("ironposh_async=loud")).is_err());
+    }
+}
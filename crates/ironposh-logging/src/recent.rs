@@ -0,0 +1,108 @@
+//! The last log records, kept in memory for the crash report.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Records kept for a crash report.
+pub const RECENT_RECORDS: usize = 200;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// A ring of the last `capacity` formatted records.
+pub struct Recent {
+    records: &'static Mutex<VecDeque<String>>,
+    capacity: usize,
+}
+
+impl Recent {
+    /// The process-wide ring read by the panic hook.
+    pub(crate) const fn global() -> Self {
+        Self {
+            records: &RECENT,
+            capacity: RECENT_RECORDS,
+        }
+    }
+
+    fn push(&self, record: String) {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// The records in the global ring, oldest first. Gives up rather than wait
+/// if the ring is locked, as it may be by the thread that panicked.
+pub fn snapshot() -> Vec<String> {
+    match RECENT.try_lock() {
+        Ok(records) => records.iter().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(records)) => {
+            records.into_inner().iter().cloned().collect()
+        }
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    }
+}
+
+pub struct RecentWriter<'a> {
+    ring: &'a Recent,
+    record: Vec<u8>,
+}
+
+impl Write for RecentWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.record.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The formatter writes a record and drops the writer, so the record is
+/// complete here.
+impl Drop for RecentWriter<'_> {
+    fn drop(&mut self) {
+        if self.record.is_empty() {
+            return;
+        }
+        let record = String::from_utf8_lossy(&self.record);
+        self.ring.push(record.trim_end().to_owned());
+    }
+}
+
+impl<'a> MakeWriter<'a> for Recent {
+    type Writer = RecentWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RecentWriter {
+            ring: self,
+            record: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_records() {
+        static RING: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+        let ring = Recent {
+            records: &RING,
+            capacity: 2,
+        };
+
+        for line in ["first\n", "second\n", "third\n"] {
+            ring.make_writer().write_all(line.as_bytes()).unwrap();
+        }
+
+        let records: Vec<String> = RING.lock().unwrap().iter().cloned().collect();
+        assert_eq!(records, ["second", "third"]);
+    }
+}
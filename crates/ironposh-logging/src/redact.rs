@@ -0,0 +1,173 @@
+//! `--log-redact`: host and user names replaced in every log record.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// Names shorter than this are not scrubbed on their own: `ad` or `dc`
+/// would mangle every other word in the log.
+const MIN_LEN: usize = 3;
+
+/// The names to scrub, each with its placeholder. Matching ignores ASCII
+/// case, as host and Windows user names do.
+#[derive(Debug, Default, Clone)]
+pub struct Redactions {
+    rules: Vec<(String, &'static str)>,
+}
+
+impl Redactions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A server name: the name itself and, for a dotted DNS name, its
+    /// first label (the short NetBIOS-style name) and the rest (the
+    /// domain). IP addresses are scrubbed whole.
+    pub fn host(mut self, host: &str) -> Self {
+        self.push(host, "<host>");
+        let is_ip = host.parse::<std::net::IpAddr>().is_ok();
+        if let Some((short, domain)) = host.split_once('.').filter(|_| !is_ip) {
+            self.push(short, "<host>");
+            self.push(domain, "<domain>");
+        }
+        self
+    }
+
+    /// A user name, in any of `user`, `DOMAIN\user` or `user@realm` form;
+    /// the domain or realm is scrubbed too.
+    pub fn user(mut self, user: &str) -> Self {
+        self.push(user, "<user>");
+        let parts = user
+            .split_once('\\')
+            .map(|(domain, name)| (name, domain))
+            .or_else(|| user.split_once('@'));
+        if let Some((name, domain)) = parts {
+            self.push(name, "<user>");
+            self = self.domain(domain);
+        }
+        self
+    }
+
+    /// A domain or Kerberos realm.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.push(domain, "<domain>");
+        self
+    }
+
+    fn push(&mut self, name: &str, placeholder: &'static str) {
+        let name = name.trim();
+        if name.len() < MIN_LEN
+            || self
+                .rules
+                .iter()
+                .any(|(known, _)| known.eq_ignore_ascii_case(name))
+        {
+            return;
+        }
+        self.rules.push((name.to_ascii_lowercase(), placeholder));
+        // Longest first, so `host.example.com` wins over `host`.
+        self.rules
+            .sort_by_key(|(known, _)| std::cmp::Reverse(known.len()));
+    }
+
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (name, placeholder) in &self.rules {
+            // ASCII lowercasing keeps byte offsets, so matches found in the
+            // lowered copy index the original.
+            let lowered = text.to_ascii_lowercase();
+            if !lowered.contains(name.as_str()) {
+                continue;
+            }
+            let mut scrubbed = String::with_capacity(text.len());
+            let mut last = 0;
+            for (at, _) in lowered.match_indices(name.as_str()) {
+                scrubbed.push_str(&text[last..at]);
+                scrubbed.push_str(placeholder);
+                last = at + name.len();
+            }
+            scrubbed.push_str(&text[last..]);
+            text = Cow::Owned(scrubbed);
+        }
+        text
+    }
+
+    /// `writer` with every record scrubbed before it is written.
+    pub(crate) fn wrap(self, writer: BoxMakeWriter) -> Redacting {
+        Redacting {
+            redactions: self,
+            inner: writer,
+        }
+    }
+}
+
+pub struct Redacting {
+    redactions: Redactions,
+    inner: BoxMakeWriter,
+}
+
+impl<'a> MakeWriter<'a> for Redacting {
+    type Writer = RedactingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            redactions: &self.redactions,
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/// Scrubs each write. The formatter writes a whole record at once, so a
+/// name is never split across two writes.
+pub struct RedactingWriter<'a> {
+    redactions: &'a Redactions,
+    inner: Box<dyn Write + 'a>,
+}
+
+impl Write for RedactingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner
+            .write_all(self.redactions.apply(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_host_and_user_names_in_any_case() {
+        let redactions = Redactions::new()
+            .host("IT-HELP-DC.ad.it-help.ninja")
+            .user("Administrator@ad.it-help.ninja");
+
+        let line =
+            "connecting to it-help-dc.AD.it-help.ninja:5985 as administrator@AD.IT-HELP.NINJA";
+        assert_eq!(
+            redactions.apply(line),
+            "connecting to <host>:5985 as <user>"
+        );
+        assert_eq!(
+            redactions.apply("SPN HTTP/IT-HELP-DC for AD.IT-HELP.NINJA\\Administrator"),
+            "SPN HTTP/<host> for <domain>\\<user>"
+        );
+    }
+
+    #[test]
+    fn leaves_short_names_and_unrelated_text_alone() {
+        let redactions = Redactions::new().user("ad\\bob").host("10.0.0.5");
+        assert_eq!(redactions.apply("address 10.0.0.5"), "address <host>");
+        assert!(matches!(
+            redactions.apply("loading ad settings"),
+            Cow::Borrowed("loading ad settings")
+        ));
+    }
+}
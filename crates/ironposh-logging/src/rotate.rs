@@ -0,0 +1,173 @@
+//! Size-based log rotation.
+
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// A log file rotated by size.
+///
+/// It is rotated once a write would take it past `max_size`: `<file>`
+/// becomes `<file>.1`, `<file>.1` becomes `<file>.2`, and so on up to `keep`
+/// files; the oldest is dropped. A `max_size` of 0 never rotates.
+///
+/// The file is opened for appending, so previous runs are kept until
+/// rotation reaches them.
+pub struct RotatingFile {
+    state: Mutex<State>,
+}
+
+struct State {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_size: u64, keep: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = append(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            state: Mutex::new(State {
+                path: path.to_owned(),
+                file,
+                size,
+                max_size,
+                keep,
+            }),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic mid-write leaves nothing inconsistent worth refusing to log.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// `<path>.<n>`
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+impl State {
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            ignore_missing(std::fs::remove_file(rotated(&self.path, self.keep)))?;
+            for n in (1..self.keep).rev() {
+                ignore_missing(std::fs::rename(
+                    rotated(&self.path, n),
+                    rotated(&self.path, n + 1),
+                ))?;
+            }
+            std::fs::rename(&self.path, rotated(&self.path, 1))?;
+            self.file = append(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// A write to a [`RotatingFile`]; one per log record.
+pub struct RotatingWriter<'a>(&'a RotatingFile);
+
+impl Write for RotatingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.0.lock();
+        if state.max_size > 0 && state.size > 0 && state.size + buf.len() as u64 > state.max_size {
+            state.rotate()?;
+        }
+        let written = state.file.write(buf)?;
+        state.size += written as u64;
+        drop(state);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = RotatingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RotatingWriter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ironposh-log-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn rotates_past_max_size_and_keeps_the_newest_files() {
+        let dir = scratch_dir("rotate");
+        let path = dir.join("client.log");
+        let log = RotatingFile::open(&path, 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.make_writer().write_all(line.as_bytes()).unwrap();
+        }
+
+        let read = |p: &Path| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&rotated(&path, 1)), "third\n");
+        assert_eq!(read(&rotated(&path, 2)), "second\n");
+        assert!(
+            !rotated(&path, 3).exists(),
+            "only `keep` rotated files remain"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn appends_to_an_existing_log_and_counts_its_size() {
+        let dir = scratch_dir("append");
+        let path = dir.join("client.log");
+        RotatingFile::open(&path, 0, 1)
+            .unwrap()
+            .make_writer()
+            .write_all(b"previous run\n")
+            .unwrap();
+
+        let log = RotatingFile::open(&path, 16, 1).unwrap();
+        log.make_writer().write_all(b"this run\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "this run\n");
+        assert_eq!(
+            std::fs::read_to_string(rotated(&path, 1)).unwrap(),
+            "previous run\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -66,9 +66,9 @@ struct PsFieldOpts {
     /// deserialize) — e.g. a PascalCase alias alongside the camelCase name, for
     /// .NET host objects that are read under either casing.
     also: Vec<String>,
-    /// Dictionary mode only: a `BTreeMap<String, PsValue>` whose entries are
-    /// merged directly into the parent `<DCT>` (and, on deserialize, collect all
-    /// keys not claimed by a named field).
+    /// Dictionary mode only: a `PsDict` (or another `String` → `PsValue` map)
+    /// whose entries are merged directly into the parent `<DCT>` (and, on
+    /// deserialize, collect all keys not claimed by a named field).
     flatten: bool,
     /// `value_dictionary` mode only: the integer key this field occupies in the
     /// `<DCT>`.
@@ -857,8 +857,9 @@ fn impl_ps_deserialize(input: &DeriveInput) -> syn::Result<TokenStream2> {
 ///
 /// Each variant must be unit and carry an explicit discriminant (`= N`).
 /// `#[ps(rename = "..")]` overrides the `<ToString>` name for a variant.
-/// Generates `ToPsValue`/`FromPsValue` (+ `From`/`TryFrom<ComplexObject>` for
-/// the object repr), so the enum composes inside derived structs.
+/// Generates `ToPsValue`/`FromPsValue` (+ `From<Self> for ComplexObject` and
+/// `from_ps_object` for the object repr), so the enum composes inside derived
+/// structs.
 #[proc_macro_derive(PsEnum, attributes(ps))]
 pub fn derive_ps_enum(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -1,6 +1,7 @@
 use std::io::Read;
 
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use ironposh_xml::parser::ParseLimits;
 use tracing::instrument;
 use uuid::Uuid;
 
@@ -191,9 +192,10 @@ impl PowerShellRemotingMessage {
             })?,
             message_type,
             rpid: Uuid::from_bytes(rpid_bytes),
+            // An all-zero PID marks a runspace-pool level message.
             pid: pid_bytes
                 .iter()
-                .all(|&b| b == 0)
+                .any(|&b| b != 0)
                 .then_some(Uuid::from_bytes(pid_bytes)),
             data: rest,
         })
@@ -242,9 +244,17 @@ impl PowerShellRemotingMessage {
 
     #[instrument(skip(self), level = "debug")]
     pub fn parse_ps_message(&self) -> Result<PsValue, crate::PowerShellRemotingError> {
+        self.parse_ps_message_with_limits(ParseLimits::default())
+    }
+
+    /// Like [`Self::parse_ps_message`], with explicit XML depth/fan-out limits.
+    pub fn parse_ps_message_with_limits(
+        &self,
+        limits: ParseLimits,
+    ) -> Result<PsValue, crate::PowerShellRemotingError> {
         let xml = str::from_utf8(&self.data)?;
 
-        let parsed = ironposh_xml::parser::parse(xml)?;
+        let parsed = ironposh_xml::parser::parse_with_limits(xml, limits)?;
         let root = parsed.root_element();
 
         let ps_object =
@@ -276,19 +286,24 @@ impl PowerShellFragment {
 
         let blob_length = cursor.read_u32::<byteorder::BigEndian>()?;
 
-        let current_pos = cursor.position() as usize;
         let data = cursor.get_ref();
-        if current_pos + (blob_length as usize) > data.len() {
-            return Err(crate::PowerShellRemotingError::InvalidMessage(format!(
-                "Not enough data for PowerShell fragment at position {}: expected {}, got {}",
-                current_pos,
-                blob_length,
-                data.len() - current_pos
-            )));
-        }
+        let current_pos = usize::try_from(cursor.position())
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let blob_end = current_pos
+            .checked_add(blob_length as usize)
+            .filter(|blob_end| *blob_end <= data.len())
+            .ok_or_else(|| {
+                crate::PowerShellRemotingError::InvalidMessage(format!(
+                    "Not enough data for PowerShell fragment at position {}: expected {}, got {}",
+                    current_pos,
+                    blob_length,
+                    data.len() - current_pos
+                ))
+            })?;
 
-        let blob = &cursor.get_ref()[current_pos..current_pos + blob_length as usize];
-        cursor.set_position(current_pos as u64 + blob_length as u64);
+        let blob = &data[current_pos..blob_end];
+        cursor.set_position(blob_end as u64);
 
         Ok(Self {
             object_id,
@@ -348,3 +363,32 @@ impl std::fmt::Display for PowerShellFragment {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(pid: Option<Uuid>) -> Option<Uuid> {
+        let message = PowerShellRemotingMessage {
+            destination: Destination::Client,
+            message_type: MessageType::PipelineOutput,
+            rpid: Uuid::new_v4(),
+            pid,
+            data: b"<Obj RefId=\"0\"/>".to_vec(),
+        };
+        PowerShellRemotingMessage::parse(&mut std::io::Cursor::new(message.pack()))
+            .expect("parse")
+            .pid
+    }
+
+    #[test]
+    fn all_zero_pid_parses_as_a_pool_message() {
+        assert_eq!(round_trip(None), None);
+    }
+
+    #[test]
+    fn non_zero_pid_is_kept() {
+        let pid = Uuid::new_v4();
+        assert_eq!(round_trip(Some(pid)), Some(pid));
+    }
+}
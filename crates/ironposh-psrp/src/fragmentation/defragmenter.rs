@@ -1,51 +1,92 @@
 use tracing::trace;
 
-use super::{DefragmentResult, fragment::Fragment};
-use crate::{PowerShellRemotingError, PowerShellRemotingMessage};
+use super::{
+    DefragmentResult,
+    diagnostics::{BlobDiagnostics, MessageOrigin},
+    fragment::FragmentRef,
+};
+use crate::{MessageType, PowerShellRemotingError, PowerShellRemotingMessage};
 use std::collections::HashMap;
 
 /// Buffer for accumulating fragments during defragmentation
 #[derive(Debug)]
 struct FragmentBuffer {
-    fragments: Vec<Fragment>,
+    /// Message data of the fragments accepted so far, in order.
+    data: Vec<u8>,
+    first_fragment_id: u64,
+    last_fragment_id: u64,
     is_complete: bool,
 }
 
 impl FragmentBuffer {
-    fn new() -> Self {
+    fn new(start: &FragmentRef<'_>) -> Self {
         Self {
-            fragments: Vec::new(),
-            is_complete: false,
+            data: start.data.to_vec(),
+            first_fragment_id: start.fragment_id,
+            last_fragment_id: start.fragment_id,
+            is_complete: start.end,
         }
     }
 
-    /// Add a fragment to this buffer if it's the expected next fragment
-    fn add_fragment(&mut self, fragment: Fragment) {
+    /// The fragment id a continuation fragment must carry to be accepted.
+    fn expected_fragment_id(&self) -> Option<u64> {
+        self.last_fragment_id.checked_add(1)
+    }
+
+    /// Append the expected next fragment
+    fn add_fragment(&mut self, fragment: &FragmentRef<'_>) {
         if fragment.end {
             self.is_complete = true;
         }
-        self.fragments.push(fragment);
+        self.last_fragment_id = fragment.fragment_id;
+        self.data.extend_from_slice(fragment.data);
+    }
+
+    /// Bytes of message data buffered so far.
+    fn buffered_len(&self) -> usize {
+        self.data.len()
     }
+}
+
+/// Size limits the [`Defragmenter`] enforces on incoming fragments.
+///
+/// A server could otherwise announce an endless message (start fragment, then
+/// continuations that never end) and make the client buffer it all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefragmentLimits {
+    /// Maximum blob size of a single fragment.
+    pub max_fragment_blob_size: usize,
+    /// Maximum bytes held across all partially reassembled messages.
+    pub max_buffered_bytes: usize,
+}
 
-    /// Reassemble all fragments into complete message data
-    fn reassemble(&self) -> Vec<u8> {
-        let mut frags = self.fragments.clone();
-        frags.sort_by_key(|f| f.fragment_id);
-        let total_len: usize = frags.iter().map(|f| f.data.len()).sum();
-        let mut out = Vec::with_capacity(total_len);
+impl DefragmentLimits {
+    pub const DEFAULT_MAX_FRAGMENT_BLOB_SIZE: usize = 16 * 1024 * 1024;
+    pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 128 * 1024 * 1024;
+}
 
-        for f in frags {
-            out.extend_from_slice(&f.data);
+impl Default for DefragmentLimits {
+    fn default() -> Self {
+        Self {
+            max_fragment_blob_size: Self::DEFAULT_MAX_FRAGMENT_BLOB_SIZE,
+            max_buffered_bytes: Self::DEFAULT_MAX_BUFFERED_BYTES,
         }
-        out
     }
 }
 
 /// Defragmenter handles defragmentation of incoming PowerShell remoting message fragments
 /// with internal state management
+///
+/// Fragments and messages it rejects come back as
+/// [`PowerShellRemotingError::Diagnosed`], carrying the object and fragment
+/// ids, the previous message type and a hex dump of the blob.
 #[derive(Debug, Default)]
 pub struct Defragmenter {
     buffers: HashMap<u64, FragmentBuffer>,
+    limits: DefragmentLimits,
+    previous_message_type: Option<MessageType>,
+    /// Origins of the messages in the last `Complete` result, in order.
+    completed_origins: Vec<MessageOrigin>,
 }
 
 impl Defragmenter {
@@ -54,6 +95,22 @@ impl Defragmenter {
         Self::default()
     }
 
+    /// Create a defragmenter enforcing custom size limits.
+    pub fn with_limits(limits: DefragmentLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Bytes currently held across all incomplete message buffers.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffers
+            .values()
+            .map(FragmentBuffer::buffered_len)
+            .sum()
+    }
+
     /// Process incoming packet data containing one or more fragments
     /// Returns complete messages if any are ready, or Incomplete if still waiting
     pub fn defragment(
@@ -62,10 +119,11 @@ impl Defragmenter {
     ) -> Result<DefragmentResult, PowerShellRemotingError> {
         let mut remaining_data = packet_data;
         let mut completed_messages = Vec::new();
+        self.completed_origins.clear();
 
         // Parse all fragments from the packet data
         while !remaining_data.is_empty() {
-            let (fragment, rest) = Fragment::unpack(remaining_data)?;
+            let (fragment, rest) = FragmentRef::unpack(remaining_data)?;
             trace!(
                 fragment = ?fragment,
                 "Defragmenter unpacked fragment"
@@ -79,33 +137,82 @@ impl Defragmenter {
 
             let object_id = fragment.object_id;
 
+            if fragment.data.len() > self.limits.max_fragment_blob_size {
+                self.buffers.remove(&object_id);
+                return Err(PowerShellRemotingError::ResourceLimitExceeded {
+                    resource: "fragment blob size",
+                    limit: self.limits.max_fragment_blob_size,
+                });
+            }
+
             // Handle complete single-fragment message
             if fragment.start && fragment.end {
-                let message = Self::parse_message(fragment.data)?;
+                let message = self.parse_message(
+                    object_id,
+                    fragment.fragment_id..=fragment.fragment_id,
+                    fragment.data,
+                )?;
                 completed_messages.push(message);
                 continue;
             }
 
-            // Get or create buffer for this object
-            let buffer = self
-                .buffers
-                .entry(object_id)
-                .or_insert_with(FragmentBuffer::new);
+            // A start fragment (re)opens the buffer for this object; anything
+            // else must continue a buffer we already hold. Buffering orphan or
+            // out-of-sequence fragments would let a misbehaving server grow
+            // state without bound and reassemble garbage.
+            let buffered = if fragment.start {
+                self.buffers.remove(&object_id);
+                self.buffered_bytes()
+            } else {
+                let buffered = self.buffered_bytes();
+                let Some(buffer) = self.buffers.get(&object_id) else {
+                    return Err(self.rejected_fragment(
+                        &fragment,
+                        PowerShellRemotingError::InvalidMessage(format!(
+                            "Fragment {} of object {object_id} arrived without a start fragment",
+                            fragment.fragment_id
+                        )),
+                    ));
+                };
+                let expected = buffer.expected_fragment_id();
+                if expected != Some(fragment.fragment_id) {
+                    self.buffers.remove(&object_id);
+                    return Err(self.rejected_fragment(
+                        &fragment,
+                        PowerShellRemotingError::InvalidMessage(format!(
+                            "Out-of-sequence fragment for object {object_id}: expected {expected:?}, got {}",
+                            fragment.fragment_id
+                        )),
+                    ));
+                }
+                buffered
+            };
 
-            // Handle start fragment - reset buffer
-            if fragment.start {
-                *buffer = FragmentBuffer::new();
+            if buffered.saturating_add(fragment.data.len()) > self.limits.max_buffered_bytes {
+                self.buffers.remove(&object_id);
+                return Err(PowerShellRemotingError::ResourceLimitExceeded {
+                    resource: "defragmentation buffer",
+                    limit: self.limits.max_buffered_bytes,
+                });
             }
 
-            // Add fragment to buffer
-            buffer.add_fragment(fragment);
+            if fragment.start {
+                self.buffers
+                    .insert(object_id, FragmentBuffer::new(&fragment));
+                continue;
+            }
 
-            // Check if message is complete
-            if buffer.is_complete {
-                let complete_data = buffer.reassemble();
-                let message = Self::parse_message(complete_data)?;
+            let is_complete = self.buffers.get_mut(&object_id).is_some_and(|buffer| {
+                buffer.add_fragment(&fragment);
+                buffer.is_complete
+            });
+            if is_complete && let Some(buffer) = self.buffers.remove(&object_id) {
+                let message = self.parse_message(
+                    object_id,
+                    buffer.first_fragment_id..=buffer.last_fragment_id,
+                    &buffer.data,
+                )?;
                 completed_messages.push(message);
-                self.buffers.remove(&object_id);
             }
         }
 
@@ -126,9 +233,55 @@ impl Defragmenter {
         self.buffers.clear();
     }
 
+    /// Wrap an error decoding message `index` of the last `Complete` result
+    /// (e.g. from [`PowerShellRemotingMessage::parse_ps_message`]) with where
+    /// the message came from and a dump of its bytes.
+    pub fn decode_error(
+        &self,
+        index: usize,
+        message: &PowerShellRemotingMessage,
+        error: PowerShellRemotingError,
+    ) -> PowerShellRemotingError {
+        let Some(origin) = self.completed_origins.get(index) else {
+            return error;
+        };
+        let blob = message.clone().pack();
+        error.diagnosed(BlobDiagnostics::new(origin.clone(), &blob))
+    }
+
     /// Parse a complete message from reassembled data
-    fn parse_message(data: Vec<u8>) -> Result<PowerShellRemotingMessage, PowerShellRemotingError> {
+    fn parse_message(
+        &mut self,
+        object_id: u64,
+        fragment_ids: std::ops::RangeInclusive<u64>,
+        data: &[u8],
+    ) -> Result<PowerShellRemotingMessage, PowerShellRemotingError> {
+        let origin = MessageOrigin {
+            object_id,
+            fragment_ids,
+            previous_message_type: self.previous_message_type.clone(),
+        };
         let mut cursor = std::io::Cursor::new(data);
-        PowerShellRemotingMessage::parse(&mut cursor)
+        match PowerShellRemotingMessage::parse(&mut cursor) {
+            Ok(message) => {
+                self.previous_message_type = Some(message.message_type.clone());
+                self.completed_origins.push(origin);
+                Ok(message)
+            }
+            Err(error) => Err(error.diagnosed(BlobDiagnostics::new(origin, data))),
+        }
+    }
+
+    fn rejected_fragment(
+        &self,
+        fragment: &FragmentRef<'_>,
+        error: PowerShellRemotingError,
+    ) -> PowerShellRemotingError {
+        let origin = MessageOrigin {
+            object_id: fragment.object_id,
+            fragment_ids: fragment.fragment_id..=fragment.fragment_id,
+            previous_message_type: self.previous_message_type.clone(),
+        };
+        error.diagnosed(BlobDiagnostics::new(origin, fragment.data))
     }
 }
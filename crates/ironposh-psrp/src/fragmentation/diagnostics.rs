@@ -0,0 +1,123 @@
+//! Context attached to fragments and messages that fail to reassemble or
+//! decode, so a bug report can carry the offending bytes.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::MessageType;
+
+/// Bytes of a failing blob shown in its error's hex dump.
+pub const HEXDUMP_LIMIT: usize = 256;
+
+/// Where a message came from in the fragment stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageOrigin {
+    pub object_id: u64,
+    /// Fragments of the message, first to last, or the one fragment that
+    /// was rejected.
+    pub fragment_ids: RangeInclusive<u64>,
+    /// The message completed just before this one, if any.
+    pub previous_message_type: Option<MessageType>,
+}
+
+/// What is known about a blob that failed, carried by
+/// [`crate::PowerShellRemotingError::Diagnosed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobDiagnostics {
+    pub origin: MessageOrigin,
+    /// Full length of the blob; the hex dump shows at most [`HEXDUMP_LIMIT`]
+    /// bytes of it.
+    pub len: usize,
+    pub hexdump: String,
+    /// The whole blob, for the application to save with a bug report.
+    pub blob: Vec<u8>,
+}
+
+impl BlobDiagnostics {
+    pub(crate) fn new(origin: MessageOrigin, blob: &[u8]) -> Self {
+        Self {
+            origin,
+            len: blob.len(),
+            hexdump: hexdump(blob, HEXDUMP_LIMIT),
+            blob: blob.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for BlobDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let MessageOrigin {
+            object_id,
+            fragment_ids,
+            previous_message_type,
+        } = &self.origin;
+        write!(f, "object {object_id}, fragment ")?;
+        if fragment_ids.start() == fragment_ids.end() {
+            write!(f, "{}", fragment_ids.start())?;
+        } else {
+            write!(f, "{}-{}", fragment_ids.start(), fragment_ids.end())?;
+        }
+        match previous_message_type {
+            Some(message_type) => write!(f, ", after {message_type:?}")?,
+            None => write!(f, ", first message")?,
+        }
+        write!(f, ", {} bytes", self.len)?;
+        write!(f, "\n{}", self.hexdump)
+    }
+}
+
+/// `offset  hex bytes  |ascii|`, 16 bytes a line, for the first `limit`
+/// bytes of `data`.
+pub fn hexdump(data: &[u8], limit: usize) -> String {
+    use fmt::Write as _;
+
+    let shown = &data[..data.len().min(limit)];
+    let mut out = String::new();
+    for (line, chunk) in shown.chunks(16).enumerate() {
+        let _ = write!(out, "{:08x} ", line * 16);
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(out, " {byte:02x}");
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("  |");
+        out.extend(chunk.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    if data.len() > shown.len() {
+        let _ = writeln!(out, "... {} more bytes", data.len() - shown.len());
+    }
+    out.pop();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hexdump_pads_the_last_line_and_counts_what_it_cut() {
+        let dump = hexdump(b"<Obj RefId=\"0\">\x00\x01xyz", 16);
+        assert_eq!(
+            dump,
+            "00000000  3c 4f 62 6a 20 52 65 66 49 64 3d 22 30 22 3e 00  |<Obj RefId=\"0\">.|\n\
+             ... 4 more bytes"
+        );
+
+        let dump = hexdump(b"ab", HEXDUMP_LIMIT);
+        assert_eq!(
+            dump,
+            "00000000  61 62                                            |ab|"
+        );
+        assert_eq!(hexdump(b"", HEXDUMP_LIMIT), "");
+    }
+}
@@ -5,6 +5,9 @@ use base64::Engine;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use tracing::trace;
 
+/// Size of the fixed fragment header: ObjectId (8) + FragmentId (8) + flags (1) + BlobLength (4).
+const HEADER_LEN: usize = 21;
+
 /// Fragment represents a single fragment of a PowerShell remoting message
 #[derive(Debug, Clone)]
 pub struct Fragment {
@@ -72,10 +75,32 @@ impl Fragment {
 
     /// Unpack a fragment from wire format bytes
     pub fn unpack(data: &[u8]) -> Result<(Self, &[u8]), PowerShellRemotingError> {
-        if data.len() < 21 {
-            return Err(PowerShellRemotingError::InvalidMessage(
-                "Fragment too short, need at least 21 bytes".to_string(),
-            ));
+        let (fragment, remaining) = FragmentRef::unpack(data)?;
+        Ok((fragment.into_fragment(), remaining))
+    }
+}
+
+/// A fragment whose data borrows from the packet it was unpacked from.
+///
+/// The defragmenter works on these so a single-fragment message is parsed
+/// straight out of the decoded stream, and a multi-fragment one is copied
+/// exactly once, into its reassembly buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct FragmentRef<'a> {
+    pub object_id: u64,
+    pub fragment_id: u64,
+    pub start: bool,
+    pub end: bool,
+    pub data: &'a [u8],
+}
+
+impl<'a> FragmentRef<'a> {
+    /// Unpack a fragment from wire format bytes without copying its data.
+    pub fn unpack(data: &'a [u8]) -> Result<(Self, &'a [u8]), PowerShellRemotingError> {
+        if data.len() < HEADER_LEN {
+            return Err(PowerShellRemotingError::InvalidMessage(format!(
+                "Fragment too short, need at least {HEADER_LEN} bytes"
+            )));
         }
 
         let mut cursor = Cursor::new(data);
@@ -94,23 +119,41 @@ impl Fragment {
 
         trace!(start, end, "Unpacking fragment with start and end flags");
 
-        // let length = u32::from_be_bytes([data[17], data[18], data[19], data[20]]) as usize;
         let length = cursor.read_u32::<BigEndian>()? as usize;
 
         trace!(length, "Unpacking fragment with data length");
-        if data.len() < 21 + length {
-            return Err(PowerShellRemotingError::InvalidMessage(format!(
-                "Fragment data truncated: expected {} bytes, got {}",
-                21 + length,
-                data.len()
-            )));
-        }
 
-        let fragment_data = data[21..21 + length].to_vec();
-        let remaining = &data[21 + length..];
+        // The length comes straight off the wire: on 32-bit targets (wasm)
+        // `HEADER_LEN + length` can overflow, so never trust it unchecked.
+        let data_end = HEADER_LEN
+            .checked_add(length)
+            .filter(|data_end| *data_end <= data.len())
+            .ok_or_else(|| {
+                PowerShellRemotingError::InvalidMessage(format!(
+                    "Fragment data truncated: header declares {length} bytes, got {}",
+                    data.len() - HEADER_LEN
+                ))
+            })?;
+
+        let fragment = Self {
+            object_id,
+            fragment_id,
+            start,
+            end,
+            data: &data[HEADER_LEN..data_end],
+        };
 
-        let fragment = Self::new(object_id, fragment_id, fragment_data, start, end);
+        Ok((fragment, &data[data_end..]))
+    }
 
-        Ok((fragment, remaining))
+    /// Copy the borrowed data into an owned [`Fragment`].
+    pub fn into_fragment(self) -> Fragment {
+        Fragment::new(
+            self.object_id,
+            self.fragment_id,
+            self.data.to_vec(),
+            self.start,
+            self.end,
+        )
     }
 }
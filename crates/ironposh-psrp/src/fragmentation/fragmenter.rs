@@ -65,8 +65,10 @@ impl Fragmenter {
             start = false;
         }
 
-        for chunk in remaining_bytes.chunks(max_size) {
-            let end = chunk.len() < max_size;
+        let mut chunks = remaining_bytes.chunks(max_size).peekable();
+        while let Some(chunk) = chunks.next() {
+            // A message that fills its last chunk exactly still has to end.
+            let end = chunks.peek().is_none();
             let fragment = Fragment::new(
                 self.outgoing_counter,
                 fragment_id,
@@ -78,10 +80,6 @@ impl Fragmenter {
             fragments.push(fragment.pack());
             fragment_id += 1;
             start = false;
-
-            if end {
-                break;
-            }
         }
 
         self.outgoing_counter += 1;
@@ -124,7 +122,11 @@ impl Fragmenter {
 
             fragements.extend(message_fragments);
 
-            remaing_size = self.max_fragment_size - fragements.last().map_or(0, Vec::len);
+            // The last group already holds a header, so it can be fuller than
+            // `max_fragment_size` alone.
+            remaing_size = self
+                .max_fragment_size
+                .saturating_sub(fragements.last().map_or(0, Vec::len));
             if remaing_size == 0 {
                 remaing_size = self.max_fragment_size; // Reset for next message
             }
@@ -1,4 +1,5 @@
 pub mod defragmenter;
+pub mod diagnostics;
 pub mod fragment;
 pub mod fragmenter;
 
@@ -6,6 +7,7 @@ pub mod fragmenter;
 mod tests;
 
 pub use defragmenter::*;
+pub use diagnostics::{BlobDiagnostics, MessageOrigin};
 pub use fragment::*;
 pub use fragmenter::*;
 
@@ -8,6 +8,7 @@ pub mod cores;
 pub mod fragmentation;
 pub mod messages;
 pub mod ps_value;
+pub mod testing;
 
 use std::str::Utf8Error;
 
@@ -42,6 +43,37 @@ pub enum PowerShellRemotingError {
 
     #[error("Output formatting error: {0}")]
     OutputFormattingError(&'static str),
+
+    #[error("Resource limit exceeded: {resource} is limited to {limit} bytes")]
+    ResourceLimitExceeded {
+        resource: &'static str,
+        limit: usize,
+    },
+
+    /// A fragment or message that failed, with where it came from and a dump
+    /// of its bytes.
+    #[error("{source}\n{diagnostics}")]
+    Diagnosed {
+        source: Box<Self>,
+        diagnostics: Box<BlobDiagnostics>,
+    },
+}
+
+impl PowerShellRemotingError {
+    /// The blob context, if the error carries one.
+    pub fn diagnostics(&self) -> Option<&BlobDiagnostics> {
+        match self {
+            Self::Diagnosed { diagnostics, .. } => Some(diagnostics),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn diagnosed(self, diagnostics: BlobDiagnostics) -> Self {
+        Self::Diagnosed {
+            source: Box::new(self),
+            diagnostics: Box::new(diagnostics),
+        }
+    }
 }
 
 impl From<std::io::Error> for PowerShellRemotingError {
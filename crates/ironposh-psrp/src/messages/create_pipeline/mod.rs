@@ -24,7 +24,7 @@ pub struct CreatePipeline {
     #[builder(default = true)]
     #[ps(name = "NoInput")]
     pub no_input: bool,
-    #[builder(default = ApartmentState::Unknown)]
+    #[builder(default)]
     #[ps(name = "ApartmentState")]
     pub apartment_state: ApartmentState,
     #[builder(default = RemoteStreamOptions::None)]
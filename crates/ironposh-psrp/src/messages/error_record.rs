@@ -1,14 +1,14 @@
-use std::fmt::Write;
+use std::fmt::{self, Write};
 
 use crate::ps_value::{Properties, PsPrimitiveValue, PsValue};
-use ironposh_macros::{PsDeserialize, PsSerialize};
+use ironposh_macros::{PsDeserialize, PsEnum, PsSerialize};
 
 /// ERROR_RECORD (MS-PSRP §2.2.2.16). Fully macro-derived.
 ///
 /// The message is emitted under both `ErrorRecord` and `Message` (and as
-/// `<ToString>`); the category is a prefix-flattened sub-object
-/// (`ErrorCategory_*`); `exception`/`invocation_info` stay as raw `PsValue`
-/// (genuinely-arbitrary remote objects).
+/// `<ToString>`); the category and details are prefix-flattened sub-objects
+/// (`ErrorCategory_*`, `ErrorDetails_*`); `exception`/`invocation_info` stay
+/// as raw `PsValue` (genuinely-arbitrary remote objects).
 #[derive(Debug, Clone, PartialEq, Eq, typed_builder::TypedBuilder, PsSerialize, PsDeserialize)]
 #[ps(
     message_type = ErrorRecord,
@@ -47,7 +47,7 @@ pub struct ErrorRecord {
     /// Error category information (flattened as `ErrorCategory_*`)
     #[builder(default)]
     #[ps(flatten_prefix = "ErrorCategory_")]
-    pub error_category: Option<ErrorCategory>,
+    pub error_category: Option<ErrorCategoryInfo>,
     /// Whether to serialize extended information
     #[builder(default = false)]
     #[ps(name = "SerializeExtendedInfo", default)]
@@ -56,6 +56,15 @@ pub struct ErrorRecord {
     #[builder(default)]
     #[ps(name = "InvocationInfo")]
     pub invocation_info: Option<PsValue>,
+    /// Remote script stack trace (`$_.ScriptStackTrace`), when the server sent it
+    #[builder(default)]
+    #[ps(name = "ErrorDetails_ScriptStackTrace")]
+    pub script_stack_trace: Option<String>,
+    /// `$_.ErrorDetails` (flattened as `ErrorDetails_*`). Also `Some` when the
+    /// server sent only the script stack trace under that prefix.
+    #[builder(default)]
+    #[ps(flatten_prefix = "ErrorDetails_")]
+    pub error_details: Option<ErrorDetails>,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -68,13 +77,87 @@ pub struct RenderOptions {
     pub trim: bool,
 }
 
-/// Error category information. Macro-derived; flattened into [`ErrorRecord`] with
-/// an `ErrorCategory_` prefix.
+/// `System.Management.Automation.ErrorCategory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PsEnum)]
+#[ps(repr = "i32")]
+pub enum ErrorCategory {
+    #[default]
+    NotSpecified = 0,
+    OpenError = 1,
+    CloseError = 2,
+    DeviceError = 3,
+    DeadlockDetected = 4,
+    InvalidArgument = 5,
+    InvalidData = 6,
+    InvalidOperation = 7,
+    InvalidResult = 8,
+    InvalidType = 9,
+    MetadataError = 10,
+    NotImplemented = 11,
+    NotInstalled = 12,
+    ObjectNotFound = 13,
+    OperationStopped = 14,
+    OperationTimeout = 15,
+    SyntaxError = 16,
+    ParserError = 17,
+    PermissionDenied = 18,
+    ResourceBusy = 19,
+    ResourceExists = 20,
+    ResourceUnavailable = 21,
+    ReadError = 22,
+    WriteError = 23,
+    FromStdErr = 24,
+    SecurityError = 25,
+    ProtocolError = 26,
+    ConnectionError = 27,
+    AuthenticationError = 28,
+    LimitsExceeded = 29,
+    QuotaExceeded = 30,
+    NotEnabled = 31,
+}
+
+impl From<ErrorCategory> for i32 {
+    fn from(category: ErrorCategory) -> Self {
+        category as Self
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Newer servers may send categories this client does not know yet; those
+/// read as `NotSpecified` rather than failing the whole record.
+mod category_conv {
+    use super::ErrorCategory;
+    use crate::PowerShellRemotingError;
+    use crate::ps_value::{FromPsValue, PsPrimitiveValue, PsValue, ToPsValue};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // signature fixed by #[ps(with)]
+    pub fn to_ps_value(category: &ErrorCategory) -> PsValue {
+        category.to_ps_value()
+    }
+
+    pub fn from_ps_value(value: &PsValue) -> Result<ErrorCategory, PowerShellRemotingError> {
+        ErrorCategory::from_ps_value(value).or_else(|err| match value {
+            PsValue::Primitive(PsPrimitiveValue::I32(_)) => Ok(ErrorCategory::NotSpecified),
+            _ => Err(err),
+        })
+    }
+}
+
+/// Error category information (`$_.CategoryInfo`). Macro-derived; flattened
+/// into [`ErrorRecord`] with an `ErrorCategory_` prefix.
+///
+/// `Display` is PowerShell's category line,
+/// `ObjectNotFound: (ea:String) [], CommandNotFoundException`.
 #[derive(Debug, Clone, PartialEq, Eq, typed_builder::TypedBuilder, PsSerialize, PsDeserialize)]
-pub struct ErrorCategory {
-    /// The error category number
-    #[ps(name = "Category")]
-    pub category: i32,
+pub struct ErrorCategoryInfo {
+    /// The error category
+    #[ps(name = "Category", with = "category_conv")]
+    pub category: ErrorCategory,
     /// The activity that caused the error
     #[builder(default)]
     #[ps(name = "Activity")]
@@ -91,16 +174,68 @@ pub struct ErrorCategory {
     #[builder(default)]
     #[ps(name = "TargetType")]
     pub target_type: Option<String>,
-    /// The error category message
+    /// The category line as the server rendered it
+    #[builder(default)]
+    #[ps(name = "Message")]
+    pub message: Option<String>,
+}
+
+impl ErrorCategoryInfo {
+    /// The server's category line, or one built from the parts when it sent none.
+    pub fn line(&self) -> String {
+        self.message.clone().unwrap_or_else(|| self.to_string())
+    }
+}
+
+impl fmt::Display for ErrorCategoryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let part = |s: &Option<String>| s.clone().unwrap_or_default();
+        write!(
+            f,
+            "{}: ({}:{}) [{}], {}",
+            self.category,
+            part(&self.target_name),
+            part(&self.target_type),
+            part(&self.activity),
+            part(&self.reason)
+        )
+    }
+}
+
+/// `$_.ErrorDetails`: a message set by the command in place of the
+/// exception's, and what the user can do about it. Macro-derived; flattened
+/// into [`ErrorRecord`] with an `ErrorDetails_` prefix.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, typed_builder::TypedBuilder, PsSerialize, PsDeserialize,
+)]
+pub struct ErrorDetails {
     #[builder(default)]
     #[ps(name = "Message")]
     pub message: Option<String>,
+    #[builder(default)]
+    #[ps(name = "RecommendedAction")]
+    pub recommended_action: Option<String>,
+}
+
+impl fmt::Display for ErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message.as_deref().unwrap_or_default())
+    }
 }
 
 impl ErrorRecord {
+    /// The message PowerShell shows: `ErrorDetails.Message` when the command
+    /// set one, else the exception's.
+    pub fn display_message(&self) -> &str {
+        self.error_details
+            .as_ref()
+            .and_then(|details| details.message.as_deref())
+            .unwrap_or(&self.message)
+    }
+
     /// PS 7 "ConciseView": just the main message.
     pub fn render_concise(&self) -> String {
-        normalize(self.message.as_str())
+        normalize(self.display_message())
     }
 
     /// Classic "NormalView": message + category + position (when available).
@@ -117,18 +252,13 @@ impl ErrorRecord {
         let mut out = String::new();
 
         // 1) Primary message
-        push_line(&mut out, &normalize(&self.message), opts.trim);
+        push_line(&mut out, &normalize(self.display_message()), opts.trim);
 
         // 2) Category line (short diagnostic summary)
         if opts.include_category
-            && let Some(cat) = self
-                .error_category
-                .as_ref()
-                .and_then(|c| c.message.as_ref())
-                .map(|s| normalize(s))
-            && !cat.is_empty()
+            && let Some(cat) = &self.error_category
         {
-            push_line(&mut out, &cat, opts.trim);
+            push_line(&mut out, &normalize(&cat.line()), opts.trim);
         }
 
         // 3) Position block (from InvocationInfo if present)
@@ -296,8 +426,8 @@ mod tests {
 
     #[test]
     fn test_error_record_with_category() {
-        let category = ErrorCategory::builder()
-            .category(13)
+        let category = ErrorCategoryInfo::builder()
+            .category(ErrorCategory::ObjectNotFound)
             .reason(Some("CommandNotFoundException".to_string()))
             .target_name(Some("ea".to_string()))
             .target_type(Some("String".to_string()))
@@ -356,8 +486,8 @@ mod tests {
 
     #[test]
     fn test_command_not_found_error() {
-        let category = ErrorCategory::builder()
-            .category(13)
+        let category = ErrorCategoryInfo::builder()
+            .category(ErrorCategory::ObjectNotFound)
             .activity(Some(String::new()))
             .reason(Some("CommandNotFoundException".to_string()))
             .target_name(Some("ea".to_string()))
@@ -395,8 +525,8 @@ mod tests {
 
     #[test]
     fn test_render_normal() {
-        let category = ErrorCategory::builder()
-            .category(13)
+        let category = ErrorCategoryInfo::builder()
+            .category(ErrorCategory::ObjectNotFound)
             .message(Some(
                 "ObjectNotFound: (ea:String) [], CommandNotFoundException".to_string(),
             ))
@@ -414,8 +544,8 @@ mod tests {
 
     #[test]
     fn test_render_with_options() {
-        let category = ErrorCategory::builder()
-            .category(13)
+        let category = ErrorCategoryInfo::builder()
+            .category(ErrorCategory::ObjectNotFound)
             .message(Some(
                 "ObjectNotFound: (ea:String) [], CommandNotFoundException".to_string(),
             ))
@@ -443,4 +573,58 @@ mod tests {
         });
         assert_eq!(rendered, "Test error");
     }
+
+    #[test]
+    fn category_line_is_built_from_the_parts_without_a_message() {
+        let category = ErrorCategoryInfo::builder()
+            .category(ErrorCategory::PermissionDenied)
+            .activity(Some("Remove-Item".to_string()))
+            .reason(Some("UnauthorizedAccessException".to_string()))
+            .target_name(Some("C:\\secret".to_string()))
+            .target_type(Some("String".to_string()))
+            .build();
+
+        assert_eq!(
+            category.line(),
+            "PermissionDenied: (C:\\secret:String) [Remove-Item], UnauthorizedAccessException"
+        );
+        assert_eq!(i32::from(category.category), 18);
+        assert_eq!(ErrorCategory::default().to_string(), "NotSpecified");
+    }
+
+    #[test]
+    fn unknown_category_reads_as_not_specified() {
+        let mut obj = ComplexObject::from(
+            ErrorCategoryInfo::builder()
+                .category(ErrorCategory::NotEnabled)
+                .build(),
+        );
+        obj.properties.insert_extended("Category", 99i32);
+
+        let category = ErrorCategoryInfo::try_from(obj).unwrap();
+        assert_eq!(category.category, ErrorCategory::NotSpecified);
+    }
+
+    #[test]
+    fn error_details_replace_the_message_and_roundtrip() {
+        let record = ErrorRecord::builder()
+            .message("Exception calling \"Connect\"".to_string())
+            .error_details(Some(
+                ErrorDetails::builder()
+                    .message(Some("The vault is locked.".to_string()))
+                    .recommended_action(Some("Run Unlock-Vault first.".to_string()))
+                    .build(),
+            ))
+            .build();
+
+        let complex_obj = ComplexObject::from(record.clone());
+        assert!(
+            complex_obj
+                .properties
+                .get("ErrorDetails_RecommendedAction")
+                .is_some()
+        );
+        assert_eq!(ErrorRecord::try_from(complex_obj).unwrap(), record);
+        assert_eq!(record.render_concise(), "The vault is locked.");
+    }
 }
@@ -1,6 +1,8 @@
 use ironposh_macros::PsEnum;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PsEnum)]
+/// `System.Threading.ApartmentState` of the runspace pool's and pipelines'
+/// threads. Servers treat `Unknown` as "use the default".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PsEnum)]
 #[ps(
     repr = "object",
     type_names(
@@ -13,5 +15,8 @@ use ironposh_macros::PsEnum;
 pub enum ApartmentState {
     STA = 0,
     MTA = 1,
+    #[default]
     Unknown = 2,
 }
+
+super::enum_conversions!(ApartmentState);
@@ -1,6 +1,5 @@
-use crate::ps_value::PsValue;
+use crate::ps_value::PsDict;
 use ironposh_macros::{PsDeserialize, PsSerialize};
-use std::collections::BTreeMap;
 
 /// PSVersionTable entry of ApplicationArguments — a PSPrimitiveDictionary whose
 /// values are macro-derived (Version values via `version_conv`, the compatible-
@@ -141,7 +140,7 @@ pub struct ApplicationArguments {
     pub ps_version_table: Option<PSVersionTable>,
     #[builder(default)]
     #[ps(flatten)]
-    pub additional_arguments: BTreeMap<String, PsValue>,
+    pub additional_arguments: PsDict,
 }
 
 impl ApplicationArguments {
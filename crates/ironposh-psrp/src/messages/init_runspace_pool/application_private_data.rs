@@ -1,6 +1,5 @@
-use crate::ps_value::PsValue;
+use crate::ps_value::PsDict;
 use ironposh_macros::{PsDeserialize, PsSerialize};
-use std::collections::BTreeMap;
 
 /// APPLICATION_PRIVATE_DATA (MS-PSRP §2.2.2.13): server → client.
 ///
@@ -11,7 +10,7 @@ use std::collections::BTreeMap;
 pub struct ApplicationPrivateData {
     /// The application private data as a dictionary of string keys to values.
     #[ps(name = "ApplicationPrivateData", nil_when_none)]
-    pub data: Option<BTreeMap<String, PsValue>>,
+    pub data: Option<PsDict>,
 }
 
 impl ApplicationPrivateData {
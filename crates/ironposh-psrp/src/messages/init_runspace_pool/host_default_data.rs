@@ -31,35 +31,51 @@ pub struct Size {
 /// `{T, V}` object pairing a .NET type name with the value. Both sides are
 /// macro-generated by `#[ps(value_dictionary)]` + the per-field `key`/`type_tag`
 /// attributes — there is no hand-written `ComplexObject` here.
+///
+/// The builder checks the values against each other (see
+/// [`HostDefaultData::validate`]) and returns a `Result`: servers accept an
+/// inconsistent set (a cursor outside the buffer, a window larger than the
+/// screen) and then misbehave on the first RawUI call that relies on it.
 #[derive(Debug, Clone, PartialEq, Eq, TypedBuilder, PsSerialize, PsDeserialize)]
+#[builder(build_method(into = Result<HostDefaultData, HostDefaultDataError>))]
 #[ps(
     value_dictionary,
     type_names("System.Collections.Hashtable", "System.Object")
 )]
 pub struct HostDefaultData {
+    /// `ForegroundColor`, a `System.ConsoleColor` (0-15).
     #[builder(default = 7)]
     #[ps(key = 0, type_tag = "System.ConsoleColor")]
     pub foreground_color: i32,
+    /// `BackgroundColor`, a `System.ConsoleColor` (0-15).
     #[builder(default = 0)]
     #[ps(key = 1, type_tag = "System.ConsoleColor")]
     pub background_color: i32,
+    /// `CursorPosition`, in buffer cells.
     #[builder(default)]
     #[ps(key = 2, type_tag = "System.Management.Automation.Host.Coordinates")]
     pub cursor_position: Coordinates,
+    /// `WindowPosition`: the buffer cell shown at the window's top left.
     #[builder(default)]
     #[ps(key = 3, type_tag = "System.Management.Automation.Host.Coordinates")]
     pub window_position: Coordinates,
+    /// `CursorSize`, as a percentage of the cell height (1-100).
     #[builder(default = 25)]
     #[ps(key = 4, type_tag = "System.Int32")]
     pub cursor_size: i32,
+    /// `BufferSize`, in cells.
     #[ps(key = 5, type_tag = "System.Management.Automation.Host.Size")]
     pub buffer_size: Size,
+    /// `WindowSize`, in cells; never larger than the buffer.
     #[ps(key = 6, type_tag = "System.Management.Automation.Host.Size")]
     pub window_size: Size,
+    /// `MaxWindowSize`: the largest window the current buffer allows.
     #[ps(key = 7, type_tag = "System.Management.Automation.Host.Size")]
     pub max_window_size: Size,
+    /// `MaxPhysicalWindowSize`: the largest window the screen allows.
     #[ps(key = 8, type_tag = "System.Management.Automation.Host.Size")]
     pub max_physical_window_size: Size,
+    /// `WindowTitle`.
     #[builder(default = "PowerShell".to_string())]
     #[ps(key = 9, type_tag = "System.String")]
     pub window_title: String,
@@ -71,6 +87,48 @@ pub struct HostDefaultData {
     pub ui_locale: String,
 }
 
+/// Why a [`HostDefaultData`] set is inconsistent.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HostDefaultDataError {
+    #[error("{field} must be a ConsoleColor (0-15), got {value}")]
+    InvalidColor { field: &'static str, value: i32 },
+
+    #[error("cursor_size must be a percentage (1-100), got {0}")]
+    InvalidCursorSize(i32),
+
+    #[error("{field} must be at least 1x1, got {width}x{height}")]
+    EmptySize {
+        field: &'static str,
+        width: i32,
+        height: i32,
+    },
+
+    #[error("{field} ({x}, {y}) lies outside the {width}x{height} buffer")]
+    OutsideBuffer {
+        field: &'static str,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+
+    #[error("window_size {width}x{height} exceeds {limit} {limit_width}x{limit_height}")]
+    WindowTooLarge {
+        width: i32,
+        height: i32,
+        limit: &'static str,
+        limit_width: i32,
+        limit_height: i32,
+    },
+}
+
+/// Lets the builder's `build()` validate; see [`HostDefaultData::validate`].
+impl From<HostDefaultData> for Result<HostDefaultData, HostDefaultDataError> {
+    fn from(data: HostDefaultData) -> Self {
+        data.validate().map(|()| data)
+    }
+}
+
 impl Default for HostDefaultData {
     /// The default host data PowerShell assumes when none is supplied.
     fn default() -> Self {
@@ -127,6 +185,78 @@ fn console_color_to_i32(color: Color) -> i32 {
 }
 
 impl HostDefaultData {
+    /// Check that the values fit together: colors are ConsoleColors, the
+    /// cursor size is a percentage, every size is non-empty, the cursor and the
+    /// window lie inside the buffer, and the window fits the buffer and the
+    /// screen.
+    pub fn validate(&self) -> Result<(), HostDefaultDataError> {
+        for (field, value) in [
+            ("foreground_color", self.foreground_color),
+            ("background_color", self.background_color),
+        ] {
+            if !(0..=15).contains(&value) {
+                return Err(HostDefaultDataError::InvalidColor { field, value });
+            }
+        }
+        if !(1..=100).contains(&self.cursor_size) {
+            return Err(HostDefaultDataError::InvalidCursorSize(self.cursor_size));
+        }
+        for (field, size) in [
+            ("buffer_size", &self.buffer_size),
+            ("window_size", &self.window_size),
+            ("max_window_size", &self.max_window_size),
+            ("max_physical_window_size", &self.max_physical_window_size),
+        ] {
+            if size.width < 1 || size.height < 1 {
+                return Err(HostDefaultDataError::EmptySize {
+                    field,
+                    width: size.width,
+                    height: size.height,
+                });
+            }
+        }
+
+        let buffer = &self.buffer_size;
+        let window_end = Coordinates {
+            x: self.window_position.x + self.window_size.width - 1,
+            y: self.window_position.y + self.window_size.height - 1,
+        };
+        for (field, position) in [
+            ("cursor_position", &self.cursor_position),
+            ("window_position", &self.window_position),
+            ("window_position + window_size", &window_end),
+        ] {
+            let inside =
+                (0..buffer.width).contains(&position.x) && (0..buffer.height).contains(&position.y);
+            if !inside {
+                return Err(HostDefaultDataError::OutsideBuffer {
+                    field,
+                    x: position.x,
+                    y: position.y,
+                    width: buffer.width,
+                    height: buffer.height,
+                });
+            }
+        }
+
+        let window = &self.window_size;
+        for (limit, size) in [
+            ("max_window_size", &self.max_window_size),
+            ("max_physical_window_size", &self.max_physical_window_size),
+        ] {
+            if window.width > size.width || window.height > size.height {
+                return Err(HostDefaultDataError::WindowTooLarge {
+                    width: window.width,
+                    height: window.height,
+                    limit,
+                    limit_width: size.width,
+                    limit_height: size.height,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Creates HostDefaultData from current crossterm terminal state
     ///
     /// Queries the terminal for current cursor position and buffer size,
@@ -183,3 +313,81 @@ impl HostDefaultData {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_validates_the_set() {
+        let size = |width, height| Size { width, height };
+        let built = HostDefaultData::builder()
+            .buffer_size(size(80, 25))
+            .window_size(size(80, 25))
+            .max_window_size(size(80, 25))
+            .max_physical_window_size(size(80, 25))
+            .cursor_position(Coordinates { x: 79, y: 24 })
+            .build();
+        assert!(built.is_ok(), "{built:?}");
+
+        let built = HostDefaultData::builder()
+            .buffer_size(size(80, 25))
+            .window_size(size(120, 25))
+            .max_window_size(size(120, 25))
+            .max_physical_window_size(size(120, 25))
+            .build();
+        assert!(
+            matches!(
+                built,
+                Err(HostDefaultDataError::OutsideBuffer {
+                    field: "window_position + window_size",
+                    ..
+                })
+            ),
+            "{built:?}"
+        );
+    }
+
+    #[test]
+    fn validate_reports_the_offending_value() {
+        assert_eq!(HostDefaultData::default().validate(), Ok(()));
+
+        let with = |change: fn(&mut HostDefaultData)| {
+            let mut data = HostDefaultData::default();
+            change(&mut data);
+            data.validate().unwrap_err()
+        };
+        assert_eq!(
+            with(|d| d.background_color = 16),
+            HostDefaultDataError::InvalidColor {
+                field: "background_color",
+                value: 16
+            }
+        );
+        assert_eq!(
+            with(|d| d.cursor_size = 0),
+            HostDefaultDataError::InvalidCursorSize(0)
+        );
+        assert!(matches!(
+            with(|d| d.max_physical_window_size.height = 0),
+            HostDefaultDataError::EmptySize {
+                field: "max_physical_window_size",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(|d| d.cursor_position.y = 3000),
+            HostDefaultDataError::OutsideBuffer {
+                field: "cursor_position",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(|d| d.max_window_size.width = 100),
+            HostDefaultDataError::WindowTooLarge {
+                limit: "max_window_size",
+                ..
+            }
+        ));
+    }
+}
@@ -1,3 +1,34 @@
+/// `i32` and `ComplexObject` conversions for a `PsEnum` with the object repr.
+///
+/// `PsEnum` leaves out `TryFrom` so enums with an `Error` variant still derive
+/// (rust#57644); the enums here have none, so they get the std conversions.
+macro_rules! enum_conversions {
+    ($name:ident) => {
+        impl From<$name> for i32 {
+            fn from(value: $name) -> Self {
+                value as Self
+            }
+        }
+
+        impl TryFrom<i32> for $name {
+            type Error = crate::PowerShellRemotingError;
+
+            fn try_from(value: i32) -> Result<Self, Self::Error> {
+                crate::ps_value::FromPsValue::from_ps_value(&crate::ps_value::PsValue::from(value))
+            }
+        }
+
+        impl TryFrom<crate::ps_value::ComplexObject> for $name {
+            type Error = crate::PowerShellRemotingError;
+
+            fn try_from(value: crate::ps_value::ComplexObject) -> Result<Self, Self::Error> {
+                Self::from_ps_object(value)
+            }
+        }
+    };
+}
+use enum_conversions;
+
 pub mod apartment_state;
 pub mod application_arguments;
 pub mod application_private_data;
@@ -8,7 +39,7 @@ pub mod ps_thread_options;
 pub use apartment_state::ApartmentState;
 pub use application_arguments::{ApplicationArguments, PSVersionTable};
 pub use application_private_data::ApplicationPrivateData;
-pub use host_default_data::{Coordinates, HostDefaultData, Size};
+pub use host_default_data::{Coordinates, HostDefaultData, HostDefaultDataError, Size};
 pub use host_info::HostInfo;
 pub use ps_thread_options::PSThreadOptions;
 
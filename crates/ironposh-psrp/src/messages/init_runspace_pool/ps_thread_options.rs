@@ -1,6 +1,7 @@
 use ironposh_macros::PsEnum;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PsEnum)]
+/// `PSThreadOptions`: which thread the server runs each pipeline on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PsEnum)]
 #[ps(
     repr = "object",
     type_names(
@@ -11,8 +12,11 @@ use ironposh_macros::PsEnum;
     )
 )]
 pub enum PSThreadOptions {
+    #[default]
     Default = 0,
     UseNewThread = 1,
     ReuseThread = 2,
     UseCurrentThread = 3,
 }
+
+super::enum_conversions!(PSThreadOptions);
@@ -1,7 +1,12 @@
+use std::borrow::Cow;
+
 use crate::RemoteHostMethodId;
-use crate::ps_value::PsValue;
+use crate::ps_value::{ComplexObject, PsValue};
 use ironposh_macros::{PsDeserialize, PsSerialize};
 
+/// `COR_E_SYSTEM`, the HRESULT of every `RuntimeException`.
+const RUNTIME_EXCEPTION_HRESULT: i32 = -2_146_233_087;
+
 /// PIPELINE_HOST_RESPONSE (MS-PSRP §2.2.2.28): client → server response to a
 /// pipeline host call.
 ///
@@ -22,11 +27,56 @@ pub struct PipelineHostResponse {
     pub method_exception: Option<PsValue>,
 }
 
+impl PipelineHostResponse {
+    /// A response failing the call with a [`remote_exception`] for `message`.
+    pub fn exception(call_id: i64, method: RemoteHostMethodId, message: impl Into<String>) -> Self {
+        Self {
+            call_id,
+            method,
+            method_result: None,
+            method_exception: Some(remote_exception(message)),
+        }
+    }
+}
+
+/// A serialized `System.Management.Automation.RemoteException` for the `me`
+/// property.
+///
+/// It is shaped like one PowerShell would send: the full type chain, a
+/// `Type: message` `<ToString>`, and the `Exception` properties with the
+/// stack trace and source left `Nil`. The server decodes `me` as an
+/// exception, so scripts catching `[RuntimeException]` see the host's
+/// failure instead of a decoding error.
+pub fn remote_exception(message: impl Into<String>) -> PsValue {
+    let message = message.into();
+    PsValue::Object(
+        ComplexObject::standard()
+            .type_names([
+                Cow::Borrowed("System.Management.Automation.RemoteException"),
+                Cow::Borrowed("System.Management.Automation.RuntimeException"),
+                Cow::Borrowed("System.SystemException"),
+                Cow::Borrowed("System.Exception"),
+                Cow::Borrowed("System.Object"),
+            ])
+            .to_string_repr(format!(
+                "System.Management.Automation.RemoteException: {message}"
+            ))
+            .adapted("WasThrownFromThrowStatement", false)
+            .adapted("Message", message)
+            .adapted("InnerException", None::<String>)
+            .adapted("StackTrace", None::<String>)
+            .adapted("HelpLink", None::<String>)
+            .adapted("Source", None::<String>)
+            .adapted("HResult", RUNTIME_EXCEPTION_HRESULT)
+            .build(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::MessageType;
-    use crate::ps_value::{ComplexObject, PsObjectWithType, PsPrimitiveValue};
+    use crate::ps_value::{PsObjectWithType, PsPrimitiveValue};
 
     #[test]
     fn test_pipeline_host_response_roundtrip() {
@@ -62,6 +112,34 @@ mod tests {
         assert!(restored.method_exception.is_some());
     }
 
+    #[test]
+    fn host_errors_are_sent_as_remote_exceptions() {
+        let response =
+            PipelineHostResponse::exception(7, RemoteHostMethodId::ReadLine, "input was cancelled");
+        let restored =
+            PipelineHostResponse::try_from(ComplexObject::from(response.clone())).unwrap();
+        assert_eq!(restored, response);
+
+        let Some(PsValue::Object(exception)) = &restored.method_exception else {
+            panic!("expected an exception object, got {restored:?}");
+        };
+        let type_names = &exception.type_def.as_ref().unwrap().type_names;
+        assert_eq!(
+            type_names[0],
+            "System.Management.Automation.RemoteException"
+        );
+        assert!(type_names.iter().any(|name| name == "System.Exception"));
+        assert_eq!(
+            exception.req::<String>("Message").unwrap(),
+            "input was cancelled"
+        );
+        assert_eq!(exception.opt::<String>("StackTrace").unwrap(), None);
+        assert_eq!(
+            exception.to_string.as_deref(),
+            Some("System.Management.Automation.RemoteException: input was cancelled")
+        );
+    }
+
     #[test]
     fn test_pipeline_host_response_empty() {
         let original = PipelineHostResponse::builder()
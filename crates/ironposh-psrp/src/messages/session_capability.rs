@@ -21,9 +21,10 @@ pub struct SessionCapability {
     pub ps_version: String,
     #[ps(name = "SerializationVersion", with = "version_conv")]
     pub serialization_version: String,
-    /// Opaque serialized .NET TimeZone blob, carried as a `<BA>` byte array.
+    /// BinaryFormatter-serialized .NET time zone (see [`TimeZone::to_bytes`]),
+    /// carried as a `<BA>` byte array.
     #[ps(name = "TimeZone", with = "timezone_conv")]
-    pub time_zone: Option<String>,
+    pub time_zone: Option<Vec<u8>>,
 }
 
 /// `#[ps(with = ..)]` converter: these fields are .NET `Version` values
@@ -46,24 +47,407 @@ mod version_conv {
     }
 }
 
-/// `#[ps(with = ..)]` converter: the TimeZone is an opaque serialized blob the
-/// client never interprets, carried as a `<BA>` byte array.
+/// `#[ps(with = ..)]` converter: the TimeZone is a serialized blob the client
+/// never reads back, carried as a `<BA>` byte array.
 mod timezone_conv {
     use crate::PowerShellRemotingError;
     use crate::ps_value::{PsPrimitiveValue, PsValue};
 
-    pub fn to_ps_value(value: &str) -> PsValue {
-        PsValue::Primitive(PsPrimitiveValue::Bytes(value.as_bytes().to_vec()))
+    pub fn to_ps_value(value: &[u8]) -> PsValue {
+        PsValue::Primitive(PsPrimitiveValue::Bytes(value.to_vec()))
     }
 
-    pub fn from_ps_value(value: &PsValue) -> Result<String, PowerShellRemotingError> {
+    pub fn from_ps_value(value: &PsValue) -> Result<Vec<u8>, PowerShellRemotingError> {
         match value {
-            PsValue::Primitive(PsPrimitiveValue::Bytes(bytes)) => {
-                Ok(String::from_utf8_lossy(bytes).to_string())
-            }
+            PsValue::Primitive(PsPrimitiveValue::Bytes(bytes)) => Ok(bytes.clone()),
             other => Err(PowerShellRemotingError::InvalidMessage(format!(
                 "expected ByteArray TimeZone, got {other:?}"
             ))),
         }
     }
 }
+
+/// The client's time zone for SESSION_CAPABILITY, which carries it as a
+/// .NET `System.CurrentSystemTimeZone` in BinaryFormatter form (MS-NRBF).
+///
+/// The server uses it for `[DateTime]` conversions on the client's behalf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeZone {
+    /// Local time minus UTC, in minutes (`-480` for Pacific Standard Time).
+    pub utc_offset_minutes: i32,
+    pub standard_name: Option<String>,
+    pub daylight_name: Option<String>,
+    /// Daylight saving periods the zone has looked up, by year. Empty leaves
+    /// them to the server.
+    pub daylight_changes: Vec<DaylightChanges>,
+}
+
+/// One year's daylight saving period (`System.Globalization.DaylightTime`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaylightChanges {
+    pub year: i32,
+    /// Local `DateTime` ticks (100 ns since 0001-01-01) when it starts.
+    pub start_ticks: i64,
+    /// Local `DateTime` ticks when it ends.
+    pub end_ticks: i64,
+    /// How far clocks move, in ticks.
+    pub delta_ticks: i64,
+}
+
+impl TimeZone {
+    /// A zone `utc_offset_minutes` from UTC, without daylight saving.
+    pub fn fixed(utc_offset_minutes: i32) -> Self {
+        Self {
+            utc_offset_minutes,
+            standard_name: None,
+            daylight_name: None,
+            daylight_changes: Vec::new(),
+        }
+    }
+
+    pub fn utc() -> Self {
+        Self {
+            standard_name: Some("Coordinated Universal Time".to_owned()),
+            daylight_name: Some("Coordinated Universal Time".to_owned()),
+            ..Self::fixed(0)
+        }
+    }
+
+    /// The zone as a BinaryFormatter stream, the way PowerShell sends it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = nrbf::Writer::default();
+        w.header(ROOT_ID);
+
+        w.class_header(
+            ROOT_ID,
+            "System.CurrentSystemTimeZone",
+            &[
+                "m_CachedDaylightChanges",
+                "m_ticksOffset",
+                "m_standardName",
+                "m_daylightName",
+            ],
+            &[
+                nrbf::BinaryType::SystemClass("System.Collections.Hashtable"),
+                nrbf::BinaryType::Primitive(nrbf::INT64),
+                nrbf::BinaryType::String,
+                nrbf::BinaryType::String,
+            ],
+        );
+        let table_id = w.next_id();
+        w.reference(table_id);
+        w.i64(i64::from(self.utc_offset_minutes) * TICKS_PER_MINUTE);
+        w.string_or_null(self.standard_name.as_deref());
+        w.string_or_null(self.daylight_name.as_deref());
+
+        let count = i32::try_from(self.daylight_changes.len()).unwrap_or(i32::MAX);
+        w.class_header(
+            table_id,
+            "System.Collections.Hashtable",
+            &[
+                "LoadFactor",
+                "Version",
+                "Comparer",
+                "HashCodeProvider",
+                "HashSize",
+                "Keys",
+                "Values",
+            ],
+            &[
+                nrbf::BinaryType::Primitive(nrbf::SINGLE),
+                nrbf::BinaryType::Primitive(nrbf::INT32),
+                nrbf::BinaryType::SystemClass("System.Collections.IComparer"),
+                nrbf::BinaryType::SystemClass("System.Collections.IHashCodeProvider"),
+                nrbf::BinaryType::Primitive(nrbf::INT32),
+                nrbf::BinaryType::ObjectArray,
+                nrbf::BinaryType::ObjectArray,
+            ],
+        );
+        w.f32(HASHTABLE_LOAD_FACTOR);
+        w.i32(count);
+        w.null();
+        w.null();
+        w.i32(HASHTABLE_SIZE);
+        let keys_id = w.next_id();
+        let values_id = w.next_id();
+        w.reference(keys_id);
+        w.reference(values_id);
+
+        w.object_array(keys_id, count);
+        for changes in &self.daylight_changes {
+            w.typed_i32(changes.year);
+        }
+        w.object_array(values_id, count);
+        let first_id = w.peek_id();
+        for _ in &self.daylight_changes {
+            let id = w.next_id();
+            w.reference(id);
+        }
+        for (index, changes) in self.daylight_changes.iter().enumerate() {
+            let id = first_id + i32::try_from(index).unwrap_or(i32::MAX);
+            if index == 0 {
+                w.class_header(
+                    id,
+                    "System.Globalization.DaylightTime",
+                    &["m_start", "m_end", "m_delta"],
+                    &[
+                        nrbf::BinaryType::Primitive(nrbf::DATE_TIME),
+                        nrbf::BinaryType::Primitive(nrbf::DATE_TIME),
+                        nrbf::BinaryType::Primitive(nrbf::TIME_SPAN),
+                    ],
+                );
+            } else {
+                w.class_with_id(id, first_id);
+            }
+            w.local_date_time(changes.start_ticks);
+            w.local_date_time(changes.end_ticks);
+            w.i64(changes.delta_ticks);
+        }
+
+        w.end()
+    }
+}
+
+const ROOT_ID: i32 = 1;
+const TICKS_PER_MINUTE: i64 = 600_000_000;
+/// What `new Hashtable()` serializes.
+const HASHTABLE_LOAD_FACTOR: f32 = 0.72;
+const HASHTABLE_SIZE: i32 = 11;
+
+/// Just enough of a BinaryFormatter (MS-NRBF) writer for [`TimeZone`].
+mod nrbf {
+    pub const INT32: u8 = 8;
+    pub const INT64: u8 = 9;
+    pub const SINGLE: u8 = 11;
+    pub const TIME_SPAN: u8 = 12;
+    pub const DATE_TIME: u8 = 13;
+
+    const SERIALIZED_STREAM_HEADER: u8 = 0;
+    const CLASS_WITH_ID: u8 = 1;
+    const SYSTEM_CLASS_WITH_MEMBERS_AND_TYPES: u8 = 4;
+    const BINARY_OBJECT_STRING: u8 = 6;
+    const MEMBER_PRIMITIVE_TYPED: u8 = 8;
+    const MEMBER_REFERENCE: u8 = 9;
+    const OBJECT_NULL: u8 = 10;
+    const MESSAGE_END: u8 = 11;
+    const ARRAY_SINGLE_OBJECT: u8 = 16;
+
+    /// `DateTimeKind.Local` in the top two bits of a serialized `DateTime`.
+    const KIND_LOCAL: u64 = 2 << 62;
+
+    pub enum BinaryType {
+        Primitive(u8),
+        String,
+        SystemClass(&'static str),
+        ObjectArray,
+    }
+
+    impl BinaryType {
+        const fn tag(&self) -> u8 {
+            match self {
+                Self::Primitive(_) => 0,
+                Self::String => 1,
+                Self::SystemClass(_) => 3,
+                Self::ObjectArray => 5,
+            }
+        }
+    }
+
+    pub struct Writer {
+        buf: Vec<u8>,
+        next_id: i32,
+    }
+
+    impl Default for Writer {
+        fn default() -> Self {
+            Self {
+                buf: Vec::new(),
+                next_id: 2,
+            }
+        }
+    }
+
+    impl Writer {
+        pub fn header(&mut self, root_id: i32) {
+            self.buf.push(SERIALIZED_STREAM_HEADER);
+            self.i32(root_id);
+            self.i32(-1);
+            self.i32(1);
+            self.i32(0);
+        }
+
+        pub fn class_header(
+            &mut self,
+            id: i32,
+            name: &str,
+            members: &[&str],
+            types: &[BinaryType],
+        ) {
+            self.buf.push(SYSTEM_CLASS_WITH_MEMBERS_AND_TYPES);
+            self.i32(id);
+            self.length_prefixed(name);
+            self.i32(i32::try_from(members.len()).unwrap_or(i32::MAX));
+            for member in members {
+                self.length_prefixed(member);
+            }
+            self.buf.extend(types.iter().map(BinaryType::tag));
+            for ty in types {
+                match ty {
+                    BinaryType::Primitive(primitive) => self.buf.push(*primitive),
+                    BinaryType::SystemClass(class) => self.length_prefixed(class),
+                    BinaryType::String | BinaryType::ObjectArray => {}
+                }
+            }
+        }
+
+        pub fn class_with_id(&mut self, id: i32, metadata_id: i32) {
+            self.buf.push(CLASS_WITH_ID);
+            self.i32(id);
+            self.i32(metadata_id);
+        }
+
+        pub fn object_array(&mut self, id: i32, len: i32) {
+            self.buf.push(ARRAY_SINGLE_OBJECT);
+            self.i32(id);
+            self.i32(len);
+        }
+
+        pub fn next_id(&mut self) -> i32 {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        }
+
+        pub const fn peek_id(&self) -> i32 {
+            self.next_id
+        }
+
+        pub fn reference(&mut self, id: i32) {
+            self.buf.push(MEMBER_REFERENCE);
+            self.i32(id);
+        }
+
+        pub fn null(&mut self) {
+            self.buf.push(OBJECT_NULL);
+        }
+
+        pub fn string_or_null(&mut self, value: Option<&str>) {
+            match value {
+                Some(value) => {
+                    self.buf.push(BINARY_OBJECT_STRING);
+                    let id = self.next_id();
+                    self.i32(id);
+                    self.length_prefixed(value);
+                }
+                None => self.null(),
+            }
+        }
+
+        pub fn typed_i32(&mut self, value: i32) {
+            self.buf.push(MEMBER_PRIMITIVE_TYPED);
+            self.buf.push(INT32);
+            self.i32(value);
+        }
+
+        pub fn i32(&mut self, value: i32) {
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn i64(&mut self, value: i64) {
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn f32(&mut self, value: f32) {
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn local_date_time(&mut self, ticks: i64) {
+            let ticks = u64::try_from(ticks).unwrap_or(0);
+            self.buf
+                .extend_from_slice(&(ticks | KIND_LOCAL).to_le_bytes());
+        }
+
+        /// A UTF-8 string after its length, seven bits per byte.
+        fn length_prefixed(&mut self, value: &str) {
+            let mut len = value.len();
+            while len >= 0x80 {
+                self.buf.push((len & 0x7f) as u8 | 0x80);
+                len >>= 7;
+            }
+            self.buf.push(len as u8);
+            self.buf.extend_from_slice(value.as_bytes());
+        }
+
+        pub fn end(mut self) -> Vec<u8> {
+            self.buf.push(MESSAGE_END);
+            self.buf
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    /// What Windows PowerShell 2.0 sent from a Pacific time machine in 2009.
+    const PACIFIC_2009: &str = "AAEAAAD/////AQAAAAAAAAAEAQAAABxTeXN0ZW0uQ3VycmVudFN5c3RlbVRpbWVab25lBAAAABdtX0NhY2hlZERheWxpZ2h0Q2hhbmdlcw1tX3RpY2tzT2Zmc2V0Dm1fc3RhbmRhcmROYW1lDm1fZGF5bGlnaHROYW1lAwABARxTeXN0ZW0uQ29sbGVjdGlvbnMuSGFzaHRhYmxlCQkCAAAAAMDc8bz///8KCgQCAAAAHFN5c3RlbS5Db2xsZWN0aW9ucy5IYXNodGFibGUHAAAACkxvYWRGYWN0b3IHVmVyc2lvbghDb21wYXJlchBIYXNoQ29kZVByb3ZpZGVyCEhhc2hTaXplBEtleXMGVmFsdWVzAAADAwAFBQsIHFN5c3RlbS5Db2xsZWN0aW9ucy5JQ29tcGFyZXIkU3lzdGVtLkNvbGxlY3Rpb25zLklIYXNoQ29kZVByb3ZpZGVyCOxROD8BAAAACgoLAAAACQMAAAAJBAAAABADAAAAAQAAAAgI2QcAABAEAAAAAQAAAAkFAAAABAUAAAAhU3lzdGVtLkdsb2JhbGl6YXRpb24uRGF5bGlnaHRUaW1lAwAAAAdtX3N0YXJ0BW1fZW5kB21fZGVsdGEAAAANDQwAkOq4qG3LiAAQOyeuKMyIAGjEYQgAAAAL";
+
+    #[test]
+    fn encodes_like_powershell() {
+        let zone = TimeZone {
+            daylight_changes: vec![DaylightChanges {
+                year: 2009,
+                start_ticks: 0x08cb_6da8_b8ea_9000,
+                end_ticks: 0x08cc_28ae_273b_1000,
+                delta_ticks: 60 * TICKS_PER_MINUTE,
+            }],
+            ..TimeZone::fixed(-480)
+        };
+        let expected = base64::engine::general_purpose::STANDARD
+            .decode(PACIFIC_2009)
+            .unwrap();
+        assert_eq!(zone.to_bytes(), expected);
+    }
+
+    #[test]
+    fn names_and_extra_years_get_their_own_records() {
+        let changes = DaylightChanges {
+            year: 2026,
+            start_ticks: 1,
+            end_ticks: 2,
+            delta_ticks: 3,
+        };
+        let zone = TimeZone {
+            standard_name: Some("GMT Standard Time".to_owned()),
+            daylight_name: Some("GMT Summer Time".to_owned()),
+            daylight_changes: vec![
+                changes,
+                DaylightChanges {
+                    year: 2027,
+                    ..changes
+                },
+            ],
+            ..TimeZone::fixed(0)
+        };
+        let bytes = zone.to_bytes();
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+
+        assert!(contains(b"\x06\x03\x00\x00\x00\x11GMT Standard Time"));
+        assert!(contains(b"\x06\x04\x00\x00\x00\x0fGMT Summer Time"));
+        // The second DaylightTime reuses the first one's member list.
+        assert!(contains(&[1, 8, 0, 0, 0, 7, 0, 0, 0]));
+        assert_eq!(bytes.last(), Some(&11));
+
+        let capability = SessionCapability {
+            protocol_version: "2.3".to_owned(),
+            ps_version: "2.0".to_owned(),
+            serialization_version: "1.1.0.1".to_owned(),
+            time_zone: Some(TimeZone::utc().to_bytes()),
+        };
+        let roundtrip =
+            SessionCapability::try_from(crate::ps_value::ComplexObject::from(capability.clone()))
+                .unwrap();
+        assert_eq!(roundtrip, capability);
+    }
+}
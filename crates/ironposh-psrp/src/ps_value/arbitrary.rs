@@ -0,0 +1,174 @@
+//! `proptest` strategies for PSRP values, for serializer/parser property tests.
+//!
+//! Generated values stay inside what CLIXML can carry: strings hold no control
+//! characters (CLIXML would need `_xHHHH_` escapes for those), an enum object
+//! always has a type naming `System.Enum` and nothing else does (the parser
+//! tells enums from integer Extended Primitives by their type), and type names
+//! come from a small pool so `<TNRef>` reuse actually happens.
+
+use proptest::prelude::*;
+
+use super::{
+    ComplexObject, ComplexObjectContent, Container, Properties, PsEnums, PsPrimitiveValue, PsType,
+    PsValue,
+};
+
+/// Nesting depth of generated objects.
+const MAX_DEPTH: u32 = 3;
+
+const TYPE_NAMES: &[&str] = &[
+    "System.Object",
+    "System.Collections.ArrayList",
+    "System.Management.Automation.PSCustomObject",
+    "Deserialized.System.Diagnostics.Process",
+];
+
+fn text() -> impl Strategy<Value = String> {
+    "\\PC{0,12}"
+}
+
+fn plain_type() -> impl Strategy<Value = PsType> {
+    prop::collection::vec(prop::sample::select(TYPE_NAMES), 1..3).prop_map(|names| PsType {
+        type_names: names.into_iter().map(Into::into).collect(),
+    })
+}
+
+fn enum_type() -> impl Strategy<Value = PsType> {
+    "System\\.[A-Z][a-z]{2,8}Color".prop_map(|name| PsType {
+        type_names: vec![
+            name.into(),
+            "System.Enum".into(),
+            "System.ValueType".into(),
+            "System.Object".into(),
+        ],
+    })
+}
+
+fn properties(value: BoxedStrategy<PsValue>) -> impl Strategy<Value = Properties> {
+    prop::collection::btree_map("[A-Za-z][A-Za-z0-9_]{0,8}", (any::<bool>(), value), 0..4).prop_map(
+        |entries| {
+            let mut properties = Properties::new();
+            for (name, (adapted, value)) in entries {
+                if adapted {
+                    properties.insert_adapted(name, value);
+                } else {
+                    properties.insert_extended(name, value);
+                }
+            }
+            properties
+        },
+    )
+}
+
+fn container(value: &BoxedStrategy<PsValue>) -> impl Strategy<Value = Container> + use<> {
+    let items = || prop::collection::vec(value.clone(), 0..4);
+    prop_oneof![
+        items().prop_map(Container::Stack),
+        items().prop_map(Container::Queue),
+        items().prop_map(Container::List),
+        prop::collection::btree_map(value.clone(), value.clone(), 0..4)
+            .prop_map(Container::Dictionary),
+    ]
+}
+
+/// A [`ComplexObject`] whose nested values come from `value`.
+fn complex_object(value: &BoxedStrategy<PsValue>) -> impl Strategy<Value = ComplexObject> + use<> {
+    let object = |type_def: BoxedStrategy<Option<PsType>>,
+                  content: BoxedStrategy<ComplexObjectContent>| {
+        (
+            type_def,
+            prop::option::of(text()),
+            content,
+            properties(value.clone()),
+        )
+            .prop_map(|(type_def, to_string, content, properties)| ComplexObject {
+                type_def,
+                to_string,
+                content,
+                properties,
+            })
+    };
+    prop_oneof![
+        object(
+            prop::option::of(plain_type()).boxed(),
+            Just(ComplexObjectContent::Standard).boxed(),
+        ),
+        object(
+            prop::option::of(plain_type()).boxed(),
+            any::<PsPrimitiveValue>()
+                .prop_map(ComplexObjectContent::ExtendedPrimitive)
+                .boxed(),
+        ),
+        object(
+            prop::option::of(plain_type()).boxed(),
+            container(value)
+                .prop_map(ComplexObjectContent::Container)
+                .boxed(),
+        ),
+        object(
+            enum_type().prop_map(Some).boxed(),
+            any::<i32>()
+                .prop_map(|value| ComplexObjectContent::PsEnums(PsEnums { value }))
+                .boxed(),
+        ),
+    ]
+}
+
+impl Arbitrary for PsPrimitiveValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            text().prop_map(Self::Str),
+            any::<bool>().prop_map(Self::Bool),
+            any::<i32>().prop_map(Self::I32),
+            any::<u32>().prop_map(Self::U32),
+            any::<i64>().prop_map(Self::I64),
+            any::<u64>().prop_map(Self::U64),
+            any::<u128>().prop_map(|n| Self::Guid(uuid::Uuid::from_u128(n).to_string())),
+            any::<char>().prop_map(Self::Char),
+            Just(Self::Nil),
+            prop::collection::vec(any::<u8>(), 0..16).prop_map(Self::Bytes),
+            prop::collection::vec(any::<u8>(), 0..16).prop_map(Self::SecureString),
+            "[0-9]{1,3}(\\.[0-9]{1,3}){1,3}".prop_map(Self::Version),
+            "20[0-9]{2}-[01][0-9]-[0-3][0-9]T[0-2][0-9]:[0-5][0-9]:[0-5][0-9]"
+                .prop_map(Self::DateTime),
+            "PT[0-9]{1,4}(\\.[0-9]{1,7})?S".prop_map(Self::TimeSpan),
+            any::<f64>().prop_map(|n| Self::Double(n.to_string())),
+            any::<f32>().prop_map(|n| Self::Single(n.to_string())),
+            "-?[0-9]{1,10}(\\.[0-9]{1,6})?".prop_map(Self::Decimal),
+            any::<i16>().prop_map(Self::Int16),
+            any::<u16>().prop_map(Self::UInt16),
+            any::<u8>().prop_map(Self::Byte),
+            any::<i8>().prop_map(Self::SByte),
+            "https://[a-z]{1,10}\\.example/[a-z]{0,8}".prop_map(Self::Uri),
+            text().prop_map(Self::ScriptBlock),
+            text().prop_map(Self::Xml),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for PsValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        any::<PsPrimitiveValue>()
+            .prop_map(Self::Primitive)
+            .prop_recursive(MAX_DEPTH, 32, 4, |value| {
+                complex_object(&value).prop_map(Self::Object)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for ComplexObject {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        complex_object(&any::<PsValue>()).boxed()
+    }
+}
@@ -294,8 +294,10 @@ pub trait PsXmlDeserialize<'a>: Sized {
         context: &mut DeserializationContext,
     ) -> Result<Self> {
         let mut visitor = Self::visitor_with_context();
-        visitor.visit_node(node, context)?;
-        visitor.finish()
+        visitor
+            .visit_node(node, context)
+            .and_then(|()| visitor.finish())
+            .map_err(|e| e.at(node))
     }
 
     fn from_children_with_context(
@@ -396,10 +398,8 @@ impl<'a> PsXmlVisitor<'a> for PsTypeContextVisitor<'a> {
         _context: &mut DeserializationContext,
     ) -> Result<()> {
         for child in children {
-            if child.is_element()
-                && child.tag_name().name() == "T"
-                && let Some(text) = child.text()
-            {
+            if child.is_element() && child.tag_name().name() == "T" {
+                let text = child.text().unwrap_or_default();
                 self.type_names.push(Cow::Owned(text.to_string()));
             }
         }
@@ -502,9 +502,8 @@ impl<'a> PsXmlVisitor<'a> for ComplexObjectContextVisitor<'a> {
                     self.type_def = Some(ps_type);
                 }
                 "ToString" => {
-                    if let Some(text) = child.text() {
-                        self.to_string = Some(text.to_string());
-                    }
+                    // `<ToString></ToString>` is an empty string, not a missing one.
+                    self.to_string = Some(child.text().unwrap_or_default().to_string());
                 }
                 // Handle containers with context
                 "STK" | "QUE" | "LST" | "DCT" => {
@@ -537,7 +536,7 @@ impl<'a> PsXmlVisitor<'a> for ComplexObjectContextVisitor<'a> {
                     Ok(primitive) => {
                         self.content = ComplexObjectContent::ExtendedPrimitive(primitive);
                     }
-                    Err(ironposh_xml::XmlError::UnexpectedTag(_)) => {}
+                    Err(e) if matches!(e.kind(), ironposh_xml::XmlError::UnexpectedTag(_)) => {}
                     Err(e) => return Err(e),
                 },
             }
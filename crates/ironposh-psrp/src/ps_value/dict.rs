@@ -0,0 +1,189 @@
+//! [`PsDict`]: a string-keyed `PSPrimitiveDictionary`.
+//!
+//! ApplicationArguments, ApplicationPrivateData and host call results all
+//! carry `<DCT>`s whose keys are plain strings. On the wire those are
+//! `BTreeMap<PsValue, PsValue>`s with `Str` keys; `PsDict` keeps the keys as
+//! `String`s and reads values through [`FromPsValue`], so callers never wrap
+//! or match keys by hand.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, btree_map};
+use std::hash::BuildHasher;
+
+use super::{
+    ComplexObject, ComplexObjectContent, Container, FromPsValue, Properties, PsPrimitiveValue,
+    PsType, PsValue, ToPsValue,
+};
+use crate::PowerShellRemotingError;
+
+/// A dictionary of string keys to values, ordered by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PsDict {
+    entries: BTreeMap<String, PsValue>,
+}
+
+impl PsDict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&PsValue> {
+        self.entries.get(key)
+    }
+
+    /// The entry at `key` as `T`; `None` when it is missing or of another type.
+    pub fn get_as<T: FromPsValue>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|value| T::from_ps_value(value).ok())
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(PsValue::Primitive(PsPrimitiveValue::Str(s))) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.get(key).and_then(PsValue::as_i32)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_as(key)
+    }
+
+    /// Set `key`, returning the value it replaced.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl ToPsValue) -> Option<PsValue> {
+        self.entries.insert(key.into(), value.to_ps_value())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<PsValue> {
+        self.entries.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, String, PsValue> {
+        self.entries.iter()
+    }
+
+    /// The dictionary as a plain `System.Collections.Hashtable`, for the
+    /// places (e.g. Prompt results) that do not accept a
+    /// `PSPrimitiveDictionary`.
+    pub fn to_hashtable(&self) -> PsValue {
+        self.to_dictionary_value(PsType {
+            type_names: vec![
+                Cow::Borrowed("System.Collections.Hashtable"),
+                Cow::Borrowed("System.Object"),
+            ],
+        })
+    }
+
+    fn to_dictionary_value(&self, type_def: PsType) -> PsValue {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(key, value)| (PsValue::from(key.as_str()), value.clone()))
+            .collect();
+        PsValue::Object(ComplexObject {
+            type_def: Some(type_def),
+            to_string: None,
+            content: ComplexObjectContent::Container(Container::Dictionary(entries)),
+            properties: Properties::new(),
+        })
+    }
+}
+
+impl ToPsValue for PsDict {
+    fn to_ps_value(&self) -> PsValue {
+        self.to_dictionary_value(PsType::ps_primitive_dictionary())
+    }
+}
+
+impl FromPsValue for PsDict {
+    const TYPE_LABEL: &'static str = "PSPrimitiveDictionary";
+
+    fn from_ps_value(value: &PsValue) -> Result<Self, PowerShellRemotingError> {
+        let dict = match value {
+            PsValue::Object(ComplexObject {
+                content: ComplexObjectContent::Container(Container::Dictionary(dict)),
+                ..
+            }) => dict,
+            other => {
+                return Err(PowerShellRemotingError::InvalidMessage(format!(
+                    "expected {}, got {other:?}",
+                    Self::TYPE_LABEL
+                )));
+            }
+        };
+        dict.iter()
+            .map(|(key, value)| match key {
+                PsValue::Primitive(PsPrimitiveValue::Str(key)) => Ok((key.clone(), value.clone())),
+                _ => Err(PowerShellRemotingError::InvalidMessage(
+                    "PSPrimitiveDictionary key is not a string".to_string(),
+                )),
+            })
+            .collect()
+    }
+}
+
+impl From<BTreeMap<String, PsValue>> for PsDict {
+    fn from(entries: BTreeMap<String, PsValue>) -> Self {
+        Self { entries }
+    }
+}
+
+impl<V: Into<PsValue>, S: BuildHasher> From<HashMap<String, V, S>> for PsDict {
+    fn from(map: HashMap<String, V, S>) -> Self {
+        map.into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect()
+    }
+}
+
+impl From<PsDict> for BTreeMap<String, PsValue> {
+    fn from(dict: PsDict) -> Self {
+        dict.entries
+    }
+}
+
+impl FromIterator<(String, PsValue)> for PsDict {
+    fn from_iter<I: IntoIterator<Item = (String, PsValue)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(String, PsValue)> for PsDict {
+    fn extend<I: IntoIterator<Item = (String, PsValue)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl IntoIterator for PsDict {
+    type Item = (String, PsValue);
+    type IntoIter = btree_map::IntoIter<String, PsValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PsDict {
+    type Item = (&'a String, &'a PsValue);
+    type IntoIter = btree_map::Iter<'a, String, PsValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,250 @@
+//! Path-based differences between two value trees.
+//!
+//! When a payload we serialize does not match one captured from PowerShell,
+//! `assert_eq!` on the two trees prints both in full and leaves finding the
+//! divergence to the reader. [`diff`] walks them together instead and reports
+//! each place they differ, e.g. `$.Args[1].Value: I32(1) != I64(1)`.
+//!
+//! Paths start at `$`. Properties are `.Name`, list items `[index]`,
+//! dictionary entries `[key]`; `.TypeNames`, `.ToString`, `.Content` and
+//! `.Value` name the object's own parts.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use super::{ComplexObject, ComplexObjectContent, Container, PropertyKind, PsValue};
+
+/// How the two sides differ at a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Both sides have something here, but not the same thing.
+    Changed { left: String, right: String },
+    /// Only the left side has something here.
+    OnlyLeft(String),
+    /// Only the right side has something here.
+    OnlyRight(String),
+}
+
+/// One difference found by [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+}
+
+impl Display for DiffEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DiffKind::Changed { left, right } => write!(f, "{}: {left} != {right}", self.path),
+            DiffKind::OnlyLeft(left) => write!(f, "{}: only on the left: {left}", self.path),
+            DiffKind::OnlyRight(right) => write!(f, "{}: only on the right: {right}", self.path),
+        }
+    }
+}
+
+/// Every place `left` and `right` differ, in tree order; empty when equal.
+pub fn diff(left: &PsValue, right: &PsValue) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    diff_value("$", left, right, &mut entries);
+    entries
+}
+
+fn changed(path: String, left: String, right: String, out: &mut Vec<DiffEntry>) {
+    out.push(DiffEntry {
+        path,
+        kind: DiffKind::Changed { left, right },
+    });
+}
+
+fn diff_value(path: &str, left: &PsValue, right: &PsValue, out: &mut Vec<DiffEntry>) {
+    match (left, right) {
+        _ if left == right => {}
+        (PsValue::Object(left), PsValue::Object(right)) => diff_object(path, left, right, out),
+        _ => changed(path.to_owned(), describe(left), describe(right), out),
+    }
+}
+
+fn diff_object(path: &str, left: &ComplexObject, right: &ComplexObject, out: &mut Vec<DiffEntry>) {
+    let type_names = |object: &ComplexObject| {
+        object.type_def.as_ref().map_or_else(
+            || "<none>".to_owned(),
+            |type_def| type_def.type_names.join(", "),
+        )
+    };
+    if left.type_def != right.type_def {
+        changed(
+            format!("{path}.TypeNames"),
+            type_names(left),
+            type_names(right),
+            out,
+        );
+    }
+
+    let to_string = |object: &ComplexObject| {
+        object
+            .to_string
+            .as_ref()
+            .map_or_else(|| "<none>".to_owned(), |s| format!("{s:?}"))
+    };
+    if left.to_string != right.to_string {
+        changed(
+            format!("{path}.ToString"),
+            to_string(left),
+            to_string(right),
+            out,
+        );
+    }
+
+    match (&left.content, &right.content) {
+        (
+            ComplexObjectContent::ExtendedPrimitive(l),
+            ComplexObjectContent::ExtendedPrimitive(r),
+        ) if l != r => {
+            changed(
+                format!("{path}.Value"),
+                format!("{l:?}"),
+                format!("{r:?}"),
+                out,
+            );
+        }
+        (ComplexObjectContent::PsEnums(l), ComplexObjectContent::PsEnums(r)) if l != r => {
+            changed(
+                format!("{path}.Value"),
+                l.value.to_string(),
+                r.value.to_string(),
+                out,
+            );
+        }
+        (ComplexObjectContent::Container(l), ComplexObjectContent::Container(r)) => {
+            diff_container(path, l, r, out);
+        }
+        (l, r) if content_kind(l) != content_kind(r) => changed(
+            format!("{path}.Content"),
+            content_kind(l).to_owned(),
+            content_kind(r).to_owned(),
+            out,
+        ),
+        _ => {}
+    }
+
+    let names: BTreeSet<&str> = left
+        .properties
+        .iter()
+        .chain(right.properties.iter())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    for name in names {
+        let path = format!("{path}.{name}");
+        match (
+            left.properties.get_property(name),
+            right.properties.get_property(name),
+        ) {
+            (Some(l), Some(r)) => {
+                if l.kind != r.kind {
+                    changed(
+                        format!("{path} (member set)"),
+                        kind_name(l.kind).to_owned(),
+                        kind_name(r.kind).to_owned(),
+                        out,
+                    );
+                }
+                diff_value(&path, &l.value, &r.value, out);
+            }
+            (Some(l), None) => out.push(DiffEntry {
+                path,
+                kind: DiffKind::OnlyLeft(describe(&l.value)),
+            }),
+            (None, Some(r)) => out.push(DiffEntry {
+                path,
+                kind: DiffKind::OnlyRight(describe(&r.value)),
+            }),
+            (None, None) => {}
+        }
+    }
+}
+
+fn diff_container(path: &str, left: &Container, right: &Container, out: &mut Vec<DiffEntry>) {
+    match (left, right) {
+        (Container::Stack(l), Container::Stack(r))
+        | (Container::Queue(l), Container::Queue(r))
+        | (Container::List(l), Container::List(r)) => {
+            for (index, (l, r)) in l.iter().zip(r).enumerate() {
+                diff_value(&format!("{path}[{index}]"), l, r, out);
+            }
+            for (index, l) in l.iter().enumerate().skip(r.len()) {
+                out.push(DiffEntry {
+                    path: format!("{path}[{index}]"),
+                    kind: DiffKind::OnlyLeft(describe(l)),
+                });
+            }
+            for (index, r) in r.iter().enumerate().skip(l.len()) {
+                out.push(DiffEntry {
+                    path: format!("{path}[{index}]"),
+                    kind: DiffKind::OnlyRight(describe(r)),
+                });
+            }
+        }
+        (Container::Dictionary(l), Container::Dictionary(r)) => {
+            let keys: BTreeSet<&PsValue> = l.keys().chain(r.keys()).collect();
+            for key in keys {
+                let path = format!("{path}[{}]", describe(key));
+                match (l.get(key), r.get(key)) {
+                    (Some(l), Some(r)) => diff_value(&path, l, r, out),
+                    (Some(l), None) => out.push(DiffEntry {
+                        path,
+                        kind: DiffKind::OnlyLeft(describe(l)),
+                    }),
+                    (None, Some(r)) => out.push(DiffEntry {
+                        path,
+                        kind: DiffKind::OnlyRight(describe(r)),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => changed(
+            format!("{path}.Content"),
+            container_kind(left).to_owned(),
+            container_kind(right).to_owned(),
+            out,
+        ),
+    }
+}
+
+/// A one-line rendering of `value`: primitives with their type, objects by
+/// their most specific type name.
+fn describe(value: &PsValue) -> String {
+    match value {
+        PsValue::Primitive(primitive) => format!("{primitive:?}"),
+        PsValue::Object(object) => object
+            .type_def
+            .as_ref()
+            .and_then(|type_def| type_def.type_names.first())
+            .map_or_else(|| "<Obj>".to_owned(), |name| format!("<Obj {name}>")),
+    }
+}
+
+const fn content_kind(content: &ComplexObjectContent) -> &'static str {
+    match content {
+        ComplexObjectContent::ExtendedPrimitive(_) => "extended primitive",
+        ComplexObjectContent::Container(container) => container_kind(container),
+        ComplexObjectContent::PsEnums(_) => "enum",
+        ComplexObjectContent::Standard => "standard",
+    }
+}
+
+const fn container_kind(container: &Container) -> &'static str {
+    match container {
+        Container::Stack(_) => "stack",
+        Container::Queue(_) => "queue",
+        Container::List(_) => "list",
+        Container::Dictionary(_) => "dictionary",
+    }
+}
+
+const fn kind_name(kind: PropertyKind) -> &'static str {
+    match kind {
+        PropertyKind::Adapted => "adapted",
+        PropertyKind::Extended => "extended",
+    }
+}
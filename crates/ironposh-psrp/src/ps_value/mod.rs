@@ -1,8 +1,12 @@
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod arbitrary;
 pub mod builder;
 pub mod complex;
 pub mod container;
 pub mod convert;
 pub mod deserialize;
+pub mod dict;
+pub mod diff;
 pub mod known_types;
 pub mod primitive;
 pub mod property;
@@ -15,6 +19,8 @@ pub use complex::*;
 pub use container::*;
 pub use convert::*;
 pub use deserialize::*;
+pub use dict::*;
+pub use diff::*;
 pub use known_types::*;
 pub use primitive::*;
 pub use property::*;
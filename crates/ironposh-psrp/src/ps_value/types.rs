@@ -58,12 +58,13 @@ impl PsType {
 
 impl PartialEq for PsType {
     fn eq(&self, other: &Self) -> bool {
-        for (ty1, ty2) in self.type_names.iter().zip(other.type_names.iter()) {
-            if ty1.as_ref() != ty2.as_ref() {
-                return false;
-            }
-        }
-        true
+        // Must agree with `Hash`: a type is not equal to a prefix of itself.
+        self.type_names.len() == other.type_names.len()
+            && self
+                .type_names
+                .iter()
+                .zip(other.type_names.iter())
+                .all(|(ty1, ty2)| ty1.as_ref() == ty2.as_ref())
     }
 }
 
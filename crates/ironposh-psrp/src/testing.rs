@@ -0,0 +1,196 @@
+//! CLIXML canonicalization for golden tests.
+//!
+//! PowerShell and this crate serialize the same object graph with different
+//! attribute order, indentation and RefId numbering. [`canonicalize_clixml`]
+//! rewrites a document into one compact form so those differences disappear:
+//!
+//! - attributes are sorted by name and namespace declarations dropped;
+//! - `Obj`/`Ref` RefIds and `TN`/`TNRef` RefIds are renumbered from 0 in
+//!   document order (each kind separately, as in CLIXML);
+//! - whitespace-only text between elements is dropped, while the text of leaf
+//!   elements (`<S>  </S>`) is kept as-is;
+//! - comments and processing instructions are dropped, empty elements are
+//!   written as `<Name/>`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use ironposh_xml::parser::{Node, NodeType};
+
+/// Rewrite `xml` into its canonical compact form (see the module docs).
+pub fn canonicalize_clixml(xml: &str) -> Result<String, ironposh_xml::XmlError> {
+    let document = ironposh_xml::parser::parse(xml)?;
+    let mut canonicalizer = Canonicalizer::default();
+    canonicalizer.element(document.root_element());
+    Ok(canonicalizer.out)
+}
+
+/// Assert that two CLIXML documents are equal once canonicalized, showing
+/// both canonical forms around the first difference on failure.
+#[track_caller]
+pub fn assert_clixml_eq(actual: &str, expected: &str) {
+    let actual = canonicalize_clixml(actual).expect("actual CLIXML must parse");
+    let expected = canonicalize_clixml(expected).expect("expected CLIXML must parse");
+    if actual == expected {
+        return;
+    }
+
+    let at = actual
+        .bytes()
+        .zip(expected.bytes())
+        .position(|(a, e)| a != e)
+        .unwrap_or_else(|| actual.len().min(expected.len()));
+    panic!(
+        "CLIXML differs at byte {at}\n  actual:   …{}\n  expected: …{}",
+        excerpt(&actual, at),
+        excerpt(&expected, at)
+    );
+}
+
+fn excerpt(s: &str, at: usize) -> &str {
+    const CONTEXT: usize = 80;
+    let mut start = at.saturating_sub(CONTEXT);
+    while !s.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (at + CONTEXT).min(s.len());
+    while !s.is_char_boundary(end) {
+        end += 1;
+    }
+    &s[start..end]
+}
+
+#[derive(Default)]
+struct Canonicalizer {
+    out: String,
+    /// RefIds of `Obj`, referenced by `Ref`.
+    objects: HashMap<String, usize>,
+    /// RefIds of `TN`, referenced by `TNRef`.
+    types: HashMap<String, usize>,
+}
+
+impl Canonicalizer {
+    fn element(&mut self, node: Node<'_, '_>) {
+        let name = node.tag_name().name();
+        self.out.push('<');
+        self.out.push_str(name);
+
+        let mut attributes: Vec<(&str, String)> = node
+            .attributes()
+            .map(|attr| {
+                (
+                    attr.name(),
+                    self.attribute_value(name, attr.name(), attr.value()),
+                )
+            })
+            .collect();
+        attributes.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in attributes {
+            let _ = write!(self.out, " {key}=\"{}\"", escape(&value, true));
+        }
+
+        let has_element_children = node.children().any(|child| child.is_element());
+        let children: Vec<Node<'_, '_>> = node
+            .children()
+            .filter(|child| match child.node_type() {
+                NodeType::Element => true,
+                NodeType::Text => {
+                    !has_element_children || !child.text().unwrap_or_default().trim().is_empty()
+                }
+                _ => false,
+            })
+            .collect();
+        if children.is_empty() {
+            self.out.push_str("/>");
+            return;
+        }
+
+        self.out.push('>');
+        for child in children {
+            if child.is_element() {
+                self.element(child);
+            } else {
+                self.out
+                    .push_str(&escape(child.text().unwrap_or_default(), false));
+            }
+        }
+        let _ = write!(self.out, "</{name}>");
+    }
+
+    fn attribute_value(&mut self, element: &str, attribute: &str, value: &str) -> String {
+        let ids = match (element, attribute) {
+            ("Obj" | "Ref", "RefId") => &mut self.objects,
+            ("TN" | "TNRef", "RefId") => &mut self.types,
+            _ => return value.to_owned(),
+        };
+        let next = ids.len();
+        ids.entry(value.to_owned()).or_insert(next).to_string()
+    }
+}
+
+fn escape(s: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_attribute_order_and_ref_ids_are_ignored() {
+        let captured = r#"<?xml version="1.0"?>
+<Objs Version="1.1.0.1" xmlns="http://schemas.microsoft.com/powershell/2004/04">
+  <Obj RefId="7">
+    <TN RefId="3">
+      <T>System.Object</T>
+    </TN>
+    <MS>
+      <S N="Name">  padded  </S>
+      <Obj N="Inner" RefId="9"><TNRef RefId="3" /></Obj>
+      <Ref N="Again" RefId="9" />
+      <Nil N="Empty"></Nil>
+    </MS>
+  </Obj>
+</Objs>"#;
+        let ours = concat!(
+            r#"<Objs Version="1.1.0.1"><Obj RefId="0"><TN RefId="0"><T>System.Object</T></TN>"#,
+            r#"<MS><S N="Name">  padded  </S><Obj RefId="1" N="Inner"><TNRef RefId="0"/></Obj>"#,
+            r#"<Ref RefId="1" N="Again"/><Nil N="Empty"/></MS></Obj></Objs>"#
+        );
+
+        assert_clixml_eq(ours, captured);
+        assert_eq!(
+            canonicalize_clixml(ours).unwrap(),
+            concat!(
+                r#"<Objs Version="1.1.0.1"><Obj RefId="0"><TN RefId="0"><T>System.Object</T></TN>"#,
+                r#"<MS><S N="Name">  padded  </S><Obj N="Inner" RefId="1"><TNRef RefId="0"/></Obj>"#,
+                r#"<Ref N="Again" RefId="1"/><Nil N="Empty"/></MS></Obj></Objs>"#
+            )
+        );
+    }
+
+    #[test]
+    fn object_and_type_ref_ids_are_numbered_separately() {
+        let xml = r#"<Obj RefId="5"><TN RefId="5"><T>A</T></TN><Obj RefId="6"><TNRef RefId="5"/></Obj></Obj>"#;
+        assert_eq!(
+            canonicalize_clixml(xml).unwrap(),
+            r#"<Obj RefId="0"><TN RefId="0"><T>A</T></TN><Obj RefId="1"><TNRef RefId="0"/></Obj></Obj>"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CLIXML differs")]
+    fn different_values_still_fail() {
+        assert_clixml_eq(r#"<S N="a">one</S>"#, r#"<S N="a">two</S>"#);
+    }
+}
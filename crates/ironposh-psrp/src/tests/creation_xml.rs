@@ -8,7 +8,7 @@ fn test_combined_messages_like_runspace_open() {
         protocol_version: "2.3".to_string(),
         ps_version: "2.0".to_string(),
         serialization_version: "1.1.0.1".to_string(),
-        time_zone: Some("UTC".to_string()),
+        time_zone: Some(crate::TimeZone::utc().to_bytes()),
     };
 
     let init_runspace_pool = InitRunspacePool {
@@ -3,7 +3,7 @@ mod error_record_integration_tests {
     use crate::ps_value::{
         ComplexObject, ComplexObjectContent, Properties, PsPrimitiveValue, PsValue,
     };
-    use crate::{ErrorCategory, ErrorRecord};
+    use crate::{ErrorCategory, ErrorCategoryInfo, ErrorRecord};
 
     /// Test based on the actual failing message from the logs:
     /// "Protocol error: Invalid PowerShell remoting message: Missing Message or ErrorRecord property"
@@ -69,6 +69,10 @@ mod error_record_integration_tests {
                     error_record.fully_qualified_error_id,
                     Some("CommandNotFoundException".to_string())
                 );
+                assert_eq!(
+                    error_record.script_stack_trace.as_deref(),
+                    Some("at <ScriptBlock>, <No file>: line 1")
+                );
                 assert!(!error_record.was_thrown_from_throw_statement);
                 assert!(!error_record.serialize_extended_info);
 
@@ -76,7 +80,7 @@ mod error_record_integration_tests {
                 let error_category = error_record
                     .error_category
                     .expect("Error category should be present");
-                assert_eq!(error_category.category, 13);
+                assert_eq!(error_category.category, ErrorCategory::ObjectNotFound);
                 assert_eq!(
                     error_category.reason,
                     Some("CommandNotFoundException".to_string())
@@ -178,8 +182,8 @@ mod error_record_integration_tests {
             .fully_qualified_error_id(Some("CommandNotFoundException".to_string()))
             .was_thrown_from_throw_statement(false)
             .serialize_extended_info(false)
-            .error_category(Some(ErrorCategory::builder()
-                .category(13)
+            .error_category(Some(ErrorCategoryInfo::builder()
+                .category(ErrorCategory::ObjectNotFound)
                 .activity(Some(String::new()))
                 .reason(Some("CommandNotFoundException".to_string()))
                 .target_name(Some("ed".to_string()))
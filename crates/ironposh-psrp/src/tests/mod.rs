@@ -7,4 +7,6 @@ pub mod command_completion_test;
 pub mod command_xml_tests;
 pub mod error_record_test;
 pub mod parse_real_pipeline_host_call;
+pub mod ps_value_diff_tests;
+pub mod ps_value_proptest;
 pub mod value_layer_tests;
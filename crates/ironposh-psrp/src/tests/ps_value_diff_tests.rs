@@ -0,0 +1,118 @@
+//! Tests for [`crate::ps_value::diff`].
+
+use std::collections::BTreeMap;
+
+use crate::ps_value::{
+    ComplexObject, ComplexObjectContent, Container, DiffEntry, DiffKind, PsPrimitiveValue, PsType,
+    PsValue, diff,
+};
+
+fn rendered(left: &PsValue, right: &PsValue) -> Vec<String> {
+    diff(left, right).iter().map(ToString::to_string).collect()
+}
+
+fn list(items: Vec<PsValue>) -> PsValue {
+    PsValue::Object(ComplexObject {
+        content: ComplexObjectContent::Container(Container::List(items)),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn equal_values_have_no_differences() {
+    let value = PsValue::Object(
+        ComplexObject::standard()
+            .extended("Name", "pwsh")
+            .adapted("Id", 4i32)
+            .build(),
+    );
+    assert!(diff(&value, &value.clone()).is_empty());
+}
+
+#[test]
+fn nested_differences_are_reported_by_path() {
+    let args = |second: PsValue| list(vec![PsValue::from("a"), second]);
+    let left = PsValue::Object(
+        ComplexObject::standard()
+            .extended("Args", args(PsValue::from(1i32)))
+            .extended("Only", "left")
+            .build(),
+    );
+    let right = PsValue::Object(
+        ComplexObject::standard()
+            .extended("Args", args(PsValue::from(1i64)))
+            .adapted("Extra", true)
+            .build(),
+    );
+
+    assert_eq!(
+        rendered(&left, &right),
+        [
+            "$.Args[1]: I32(1) != I64(1)",
+            "$.Extra: only on the right: Bool(true)",
+            "$.Only: only on the left: Str(\"left\")",
+        ]
+    );
+}
+
+#[test]
+fn object_parts_and_member_sets_are_compared() {
+    let object = |type_name: &'static str, to_string: &str| ComplexObject {
+        type_def: Some(PsType {
+            type_names: vec![type_name.into(), "System.Object".into()],
+        }),
+        to_string: Some(to_string.to_owned()),
+        ..Default::default()
+    };
+    let mut left = object("System.String", "x");
+    left.properties.insert_adapted("Length", 1i32);
+    let mut right = object("System.Char", "y");
+    right.properties.insert_extended("Length", 1i32);
+    right.content = ComplexObjectContent::ExtendedPrimitive(PsPrimitiveValue::Char('y'));
+
+    assert_eq!(
+        rendered(&PsValue::Object(left), &PsValue::Object(right)),
+        [
+            "$.TypeNames: System.String, System.Object != System.Char, System.Object",
+            "$.ToString: \"x\" != \"y\"",
+            "$.Content: standard != extended primitive",
+            "$.Length (member set): adapted != extended",
+        ]
+    );
+}
+
+#[test]
+fn list_length_and_dictionary_keys() {
+    let dictionary = |entries: &[(&str, i32)]| {
+        PsValue::Object(ComplexObject {
+            content: ComplexObjectContent::Container(Container::Dictionary(
+                entries
+                    .iter()
+                    .map(|(key, value)| (PsValue::from(*key), PsValue::from(*value)))
+                    .collect::<BTreeMap<_, _>>(),
+            )),
+            ..Default::default()
+        })
+    };
+    assert_eq!(
+        diff(
+            &list(vec![PsValue::from(1i32)]),
+            &list(vec![PsValue::from(1i32), PsValue::from(2i32)])
+        ),
+        [DiffEntry {
+            path: "$[1]".to_owned(),
+            kind: DiffKind::OnlyRight("I32(2)".to_owned()),
+        }]
+    );
+    assert_eq!(
+        rendered(
+            &dictionary(&[("a", 1), ("b", 2)]),
+            &dictionary(&[("a", 3), ("c", 4)])
+        ),
+        [
+            "$[Str(\"a\")]: I32(1) != I32(3)",
+            "$[Str(\"b\")]: only on the left: I32(2)",
+            "$[Str(\"c\")]: only on the right: I32(4)",
+        ]
+    );
+}
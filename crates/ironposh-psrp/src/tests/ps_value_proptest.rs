@@ -0,0 +1,76 @@
+//! Serialize→parse round trips of generated values (see
+//! [`crate::ps_value::arbitrary`]), including `<Ref>` and `<TNRef>` reuse.
+
+use std::borrow::Cow;
+
+use ironposh_xml::builder::Builder;
+use proptest::prelude::*;
+
+use crate::ps_value::{
+    ComplexObject, ComplexObjectContent, Container, PsType, PsValue,
+    deserialize::{DeserializationContext, PsXmlDeserialize},
+    diff,
+};
+
+fn to_clixml(value: &PsValue) -> String {
+    let element = value.to_element_as_root().expect("serialize");
+    Builder::new(None, element).to_xml_string().expect("render")
+}
+
+fn from_clixml(xml: &str) -> PsValue {
+    let doc = ironposh_xml::parser::parse(xml).expect("parse");
+    let mut context = DeserializationContext::new();
+    PsValue::from_node_with_context(doc.root_element(), &mut context).expect("deserialize")
+}
+
+fn list(items: Vec<PsValue>) -> PsValue {
+    PsValue::Object(ComplexObject {
+        content: ComplexObjectContent::Container(Container::List(items)),
+        ..Default::default()
+    })
+}
+
+proptest! {
+    #[test]
+    fn any_value_round_trips(value in any::<PsValue>()) {
+        let xml = to_clixml(&value);
+        let parsed = from_clixml(&xml);
+        let differences: Vec<String> = diff(&parsed, &value).iter().map(ToString::to_string).collect();
+        prop_assert!(parsed == value, "xml={}\n{}", xml, differences.join("\n"));
+    }
+
+    #[test]
+    fn repeated_object_is_written_once(shared in any::<ComplexObject>(), copies in 2usize..5) {
+        let value = list(vec![PsValue::Object(shared); copies]);
+
+        let xml = to_clixml(&value);
+        prop_assert!(xml.matches("<Ref ").count() >= copies - 1, "xml={}", xml);
+        prop_assert_eq!(from_clixml(&xml), value, "xml={}", xml);
+    }
+
+    #[test]
+    fn repeated_type_is_written_once(
+        type_names in prop::collection::vec("[A-Z][a-z]{1,8}(\\.[A-Z][a-z]{1,8}){0,2}", 1..4),
+        to_strings in prop::collection::btree_set("\\PC{0,12}", 2..5),
+    ) {
+        let type_def = PsType {
+            type_names: type_names.into_iter().map(Cow::Owned).collect(),
+        };
+        let objects: Vec<PsValue> = to_strings
+            .iter()
+            .map(|to_string| {
+                PsValue::Object(ComplexObject {
+                    type_def: Some(type_def.clone()),
+                    to_string: Some(to_string.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let value = list(objects);
+
+        let xml = to_clixml(&value);
+        prop_assert_eq!(xml.matches("<TN ").count(), 1, "xml={}", xml);
+        prop_assert_eq!(xml.matches("<TNRef ").count(), to_strings.len() - 1, "xml={}", xml);
+        prop_assert_eq!(from_clixml(&xml), value, "xml={}", xml);
+    }
+}
@@ -124,6 +124,68 @@ fn builder_container_content_and_types() {
     assert!(obj.type_def.is_some());
 }
 
+#[test]
+fn runspace_enums_convert_to_and_from_i32_and_enum_objects() {
+    use crate::{ApartmentState, PSThreadOptions};
+
+    assert_eq!(i32::from(ApartmentState::MTA), 1);
+    assert_eq!(
+        PSThreadOptions::try_from(2).unwrap(),
+        PSThreadOptions::ReuseThread
+    );
+    assert!(ApartmentState::try_from(7).is_err());
+
+    let obj = ComplexObject::from(ApartmentState::default());
+    assert_eq!(obj.to_string.as_deref(), Some("Unknown"));
+    assert_eq!(
+        ApartmentState::try_from(obj).unwrap(),
+        ApartmentState::Unknown
+    );
+    assert!(PSThreadOptions::try_from(ComplexObject::standard().build()).is_err());
+}
+
+#[test]
+fn ps_dict_typed_accessors_and_roundtrip() {
+    use crate::ps_value::{FromPsValue, PsDict, ToPsValue};
+
+    let mut dict = PsDict::from(std::collections::HashMap::from([(
+        "Tenant".to_string(),
+        "contoso",
+    )]));
+    dict.insert("Retries", 3i32);
+    dict.insert("Verbose", true);
+
+    assert_eq!(dict.get_str("Tenant"), Some("contoso"));
+    assert_eq!(dict.get_i32("Retries"), Some(3));
+    assert_eq!(dict.get_bool("Verbose"), Some(true));
+    assert_eq!(dict.get_i32("Tenant"), None, "wrong type is not coerced");
+    assert_eq!(dict.get_str("Missing"), None);
+
+    let value = dict.to_ps_value();
+    let PsValue::Object(obj) = &value else {
+        panic!("expected an object, got {value:?}");
+    };
+    assert_eq!(
+        obj.type_def.as_ref().unwrap().type_names[0],
+        "System.Management.Automation.PSPrimitiveDictionary"
+    );
+    assert_eq!(PsDict::from_ps_value(&value).unwrap(), dict);
+}
+
+#[test]
+fn ps_dict_rejects_non_string_keys() {
+    use crate::ps_value::{FromPsValue, PsDict};
+
+    let value = PsValue::Object(ComplexObject {
+        content: ComplexObjectContent::Container(Container::Dictionary(
+            [(PsValue::from(1i32), PsValue::from("one"))].into(),
+        )),
+        ..Default::default()
+    });
+    let err = PsDict::from_ps_value(&value).unwrap_err().to_string();
+    assert!(err.contains("not a string"), "{err}");
+}
+
 // ---------------------------------------------------------------------------
 // RFC #12 L3 derive: nesting of sub-objects (a derive with no message_type
 // still generates the ComplexObject conversions and the ToPsValue/FromPsValue
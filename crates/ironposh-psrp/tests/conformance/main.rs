@@ -0,0 +1,191 @@
+//! PSRP conformance suite.
+//!
+//! Replays server fragment streams, captured or synthetic (see
+//! `transcripts/README.md`), through the [`Defragmenter`] and the typed
+//! [`PsrpMessage`] parser, asserting that every transcript decodes without
+//! error and leaves no dangling fragments, and compares a textual summary of
+//! the decoded messages against the sibling `.snap` file.
+//!
+//! Set `IRONPOSH_UPDATE_SNAPSHOTS=1` to (re)write the snapshots.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter};
+use ironposh_psrp::messages::PsrpMessage;
+
+const UPDATE_ENV: &str = "IRONPOSH_UPDATE_SNAPSHOTS";
+
+fn transcripts_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/transcripts")
+}
+
+/// All `*.b64` transcripts, sorted so failures are reported in a stable order.
+fn collect_transcripts() -> Vec<PathBuf> {
+    let mut transcripts = Vec::new();
+    for group in fs::read_dir(transcripts_root()).expect("transcripts directory") {
+        let group = group.expect("transcript directory entry").path();
+        if !group.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&group).expect("transcript directory") {
+            let path = entry.expect("transcript entry").path();
+            if path.extension().is_some_and(|ext| ext == "b64") {
+                transcripts.push(path);
+            }
+        }
+    }
+    transcripts.sort();
+    transcripts
+}
+
+fn decode_payloads(transcript: &str) -> Vec<Vec<u8>> {
+    transcript
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            base64::engine::general_purpose::STANDARD
+                .decode(line)
+                .expect("transcript line must be valid base64")
+        })
+        .collect()
+}
+
+fn describe(message: &PsrpMessage) -> Option<String> {
+    match message {
+        PsrpMessage::RunspacePoolState(state) => {
+            Some(format!("RunspaceState={:?}", state.runspace_state))
+        }
+        PsrpMessage::PipelineState(state) => {
+            Some(format!("PipelineState={:?}", state.pipeline_state))
+        }
+        PsrpMessage::PipelineOutput(output) => Some(format!("Output={}", clip(&output.data))),
+        PsrpMessage::ErrorRecord(record) => {
+            Some(format!("ErrorRecord={}", record.render_concise()))
+        }
+        PsrpMessage::ProgressRecord(record) => Some(format!(
+            "Progress={} {}% {:?}",
+            record.activity, record.percent_complete, record.progress_type
+        )),
+        PsrpMessage::PipelineHostCall(call) => Some(format!(
+            "HostCall={:?} ci={} params={}",
+            call.method,
+            call.call_id,
+            call.parameters.len()
+        )),
+        PsrpMessage::WarningRecord(value) => Some(format!("Warning={}", clip(value))),
+        PsrpMessage::VerboseRecord(value) => Some(format!("Verbose={}", clip(value))),
+        PsrpMessage::Other { value, .. } => Some(format!("Value={value}")),
+        _ => None,
+    }
+}
+
+/// Long values (e.g. a payload split over several fragments) are summarized
+/// by their length so snapshots stay readable.
+fn clip(value: &impl std::fmt::Display) -> String {
+    const MAX: usize = 80;
+    let text = value.to_string();
+    if text.chars().count() <= MAX {
+        return text;
+    }
+    let head: String = text.chars().take(MAX).collect();
+    format!("{head}... ({} chars)", text.chars().count())
+}
+
+/// Replay one transcript, returning the rendered snapshot or a failure reason.
+fn replay(path: &Path) -> Result<String, String> {
+    let transcript = fs::read_to_string(path).map_err(|e| format!("read failed: {e}"))?;
+    let mut defragmenter = Defragmenter::new();
+    let mut snapshot = String::new();
+    let mut index = 0;
+
+    for (line, payload) in decode_payloads(&transcript).iter().enumerate() {
+        let messages = match defragmenter.defragment(payload) {
+            Ok(DefragmentResult::Complete(messages)) => messages,
+            Ok(DefragmentResult::Incomplete) => continue,
+            Err(e) => return Err(format!("payload {line}: defragment failed: {e}")),
+        };
+
+        for message in messages {
+            let parsed = PsrpMessage::parse(&message)
+                .map_err(|e| format!("message {index}: parse failed: {e}"))?;
+            if parsed.message_type() != message.message_type {
+                return Err(format!(
+                    "message {index}: typed message reports {:?}, wire says {:?}",
+                    parsed.message_type(),
+                    message.message_type
+                ));
+            }
+
+            let pid = message
+                .pid
+                .map_or_else(|| "-".to_string(), |pid| pid.to_string());
+            let _ = writeln!(
+                snapshot,
+                "[{index}] {:?} destination={:?} rpid={} pid={pid}",
+                message.message_type, message.destination, message.rpid
+            );
+            if let Some(detail) = describe(&parsed) {
+                let _ = writeln!(snapshot, "    {detail}");
+            }
+            index += 1;
+        }
+    }
+
+    if defragmenter.pending_count() != 0 {
+        return Err(format!(
+            "{} incomplete fragment buffer(s) left after the last payload",
+            defragmenter.pending_count()
+        ));
+    }
+    Ok(snapshot)
+}
+
+#[test]
+fn replay_captured_transcripts() {
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    let transcripts = collect_transcripts();
+    assert!(!transcripts.is_empty(), "no conformance transcripts found");
+
+    let mut failures = Vec::new();
+    for path in &transcripts {
+        let name = path
+            .strip_prefix(transcripts_root())
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let actual = match replay(path) {
+            Ok(actual) => actual,
+            Err(reason) => {
+                failures.push(format!("{name}: {reason}"));
+                continue;
+            }
+        };
+
+        let snap_path = path.with_extension("snap");
+        if update {
+            fs::write(&snap_path, &actual).expect("write snapshot");
+            continue;
+        }
+        match fs::read_to_string(&snap_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{name}: snapshot mismatch\n--- expected\n{expected}--- actual\n{actual}"
+            )),
+            Err(_) => failures.push(format!(
+                "{name}: missing snapshot, rerun with {UPDATE_ENV}=1\n{actual}"
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} transcripts failed:\n{}",
+        failures.len(),
+        transcripts.len(),
+        failures.join("\n")
+    );
+}
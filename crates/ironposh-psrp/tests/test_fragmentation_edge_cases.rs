@@ -4,7 +4,10 @@
 //! and unexpected fragment data gracefully.
 
 use byteorder::{BigEndian, WriteBytesExt};
-use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter};
+use ironposh_psrp::PowerShellRemotingError;
+use ironposh_psrp::fragmentation::{
+    DefragmentLimits, DefragmentResult, Defragmenter, Fragment, FragmentRef,
+};
 
 /// Create a minimal valid fragment header + data
 fn create_fragment(
@@ -381,6 +384,247 @@ mod tests {
         println!("Multiple messages in one packet: {result:?}");
     }
 
+    // =========================================================================
+    // SEQUENCE ENFORCEMENT
+    // =========================================================================
+
+    /// Test: A continuation fragment for an unknown object is rejected, not buffered
+    #[test]
+    fn test_orphan_fragment_is_rejected() {
+        let mut defrag = Defragmenter::new();
+
+        let orphan = create_fragment(7, 3, false, false, b"ORPHAN");
+        let result = defrag.defragment(&orphan);
+
+        assert!(
+            result.is_err(),
+            "Orphan fragment should fail, got: {result:?}"
+        );
+        assert_eq!(
+            defrag.pending_count(),
+            0,
+            "Orphan must not allocate a buffer"
+        );
+    }
+
+    /// Test: A gap in fragment ids fails and drops the partial message
+    #[test]
+    fn test_fragment_gap_drops_buffer() {
+        let mut defrag = Defragmenter::new();
+
+        let _ = defrag.defragment(&create_fragment(1, 0, true, false, b"START"));
+        assert_eq!(defrag.pending_count(), 1);
+
+        let result = defrag.defragment(&create_fragment(1, 2, false, true, b"END"));
+
+        assert!(result.is_err(), "Gap should fail, got: {result:?}");
+        assert_eq!(
+            defrag.pending_count(),
+            0,
+            "Partial message should be dropped"
+        );
+    }
+
+    /// Test: A blob length of u32::MAX is a typed error, not an overflow
+    #[test]
+    fn test_max_blob_length_is_rejected() {
+        let mut buffer = Vec::new();
+        buffer.write_u64::<BigEndian>(1).unwrap();
+        buffer.write_u64::<BigEndian>(0).unwrap();
+        buffer.push(0x03);
+        buffer.write_u32::<BigEndian>(u32::MAX).unwrap();
+
+        let result = Defragmenter::new().defragment(&buffer);
+        assert!(
+            result.is_err(),
+            "Oversized length should fail, got: {result:?}"
+        );
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert!(ironposh_psrp::PowerShellFragment::parse(&mut cursor).is_err());
+    }
+
+    // =========================================================================
+    // RESOURCE LIMITS
+    // =========================================================================
+
+    /// Test: A fragment larger than the configured blob limit is rejected
+    #[test]
+    fn test_fragment_blob_limit() {
+        let mut defrag = Defragmenter::with_limits(DefragmentLimits {
+            max_fragment_blob_size: 4,
+            ..DefragmentLimits::default()
+        });
+
+        let result = defrag.defragment(&create_fragment(1, 0, true, false, b"TOO_BIG"));
+
+        assert!(matches!(
+            result,
+            Err(PowerShellRemotingError::ResourceLimitExceeded { limit: 4, .. })
+        ));
+    }
+
+    /// Test: A message that never ends cannot grow the buffer past its limit
+    #[test]
+    fn test_buffered_bytes_limit() {
+        let mut defrag = Defragmenter::with_limits(DefragmentLimits {
+            max_buffered_bytes: 8,
+            ..DefragmentLimits::default()
+        });
+
+        assert!(
+            defrag
+                .defragment(&create_fragment(1, 0, true, false, b"1234"))
+                .is_ok()
+        );
+        assert!(
+            defrag
+                .defragment(&create_fragment(1, 1, false, false, b"5678"))
+                .is_ok()
+        );
+        assert_eq!(defrag.buffered_bytes(), 8);
+
+        let result = defrag.defragment(&create_fragment(1, 2, false, false, b"9"));
+        assert!(matches!(
+            result,
+            Err(PowerShellRemotingError::ResourceLimitExceeded { limit: 8, .. })
+        ));
+        assert_eq!(
+            defrag.pending_count(),
+            0,
+            "Offending message should be dropped"
+        );
+    }
+
+    // =========================================================================
+    // BORROWED FRAGMENTS
+    // =========================================================================
+
+    /// Test: FragmentRef borrows its payload and agrees with Fragment::unpack
+    #[test]
+    fn test_fragment_ref_borrows_payload() {
+        let mut packet = create_fragment(7, 3, false, true, b"PAYLOAD");
+        packet.extend_from_slice(b"REST");
+
+        let (fragment, rest) = FragmentRef::unpack(&packet).unwrap();
+        assert_eq!(fragment.data, b"PAYLOAD");
+        assert_eq!(rest, b"REST");
+        assert!(std::ptr::eq(fragment.data.as_ptr(), packet[21..].as_ptr()));
+
+        let (owned, _) = Fragment::unpack(&packet).unwrap();
+        let copied = fragment.into_fragment();
+        assert_eq!(
+            (
+                copied.object_id,
+                copied.fragment_id,
+                copied.start,
+                copied.end
+            ),
+            (owned.object_id, owned.fragment_id, owned.start, owned.end)
+        );
+        assert_eq!(copied.data, owned.data);
+    }
+
+    // =========================================================================
+    // DIAGNOSTICS
+    // =========================================================================
+
+    fn valid_message(message_type: ironposh_psrp::MessageType, xml: &str) -> Vec<u8> {
+        ironposh_psrp::PowerShellRemotingMessage {
+            destination: ironposh_psrp::Destination::Client,
+            message_type,
+            rpid: uuid::Uuid::nil(),
+            pid: None,
+            data: xml.as_bytes().to_vec(),
+        }
+        .pack()
+    }
+
+    /// Test: A message that fails to decode names its fragments, the message
+    /// before it, and dumps its bytes
+    #[test]
+    fn test_decode_failure_carries_diagnostics() {
+        let mut defrag = Defragmenter::new();
+        let state = valid_message(
+            ironposh_psrp::MessageType::RunspacepoolState,
+            "<Obj RefId=\"0\"/>",
+        );
+        assert!(matches!(
+            defrag.defragment(&create_fragment(1, 0, true, true, &state)),
+            Ok(DefragmentResult::Complete(_))
+        ));
+
+        let _ = defrag.defragment(&create_fragment(2, 0, true, false, b"NOT_A_"));
+        let err = defrag
+            .defragment(&create_fragment(2, 1, false, true, b"PSRP_MESSAGE"))
+            .unwrap_err();
+
+        let diagnostics = err.diagnostics().expect("diagnosed");
+        assert_eq!(diagnostics.origin.object_id, 2);
+        assert_eq!(diagnostics.origin.fragment_ids, 0..=1);
+        assert_eq!(
+            diagnostics.origin.previous_message_type,
+            Some(ironposh_psrp::MessageType::RunspacepoolState)
+        );
+        assert_eq!(diagnostics.len, 18);
+        assert_eq!(diagnostics.blob, b"NOT_A_PSRP_MESSAGE");
+
+        let text = err.to_string();
+        assert!(
+            text.contains("object 2, fragment 0-1, after RunspacepoolState, 18 bytes"),
+            "{text}"
+        );
+        assert!(text.contains("|NOT_A_PSRP_MESSA|"), "{text}");
+    }
+
+    /// Test: A rejected fragment is kept whole, though its dump is capped
+    #[test]
+    fn test_rejected_fragment_keeps_the_whole_blob() {
+        let mut defrag = Defragmenter::new();
+
+        let payload = vec![0xab; 1000];
+        let err = defrag
+            .defragment(&create_fragment(9, 4, false, true, &payload))
+            .unwrap_err();
+
+        let diagnostics = err.diagnostics().expect("diagnosed");
+        assert_eq!(diagnostics.origin.fragment_ids, 4..=4);
+        assert_eq!(diagnostics.origin.previous_message_type, None);
+        assert!(
+            diagnostics.hexdump.ends_with("... 744 more bytes"),
+            "dump is capped: {}",
+            diagnostics.hexdump
+        );
+        assert_eq!(diagnostics.blob, payload);
+    }
+
+    /// Test: decode_error() attaches the origin of a message from the last batch
+    #[test]
+    fn test_decode_error_names_the_message_origin() {
+        let mut defrag = Defragmenter::new();
+        let bad_xml = valid_message(ironposh_psrp::MessageType::PipelineOutput, "<Obj");
+        let Ok(DefragmentResult::Complete(messages)) =
+            defrag.defragment(&create_fragment(5, 0, true, true, &bad_xml))
+        else {
+            panic!("message should reassemble");
+        };
+
+        let error = messages[0].parse_ps_message().unwrap_err();
+        let err = defrag.decode_error(0, &messages[0], error);
+        let diagnostics = err.diagnostics().expect("diagnosed");
+        assert_eq!(diagnostics.origin.object_id, 5);
+        assert_eq!(diagnostics.len, bad_xml.len());
+
+        let error = messages[0].parse_ps_message().unwrap_err();
+        assert!(
+            defrag
+                .decode_error(1, &messages[0], error)
+                .diagnostics()
+                .is_none(),
+            "no such message in the last batch"
+        );
+    }
+
     // =========================================================================
     // BUFFER MANAGEMENT
     // =========================================================================
@@ -0,0 +1,211 @@
+//! Property tests for the PSRP fragmentation layer.
+//!
+//! Random message sizes, envelope limits and fragment orderings are run
+//! through `Fragmenter` and `Defragmenter`: intact fragments must reassemble
+//! to the message that was sent, and dropped, duplicated, reordered or
+//! truncated ones must be rejected rather than reassembled into garbage.
+
+use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter, Fragmenter};
+use ironposh_psrp::ps_value::{PsPrimitiveValue, PsValue};
+use ironposh_psrp::{MessageType, PowerShellRemotingMessage, PsObjectWithType};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+/// Fragment header: object id, fragment id, flags and blob length.
+const HEADER_LEN: usize = 21;
+
+/// A message carrying a single string, so its size is easy to steer.
+struct Payload(String);
+
+impl PsObjectWithType for Payload {
+    fn message_type(&self) -> MessageType {
+        MessageType::PipelineInput
+    }
+
+    fn to_ps_object(&self) -> PsValue {
+        PsValue::Primitive(PsPrimitiveValue::Str(self.0.clone()))
+    }
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[ -~]{0,3000}"
+}
+
+fn encode(text: &str) -> PowerShellRemotingMessage {
+    PowerShellRemotingMessage::from_ps_message(&Payload(text.to_owned()), Uuid::nil(), None)
+        .unwrap()
+}
+
+fn fragment(text: &str, max_envelope: usize) -> Vec<Vec<u8>> {
+    Fragmenter::new(max_envelope)
+        .fragment(&Payload(text.to_owned()), Uuid::nil(), None, None)
+        .unwrap()
+}
+
+/// Feed `packets` in order, collecting every completed message and stopping
+/// at the first error.
+fn reassemble(
+    defragmenter: &mut Defragmenter,
+    packets: &[Vec<u8>],
+) -> Result<Vec<PowerShellRemotingMessage>, ironposh_psrp::PowerShellRemotingError> {
+    let mut messages = Vec::new();
+    for packet in packets {
+        if let DefragmentResult::Complete(complete) = defragmenter.defragment(packet)? {
+            messages.extend(complete);
+        }
+    }
+    Ok(messages)
+}
+
+fn decoded_text(message: &PowerShellRemotingMessage) -> PsValue {
+    message.parse_ps_message().unwrap()
+}
+
+proptest! {
+    #[test]
+    fn single_message_round_trips(text in text(), max_envelope in HEADER_LEN + 1..2048) {
+        let fragments = fragment(&text, max_envelope);
+        prop_assert!(fragments.iter().all(|f| f.len() <= max_envelope));
+
+        let mut defragmenter = Defragmenter::new();
+        let messages = reassemble(&mut defragmenter, &fragments).unwrap();
+
+        prop_assert_eq!(messages.len(), 1);
+        prop_assert_eq!(&messages[0].data, &encode(&text).data);
+        prop_assert_eq!(decoded_text(&messages[0]), PsValue::from(text));
+        prop_assert_eq!(defragmenter.pending_count(), 0);
+    }
+
+    #[test]
+    fn grouped_messages_round_trip(
+        texts in prop::collection::vec(text(), 1..6),
+        max_envelope in 64usize..2048,
+    ) {
+        let payloads: Vec<Payload> = texts.iter().cloned().map(Payload).collect();
+        let objects: Vec<&dyn PsObjectWithType> =
+            payloads.iter().map(|p| p as &dyn PsObjectWithType).collect();
+        let groups = Fragmenter::new(max_envelope)
+            .fragment_multiple(&objects, Uuid::nil(), None)
+            .unwrap();
+        prop_assert!(groups.iter().all(|g| g.len() <= max_envelope));
+
+        let messages = reassemble(&mut Defragmenter::new(), &groups).unwrap();
+
+        let data: Vec<_> = messages.into_iter().map(|m| m.data).collect();
+        let expected: Vec<_> = texts.iter().map(|t| encode(t).data).collect();
+        prop_assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn interleaved_objects_reassemble(
+        first in text(),
+        second in text(),
+        max_envelope in HEADER_LEN + 1..512,
+        picks in prop::collection::vec(any::<bool>(), 0..4096),
+        per_packet in 1usize..8,
+    ) {
+        let mut fragmenter = Fragmenter::new(max_envelope);
+        let mut a = fragmenter
+            .fragment(&Payload(first.clone()), Uuid::nil(), None, None)
+            .unwrap()
+            .into_iter()
+            .peekable();
+        let mut b = fragmenter
+            .fragment(&Payload(second.clone()), Uuid::nil(), None, None)
+            .unwrap()
+            .into_iter()
+            .peekable();
+
+        // Merge the two streams, keeping each one's own order.
+        let mut picks = picks.into_iter();
+        let mut merged = Vec::new();
+        while a.peek().is_some() || b.peek().is_some() {
+            let take_a = b.peek().is_none() || (a.peek().is_some() && picks.next().unwrap_or(true));
+            merged.extend(if take_a { a.next() } else { b.next() });
+        }
+        let packets: Vec<Vec<u8>> = merged.chunks(per_packet).map(<[_]>::concat).collect();
+
+        let mut data: Vec<_> = reassemble(&mut Defragmenter::new(), &packets)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.data)
+            .collect();
+        let mut expected = vec![encode(&first).data, encode(&second).data];
+        data.sort();
+        expected.sort();
+        prop_assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn reordered_fragments_are_rejected(
+        text in "[ -~]{200,2000}",
+        max_envelope in HEADER_LEN + 1..128,
+        i in any::<prop::sample::Index>(),
+        j in any::<prop::sample::Index>(),
+    ) {
+        let mut fragments = fragment(&text, max_envelope);
+        let (i, j) = (i.index(fragments.len()), j.index(fragments.len()));
+        prop_assume!(i != j);
+        fragments.swap(i, j);
+
+        prop_assert!(reassemble(&mut Defragmenter::new(), &fragments).is_err());
+    }
+
+    #[test]
+    fn dropped_fragment_never_completes(
+        text in "[ -~]{200,2000}",
+        max_envelope in HEADER_LEN + 1..128,
+        dropped in any::<prop::sample::Index>(),
+    ) {
+        let mut fragments = fragment(&text, max_envelope);
+        let dropped = dropped.index(fragments.len());
+        let is_last = dropped == fragments.len() - 1;
+        fragments.remove(dropped);
+
+        let mut defragmenter = Defragmenter::new();
+        match reassemble(&mut defragmenter, &fragments) {
+            // Without its end fragment the message just stays pending.
+            Ok(messages) => {
+                prop_assert!(is_last);
+                prop_assert!(messages.is_empty());
+                prop_assert_eq!(defragmenter.pending_count(), 1);
+            }
+            Err(_) => prop_assert!(!is_last),
+        }
+    }
+
+    #[test]
+    fn duplicated_continuation_is_rejected(
+        text in "[ -~]{200,2000}",
+        max_envelope in HEADER_LEN + 1..128,
+        duplicated in any::<prop::sample::Index>(),
+    ) {
+        let mut fragments = fragment(&text, max_envelope);
+        // A repeated start fragment legitimately restarts the message.
+        let duplicated = 1 + duplicated.index(fragments.len() - 1);
+        fragments.insert(duplicated, fragments[duplicated].clone());
+
+        prop_assert!(reassemble(&mut Defragmenter::new(), &fragments).is_err());
+    }
+
+    #[test]
+    fn truncated_fragment_is_rejected(
+        text in text(),
+        max_envelope in HEADER_LEN + 1..2048,
+        truncated in any::<prop::sample::Index>(),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let mut fragments = fragment(&text, max_envelope);
+        let truncated = truncated.index(fragments.len());
+        let cut = 1 + cut.index(fragments[truncated].len() - 1);
+        fragments.truncate(truncated + 1);
+        fragments[truncated].truncate(cut);
+
+        prop_assert!(reassemble(&mut Defragmenter::new(), &fragments).is_err());
+    }
+
+    #[test]
+    fn arbitrary_bytes_do_not_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Defragmenter::new().defragment(&bytes);
+    }
+}
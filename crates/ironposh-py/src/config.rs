@@ -0,0 +1,137 @@
+//! The keyword arguments of `Session.connect`, mapped onto [`WinRmConfig`].
+
+use anyhow::Context;
+use ironposh::WinRmConfig;
+use ironposh::auth::{
+    AuthenticatorConfig, ClientAuthIdentity, ClientUserName, Delegation, KerberosConfig,
+    SspiAuthConfig,
+};
+use ironposh::config::{
+    HostDefaultData, HostInfo, HostMode, ParseMode, ReceiveFlowConfig, ServerAddress, TlsOptions,
+    TransportSecurity,
+};
+
+/// How the session authenticates; the `auth=` argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auth {
+    Basic,
+    Ntlm,
+    Kerberos,
+    Negotiate,
+}
+
+impl Auth {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        Ok(match value.to_ascii_lowercase().as_str() {
+            "basic" => Self::Basic,
+            "ntlm" => Self::Ntlm,
+            "kerberos" => Self::Kerberos,
+            "negotiate" => Self::Negotiate,
+            _ => {
+                anyhow::bail!("unknown auth {value:?}; expected basic, ntlm, kerberos or negotiate")
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    pub server: String,
+    /// Defaults to 5985, or 5986 with `https`.
+    pub port: Option<u16>,
+    pub https: bool,
+    /// Accept any server certificate. Lab use only.
+    pub accept_invalid_certs: bool,
+    pub auth: Auth,
+    pub username: String,
+    pub password: String,
+    pub domain: Option<String>,
+    /// JEA endpoint; `None` is `Microsoft.PowerShell`.
+    pub configuration_name: Option<String>,
+    pub operation_timeout_secs: Option<f64>,
+    /// Most Receive requests in flight at once; `None` is unlimited.
+    pub max_concurrent_receives: Option<usize>,
+    /// Per-pipeline budget of received output not yet acknowledged.
+    pub max_pending_output_bytes: Option<usize>,
+}
+
+impl ConnectOptions {
+    /// Scripts run unattended, so the server is offered a null host: a
+    /// `Read-Host` fails instead of waiting for an answer that never comes.
+    pub fn into_winrm_config(self) -> anyhow::Result<WinRmConfig> {
+        let server = ServerAddress::parse(&self.server)
+            .with_context(|| format!("invalid server address {}", self.server))?;
+        let port = self.port.unwrap_or(if self.https { 5986 } else { 5985 });
+
+        let authentication = if self.auth == Auth::Basic {
+            AuthenticatorConfig::Basic {
+                username: self.username,
+                password: self.password,
+            }
+        } else {
+            let user_name = ClientUserName::new(&self.username, self.domain.as_deref())?;
+            let identity = ClientAuthIdentity::new(user_name, self.password);
+            let target = self.server.clone();
+            let kerberos_config = KerberosConfig {
+                kdc: None,
+                client_computer_name: self.server.clone(),
+                delegation: Delegation::None,
+            };
+            AuthenticatorConfig::Sspi(match self.auth {
+                Auth::Ntlm => SspiAuthConfig::NTLM { target, identity },
+                Auth::Kerberos => SspiAuthConfig::Kerberos {
+                    target,
+                    identity,
+                    kerberos_config,
+                },
+                Auth::Negotiate | Auth::Basic => SspiAuthConfig::Negotiate {
+                    target,
+                    identity,
+                    kerberos_config: Some(kerberos_config),
+                },
+            })
+        };
+
+        // Basic has no SSPI context to seal plain HTTP with.
+        let transport = match (self.https, &authentication) {
+            (true, _) => TransportSecurity::Https,
+            (false, AuthenticatorConfig::Basic { .. }) => TransportSecurity::HttpInsecure,
+            (false, _) => TransportSecurity::Http,
+        };
+
+        Ok(WinRmConfig::builder()
+            .server((server, port))
+            .transport(transport)
+            .authentication(authentication)
+            .host_info(
+                HostInfo::builder()
+                    .host_default_data(HostDefaultData::default())
+                    .build(),
+            )
+            .operation_timeout_secs(self.operation_timeout_secs)
+            .tls(TlsOptions {
+                accept_invalid_certs: self.accept_invalid_certs,
+                ..TlsOptions::default()
+            })
+            .configuration_name(self.configuration_name)
+            .receive_flow(ReceiveFlowConfig {
+                max_concurrent_receives: self.max_concurrent_receives,
+                max_pending_output_bytes: self.max_pending_output_bytes,
+            })
+            .host_mode(HostMode::Headless)
+            .parse_mode(ParseMode::Strict)
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_names_are_case_insensitive() {
+        assert_eq!(Auth::parse("Kerberos").unwrap(), Auth::Kerberos);
+        assert_eq!(Auth::parse("NTLM").unwrap(), Auth::Ntlm);
+        assert!(Auth::parse("digest").is_err());
+    }
+}
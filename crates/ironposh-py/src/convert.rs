@@ -0,0 +1,296 @@
+//! [`PsValue`] to Python objects.
+//!
+//! Primitives become their Python counterparts (`Double` a `float`, `Decimal`
+//! a `decimal.Decimal`, `Guid` a `uuid.UUID`; `DateTime` and `TimeSpan` keep
+//! their wire text). Lists, stacks and queues become `list`, hashtables
+//! `dict`, enums their name, and everything else a [`PsObject`].
+
+use pyo3::exceptions::PyAttributeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use ironposh::value::{ComplexObject, ComplexObjectContent, Container, PsPrimitiveValue, PsValue};
+
+/// A deserialized PowerShell object: its type names, its properties and the
+/// server's `ToString()` of it. Properties are readable as attributes.
+#[pyclass(module = "ironposh_py", name = "PSObject", frozen)]
+pub struct PsObject {
+    #[pyo3(get)]
+    type_names: Vec<String>,
+    #[pyo3(get)]
+    properties: Py<PyDict>,
+    to_string: Option<String>,
+}
+
+#[pymethods]
+impl PsObject {
+    fn __getattr__<'py>(&self, py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.properties
+            .bind(py)
+            .get_item(name)?
+            .ok_or_else(|| PyAttributeError::new_err(name.to_owned()))
+    }
+
+    fn __str__(&self) -> String {
+        self.to_string.clone().unwrap_or_default()
+    }
+
+    fn __repr__(&self) -> String {
+        let type_name = self.type_names.first().map_or("PSObject", String::as_str);
+        format!("<{type_name} {:?}>", self.__str__())
+    }
+}
+
+pub fn ps_value_to_py<'py>(py: Python<'py>, value: &PsValue) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        PsValue::Primitive(primitive) => primitive_to_py(py, primitive),
+        PsValue::Object(object) => object_to_py(py, object),
+    }
+}
+
+fn primitive_to_py<'py>(py: Python<'py>, value: &PsPrimitiveValue) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        PsPrimitiveValue::Nil => py.None().into_bound(py),
+        PsPrimitiveValue::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        PsPrimitiveValue::I32(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::U32(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::I64(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::U64(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::Int16(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::UInt16(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::Byte(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::SByte(n) => n.into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::Char(c) => c.to_string().into_pyobject(py)?.into_any(),
+        PsPrimitiveValue::Double(s) | PsPrimitiveValue::Single(s) => {
+            // `float()` understands .NET's `Infinity`/`NaN` spellings.
+            py.import("builtins")?.getattr("float")?.call1((s,))?
+        }
+        PsPrimitiveValue::Decimal(s) => py.import("decimal")?.getattr("Decimal")?.call1((s,))?,
+        PsPrimitiveValue::Guid(s) => py.import("uuid")?.getattr("UUID")?.call1((s,))?,
+        PsPrimitiveValue::Bytes(bytes) | PsPrimitiveValue::SecureString(bytes) => {
+            PyBytes::new(py, bytes).into_any()
+        }
+        PsPrimitiveValue::Str(s)
+        | PsPrimitiveValue::Version(s)
+        | PsPrimitiveValue::DateTime(s)
+        | PsPrimitiveValue::TimeSpan(s)
+        | PsPrimitiveValue::Uri(s)
+        | PsPrimitiveValue::ScriptBlock(s)
+        | PsPrimitiveValue::Xml(s) => s.into_pyobject(py)?.into_any(),
+    })
+}
+
+fn object_to_py<'py>(py: Python<'py>, object: &ComplexObject) -> PyResult<Bound<'py, PyAny>> {
+    match &object.content {
+        ComplexObjectContent::ExtendedPrimitive(primitive) => primitive_to_py(py, primitive),
+        ComplexObjectContent::PsEnums(e) => match &object.to_string {
+            Some(name) => Ok(name.into_pyobject(py)?.into_any()),
+            None => Ok(e.value.into_pyobject(py)?.into_any()),
+        },
+        ComplexObjectContent::Container(
+            Container::List(items) | Container::Stack(items) | Container::Queue(items),
+        ) => {
+            let items = items
+                .iter()
+                .map(|item| ps_value_to_py(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyList::new(py, items)?.into_any())
+        }
+        ComplexObjectContent::Container(Container::Dictionary(entries)) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries {
+                let key = ps_value_to_py(py, key)?;
+                // Keys that are objects are unhashable on the Python side.
+                let key = if key.hash().is_ok() {
+                    key
+                } else {
+                    key.str()?.into_any()
+                };
+                dict.set_item(key, ps_value_to_py(py, value)?)?;
+            }
+            Ok(dict.into_any())
+        }
+        ComplexObjectContent::Standard => {
+            let properties = PyDict::new(py);
+            for (name, property) in &object.properties {
+                properties.set_item(name, ps_value_to_py(py, &property.value)?)?;
+            }
+            let type_names = object
+                .type_def
+                .as_ref()
+                .map(|t| t.type_names.iter().map(ToString::to_string).collect())
+                .unwrap_or_default();
+            let object = PsObject {
+                type_names,
+                properties: properties.unbind(),
+                to_string: object.to_string.clone(),
+            };
+            Ok(Bound::new(py, object)?.into_any())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn with_py<R>(f: impl FnOnce(Python<'_>) -> PyResult<R>) -> R {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f).expect("python call")
+    }
+
+    fn primitive(value: PsPrimitiveValue) -> PsValue {
+        PsValue::Primitive(value)
+    }
+
+    fn list(items: Vec<PsValue>) -> PsValue {
+        ComplexObject::builder(ComplexObjectContent::Container(Container::List(items)))
+            .build_value()
+    }
+
+    #[test]
+    fn primitives_become_python_values() {
+        with_py(|py| {
+            let convert = |value| ps_value_to_py(py, &primitive(value));
+            assert!(convert(PsPrimitiveValue::Nil)?.is_none());
+            assert!(convert(PsPrimitiveValue::Bool(true))?.extract::<bool>()?);
+            assert_eq!(convert(PsPrimitiveValue::I64(-5))?.extract::<i64>()?, -5);
+            assert_eq!(convert(PsPrimitiveValue::Byte(255))?.extract::<u8>()?, 255);
+            assert_eq!(
+                convert(PsPrimitiveValue::Char('x'))?.extract::<String>()?,
+                "x"
+            );
+            let bytes = convert(PsPrimitiveValue::Bytes(vec![1, 2]))?;
+            assert_eq!(bytes.downcast::<PyBytes>()?.as_bytes(), [1, 2]);
+            assert_eq!(
+                convert(PsPrimitiveValue::DateTime(
+                    "2024-01-02T03:04:05Z".to_owned()
+                ))?
+                .extract::<String>()?,
+                "2024-01-02T03:04:05Z"
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn floats_accept_dotnet_spellings() {
+        with_py(|py| {
+            let float = |text: &str| {
+                ps_value_to_py(py, &primitive(PsPrimitiveValue::Double(text.to_owned())))?
+                    .extract::<f64>()
+            };
+            assert!((float("1.5")? - 1.5).abs() < f64::EPSILON);
+            let infinity = float("Infinity")?;
+            assert!(infinity.is_infinite() && infinity.is_sign_positive());
+            let negative = float("-Infinity")?;
+            assert!(negative.is_infinite() && negative.is_sign_negative());
+            assert!(float("NaN")?.is_nan());
+            let single = ps_value_to_py(py, &primitive(PsPrimitiveValue::Single("2".to_owned())))?;
+            assert!((single.extract::<f64>()? - 2.0).abs() < f64::EPSILON);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn decimals_and_guids_use_the_standard_library_types() {
+        with_py(|py| {
+            let decimal =
+                ps_value_to_py(py, &primitive(PsPrimitiveValue::Decimal("1.10".to_owned())))?;
+            assert!(decimal.is_instance(&py.import("decimal")?.getattr("Decimal")?)?);
+            // Decimal keeps the trailing zero a float would lose.
+            assert_eq!(decimal.str()?.to_string(), "1.10");
+
+            let guid = ps_value_to_py(
+                py,
+                &primitive(PsPrimitiveValue::Guid(
+                    "6F9619FF-8B86-D011-B42D-00C04FC964FF".to_owned(),
+                )),
+            )?;
+            assert!(guid.is_instance(&py.import("uuid")?.getattr("UUID")?)?);
+            assert_eq!(
+                guid.str()?.to_string(),
+                "6f9619ff-8b86-d011-b42d-00c04fc964ff"
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn dictionaries_stringify_unhashable_keys() {
+        let string = |s: &str| primitive(PsPrimitiveValue::Str(s.to_owned()));
+        let entries = BTreeMap::from([
+            (string("name"), string("svc")),
+            (
+                list(vec![primitive(PsPrimitiveValue::I32(1))]),
+                string("listed"),
+            ),
+        ]);
+        let dictionary = ComplexObject::builder(ComplexObjectContent::Container(
+            Container::Dictionary(entries),
+        ))
+        .build_value();
+
+        with_py(|py| {
+            let dict = ps_value_to_py(py, &dictionary)?.downcast_into::<PyDict>()?;
+            assert_eq!(dict.len(), 2);
+            assert_eq!(
+                dict.get_item("name")?.expect("name").extract::<String>()?,
+                "svc"
+            );
+            assert_eq!(
+                dict.get_item("[1]")?
+                    .expect("list key")
+                    .extract::<String>()?,
+                "listed"
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn objects_expose_properties_as_attributes() {
+        let service = ComplexObject::standard()
+            .type_names([
+                Cow::Borrowed("System.ServiceProcess.ServiceController"),
+                Cow::Borrowed("System.Object"),
+            ])
+            .to_string_repr("System.ServiceProcess.ServiceController")
+            .extended("Name", "WinRM")
+            .adapted("Dependencies", list(vec![]))
+            .build_value();
+
+        with_py(|py| {
+            let object = ps_value_to_py(py, &service)?;
+            assert_eq!(object.getattr("Name")?.extract::<String>()?, "WinRM");
+            assert!(
+                object
+                    .getattr("Dependencies")?
+                    .downcast::<PyList>()?
+                    .is_empty()
+            );
+            assert!(
+                object
+                    .getattr("Missing")
+                    .unwrap_err()
+                    .is_instance_of::<PyAttributeError>(py)
+            );
+            assert_eq!(
+                object.getattr("type_names")?.extract::<Vec<String>>()?,
+                ["System.ServiceProcess.ServiceController", "System.Object"]
+            );
+            assert_eq!(
+                object.str()?.to_string(),
+                "System.ServiceProcess.ServiceController"
+            );
+            assert_eq!(
+                object.repr()?.to_string(),
+                r#"<System.ServiceProcess.ServiceController "System.ServiceProcess.ServiceController">"#
+            );
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,42 @@
+//! Python bindings for the async client, built into the `ironposh_py`
+//! extension module with maturin (`pyproject.toml`).
+//!
+//! ```python
+//! from ironposh_py import Session
+//!
+//! with Session.connect("srv01.corp.local", "alice", "secret", auth="kerberos") as session:
+//!     result = session.run("Get-Service | Select-Object -First 3")
+//!     for service in result.output:
+//!         print(service.Name, service.Status)
+//! ```
+//!
+//! `Session.connect_async` and `Session.run_async` are the asyncio variants.
+//! Failures to connect or run raise `IronPoshError`; errors written by the
+//! script do not, and are in `RunResult.errors`.
+
+mod config;
+mod convert;
+mod session;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+pub use convert::PsObject;
+pub use session::{RunResult, Session};
+
+create_exception!(
+    ironposh_py,
+    IronPoshError,
+    PyException,
+    "The session could not connect, or lost its connection."
+);
+
+#[pymodule]
+fn ironposh_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Session>()?;
+    m.add_class::<RunResult>()?;
+    m.add_class::<PsObject>()?;
+    m.add("IronPoshError", m.py().get_type::<IronPoshError>())?;
+    Ok(())
+}
@@ -0,0 +1,396 @@
+//! `Session`: a connected runspace pool, and `RunResult`: what a script
+//! produced.
+//!
+//! Everything runs on the tokio runtime owned by `pyo3_async_runtimes`. The
+//! blocking methods release the GIL while they wait; the `_async` variants
+//! return awaitables for the running asyncio loop.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use futures::StreamExt;
+use ironposh::client::{
+    OpenedSession, RemoteAsyncPowershellClient, ReqwestHttpClient, SessionEvent, SessionHooks,
+};
+use ironposh::pipeline::{PsrpRecord, UserEvent};
+use ironposh::value::{InformationMessageData, PsValue};
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyTuple};
+use pyo3_async_runtimes::tokio::{future_into_py, get_runtime};
+use tracing::{debug, info, warn};
+
+use crate::IronPoshError;
+use crate::config::{Auth, ConnectOptions};
+use crate::convert::ps_value_to_py;
+
+/// How long closing waits for the server to delete the shell.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type ConnectionTask = tokio::task::JoinHandle<anyhow::Result<()>>;
+
+fn to_py_err(error: &anyhow::Error) -> PyErr {
+    IronPoshError::new_err(format!("{error:#}"))
+}
+
+/// A connected session. Use it as a context manager, or call `close()`.
+#[pyclass(module = "ironposh_py", frozen)]
+pub struct Session {
+    client: RemoteAsyncPowershellClient,
+    connection: Mutex<Option<ConnectionTask>>,
+}
+
+impl Session {
+    /// Open the session; must run on the runtime.
+    async fn open(options: ConnectOptions) -> anyhow::Result<Self> {
+        let config = options.into_winrm_config()?;
+        let http_client = ReqwestHttpClient::with_tls_options(config.tls.clone());
+
+        let OpenedSession {
+            client,
+            host_io,
+            mut session_events,
+            lifecycle_events,
+            connection_task,
+        } = RemoteAsyncPowershellClient::open_task(
+            config,
+            None,
+            http_client,
+            SessionHooks::default(),
+        );
+        let connection = tokio::spawn(connection_task);
+        drop(lifecycle_events);
+
+        // The server is offered no host, so nothing should arrive here.
+        let (mut host_calls, submitter) = host_io.into_parts();
+        tokio::spawn(async move {
+            let _submitter = submitter;
+            while let Some(call) = host_calls.next().await {
+                warn!(
+                    method = call.method_name(),
+                    "ignoring host call on a headless session"
+                );
+            }
+        });
+
+        while let Some(event) = session_events.next().await {
+            match event {
+                SessionEvent::ActiveSessionStarted => {
+                    tokio::spawn(async move {
+                        while let Some(event) = session_events.next().await {
+                            debug!(event = ?event, "session event");
+                        }
+                    });
+                    info!("session established");
+                    return Ok(Self {
+                        client,
+                        connection: Mutex::new(Some(connection)),
+                    });
+                }
+                SessionEvent::Error(e) => {
+                    connection.abort();
+                    anyhow::bail!("Session error: {e}");
+                }
+                other => debug!(event = ?other, "session event"),
+            }
+        }
+        connection.abort();
+        anyhow::bail!("Session ended before it was established")
+    }
+
+    /// The connection task, for the first close only.
+    fn take_connection(&self) -> Option<ConnectionTask> {
+        self.connection.lock().expect("connection lock").take()
+    }
+}
+
+/// Delete the shell and wait for the connection task to end, for at most
+/// `CLOSE_TIMEOUT`, then stop the task.
+async fn shutdown(mut client: RemoteAsyncPowershellClient, mut connection: ConnectionTask) {
+    let closed = tokio::time::timeout(CLOSE_TIMEOUT, async {
+        client.close().await?;
+        // The connection task ends once the server answered the Delete.
+        (&mut connection)
+            .await
+            .context("The connection task panicked")?
+    })
+    .await;
+    match closed {
+        Ok(Ok(())) => info!("session closed"),
+        Ok(Err(e)) => warn!(error = %e, "closing the session failed"),
+        Err(_) => warn!(timeout = ?CLOSE_TIMEOUT, "shell not deleted before the session closed"),
+    }
+    connection.abort();
+}
+
+#[pymethods]
+impl Session {
+    /// Connect and open a runspace pool. `auth` is one of `negotiate`
+    /// (default), `kerberos`, `ntlm` or `basic`.
+    ///
+    /// `max_concurrent_receives` and `max_pending_output_bytes` set the
+    /// receive flow control. `run` holds a script's whole output until it
+    /// ends and acknowledges each object as it collects it, so the output
+    /// budget does not bound its memory.
+    #[staticmethod]
+    #[pyo3(signature = (
+        server, username, password, *, port=None, https=false, auth="negotiate", domain=None,
+        configuration_name=None, accept_invalid_certs=false, operation_timeout=None,
+        max_concurrent_receives=None, max_pending_output_bytes=None,
+    ))]
+    #[expect(clippy::too_many_arguments)]
+    fn connect(
+        py: Python<'_>,
+        server: &str,
+        username: &str,
+        password: &str,
+        port: Option<u16>,
+        https: bool,
+        auth: &str,
+        domain: Option<&str>,
+        configuration_name: Option<&str>,
+        accept_invalid_certs: bool,
+        operation_timeout: Option<f64>,
+        max_concurrent_receives: Option<usize>,
+        max_pending_output_bytes: Option<usize>,
+    ) -> PyResult<Self> {
+        let options = ConnectOptions {
+            server: server.to_owned(),
+            port,
+            https,
+            accept_invalid_certs,
+            auth: Auth::parse(auth).map_err(|e| to_py_err(&e))?,
+            username: username.to_owned(),
+            password: password.to_owned(),
+            domain: domain.map(str::to_owned),
+            configuration_name: configuration_name.map(str::to_owned),
+            operation_timeout_secs: operation_timeout,
+            max_concurrent_receives,
+            max_pending_output_bytes,
+        };
+        py.allow_threads(|| get_runtime().block_on(Self::open(options)))
+            .map_err(|e| to_py_err(&e))
+    }
+
+    /// `connect()` as an awaitable.
+    #[staticmethod]
+    #[pyo3(signature = (
+        server, username, password, *, port=None, https=false, auth="negotiate", domain=None,
+        configuration_name=None, accept_invalid_certs=false, operation_timeout=None,
+        max_concurrent_receives=None, max_pending_output_bytes=None,
+    ))]
+    #[expect(clippy::too_many_arguments)]
+    fn connect_async<'py>(
+        py: Python<'py>,
+        server: &str,
+        username: &str,
+        password: &str,
+        port: Option<u16>,
+        https: bool,
+        auth: &str,
+        domain: Option<&str>,
+        configuration_name: Option<&str>,
+        accept_invalid_certs: bool,
+        operation_timeout: Option<f64>,
+        max_concurrent_receives: Option<usize>,
+        max_pending_output_bytes: Option<usize>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let options = ConnectOptions {
+            server: server.to_owned(),
+            port,
+            https,
+            accept_invalid_certs,
+            auth: Auth::parse(auth).map_err(|e| to_py_err(&e))?,
+            username: username.to_owned(),
+            password: password.to_owned(),
+            domain: domain.map(str::to_owned),
+            configuration_name: configuration_name.map(str::to_owned),
+            operation_timeout_secs: operation_timeout,
+            max_concurrent_receives,
+            max_pending_output_bytes,
+        };
+        future_into_py(py, async move {
+            Self::open(options).await.map_err(|e| to_py_err(&e))
+        })
+    }
+
+    /// Run `script` to completion. Output objects are converted to Python
+    /// values; a script that writes errors still returns, with them in
+    /// `RunResult.errors`.
+    fn run(&self, py: Python<'_>, script: &str) -> PyResult<RunResult> {
+        let (client, script) = (self.client.clone(), script.to_owned());
+        let collected = py
+            .allow_threads(|| get_runtime().block_on(run_script(client, script)))
+            .map_err(|e| to_py_err(&e))?;
+        RunResult::new(py, collected)
+    }
+
+    /// `run()` as an awaitable.
+    fn run_async<'py>(&self, py: Python<'py>, script: &str) -> PyResult<Bound<'py, PyAny>> {
+        let (client, script) = (self.client.clone(), script.to_owned());
+        future_into_py(py, async move {
+            let collected = run_script(client, script)
+                .await
+                .map_err(|e| to_py_err(&e))?;
+            Python::with_gil(|py| RunResult::new(py, collected))
+        })
+    }
+
+    /// Close the session: delete the shell, waiting at most five seconds for
+    /// the server. Closing twice is harmless.
+    fn close(&self, py: Python<'_>) {
+        if let Some(connection) = self.take_connection() {
+            let client = self.client.clone();
+            py.allow_threads(|| get_runtime().block_on(shutdown(client, connection)));
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) {
+        self.close(py);
+    }
+
+    fn __aenter__<'py>(slf: Py<Self>, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        future_into_py(py, async move { Ok(slf) })
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __aexit__<'py>(
+        slf: Py<Self>,
+        py: Python<'py>,
+        _args: &Bound<'py, PyTuple>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let session = slf.get();
+        let closing = session
+            .take_connection()
+            .map(|connection| shutdown(session.client.clone(), connection));
+        future_into_py(py, async move {
+            if let Some(closing) = closing {
+                closing.await;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let Some(connection) = self.take_connection() else {
+            return;
+        };
+        let closing = shutdown(self.client.clone(), connection);
+        // Dropped on a runtime thread (by an awaitable that held the last
+        // reference): blocking there would panic, so close in the background.
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(closing);
+        } else {
+            Python::with_gil(|py| py.allow_threads(|| get_runtime().block_on(closing)));
+        }
+    }
+}
+
+/// The streams of a finished script, gathered without the GIL.
+#[derive(Default)]
+struct Collected {
+    output: Vec<PsValue>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    verbose: Vec<String>,
+    debug: Vec<String>,
+    information: Vec<String>,
+    timed_out: bool,
+}
+
+async fn run_script(
+    mut client: RemoteAsyncPowershellClient,
+    script: String,
+) -> anyhow::Result<Collected> {
+    let mut events = client.send_script_raw(script).await?;
+    let mut collected = Collected::default();
+    while let Some(event) = events.next().await {
+        match event {
+            UserEvent::PipelineOutput {
+                output,
+                pipeline,
+                data_len,
+            } => {
+                collected.output.push(output.data);
+                client.acknowledge_output(pipeline, data_len).await?;
+            }
+            UserEvent::ErrorRecord { error_record, .. } => {
+                collected.errors.push(error_record.render_concise());
+            }
+            UserEvent::PipelineRecord { record, .. } => match record {
+                PsrpRecord::Debug { message, .. } => collected.debug.push(message),
+                PsrpRecord::Verbose { message, .. } => collected.verbose.push(message),
+                PsrpRecord::Warning { message, .. } => collected.warnings.push(message),
+                PsrpRecord::Information { record, .. } => {
+                    collected.information.push(match record.message_data {
+                        InformationMessageData::String(s) => s,
+                        InformationMessageData::HostInformationMessage(m) => m.message,
+                        InformationMessageData::Object(v) => v.to_string(),
+                    });
+                }
+                PsrpRecord::Progress { .. } | PsrpRecord::Unsupported { .. } => {}
+            },
+            UserEvent::PipelineTimedOut { .. } => collected.timed_out = true,
+            UserEvent::PipelineCreated { .. } | UserEvent::PipelineFinished { .. } => {}
+        }
+    }
+    Ok(collected)
+}
+
+/// What a script produced, one list per stream.
+#[pyclass(module = "ironposh_py", frozen, get_all)]
+pub struct RunResult {
+    /// Output objects, converted to Python values.
+    output: Py<PyList>,
+    /// Error records, rendered as PowerShell prints them.
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    verbose: Vec<String>,
+    debug: Vec<String>,
+    information: Vec<String>,
+    /// The operation timeout stopped the script.
+    timed_out: bool,
+}
+
+impl RunResult {
+    fn new(py: Python<'_>, collected: Collected) -> PyResult<Self> {
+        let output = collected
+            .output
+            .iter()
+            .map(|value| ps_value_to_py(py, value))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self {
+            output: PyList::new(py, output)?.unbind(),
+            errors: collected.errors,
+            warnings: collected.warnings,
+            verbose: collected.verbose,
+            debug: collected.debug,
+            information: collected.information,
+            timed_out: collected.timed_out,
+        })
+    }
+}
+
+#[pymethods]
+impl RunResult {
+    /// Whether the script wrote to the error stream or timed out.
+    #[getter]
+    fn had_errors(&self) -> bool {
+        !self.errors.is_empty() || self.timed_out
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> String {
+        format!(
+            "<RunResult output={} errors={}>",
+            self.output.bind(py).len(),
+            self.errors.len()
+        )
+    }
+}
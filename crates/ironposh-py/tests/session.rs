@@ -0,0 +1,91 @@
+//! `Session.connect`, `run` and `close`, called the way Python calls them,
+//! against the in-process mock WinRM/PSRP server.
+
+use std::borrow::Cow;
+
+use ironposh::value::ComplexObject;
+use ironposh_py::{IronPoshError, Session};
+use ironposh_test_server::{CannedPipeline, MockAuth, MockServer, RunningServer};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyList};
+use pyo3_async_runtimes::tokio::get_runtime;
+
+fn start(pipelines: Vec<CannedPipeline>) -> RunningServer {
+    get_runtime()
+        .block_on(
+            MockServer::builder()
+                .auth(MockAuth::basic("user", "pass"))
+                .pipelines(pipelines)
+                .build()
+                .start(),
+        )
+        .expect("start mock server")
+}
+
+fn connect<'py>(
+    py: Python<'py>,
+    server: &RunningServer,
+    password: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let options = [
+        ("port", server.addr().port().into_pyobject(py)?.into_any()),
+        ("auth", "basic".into_pyobject(py)?.into_any()),
+    ]
+    .into_py_dict(py)?;
+    py.get_type::<Session>()
+        .call_method("connect", ("127.0.0.1", "user", password), Some(&options))
+}
+
+fn deletes(server: &RunningServer) -> usize {
+    server
+        .requests()
+        .iter()
+        .filter(|request| request.body.contains("/transfer/Delete<"))
+        .count()
+}
+
+#[test]
+fn run_returns_converted_output_and_close_deletes_the_shell() {
+    let service = ComplexObject::standard()
+        .type_names([Cow::Borrowed("System.ServiceProcess.ServiceController")])
+        .to_string_repr("WinRM")
+        .extended("Name", "WinRM")
+        .build_value();
+    let server = start(vec![
+        CannedPipeline::new().string_output("hello").output(service),
+    ]);
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| -> PyResult<()> {
+        let session = connect(py, &server, "pass")?;
+        let result = session.call_method1("run", ("Get-Service WinRM",))?;
+
+        assert!(!result.getattr("had_errors")?.extract::<bool>()?);
+        let output = result.getattr("output")?.downcast_into::<PyList>()?;
+        assert_eq!(output.len(), 2);
+        assert_eq!(output.get_item(0)?.extract::<String>()?, "hello");
+        assert_eq!(
+            output.get_item(1)?.getattr("Name")?.extract::<String>()?,
+            "WinRM"
+        );
+
+        session.call_method0("close")?;
+        // Closing twice is harmless and deletes the shell once.
+        session.call_method0("close")?;
+        Ok(())
+    })
+    .expect("python calls");
+
+    assert_eq!(deletes(&server), 1);
+}
+
+#[test]
+fn connecting_with_a_wrong_password_raises() {
+    let server = start(Vec::new());
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let error = connect(py, &server, "wrong").expect_err("connected with a wrong password");
+        assert!(error.is_instance_of::<IronPoshError>(py));
+    });
+}
@@ -28,7 +28,7 @@ fn main() -> Result<()> {
                         writeln!(io, "You typed: {line}")?;
                     }
                 }
-                ReadOutcome::Interrupt => {
+                ReadOutcome::Interrupt | ReadOutcome::Detach => {
                     // graceful: just reprompt (like bash/zsh)
                 }
                 ReadOutcome::Eof => {
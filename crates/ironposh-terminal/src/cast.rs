@@ -0,0 +1,352 @@
+//! Session recordings in the asciicast v2 format (`.cast`): a JSON header
+//! line, then one `[seconds, code, data]` line per event.
+//!
+//! The recorder sees every byte the guest emulator processes and every
+//! resize, so a replay drives the guest through the same states. Output
+//! events (`o`) are plain terminal output and play in any asciicast player.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::term::{CrosstermRenderer, GuestTerm, HostRenderer, TerminalOp};
+
+/// Writes a recording as the session runs. Every event is flushed, so the
+/// file is complete up to the last event even if the client dies.
+pub struct CastRecorder {
+    out: Box<dyn Write + Send>,
+    started: Instant,
+    /// The start of a UTF-8 sequence cut off at the end of the last output.
+    partial: Vec<u8>,
+}
+
+impl CastRecorder {
+    /// Start a recording of a `cols`x`rows` terminal in `out`.
+    pub fn new(mut out: Box<dyn Write + Send>, cols: u16, rows: u16) -> std::io::Result<Self> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let header = serde_json::json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": timestamp,
+        });
+        writeln!(out, "{header}")?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+            partial: Vec::new(),
+        })
+    }
+
+    /// Start a recording in a new file, replacing an existing one.
+    pub fn create(path: &Path, cols: u16, rows: u16) -> std::io::Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Self::new(Box::new(std::io::BufWriter::new(file)), cols, rows)
+    }
+
+    /// Record bytes fed to the guest.
+    pub fn output(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        // Events carry strings: hold back a trailing incomplete UTF-8
+        // sequence until the rest of it arrives.
+        self.partial.extend_from_slice(bytes);
+        let incomplete = match std::str::from_utf8(&self.partial) {
+            Err(e) if e.error_len().is_none() => self.partial.len() - e.valid_up_to(),
+            _ => 0,
+        };
+        let rest = self.partial.split_off(self.partial.len() - incomplete);
+        let text =
+            String::from_utf8_lossy(&std::mem::replace(&mut self.partial, rest)).into_owned();
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.event("o", &text)
+    }
+
+    /// Record a guest resize.
+    pub fn resize(&mut self, rows: u16, cols: u16) -> std::io::Result<()> {
+        self.event("r", &format!("{cols}x{rows}"))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        let at = self.started.elapsed().as_secs_f64();
+        let line = serde_json::json!([(at * 1e6).round() / 1e6, code, data]);
+        writeln!(self.out, "{line}")?;
+        self.out.flush()
+    }
+}
+
+/// A recording read back for replay.
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub width: u16,
+    pub height: u16,
+    pub events: Vec<CastEvent>,
+}
+
+/// An operation and when it happened, from the start of the recording.
+#[derive(Debug, Clone)]
+pub struct CastEvent {
+    pub at: Duration,
+    pub op: TerminalOp,
+}
+
+impl Cast {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read recording {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("invalid recording {}", path.display()))
+    }
+
+    /// Parse an asciicast v2 recording. Events other than output and
+    /// resizes (input, markers) are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().context("empty recording")?;
+        let header: serde_json::Value =
+            serde_json::from_str(header).context("line 1: header is not JSON")?;
+        if header["version"] != 2 {
+            bail!("line 1: only asciicast version 2 is supported");
+        }
+        let dimension = |name: &str| {
+            header[name]
+                .as_u64()
+                .and_then(|value| u16::try_from(value).ok())
+                .filter(|&value| value > 0)
+                .with_context(|| format!("line 1: header has no valid {name}"))
+        };
+        let (width, height) = (dimension("width")?, dimension("height")?);
+
+        let mut events = Vec::new();
+        for (index, line) in lines {
+            let event = parse_event(line).with_context(|| format!("line {}", index + 1))?;
+            events.extend(event);
+        }
+        Ok(Self {
+            width,
+            height,
+            events,
+        })
+    }
+}
+
+fn parse_event(line: &str) -> Result<Option<CastEvent>> {
+    let (at, code, data): (f64, String, String) =
+        serde_json::from_str(line).context("event is not [time, code, data]")?;
+    if !at.is_finite() || at < 0.0 {
+        bail!("event time {at} is not a time");
+    }
+    let op = match code.as_str() {
+        "o" => TerminalOp::FeedBytes(data.into_bytes()),
+        "r" => {
+            let (cols, rows) = data
+                .split_once('x')
+                .and_then(|(cols, rows)| Some((cols.parse().ok()?, rows.parse().ok()?)))
+                .with_context(|| format!("resize '{data}' is not COLSxROWS"))?;
+            TerminalOp::Resize { rows, cols }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(CastEvent {
+        at: Duration::from_secs_f64(at),
+        op,
+    }))
+}
+
+/// How fast a recording plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    /// 2.0 plays twice as fast as recorded.
+    pub speed: f64,
+    /// Pauses longer than this (in recorded time) are cut to it.
+    pub max_idle: Option<Duration>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            max_idle: None,
+        }
+    }
+}
+
+impl ReplayOptions {
+    /// The real time to wait for a recorded pause of `gap`.
+    pub fn delay(&self, gap: Duration) -> Duration {
+        let gap = self.max_idle.map_or(gap, |max| gap.min(max));
+        gap.div_f64(self.speed)
+    }
+}
+
+/// Play `cast` into `renderer` through a guest terminal of the recorded size.
+///
+/// `wait` sleeps for the given time between events and returns `true` to
+/// stop the replay early; the return value says whether it did.
+pub fn replay<R, W>(
+    cast: &Cast,
+    options: &ReplayOptions,
+    renderer: &mut R,
+    mut wait: W,
+) -> Result<bool>
+where
+    R: HostRenderer,
+    W: FnMut(Duration) -> Result<bool>,
+{
+    let mut guest = GuestTerm::new(cast.height, cast.width, 0);
+    let mut last = Duration::ZERO;
+    for event in &cast.events {
+        let gap = event.at.saturating_sub(last);
+        last = event.at;
+        if !gap.is_zero() && wait(options.delay(gap))? {
+            return Ok(true);
+        }
+        guest.apply(event.op.clone());
+        guest.render_to(renderer)?;
+    }
+    Ok(false)
+}
+
+/// Play `cast` on this terminal, then wait for a key so the last frame
+/// stays up. `q`, Esc or Ctrl+C stops early.
+pub fn play(cast: &Cast, options: &ReplayOptions) -> Result<()> {
+    let mut renderer = CrosstermRenderer::new();
+    renderer.init()?;
+    let played = replay(cast, options, &mut renderer, wait_for_quit).and_then(|stopped| {
+        if !stopped {
+            while !matches!(event::read()?, Event::Key(key) if key.kind == KeyEventKind::Press) {}
+        }
+        Ok(())
+    });
+    renderer.deinit();
+    played
+}
+
+/// Sleep for `timeout` unless a quit key comes first.
+fn wait_for_quit(timeout: Duration) -> Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(false);
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && is_quit(key)
+        {
+            return Ok(true);
+        }
+    }
+}
+
+fn is_quit(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term::MemoryRenderer;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer the test can read back after the recorder owns it.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("lock").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recorded_output_and_resizes_read_back_as_ops() {
+        let out = Shared::default();
+        let mut recorder = CastRecorder::new(Box::new(out.clone()), 80, 24).expect("header");
+        // "日" split between two writes is recorded whole.
+        recorder.output(b"PS> \xe6\x97").expect("output");
+        recorder.output(b"\xa5\r\n").expect("output");
+        recorder.resize(30, 100).expect("resize");
+
+        let text = String::from_utf8(out.0.lock().expect("lock").clone()).expect("utf-8");
+        let cast = Cast::parse(&text).expect("parse");
+        assert_eq!((cast.width, cast.height), (80, 24));
+        let ops: Vec<TerminalOp> = cast.events.into_iter().map(|e| e.op).collect();
+        assert_eq!(
+            ops,
+            [
+                TerminalOp::FeedBytes(b"PS> ".to_vec()),
+                TerminalOp::FeedBytes("日\r\n".as_bytes().to_vec()),
+                TerminalOp::Resize {
+                    rows: 30,
+                    cols: 100
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_events_are_skipped_and_bad_lines_reported() {
+        let cast = Cast::parse(
+            "{\"version\": 2, \"width\": 10, \"height\": 2}\n\
+             [0.5, \"i\", \"x\"]\n[0.6, \"m\", \"\"]\n[1.0, \"o\", \"hi\"]\n",
+        )
+        .expect("parse");
+        assert_eq!(cast.events.len(), 1);
+        assert_eq!(cast.events[0].at, Duration::from_secs(1));
+
+        let error =
+            Cast::parse("{\"version\": 2, \"width\": 10, \"height\": 2}\n[1, \"r\", \"wide\"]")
+                .expect_err("bad resize");
+        assert!(format!("{error:#}").starts_with("line 2"), "{error:#}");
+        assert!(Cast::parse("{\"version\": 1, \"width\": 10, \"height\": 2}").is_err());
+    }
+
+    #[test]
+    fn replay_waits_scaled_gaps_and_renders_every_event() {
+        let cast = Cast::parse(
+            "{\"version\": 2, \"width\": 20, \"height\": 3}\n\
+             [0.0, \"o\", \"a\"]\n[2.0, \"o\", \"\\u001b]2;t\\u0007b\"]\n[12.0, \"o\", \"c\"]\n",
+        )
+        .expect("parse");
+        let options = ReplayOptions {
+            speed: 2.0,
+            max_idle: Some(Duration::from_secs(4)),
+        };
+        let mut renderer = MemoryRenderer::new(20, 3);
+        let mut waits = Vec::new();
+        let stopped = replay(&cast, &options, &mut renderer, |delay| {
+            waits.push(delay);
+            Ok(false)
+        })
+        .expect("replay");
+
+        assert!(!stopped);
+        assert_eq!(waits, [Duration::from_secs(1), Duration::from_secs(2)]);
+        assert_eq!(renderer.frames().len(), 3);
+        assert_eq!(renderer.titles(), ["t"]);
+        assert_eq!(renderer.contents(), "abc");
+
+        // Stopping at the first pause renders only what came before it.
+        let mut renderer = MemoryRenderer::new(20, 3);
+        assert!(replay(&cast, &options, &mut renderer, |_| Ok(true)).expect("replay"));
+        assert_eq!(renderer.frames().len(), 1);
+    }
+}
@@ -0,0 +1,426 @@
+//! Out-GridView-style table viewer.
+//!
+//! [`GridView`] holds the table and its view state (sort column, filter,
+//! scroll position) and renders whole frames.
+//! [`StdTerm::show_grid`](crate::StdTerm::show_grid) runs it on the alternate
+//! screen until the user closes it.
+
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::text::{display_width, pad_to_width, truncate_to_width};
+
+/// Widest a column gets; longer cells are cut with an ellipsis.
+const MAX_COLUMN_WIDTH: usize = 40;
+/// Spaces between two columns.
+const COLUMN_GAP: usize = 1;
+
+/// What a key did to the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridAction {
+    /// The view changed; draw a new frame.
+    Redraw,
+    /// Nothing to do.
+    Ignore,
+    /// The user closed the grid.
+    Close,
+}
+
+/// A table of text cells with sorting, filtering and scrolling.
+#[derive(Debug, Clone)]
+pub struct GridView {
+    title: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    widths: Vec<usize>,
+    /// Indices into `rows` that pass the filter, in display order.
+    view: Vec<usize>,
+    /// Sort column and whether the order is descending.
+    sort: Option<(usize, bool)>,
+    filter: String,
+    editing_filter: bool,
+    selected_column: usize,
+    top: usize,
+    /// Body rows of the last rendered frame, the step for PageUp/PageDown.
+    page_rows: usize,
+}
+
+impl GridView {
+    /// A grid over `rows`; every row is padded or cut to `columns.len()` cells.
+    pub fn new(title: impl Into<String>, columns: Vec<String>, mut rows: Vec<Vec<String>>) -> Self {
+        for row in &mut rows {
+            row.resize(columns.len(), String::new());
+        }
+
+        let widths = columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                // Leave room for the sort marker.
+                let header = display_width(name) + 2;
+                rows.iter()
+                    .map(|row| display_width(&row[i]))
+                    .fold(header, usize::max)
+                    .min(MAX_COLUMN_WIDTH)
+            })
+            .collect();
+
+        let mut grid = Self {
+            title: title.into(),
+            columns,
+            view: (0..rows.len()).collect(),
+            rows,
+            widths,
+            sort: None,
+            filter: String::new(),
+            editing_filter: false,
+            selected_column: 0,
+            top: 0,
+            page_rows: 1,
+        };
+        grid.refresh();
+        grid
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Number of rows passing the filter.
+    pub fn visible_rows(&self) -> usize {
+        self.view.len()
+    }
+
+    /// The `index`-th row in display order (filtered and sorted).
+    pub fn row(&self, index: usize) -> Option<&[String]> {
+        self.view.get(index).map(|&i| self.rows[i].as_slice())
+    }
+
+    /// Sort by `column`, ascending; sorting again by the same column flips
+    /// the order. Cells that all parse as numbers compare numerically.
+    pub fn sort_by(&mut self, column: usize) {
+        if column >= self.columns.len() {
+            return;
+        }
+        let descending = matches!(self.sort, Some((current, false)) if current == column);
+        self.sort = Some((column, descending));
+        self.refresh();
+    }
+
+    /// Keep only the rows with a cell containing `filter` (case-insensitive).
+    pub fn set_filter(&mut self, filter: impl Into<String>) {
+        self.filter = filter.into();
+        self.refresh();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> GridAction {
+        if key.kind != KeyEventKind::Press {
+            return GridAction::Ignore;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return GridAction::Close;
+        }
+        if self.editing_filter {
+            return self.edit_filter(key);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return GridAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => self.top = self.top.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.top += 1,
+            KeyCode::PageUp => self.top = self.top.saturating_sub(self.page_rows),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.top += self.page_rows,
+            KeyCode::Home => self.top = 0,
+            KeyCode::End => self.top = usize::MAX,
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.selected_column = self.selected_column.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if self.selected_column + 1 < self.columns.len() {
+                    self.selected_column += 1;
+                }
+            }
+            KeyCode::Char('s') | KeyCode::Enter => self.sort_by(self.selected_column),
+            KeyCode::Char('/') => self.editing_filter = true,
+            _ => return GridAction::Ignore,
+        }
+        self.clamp_top();
+        GridAction::Redraw
+    }
+
+    fn edit_filter(&mut self, key: KeyEvent) -> GridAction {
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.filter.push(c);
+                self.refresh();
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.refresh();
+            }
+            KeyCode::Enter => self.editing_filter = false,
+            KeyCode::Esc => {
+                self.editing_filter = false;
+                self.set_filter(String::new());
+            }
+            _ => return GridAction::Ignore,
+        }
+        GridAction::Redraw
+    }
+
+    /// Render a full frame for a `rows` x `cols` screen: the header, a page
+    /// of rows and a status line.
+    pub fn render(&mut self, rows: u16, cols: u16) -> String {
+        let rows = usize::from(rows).max(3);
+        let cols = usize::from(cols).max(1);
+        self.page_rows = rows - 2;
+        self.clamp_top();
+
+        let first_column = self.first_visible_column(cols);
+        let mut frame = String::new();
+
+        frame.push_str("\x1b[1;1H");
+        let header = |i: usize| match self.sort {
+            Some((column, descending)) if column == i => {
+                format!("{} {}", self.columns[i], if descending { '▼' } else { '▲' })
+            }
+            _ => self.columns[i].clone(),
+        };
+        self.push_cells(&mut frame, first_column, cols, header, true);
+        frame.push_str("\x1b[K");
+
+        for line in 0..self.page_rows {
+            let _ = write!(frame, "\x1b[{};1H", line + 2);
+            if let Some(&row) = self.view.get(self.top + line) {
+                let cells = &self.rows[row];
+                self.push_cells(&mut frame, first_column, cols, |i| cells[i].clone(), false);
+            }
+            frame.push_str("\x1b[K");
+        }
+
+        let _ = write!(
+            frame,
+            "\x1b[{rows};1H\x1b[7m{}\x1b[K\x1b[0m",
+            fit(&self.status_line(), cols)
+        );
+        frame
+    }
+
+    fn status_line(&self) -> String {
+        if self.editing_filter {
+            return format!("Filter: {}_  (Enter: apply, Esc: clear)", self.filter);
+        }
+
+        let mut status = self.title.clone();
+        if self.view.is_empty() {
+            status.push_str("  no rows");
+        } else {
+            let last = (self.top + self.page_rows).min(self.view.len());
+            let _ = write!(status, "  {}-{} of {}", self.top + 1, last, self.view.len());
+        }
+        if !self.filter.is_empty() {
+            let _ = write!(
+                status,
+                " (filter \"{}\", {} total)",
+                self.filter,
+                self.rows.len()
+            );
+        }
+        status.push_str("  \u{2190}\u{2192} column  s sort  / filter  q close");
+        status
+    }
+
+    /// Append the cells of one line from `first` on, stopping at `cols`.
+    fn push_cells(
+        &self,
+        line: &mut String,
+        first: usize,
+        cols: usize,
+        cell: impl Fn(usize) -> String,
+        header: bool,
+    ) {
+        let mut used = 0;
+        for i in first..self.columns.len() {
+            let gap = if i == first { 0 } else { COLUMN_GAP };
+            let width = self.widths[i].min(cols.saturating_sub(used + gap));
+            if width == 0 {
+                break;
+            }
+            line.push_str(&" ".repeat(gap));
+            let text = fit(&cell(i), width);
+            if header && i == self.selected_column {
+                let _ = write!(line, "\x1b[7m{text}\x1b[0m");
+            } else if header {
+                let _ = write!(line, "\x1b[1m{text}\x1b[0m");
+            } else {
+                line.push_str(&text);
+            }
+            used += gap + width;
+        }
+    }
+
+    /// Leftmost column to draw so the selected one is on screen.
+    fn first_visible_column(&self, cols: usize) -> usize {
+        let Some(&selected_width) = self.widths.get(self.selected_column) else {
+            return 0;
+        };
+        let mut first = self.selected_column;
+        let mut used = selected_width;
+        while first > 0 && used + COLUMN_GAP + self.widths[first - 1] <= cols {
+            first -= 1;
+            used += COLUMN_GAP + self.widths[first];
+        }
+        first
+    }
+
+    /// Rebuild the filtered, sorted view.
+    fn refresh(&mut self) {
+        let needle = self.filter.to_lowercase();
+        self.view = (0..self.rows.len())
+            .filter(|&i| {
+                needle.is_empty()
+                    || self.rows[i]
+                        .iter()
+                        .any(|cell| cell.to_lowercase().contains(&needle))
+            })
+            .collect();
+
+        if let Some((column, descending)) = self.sort {
+            let rows = &self.rows;
+            self.view.sort_by(|&a, &b| {
+                let order = compare_cells(&rows[a][column], &rows[b][column]);
+                if descending { order.reverse() } else { order }
+            });
+        }
+        self.clamp_top();
+    }
+
+    fn clamp_top(&mut self) {
+        self.top = self.top.min(self.view.len().saturating_sub(self.page_rows));
+    }
+}
+
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// `text` padded or cut (with an ellipsis) to exactly `width` columns.
+fn fit(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return pad_to_width(text, width);
+    }
+    let (kept, _) = truncate_to_width(text, width.saturating_sub(1));
+    pad_to_width(&format!("{kept}…"), width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processes() -> GridView {
+        let rows = [("pwsh", "120"), ("explorer", "95"), ("svchost", "1024")]
+            .into_iter()
+            .map(|(name, id)| vec![name.to_owned(), id.to_owned()])
+            .collect();
+        GridView::new(
+            "Get-Process",
+            vec!["ProcessName".to_owned(), "Id".to_owned()],
+            rows,
+        )
+    }
+
+    fn first_cells(grid: &GridView) -> Vec<&str> {
+        (0..grid.visible_rows())
+            .map(|i| grid.row(i).expect("row")[0].as_str())
+            .collect()
+    }
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn sorts_numbers_numerically_and_flips_on_second_sort() {
+        let mut grid = processes();
+        grid.sort_by(1);
+        assert_eq!(first_cells(&grid), ["explorer", "pwsh", "svchost"]);
+
+        grid.sort_by(1);
+        assert_eq!(first_cells(&grid), ["svchost", "pwsh", "explorer"]);
+    }
+
+    #[test]
+    fn filter_is_case_insensitive_over_all_cells() {
+        let mut grid = processes();
+        grid.set_filter("SV");
+        assert_eq!(first_cells(&grid), ["svchost"]);
+
+        grid.set_filter("12");
+        assert_eq!(first_cells(&grid), ["pwsh"]);
+    }
+
+    #[test]
+    fn filter_is_typed_after_a_slash() {
+        let mut grid = processes();
+        for code in [
+            KeyCode::Char('/'),
+            KeyCode::Char('e'),
+            KeyCode::Char('x'),
+            KeyCode::Enter,
+        ] {
+            assert_eq!(grid.handle_key(press(code)), GridAction::Redraw);
+        }
+        assert_eq!(first_cells(&grid), ["explorer"]);
+        assert_eq!(
+            grid.handle_key(press(KeyCode::Char('q'))),
+            GridAction::Close
+        );
+    }
+
+    #[test]
+    fn sort_key_sorts_the_selected_column() {
+        let mut grid = processes();
+        grid.handle_key(press(KeyCode::Right));
+        grid.handle_key(press(KeyCode::Char('s')));
+        assert_eq!(first_cells(&grid), ["explorer", "pwsh", "svchost"]);
+    }
+
+    #[test]
+    fn short_rows_are_padded_to_the_column_count() {
+        let grid = GridView::new(
+            "t",
+            vec!["A".to_owned(), "B".to_owned()],
+            vec![vec!["only".to_owned()]],
+        );
+        assert_eq!(
+            grid.row(0),
+            Some(["only".to_owned(), String::new()].as_slice())
+        );
+    }
+
+    #[test]
+    fn render_pages_the_rows_and_reports_the_range() {
+        let mut grid = processes();
+        let frame = grid.render(4, 80);
+        assert!(frame.contains("pwsh"));
+        assert!(frame.contains("explorer"));
+        assert!(!frame.contains("svchost"));
+        assert!(frame.contains("1-2 of 3"));
+
+        grid.handle_key(press(KeyCode::PageDown));
+        assert!(grid.render(4, 80).contains("svchost"));
+    }
+
+    #[test]
+    fn fit_cuts_long_cells_with_an_ellipsis() {
+        assert_eq!(fit("abc", 5), "abc  ");
+        assert_eq!(fit("abcdef", 4), "abc…");
+        assert_eq!(fit("日本語", 5), "日本…");
+        assert_eq!(fit("日本語", 4), "日… ");
+    }
+}
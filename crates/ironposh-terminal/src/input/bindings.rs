@@ -0,0 +1,414 @@
+//! Key bindings for the line editor: an emacs or vi keymap plus user
+//! overrides such as `ctrl+l = clear-screen`.
+//!
+//! Besides editing, keys can be bound to client actions: `cancel-pipeline`
+//! (Ctrl+C by default, also while a command runs), `clear-screen` and
+//! `detach-session`.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// What a bound key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditAction {
+    AcceptLine,
+    BackwardDeleteChar,
+    BackwardKillWord,
+    /// Erase the whole line typed so far.
+    KillLine,
+    /// Ask for tab completion (where the reader supports it).
+    Complete,
+    /// End of input, when the line is empty.
+    EndOfFile,
+    ClearScreen,
+    /// Stop the running command, or drop the line being typed.
+    CancelPipeline,
+    /// Leave the session running on the server and disconnect from it.
+    DetachSession,
+    ViCommandMode,
+    ViInsertMode,
+    /// Do nothing; binds a key away from its default.
+    Ignore,
+}
+
+impl EditAction {
+    const ALL: [Self; 12] = [
+        Self::AcceptLine,
+        Self::BackwardDeleteChar,
+        Self::BackwardKillWord,
+        Self::KillLine,
+        Self::Complete,
+        Self::EndOfFile,
+        Self::ClearScreen,
+        Self::CancelPipeline,
+        Self::DetachSession,
+        Self::ViCommandMode,
+        Self::ViInsertMode,
+        Self::Ignore,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::AcceptLine => "accept-line",
+            Self::BackwardDeleteChar => "backward-delete-char",
+            Self::BackwardKillWord => "backward-kill-word",
+            Self::KillLine => "kill-line",
+            Self::Complete => "complete",
+            Self::EndOfFile => "end-of-file",
+            Self::ClearScreen => "clear-screen",
+            Self::CancelPipeline => "cancel-pipeline",
+            Self::DetachSession => "detach-session",
+            Self::ViCommandMode => "vi-command-mode",
+            Self::ViInsertMode => "vi-insert-mode",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+impl fmt::Display for EditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown key action '{s}'"))
+    }
+}
+
+/// Editing style: emacs (modeless) or vi (insert and command modes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditMode {
+    #[default]
+    Emacs,
+    Vi,
+}
+
+impl FromStr for EditMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "emacs" => Ok(Self::Emacs),
+            "vi" => Ok(Self::Vi),
+            _ => Err(format!("unknown edit mode '{s}' (expected emacs or vi)")),
+        }
+    }
+}
+
+/// A key with its modifiers, e.g. `ctrl+l` or `alt+backspace`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of a typed character, and terminals report
+        // Ctrl+letter in either case.
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Self {
+                code: KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers: modifiers.difference(KeyModifiers::SHIFT),
+            },
+            KeyCode::Char(_) => Self {
+                code,
+                modifiers: modifiers.difference(KeyModifiers::SHIFT),
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key '{s}'");
+        // `ctrl++` names the plus key itself.
+        let (prefix, key) = s.strip_suffix("++").map_or_else(
+            || s.rsplit_once('+').unwrap_or(("", s)),
+            |prefix| (prefix, "+"),
+        );
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+        let code = match key.trim().to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            name => {
+                let mut chars = key.trim().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => name
+                        .strip_prefix('f')
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| (1..=24).contains(n))
+                        .map(KeyCode::F)
+                        .ok_or_else(invalid)?,
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// The keymaps the line editor reads keys through.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    mode: EditMode,
+    /// Emacs keymap, or vi insert mode.
+    insert: HashMap<KeyChord, EditAction>,
+    /// Vi command mode; empty in emacs mode.
+    command: HashMap<KeyChord, EditAction>,
+    overrides: Vec<(KeyChord, EditAction)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new(EditMode::default())
+    }
+}
+
+impl KeyBindings {
+    pub fn new(mode: EditMode) -> Self {
+        let mut bindings = Self {
+            mode,
+            insert: HashMap::new(),
+            command: HashMap::new(),
+            overrides: Vec::new(),
+        };
+        bindings.load_defaults();
+        bindings
+    }
+
+    pub fn mode(&self) -> EditMode {
+        self.mode
+    }
+
+    /// Switch keymaps; bindings made with [`bind`](Self::bind) are kept.
+    pub fn set_mode(&mut self, mode: EditMode) {
+        self.mode = mode;
+        self.load_defaults();
+        for (chord, action) in self.overrides.clone() {
+            self.insert_binding(chord, action);
+        }
+    }
+
+    /// Bind `chord` to `action`; in vi mode the binding applies in both
+    /// insert and command mode.
+    pub fn bind(&mut self, chord: KeyChord, action: EditAction) {
+        self.overrides.push((chord, action));
+        self.insert_binding(chord, action);
+    }
+
+    /// Apply one config line: `mode = emacs|vi` or `<key> = <action>`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if key.trim().eq_ignore_ascii_case("mode") {
+            self.set_mode(value.trim().parse()?);
+        } else {
+            self.bind(key.trim().parse()?, value.trim().parse()?);
+        }
+        Ok(())
+    }
+
+    /// The action bound to `key`, from the vi command keymap when
+    /// `vi_command` is set. `None` means the key inserts its character (if
+    /// it has one).
+    pub fn action(&self, key: &KeyEvent, vi_command: bool) -> Option<EditAction> {
+        let keymap = if vi_command && self.mode == EditMode::Vi {
+            &self.command
+        } else {
+            &self.insert
+        };
+        keymap.get(&KeyChord::from(key)).copied()
+    }
+
+    /// Whether `key` cancels the running command, in any mode.
+    pub fn is_cancel(&self, key: &KeyEvent) -> bool {
+        self.insert.get(&KeyChord::from(key)) == Some(&EditAction::CancelPipeline)
+    }
+
+    fn insert_binding(&mut self, chord: KeyChord, action: EditAction) {
+        self.insert.insert(chord, action);
+        if self.mode == EditMode::Vi {
+            self.command.insert(chord, action);
+        }
+    }
+
+    fn load_defaults(&mut self) {
+        let mut insert = HashMap::from([
+            (KeyChord::key(KeyCode::Enter), EditAction::AcceptLine),
+            (
+                KeyChord::key(KeyCode::Backspace),
+                EditAction::BackwardDeleteChar,
+            ),
+            (KeyChord::ctrl('h'), EditAction::BackwardDeleteChar),
+            (KeyChord::ctrl('w'), EditAction::BackwardKillWord),
+            (KeyChord::ctrl('u'), EditAction::KillLine),
+            (KeyChord::key(KeyCode::Tab), EditAction::Complete),
+            (KeyChord::ctrl('c'), EditAction::CancelPipeline),
+            (KeyChord::ctrl('d'), EditAction::EndOfFile),
+            (KeyChord::ctrl('l'), EditAction::ClearScreen),
+        ]);
+        if cfg!(windows) {
+            insert.insert(KeyChord::ctrl('z'), EditAction::EndOfFile);
+        }
+        if self.mode == EditMode::Emacs {
+            insert.insert(
+                KeyChord::new(KeyCode::Backspace, KeyModifiers::ALT),
+                EditAction::BackwardKillWord,
+            );
+            self.command = HashMap::new();
+        } else {
+            insert.insert(KeyChord::key(KeyCode::Esc), EditAction::ViCommandMode);
+            let mut command: HashMap<_, _> = insert
+                .iter()
+                .filter(|(_, action)| {
+                    !matches!(
+                        action,
+                        EditAction::BackwardKillWord
+                            | EditAction::KillLine
+                            | EditAction::Complete
+                            | EditAction::ViCommandMode
+                    )
+                })
+                .map(|(chord, action)| (*chord, *action))
+                .collect();
+            for c in ['i', 'a', 'I', 'A'] {
+                command.insert(KeyChord::key(KeyCode::Char(c)), EditAction::ViInsertMode);
+            }
+            for c in ['x', 'X'] {
+                command.insert(
+                    KeyChord::key(KeyCode::Char(c)),
+                    EditAction::BackwardDeleteChar,
+                );
+            }
+            command.insert(KeyChord::key(KeyCode::Char('D')), EditAction::KillLine);
+            self.command = command;
+        }
+        self.insert = insert;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn chords_parse_like_they_are_typed() {
+        assert_eq!(
+            "Ctrl+L".parse::<KeyChord>(),
+            Ok(KeyChord::from(&press(
+                KeyCode::Char('L'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT
+            )))
+        );
+        assert_eq!(
+            "alt+backspace".parse::<KeyChord>(),
+            Ok(KeyChord::new(KeyCode::Backspace, KeyModifiers::ALT))
+        );
+        assert_eq!("ctrl++".parse::<KeyChord>(), Ok(KeyChord::ctrl('+')));
+        assert_eq!("f5".parse::<KeyChord>(), Ok(KeyChord::key(KeyCode::F(5))));
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("ctrl+nope".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn emacs_defaults_and_overrides() {
+        let mut bindings = KeyBindings::default();
+        let ctrl_c = press(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let ctrl_x = press(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert!(bindings.is_cancel(&ctrl_c));
+        assert_eq!(
+            bindings.action(&press(KeyCode::Char('a'), KeyModifiers::NONE), false),
+            None
+        );
+
+        bindings.set("ctrl+x", "detach-session").unwrap();
+        bindings.set("ctrl+c", "ignore").unwrap();
+        bindings.set("ctrl+g", "cancel-pipeline").unwrap();
+        assert_eq!(
+            bindings.action(&ctrl_x, false),
+            Some(EditAction::DetachSession)
+        );
+        assert!(!bindings.is_cancel(&ctrl_c));
+        assert!(bindings.is_cancel(&press(KeyCode::Char('g'), KeyModifiers::CONTROL)));
+        assert!(bindings.set("ctrl+x", "launch-rockets").is_err());
+    }
+
+    #[test]
+    fn vi_mode_has_a_command_keymap_and_keeps_overrides() {
+        let mut bindings = KeyBindings::default();
+        bindings.set("ctrl+x", "detach-session").unwrap();
+        bindings.set("mode", "vi").unwrap();
+        assert_eq!(bindings.mode(), EditMode::Vi);
+
+        let esc = press(KeyCode::Esc, KeyModifiers::NONE);
+        let x = press(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(
+            bindings.action(&esc, false),
+            Some(EditAction::ViCommandMode)
+        );
+        assert_eq!(bindings.action(&x, false), None);
+        assert_eq!(
+            bindings.action(&x, true),
+            Some(EditAction::BackwardDeleteChar)
+        );
+        assert_eq!(
+            bindings.action(&press(KeyCode::Char('i'), KeyModifiers::NONE), true),
+            Some(EditAction::ViInsertMode)
+        );
+        let ctrl_x = press(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(
+            bindings.action(&ctrl_x, true),
+            Some(EditAction::DetachSession)
+        );
+    }
+}
@@ -1 +1,2 @@
+pub mod bindings;
 pub mod keys;
@@ -2,18 +2,43 @@ use anyhow::Result;
 use std::time::{Duration, Instant};
 use tracing::{debug, info, instrument, trace};
 
+pub mod cast;
+pub mod grid;
 pub mod input;
+pub mod pager;
+pub mod secret;
 pub mod stdio;
 pub mod term;
+pub mod text;
 
+pub use cast::{Cast, CastRecorder, ReplayOptions};
+pub use grid::{GridAction, GridView};
+pub use input::bindings::{EditAction, EditMode, KeyBindings, KeyChord};
+pub use pager::{MoreAction, Pager};
+pub use secret::read_secret;
 pub use stdio::{ReadOutcome, StdTerm};
-pub use term::{CrosstermRenderer, GuestTerm, HostRenderer, TerminalOp};
+pub use term::{
+    ClipboardPolicy, ClipboardRequest, CommandStatus, CrosstermRenderer, GuestTerm, HostEvent,
+    HostRenderer, MemoryRenderer, StatusLine, TerminalOp, restore_host_terminal,
+};
+
+/// Shortest time between two frames.
+const FRAME_INTERVAL: Duration = Duration::from_millis(8);
+/// The same in power saving while the window is out of focus.
+const UNFOCUSED_FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Told about focus changes in power saving; `true` when focus came back.
+pub type FocusListener = Box<dyn FnMut(bool) + Send>;
 
 /// Clean terminal pipeline with separated concerns
-pub struct Terminal {
+pub struct Terminal<R: HostRenderer = CrosstermRenderer> {
     guest: GuestTerm,
-    renderer: CrosstermRenderer,
+    renderer: R,
     last_render: Instant,
+    key_bindings: KeyBindings,
+    power_saving: bool,
+    focused: bool,
+    focus_listener: Option<FocusListener>,
 }
 
 impl Terminal {
@@ -21,25 +46,42 @@ impl Terminal {
     #[instrument]
     pub fn new(scrollback: usize) -> Result<Self> {
         info!("Initializing terminal emulator");
+        Self::with_renderer(CrosstermRenderer::new(), scrollback)
+    }
 
-        // Get host size for guest terminal
-        let (cols, rows) = crossterm::terminal::size()?;
+    /// Borrow a stdio-like handle. Scope it to release the &mut borrow when done.
+    pub fn stdio(&mut self) -> StdTerm<'_> {
+        StdTerm::new(self)
+    }
+}
+
+impl<R: HostRenderer> Terminal<R> {
+    /// Create a terminal drawing through `renderer`, sized to its host.
+    pub fn with_renderer(mut renderer: R, scrollback: usize) -> Result<Self> {
+        let (cols, rows) = renderer.host_size()?;
         info!(
             "Terminal initialized with size: {}x{}, scrollback: {}",
             cols, rows, scrollback
         );
 
         let guest = GuestTerm::new(rows, cols, scrollback);
-        let mut renderer = CrosstermRenderer::new();
         renderer.init()?;
 
         Ok(Self {
             guest,
             renderer,
             last_render: Instant::now(),
+            key_bindings: KeyBindings::default(),
+            power_saving: false,
+            focused: true,
+            focus_listener: None,
         })
     }
 
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
     /// Apply terminal operations to the guest
     pub fn apply_ops(&mut self, ops: Vec<TerminalOp>) {
         for op in ops {
@@ -53,25 +95,47 @@ impl Terminal {
         self.guest.apply(op);
     }
 
-    /// Update guest size when the host reports a resize event.
+    /// Update guest size when the host reports a resize event. The status
+    /// bar, when shown, keeps the bottom row.
     pub fn on_host_resize(&mut self, cols: u16, rows: u16) {
+        let rows = if self.guest.status_line().is_some() {
+            rows.saturating_sub(1).max(1)
+        } else {
+            rows
+        };
         self.apply_op(TerminalOp::Resize { rows, cols });
     }
 
+    /// Show a status bar on the bottom host row, or stop with `None`; the
+    /// guest screen shrinks or grows by that row.
+    pub fn set_status_line(&mut self, status: Option<StatusLine>) -> Result<()> {
+        self.guest.set_status_line(status);
+        let (cols, rows) = self.renderer.host_size()?;
+        self.on_host_resize(cols, rows);
+        Ok(())
+    }
+
+    pub fn status_line_mut(&mut self) -> Option<&mut StatusLine> {
+        self.guest.status_line_mut()
+    }
+
     /// Render the terminal if dirty
     pub fn render(&mut self) -> Result<()> {
         trace!(dirty = self.guest.is_dirty(), "Render called");
 
         // Simple throttle to avoid spamming the host terminal
-        if self.last_render.elapsed() < Duration::from_millis(8) {
+        let interval = if self.focused {
+            FRAME_INTERVAL
+        } else {
+            UNFOCUSED_FRAME_INTERVAL
+        };
+        if self.last_render.elapsed() < interval {
             trace!("Skipping render due to throttle");
             return Ok(());
         }
 
-        if let Some(bytes) = self.guest.take_render_bytes() {
+        if self.guest.render_to(&mut self.renderer)? {
             self.last_render = Instant::now();
-            trace!(bytes_len = bytes.len(), "Presenting bytes to renderer");
-            self.renderer.present(&bytes)?;
         }
 
         Ok(())
@@ -97,13 +161,73 @@ impl Terminal {
         self.guest.cell(row, col)
     }
 
-    /// Borrow a stdio-like handle. Scope it to release the &mut borrow when done.
-    pub fn stdio(&mut self) -> StdTerm<'_> {
-        StdTerm::new(self)
+    /// Record the session from now on in asciicast v2 form (`--record`);
+    /// `ironposh-client-tokio replay` plays it back.
+    pub fn start_recording(&mut self, path: &std::path::Path) -> Result<()> {
+        let (rows, cols) = self.guest.screen_size();
+        let recorder = CastRecorder::create(path, cols, rows)?;
+        info!(path = %path.display(), cols, rows, "recording the session");
+        self.guest.set_recorder(Some(recorder));
+        Ok(())
+    }
+
+    /// How OSC 52 clipboard writes from the remote are handled.
+    pub fn set_clipboard_policy(&mut self, policy: ClipboardPolicy) {
+        self.guest.set_clipboard_policy(policy);
+    }
+
+    pub fn take_clipboard_request(&mut self) -> Option<ClipboardRequest> {
+        self.guest.take_clipboard_request()
+    }
+
+    pub fn approve_clipboard(&mut self, request: ClipboardRequest) {
+        self.guest.approve_clipboard(request);
+    }
+
+    /// Power saving (`--power-saving`): while the host window is out of
+    /// focus, frames are drawn at most four times a second and the focus
+    /// listener is told, so the session can poll the server less often too.
+    pub fn set_power_saving(&mut self, on: bool) -> Result<()> {
+        self.renderer.report_focus(on)?;
+        self.power_saving = on;
+        if !on {
+            self.on_focus(true)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_focus_listener(&mut self, listener: Option<FocusListener>) {
+        self.focus_listener = listener;
+    }
+
+    /// The host window gained or lost focus. Ignored unless power saving is
+    /// on; regaining focus draws what was held back right away.
+    pub fn on_focus(&mut self, focused: bool) -> Result<()> {
+        if focused == self.focused || (!focused && !self.power_saving) {
+            return Ok(());
+        }
+        debug!(focused, "host focus changed");
+        self.focused = focused;
+        if let Some(listener) = self.focus_listener.as_mut() {
+            listener(focused);
+        }
+        if focused && self.guest.render_to(&mut self.renderer)? {
+            self.last_render = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Keys used by line reads from now on.
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings) {
+        self.key_bindings = key_bindings;
+    }
+
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
     }
 }
 
-impl Drop for Terminal {
+impl<R: HostRenderer> Drop for Terminal<R> {
     fn drop(&mut self) {
         self.renderer.deinit();
     }
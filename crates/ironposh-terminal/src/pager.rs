@@ -0,0 +1,170 @@
+//! `more`-style paging of command output.
+//!
+//! [`Pager`] only does the bookkeeping: it counts the screen rows written
+//! since the last pause and says when the page is full.
+//! [`StdTerm::write_paged`](crate::StdTerm::write_paged) puts the
+//! `-- More --` prompt in front of the user at that point.
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::text::display_width;
+
+/// What the user asked for at the `-- More --` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoreAction {
+    /// Space / PageDown: show the next screenful.
+    NextPage,
+    /// Enter / Down: show one more line.
+    NextLine,
+    /// `q` / Esc / Ctrl+C: drop the rest of the output.
+    Quit,
+}
+
+impl MoreAction {
+    /// Map a host event to a pager action; other events are ignored.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let Event::Key(KeyEvent {
+            kind: KeyEventKind::Press,
+            code,
+            modifiers,
+            ..
+        }) = event
+        else {
+            return None;
+        };
+
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Some(Self::Quit),
+            KeyCode::Char(' ') | KeyCode::PageDown => Some(Self::NextPage),
+            KeyCode::Enter | KeyCode::Down => Some(Self::NextLine),
+            KeyCode::Char('q' | 'Q') | KeyCode::Esc => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Row accounting for one paged command.
+#[derive(Debug, Clone)]
+pub struct Pager {
+    /// Rows available per page; the last screen row holds the prompt.
+    page_rows: usize,
+    cols: usize,
+    used_rows: usize,
+    quit: bool,
+}
+
+impl Pager {
+    /// A pager for a screen of `rows` x `cols`.
+    pub fn new(rows: u16, cols: u16) -> Self {
+        let mut pager = Self {
+            page_rows: 1,
+            cols: 1,
+            used_rows: 0,
+            quit: false,
+        };
+        pager.resize(rows, cols);
+        pager
+    }
+
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.page_rows = usize::from(rows).saturating_sub(1).max(1);
+        self.cols = usize::from(cols).max(1);
+    }
+
+    /// Whether the user quit; everything written afterwards is dropped.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Count `line` (without its line break) against the current page.
+    /// Returns `false`, without counting it, when it no longer fits and the
+    /// user has to be asked first. A line taller than a whole page always
+    /// fits on an empty one.
+    pub fn admit(&mut self, line: &str) -> bool {
+        let rows = display_width(line).div_ceil(self.cols).max(1);
+        if self.used_rows > 0 && self.used_rows + rows > self.page_rows {
+            return false;
+        }
+        self.used_rows += rows;
+        true
+    }
+
+    pub fn apply(&mut self, action: MoreAction) {
+        match action {
+            MoreAction::NextPage => self.used_rows = 0,
+            MoreAction::NextLine => self.used_rows = self.page_rows.saturating_sub(1),
+            MoreAction::Quit => self.quit = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_page(pager: &mut Pager) -> usize {
+        let mut admitted = 0;
+        while pager.admit("line") {
+            admitted += 1;
+        }
+        admitted
+    }
+
+    #[test]
+    fn pauses_one_row_short_of_the_screen() {
+        let mut pager = Pager::new(5, 80);
+        assert_eq!(fill_page(&mut pager), 4);
+    }
+
+    #[test]
+    fn next_page_and_next_line_release_the_pause() {
+        let mut pager = Pager::new(5, 80);
+        fill_page(&mut pager);
+
+        pager.apply(MoreAction::NextLine);
+        assert_eq!(fill_page(&mut pager), 1);
+
+        pager.apply(MoreAction::NextPage);
+        assert_eq!(fill_page(&mut pager), 4);
+    }
+
+    #[test]
+    fn wrapped_lines_take_several_rows() {
+        let mut pager = Pager::new(5, 10);
+        assert!(pager.admit(&"x".repeat(25)));
+        assert!(pager.admit("tail"));
+        assert!(!pager.admit("one too many"));
+    }
+
+    #[test]
+    fn a_line_taller_than_the_page_still_gets_shown() {
+        let mut pager = Pager::new(3, 10);
+        assert!(pager.admit(&"x".repeat(100)));
+        assert!(!pager.admit("next"));
+    }
+
+    #[test]
+    fn maps_more_keys() {
+        let key = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+        assert_eq!(
+            MoreAction::from_event(&key(KeyCode::Char(' '), KeyModifiers::NONE)),
+            Some(MoreAction::NextPage)
+        );
+        assert_eq!(
+            MoreAction::from_event(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(MoreAction::NextLine)
+        );
+        assert_eq!(
+            MoreAction::from_event(&key(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Some(MoreAction::Quit)
+        );
+        assert_eq!(
+            MoreAction::from_event(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(MoreAction::Quit)
+        );
+        assert_eq!(
+            MoreAction::from_event(&key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            None
+        );
+    }
+}
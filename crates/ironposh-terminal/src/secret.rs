@@ -0,0 +1,90 @@
+//! Reading a secret (a smart card PIN) from the console before the session
+//! takes over the terminal.
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{self, Write as _};
+
+/// Print `prompt` on stderr and read one line without echoing it. Escape and
+/// Ctrl+C cancel with [`io::ErrorKind::Interrupted`].
+pub fn read_secret(prompt: &str) -> io::Result<String> {
+    let mut stderr = io::stderr();
+    write!(stderr, "{prompt}")?;
+    stderr.flush()?;
+
+    // The session's terminal may already be in raw mode; leave it as found.
+    let was_raw = terminal::is_raw_mode_enabled()?;
+    terminal::enable_raw_mode()?;
+    let result = read_hidden_line();
+    if !was_raw {
+        terminal::disable_raw_mode()?;
+    }
+    write!(stderr, "\r\n")?;
+    result
+}
+
+fn read_hidden_line() -> io::Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match edit(&mut line, key) {
+            Some(Ok(())) => return Ok(line),
+            Some(Err(kind)) => return Err(io::Error::from(kind)),
+            None => {}
+        }
+    }
+}
+
+/// Apply `key` to `line`. `Some` once the line is entered or cancelled.
+fn edit(line: &mut String, key: KeyEvent) -> Option<Result<(), io::ErrorKind>> {
+    match key.code {
+        KeyCode::Enter => Some(Ok(())),
+        KeyCode::Esc => Some(Err(io::ErrorKind::Interrupted)),
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Err(io::ErrorKind::Interrupted))
+        }
+        KeyCode::Char(c) => {
+            line.push(c);
+            None
+        }
+        KeyCode::Backspace => {
+            line.pop();
+            None
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_edit_the_hidden_line() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut line = String::new();
+        for code in [
+            KeyCode::Char('1'),
+            KeyCode::Char('2'),
+            KeyCode::Char('x'),
+            KeyCode::Backspace,
+            KeyCode::Left,
+        ] {
+            assert_eq!(edit(&mut line, key(code)), None);
+        }
+        assert_eq!(line, "12");
+        assert_eq!(edit(&mut line, key(KeyCode::Enter)), Some(Ok(())));
+        assert_eq!(
+            edit(
+                &mut line,
+                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+            ),
+            Some(Err(io::ErrorKind::Interrupted))
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{Terminal, TerminalOp};
+use crate::grid::{GridAction, GridView};
+use crate::input::bindings::{EditAction, KeyBindings};
+use crate::pager::{MoreAction, Pager};
+use crate::text::{display_width, pop_grapheme};
+use crate::{ClipboardRequest, StatusLine, Terminal, TerminalOp};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use std::collections::VecDeque;
 use std::io::{self, Write as IoWrite};
@@ -9,6 +13,8 @@ pub enum ReadOutcome {
     Line(String),
     Interrupt, // ^C
     Eof,       // ^D (UNIX) / ^Z (Windows)
+    /// A `detach-session` key was pressed.
+    Detach,
 }
 
 /// Stdio-like wrapper that borrows a Terminal for ergonomic input/output
@@ -17,6 +23,8 @@ pub struct StdTerm<'a> {
     buf: Vec<u8>,
     auto_render: bool,      // paint after each flush/println
     flush_on_newline: bool, // common stdio behavior
+    prompt: String,         // redrawn by clear-screen
+    vi_command: bool,       // vi command mode is active
 }
 
 impl<'a> StdTerm<'a> {
@@ -26,6 +34,8 @@ impl<'a> StdTerm<'a> {
             buf: Vec::new(),
             auto_render: true,
             flush_on_newline: true,
+            prompt: String::new(),
+            vi_command: false,
         }
     }
 
@@ -49,6 +59,23 @@ impl<'a> StdTerm<'a> {
         self.term.guest_cell(row, col)
     }
 
+    /// The host was resized to `cols` x `rows`.
+    pub fn on_host_resize(&mut self, cols: u16, rows: u16) {
+        self.term.on_host_resize(cols, rows);
+    }
+
+    pub fn status_line_mut(&mut self) -> Option<&mut StatusLine> {
+        self.term.status_line_mut()
+    }
+
+    pub fn take_clipboard_request(&mut self) -> Option<ClipboardRequest> {
+        self.term.take_clipboard_request()
+    }
+
+    pub fn approve_clipboard(&mut self, request: ClipboardRequest) {
+        self.term.approve_clipboard(request);
+    }
+
     pub fn set_auto_render(&mut self, on: bool) {
         self.auto_render = on;
     }
@@ -75,8 +102,8 @@ impl<'a> StdTerm<'a> {
     }
 
     /// Shared event handler for line editing and one-off checks.
-    /// When `edit_line` is false, printable/paste/backspace are ignored and we only
-    /// react to Enter / Ctrl+C / Ctrl+D(^Z on Windows) / Resize.
+    /// When `edit_line` is false, printable/paste/editing keys are ignored and we only
+    /// react to accept / cancel / end-of-file / detach / Resize.
     fn process_event(
         &mut self,
         line: &mut String,
@@ -91,12 +118,46 @@ impl<'a> StdTerm<'a> {
                 Ok(None)
             }
 
-            // ---- ENTER ----
-            Event::Key(KeyEvent {
-                kind: KeyEventKind::Press,
-                code: KeyCode::Enter,
-                ..
-            }) => {
+            Event::FocusGained | Event::FocusLost => {
+                self.on_focus(&evt)?;
+                Ok(None)
+            }
+
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.process_key(line, key, edit_line)
+            }
+
+            // ---- Paste ----
+            // Kept verbatim, newlines included: the whole paste is submitted
+            // as one line when the user accepts it.
+            Event::Paste(s) if edit_line => {
+                let pasted = normalize_paste(&s);
+                line.push_str(&pasted);
+                self.write_all(pasted.as_bytes())?;
+                self.flush()?;
+                Ok(None)
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    fn on_focus(&mut self, evt: &Event) -> io::Result<()> {
+        self.term
+            .on_focus(matches!(evt, Event::FocusGained))
+            .map_err(io::Error::other)
+    }
+
+    /// Run the action bound to `key` (see [`KeyBindings`]); unbound keys
+    /// insert their character.
+    fn process_key(
+        &mut self,
+        line: &mut String,
+        key: KeyEvent,
+        edit_line: bool,
+    ) -> io::Result<Option<ReadOutcome>> {
+        match self.term.key_bindings.action(&key, self.vi_command) {
+            Some(EditAction::AcceptLine) => {
                 self.write_all(b"\r\n")?;
                 self.flush()?;
                 // Return accumulated line when editing; empty string in one-off mode.
@@ -107,43 +168,13 @@ impl<'a> StdTerm<'a> {
                 };
                 Ok(Some(ReadOutcome::Line(out)))
             }
-
-            // ---- BACKSPACE ----
-            Event::Key(KeyEvent {
-                kind: KeyEventKind::Press,
-                code: KeyCode::Backspace,
-                ..
-            }) if edit_line => {
-                if !line.is_empty() {
-                    line.pop();
-                    self.write_all(b"\x08 \x08")?; // BS, erase, BS
-                    self.flush()?;
-                }
-                Ok(None)
-            }
-
-            // ---- CTRL+C → Interrupt ----
-            Event::Key(KeyEvent {
-                kind: KeyEventKind::Press,
-                code: KeyCode::Char('c'),
-                modifiers,
-                ..
-            }) if modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(EditAction::CancelPipeline) => {
                 // Visual ACK like real shells:
                 self.write_all(b"^C\r\n")?;
                 self.flush()?;
                 Ok(Some(ReadOutcome::Interrupt))
             }
-
-            // ---- CTRL+D / CTRL+Z → EOF ----
-            Event::Key(KeyEvent {
-                kind: KeyEventKind::Press,
-                code: KeyCode::Char(ch),
-                modifiers,
-                ..
-            }) if modifiers.contains(KeyModifiers::CONTROL)
-                && (ch == 'd' || (cfg!(windows) && ch == 'z')) =>
-            {
+            Some(EditAction::EndOfFile) => {
                 // Only emit EOF if the current line is empty (or we're in one-off mode).
                 if !edit_line || line.is_empty() {
                     self.write_all(b"\r\n")?;
@@ -153,32 +184,92 @@ impl<'a> StdTerm<'a> {
                     Ok(None)
                 }
             }
-
-            // ---- Printable ----
-            Event::Key(KeyEvent {
-                kind: KeyEventKind::Press,
-                code: KeyCode::Char(c),
-                modifiers,
-                ..
-            }) if edit_line && !modifiers.contains(KeyModifiers::CONTROL) => {
-                let mut buf = [0u8; 4];
-                let s = c.encode_utf8(&mut buf);
-                line.push(c);
-                self.write_all(s.as_bytes())?;
+            Some(EditAction::DetachSession) => {
+                self.write_all(b"\r\n")?;
                 self.flush()?;
+                Ok(Some(ReadOutcome::Detach))
+            }
+            Some(action) if edit_line => {
+                self.edit(line, action)?;
                 Ok(None)
             }
-
-            // ---- Paste ----
-            Event::Paste(s) if edit_line => {
-                line.push_str(&s);
-                self.write_all(s.as_bytes())?;
-                self.flush()?;
+            Some(_) => Ok(None),
+            None => {
+                let printable = match key.code {
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(c),
+                    _ => None,
+                };
+                if let Some(c) = printable
+                    && edit_line
+                    && !self.vi_command
+                {
+                    let mut buf = [0u8; 4];
+                    let s = c.encode_utf8(&mut buf);
+                    line.push(c);
+                    self.write_all(s.as_bytes())?;
+                    self.flush()?;
+                }
                 Ok(None)
             }
+        }
+    }
 
-            _ => Ok(None),
+    /// Apply an editing action to the line being typed.
+    fn edit(&mut self, line: &mut String, action: EditAction) -> io::Result<()> {
+        let erase = match action {
+            EditAction::BackwardDeleteChar => pop_grapheme(line).unwrap_or(0),
+            EditAction::BackwardKillWord => {
+                let kept = line
+                    .trim_end()
+                    .trim_end_matches(|c: char| !c.is_whitespace());
+                let erased = display_width(&line[kept.len()..]);
+                line.truncate(kept.len());
+                erased
+            }
+            EditAction::KillLine => {
+                let erased = display_width(line);
+                line.clear();
+                erased
+            }
+            EditAction::ClearScreen => {
+                self.write_all(b"\x1b[2J\x1b[H")?;
+                let prompt = std::mem::take(&mut self.prompt);
+                self.write_all(prompt.as_bytes())?;
+                self.prompt = prompt;
+                self.write_all(line.as_bytes())?;
+                0
+            }
+            EditAction::ViCommandMode => {
+                self.vi_command = true;
+                0
+            }
+            EditAction::ViInsertMode => {
+                self.vi_command = false;
+                0
+            }
+            EditAction::AcceptLine
+            | EditAction::Complete
+            | EditAction::EndOfFile
+            | EditAction::CancelPipeline
+            | EditAction::DetachSession
+            | EditAction::Ignore => 0,
+        };
+        for _ in 0..erase {
+            self.write_all(b"\x08 \x08")?; // BS, erase, BS: once per column
         }
+        self.flush()
+    }
+
+    /// Show `prompt` and start editing a new line.
+    fn begin_line(&mut self, prompt: &str) -> io::Result<()> {
+        prompt.clone_into(&mut self.prompt);
+        self.vi_command = false;
+        if !prompt.is_empty() {
+            self.write_all(b"\r")?; // ensure column 0
+            self.write_all(prompt.as_bytes())?;
+            self.flush()?; // show prompt
+        }
+        Ok(())
     }
 
     fn next_event_from_queue_or_host(
@@ -213,12 +304,13 @@ impl<'a> StdTerm<'a> {
 
     /// Non-blocking interrupt check that does not steal typed input.
     ///
-    /// Scans `queue` and any host events pending right now for Ctrl+C. Every
+    /// Scans `queue` and any host events pending right now for the
+    /// `cancel-pipeline` key (Ctrl+C by default). Every
     /// other key event is pushed onto `queue` so the next read still sees it;
-    /// resize events are applied immediately. Returns `true` when a Ctrl+C
-    /// was consumed.
+    /// resize and focus events are applied immediately. Returns `true` when a cancel
+    /// key was consumed.
     pub fn check_interrupt_queued(&mut self, queue: &mut VecDeque<Event>) -> io::Result<bool> {
-        if take_queued_interrupt(queue) {
+        if take_queued_interrupt(queue, &self.term.key_bindings) {
             self.write_all(b"^C\r\n")?;
             self.flush()?;
             return Ok(true);
@@ -230,7 +322,8 @@ impl<'a> StdTerm<'a> {
                     self.term.on_host_resize(cols, rows);
                     self.term.render().map_err(io::Error::other)?;
                 }
-                evt if is_interrupt_event(&evt) => {
+                evt @ (Event::FocusGained | Event::FocusLost) => self.on_focus(&evt)?,
+                evt if is_interrupt_event(&evt, &self.term.key_bindings) => {
                     self.write_all(b"^C\r\n")?;
                     self.flush()?;
                     return Ok(true);
@@ -244,11 +337,7 @@ impl<'a> StdTerm<'a> {
 
     /// Line-buffered input with prompt. Filters key repeats; supports paste.
     pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadOutcome> {
-        if !prompt.is_empty() {
-            self.write_all(b"\r")?; // ensure column 0
-            self.write_all(prompt.as_bytes())?;
-            self.flush()?; // show prompt
-        }
+        self.begin_line(prompt)?;
 
         let mut line = String::new();
 
@@ -278,11 +367,7 @@ impl<'a> StdTerm<'a> {
         prompt: &str,
         queue: &mut VecDeque<Event>,
     ) -> io::Result<ReadOutcome> {
-        if !prompt.is_empty() {
-            self.write_all(b"\r")?; // ensure column 0
-            self.write_all(prompt.as_bytes())?;
-            self.flush()?; // show prompt
-        }
+        self.begin_line(prompt)?;
 
         let mut line = String::new();
 
@@ -309,11 +394,7 @@ impl<'a> StdTerm<'a> {
         queue: &mut VecDeque<Event>,
         mut tab_complete: impl FnMut(&str, usize) -> io::Result<Option<String>>,
     ) -> io::Result<ReadOutcome> {
-        if !prompt.is_empty() {
-            self.write_all(b"\r")?; // ensure column 0
-            self.write_all(prompt.as_bytes())?;
-            self.flush()?; // show prompt
-        }
+        self.begin_line(prompt)?;
 
         let mut line = String::new();
 
@@ -321,11 +402,10 @@ impl<'a> StdTerm<'a> {
             if let Some(evt) =
                 Self::next_event_from_queue_or_host(queue, Duration::from_millis(50))?
             {
-                if let Event::Key(KeyEvent {
-                    kind: KeyEventKind::Press,
-                    code: KeyCode::Tab,
-                    ..
-                }) = evt
+                if let Event::Key(key) = &evt
+                    && key.kind == KeyEventKind::Press
+                    && self.term.key_bindings.action(key, self.vi_command)
+                        == Some(EditAction::Complete)
                 {
                     let cursor_utf16 = line.encode_utf16().count();
                     if let Some(new_line) = tab_complete(&line, cursor_utf16)? {
@@ -353,31 +433,136 @@ impl<'a> StdTerm<'a> {
             }
         }
     }
+
+    /// Write `text` (plus a line break when `newline`) through `pager`,
+    /// pausing at a `-- More --` prompt whenever a page is full.
+    ///
+    /// Blocks until the user lets the output through. Returns `false` once
+    /// the user quit the pager; the rest of `text` is dropped then.
+    pub fn write_paged(
+        &mut self,
+        pager: &mut Pager,
+        text: &str,
+        newline: bool,
+    ) -> io::Result<bool> {
+        if pager.has_quit() {
+            return Ok(false);
+        }
+
+        let mut rest = text;
+        loop {
+            let next_break = rest.find('\n');
+            let (line, line_break) = next_break.map_or((rest, newline), |pos| (&rest[..pos], true));
+
+            if line_break {
+                while !pager.admit(line.trim_end_matches('\r')) {
+                    let action = self.more_prompt(pager)?;
+                    pager.apply(action);
+                    if pager.has_quit() {
+                        return Ok(false);
+                    }
+                }
+            }
+            self.write_all(line.as_bytes())?;
+            if line_break {
+                self.write_all(b"\n")?;
+            }
+
+            match next_break {
+                Some(pos) => rest = &rest[pos + 1..],
+                None => break,
+            }
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Show `-- More --` and wait for a pager key. Resizes are applied to
+    /// both the terminal and `pager` while waiting.
+    fn more_prompt(&mut self, pager: &mut Pager) -> io::Result<MoreAction> {
+        self.write_all(b"\x1b[7m-- More --\x1b[0m")?;
+        self.flush()?;
+
+        let action = loop {
+            if event::poll(Duration::from_millis(50))? {
+                match event::read()? {
+                    Event::Resize(cols, rows) => {
+                        self.term.on_host_resize(cols, rows);
+                        pager.resize(rows, cols);
+                    }
+                    evt @ (Event::FocusGained | Event::FocusLost) => self.on_focus(&evt)?,
+                    evt => {
+                        if let Some(action) = MoreAction::from_event(&evt) {
+                            break action;
+                        }
+                    }
+                }
+            }
+            self.term.render().map_err(io::Error::other)?;
+        };
+
+        self.write_all(b"\r\x1b[2K")?;
+        self.flush()?;
+        Ok(action)
+    }
+
+    /// Show `grid` on the alternate screen until the user closes it, then
+    /// restore the screen as it was.
+    pub fn show_grid(&mut self, grid: &mut GridView) -> io::Result<()> {
+        self.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        let mut redraw = true;
+
+        loop {
+            if redraw {
+                let (rows, cols) = self.term.guest_screen_size();
+                self.write_all(grid.render(rows, cols).as_bytes())?;
+                self.flush()?;
+                redraw = false;
+            }
+
+            if event::poll(Duration::from_millis(50))? {
+                match event::read()? {
+                    Event::Resize(cols, rows) => {
+                        self.term.on_host_resize(cols, rows);
+                        redraw = true;
+                    }
+                    evt @ (Event::FocusGained | Event::FocusLost) => self.on_focus(&evt)?,
+                    Event::Key(key) => match grid.handle_key(key) {
+                        GridAction::Close => break,
+                        GridAction::Redraw => redraw = true,
+                        GridAction::Ignore => {}
+                    },
+                    _ => {}
+                }
+            }
+            self.term.render().map_err(io::Error::other)?;
+        }
+
+        self.write_all(b"\x1b[?25h\x1b[?1049l")?;
+        self.flush()
+    }
 }
 
-fn is_interrupt_event(evt: &Event) -> bool {
-    matches!(
-        evt,
-        Event::Key(KeyEvent {
-            kind: KeyEventKind::Press,
-            code: KeyCode::Char('c'),
-            modifiers,
-            ..
-        }) if modifiers.contains(KeyModifiers::CONTROL)
-    )
+fn is_interrupt_event(evt: &Event, bindings: &KeyBindings) -> bool {
+    matches!(evt, Event::Key(key) if key.kind == KeyEventKind::Press && bindings.is_cancel(key))
 }
 
-/// Remove the first queued Ctrl+C, leaving every other event untouched.
-fn take_queued_interrupt(queue: &mut VecDeque<Event>) -> bool {
+/// Remove the first queued cancel key, leaving every other event untouched.
+fn take_queued_interrupt(queue: &mut VecDeque<Event>, bindings: &KeyBindings) -> bool {
     queue
         .iter()
-        .position(is_interrupt_event)
+        .position(|evt| is_interrupt_event(evt, bindings))
         .is_some_and(|pos| {
             queue.remove(pos);
             true
         })
 }
 
+/// Terminals deliver pasted line breaks as `\r` or `\r\n`; the line keeps `\n`.
+fn normalize_paste(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 impl IoWrite for StdTerm<'_> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         // Normalize newlines: LF -> CRLF unless already CRLF
@@ -421,9 +606,34 @@ mod tests {
 
     #[test]
     fn plain_chars_are_not_interrupts() {
-        assert!(!is_interrupt_event(&key(':', KeyModifiers::NONE)));
-        assert!(!is_interrupt_event(&key('c', KeyModifiers::NONE)));
-        assert!(is_interrupt_event(&key('c', KeyModifiers::CONTROL)));
+        let bindings = KeyBindings::default();
+        assert!(!is_interrupt_event(
+            &key(':', KeyModifiers::NONE),
+            &bindings
+        ));
+        assert!(!is_interrupt_event(
+            &key('c', KeyModifiers::NONE),
+            &bindings
+        ));
+        assert!(is_interrupt_event(
+            &key('c', KeyModifiers::CONTROL),
+            &bindings
+        ));
+    }
+
+    #[test]
+    fn interrupts_follow_the_cancel_binding() {
+        let mut bindings = KeyBindings::default();
+        bindings.set("ctrl+c", "ignore").unwrap();
+        bindings.set("ctrl+g", "cancel-pipeline").unwrap();
+        assert!(!is_interrupt_event(
+            &key('c', KeyModifiers::CONTROL),
+            &bindings
+        ));
+        assert!(is_interrupt_event(
+            &key('g', KeyModifiers::CONTROL),
+            &bindings
+        ));
     }
 
     #[test]
@@ -431,7 +641,7 @@ mod tests {
         let mut queue: VecDeque<Event> =
             [key(':', KeyModifiers::NONE), key('d', KeyModifiers::NONE)].into();
 
-        assert!(!take_queued_interrupt(&mut queue));
+        assert!(!take_queued_interrupt(&mut queue, &KeyBindings::default()));
         assert_eq!(queue.len(), 2);
         assert_eq!(queue.front(), Some(&key(':', KeyModifiers::NONE)));
     }
@@ -445,11 +655,19 @@ mod tests {
         ]
         .into();
 
-        assert!(take_queued_interrupt(&mut queue));
+        assert!(take_queued_interrupt(&mut queue, &KeyBindings::default()));
         let remaining: Vec<Event> = queue.into_iter().collect();
         assert_eq!(
             remaining,
             vec![key(':', KeyModifiers::NONE), key('d', KeyModifiers::NONE)]
         );
     }
+
+    #[test]
+    fn pasted_line_breaks_become_newlines() {
+        assert_eq!(
+            normalize_paste("Get-Date\r\nGet-Item .\rdir\n"),
+            "Get-Date\nGet-Item .\ndir\n"
+        );
+    }
 }
@@ -0,0 +1,236 @@
+//! Host-side controls in the output stream.
+//!
+//! Picks out the controls the guest emulator has no use for: the bell,
+//! window title changes (OSC 0/2), OSC 8 hyperlinks and OSC 52 clipboard
+//! writes. The bytes themselves still go to the emulator; this only watches
+//! them.
+
+use tracing::warn;
+
+/// Longest OSC payload kept; anything longer is not a title or a link
+/// worth passing on.
+const MAX_OSC_LEN: usize = 8 * 1024;
+
+/// Longest OSC 52 payload kept: base64 for about 750 KiB of text. A longer
+/// copy is dropped whole, since a cut one would decode to the wrong text.
+pub const MAX_CLIPBOARD_LEN: usize = 1024 * 1024;
+
+const BEL: u8 = 0x07;
+const ESC: u8 = 0x1b;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Control {
+    Bell,
+    Title(String),
+    /// A link starts at the cursor; `None` ends the open one.
+    Hyperlink(Option<String>),
+    /// Set the clipboard to this base64-encoded text.
+    Clipboard(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+    /// DCS, SOS, PM or APC: skipped up to its ST.
+    Str,
+    StrEscape,
+}
+
+/// Streaming decoder; sequences may be split across any number of pushes.
+#[derive(Debug)]
+pub struct ControlDecoder {
+    state: State,
+    osc: Vec<u8>,
+    /// The open OSC went past its length limit.
+    overflowed: bool,
+}
+
+impl Default for ControlDecoder {
+    fn default() -> Self {
+        Self {
+            state: State::Ground,
+            osc: Vec::new(),
+            overflowed: false,
+        }
+    }
+}
+
+impl ControlDecoder {
+    /// Feed one byte; returns the control it completes, if any.
+    pub fn push(&mut self, byte: u8) -> Option<Control> {
+        match self.state {
+            State::Ground => match byte {
+                BEL => return Some(Control::Bell),
+                ESC => self.state = State::Escape,
+                _ => {}
+            },
+            State::Escape => {
+                self.state = match byte {
+                    b']' => {
+                        self.osc.clear();
+                        self.overflowed = false;
+                        State::Osc
+                    }
+                    b'P' | b'X' | b'^' | b'_' => State::Str,
+                    ESC => State::Escape,
+                    _ => State::Ground,
+                };
+            }
+            State::Osc => match byte {
+                BEL => {
+                    self.state = State::Ground;
+                    return self.finish_osc();
+                }
+                ESC => self.state = State::OscEscape,
+                CAN | SUB => self.state = State::Ground,
+                _ if self.osc.len() < self.osc_limit() => self.osc.push(byte),
+                _ => self.overflowed = true,
+            },
+            State::OscEscape => {
+                if byte == b'\\' {
+                    self.state = State::Ground;
+                    return self.finish_osc();
+                }
+                // An unterminated OSC; the ESC starts the next sequence.
+                self.state = State::Escape;
+                return self.push(byte);
+            }
+            State::Str => match byte {
+                ESC => self.state = State::StrEscape,
+                CAN | SUB => self.state = State::Ground,
+                _ => {}
+            },
+            State::StrEscape => {
+                if byte == b'\\' {
+                    self.state = State::Ground;
+                } else {
+                    self.state = State::Escape;
+                    return self.push(byte);
+                }
+            }
+        }
+        None
+    }
+
+    fn osc_limit(&self) -> usize {
+        if self.osc.starts_with(b"52;") {
+            MAX_CLIPBOARD_LEN + 64
+        } else {
+            MAX_OSC_LEN
+        }
+    }
+
+    fn finish_osc(&self) -> Option<Control> {
+        let payload = String::from_utf8_lossy(&self.osc);
+        let (code, rest) = payload.split_once(';')?;
+        match code {
+            "0" | "2" => Some(Control::Title(printable(rest))),
+            // `8;params;uri`, an empty uri closing the link.
+            "8" => {
+                let (_params, uri) = rest.split_once(';')?;
+                let uri = printable(uri);
+                Some(Control::Hyperlink((!uri.is_empty()).then_some(uri)))
+            }
+            // `52;selection;base64`. A `?` asks for the local clipboard, which
+            // the remote is never given.
+            "52" => {
+                let (_selection, data) = rest.split_once(';')?;
+                if self.overflowed {
+                    warn!(
+                        limit = MAX_CLIPBOARD_LEN,
+                        "clipboard write over the size limit dropped"
+                    );
+                    return None;
+                }
+                let valid = !data.is_empty()
+                    && data
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='));
+                valid.then(|| Control::Clipboard(data.to_owned()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Remote text is re-emitted to the host terminal: drop control characters
+/// so it cannot smuggle in sequences of its own.
+fn printable(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(chunks: &[&[u8]]) -> Vec<Control> {
+        let mut decoder = ControlDecoder::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter())
+            .filter_map(|&byte| decoder.push(byte))
+            .collect()
+    }
+
+    #[test]
+    fn bell_title_and_hyperlinks_are_decoded() {
+        assert_eq!(
+            decode(&[
+                b"done\x07",
+                b"\x1b]0;PS C:\\>\x07",
+                b"\x1b]8;id=1;https://example.com\x1b\\here\x1b]8;;\x1b\\",
+            ]),
+            [
+                Control::Bell,
+                Control::Title("PS C:\\>".to_owned()),
+                Control::Hyperlink(Some("https://example.com".to_owned())),
+                Control::Hyperlink(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequences_split_across_chunks_are_decoded() {
+        assert_eq!(
+            decode(&[b"\x1b]", b"2;ti", b"tle\x1b", b"\\"]),
+            [Control::Title("title".to_owned())]
+        );
+    }
+
+    #[test]
+    fn other_sequences_do_not_ring_the_bell() {
+        // The BEL terminating an OSC is not a bell, nor is a BEL inside a DCS.
+        assert_eq!(
+            decode(&[b"\x1b]4;1;rgb:ff/00/00\x07\x1bP\x07q\x1b\\\x1b[31mred"]),
+            []
+        );
+        // An OSC cut short by another sequence is dropped.
+        assert_eq!(decode(&[b"\x1b]0;a\x1b[m\x07"]), [Control::Bell]);
+    }
+
+    #[test]
+    fn clipboard_writes_are_decoded_and_oversized_ones_dropped() {
+        assert_eq!(
+            decode(&[b"\x1b]52;c;aGVsbG8=\x07", b"\x1b]52;c;?\x07"]),
+            [Control::Clipboard("aGVsbG8=".to_owned())]
+        );
+
+        let mut huge = b"\x1b]52;c;".to_vec();
+        huge.resize(huge.len() + MAX_CLIPBOARD_LEN + 100, b'A');
+        huge.extend_from_slice(b"\x1b\\\x1b]52;c;b2s=\x1b\\");
+        assert_eq!(decode(&[&huge]), [Control::Clipboard("b2s=".to_owned())]);
+    }
+
+    #[test]
+    fn control_characters_are_stripped_from_titles() {
+        assert_eq!(
+            decode(&[b"\x1b]2;evil\x08\x7f title\x07"]),
+            [Control::Title("evil title".to_owned())]
+        );
+    }
+}
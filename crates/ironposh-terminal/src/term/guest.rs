@@ -1,4 +1,12 @@
-use super::TerminalOp;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use tracing::{debug, warn};
+
+use super::controls::{Control, ControlDecoder};
+use super::{HostRenderer, StatusLine, TerminalOp};
+use crate::cast::CastRecorder;
+use crate::text::repeat_to_width;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FillArea {
@@ -16,10 +24,78 @@ pub struct FillRectParams {
     pub bg: u8,
 }
 
+/// Hyperlink spans remembered at most; the oldest are dropped first.
+const MAX_LINKS: usize = 64;
+
+/// Output meant for the host terminal itself rather than the guest screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostEvent {
+    Bell,
+    Title(String),
+    /// Set the local clipboard to this base64-encoded text.
+    Clipboard(String),
+}
+
+/// What to do when the remote sets the clipboard with OSC 52.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipboardPolicy {
+    /// Ignore it.
+    #[default]
+    Deny,
+    /// Hold the latest write until the user approves it; see
+    /// [`GuestTerm::take_clipboard_request`].
+    Ask,
+    /// Pass it on to the host.
+    Allow,
+}
+
+/// A clipboard write waiting for the user's consent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardRequest {
+    base64: String,
+}
+
+impl ClipboardRequest {
+    /// Bytes of text it would put on the clipboard.
+    pub fn len(&self) -> usize {
+        let padding = self.base64.bytes().rev().take_while(|&b| b == b'=').count();
+        (self.base64.len() / 4 * 3).saturating_sub(padding)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Cells `start..end` of `row`, showing `text`, link to `uri`.
+#[derive(Debug, Clone)]
+struct LinkSpan {
+    row: u16,
+    start: u16,
+    end: u16,
+    uri: String,
+    text: String,
+}
+
 pub struct GuestTerm {
     parser: vt100::Parser,
     prev: Option<vt100::Screen>,
     dirty: bool,
+    controls: ControlDecoder,
+    host_events: Vec<HostEvent>,
+    /// Where the open OSC 8 link started: row, column and uri.
+    open_link: Option<(u16, u16, String)>,
+    /// vt100 keeps no hyperlinks, so they are tracked here and drawn over
+    /// the screen on every frame.
+    links: VecDeque<LinkSpan>,
+    /// `--record`: everything fed to the emulator, with resizes.
+    recorder: Option<CastRecorder>,
+    clipboard_policy: ClipboardPolicy,
+    clipboard_request: Option<ClipboardRequest>,
+    /// Drawn on the host row under the guest screen.
+    status: Option<StatusLine>,
+    /// The status row as last drawn.
+    status_drawn: Option<String>,
 }
 
 impl GuestTerm {
@@ -28,6 +104,69 @@ impl GuestTerm {
             parser: vt100::Parser::new(rows, cols, scrollback),
             prev: None,
             dirty: true,
+            controls: ControlDecoder::default(),
+            host_events: Vec::new(),
+            open_link: None,
+            links: VecDeque::new(),
+            recorder: None,
+            clipboard_policy: ClipboardPolicy::default(),
+            clipboard_request: None,
+            status: None,
+            status_drawn: None,
+        }
+    }
+
+    /// Show a status bar under the guest screen, or stop with `None`. The
+    /// host needs a row more than the guest screen for it; see
+    /// [`Terminal::on_host_resize`](crate::Terminal::on_host_resize).
+    pub fn set_status_line(&mut self, status: Option<StatusLine>) {
+        self.status = status;
+        self.status_drawn = None;
+        self.prev = None;
+        self.dirty = true;
+    }
+
+    pub fn status_line(&self) -> Option<&StatusLine> {
+        self.status.as_ref()
+    }
+
+    /// Changes show with the next frame.
+    pub fn status_line_mut(&mut self) -> Option<&mut StatusLine> {
+        self.status.as_mut()
+    }
+
+    pub fn set_clipboard_policy(&mut self, policy: ClipboardPolicy) {
+        self.clipboard_policy = policy;
+        if policy != ClipboardPolicy::Ask {
+            self.clipboard_request = None;
+        }
+    }
+
+    /// The clipboard write held under [`ClipboardPolicy::Ask`], if any.
+    /// Only the latest is kept.
+    pub fn take_clipboard_request(&mut self) -> Option<ClipboardRequest> {
+        self.clipboard_request.take()
+    }
+
+    /// Pass a held write on to the host with the next frame.
+    pub fn approve_clipboard(&mut self, request: ClipboardRequest) {
+        self.host_events.push(HostEvent::Clipboard(request.base64));
+        self.dirty = true;
+    }
+
+    /// Record from now on; `None` stops recording.
+    pub fn set_recorder(&mut self, recorder: Option<CastRecorder>) {
+        self.recorder = recorder;
+    }
+
+    /// Pass an event to the recorder. A recording that fails to write is
+    /// stopped; the session itself goes on.
+    fn record(&mut self, event: impl FnOnce(&mut CastRecorder) -> std::io::Result<()>) {
+        if let Some(recorder) = self.recorder.as_mut()
+            && let Err(e) = event(recorder)
+        {
+            warn!(error = %e, "session recording stopped");
+            self.recorder = None;
         }
     }
 
@@ -38,6 +177,14 @@ impl GuestTerm {
             TerminalOp::SetCursor { x, y } => {
                 self.feed(format!("\x1b[{};{}H", y + 1, x + 1).as_bytes());
             }
+            TerminalOp::SetScrollRegion { top, bottom } => {
+                self.feed(format!("\x1b[{};{}r", top + 1, bottom + 1).as_bytes());
+            }
+            TerminalOp::ResetScrollRegion => self.feed(b"\x1b[r"),
+            TerminalOp::InsertLines { count } => self.feed(format!("\x1b[{count}L").as_bytes()),
+            TerminalOp::DeleteLines { count } => self.feed(format!("\x1b[{count}M").as_bytes()),
+            TerminalOp::InsertChars { count } => self.feed(format!("\x1b[{count}@").as_bytes()),
+            TerminalOp::DeleteChars { count } => self.feed(format!("\x1b[{count}P").as_bytes()),
             TerminalOp::ClearScreen => self.feed(b"\x1b[2J\x1b[H"),
             TerminalOp::ClearScrollback => self.feed(b"\x1b[3J\x1b[2J\x1b[H"),
             TerminalOp::SetScrollback { rows } => {
@@ -46,10 +193,16 @@ impl GuestTerm {
                 self.dirty = true;
             }
             TerminalOp::Resize { rows, cols } => {
+                self.record(|recorder| recorder.resize(rows, cols));
                 self.parser.screen_mut().set_size(rows, cols);
                 self.prev = None;
+                self.open_link = None;
+                self.links.clear();
                 self.dirty = true;
             }
+            TerminalOp::Bell => self.on_control(Control::Bell),
+            TerminalOp::SetTitle(title) => self.on_control(Control::Title(title)),
+            TerminalOp::Hyperlink { uri } => self.on_control(Control::Hyperlink(uri)),
             TerminalOp::FillRect {
                 left,
                 top,
@@ -71,10 +224,116 @@ impl GuestTerm {
     }
 
     fn feed(&mut self, bytes: &[u8]) {
-        self.parser.process(bytes);
+        self.record(|recorder| recorder.output(bytes));
+        // Controls are acted on where they occur in the stream, so a link
+        // starts and ends at the cursor position of that moment.
+        let mut start = 0;
+        for (i, &byte) in bytes.iter().enumerate() {
+            if let Some(control) = self.controls.push(byte) {
+                self.parser.process(&bytes[start..=i]);
+                start = i + 1;
+                self.on_control(control);
+            }
+        }
+        self.parser.process(&bytes[start..]);
+        self.dirty = true;
+    }
+
+    fn on_control(&mut self, control: Control) {
+        match control {
+            Control::Bell => self.host_events.push(HostEvent::Bell),
+            Control::Title(title) => self.host_events.push(HostEvent::Title(title)),
+            Control::Clipboard(base64) => match self.clipboard_policy {
+                ClipboardPolicy::Deny => debug!("clipboard write from the remote ignored"),
+                ClipboardPolicy::Ask => self.clipboard_request = Some(ClipboardRequest { base64 }),
+                ClipboardPolicy::Allow => self.host_events.push(HostEvent::Clipboard(base64)),
+            },
+            Control::Hyperlink(uri) => {
+                self.close_link();
+                if let Some(uri) = uri {
+                    let (row, col) = self.cursor_position();
+                    self.open_link = Some((row, col, uri));
+                }
+            }
+        }
         self.dirty = true;
     }
 
+    /// Record the cells printed since the open link started, one span per row.
+    fn close_link(&mut self) {
+        let Some((row, col, uri)) = self.open_link.take() else {
+            return;
+        };
+        let (end_row, end_col) = self.cursor_position();
+        let (_, cols) = self.screen_size();
+        for r in row..=end_row {
+            let start = if r == row { col } else { 0 };
+            let end = if r == end_row { end_col } else { cols };
+            let text = self.parser.screen().contents_between(r, start, r, end);
+            if start >= end || text.trim().is_empty() {
+                continue;
+            }
+            self.links.push_back(LinkSpan {
+                row: r,
+                start,
+                end,
+                uri: uri.clone(),
+                text,
+            });
+        }
+        while self.links.len() > MAX_LINKS {
+            self.links.pop_front();
+        }
+    }
+
+    /// Redraw the linked cells wrapped in OSC 8. A span whose text scrolled
+    /// up is followed to its new row; one overwritten is forgotten.
+    fn link_overlays(&mut self, screen: &vt100::Screen) -> Vec<u8> {
+        let (rows, _) = screen.size();
+        self.links.retain_mut(|link| {
+            let found = (0..=link.row.min(rows.saturating_sub(1)))
+                .rev()
+                .find(|&r| screen.contents_between(r, link.start, r, link.end) == link.text);
+            if let Some(row) = found {
+                link.row = row;
+            }
+            found.is_some()
+        });
+
+        let mut out = Vec::new();
+        if self.links.is_empty() {
+            return out;
+        }
+        for link in &self.links {
+            let Some(cells) = screen
+                .rows_formatted(link.start, link.end - link.start)
+                .nth(usize::from(link.row))
+            else {
+                continue;
+            };
+            out.extend_from_slice(
+                format!(
+                    "\x1b[{};{}H\x1b[m\x1b]8;;{}\x1b\\",
+                    link.row + 1,
+                    link.start + 1,
+                    link.uri
+                )
+                .as_bytes(),
+            );
+            out.extend_from_slice(&cells);
+            out.extend_from_slice(b"\x1b]8;;\x1b\\");
+        }
+        out.extend_from_slice(b"\x1b[m");
+        out.extend_from_slice(&screen.attributes_formatted());
+        out
+    }
+
+    /// Bells, title changes and clipboard writes since the last call, in
+    /// order.
+    pub fn take_host_events(&mut self) -> Vec<HostEvent> {
+        std::mem::take(&mut self.host_events)
+    }
+
     fn fill_rect(&mut self, rect: FillRectParams) {
         let FillRectParams { area, ch, fg, bg } = rect;
 
@@ -96,7 +355,8 @@ impl GuestTerm {
 
                 // Use wider arithmetic to prevent overflow
                 let width = (r as u32 - l as u32 + 1) as usize;
-                let run = ch.to_string().repeat(width);
+                // In columns: a wide `ch` covers two cells per copy.
+                let run = repeat_to_width(ch, width);
                 let sgr = format!("\x1b[{fg};{bg}m");
 
                 for y in t..=b {
@@ -111,7 +371,12 @@ impl GuestTerm {
 
     /// Produce bytes to render: full on first frame, diffs after, and keep host cursor in sync.
     pub fn take_render_bytes(&mut self) -> Option<Vec<u8>> {
-        if !self.dirty {
+        let (rows, cols) = self.screen_size();
+        let status = self
+            .status
+            .as_ref()
+            .map(|status| status.text(cols, Instant::now()));
+        if !self.dirty && status == self.status_drawn {
             return None;
         }
         let screen = self.parser.screen().clone();
@@ -119,12 +384,36 @@ impl GuestTerm {
             .prev
             .as_ref()
             .map_or_else(|| screen.state_formatted(), |prev| screen.state_diff(prev));
+        bytes.extend(self.link_overlays(&screen));
+        if let Some(text) = &status {
+            bytes
+                .extend_from_slice(format!("\x1b[{};1H\x1b[0;7m{text}\x1b[m", rows + 1).as_bytes());
+            bytes.extend_from_slice(&screen.attributes_formatted());
+        }
+        self.status_drawn = status;
         bytes.extend_from_slice(&screen.cursor_state_formatted());
         self.prev = Some(screen);
         self.dirty = false;
         Some(bytes)
     }
 
+    /// Present the next frame, if the screen changed, and pass on bells and
+    /// title changes. Returns whether a frame was presented.
+    pub fn render_to(&mut self, renderer: &mut impl HostRenderer) -> anyhow::Result<bool> {
+        let frame = self.take_render_bytes();
+        if let Some(bytes) = &frame {
+            renderer.present(bytes)?;
+        }
+        for event in self.take_host_events() {
+            match event {
+                HostEvent::Bell => renderer.bell()?,
+                HostEvent::Title(title) => renderer.set_title(&title)?,
+                HostEvent::Clipboard(base64) => renderer.set_clipboard(&base64)?,
+            }
+        }
+        Ok(frame.is_some())
+    }
+
     pub fn is_dirty(&self) -> bool {
         self.dirty
     }
@@ -186,3 +475,112 @@ fn idx_to_sgr_bg(i: u8) -> &'static str {
         _ => "49",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term::MemoryRenderer;
+
+    fn render(guest: &mut GuestTerm) -> String {
+        String::from_utf8(guest.take_render_bytes().expect("screen changed")).expect("utf-8")
+    }
+
+    #[test]
+    fn hyperlinks_are_drawn_over_their_cells() {
+        let mut guest = GuestTerm::new(5, 40, 0);
+        guest.apply(TerminalOp::FeedBytes(
+            b"see \x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\\r\n".to_vec(),
+        ));
+        let frame = render(&mut guest);
+        assert!(
+            frame.contains("\x1b[1;5H\x1b[m\x1b]8;;https://example.com\x1b\\"),
+            "{frame:?}"
+        );
+
+        // Overwritten text takes its link with it.
+        guest.apply(TerminalOp::FeedBytes(b"\x1b[2J".to_vec()));
+        assert!(!render(&mut guest).contains("example.com"));
+    }
+
+    /// Frames are diffs against the last one, so a test keeps one host.
+    fn screen_after(guest: &mut GuestTerm, host: &mut MemoryRenderer) -> String {
+        guest.render_to(host).expect("render");
+        host.contents()
+    }
+
+    #[test]
+    fn scroll_region_keeps_rows_outside_it_in_place() {
+        let mut guest = GuestTerm::new(5, 10, 0);
+        let mut host = MemoryRenderer::new(10, 5);
+        guest.apply(TerminalOp::FeedBytes(
+            b"title\r\na\r\nb\r\nc\r\nstatus".to_vec(),
+        ));
+        guest.apply(TerminalOp::SetScrollRegion { top: 1, bottom: 3 });
+        guest.apply(TerminalOp::SetCursor { x: 0, y: 3 });
+        guest.apply(TerminalOp::FeedBytes(b"\nd".to_vec()));
+        assert_eq!(
+            screen_after(&mut guest, &mut host),
+            "title\nb\nc\nd\nstatus"
+        );
+
+        // Deleting a line pulls up only the rows inside the region.
+        guest.apply(TerminalOp::SetCursor { x: 0, y: 1 });
+        guest.apply(TerminalOp::DeleteLines { count: 1 });
+        assert_eq!(screen_after(&mut guest, &mut host), "title\nc\nd\n\nstatus");
+        guest.apply(TerminalOp::InsertLines { count: 2 });
+        assert_eq!(screen_after(&mut guest, &mut host), "title\n\n\nc\nstatus");
+
+        guest.apply(TerminalOp::ResetScrollRegion);
+        guest.apply(TerminalOp::SetCursor { x: 0, y: 4 });
+        guest.apply(TerminalOp::FeedBytes(b"\n".to_vec()));
+        // The whole screen scrolls again, so the title goes.
+        assert_eq!(screen_after(&mut guest, &mut host), "\n\nc\nstatus");
+    }
+
+    #[test]
+    fn characters_are_inserted_and_deleted_within_the_row() {
+        let mut guest = GuestTerm::new(2, 10, 0);
+        let mut host = MemoryRenderer::new(10, 2);
+        guest.apply(TerminalOp::FeedBytes(b"abcdef".to_vec()));
+        guest.apply(TerminalOp::SetCursor { x: 1, y: 0 });
+        guest.apply(TerminalOp::DeleteChars { count: 2 });
+        assert_eq!(screen_after(&mut guest, &mut host), "adef");
+        guest.apply(TerminalOp::InsertChars { count: 3 });
+        guest.apply(TerminalOp::FeedBytes(b"XYZ".to_vec()));
+        assert_eq!(screen_after(&mut guest, &mut host), "aXYZdef");
+    }
+
+    #[test]
+    fn editor_escape_sequences_from_the_remote_are_honoured() {
+        // What a full-screen editor sends to scroll its text area up a line
+        // under a fixed header and status bar.
+        let mut guest = GuestTerm::new(4, 10, 0);
+        let mut host = MemoryRenderer::new(10, 4);
+        guest.apply(TerminalOp::FeedBytes(
+            b"head\r\none\r\ntwo\r\n-- INSERT".to_vec(),
+        ));
+        guest.apply(TerminalOp::FeedBytes(
+            b"\x1b[2;3r\x1b[2;1H\x1b[M\x1b[3;1Hthree\x1b[r".to_vec(),
+        ));
+        assert_eq!(
+            screen_after(&mut guest, &mut host),
+            "head\ntwo\nthree\n-- INSERT"
+        );
+    }
+
+    #[test]
+    fn bells_and_titles_go_to_the_host() {
+        let mut guest = GuestTerm::new(5, 40, 0);
+        guest.apply(TerminalOp::FeedBytes(b"\x1b]0;remote\x07done\x07".to_vec()));
+        guest.apply(TerminalOp::SetTitle("local".to_owned()));
+        assert_eq!(
+            guest.take_host_events(),
+            [
+                HostEvent::Title("remote".to_owned()),
+                HostEvent::Bell,
+                HostEvent::Title("local".to_owned()),
+            ]
+        );
+        assert!(guest.take_host_events().is_empty());
+    }
+}
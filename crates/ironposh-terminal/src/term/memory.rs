@@ -0,0 +1,286 @@
+use anyhow::Result;
+
+use super::HostRenderer;
+
+/// A [`HostRenderer`] that draws into an in-memory screen, for tests.
+///
+/// Every presented frame is kept as sent and also played into a host-side
+/// emulator, so a test can check both the bytes and what a real terminal
+/// would show after them.
+pub struct MemoryRenderer {
+    screen: vt100::Parser,
+    frames: Vec<Vec<u8>>,
+    bells: usize,
+    titles: Vec<String>,
+    clipboard: Vec<String>,
+    focus_reports: bool,
+    active: bool,
+}
+
+impl MemoryRenderer {
+    /// A host display of `cols` x `rows`.
+    pub fn new(cols: u16, rows: u16) -> Self {
+        Self {
+            screen: vt100::Parser::new(rows, cols, 0),
+            frames: Vec::new(),
+            bells: 0,
+            titles: Vec::new(),
+            clipboard: Vec::new(),
+            focus_reports: false,
+            active: false,
+        }
+    }
+
+    /// Change the host display size, as a window resize would.
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.screen.screen_mut().set_size(rows, cols);
+    }
+
+    /// The frames presented so far, oldest first.
+    pub fn frames(&self) -> &[Vec<u8>] {
+        &self.frames
+    }
+
+    /// The host screen after the frames presented so far.
+    pub fn screen(&self) -> &vt100::Screen {
+        self.screen.screen()
+    }
+
+    /// The text on the host screen, rows separated by `\n`.
+    pub fn contents(&self) -> String {
+        self.screen().contents()
+    }
+
+    /// The text of one row of the host screen, trailing blanks trimmed.
+    pub fn row(&self, row: u16) -> String {
+        let (_, cols) = self.screen().size();
+        self.screen()
+            .contents_between(row, 0, row, cols)
+            .trim_end()
+            .to_owned()
+    }
+
+    pub fn bells(&self) -> usize {
+        self.bells
+    }
+
+    pub fn titles(&self) -> &[String] {
+        &self.titles
+    }
+
+    /// Clipboard writes so far, base64-encoded as received.
+    pub fn clipboard(&self) -> &[String] {
+        &self.clipboard
+    }
+
+    /// Whether focus changes were asked for.
+    pub fn reports_focus(&self) -> bool {
+        self.focus_reports
+    }
+
+    /// Whether `init` was called and `deinit` has not been since.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+impl HostRenderer for MemoryRenderer {
+    fn init(&mut self) -> Result<()> {
+        self.active = true;
+        Ok(())
+    }
+
+    fn present(&mut self, bytes: &[u8]) -> Result<()> {
+        self.screen.process(bytes);
+        self.frames.push(bytes.to_vec());
+        Ok(())
+    }
+
+    fn host_size(&mut self) -> Result<(u16, u16)> {
+        let (rows, cols) = self.screen().size();
+        Ok((cols, rows))
+    }
+
+    fn bell(&mut self) -> Result<()> {
+        self.bells += 1;
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        self.titles.push(title.to_owned());
+        Ok(())
+    }
+
+    fn set_clipboard(&mut self, base64: &str) -> Result<()> {
+        self.clipboard.push(base64.to_owned());
+        Ok(())
+    }
+
+    fn report_focus(&mut self, on: bool) -> Result<()> {
+        self.focus_reports = on;
+        Ok(())
+    }
+
+    fn deinit(&mut self) {
+        self.active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term::{ClipboardPolicy, GuestTerm, StatusLine, TerminalOp};
+
+    fn feed(guest: &mut GuestTerm, bytes: &[u8]) {
+        guest.apply(TerminalOp::FeedBytes(bytes.to_vec()));
+    }
+
+    #[test]
+    fn output_scrolls_off_the_top() {
+        let mut guest = GuestTerm::new(3, 10, 100);
+        let mut host = MemoryRenderer::new(10, 3);
+        feed(&mut guest, b"one\r\ntwo\r\n");
+        assert!(guest.render_to(&mut host).expect("render"));
+        feed(&mut guest, b"three\r\nfour");
+        assert!(guest.render_to(&mut host).expect("render"));
+        assert!(
+            !guest.render_to(&mut host).expect("render"),
+            "nothing changed"
+        );
+
+        assert_eq!(host.frames().len(), 2);
+        assert_eq!(host.contents(), "two\nthree\nfour");
+    }
+
+    #[test]
+    fn resize_redraws_the_whole_screen() {
+        let mut guest = GuestTerm::new(3, 10, 0);
+        let mut host = MemoryRenderer::new(10, 3);
+        feed(&mut guest, b"abcdefghij");
+        guest.render_to(&mut host).expect("render");
+
+        guest.apply(TerminalOp::Resize { rows: 2, cols: 5 });
+        host.resize(5, 2);
+        guest.render_to(&mut host).expect("render");
+
+        assert_eq!(host.host_size().expect("size"), (5, 2));
+        assert_eq!(host.row(0), "abcde");
+    }
+
+    #[test]
+    fn fill_rect_paints_only_its_cells() {
+        let mut guest = GuestTerm::new(3, 6, 0);
+        let mut host = MemoryRenderer::new(6, 3);
+        feed(&mut guest, b"xxxxxx\r\nxxxxxx");
+        guest.apply(TerminalOp::FillRect {
+            left: 1,
+            top: 0,
+            right: 3,
+            bottom: 1,
+            ch: '#',
+            fg: 15,
+            bg: 1,
+        });
+        guest.render_to(&mut host).expect("render");
+
+        assert_eq!(host.row(0), "x###xx");
+        assert_eq!(host.row(1), "x###xx");
+        assert_eq!(host.row(2), "");
+        let cell = host.screen().cell(0, 1).expect("cell");
+        assert_eq!(
+            cell.bgcolor(),
+            vt100::Color::Idx(4),
+            "ConsoleColor 1 is blue"
+        );
+    }
+
+    #[test]
+    fn bells_and_titles_reach_the_renderer() {
+        let mut guest = GuestTerm::new(2, 10, 0);
+        let mut host = MemoryRenderer::new(10, 2);
+        host.init().expect("init");
+        feed(&mut guest, b"\x1b]0;remote\x07\x07");
+        guest.render_to(&mut host).expect("render");
+        host.deinit();
+
+        assert_eq!(host.bells(), 1);
+        assert_eq!(host.titles(), ["remote"]);
+        assert!(!host.is_active());
+    }
+
+    #[test]
+    fn status_line_takes_the_row_under_the_guest_screen() {
+        let mut guest = GuestTerm::new(2, 24, 100);
+        let mut host = MemoryRenderer::new(24, 3);
+        guest.set_status_line(Some(StatusLine::new("srv01")));
+        feed(&mut guest, b"one\r\ntwo\r\nthree");
+        guest.render_to(&mut host).expect("render");
+
+        assert_eq!(host.row(0), "two");
+        assert_eq!(host.row(1), "three");
+        assert_eq!(host.row(2), " srv01 | connected");
+        assert!(host.screen().cell(2, 0).expect("cell").inverse());
+        assert_eq!(host.screen().cursor_position(), (1, 5));
+
+        // A status change alone is enough for a frame.
+        let status = guest.status_line_mut().expect("shown");
+        status.set_state("disconnected");
+        assert!(guest.render_to(&mut host).expect("render"));
+        assert_eq!(host.row(2), " srv01 | disconnected");
+        assert!(!guest.render_to(&mut host).expect("render"));
+    }
+
+    #[test]
+    fn regaining_focus_draws_held_back_output_at_once() {
+        use std::sync::{Arc, Mutex};
+
+        let mut terminal =
+            crate::Terminal::with_renderer(MemoryRenderer::new(10, 2), 0).expect("terminal");
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let listener_seen = Arc::clone(&seen);
+        terminal.set_focus_listener(Some(Box::new(move |focused| {
+            listener_seen.lock().unwrap().push(focused);
+        })));
+
+        terminal.on_focus(false).expect("focus");
+        assert!(seen.lock().unwrap().is_empty(), "power saving is off");
+        terminal.set_power_saving(true).expect("power saving");
+        assert!(terminal.renderer().reports_focus());
+
+        terminal.on_focus(false).expect("focus");
+        terminal.apply_op(TerminalOp::FeedBytes(b"idle".to_vec()));
+        terminal.render().expect("render");
+        assert!(terminal.renderer().frames().is_empty(), "held back");
+
+        terminal.on_focus(true).expect("focus");
+        assert_eq!(terminal.renderer().row(0), "idle");
+        assert_eq!(*seen.lock().unwrap(), [false, true]);
+    }
+
+    #[test]
+    fn clipboard_writes_follow_the_policy() {
+        let copy = b"\x1b]52;c;aGVsbG8=\x07";
+        let mut guest = GuestTerm::new(2, 10, 0);
+        let mut host = MemoryRenderer::new(10, 2);
+
+        feed(&mut guest, copy);
+        guest.render_to(&mut host).expect("render");
+        assert!(host.clipboard().is_empty(), "denied by default");
+
+        guest.set_clipboard_policy(ClipboardPolicy::Ask);
+        feed(&mut guest, copy);
+        guest.render_to(&mut host).expect("render");
+        assert!(host.clipboard().is_empty(), "held for consent");
+        let request = guest.take_clipboard_request().expect("held write");
+        assert_eq!(request.len(), 5);
+        guest.approve_clipboard(request);
+        guest.render_to(&mut host).expect("render");
+        assert_eq!(host.clipboard(), ["aGVsbG8="]);
+
+        guest.set_clipboard_policy(ClipboardPolicy::Allow);
+        feed(&mut guest, b"\x1b]52;c;b2s=\x1b\\");
+        guest.render_to(&mut host).expect("render");
+        assert_eq!(host.clipboard(), ["aGVsbG8=", "b2s="]);
+        assert!(guest.take_clipboard_request().is_none());
+    }
+}
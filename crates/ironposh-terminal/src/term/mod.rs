@@ -1,7 +1,12 @@
+pub mod controls;
 pub mod guest;
+pub mod memory;
 pub mod ops;
 pub mod renderer;
+pub mod status;
 
-pub use guest::GuestTerm;
+pub use guest::{ClipboardPolicy, ClipboardRequest, GuestTerm, HostEvent};
+pub use memory::MemoryRenderer;
 pub use ops::TerminalOp;
-pub use renderer::{CrosstermRenderer, HostRenderer};
+pub use renderer::{CrosstermRenderer, HostRenderer, restore_host_terminal};
+pub use status::{CommandStatus, StatusLine};
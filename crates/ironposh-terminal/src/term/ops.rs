@@ -1,4 +1,4 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TerminalOp {
     FeedBytes(Vec<u8>),
     CursorHome,
@@ -24,4 +24,38 @@ pub enum TerminalOp {
         rows: u16,
         cols: u16,
     },
+    /// Scroll only rows `top..=bottom` (0-based) from now on, as DECSTBM
+    /// does; the cursor moves home.
+    SetScrollRegion {
+        top: u16,
+        bottom: u16,
+    },
+    /// Scroll the whole screen again.
+    ResetScrollRegion,
+    /// Insert blank lines at the cursor row, pushing the rows below it down
+    /// within the scroll region.
+    InsertLines {
+        count: u16,
+    },
+    /// Delete lines at the cursor row, pulling the rows below it up within
+    /// the scroll region.
+    DeleteLines {
+        count: u16,
+    },
+    /// Insert blank cells at the cursor, shifting the rest of the row right.
+    InsertChars {
+        count: u16,
+    },
+    /// Delete cells at the cursor, shifting the rest of the row left.
+    DeleteChars {
+        count: u16,
+    },
+    /// Ring the host terminal's bell.
+    Bell,
+    /// Set the host window title.
+    SetTitle(String),
+    /// Start an OSC 8 hyperlink at the cursor; `None` ends the open one.
+    Hyperlink {
+        uri: Option<String>,
+    },
 }
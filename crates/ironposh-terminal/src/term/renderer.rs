@@ -1,13 +1,34 @@
 use anyhow::Result;
 use crossterm::{
-    ExecutableCommand,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    ExecutableCommand, cursor,
+    event::{DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange},
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode,
+    },
 };
 use std::io::{Stdout, Write};
 
+/// Where a [`GuestTerm`](super::GuestTerm)'s frames end up.
+///
+/// [`CrosstermRenderer`] draws on the terminal the client runs in and
+/// [`MemoryRenderer`](super::MemoryRenderer) keeps frames for tests; another
+/// frontend (a GUI or a web canvas) implements this to show the same screen.
 pub trait HostRenderer {
+    /// Take over the host display, e.g. raw mode and the alternate screen.
     fn init(&mut self) -> Result<()>;
+    /// Draw a frame: VT output from [`GuestTerm::take_render_bytes`].
+    ///
+    /// [`GuestTerm::take_render_bytes`]: super::GuestTerm::take_render_bytes
     fn present(&mut self, bytes: &[u8]) -> Result<()>;
+    /// The host display's size as (cols, rows).
+    fn host_size(&mut self) -> Result<(u16, u16)>;
+    fn bell(&mut self) -> Result<()>;
+    fn set_title(&mut self, title: &str) -> Result<()>;
+    /// Put base64-encoded text on the local clipboard.
+    fn set_clipboard(&mut self, base64: &str) -> Result<()>;
+    /// Ask the host to report focus changes (`Event::FocusGained`/`FocusLost`), or stop.
+    fn report_focus(&mut self, on: bool) -> Result<()>;
+    /// Give the host display back. Safe to call more than once.
     fn deinit(&mut self);
 }
 
@@ -27,16 +48,15 @@ impl CrosstermRenderer {
             out: std::io::stdout(),
         }
     }
-
-    pub fn host_size(&mut self) -> Result<(u16, u16)> {
-        Ok(crossterm::terminal::size()?)
-    }
 }
 
 impl HostRenderer for CrosstermRenderer {
     fn init(&mut self) -> Result<()> {
         enable_raw_mode()?;
         self.out.execute(EnterAlternateScreen)?;
+        // Pastes arrive as one `Event::Paste` instead of keystrokes, so their
+        // newlines do not submit the line piece by piece.
+        self.out.execute(EnableBracketedPaste)?;
         Ok(())
     }
 
@@ -46,8 +66,48 @@ impl HostRenderer for CrosstermRenderer {
         Ok(())
     }
 
+    fn host_size(&mut self) -> Result<(u16, u16)> {
+        Ok(crossterm::terminal::size()?)
+    }
+
+    fn bell(&mut self) -> Result<()> {
+        self.present(b"\x07")
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        self.out.execute(SetTitle(title))?;
+        Ok(())
+    }
+
+    /// Passed on as OSC 52; the host terminal decides whether to honour it.
+    fn set_clipboard(&mut self, base64: &str) -> Result<()> {
+        self.present(format!("\x1b]52;c;{base64}\x07").as_bytes())
+    }
+
+    fn report_focus(&mut self, on: bool) -> Result<()> {
+        if on {
+            self.out.execute(EnableFocusChange)?;
+        } else {
+            self.out.execute(DisableFocusChange)?;
+        }
+        Ok(())
+    }
+
     fn deinit(&mut self) {
-        let _ = self.out.execute(LeaveAlternateScreen);
-        let _ = disable_raw_mode();
+        restore_host_terminal();
     }
 }
+
+/// Put the host terminal back the way the shell expects it.
+///
+/// Bracketed paste and focus reports off, main screen, cursor visible, raw mode off. Best
+/// effort, and safe to repeat, so a panic hook can call it before the
+/// [`CrosstermRenderer`] is dropped.
+pub fn restore_host_terminal() {
+    let mut out = std::io::stdout();
+    let _ = out.execute(DisableBracketedPaste);
+    let _ = out.execute(DisableFocusChange);
+    let _ = out.execute(LeaveAlternateScreen);
+    let _ = out.execute(cursor::Show);
+    let _ = disable_raw_mode();
+}
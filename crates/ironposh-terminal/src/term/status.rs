@@ -0,0 +1,133 @@
+//! The optional status bar: one host row under the guest screen with the
+//! server, the session state, how long the running command has taken and
+//! how the last one ended.
+
+use std::time::{Duration, Instant};
+
+use crate::text::{pad_to_width, truncate_to_width};
+
+/// How a command ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandStatus {
+    Succeeded,
+    /// It wrote errors.
+    Failed,
+    /// Ctrl+C, or the server timed it out.
+    Stopped,
+}
+
+impl CommandStatus {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Succeeded => "ok",
+            Self::Failed => "failed",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusLine {
+    server: String,
+    state: String,
+    running_since: Option<Instant>,
+    last: Option<(CommandStatus, Duration)>,
+}
+
+impl StatusLine {
+    /// A status line for a session with `server`, in the `connected` state.
+    pub fn new(server: impl Into<String>) -> Self {
+        Self {
+            server: server.into(),
+            state: "connected".to_owned(),
+            running_since: None,
+            last: None,
+        }
+    }
+
+    /// E.g. `connected` or `disconnected`.
+    pub fn set_state(&mut self, state: impl Into<String>) {
+        self.state = state.into();
+    }
+
+    pub fn command_started(&mut self, at: Instant) {
+        self.running_since = Some(at);
+    }
+
+    /// End the running command; without one this does nothing.
+    pub fn command_finished(&mut self, status: CommandStatus, at: Instant) {
+        if let Some(started) = self.running_since.take() {
+            self.last = Some((status, at.saturating_duration_since(started)));
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// The row as drawn at `now`: cut or padded to `cols` columns.
+    pub fn text(&self, cols: u16, now: Instant) -> String {
+        let mut parts = vec![printable(&self.server), printable(&self.state)];
+        if let Some(started) = self.running_since {
+            // Whole seconds, so a running command redraws the row once a second.
+            let elapsed = now.saturating_duration_since(started);
+            parts.push(format!(
+                "running {}",
+                format_duration(Duration::from_secs(elapsed.as_secs()))
+            ));
+        }
+        if let Some((status, took)) = self.last {
+            parts.push(format!(
+                "last: {} in {}",
+                status.label(),
+                format_duration(took)
+            ));
+        }
+        let text = format!(" {} ", parts.join(" | "));
+        let (text, _) = truncate_to_width(&text, usize::from(cols));
+        pad_to_width(text, usize::from(cols))
+    }
+}
+
+/// `4.2s` under a minute, then `1:05` and `1:02:03`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 if duration.subsec_millis() == 0 => format!("{secs}s"),
+        0..60 => format!("{:.1}s", duration.as_secs_f64()),
+        60..3600 => format!("{}:{:02}", secs / 60, secs % 60),
+        _ => format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// The row is drawn on the host as is; keep control characters out of it.
+fn printable(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_the_running_command_then_how_it_ended() {
+        let start = Instant::now();
+        let mut status = StatusLine::new("srv01");
+        assert_eq!(status.text(24, start), " srv01 | connected      ");
+
+        status.command_started(start);
+        assert_eq!(
+            status
+                .text(40, start + Duration::from_millis(65_900))
+                .trim_end(),
+            " srv01 | connected | running 1:05"
+        );
+        status.command_finished(CommandStatus::Failed, start + Duration::from_millis(1_240));
+        status.set_state("disconnected");
+        assert_eq!(
+            status.text(60, start).trim_end(),
+            " srv01 | disconnected | last: failed in 1.2s"
+        );
+        assert_eq!(status.text(10, start), " srv01 | d");
+    }
+}
@@ -0,0 +1,104 @@
+//! Display width of text on the terminal grid. Layout works on grapheme
+//! clusters: a CJK character or an emoji takes two columns, a combining mark
+//! none (it joins the character before it).
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns one grapheme cluster takes. A cluster is drawn as one glyph, so an
+/// emoji ZWJ sequence counts two columns, not two per emoji.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+/// Columns `text` takes on a single line.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// The longest prefix of `text` that fits in `max` columns, and its width.
+/// A grapheme is never split, so the width may fall short of `max`.
+pub fn truncate_to_width(text: &str, max: usize) -> (&str, usize) {
+    let mut width = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        let next = width + grapheme_width(grapheme);
+        if next > max {
+            return (&text[..index], width);
+        }
+        width = next;
+    }
+    (text, width)
+}
+
+/// `text` padded with spaces to `width` columns; wider text is left as is.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    let mut out = String::with_capacity(text.len() + padding);
+    out.push_str(text);
+    out.extend(std::iter::repeat_n(' ', padding));
+    out
+}
+
+/// Remove the last grapheme of `line` and return the columns it took.
+pub fn pop_grapheme(line: &mut String) -> Option<usize> {
+    let (index, grapheme) = line.grapheme_indices(true).next_back()?;
+    let width = grapheme_width(grapheme);
+    line.truncate(index);
+    Some(width)
+}
+
+/// `ch` repeated to fill exactly `width` columns. A wide `ch` leaves a space
+/// for an odd width; a zero-width one (a combining mark, a control) fills
+/// with spaces.
+pub fn repeat_to_width(ch: char, width: usize) -> String {
+    match ch.width().unwrap_or(0).min(2) {
+        0 => " ".repeat(width),
+        1 => ch.to_string().repeat(width),
+        _ => {
+            let mut run = ch.to_string().repeat(width / 2);
+            if !width.is_multiple_of(2) {
+                run.push(' ');
+            }
+            run
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_and_combining_graphemes_are_measured_in_columns() {
+        assert_eq!(display_width("Get-Item"), 8);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}te\u{301}"), 3);
+        assert_eq!(display_width("ok 👍"), 5);
+        assert_eq!(display_width("👨\u{200d}👩\u{200d}👧"), 2);
+    }
+
+    #[test]
+    fn truncation_keeps_whole_graphemes() {
+        assert_eq!(truncate_to_width("日本語", 5), ("日本", 4));
+        assert_eq!(truncate_to_width("e\u{301}x", 1), ("e\u{301}", 1));
+        assert_eq!(truncate_to_width("abc", 10), ("abc", 3));
+        assert_eq!(pad_to_width("日本", 6), "日本  ");
+    }
+
+    #[test]
+    fn pop_grapheme_removes_a_whole_cluster() {
+        let mut line = String::from("a日e\u{301}");
+        assert_eq!(pop_grapheme(&mut line), Some(1));
+        assert_eq!(line, "a日");
+        assert_eq!(pop_grapheme(&mut line), Some(2));
+        assert_eq!(pop_grapheme(&mut line), Some(1));
+        assert_eq!(pop_grapheme(&mut line), None);
+    }
+
+    #[test]
+    fn fill_runs_match_the_column_count() {
+        assert_eq!(repeat_to_width('#', 3), "###");
+        assert_eq!(repeat_to_width('中', 5), "中中 ");
+        assert_eq!(repeat_to_width('\u{301}', 2), "  ");
+    }
+}
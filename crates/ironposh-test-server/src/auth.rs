@@ -0,0 +1,111 @@
+//! HTTP authentication schemes the mock server can demand.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// SPNEGO opens with a DER `[APPLICATION 0]` NegTokenInit; follow-up legs are `[1]` NegTokenResp.
+const SPNEGO_INIT_TAG: u8 = 0x60;
+const SPNEGO_RESPONSE_TAG: u8 = 0xa1;
+const NTLM_SIGNATURE: &[u8] = b"NTLMSSP\0";
+
+/// How the mock server authenticates requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MockAuth {
+    /// Accept every request.
+    #[default]
+    None,
+    /// Require `Authorization: Basic` with exactly these credentials.
+    Basic { username: String, password: String },
+    /// Advertise `WWW-Authenticate: Negotiate` and check that the client
+    /// answers with a well-formed SPNEGO or NTLM token.
+    ///
+    /// The mock cannot complete a real SSPI exchange, so every token is
+    /// recorded and then rejected with a terminal 401 — enough to cover
+    /// challenge handling and the client's authentication-failure path.
+    Negotiate,
+}
+
+impl MockAuth {
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Check a request's `Authorization` header.
+    pub(super) fn check(&self, authorization: Option<&str>) -> AuthDecision {
+        match self {
+            Self::None => AuthDecision::Allow,
+            Self::Basic { username, password } => {
+                let expected = STANDARD.encode(format!("{username}:{password}"));
+                match authorization.and_then(|value| value.strip_prefix("Basic ")) {
+                    Some(credentials) if credentials.trim() == expected => AuthDecision::Allow,
+                    _ => AuthDecision::Challenge("Basic realm=\"WSMAN\""),
+                }
+            }
+            Self::Negotiate => {
+                let token = authorization
+                    .and_then(|value| value.strip_prefix("Negotiate "))
+                    .and_then(|token| STANDARD.decode(token.trim()).ok());
+                match token {
+                    Some(token) if is_negotiate_token(&token) => AuthDecision::Reject,
+                    _ => AuthDecision::Challenge("Negotiate"),
+                }
+            }
+        }
+    }
+}
+
+/// Outcome of [`MockAuth::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthDecision {
+    Allow,
+    /// 401 with this `WWW-Authenticate` value; the client may retry.
+    Challenge(&'static str),
+    /// Terminal 401 with no further challenge.
+    Reject,
+}
+
+fn is_negotiate_token(token: &[u8]) -> bool {
+    matches!(
+        token.first(),
+        Some(&(SPNEGO_INIT_TAG | SPNEGO_RESPONSE_TAG))
+    ) || token.starts_with(NTLM_SIGNATURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_accepts_only_matching_credentials() {
+        let auth = MockAuth::basic("user", "pass");
+        assert_eq!(auth.check(Some("Basic dXNlcjpwYXNz")), AuthDecision::Allow);
+        assert_eq!(
+            auth.check(Some("Basic dXNlcjp3cm9uZw==")),
+            AuthDecision::Challenge("Basic realm=\"WSMAN\"")
+        );
+        assert_eq!(
+            auth.check(None),
+            AuthDecision::Challenge("Basic realm=\"WSMAN\"")
+        );
+    }
+
+    #[test]
+    fn negotiate_rejects_well_formed_tokens_and_challenges_the_rest() {
+        let ntlm = STANDARD.encode(b"NTLMSSP\0\x01\0\0\0");
+        assert_eq!(
+            MockAuth::Negotiate.check(Some(&format!("Negotiate {ntlm}"))),
+            AuthDecision::Reject
+        );
+        assert_eq!(
+            MockAuth::Negotiate.check(Some("Negotiate bm90IGEgdG9rZW4=")),
+            AuthDecision::Challenge("Negotiate")
+        );
+        assert_eq!(
+            MockAuth::Negotiate.check(Some("Basic dXNlcjpwYXNz")),
+            AuthDecision::Challenge("Negotiate")
+        );
+    }
+}
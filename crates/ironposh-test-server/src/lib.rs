@@ -0,0 +1,62 @@
+//! Mock WinRM/PSRP server for client integration tests (`publish = false`).
+//!
+//! Unlike the sans-IO `fake_server` harness in `ironposh-test-support`, this
+//! listens on a real loopback socket and speaks HTTP, so the full client stack
+//! — HTTP client, authentication, connection pool and session loop — runs
+//! unmodified against it. It answers the runspace pool handshake, plays back
+//! [`CannedPipeline`]s one per Command, holds Receives until their
+//! OperationTimeout (answering `w:TimedOut`), and can inject faults or drop
+//! the shell.
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! use ironposh_test_server::{CannedPipeline, MockAuth, MockServer};
+//!
+//! let server = MockServer::builder()
+//!     .auth(MockAuth::basic("user", "pass"))
+//!     .pipelines(vec![CannedPipeline::new().string_output("hello")])
+//!     .build()
+//!     .start()
+//!     .await?;
+//! // Point a client at `server.addr()` over HttpInsecure ...
+//! # Ok(())
+//! # }
+//! ```
+
+mod auth;
+mod responses;
+mod script;
+mod service;
+mod wsman;
+
+pub use auth::MockAuth;
+pub use script::{CannedPipeline, PipelineStep, WsmanFault};
+pub use service::{RecordedRequest, RunningServer};
+
+use typed_builder::TypedBuilder;
+
+/// What the mock server does; [`Self::start`] binds it to `127.0.0.1`.
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct MockServer {
+    #[builder(default)]
+    auth: MockAuth,
+    /// Played back in order, one per Command; later Commands complete empty.
+    #[builder(default)]
+    pipelines: Vec<CannedPipeline>,
+    /// Answer the runspace pool Create with this fault.
+    #[builder(default, setter(strip_option))]
+    create_fault: Option<WsmanFault>,
+    /// Drop the shell when the Command with this index (from 0) arrives, as
+    /// when its idle timeout expires: that Command and every later request
+    /// for the shell fail with [`WsmanFault::shell_not_found`].
+    #[builder(default, setter(strip_option))]
+    drop_shell_at_command: Option<usize>,
+}
+
+impl MockServer {
+    /// Bind an ephemeral loopback port and serve until the returned
+    /// [`RunningServer`] is dropped.
+    pub async fn start(self) -> std::io::Result<RunningServer> {
+        service::spawn(self).await
+    }
+}
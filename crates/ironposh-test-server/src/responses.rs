@@ -0,0 +1,148 @@
+//! Response envelopes, shaped like the ones a Windows WinRM service returns.
+//!
+//! Every response echoes the request's MessageID as `a:RelatesTo`.
+
+use std::fmt::Write as _;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ironposh_psrp::fragmentation::Fragment;
+use ironposh_psrp::ps_value::PsObjectWithType;
+use ironposh_psrp::{Destination, PowerShellRemotingMessage};
+use uuid::Uuid;
+
+use crate::script::WsmanFault;
+
+const FAULT_ACTION: &str = "http://schemas.dmtf.org/wbem/wsman/1/wsman/fault";
+const SHELL_ACTION_PREFIX: &str = "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/";
+const RESOURCE_URI: &str = "http://schemas.microsoft.com/powershell/Microsoft.PowerShell";
+
+fn envelope(action: &str, relates_to: Option<&str>, body: &str) -> String {
+    let relates_to = relates_to
+        .map(|id| format!("<a:RelatesTo>{id}</a:RelatesTo>"))
+        .unwrap_or_default();
+    format!(
+        r#"<s:Envelope xml:lang="en-US" xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:x="http://schemas.xmlsoap.org/ws/2004/09/transfer" xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd" xmlns:rsp="http://schemas.microsoft.com/wbem/wsman/1/windows/shell" xmlns:p="http://schemas.microsoft.com/wbem/wsman/1/wsman.xsd"><s:Header><a:Action>{action}</a:Action><a:MessageID>uuid:{message_id}</a:MessageID><a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>{relates_to}</s:Header><s:Body>{body}</s:Body></s:Envelope>"#,
+        message_id = Uuid::new_v4(),
+    )
+}
+
+/// CreateResponse for the runspace pool shell `shell_id`.
+pub fn create_response(shell_id: Uuid, relates_to: Option<&str>) -> String {
+    let shell_id = shell_id.to_string().to_uppercase();
+    envelope(
+        "http://schemas.xmlsoap.org/ws/2004/09/transfer/CreateResponse",
+        relates_to,
+        &format!(
+            r#"<x:ResourceCreated><a:Address>http://127.0.0.1/wsman</a:Address><a:ReferenceParameters><w:ResourceURI>{RESOURCE_URI}</w:ResourceURI><w:SelectorSet><w:Selector Name="ShellId">{shell_id}</w:Selector></w:SelectorSet></a:ReferenceParameters></x:ResourceCreated><rsp:Shell><rsp:ShellId>{shell_id}</rsp:ShellId><rsp:Name>Runspace1</rsp:Name><rsp:ResourceUri>{RESOURCE_URI}</rsp:ResourceUri><rsp:Owner>mock</rsp:Owner><rsp:ClientIP>127.0.0.1</rsp:ClientIP><rsp:ProcessId>1</rsp:ProcessId><rsp:IdleTimeOut>PT7200.000S</rsp:IdleTimeOut><rsp:InputStreams>stdin pr</rsp:InputStreams><rsp:OutputStreams>stdout</rsp:OutputStreams><rsp:MaxIdleTimeOut>PT2147483.647S</rsp:MaxIdleTimeOut><rsp:Locale>en-US</rsp:Locale><rsp:DataLocale>en-CA</rsp:DataLocale><rsp:CompressionMode>XpressCompression</rsp:CompressionMode><rsp:ProfileLoaded>Yes</rsp:ProfileLoaded><rsp:Encoding>UTF8</rsp:Encoding><rsp:BufferMode>Block</rsp:BufferMode><rsp:State>Connected</rsp:State><rsp:ShellRunTime>P0DT0H0M0S</rsp:ShellRunTime><rsp:ShellInactivity>P0DT0H0M0S</rsp:ShellInactivity></rsp:Shell>"#
+        ),
+    )
+}
+
+pub fn command_response(command_id: Uuid, relates_to: Option<&str>) -> String {
+    envelope(
+        &format!("{SHELL_ACTION_PREFIX}CommandResponse"),
+        relates_to,
+        &format!(
+            "<rsp:CommandResponse><rsp:CommandId>{command_id}</rsp:CommandId></rsp:CommandResponse>"
+        ),
+    )
+}
+
+/// A ReceiveResponse carrying `messages` as single-fragment `stdout` streams.
+///
+/// Pipeline messages (`command_id` set) are tagged with the `CommandId` and
+/// use it as PSRP pid; `done` appends `CommandState Done`. Fragment object ids
+/// start at `object_id_start` so they stay unique across responses.
+pub fn receive_response(
+    relates_to: Option<&str>,
+    rpid: Uuid,
+    command_id: Option<Uuid>,
+    messages: &[&dyn PsObjectWithType],
+    object_id_start: u64,
+    done: bool,
+) -> String {
+    let command_attribute = command_id
+        .map(|id| format!(r#" CommandId="{id}""#))
+        .unwrap_or_default();
+
+    let mut body = String::from("<rsp:ReceiveResponse>");
+    for (object_id, message) in (object_id_start..).zip(messages) {
+        let remoting_message = PowerShellRemotingMessage::new(
+            Destination::Client,
+            message.message_type(),
+            rpid,
+            command_id,
+            &message.to_ps_object(),
+        )
+        .expect("serialize PSRP message");
+        let fragment = Fragment::new(object_id, 0, remoting_message.pack(), true, true);
+        let payload = STANDARD.encode(fragment.pack());
+        write!(
+            body,
+            r#"<rsp:Stream Name="stdout"{command_attribute}>{payload}</rsp:Stream>"#
+        )
+        .expect("write to String");
+    }
+    if done {
+        write!(
+            body,
+            r#"<rsp:CommandState{command_attribute} State="{SHELL_ACTION_PREFIX}CommandState/Done"><rsp:ExitCode>0</rsp:ExitCode></rsp:CommandState>"#
+        )
+        .expect("write to String");
+    }
+    body.push_str("</rsp:ReceiveResponse>");
+
+    envelope(
+        &format!("{SHELL_ACTION_PREFIX}ReceiveResponse"),
+        relates_to,
+        &body,
+    )
+}
+
+pub fn send_response(relates_to: Option<&str>) -> String {
+    envelope(
+        &format!("{SHELL_ACTION_PREFIX}SendResponse"),
+        relates_to,
+        "<rsp:SendResponse/>",
+    )
+}
+
+pub fn signal_response(relates_to: Option<&str>) -> String {
+    envelope(
+        &format!("{SHELL_ACTION_PREFIX}SignalResponse"),
+        relates_to,
+        "<rsp:SignalResponse/>",
+    )
+}
+
+pub fn delete_response(relates_to: Option<&str>) -> String {
+    envelope(
+        "http://schemas.xmlsoap.org/ws/2004/09/transfer/DeleteResponse",
+        relates_to,
+        "",
+    )
+}
+
+pub fn fault(fault: &WsmanFault, relates_to: Option<&str>) -> String {
+    let WsmanFault {
+        code,
+        subcode,
+        wsman_code,
+        reason,
+    } = fault;
+    let reason = escape_text(reason);
+    envelope(
+        FAULT_ACTION,
+        relates_to,
+        &format!(
+            r#"<s:Fault><s:Code><s:Value>s:{code}</s:Value><s:Subcode><s:Value>w:{subcode}</s:Value></s:Subcode></s:Code><s:Reason><s:Text xml:lang="en-US">{reason}</s:Text></s:Reason><s:Detail><f:WSManFault xmlns:f="http://schemas.microsoft.com/wbem/wsman/1/wsmanfault" Code="{wsman_code}" Machine="mock"><f:Message>{reason}</f:Message></f:WSManFault></s:Detail></s:Fault>"#
+        ),
+    )
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,131 @@
+//! What the mock server plays back: canned pipelines and scripted faults.
+
+use ironposh_psrp::{ErrorRecord, PipelineHostCall, PsPrimitiveValue, PsValue};
+
+/// A WS-Management SOAP fault, sent with HTTP 500 like a real server does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsmanFault {
+    /// `s:Code` value, `Sender` or `Receiver`.
+    pub code: &'static str,
+    /// `s:Subcode` value in the `w:` namespace, e.g. `TimedOut`.
+    pub subcode: &'static str,
+    /// Numeric `WSManFault` code carried in the fault detail.
+    pub wsman_code: u32,
+    pub reason: String,
+}
+
+impl WsmanFault {
+    /// `w:TimedOut` — a Receive outlived its OperationTimeout with nothing to say.
+    pub fn timed_out() -> Self {
+        Self {
+            code: "Receiver",
+            subcode: "TimedOut",
+            wsman_code: 2_150_858_793,
+            reason: "The WS-Management service cannot complete the operation within the time \
+                     specified in OperationTimeout."
+                .to_owned(),
+        }
+    }
+
+    /// `w:AccessDenied`.
+    pub fn access_denied() -> Self {
+        Self {
+            code: "Sender",
+            subcode: "AccessDenied",
+            wsman_code: 5,
+            reason: "Access is denied.".to_owned(),
+        }
+    }
+
+    /// `w:InvalidSelectors` with WinRM's shell-not-found code — the request
+    /// names a shell the server no longer has.
+    pub fn shell_not_found() -> Self {
+        Self {
+            code: "Sender",
+            subcode: "InvalidSelectors",
+            wsman_code: 2_150_858_843,
+            reason: "The request for the Windows Remote Shell failed because the shell was not \
+                     found on the server."
+                .to_owned(),
+        }
+    }
+
+    /// `w:InternalError` (`ERROR_INTERNAL_ERROR`) with a custom reason.
+    pub fn internal_error(reason: impl Into<String>) -> Self {
+        Self {
+            code: "Receiver",
+            subcode: "InternalError",
+            wsman_code: 1359,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// One scripted event of a [`CannedPipeline`].
+#[derive(Debug, Clone)]
+pub enum PipelineStep {
+    /// A PIPELINE_OUTPUT message.
+    Output(PsValue),
+    /// An ERROR_RECORD message on the pipeline's error stream.
+    ErrorRecord(Box<ErrorRecord>),
+    /// A PIPELINE_HOST_CALL. With `awaits_response`, playback pauses until the
+    /// client answers with a Send for this pipeline.
+    HostCall {
+        call: PipelineHostCall,
+        awaits_response: bool,
+    },
+    /// Answer the Receive with this fault instead of data.
+    Fault(WsmanFault),
+}
+
+/// The playback for one Command: the server hands out one canned pipeline per
+/// Command request, in order.
+///
+/// Steps are delivered over successive Receives — everything up to the next
+/// awaited host call or fault goes out in one ReceiveResponse — and the
+/// pipeline then reports `Completed`, or `Failed` when [`Self::failed`] is set.
+#[derive(Debug, Clone, Default)]
+pub struct CannedPipeline {
+    pub(super) steps: Vec<PipelineStep>,
+    pub(super) failure: Option<PsValue>,
+}
+
+impl CannedPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, step: PipelineStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn output(self, value: PsValue) -> Self {
+        self.step(PipelineStep::Output(value))
+    }
+
+    pub fn string_output(self, value: impl Into<String>) -> Self {
+        self.output(PsValue::Primitive(PsPrimitiveValue::Str(value.into())))
+    }
+
+    pub fn error_record(self, record: ErrorRecord) -> Self {
+        self.step(PipelineStep::ErrorRecord(Box::new(record)))
+    }
+
+    pub fn host_call(self, call: PipelineHostCall, awaits_response: bool) -> Self {
+        self.step(PipelineStep::HostCall {
+            call,
+            awaits_response,
+        })
+    }
+
+    pub fn fault(self, fault: WsmanFault) -> Self {
+        self.step(PipelineStep::Fault(fault))
+    }
+
+    /// End in `Failed` with this error record instead of `Completed`.
+    pub fn failed(mut self, error_record: PsValue) -> Self {
+        self.failure = Some(error_record);
+        self
+    }
+}
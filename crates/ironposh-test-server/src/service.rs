@@ -0,0 +1,496 @@
+//! The hyper service: authentication, request routing and PSRP playback.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter};
+use ironposh_psrp::ps_value::PsObjectWithType;
+use ironposh_psrp::{
+    ApplicationPrivateData, PipelineOutput, PipelineStateMessage, PowerShellRemotingMessage,
+    PsValue, RunspacePoolStateMessage, RunspacePoolStateValue, SessionCapability,
+};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::MockServer;
+use crate::auth::AuthDecision;
+use crate::responses;
+use crate::script::{CannedPipeline, PipelineStep, WsmanFault};
+use crate::wsman::{ClientRequest, RequestKind};
+
+const SOAP_CONTENT_TYPE: &str = "application/soap+xml;charset=UTF-8";
+
+type HttpResponse = Response<Full<Bytes>>;
+
+/// A request as the server saw it, recorded for assertions.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub authorization: Option<String>,
+    pub body: String,
+    /// HTTP status the server answered with.
+    pub status: u16,
+}
+
+/// A mock server listening on `127.0.0.1`. Dropping it stops the listener.
+#[derive(Debug)]
+pub struct RunningServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+impl RunningServer {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Every request answered so far, in completion order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().requests.clone()
+    }
+
+    /// PSRP messages the client sent (Create, Command and Send payloads).
+    pub fn client_messages(&self) -> Vec<PowerShellRemotingMessage> {
+        self.state.lock().client_messages.clone()
+    }
+}
+
+impl Drop for RunningServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub async fn spawn(config: MockServer) -> std::io::Result<RunningServer> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let addr = listener.local_addr()?;
+    let state = Arc::new(ServerState::new(config));
+
+    let accept_state = Arc::clone(&state);
+    let task = tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    warn!(%error, "mock server failed to accept a connection");
+                    continue;
+                }
+            };
+            let state = Arc::clone(&accept_state);
+            tokio::spawn(async move {
+                let service = service_fn(move |request| handle(Arc::clone(&state), request));
+                if let Err(error) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!(%error, "mock server connection ended with an error");
+                }
+            });
+        }
+    });
+
+    Ok(RunningServer { addr, state, task })
+}
+
+async fn handle(
+    state: Arc<ServerState>,
+    request: Request<Incoming>,
+) -> Result<HttpResponse, Infallible> {
+    let authorization = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let body = match request.into_body().collect().await {
+        Ok(collected) => String::from_utf8_lossy(&collected.to_bytes()).into_owned(),
+        Err(error) => {
+            warn!(%error, "mock server failed to read a request body");
+            return Ok(empty(StatusCode::BAD_REQUEST));
+        }
+    };
+
+    let response = match state.config.auth.check(authorization.as_deref()) {
+        AuthDecision::Allow => state.dispatch(&body).await,
+        AuthDecision::Challenge(challenge) => unauthorized(Some(challenge)),
+        AuthDecision::Reject => unauthorized(None),
+    };
+
+    let status = response.status().as_u16();
+    state.lock().requests.push(RecordedRequest {
+        authorization,
+        body,
+        status,
+    });
+    Ok(response)
+}
+
+#[derive(Debug)]
+struct ServerState {
+    config: MockServer,
+    session: Mutex<Session>,
+    /// Wakes held Receives when a pipeline gets a host response or a signal.
+    progress: Notify,
+}
+
+#[derive(Debug)]
+struct Session {
+    rpid: Option<Uuid>,
+    handshake_sent: bool,
+    /// Command requests seen, including the one that dropped the shell.
+    commands: usize,
+    pipelines: VecDeque<CannedPipeline>,
+    runs: HashMap<Uuid, PipelineRun>,
+    next_object_id: u64,
+    client_defragmenter: Defragmenter,
+    client_messages: Vec<PowerShellRemotingMessage>,
+    requests: Vec<RecordedRequest>,
+}
+
+impl Session {
+    /// Reserve `count` fragment object ids for server-to-client messages.
+    fn take_object_ids(&mut self, count: usize) -> u64 {
+        let start = self.next_object_id;
+        self.next_object_id += count as u64;
+        start
+    }
+
+    fn record_client_payload(&mut self, payload: &str) {
+        let Ok(bytes) = STANDARD.decode(payload) else {
+            warn!("client PSRP payload is not valid base64");
+            return;
+        };
+        match self.client_defragmenter.defragment(&bytes) {
+            Ok(DefragmentResult::Complete(messages)) => self.client_messages.extend(messages),
+            Ok(DefragmentResult::Incomplete) => {}
+            Err(error) => warn!(%error, "failed to defragment client PSRP payload"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PipelineRun {
+    steps: VecDeque<PipelineStep>,
+    failure: Option<PsValue>,
+    awaiting_host_response: bool,
+    finished: bool,
+}
+
+impl From<CannedPipeline> for PipelineRun {
+    fn from(pipeline: CannedPipeline) -> Self {
+        Self {
+            steps: pipeline.steps.into(),
+            failure: pipeline.failure,
+            awaiting_host_response: false,
+            finished: false,
+        }
+    }
+}
+
+impl ServerState {
+    fn new(config: MockServer) -> Self {
+        let session = Session {
+            rpid: None,
+            handshake_sent: false,
+            commands: 0,
+            pipelines: config.pipelines.clone().into(),
+            runs: HashMap::new(),
+            next_object_id: 1,
+            client_defragmenter: Defragmenter::new(),
+            client_messages: Vec::new(),
+            requests: Vec::new(),
+        };
+        Self {
+            config,
+            session: Mutex::new(session),
+            progress: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Session> {
+        self.session.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn dispatch(&self, body: &str) -> HttpResponse {
+        let request = ClientRequest::new(body);
+        let relates_to = request.message_id();
+
+        let kind = request.kind();
+        if !matches!(kind, RequestKind::Create | RequestKind::Other)
+            && request.selected_shell_id() != self.lock().rpid
+        {
+            return fault(&WsmanFault::shell_not_found(), relates_to);
+        }
+        match kind {
+            RequestKind::Create => self.create(&request),
+            RequestKind::Command => self.command(&request),
+            RequestKind::Receive => self.receive(&request).await,
+            RequestKind::Send => self.send(&request),
+            RequestKind::Signal => self.signal(&request),
+            RequestKind::Delete => soap(responses::delete_response(relates_to)),
+            RequestKind::Other => fault(
+                &WsmanFault::internal_error("The mock server does not support this action."),
+                relates_to,
+            ),
+        }
+    }
+
+    fn create(&self, request: &ClientRequest<'_>) -> HttpResponse {
+        let relates_to = request.message_id();
+        if let Some(create_fault) = &self.config.create_fault {
+            return fault(create_fault, relates_to);
+        }
+        let Some(rpid) = request.shell_id() else {
+            return fault(
+                &WsmanFault::internal_error("The Create request carries no ShellId."),
+                relates_to,
+            );
+        };
+
+        {
+            let mut session = self.lock();
+            session.rpid = Some(rpid);
+            session.handshake_sent = false;
+            for payload in request.psrp_payloads() {
+                session.record_client_payload(payload);
+            }
+        }
+        soap(responses::create_response(rpid, relates_to))
+    }
+
+    fn command(&self, request: &ClientRequest<'_>) -> HttpResponse {
+        let relates_to = request.message_id();
+        let Some(command_id) = request.command_id() else {
+            return fault(
+                &WsmanFault::internal_error("The Command request carries no CommandId."),
+                relates_to,
+            );
+        };
+
+        {
+            let mut session = self.lock();
+            let index = session.commands;
+            session.commands += 1;
+            if self.config.drop_shell_at_command == Some(index) {
+                debug!(index, "mock server drops the shell");
+                session.rpid = None;
+                session.runs.clear();
+                drop(session);
+                // Held Receives find the shell gone.
+                self.progress.notify_waiters();
+                return fault(&WsmanFault::shell_not_found(), relates_to);
+            }
+            for payload in request.psrp_payloads() {
+                session.record_client_payload(payload);
+            }
+            // Commands beyond the script complete without output.
+            let pipeline = session.pipelines.pop_front().unwrap_or_default();
+            session.runs.insert(command_id, pipeline.into());
+        }
+        soap(responses::command_response(command_id, relates_to))
+    }
+
+    /// Answer as soon as there is something to say, otherwise hold the
+    /// Receive until its OperationTimeout and answer `w:TimedOut`.
+    async fn receive(&self, request: &ClientRequest<'_>) -> HttpResponse {
+        let relates_to = request.message_id();
+        let deadline = tokio::time::Instant::now() + request.operation_timeout();
+
+        loop {
+            // Register for wake-ups before looking, so a Send or Signal landing
+            // in between is not missed.
+            let mut notified = std::pin::pin!(self.progress.notified());
+            notified.as_mut().enable();
+
+            if let Some(response) = self.try_receive(
+                request.selected_shell_id(),
+                request.command_id(),
+                relates_to,
+            ) {
+                return response;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return fault(&WsmanFault::timed_out(), relates_to);
+            }
+        }
+    }
+
+    fn try_receive(
+        &self,
+        shell_id: Option<Uuid>,
+        command_id: Option<Uuid>,
+        relates_to: Option<&str>,
+    ) -> Option<HttpResponse> {
+        let mut session = self.lock();
+        // The shell may have been dropped or replaced while the Receive was held.
+        let Some(rpid) = session.rpid.filter(|rpid| shell_id == Some(*rpid)) else {
+            return Some(fault(&WsmanFault::shell_not_found(), relates_to));
+        };
+
+        let Some(command_id) = command_id else {
+            // Runspace pool stream: the opening handshake, then nothing.
+            if session.handshake_sent {
+                return None;
+            }
+            session.handshake_sent = true;
+            let session_capability = SessionCapability {
+                protocol_version: "2.3".to_owned(),
+                ps_version: "2.0".to_owned(),
+                serialization_version: "1.1.0.1".to_owned(),
+                time_zone: None,
+            };
+            let private_data = ApplicationPrivateData::new();
+            let opened = RunspacePoolStateMessage::builder()
+                .runspace_state(RunspacePoolStateValue::Opened)
+                .build();
+            let messages: [&dyn PsObjectWithType; 3] =
+                [&session_capability, &private_data, &opened];
+            let object_id = session.take_object_ids(messages.len());
+            drop(session);
+            return Some(soap(responses::receive_response(
+                relates_to, rpid, None, &messages, object_id, false,
+            )));
+        };
+
+        let Some(run) = session.runs.get_mut(&command_id) else {
+            return Some(fault(
+                &WsmanFault::internal_error("Receive for an unknown CommandId."),
+                relates_to,
+            ));
+        };
+        if run.finished || run.awaiting_host_response {
+            return None;
+        }
+
+        let mut messages: Vec<Box<dyn PsObjectWithType>> = Vec::new();
+        while let Some(step) = run.steps.pop_front() {
+            match step {
+                PipelineStep::Output(data) => messages.push(Box::new(PipelineOutput { data })),
+                PipelineStep::ErrorRecord(record) => messages.push(record),
+                PipelineStep::HostCall {
+                    call,
+                    awaits_response,
+                } => {
+                    messages.push(Box::new(call));
+                    if awaits_response {
+                        run.awaiting_host_response = true;
+                        break;
+                    }
+                }
+                PipelineStep::Fault(step_fault) => {
+                    if messages.is_empty() {
+                        return Some(fault(&step_fault, relates_to));
+                    }
+                    // Deliver what came before it first.
+                    run.steps.push_front(PipelineStep::Fault(step_fault));
+                    break;
+                }
+            }
+        }
+
+        let done = run.steps.is_empty() && !run.awaiting_host_response;
+        if done {
+            run.finished = true;
+            let state = run.failure.take().map_or_else(
+                PipelineStateMessage::completed,
+                PipelineStateMessage::failed_with_error,
+            );
+            messages.push(Box::new(state));
+        }
+
+        let messages: Vec<&dyn PsObjectWithType> = messages.iter().map(Box::as_ref).collect();
+        let object_id = session.take_object_ids(messages.len());
+        drop(session);
+        Some(soap(responses::receive_response(
+            relates_to,
+            rpid,
+            Some(command_id),
+            &messages,
+            object_id,
+            done,
+        )))
+    }
+
+    fn send(&self, request: &ClientRequest<'_>) -> HttpResponse {
+        let mut session = self.lock();
+        for payload in request.psrp_payloads() {
+            session.record_client_payload(payload);
+        }
+        if let Some(run) = request
+            .command_id()
+            .and_then(|command_id| session.runs.get_mut(&command_id))
+        {
+            run.awaiting_host_response = false;
+        }
+        drop(session);
+        self.progress.notify_waiters();
+        soap(responses::send_response(request.message_id()))
+    }
+
+    /// Any signal (Ctrl+C, terminate) drops the pipeline's remaining steps;
+    /// its next Receive reports the final state.
+    fn signal(&self, request: &ClientRequest<'_>) -> HttpResponse {
+        {
+            let mut session = self.lock();
+            if let Some(run) = request
+                .command_id()
+                .and_then(|command_id| session.runs.get_mut(&command_id))
+            {
+                run.steps.clear();
+                run.awaiting_host_response = false;
+            }
+        }
+        self.progress.notify_waiters();
+        soap(responses::signal_response(request.message_id()))
+    }
+}
+
+fn soap(xml: String) -> HttpResponse {
+    xml_response(StatusCode::OK, xml)
+}
+
+fn fault(fault: &WsmanFault, relates_to: Option<&str>) -> HttpResponse {
+    xml_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        responses::fault(fault, relates_to),
+    )
+}
+
+fn xml_response(status: StatusCode, xml: String) -> HttpResponse {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, SOAP_CONTENT_TYPE)
+        .body(Full::new(Bytes::from(xml)))
+        .expect("static response parts are valid")
+}
+
+fn unauthorized(challenge: Option<&'static str>) -> HttpResponse {
+    let mut builder = Response::builder().status(StatusCode::UNAUTHORIZED);
+    if let Some(challenge) = challenge {
+        builder = builder.header(WWW_AUTHENTICATE, challenge);
+    }
+    builder
+        .body(Full::new(Bytes::new()))
+        .expect("static response parts are valid")
+}
+
+fn empty(status: StatusCode) -> HttpResponse {
+    Response::builder()
+        .status(status)
+        .body(Full::new(Bytes::new()))
+        .expect("static response parts are valid")
+}
@@ -0,0 +1,182 @@
+//! Just enough inspection of client requests to route them.
+//!
+//! Bodies are plaintext XML (HttpInsecure), and the mock only needs a handful
+//! of values out of them, so this scans for elements and attributes directly
+//! instead of mapping the whole envelope.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+/// Upper bound on how long a Receive is held, whatever OperationTimeout says.
+const MAX_RECEIVE_HOLD: Duration = Duration::from_mins(1);
+
+/// The WS-Management operation a request performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Create,
+    Command,
+    Receive,
+    Send,
+    Signal,
+    Delete,
+    Other,
+}
+
+impl RequestKind {
+    fn from_action(action: &str) -> Self {
+        match action {
+            "http://schemas.xmlsoap.org/ws/2004/09/transfer/Create" => Self::Create,
+            "http://schemas.xmlsoap.org/ws/2004/09/transfer/Delete" => Self::Delete,
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Command" => Self::Command,
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Receive" => Self::Receive,
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Send" => Self::Send,
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Signal" => Self::Signal,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// A client request body.
+pub struct ClientRequest<'a> {
+    body: &'a str,
+}
+
+impl<'a> ClientRequest<'a> {
+    pub(super) fn new(body: &'a str) -> Self {
+        Self { body }
+    }
+
+    pub(super) fn kind(&self) -> RequestKind {
+        element_text(self.body, "a:Action").map_or(RequestKind::Other, RequestKind::from_action)
+    }
+
+    /// The request's `a:MessageID`, echoed back as `a:RelatesTo`.
+    pub(super) fn message_id(&self) -> Option<&'a str> {
+        element_text(self.body, "a:MessageID")
+    }
+
+    /// The client-proposed `ShellId` of a Create request (the runspace pool id).
+    pub(super) fn shell_id(&self) -> Option<Uuid> {
+        attribute_value(self.body, "ShellId").and_then(|id| id.parse().ok())
+    }
+
+    /// The shell named by the `ShellId` selector of a request to an existing
+    /// shell.
+    pub(super) fn selected_shell_id(&self) -> Option<Uuid> {
+        element_text(self.body, "w:Selector").and_then(|id| id.parse().ok())
+    }
+
+    /// The `CommandId` a Command, Receive, Send or Signal targets.
+    pub(super) fn command_id(&self) -> Option<Uuid> {
+        attribute_value(self.body, "CommandId").and_then(|id| id.parse().ok())
+    }
+
+    /// How long a Receive may be held before answering `w:TimedOut`.
+    pub(super) fn operation_timeout(&self) -> Duration {
+        element_text(self.body, "w:OperationTimeout")
+            .and_then(|value| {
+                value
+                    .strip_prefix("PT")?
+                    .strip_suffix('S')?
+                    .parse::<f64>()
+                    .ok()
+            })
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .map_or(MAX_RECEIVE_HOLD, |timeout| timeout.min(MAX_RECEIVE_HOLD))
+    }
+
+    /// Base64 PSRP payloads the client sent: the Create `creationXml`, the
+    /// Command `rsp:Arguments` and every Send `rsp:Stream`.
+    pub(super) fn psrp_payloads(&self) -> Vec<&'a str> {
+        ["creationXml", "rsp:Arguments", "rsp:Stream"]
+            .into_iter()
+            .flat_map(|name| element_texts(self.body, name))
+            .collect()
+    }
+}
+
+/// Trimmed text content of the first `<name ...>text</name>` element.
+fn element_text<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    element_span(body, name).map(|(text, _)| text)
+}
+
+/// Trimmed text content of every `<name ...>text</name>` element.
+fn element_texts<'a>(body: &'a str, name: &str) -> Vec<&'a str> {
+    let mut texts = Vec::new();
+    let mut rest = body;
+    while let Some((text, consumed)) = element_span(rest, name) {
+        texts.push(text);
+        rest = &rest[consumed..];
+    }
+    texts
+}
+
+/// The first `name` element's trimmed text, and the offset just past its end tag.
+fn element_span<'a>(body: &'a str, name: &str) -> Option<(&'a str, usize)> {
+    let open = format!("<{name}");
+    let close = format!("</{name}>");
+    let start = body.match_indices(&open).find_map(|(index, _)| {
+        let after = &body[index + open.len()..];
+        if !after.starts_with(['>', ' ', '\t', '\r', '\n']) {
+            return None;
+        }
+        Some(index + open.len() + after.find('>')? + 1)
+    })?;
+    let end = start + body[start..].find(&close)?;
+    Some((body[start..end].trim(), end + close.len()))
+}
+
+/// Value of the first `name="..."` attribute anywhere in the body.
+fn attribute_value<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {name}=\"");
+    let start = body.find(&needle)? + needle.len();
+    let end = body[start..].find('"')?;
+    Some(&body[start..start + end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECEIVE: &str = r#"<s:Envelope><s:Header>
+        <a:Action s:mustUnderstand="true">http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Receive</a:Action>
+        <a:MessageID>uuid:0B4C1D6E-5E2A-4C0D-9E1F-7A8B9C0D1E2F</a:MessageID>
+        <w:OperationTimeout>PT1.500S</w:OperationTimeout>
+        <w:SelectorSet><w:Selector Name="ShellId">9A3B5C7D-1E2F-4A6B-8C9D-0E1F2A3B4C5D</w:Selector></w:SelectorSet>
+    </s:Header><s:Body><rsp:Receive>
+        <rsp:DesiredStream CommandId="2f1e8a0b-3c4d-4e5f-8a9b-0c1d2e3f4a5b">stdout</rsp:DesiredStream>
+    </rsp:Receive></s:Body></s:Envelope>"#;
+
+    #[test]
+    fn inspects_receive_request() {
+        let request = ClientRequest::new(RECEIVE);
+        assert_eq!(request.kind(), RequestKind::Receive);
+        assert_eq!(
+            request.message_id(),
+            Some("uuid:0B4C1D6E-5E2A-4C0D-9E1F-7A8B9C0D1E2F")
+        );
+        assert_eq!(request.operation_timeout(), Duration::from_millis(1500));
+        assert_eq!(
+            request.selected_shell_id(),
+            Some(Uuid::from_u128(0x9a3b_5c7d_1e2f_4a6b_8c9d_0e1f_2a3b_4c5d))
+        );
+        assert_eq!(
+            request.command_id(),
+            Some(Uuid::from_u128(0x2f1e_8a0b_3c4d_4e5f_8a9b_0c1d_2e3f_4a5b))
+        );
+    }
+
+    #[test]
+    fn collects_every_psrp_payload() {
+        let request = ClientRequest::new(
+            r#"<rsp:Send><rsp:Stream Name="stdin">AAA=</rsp:Stream><rsp:Stream Name="pr">BBB=</rsp:Stream></rsp:Send>"#,
+        );
+        assert_eq!(request.psrp_payloads(), ["AAA=", "BBB="]);
+    }
+
+    #[test]
+    fn element_name_must_match_exactly() {
+        assert_eq!(element_text("<a:ActionX>no</a:ActionX>", "a:Action"), None);
+    }
+}
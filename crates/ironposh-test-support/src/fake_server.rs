@@ -2,10 +2,15 @@
 //!
 //! Uses `AuthenticatorConfig::Basic` + `TransportSecurity::HttpInsecure` so request
 //! and response bodies stay plaintext XML (no SSPI, no encryption, zero network).
+//!
+//! Response builders take the `RelatesTo` to echo as their first argument;
+//! [`relates_to`] returns it for the last request sent on a connection.
+
+use std::{cell::RefCell, collections::HashMap};
 
 use base64::Engine;
 use ironposh_client_core::connector::{
-    config::{AuthenticatorConfig, TlsOptions},
+    config::AuthenticatorConfig,
     connection_pool::{ConnectionId, TrySend},
     http::{HttpBody, HttpRequest, HttpResponse, HttpResponseTargeted, ServerAddress},
     TransportSecurity, WinRmConfig,
@@ -17,7 +22,7 @@ use ironposh_psrp::{
 use ironposh_winrm::{
     cores::{Attribute, Namespace, StreamTag, Tag, Text},
     rsp::receive::{ReceiveResponseTag, ReceiveResponseValue},
-    soap::{body::SoapBody, Envelope, SoapEnvelope},
+    soap::{body::SoapBody, header::SoapHeaders, Envelope, SoapEnvelope},
 };
 use ironposh_xml::builder::Element;
 use uuid::Uuid;
@@ -34,35 +39,60 @@ pub fn test_config() -> WinRmConfig {
         .window_size(size.clone())
         .max_window_size(size.clone())
         .max_physical_window_size(size)
-        .build();
+        .build()
+        .expect("valid host data");
 
     let host_info = HostInfo::builder()
         .host_default_data(host_data)
         .use_runspace_host(true)
         .build();
 
-    WinRmConfig {
-        server: (ServerAddress::parse("127.0.0.1").unwrap(), 5985),
-        transport: TransportSecurity::HttpInsecure,
-        authentication: AuthenticatorConfig::Basic {
+    WinRmConfig::builder()
+        .server((ServerAddress::parse("127.0.0.1").unwrap(), 5985))
+        .transport(TransportSecurity::HttpInsecure)
+        .authentication(AuthenticatorConfig::Basic {
             username: "user".into(),
             password: "pass".into(),
-        },
-        host_info,
-        operation_timeout_secs: Some(1.0),
-        tls: TlsOptions::default(),
-        configuration_name: None,
-    }
+        })
+        .host_info(host_info)
+        .operation_timeout_secs(Some(1.0))
+        .build()
+}
+
+thread_local! {
+    /// MessageID of the last request [`expect_just_send`] handed out on each
+    /// connection, echoed by [`relates_to`].
+    static SENT_MESSAGE_IDS: RefCell<HashMap<ConnectionId, String>> = RefCell::default();
 }
 
 /// Extract (request, connection_id) from a TrySend (Basic auth never hits the SSPI path).
+///
+/// Records the request's MessageID so the response can relate to it.
 pub fn expect_just_send(try_send: TrySend) -> (HttpRequest, ConnectionId) {
     match try_send {
-        TrySend::JustSend { request, conn_id } => (request, conn_id),
+        TrySend::JustSend { request, conn_id } => {
+            if let Some(message_id) = request
+                .body
+                .as_ref()
+                .and_then(|body| body.as_str().ok())
+                .and_then(extract_message_id)
+            {
+                SENT_MESSAGE_IDS.with_borrow_mut(|ids| ids.insert(conn_id, message_id));
+            }
+            (request, conn_id)
+        }
         TrySend::AuthNeeded { .. } => panic!("expected JustSend, got AuthNeeded"),
     }
 }
 
+/// The MessageID of the last request sent on `conn_id`, for the `RelatesTo`
+/// header of its response.
+pub fn relates_to(conn_id: ConnectionId) -> String {
+    SENT_MESSAGE_IDS
+        .with_borrow(|ids| ids.get(&conn_id).cloned())
+        .expect("no request was sent on this connection through expect_just_send")
+}
+
 /// Build a 200 response carrying `xml`, targeted back at `conn_id`.
 pub fn xml_response(conn_id: ConnectionId, xml: String) -> HttpResponseTargeted {
     HttpResponseTargeted::new(
@@ -86,13 +116,24 @@ pub fn extract_shell_id(create_xml: &str) -> Uuid {
     captures[1].parse().expect("ShellId must be a UUID")
 }
 
+/// Parse the `a:MessageID` out of a request envelope.
+pub fn extract_message_id(request_xml: &str) -> Option<String> {
+    let re = regex::Regex::new(r"<a:MessageID>\s*([^<]+?)\s*</a:MessageID>").unwrap();
+    re.captures(request_xml)
+        .map(|captures| captures[1].to_owned())
+}
+
 /// Build a ConnectResponse SOAP envelope whose `connectResponseXml` carries the
 /// given server-to-client PSRP messages.
 ///
 /// The messages ride as a base64 blob of concatenated single fragments,
 /// mirroring how a real server answers a WSMan Connect to a disconnected
 /// shell (MS-WSMV 3.1.4.15).
-pub fn connect_response_xml(rpid: Uuid, messages: &[&dyn PsObjectWithType]) -> String {
+pub fn connect_response_xml(
+    relates_to: &str,
+    rpid: Uuid,
+    messages: &[&dyn PsObjectWithType],
+) -> String {
     let mut payload = Vec::new();
     for (index, message) in messages.iter().enumerate() {
         let remoting_message = PowerShellRemotingMessage::new(
@@ -120,8 +161,8 @@ pub fn connect_response_xml(rpid: Uuid, messages: &[&dyn PsObjectWithType]) -> S
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ConnectResponse</a:Action>
         <a:MessageID>uuid:6C334787-EF2C-40E4-992F-DE4599ED2505</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
-        <a:RelatesTo>uuid:87d0a667-c08e-4311-8d2d-069367f452d8</a:RelatesTo>
     </s:Header>
     <s:Body>
         <rsp:ConnectResponse>
@@ -133,7 +174,7 @@ pub fn connect_response_xml(rpid: Uuid, messages: &[&dyn PsObjectWithType]) -> S
 }
 
 /// Build a CommandResponse SOAP envelope acknowledging a pipeline start.
-pub fn command_response_xml(command_id: Uuid) -> String {
+pub fn command_response_xml(relates_to: &str, command_id: Uuid) -> String {
     format!(
         r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
@@ -142,6 +183,7 @@ pub fn command_response_xml(command_id: Uuid) -> String {
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/CommandResponse</a:Action>
         <a:MessageID>uuid:{message_id}</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -156,7 +198,7 @@ pub fn command_response_xml(command_id: Uuid) -> String {
 
 /// Build a `w:TimedOut` WS-Management fault envelope — what a real server
 /// returns when a Receive exhausts its OperationTimeout with no data.
-pub fn timeout_fault_xml() -> String {
+pub fn timeout_fault_xml(relates_to: &str) -> String {
     format!(
         r#"<s:Envelope xml:lang="en-US"
     xmlns:s="http://www.w3.org/2003/05/soap-envelope"
@@ -165,6 +207,7 @@ pub fn timeout_fault_xml() -> String {
     <s:Header>
         <a:Action>http://schemas.dmtf.org/wbem/wsman/1/wsman/fault</a:Action>
         <a:MessageID>uuid:{message_id}</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -207,6 +250,25 @@ pub fn signal_response_xml(relates_to: &str) -> String {
     )
 }
 
+/// Build the WS-Transfer DeleteResponse answering a Close; it only carries
+/// the Action header.
+pub fn delete_response_xml(relates_to: &str) -> String {
+    format!(
+        r#"<s:Envelope xml:lang="en-US"
+    xmlns:s="http://www.w3.org/2003/05/soap-envelope"
+    xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing">
+    <s:Header>
+        <a:Action>http://schemas.xmlsoap.org/ws/2004/09/transfer/DeleteResponse</a:Action>
+        <a:MessageID>uuid:{message_id}</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
+        <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
+    </s:Header>
+    <s:Body></s:Body>
+</s:Envelope>"#,
+        message_id = Uuid::new_v4(),
+    )
+}
+
 /// Build a ReceiveResponse SOAP envelope carrying pipeline-scoped PSRP messages
 /// (streams tagged with `CommandId`, PSRP `pid` set to the command id).
 ///
@@ -215,6 +277,7 @@ pub fn signal_response_xml(relates_to: &str) -> String {
 /// `CommandState Done` element is appended — mirroring how a real server closes
 /// out a finished pipeline.
 pub fn pipeline_receive_response_xml(
+    relates_to: &str,
     rpid: Uuid,
     command_id: Uuid,
     messages: &[&dyn PsObjectWithType],
@@ -265,6 +328,7 @@ pub fn pipeline_receive_response_xml(
     <s:Header>
         <a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReceiveResponse</a:Action>
         <a:MessageID>uuid:{message_id}</a:MessageID>
+        <a:RelatesTo>{relates_to}</a:RelatesTo>
         <a:To>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:To>
     </s:Header>
     <s:Body>
@@ -277,7 +341,11 @@ pub fn pipeline_receive_response_xml(
 
 /// Build a ReceiveResponse SOAP envelope carrying the given server-to-client PSRP
 /// messages as single-fragment `stdout` streams (no command id => runspace pool stream).
-pub fn receive_response_xml(rpid: Uuid, messages: &[&dyn PsObjectWithType]) -> String {
+pub fn receive_response_xml(
+    relates_to: &str,
+    rpid: Uuid,
+    messages: &[&dyn PsObjectWithType],
+) -> String {
     let base64_fragments: Vec<String> = messages
         .iter()
         .enumerate()
@@ -318,7 +386,14 @@ pub fn receive_response_xml(rpid: Uuid, messages: &[&dyn PsObjectWithType]) -> S
         .receive_response(receive_response_tag)
         .build();
 
-    let envelope = SoapEnvelope::builder().body(body).build();
+    let relates_to: Uuid = relates_to
+        .strip_prefix("uuid:")
+        .unwrap_or(relates_to)
+        .parse()
+        .expect("RelatesTo must be a uuid: MessageID");
+    let header = SoapHeaders::builder().relates_to(relates_to).build();
+
+    let envelope = SoapEnvelope::builder().header(header).body(body).build();
 
     let soap = Envelope::new(envelope)
         .with_declaration(Namespace::SoapEnvelope2003)
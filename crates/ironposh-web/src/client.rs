@@ -2,23 +2,39 @@ use crate::{
     error::WasmError,
     hostcall::handle_host_calls,
     http_client::GatewayHttpViaWSClient,
+    ticket::{self, SessionTicket},
     types::{JsRunCommandEvent, SecurityWarningCallback, WasmCommandCompletion, WasmWinRmConfig},
     JsSessionEvent, WasmPowerShellStream,
 };
-use futures::StreamExt;
-use ironposh_async::RemoteAsyncPowershellClient;
-use ironposh_client_core::{connector::WinRmConfig, powershell::PipelineHandle};
+use futures::{channel::oneshot, StreamExt};
+use ironposh_async::{HostCallRules, RemoteAsyncPowershellClient, SessionEvent, SessionHooks};
+use ironposh_client_core::{
+    connector::{active_session::UserEvent, WinRmConfig},
+    pipeline::PipelineOptions,
+    powershell::PipelineHandle,
+};
 use js_sys::{Array, Function, Promise};
+use std::cell::{Cell, RefCell};
 use std::convert::TryFrom;
+use std::rc::Rc;
 use tracing::{debug, error, info, warn};
 use url::Url;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::{future_to_promise, spawn_local, JsFuture};
 
 // Main PowerShell client
+//
+// Freeing it (`free()`, or `using` through `Symbol.dispose`) closes the
+// session if `close()` was not called: the shell is deleted on the server
+// and the gateway WebSocket goes away with the connection task.
 #[wasm_bindgen]
 pub struct WasmPowerShellClient {
     client: RemoteAsyncPowershellClient,
+    closed: Cell<bool>,
+    /// Issues the ticket of a `suspend()`.
+    ticket: SessionTicket,
+    /// Completed with the shell id once a `suspend()` has disconnected it.
+    suspended: Rc<RefCell<Option<oneshot::Sender<String>>>>,
 }
 
 #[wasm_bindgen]
@@ -119,7 +135,7 @@ impl WasmPowerShellClient {
         }
 
         // Proceed with connection
-        Self::connect_internal(config, host_call_handler, session_event_handler)
+        Self::connect_internal(config, None, host_call_handler, session_event_handler)
     }
 
     /// Connect to a PowerShell session (legacy method, no security callback).
@@ -142,13 +158,51 @@ impl WasmPowerShellClient {
             )));
         }
 
-        Self::connect_internal(config, host_call_handler, session_event_handler)
+        Self::connect_internal(config, None, host_call_handler, session_event_handler)
     }
 
-    fn connect_internal(
+    /// Attach to the shell a `suspend()` left on the server, from a ticket it
+    /// returned or from `storedTicket()` after a page reload. `config` must
+    /// point at the same endpoint; like `connect()`, it is rejected if it has
+    /// security warnings. The stored ticket is removed once used.
+    #[wasm_bindgen]
+    pub fn resume(
+        token: String,
         config: WasmWinRmConfig,
         host_call_handler: HostCallHandler,
         session_event_handler: SessionEventHandler,
+    ) -> Result<Self, WasmError> {
+        let shell_id = SessionTicket::decode(&token)?.shell_id_for(&config)?;
+        let warnings = config.check_security();
+        if !warnings.is_empty() {
+            error!(?warnings, "security warnings detected, refusing to resume");
+            return Err(WasmError::Generic(format!(
+                "Resume rejected: security warnings detected ({warnings:?})."
+            )));
+        }
+
+        info!(%shell_id, "resuming suspended session");
+        let client = Self::connect_internal(
+            config,
+            Some(shell_id),
+            host_call_handler,
+            session_event_handler,
+        )?;
+        ticket::forget();
+        Ok(client)
+    }
+
+    /// The ticket of the last `suspend()` in this tab, if not resumed yet.
+    #[wasm_bindgen(js_name = "storedTicket")]
+    pub fn stored_ticket() -> Option<String> {
+        ticket::load()
+    }
+
+    pub(crate) fn connect_internal(
+        config: WasmWinRmConfig,
+        connect_shell_id: Option<uuid::Uuid>,
+        host_call_handler: HostCallHandler,
+        session_event_handler: SessionEventHandler,
     ) -> Result<Self, WasmError> {
         info!(
             gateway_url = %config.gateway_url,
@@ -175,23 +229,42 @@ impl WasmPowerShellClient {
         })?;
 
         let http_client = GatewayHttpViaWSClient::new(url, config.gateway_token.clone());
+        let mut hooks = SessionHooks::default();
+        if let Some(policy) = config.host_call_policy.clone() {
+            hooks = hooks.with_host_policy(HostCallRules::from(policy));
+        }
+        let ticket = SessionTicket::new(&config, String::new());
         let internal_config: WinRmConfig = config.into();
         // Use serial (single-connection) session loop for WASM/Gateway mode.
         // Gateway enforces jti-based token replay detection, so only one WebSocket
         // connection is allowed per token. Serial mode serializes all WinRM operations.
         let (client, host_io, session_event_rx, task) =
-            RemoteAsyncPowershellClient::open_task_serial(internal_config, http_client);
+            RemoteAsyncPowershellClient::open_task_serial(
+                internal_config,
+                connect_shell_id,
+                http_client,
+                hooks,
+            );
 
         // Spawn session event handler task
+        let suspended = Rc::new(RefCell::new(None::<oneshot::Sender<String>>));
+        let suspend_reply = Rc::clone(&suspended);
         spawn_local(async move {
             let mut session_event_rx = session_event_rx;
             let session_event_handler = session_event_handler.unchecked_into::<Function>();
             while let Some(event) = session_event_rx.next().await {
+                if let SessionEvent::Suspended { shell_id } = &event {
+                    if let Some(reply) = suspend_reply.borrow_mut().take() {
+                        let _ = reply.send(shell_id.clone());
+                    }
+                }
                 let event: JsSessionEvent = event.into();
                 if let Err(e) = session_event_handler.call1(&JsValue::NULL, &event.into()) {
                     error!(?e, "failed to call session event handler");
                 }
             }
+            // The session is over; fail a `suspend()` still waiting.
+            suspend_reply.borrow_mut().take();
             info!("session event handler task exiting");
         });
 
@@ -219,7 +292,12 @@ impl WasmPowerShellClient {
         });
 
         info!("PowerShell client connected successfully");
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            closed: Cell::new(false),
+            ticket,
+            suspended,
+        })
     }
 
     #[wasm_bindgen]
@@ -249,18 +327,21 @@ impl WasmPowerShellClient {
                 });
         });
 
-        let stream = crate::stream::WasmPowerShellStream::new(stream, kill_tx);
+        let stream = crate::stream::WasmPowerShellStream::new(stream, self.client.clone(), kill_tx);
         debug!("PowerShell command stream created successfully");
         Ok(stream)
     }
 
     /// Execute a PowerShell script and emit structured pipeline events to the callback.
     /// This uses raw PSRP output (no Out-String) so callers can inspect JsPsValue.
+    /// With `timeout_ms`, a command still running after that long is stopped and
+    /// the last event is `pipelineTimedOut`.
     #[wasm_bindgen(js_name = "runCommand")]
     pub async fn run_command(
         &mut self,
         script: String,
         on_event: RunCommandCallback,
+        timeout_ms: Option<u32>,
     ) -> Result<(), WasmError> {
         if !on_event.is_function() {
             return Err(WasmError::InvalidArgument(
@@ -268,24 +349,13 @@ impl WasmPowerShellClient {
             ));
         }
 
-        let script_len = script.len();
-        debug!(script_len = %script_len, "run_command requested");
-
-        let mut stream = self.client.send_script_raw(script).await.map_err(|e| {
-            error!(error = ?e, "run_command failed to send script");
-            e
-        })?;
-
         let callback = on_event.unchecked_into::<Function>();
-
-        while let Some(event) = stream.next().await {
-            let js_event = JsRunCommandEvent::from(&event);
-            if let Err(e) = callback.call1(&JsValue::NULL, &js_event.into()) {
+        run_command_events(self.client.clone(), script, timeout_ms, |event| {
+            if let Err(e) = callback.call1(&JsValue::NULL, &event.into()) {
                 error!(error = ?e, "run_command callback failed");
             }
-        }
-
-        Ok(())
+        })
+        .await
     }
 
     #[wasm_bindgen]
@@ -294,8 +364,6 @@ impl WasmPowerShellClient {
         input_script: String,
         cursor_column: u32,
     ) -> Result<WasmCommandCompletion, WasmError> {
-        use ironposh_client_core::connector::active_session::UserEvent;
-
         fn escape_ps_single_quoted(input: &str) -> String {
             input.replace('\'', "''")
         }
@@ -318,7 +386,12 @@ impl WasmPowerShellClient {
 
         while let Some(ev) = stream.next().await {
             match ev {
-                UserEvent::PipelineOutput { output: out, .. } => {
+                UserEvent::PipelineOutput {
+                    output: out,
+                    pipeline,
+                    data_len,
+                } => {
+                    self.client.acknowledge_output(pipeline, data_len).await?;
                     if output.is_none() {
                         output = Some(out.data);
                     }
@@ -328,7 +401,7 @@ impl WasmPowerShellClient {
                     error_message = Some(concise.clone());
                     warn!(error_message = %concise, "tab_complete: error record");
                 }
-                UserEvent::PipelineFinished { .. } => break,
+                UserEvent::PipelineFinished { .. } | UserEvent::PipelineTimedOut { .. } => break,
                 UserEvent::PipelineCreated { .. } | UserEvent::PipelineRecord { .. } => {}
             }
         }
@@ -354,7 +427,63 @@ impl WasmPowerShellClient {
         Ok(WasmCommandCompletion::from(&completion))
     }
 
-    // pub async fn next_host_call
+    /// Stop the pipeline `pipeline_id` (from its `PipelineCreated` event) with
+    /// a WinRS terminate signal. Its stream ends with `PipelineFinished`.
+    #[wasm_bindgen(js_name = "stopPipeline")]
+    pub async fn stop_pipeline(&mut self, pipeline_id: String) -> Result<(), WasmError> {
+        let id = uuid::Uuid::parse_str(&pipeline_id).map_err(|e| {
+            WasmError::InvalidArgument(format!("invalid pipeline id {pipeline_id}: {e}"))
+        })?;
+        info!(%id, "stopping pipeline");
+        self.client
+            .kill_pipeline(PipelineHandle::new(id))
+            .await
+            .map_err(|e| {
+                error!(?e, %id, "failed to stop pipeline");
+                e
+            })?;
+        Ok(())
+    }
+
+    /// Close the session: delete the runspace pool shell on the server. The
+    /// session event handler then receives `Closed`. Closing twice is a no-op.
+    #[wasm_bindgen]
+    pub fn close(&self) -> Promise {
+        let client = self.take_for_close();
+        future_to_promise(async move {
+            if let Some(client) = client {
+                close_client(client).await?;
+            }
+            Ok(JsValue::NULL)
+        })
+    }
+
+    /// Disconnect the shell and end the session, leaving the runspace (its
+    /// variables, modules, running pipelines) on the server. Resolves to a
+    /// ticket for `resume()`, also kept in sessionStorage for `storedTicket()`.
+    #[wasm_bindgen]
+    pub async fn suspend(&self) -> Result<String, WasmError> {
+        // From here on the shell must outlive this client.
+        let mut client = self
+            .take_for_close()
+            .ok_or_else(|| WasmError::Generic("the session is already closed".into()))?;
+        let (reply_tx, reply_rx) = oneshot::channel();
+        *self.suspended.borrow_mut() = Some(reply_tx);
+
+        info!("suspending PowerShell session");
+        client.suspend().await?;
+        let shell_id = reply_rx.await.map_err(|_| {
+            WasmError::Generic("the session ended before the shell was disconnected".into())
+        })?;
+
+        let token = SessionTicket {
+            shell_id,
+            ..self.ticket.clone()
+        }
+        .encode()?;
+        ticket::store(&token);
+        Ok(token)
+    }
 
     #[wasm_bindgen]
     pub fn disconnect(&self) -> Promise {
@@ -368,6 +497,75 @@ impl WasmPowerShellClient {
     }
 }
 
+impl WasmPowerShellClient {
+    /// A handle on the session, for callers that outlive a `&mut self` borrow.
+    #[cfg(feature = "worker")]
+    pub(crate) fn session(&self) -> RemoteAsyncPowershellClient {
+        self.client.clone()
+    }
+
+    /// A client handle to close the session with, unless it was closed
+    /// already.
+    pub(crate) fn take_for_close(&self) -> Option<RemoteAsyncPowershellClient> {
+        (!self.closed.replace(true)).then(|| self.client.clone())
+    }
+}
+
+impl Drop for WasmPowerShellClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.take_for_close() {
+            info!("PowerShell client freed without close(), closing the session");
+            spawn_local(async move {
+                let _ = close_client(client).await;
+            });
+        }
+    }
+}
+
+/// Run `script` raw (no Out-String) and hand each event to `on_event`.
+pub(crate) async fn run_command_events(
+    mut client: RemoteAsyncPowershellClient,
+    script: String,
+    timeout_ms: Option<u32>,
+    mut on_event: impl FnMut(JsRunCommandEvent),
+) -> Result<(), WasmError> {
+    let script_len = script.len();
+    debug!(script_len = %script_len, "run_command requested");
+
+    let options = PipelineOptions {
+        timeout: timeout_ms.map(|ms| std::time::Duration::from_millis(ms.into())),
+        stop_on_error: false,
+    };
+    let mut stream = client
+        .send_script_raw_with_options(script, options)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "run_command failed to send script");
+            e
+        })?;
+
+    while let Some(event) = stream.next().await {
+        on_event(JsRunCommandEvent::from(&event));
+        // Handed to JavaScript, so the server may send more of it.
+        if let UserEvent::PipelineOutput {
+            pipeline, data_len, ..
+        } = event
+        {
+            client.acknowledge_output(pipeline, data_len).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn close_client(mut client: RemoteAsyncPowershellClient) -> Result<(), WasmError> {
+    info!("closing PowerShell session");
+    client.close().await.map_err(|e| {
+        error!(?e, "failed to close PowerShell session");
+        WasmError::from(e)
+    })
+}
+
 async fn disconnect_client(mut client: RemoteAsyncPowershellClient) -> Result<(), WasmError> {
     // The web client always runs the serial session loop, where disconnect is not
     // supported yet. The WebTerminal component calls disconnect() fire-and-forget,
@@ -385,7 +583,7 @@ mod tests {
     use futures::FutureExt;
     use ironposh_async::HttpClient;
     use ironposh_client_core::connector::{
-        config::{AuthenticatorConfig, TlsOptions},
+        config::AuthenticatorConfig,
         connection_pool::TrySend,
         http::{HttpResponseTargeted, ServerAddress},
         TransportSecurity, WinRmConfig,
@@ -406,7 +604,12 @@ mod tests {
     #[test]
     fn web_disconnect_resolves_in_serial_mode() {
         let (client, _host_io, _session_event_rx, _task) =
-            RemoteAsyncPowershellClient::open_task_serial(test_config(), NeverHttpClient);
+            RemoteAsyncPowershellClient::open_task_serial(
+                test_config(),
+                None,
+                NeverHttpClient,
+                SessionHooks::default(),
+            );
 
         disconnect_client(client)
             .now_or_never()
@@ -424,23 +627,22 @@ mod tests {
             .window_size(size.clone())
             .max_window_size(size.clone())
             .max_physical_window_size(size)
-            .build();
+            .build()
+            .expect("valid host data");
         let host_info = HostInfo::builder()
             .host_default_data(host_data)
             .use_runspace_host(true)
             .build();
 
-        WinRmConfig {
-            server: (ServerAddress::parse("127.0.0.1").unwrap(), 5985),
-            transport: TransportSecurity::HttpInsecure,
-            authentication: AuthenticatorConfig::Basic {
+        WinRmConfig::builder()
+            .server((ServerAddress::parse("127.0.0.1").unwrap(), 5985))
+            .transport(TransportSecurity::HttpInsecure)
+            .authentication(AuthenticatorConfig::Basic {
                 username: "user".into(),
                 password: "pass".into(),
-            },
-            host_info,
-            operation_timeout_secs: Some(0.25),
-            tls: TlsOptions::default(),
-            configuration_name: None,
-        }
+            })
+            .host_info(host_info)
+            .operation_timeout_secs(Some(0.25))
+            .build()
     }
 }
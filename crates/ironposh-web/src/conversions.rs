@@ -3,16 +3,23 @@ use std::convert::TryFrom;
 use crate::{
     error::WasmError,
     types::{
-        GatewayTransport, JsRunCommandEvent, SecurityWarning, WasmAuthMethod,
-        WasmHostInformationMessage, WasmInformationMessageData, WasmPowerShellEvent,
-        WasmPsrpRecord, WasmPsrpRecordMeta, WasmWinRmConfig,
+        GatewayTransport, JsRunCommandEvent, SecurityWarning, WasmAuthMethod, WasmHostCallPolicy,
+        WasmHostInformationMessage, WasmIdleAction, WasmIdlePolicy, WasmInformationMessageData,
+        WasmInformationRecord, WasmPowerShellEvent, WasmProgressRecord, WasmPsrpRecord,
+        WasmPsrpRecordMeta, WasmWinRmConfig,
     },
     JsPsValue, WasmErrorRecord,
 };
+use ironposh_async::HostCallRules;
+#[cfg(feature = "kerberos")]
+use ironposh_client_core::connector::config::{Delegation, KdcEndpoint, KerberosConfig};
 use ironposh_client_core::{
     connector::active_session::UserEvent,
     connector::{
-        config::{AuthenticatorConfig, KerberosConfig, SspiAuthConfig},
+        config::{
+            AuthenticatorConfig, IdleAction, IdlePolicy, ReceiveFlowConfig, ServicePrincipal,
+            SspiAuthConfig, TlsOptions,
+        },
         http::ServerAddress,
         TransportSecurity, WinRmConfig,
     },
@@ -20,8 +27,46 @@ use ironposh_client_core::{
     psrp_record::PsrpRecord,
 };
 use ironposh_psrp::messages::init_runspace_pool::{HostDefaultData, HostInfo, Size};
+use ironposh_psrp::{
+    InformationMessageData, InformationRecord, ProgressRecord, ProgressRecordType,
+    RemoteHostMethodId,
+};
 use tracing::warn;
 
+impl From<WasmIdlePolicy> for IdlePolicy {
+    fn from(policy: WasmIdlePolicy) -> Self {
+        Self {
+            after: std::time::Duration::from_secs(u64::from(policy.after_secs)),
+            action: match policy.action {
+                WasmIdleAction::Disconnect => IdleAction::Disconnect,
+                WasmIdleAction::Close => IdleAction::Close,
+            },
+        }
+    }
+}
+
+impl From<WasmHostCallPolicy> for HostCallRules {
+    fn from(policy: WasmHostCallPolicy) -> Self {
+        let mut rules = Self::new();
+        for name in &policy.deny {
+            match host_method_by_name(name) {
+                Some(method) => rules = rules.with_denied(method),
+                None => warn!(method = %name, "unknown host method in host call policy, ignoring"),
+            }
+        }
+        if policy.default_choices {
+            rules = rules.with_default_choices();
+        }
+        rules
+    }
+}
+
+fn host_method_by_name(name: &str) -> Option<RemoteHostMethodId> {
+    (1..=56)
+        .filter_map(RemoteHostMethodId::from_id)
+        .find(|method| format!("{method:?}") == name)
+}
+
 // =============================================================================
 // Security Check
 // =============================================================================
@@ -36,11 +81,15 @@ impl WasmWinRmConfig {
     ///   Gateway just forwards encrypted bytes, so gateway channel security doesn't matter.
     /// - TLS provides encryption for the destination channel only.
     ///   Gateway channel security matters in this case.
+    /// - Without authentication (`WasmAuthMethod::None`) there is no SSPI
+    ///   context to seal with, and the connection is always flagged.
     pub fn check_security(&self) -> Vec<SecurityWarning> {
         let gateway_secure = self.gateway_url.starts_with("wss://");
+        let auth_disabled = matches!(self.auth, WasmAuthMethod::None);
 
         // Check if SSPI sealing is enabled (TCP transport without force_insecure)
-        let sspi_enabled = matches!(self.destination.transport, GatewayTransport::Tcp)
+        let sspi_enabled = !auth_disabled
+            && matches!(self.destination.transport, GatewayTransport::Tcp)
             && !self.force_insecure.unwrap_or(false);
 
         // SSPI is end-to-end encryption - if enabled, data is always secure regardless of gateway
@@ -51,12 +100,16 @@ impl WasmWinRmConfig {
         // No SSPI - check both channels
         let destination_secure = matches!(self.destination.transport, GatewayTransport::Tls);
 
-        match (gateway_secure, destination_secure) {
+        let mut warnings = match (gateway_secure, destination_secure) {
             (true, true) => vec![],                                         // WSS + TLS
             (false, false) => vec![SecurityWarning::BothChannelsInsecure],  // WS + TCP without SSPI
             (false, true) => vec![SecurityWarning::GatewayChannelInsecure], // WS + TLS (gateway exposed)
             (true, false) => vec![SecurityWarning::DestinationChannelInsecure], // WSS + TCP without SSPI
+        };
+        if auth_disabled {
+            warnings.insert(0, SecurityWarning::AuthenticationDisabled);
         }
+        warnings
     }
 }
 
@@ -82,6 +135,13 @@ impl From<WasmWinRmConfig> for WinRmConfig {
             raw_ui_enabled,
             force_insecure,
             configuration_name,
+            service_principal,
+            application_arguments,
+            idle_policy,
+            heartbeat_interval_ms,
+            host_call_policy: _,
+            max_concurrent_receives,
+            max_pending_output_bytes,
         } = config;
 
         let size = Size {
@@ -124,7 +184,11 @@ impl From<WasmWinRmConfig> for WinRmConfig {
                     .window_size(size.clone())
                     .max_window_size(size.clone())
                     .max_physical_window_size(size)
-                    .build(),
+                    .build()
+                    .unwrap_or_else(|e| {
+                        warn!(%e, cols, rows, "unusable terminal size, using the default host data");
+                        HostDefaultData::default()
+                    }),
             )
             .build();
 
@@ -141,22 +205,26 @@ impl From<WasmWinRmConfig> for WinRmConfig {
                     identity,
                 })
             }
+            #[cfg(feature = "kerberos")]
             WasmAuthMethod::Kerberos => {
                 let client_username =
                     ClientUserName::new(&username, domain).expect("Invalid username/domain");
                 let identity = ClientAuthIdentity::new(client_username, password);
 
-                let kdc_url = kdc_proxy_url
-                    .as_ref()
-                    .map(|url| url.parse().expect("Invalid kdc_proxy_url"));
+                // The browser owns TLS, so the proxy's TLS options are unused here.
+                let kdc = kdc_proxy_url.as_ref().map(|url| KdcEndpoint::KdcProxy {
+                    url: url.parse().expect("Invalid kdc_proxy_url"),
+                    tls: TlsOptions::default(),
+                });
 
                 AuthenticatorConfig::Sspi(SspiAuthConfig::Kerberos {
                     target: destination.host.clone(),
                     identity,
                     kerberos_config: KerberosConfig {
-                        kdc_url,
+                        kdc,
                         client_computer_name: client_computer_name
                             .unwrap_or_else(|| destination.host.clone()),
+                        delegation: Delegation::None,
                     },
                 })
             }
@@ -165,37 +233,62 @@ impl From<WasmWinRmConfig> for WinRmConfig {
                     ClientUserName::new(&username, domain).expect("Invalid username/domain");
                 let identity = ClientAuthIdentity::new(client_username, password);
 
-                let kdc_url = kdc_proxy_url
-                    .as_ref()
-                    .map(|url| url.parse().expect("Invalid kdc_proxy_url"));
+                #[cfg(feature = "kerberos")]
+                let kerberos_config = Some(KerberosConfig {
+                    kdc: kdc_proxy_url.as_ref().map(|url| KdcEndpoint::KdcProxy {
+                        url: url.parse().expect("Invalid kdc_proxy_url"),
+                        tls: TlsOptions::default(),
+                    }),
+                    client_computer_name: client_computer_name
+                        .unwrap_or_else(|| destination.host.clone()),
+                    delegation: Delegation::None,
+                });
+                #[cfg(not(feature = "kerberos"))]
+                let kerberos_config = {
+                    let _ = (kdc_proxy_url, client_computer_name);
+                    None
+                };
 
                 AuthenticatorConfig::Sspi(SspiAuthConfig::Negotiate {
                     target: destination.host.clone(),
                     identity,
-                    kerberos_config: Some(KerberosConfig {
-                        kdc_url,
-                        client_computer_name: client_computer_name
-                            .unwrap_or_else(|| destination.host.clone()),
-                    }),
+                    kerberos_config,
                 })
             }
+            WasmAuthMethod::None => AuthenticatorConfig::None,
         };
 
-        Self {
-            server: (server, destination.port),
-            transport,
-            authentication,
-            host_info,
+        Self::builder()
+            .server((server, destination.port))
+            .transport(transport)
+            .authentication(authentication)
+            .host_info(host_info)
             // Short timeout for serial/single-connection mode so Receives
             // don't block outbound sends for too long.
-            operation_timeout_secs: Some(0.25),
+            .operation_timeout_secs(Some(0.25))
             // The browser owns TLS for the WASM client; options are ignored there.
-            tls: ironposh_client_core::connector::config::TlsOptions::default(),
-            configuration_name,
-        }
+            .tls(TlsOptions::default())
+            .configuration_name(configuration_name)
+            .receive_flow(ReceiveFlowConfig {
+                max_concurrent_receives: max_concurrent_receives.map(|n| n as usize),
+                max_pending_output_bytes: max_pending_output_bytes.map(|n| n as usize),
+            })
+            .service_principal(service_principal.map(ServicePrincipal::new))
+            .idle_policy(idle_policy.map(IdlePolicy::from))
+            .heartbeat_interval(
+                heartbeat_interval_ms.map(|ms| std::time::Duration::from_millis(u64::from(ms))),
+            )
+            .host_mode(ironposh_client_core::connector::config::HostMode::Interactive)
+            .parse_mode(ironposh_client_core::connector::config::ParseMode::Strict)
+            .build()
+            .with_application_arguments(application_arguments.unwrap_or_default())
     }
 }
 
+fn timeout_ms(timeout: std::time::Duration) -> u32 {
+    u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX)
+}
+
 // Convert internal UserEvent to WASM event
 impl TryFrom<&UserEvent> for WasmPowerShellEvent {
     type Error = crate::error::WasmError;
@@ -207,7 +300,9 @@ impl TryFrom<&UserEvent> for WasmPowerShellEvent {
             UserEvent::PipelineFinished { pipeline } => Self::PipelineFinished {
                 pipeline_id: pipeline.id().to_string(),
             },
-            UserEvent::PipelineOutput { pipeline, output } => Self::PipelineOutput {
+            UserEvent::PipelineOutput {
+                pipeline, output, ..
+            } => Self::PipelineOutput {
                 pipeline_id: pipeline.id().to_string(),
                 data: if let Ok(str) = output.assume_primitive_string() {
                     str.clone()
@@ -230,18 +325,92 @@ impl TryFrom<&UserEvent> for WasmPowerShellEvent {
                 error: WasmErrorRecord::from(error_record),
             },
             UserEvent::PipelineRecord { pipeline, record } => {
-                let record = WasmPsrpRecord::from(record);
-                Self::PipelineRecord {
-                    pipeline_id: pipeline.id().to_string(),
-                    record,
+                let pipeline_id = pipeline.id().to_string();
+                match record {
+                    PsrpRecord::Verbose { message, .. } => Self::Verbose {
+                        pipeline_id,
+                        message: message.clone(),
+                    },
+                    PsrpRecord::Warning { message, .. } => Self::Warning {
+                        pipeline_id,
+                        message: message.clone(),
+                    },
+                    PsrpRecord::Debug { message, .. } => Self::Debug {
+                        pipeline_id,
+                        message: message.clone(),
+                    },
+                    PsrpRecord::Information { record, .. } => Self::Information {
+                        pipeline_id,
+                        information: WasmInformationRecord::from(record),
+                    },
+                    PsrpRecord::Progress { record, .. } => Self::Progress {
+                        pipeline_id,
+                        progress: WasmProgressRecord::from(record),
+                    },
+                    PsrpRecord::Unsupported { .. } => Self::PipelineRecord {
+                        pipeline_id,
+                        record: WasmPsrpRecord::from(record),
+                    },
                 }
             }
+            UserEvent::PipelineTimedOut { pipeline, timeout } => Self::PipelineTimedOut {
+                pipeline_id: pipeline.id().to_string(),
+                timeout_ms: timeout_ms(*timeout),
+            },
         };
 
         Ok(res)
     }
 }
 
+impl From<&InformationMessageData> for WasmInformationMessageData {
+    fn from(data: &InformationMessageData) -> Self {
+        match data {
+            InformationMessageData::String(s) => Self::String { value: s.clone() },
+            InformationMessageData::HostInformationMessage(m) => Self::HostInformationMessage {
+                value: WasmHostInformationMessage {
+                    message: m.message.clone(),
+                    foreground_color: m.foreground_color,
+                    background_color: m.background_color,
+                    no_new_line: m.no_new_line,
+                },
+            },
+            InformationMessageData::Object(v) => Self::Object {
+                value: JsPsValue::from(v.clone()),
+            },
+        }
+    }
+}
+
+impl From<&InformationRecord> for WasmInformationRecord {
+    fn from(record: &InformationRecord) -> Self {
+        Self {
+            message_data: WasmInformationMessageData::from(&record.message_data),
+            source: record.source.clone(),
+            time_generated: record.time_generated.clone(),
+            tags: record.tags.clone(),
+            user: record.user.clone(),
+            computer: record.computer.clone(),
+            process_id: record.process_id,
+        }
+    }
+}
+
+impl From<&ProgressRecord> for WasmProgressRecord {
+    fn from(record: &ProgressRecord) -> Self {
+        Self {
+            activity: record.activity.clone(),
+            activity_id: record.activity_id,
+            status_description: record.status_description.clone(),
+            current_operation: record.current_operation.clone(),
+            parent_activity_id: record.parent_activity_id,
+            percent_complete: record.percent_complete,
+            seconds_remaining: record.seconds_remaining,
+            completed: record.progress_type == ProgressRecordType::Completed,
+        }
+    }
+}
+
 impl From<&PsrpRecord> for WasmPsrpRecord {
     fn from(record: &PsrpRecord) -> Self {
         let meta = match record {
@@ -274,39 +443,16 @@ impl From<&PsrpRecord> for WasmPsrpRecord {
                 meta,
                 message: message.clone(),
             },
-            PsrpRecord::Information { record, .. } => {
-                let message_data = match &record.message_data {
-                    ironposh_psrp::InformationMessageData::String(s) => {
-                        WasmInformationMessageData::String { value: s.clone() }
-                    }
-                    ironposh_psrp::InformationMessageData::HostInformationMessage(m) => {
-                        WasmInformationMessageData::HostInformationMessage {
-                            value: WasmHostInformationMessage {
-                                message: m.message.clone(),
-                                foreground_color: m.foreground_color,
-                                background_color: m.background_color,
-                                no_new_line: m.no_new_line,
-                            },
-                        }
-                    }
-                    ironposh_psrp::InformationMessageData::Object(v) => {
-                        WasmInformationMessageData::Object {
-                            value: JsPsValue::from(v.clone()),
-                        }
-                    }
-                };
-
-                Self::Information {
-                    meta,
-                    message_data,
-                    source: record.source.clone(),
-                    time_generated: record.time_generated.clone(),
-                    tags: record.tags.clone(),
-                    user: record.user.clone(),
-                    computer: record.computer.clone(),
-                    process_id: record.process_id,
-                }
-            }
+            PsrpRecord::Information { record, .. } => Self::Information {
+                meta,
+                message_data: WasmInformationMessageData::from(&record.message_data),
+                source: record.source.clone(),
+                time_generated: record.time_generated.clone(),
+                tags: record.tags.clone(),
+                user: record.user.clone(),
+                computer: record.computer.clone(),
+                process_id: record.process_id,
+            },
             PsrpRecord::Progress { record, .. } => Self::Progress {
                 meta,
                 activity: record.activity.clone(),
@@ -334,7 +480,9 @@ impl From<&UserEvent> for JsRunCommandEvent {
             UserEvent::PipelineFinished { pipeline } => Self::PipelineFinished {
                 pipeline_id: pipeline.id().to_string(),
             },
-            UserEvent::PipelineOutput { pipeline, output } => Self::PipelineOutput {
+            UserEvent::PipelineOutput {
+                pipeline, output, ..
+            } => Self::PipelineOutput {
                 pipeline_id: pipeline.id().to_string(),
                 value: JsPsValue::from(output.data.clone()),
             },
@@ -349,6 +497,10 @@ impl From<&UserEvent> for JsRunCommandEvent {
                 pipeline_id: pipeline.id().to_string(),
                 record: Box::new(WasmPsrpRecord::from(record)),
             },
+            UserEvent::PipelineTimedOut { pipeline, timeout } => Self::PipelineTimedOut {
+                pipeline_id: pipeline.id().to_string(),
+                timeout_ms: timeout_ms(*timeout),
+            },
         }
     }
 }
@@ -380,6 +532,13 @@ mod tests {
             raw_ui_enabled: Some(true),
             force_insecure: None,
             configuration_name: None,
+            service_principal: None,
+            application_arguments: None,
+            idle_policy: None,
+            heartbeat_interval_ms: None,
+            host_call_policy: None,
+            max_concurrent_receives: None,
+            max_pending_output_bytes: None,
         };
 
         let winrm: WinRmConfig = cfg.into();
@@ -409,9 +568,110 @@ mod tests {
             raw_ui_enabled: Some(true),
             force_insecure: None,
             configuration_name: Some("MyJEAEndpoint".to_string()),
+            service_principal: None,
+            application_arguments: None,
+            idle_policy: None,
+            heartbeat_interval_ms: None,
+            host_call_policy: None,
+            max_concurrent_receives: None,
+            max_pending_output_bytes: None,
         };
 
         let winrm: WinRmConfig = cfg.into();
         assert_eq!(winrm.configuration_name.as_deref(), Some("MyJEAEndpoint"));
     }
+
+    #[test]
+    fn wasm_config_passes_receive_limits_through() {
+        let cfg = WasmWinRmConfig {
+            auth: WasmAuthMethod::Basic,
+            destination: WinRmDestination {
+                host: "127.0.0.1".to_string(),
+                port: 5985,
+                transport: GatewayTransport::Tcp,
+            },
+            gateway_url: "ws://localhost:7171".to_string(),
+            gateway_token: "token".to_string(),
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            domain: None,
+            locale: None,
+            kdc_proxy_url: None,
+            client_computer_name: None,
+            cols: 120,
+            rows: 30,
+            raw_ui_enabled: Some(true),
+            force_insecure: None,
+            configuration_name: None,
+            service_principal: None,
+            application_arguments: None,
+            idle_policy: None,
+            heartbeat_interval_ms: None,
+            host_call_policy: None,
+            max_concurrent_receives: Some(2),
+            max_pending_output_bytes: Some(1 << 20),
+        };
+
+        let winrm: WinRmConfig = cfg.into();
+        assert_eq!(winrm.receive_flow.max_concurrent_receives, Some(2));
+        assert_eq!(winrm.receive_flow.max_pending_output_bytes, Some(1 << 20));
+    }
+
+    #[test]
+    fn wasm_config_passes_application_arguments_through() {
+        let cfg = WasmWinRmConfig {
+            auth: WasmAuthMethod::Basic,
+            destination: WinRmDestination {
+                host: "127.0.0.1".to_string(),
+                port: 5985,
+                transport: GatewayTransport::Tcp,
+            },
+            gateway_url: "ws://localhost:7171".to_string(),
+            gateway_token: "token".to_string(),
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            domain: None,
+            locale: None,
+            kdc_proxy_url: None,
+            client_computer_name: None,
+            cols: 120,
+            rows: 30,
+            raw_ui_enabled: Some(true),
+            force_insecure: None,
+            configuration_name: None,
+            service_principal: None,
+            application_arguments: Some(
+                [("Tenant".to_string(), "contoso".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            idle_policy: None,
+            heartbeat_interval_ms: None,
+            host_call_policy: None,
+            max_concurrent_receives: None,
+            max_pending_output_bytes: None,
+        };
+
+        let winrm: WinRmConfig = cfg.into();
+        assert_eq!(
+            winrm
+                .application_arguments
+                .additional_arguments
+                .get("Tenant"),
+            Some(&"contoso".into())
+        );
+    }
+
+    #[test]
+    fn host_call_policy_maps_method_names() {
+        assert_eq!(
+            host_method_by_name("PromptForCredential2"),
+            Some(RemoteHostMethodId::PromptForCredential2)
+        );
+        assert_eq!(
+            host_method_by_name("PromptForChoiceMultipleSelection"),
+            Some(RemoteHostMethodId::PromptForChoiceMultipleSelection)
+        );
+        assert_eq!(host_method_by_name("NoSuchMethod"), None);
+    }
 }
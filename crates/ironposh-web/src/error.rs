@@ -1,3 +1,5 @@
+use ironposh_client_core::user_message::{EnglishCatalog, UserMessage};
+use ironposh_client_core::PwshCoreError;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 use tsify::Tsify;
@@ -37,10 +39,22 @@ unsafe impl Send for WasmError {}
 unsafe impl Sync for WasmError {}
 
 impl WasmError {
+    /// The structured session error behind this one, if any.
+    fn core_error(&self) -> Option<&PwshCoreError> {
+        match self {
+            Self::IronPoshError(e) => e
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<PwshCoreError>()),
+            _ => None,
+        }
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Self::IOError(_) => "IOError",
-            Self::IronPoshError(_) => "IronPoshError",
+            Self::IronPoshError(_) => self
+                .core_error()
+                .map_or("IronPoshError", |e| e.code().as_str()),
             Self::Generic(_) => "GenericError",
             Self::WebSocket(_) => "WebSocketError",
             Self::PowerShellError(_) => "PowerShellError",
@@ -58,9 +72,13 @@ impl From<WasmError> for IronPoshError {
             error_message = %value,
             "converting WasmError to IronPoshError"
         );
+        let core = value.core_error();
         Self {
             code: value.name().to_string(),
             message: value.to_string(),
+            retryable: core.is_some_and(PwshCoreError::is_retryable),
+            auth_failure: core.is_some_and(PwshCoreError::is_auth_failure),
+            user_message: core.map(|e| WasmUserMessage::from(&UserMessage::from(e))),
         }
     }
 }
@@ -80,6 +98,49 @@ impl From<WasmError> for JsValue {
 #[derive(Serialize, Deserialize, Tsify)]
 #[tsify(from_wasm_abi, into_wasm_abi)]
 pub struct IronPoshError {
+    /// `ErrorCode` string for session errors (e.g. `authentication_failed`),
+    /// otherwise the `WasmError` kind.
     pub code: String,
     pub message: String,
+    /// Repeating the operation later may succeed.
+    pub retryable: bool,
+    /// The credentials were rejected; prompt for new ones rather than retrying.
+    #[serde(rename = "authFailure")]
+    pub auth_failure: bool,
+    /// Friendly message for session errors; `id` and `params` let the UI
+    /// localize it, `text` is the English rendering.
+    #[serde(rename = "userMessage")]
+    pub user_message: Option<WasmUserMessage>,
+}
+
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(from_wasm_abi, into_wasm_abi)]
+pub struct WasmUserMessage {
+    pub id: String,
+    pub params: Vec<WasmMessageParam>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(from_wasm_abi, into_wasm_abi)]
+pub struct WasmMessageParam {
+    pub name: String,
+    pub value: String,
+}
+
+impl From<&UserMessage> for WasmUserMessage {
+    fn from(message: &UserMessage) -> Self {
+        Self {
+            id: message.id.to_owned(),
+            params: message
+                .params
+                .iter()
+                .map(|(name, value)| WasmMessageParam {
+                    name: (*name).to_owned(),
+                    value: value.clone(),
+                })
+                .collect(),
+            text: message.render(&EnglishCatalog),
+        }
+    }
 }
@@ -47,12 +47,7 @@ fn exception_submission(
     method: ironposh_psrp::RemoteHostMethodId,
     message: String,
 ) -> Submission {
-    Submission::Send(PipelineHostResponse {
-        call_id,
-        method,
-        method_result: None,
-        method_exception: Some(PsValue::from(message)),
-    })
+    Submission::Send(PipelineHostResponse::exception(call_id, method, message))
 }
 
 fn utf16le_bytes(s: &str) -> Vec<u8> {
@@ -2,16 +2,20 @@ use std::rc::Rc;
 
 use anyhow::Result;
 use futures::{lock::Mutex, SinkExt};
+#[cfg(feature = "kerberos")]
 use gloo_net::http::Request;
 use gloo_net::websocket::futures::WebSocket;
 use ironposh_async::HttpClient;
+#[cfg(feature = "kerberos")]
+use ironposh_client_core::connector::NetworkProtocol;
 use ironposh_client_core::connector::{
     auth_sequence::SspiAuthSequence,
     authenticator::SecContextMaybeInit,
     connection_pool::{ConnectionId, SecContextInited, TrySend},
     http::{HttpRequest, HttpRequestAction, HttpResponse, HttpResponseTargeted},
-    NetworkProtocol, NetworkRequest,
+    NetworkRequest,
 };
+#[cfg(feature = "kerberos")]
 use js_sys::Uint8Array;
 use tracing::{debug, error, info, instrument, trace};
 
@@ -203,6 +207,7 @@ impl GatewayHttpViaWSClient {
         stream.send_http(req).await
     }
 
+    #[cfg(feature = "kerberos")]
     async fn send_kdc_network_request(packet: &NetworkRequest) -> Result<Vec<u8>, WasmError> {
         debug!(
             protocol = ?packet.protocol,
@@ -243,6 +248,15 @@ impl GatewayHttpViaWSClient {
             ))),
         }
     }
+
+    #[cfg(not(feature = "kerberos"))]
+    #[expect(clippy::unused_async, reason = "same signature as the kerberos build")]
+    async fn send_kdc_network_request(packet: &NetworkRequest) -> Result<Vec<u8>, WasmError> {
+        Err(WasmError::Generic(format!(
+            "KDC request to {} needs a build with the kerberos feature",
+            packet.url
+        )))
+    }
 }
 
 #[derive(Debug, Clone)]
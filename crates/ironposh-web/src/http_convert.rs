@@ -192,6 +192,7 @@ mod tests {
             headers: vec![("User-Agent".to_string(), "test".to_string())],
             cookie: None,
             body: None,
+            connect_to: None,
         };
 
         let bytes = serialize_http_request(&request).unwrap();
@@ -210,6 +211,7 @@ mod tests {
             headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
             cookie: None,
             body: Some(HttpBody::Text("test body".to_string())),
+            connect_to: None,
         };
 
         let bytes = serialize_http_request(&request).unwrap();
@@ -14,8 +14,11 @@ pub mod http_client;
 pub mod http_convert;
 pub mod runner;
 pub mod stream;
+pub mod ticket;
 pub mod types;
 pub mod websocket;
+#[cfg(feature = "worker")]
+pub mod worker;
 pub mod ws_http_decoder;
 
 // Re-export the main types for JS/TS
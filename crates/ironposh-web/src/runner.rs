@@ -6,7 +6,7 @@ use crate::{
     error::WasmError,
     types::{
         SecurityWarningCallback, WasmCommandCompletion, WasmInformationMessageData,
-        WasmPowerShellEvent, WasmPsrpRecord, WasmWinRmConfig,
+        WasmPowerShellEvent, WasmProgressRecord, WasmPsrpRecord, WasmWinRmConfig,
     },
     WasmPowerShellClient,
 };
@@ -16,20 +16,29 @@ fn noop_js_function() -> Function {
     Function::new_no_args("return undefined;")
 }
 
+fn information_text(message_data: &WasmInformationMessageData) -> String {
+    match message_data {
+        WasmInformationMessageData::String { value } => value.clone(),
+        WasmInformationMessageData::HostInformationMessage { value } => value.message.clone(),
+        WasmInformationMessageData::Object { value } => format!("{value:?}"),
+    }
+}
+
+fn format_progress(progress: &WasmProgressRecord) -> String {
+    let status = progress.status_description.clone().unwrap_or_default();
+    format!(
+        "[progress] {}: {status} ({}%)",
+        progress.activity, progress.percent_complete
+    )
+}
+
 fn format_record(record: &WasmPsrpRecord) -> String {
     match record {
         WasmPsrpRecord::Debug { message, .. } => format!("[debug] {message}"),
         WasmPsrpRecord::Verbose { message, .. } => format!("[verbose] {message}"),
         WasmPsrpRecord::Warning { message, .. } => format!("[warning] {message}"),
         WasmPsrpRecord::Information { message_data, .. } => {
-            let text = match message_data {
-                WasmInformationMessageData::String { value } => value.clone(),
-                WasmInformationMessageData::HostInformationMessage { value } => {
-                    value.message.clone()
-                }
-                WasmInformationMessageData::Object { value } => format!("{value:?}"),
-            };
-            format!("[information] {text}")
+            format!("[information] {}", information_text(message_data))
         }
         WasmPsrpRecord::Progress {
             activity,
@@ -128,9 +137,32 @@ impl WasmPowerShellRunner {
                 WasmPowerShellEvent::PipelineRecord { record, .. } => {
                     lines.push(format_record(&record));
                 }
+                WasmPowerShellEvent::Debug { message, .. } => {
+                    lines.push(format!("[debug] {message}"));
+                }
+                WasmPowerShellEvent::Verbose { message, .. } => {
+                    lines.push(format!("[verbose] {message}"));
+                }
+                WasmPowerShellEvent::Warning { message, .. } => {
+                    lines.push(format!("[warning] {message}"));
+                }
+                WasmPowerShellEvent::Information { information, .. } => {
+                    lines.push(format!(
+                        "[information] {}",
+                        information_text(&information.message_data)
+                    ));
+                }
+                WasmPowerShellEvent::Progress { progress, .. } => {
+                    lines.push(format_progress(&progress));
+                }
                 WasmPowerShellEvent::PipelineFinished { .. } => {
                     break;
                 }
+                WasmPowerShellEvent::PipelineTimedOut { timeout_ms, .. } => {
+                    return Err(WasmError::Generic(format!(
+                        "command timed out after {timeout_ms} ms"
+                    )));
+                }
                 WasmPowerShellEvent::PipelineCreated { .. } => {}
             }
         }
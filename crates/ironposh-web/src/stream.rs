@@ -4,8 +4,9 @@ use futures::{
     channel::{mpsc::Receiver, oneshot},
     StreamExt,
 };
+use ironposh_async::RemoteAsyncPowershellClient;
 use ironposh_client_core::{connector::active_session::UserEvent, powershell::PipelineHandle};
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 use wasm_bindgen::prelude::*;
 
 use crate::{error::WasmError, WasmPowerShellEvent};
@@ -14,6 +15,8 @@ use crate::{error::WasmError, WasmPowerShellEvent};
 #[wasm_bindgen]
 pub struct WasmPowerShellStream {
     inner: Receiver<UserEvent>,
+    /// Acknowledges output as JavaScript takes it.
+    client: RemoteAsyncPowershellClient,
     pipeline_handle: Option<PipelineHandle>,
     kill_sender: Option<oneshot::Sender<PipelineHandle>>,
 }
@@ -21,11 +24,13 @@ pub struct WasmPowerShellStream {
 impl WasmPowerShellStream {
     pub(crate) fn new(
         receiver: Receiver<UserEvent>,
+        client: RemoteAsyncPowershellClient,
         kill_sender: oneshot::Sender<PipelineHandle>,
     ) -> Self {
         debug!("creating new PowerShell stream");
         Self {
             inner: receiver,
+            client,
             pipeline_handle: None,
             kill_sender: Some(kill_sender),
         }
@@ -58,8 +63,16 @@ impl WasmPowerShellStream {
             Ok(None)
         };
 
-        if let Some(UserEvent::PipelineCreated { pipeline }) = event {
-            self.pipeline_handle = Some(pipeline);
+        match event {
+            Some(UserEvent::PipelineCreated { pipeline }) => self.pipeline_handle = Some(pipeline),
+            Some(UserEvent::PipelineOutput {
+                pipeline, data_len, ..
+            }) => {
+                if let Err(e) = self.client.acknowledge_output(pipeline, data_len).await {
+                    warn!(error = %e, "failed to acknowledge pipeline output");
+                }
+            }
+            _ => {}
         }
 
         result
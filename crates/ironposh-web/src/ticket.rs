@@ -0,0 +1,85 @@
+//! Session tickets: what a page needs to attach to the shell that
+//! `WasmPowerShellClient.suspend()` left on the server.
+//!
+//! A ticket is a JSON string, also kept in `sessionStorage` so the page that
+//! reloads in the same tab finds it with `WasmPowerShellClient.storedTicket()`.
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::WasmError;
+use crate::WasmWinRmConfig;
+
+const STORAGE_KEY: &str = "ironposh.sessionTicket";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTicket {
+    pub shell_id: String,
+    pub host: String,
+    pub port: u16,
+    pub configuration_name: Option<String>,
+}
+
+impl SessionTicket {
+    pub fn new(config: &WasmWinRmConfig, shell_id: String) -> Self {
+        Self {
+            shell_id,
+            host: config.destination.host.clone(),
+            port: config.destination.port,
+            configuration_name: config.configuration_name.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<String, WasmError> {
+        let value = serde_wasm_bindgen::to_value(self)?;
+        js_sys::JSON::stringify(&value)
+            .map(String::from)
+            .map_err(|e| WasmError::Generic(format!("failed to encode session ticket: {e:?}")))
+    }
+
+    pub fn decode(ticket: &str) -> Result<Self, WasmError> {
+        let value = js_sys::JSON::parse(ticket)
+            .map_err(|e| WasmError::InvalidArgument(format!("malformed session ticket: {e:?}")))?;
+        Ok(serde_wasm_bindgen::from_value(value)?)
+    }
+
+    /// The shell to attach to, provided `config` points at the endpoint the
+    /// ticket was issued for.
+    pub fn shell_id_for(&self, config: &WasmWinRmConfig) -> Result<uuid::Uuid, WasmError> {
+        if self.host != config.destination.host
+            || self.port != config.destination.port
+            || self.configuration_name != config.configuration_name
+        {
+            return Err(WasmError::InvalidArgument(format!(
+                "session ticket is for {}:{}, not {}:{}",
+                self.host, self.port, config.destination.host, config.destination.port
+            )));
+        }
+        uuid::Uuid::parse_str(&self.shell_id).map_err(|e| {
+            WasmError::InvalidArgument(format!("invalid shell id {}: {e}", self.shell_id))
+        })
+    }
+}
+
+fn session_storage() -> Option<web_sys::Storage> {
+    // Workers have no `window`, and with it no sessionStorage.
+    web_sys::window()?.session_storage().ok().flatten()
+}
+
+pub fn store(ticket: &str) {
+    let stored = session_storage().map(|storage| storage.set_item(STORAGE_KEY, ticket));
+    if !matches!(stored, Some(Ok(()))) {
+        warn!("session ticket not kept in sessionStorage");
+    }
+}
+
+pub fn load() -> Option<String> {
+    session_storage()?.get_item(STORAGE_KEY).ok().flatten()
+}
+
+pub fn forget() {
+    if let Some(storage) = session_storage() {
+        let _ = storage.remove_item(STORAGE_KEY);
+    }
+}
@@ -3,8 +3,10 @@ mod hostcall_objects;
 pub use hostcall::*;
 pub use hostcall_objects::*;
 use ironposh_async::SessionEvent;
+use ironposh_client_core::connector::{config::IdleAction, http::Interruption};
 use ironposh_psrp::{ErrorRecord, PipelineOutput};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tsify::Tsify;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
@@ -306,6 +308,9 @@ pub enum SecurityWarning {
     DestinationChannelInsecure,
     /// Both channels are insecure - extremely dangerous!
     BothChannelsInsecure,
+    /// No WinRM authentication: the Gateway (or a proxy behind it) must
+    /// authenticate the user, and nothing is SSPI-sealed
+    AuthenticationDisabled,
 }
 
 // =============================================================================
@@ -403,6 +408,70 @@ pub struct WasmWinRmConfig {
     /// Defaults to `Microsoft.PowerShell` when omitted.
     #[serde(default)]
     pub configuration_name: Option<String>,
+
+    /// Service principal to authenticate against, when it differs from
+    /// `HTTP/<destination host>` (CNAMEs, load balancers). A `{host}`
+    /// placeholder is replaced by the destination host.
+    #[serde(default)]
+    pub service_principal: Option<String>,
+
+    /// Application arguments for the runspace pool, readable on the server as
+    /// `$PSSenderInfo.ApplicationArguments`.
+    #[serde(default)]
+    pub application_arguments: Option<BTreeMap<String, String>>,
+
+    /// Disconnect or close the session after a period without user
+    /// operations. Omit to keep the session open while the tab lives.
+    #[serde(default)]
+    pub idle_policy: Option<WasmIdlePolicy>,
+
+    /// Milliseconds between `heartbeat` session events. Omit to send none.
+    #[serde(default)]
+    pub heartbeat_interval_ms: Option<u32>,
+
+    /// Filter applied to host calls before they reach the host call handler.
+    #[serde(default)]
+    pub host_call_policy: Option<WasmHostCallPolicy>,
+
+    /// Most Receive requests in flight at once. Omit for no limit.
+    #[serde(default)]
+    pub max_concurrent_receives: Option<u32>,
+
+    /// Per-pipeline budget of output bytes received but not yet handed to
+    /// JavaScript; a pipeline over it is not polled until its events are
+    /// read. Omit for no limit.
+    #[serde(default)]
+    pub max_pending_output_bytes: Option<u32>,
+}
+
+/// Host call filter settings.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WasmHostCallPolicy {
+    /// Host methods to deny (e.g. `"PromptForCredential1"`); the script sees
+    /// a host exception and the handler is never called.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Answer choice prompts with their default choice instead of asking.
+    #[serde(default)]
+    pub default_choices: bool,
+}
+
+/// Idle auto-disconnect settings.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct WasmIdlePolicy {
+    /// Seconds without user operations before acting.
+    pub after_secs: u32,
+    /// What to do with the idle session.
+    pub action: WasmIdleAction,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum WasmIdleAction {
+    /// Keep the shell on the server so it can be reconnected.
+    Disconnect,
+    /// Delete the shell on the server.
+    Close,
 }
 
 fn default_cols() -> u16 {
@@ -419,8 +488,15 @@ pub enum WasmAuthMethod {
     #[default]
     Basic,
     Ntlm,
+    /// Only in builds with the `kerberos` feature.
+    #[cfg(feature = "kerberos")]
     Kerberos,
+    /// Without the `kerberos` feature, SPNEGO offers NTLM only.
     Negotiate,
+    /// No credentials; only for destinations behind an authenticating
+    /// gateway or reverse proxy. Always raises
+    /// `SecurityWarning::AuthenticationDisabled`.
+    None,
 }
 
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
@@ -441,10 +517,71 @@ pub enum WasmPowerShellEvent {
         pipeline_id: String,
         error: WasmErrorRecord,
     },
+    /// A stream record without a typed variant below; in practice only
+    /// `WasmPsrpRecord::Unsupported`.
     PipelineRecord {
         pipeline_id: String,
         record: WasmPsrpRecord,
     },
+    PipelineTimedOut {
+        pipeline_id: String,
+        timeout_ms: u32,
+    },
+    /// `Write-Verbose`.
+    Verbose {
+        pipeline_id: String,
+        message: String,
+    },
+    /// `Write-Warning`.
+    Warning {
+        pipeline_id: String,
+        message: String,
+    },
+    /// `Write-Debug`.
+    Debug {
+        pipeline_id: String,
+        message: String,
+    },
+    /// `Write-Information` and `Write-Host`.
+    Information {
+        pipeline_id: String,
+        information: WasmInformationRecord,
+    },
+    /// `Write-Progress`.
+    Progress {
+        pipeline_id: String,
+        progress: WasmProgressRecord,
+    },
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmInformationRecord {
+    pub message_data: WasmInformationMessageData,
+    pub source: Option<String>,
+    pub time_generated: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub user: Option<String>,
+    pub computer: Option<String>,
+    pub process_id: Option<i32>,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmProgressRecord {
+    pub activity: String,
+    pub activity_id: i32,
+    pub status_description: Option<String>,
+    pub current_operation: Option<String>,
+    /// Set for nested progress bars.
+    pub parent_activity_id: Option<i32>,
+    /// -1 when the activity reports no percentage.
+    pub percent_complete: i32,
+    pub seconds_remaining: Option<i32>,
+    /// The activity has ended; its bar should be removed.
+    pub completed: bool,
 }
 
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
@@ -474,6 +611,13 @@ pub enum JsRunCommandEvent {
         pipeline_id: String,
         record: Box<WasmPsrpRecord>,
     },
+    /// The command exceeded its timeout and was stopped; no further events follow.
+    PipelineTimedOut {
+        #[serde(rename = "pipelineId")]
+        pipeline_id: String,
+        #[serde(rename = "timeoutMs")]
+        timeout_ms: u32,
+    },
 }
 
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
@@ -605,9 +749,74 @@ pub enum JsSessionEvent {
     ActiveSessionEnded,
     #[serde(rename = "error")]
     Error(String),
+    #[serde(rename = "broken")]
+    Broken {
+        reason: String,
+    },
+    #[serde(rename = "idle")]
+    Idle {
+        action: WasmIdleAction,
+    },
+    /// A request was answered with an HTTP status instead of a reply; with
+    /// `retrying` the session resends it, otherwise it was dropped.
+    #[serde(rename = "interrupted", rename_all = "camelCase")]
+    Interrupted {
+        reason: WasmInterruption,
+        status: u16,
+        retry_after_ms: Option<u32>,
+        retrying: bool,
+    },
+    /// `suspend()` disconnected the shell; it stays on the server for
+    /// `resume()`.
+    #[serde(rename = "suspended", rename_all = "camelCase")]
+    Suspended {
+        shell_id: String,
+    },
+    /// Connection health, every `heartbeatIntervalMs`. `latencyMs` is the
+    /// fastest round trip since the previous heartbeat (none if nothing
+    /// completed); `lastServerActivityMs` is how long ago the server last
+    /// answered.
+    #[serde(rename = "heartbeat", rename_all = "camelCase")]
+    Heartbeat {
+        latency_ms: Option<u32>,
+        last_server_activity_ms: u32,
+    },
+    /// The rate limit held a request back for `waitMs`; the totals count
+    /// every wait so far.
+    #[serde(rename = "throttled", rename_all = "camelCase")]
+    Throttled {
+        wait_ms: u32,
+        throttled_requests: u32,
+        total_wait_ms: u32,
+    },
+    /// The server had dropped the shell; a new one was opened and the
+    /// commands it never answered were sent again.
+    #[serde(rename = "sessionRecreated")]
+    SessionRecreated,
+    /// An output or record message on `stream` could not be decoded and was
+    /// skipped under lenient parsing; the session goes on.
+    #[serde(rename = "protocolWarning", rename_all = "camelCase")]
+    ProtocolWarning {
+        stream: String,
+        pipeline_id: Option<String>,
+        message: String,
+    },
     Closed,
 }
 
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum WasmInterruption {
+    /// 401/419: the credentials need renewing.
+    AuthExpired,
+    /// 503 from the server.
+    ServiceUnavailable,
+    /// 502/504 from a gateway in front of the server.
+    GatewayTimeout,
+    /// A WinRM per-user quota is exhausted; the request is resent later.
+    QuotaExceeded,
+}
+
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmErrorRecord {
@@ -619,6 +828,7 @@ pub struct WasmErrorRecord {
     pub error_category: Option<i32>,
     pub serialize_extended_info: bool,
     pub normal_formated_message: String,
+    pub script_stack_trace: Option<String>,
 }
 
 impl From<&ErrorRecord> for WasmErrorRecord {
@@ -630,8 +840,12 @@ impl From<&ErrorRecord> for WasmErrorRecord {
             was_thrown_from_throw_statement: value.was_thrown_from_throw_statement,
             fully_qualified_error_id: value.fully_qualified_error_id.clone(),
             target_object: value.target_object.clone(),
-            error_category: value.error_category.as_ref().map(|ec| ec.category),
+            error_category: value
+                .error_category
+                .as_ref()
+                .map(|ec| i32::from(ec.category)),
             serialize_extended_info: value.serialize_extended_info,
+            script_stack_trace: value.script_stack_trace.clone(),
         }
     }
 }
@@ -644,6 +858,64 @@ impl From<SessionEvent> for JsSessionEvent {
             SessionEvent::ActiveSessionStarted => Self::ActiveSessionStarted,
             SessionEvent::ActiveSessionEnded => Self::ActiveSessionEnded,
             SessionEvent::Error(e) => Self::Error(e),
+            SessionEvent::Broken { reason } => Self::Broken { reason },
+            SessionEvent::Idle { action } => Self::Idle {
+                action: match action {
+                    IdleAction::Disconnect => WasmIdleAction::Disconnect,
+                    IdleAction::Close => WasmIdleAction::Close,
+                },
+            },
+            SessionEvent::Interrupted {
+                interruption,
+                retrying,
+            } => Self::Interrupted {
+                reason: match interruption {
+                    Interruption::AuthExpired { .. } => WasmInterruption::AuthExpired,
+                    Interruption::ServiceUnavailable { .. } => WasmInterruption::ServiceUnavailable,
+                    Interruption::GatewayTimeout { .. } => WasmInterruption::GatewayTimeout,
+                    Interruption::QuotaExceeded { .. } => WasmInterruption::QuotaExceeded,
+                },
+                status: interruption.status(),
+                retry_after_ms: match interruption {
+                    Interruption::ServiceUnavailable {
+                        retry_after: Some(after),
+                    } => Some(u32::try_from(after.as_millis()).unwrap_or(u32::MAX)),
+                    _ => None,
+                },
+                retrying,
+            },
+            SessionEvent::Suspended { shell_id } => Self::Suspended { shell_id },
+            SessionEvent::Heartbeat {
+                latency,
+                last_server_activity,
+            } => {
+                let millis =
+                    |d: std::time::Duration| u32::try_from(d.as_millis()).unwrap_or(u32::MAX);
+                Self::Heartbeat {
+                    latency_ms: latency.map(millis),
+                    last_server_activity_ms: millis(last_server_activity),
+                }
+            }
+            SessionEvent::Throttled { wait, stats } => {
+                let millis =
+                    |d: std::time::Duration| u32::try_from(d.as_millis()).unwrap_or(u32::MAX);
+                Self::Throttled {
+                    wait_ms: millis(wait),
+                    throttled_requests: u32::try_from(stats.throttled).unwrap_or(u32::MAX),
+                    total_wait_ms: millis(stats.total_wait),
+                }
+            }
+            SessionEvent::SessionRecreated => Self::SessionRecreated,
+            SessionEvent::ProtocolWarning {
+                stream,
+                pipeline,
+                message,
+                diagnostics: _,
+            } => Self::ProtocolWarning {
+                stream,
+                pipeline_id: pipeline.map(|id| id.to_string()),
+                message,
+            },
             SessionEvent::Closed => Self::Closed,
         }
     }
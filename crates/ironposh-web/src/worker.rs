@@ -0,0 +1,291 @@
+//! Worker mode: the client runs inside a dedicated Web Worker, so CLIXML
+//! parsing and the NTLM/SPNEGO sealing of large payloads stay off the main
+//! thread.
+//!
+//! The worker script calls [`run_worker`] once the module is initialized;
+//! the page then talks to it with `WorkerRequest` messages and receives
+//! `WorkerMessage`s (see the TypeScript section below).
+//! `worker/worker-client.ts` wraps the protocol in a promise-based client.
+//!
+//! Host calls are answered on the page, where the terminal lives: each one is
+//! posted as `hostCall` and resolved by the matching `hostCallReply`.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use futures::future::OptionFuture;
+use ironposh_async::RemoteAsyncPowershellClient;
+use ironposh_client_core::powershell::PipelineHandle;
+use js_sys::{Function, Object, Promise, Reflect};
+use serde::Deserialize;
+use tracing::{debug, error, info, warn};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+
+use crate::client::{close_client, run_command_events};
+use crate::error::WasmError;
+use crate::{WasmPowerShellClient, WasmWinRmConfig};
+
+#[wasm_bindgen(typescript_custom_section)]
+const WORKER_TS_TYPES: &str = r#"
+// Page -> worker. `id` correlates a request with its replies.
+export type WorkerRequest =
+  | { type: "connect"; id: number; config: WasmWinRmConfig; acceptWarnings: boolean }
+  | { type: "runCommand"; id: number; script: string; timeoutMs?: number }
+  | { type: "stopPipeline"; id: number; pipelineId: string }
+  | { type: "close"; id: number }
+  | { type: "hostCallReply"; callId: number; value?: unknown; error?: string };
+
+// Worker -> page.
+export type WorkerMessage =
+  | { type: "ready" }
+  | { type: "done"; id: number }
+  | { type: "failed"; id: number; error: IronPoshError }
+  | { type: "securityWarnings"; id: number; warnings: SecurityWarning[] }
+  | { type: "sessionEvent"; event: JsSessionEvent }
+  | { type: "runEvent"; id: number; event: JsRunCommandEvent }
+  | { type: "hostCall"; callId: number; call: JsHostCall };
+"#;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum WorkerRequest {
+    #[serde(rename_all = "camelCase")]
+    Connect {
+        id: u32,
+        config: WasmWinRmConfig,
+        accept_warnings: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    RunCommand {
+        id: u32,
+        script: String,
+        timeout_ms: Option<u32>,
+    },
+    #[serde(rename_all = "camelCase")]
+    StopPipeline {
+        id: u32,
+        pipeline_id: String,
+    },
+    Close {
+        id: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    HostCallReply {
+        call_id: u32,
+        #[serde(default, with = "serde_wasm_bindgen::preserve")]
+        value: JsValue,
+        error: Option<String>,
+    },
+}
+
+/// Host calls posted to the page and not answered yet.
+#[derive(Default)]
+struct PendingHostCalls {
+    next_id: Cell<u32>,
+    replies: RefCell<HashMap<u32, (Function, Function)>>,
+}
+
+#[derive(Default)]
+struct WorkerState {
+    client: RefCell<Option<WasmPowerShellClient>>,
+    host_calls: Rc<PendingHostCalls>,
+}
+
+/// Serve the page from inside a dedicated worker. Call once, after the
+/// module is initialized; posts `ready` when listening.
+#[wasm_bindgen(js_name = "runWorker")]
+pub fn run_worker() -> Result<(), WasmError> {
+    let scope = worker_scope()?;
+    let state = Rc::new(WorkerState::default());
+
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        match serde_wasm_bindgen::from_value::<WorkerRequest>(event.data()) {
+            Ok(request) => handle_request(&state, request),
+            Err(e) => error!(%e, "ignoring malformed worker request"),
+        }
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The handler lives as long as the worker.
+    on_message.forget();
+
+    info!("worker listening");
+    post("ready", &[]);
+    Ok(())
+}
+
+fn handle_request(state: &WorkerState, request: WorkerRequest) {
+    match request {
+        WorkerRequest::Connect {
+            id,
+            config,
+            accept_warnings,
+        } => {
+            let warnings = config.check_security();
+            if !warnings.is_empty() && !accept_warnings {
+                // The page asks its user, then sends `connect` again.
+                let warnings = serde_wasm_bindgen::to_value(&warnings).unwrap_or(JsValue::NULL);
+                post(
+                    "securityWarnings",
+                    &[("id", id.into()), ("warnings", warnings)],
+                );
+                return;
+            }
+            for warning in &warnings {
+                warn!(?warning, "security warning accepted by the page");
+            }
+
+            let host_call_handler = host_call_forwarder(Rc::clone(&state.host_calls));
+            let session_event_handler = Closure::<dyn FnMut(JsValue)>::new(|event: JsValue| {
+                post("sessionEvent", &[("event", event)]);
+            })
+            .into_js_value();
+
+            match WasmPowerShellClient::connect_internal(
+                config,
+                None,
+                host_call_handler.unchecked_into(),
+                session_event_handler.unchecked_into(),
+            ) {
+                Ok(client) => {
+                    // Replacing a previous client frees it, which closes its session.
+                    *state.client.borrow_mut() = Some(client);
+                    post_result(id, Ok(()));
+                }
+                Err(e) => post_result(id, Err(e)),
+            }
+        }
+        WorkerRequest::RunCommand {
+            id,
+            script,
+            timeout_ms,
+        } => {
+            let Some(client) = session(state, id) else {
+                return;
+            };
+            spawn_local(async move {
+                let result = run_command_events(client, script, timeout_ms, |event| {
+                    post("runEvent", &[("id", id.into()), ("event", event.into())]);
+                })
+                .await;
+                post_result(id, result);
+            });
+        }
+        WorkerRequest::StopPipeline { id, pipeline_id } => {
+            let Some(mut client) = session(state, id) else {
+                return;
+            };
+            spawn_local(async move {
+                let result = match uuid::Uuid::parse_str(&pipeline_id) {
+                    Ok(uuid) => client
+                        .kill_pipeline(PipelineHandle::new(uuid))
+                        .await
+                        .map_err(WasmError::from),
+                    Err(e) => Err(WasmError::InvalidArgument(format!(
+                        "invalid pipeline id {pipeline_id}: {e}"
+                    ))),
+                };
+                post_result(id, result);
+            });
+        }
+        WorkerRequest::Close { id } => {
+            let client = state
+                .client
+                .borrow_mut()
+                .take()
+                .and_then(|client| client.take_for_close());
+            spawn_local(async move {
+                let result = OptionFuture::from(client.map(close_client)).await;
+                post_result(id, result.unwrap_or(Ok(())));
+            });
+        }
+        WorkerRequest::HostCallReply {
+            call_id,
+            value,
+            error,
+        } => {
+            let Some((resolve, reject)) = state.host_calls.replies.borrow_mut().remove(&call_id)
+            else {
+                warn!(call_id, "reply for an unknown host call");
+                return;
+            };
+            let outcome = match error {
+                Some(message) => reject.call1(&JsValue::NULL, &JsValue::from_str(&message)),
+                None => resolve.call1(&JsValue::NULL, &value),
+            };
+            if let Err(e) = outcome {
+                error!(?e, call_id, "failed to settle host call");
+            }
+        }
+    }
+}
+
+/// The connected session, or a `failed` reply for request `id`.
+fn session(state: &WorkerState, id: u32) -> Option<RemoteAsyncPowershellClient> {
+    let client = state
+        .client
+        .borrow()
+        .as_ref()
+        .map(WasmPowerShellClient::session);
+    if client.is_none() {
+        post_result(
+            id,
+            Err(WasmError::InvalidArgument(
+                "not connected; send `connect` first".into(),
+            )),
+        );
+    }
+    client
+}
+
+/// A host call handler that posts each call to the page and returns a
+/// promise for the page's reply.
+fn host_call_forwarder(pending: Rc<PendingHostCalls>) -> JsValue {
+    Closure::<dyn FnMut(JsValue) -> Promise>::new(move |call: JsValue| {
+        let call_id = pending.next_id.get();
+        pending.next_id.set(call_id.wrapping_add(1));
+        let promise = Promise::new(&mut |resolve, reject| {
+            pending
+                .replies
+                .borrow_mut()
+                .insert(call_id, (resolve, reject));
+        });
+        debug!(call_id, "forwarding host call to the page");
+        post("hostCall", &[("callId", call_id.into()), ("call", call)]);
+        promise
+    })
+    .into_js_value()
+}
+
+fn worker_scope() -> Result<DedicatedWorkerGlobalScope, WasmError> {
+    js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .map_err(|_| {
+            WasmError::Generic("runWorker must be called inside a dedicated worker".into())
+        })
+}
+
+fn post_result(id: u32, result: Result<(), WasmError>) {
+    match result {
+        Ok(()) => post("done", &[("id", id.into())]),
+        Err(e) => post("failed", &[("id", id.into()), ("error", e.into())]),
+    }
+}
+
+fn post(kind: &str, fields: &[(&str, JsValue)]) {
+    let message = Object::new();
+    let mut result = Reflect::set(&message, &"type".into(), &kind.into());
+    for (name, value) in fields {
+        result = result.and_then(|_| Reflect::set(&message, &(*name).into(), value));
+    }
+    let result = result.and_then(|_| {
+        worker_scope()
+            .map_err(JsValue::from)?
+            .post_message(&message)
+    });
+    if let Err(e) = result {
+        error!(?e, kind, "failed to post worker message");
+    }
+}
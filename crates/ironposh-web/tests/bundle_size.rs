@@ -0,0 +1,60 @@
+//! Size budget for the `wasm-pack` output.
+//!
+//! Reads `pkg/` (default features) and `pkg-minimal/` (`--no-default-features`)
+//! next to this crate's manifest. A bundle that was not built is skipped unless
+//! `IRONPOSH_WASM_SIZE_REQUIRED` is set, as it is in CI after both builds.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::PathBuf;
+
+const WASM_FILE: &str = "ironposh_web_bg.wasm";
+
+/// Both builds share one budget: sspi carries its Kerberos stack either way,
+/// so `--no-default-features` is not expected to be smaller.
+const BUDGET: u64 = 4 * 1024 * 1024;
+
+fn bundle_size(out_dir: &str) -> Option<u64> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join(out_dir)
+        .join(WASM_FILE);
+    match std::fs::metadata(&path) {
+        Ok(metadata) => Some(metadata.len()),
+        Err(e) => {
+            assert!(
+                std::env::var_os("IRONPOSH_WASM_SIZE_REQUIRED").is_none(),
+                "{} is required: {}",
+                path.display(),
+                e
+            );
+            eprintln!("skipping: {} not built", path.display());
+            None
+        }
+    }
+}
+
+#[test]
+fn default_bundle_fits_budget() {
+    if let Some(size) = bundle_size("pkg") {
+        assert!(
+            size <= BUDGET,
+            "{} is {} bytes, over the {} byte budget",
+            WASM_FILE,
+            size,
+            BUDGET
+        );
+    }
+}
+
+#[test]
+fn minimal_bundle_fits_budget() {
+    if let Some(size) = bundle_size("pkg-minimal") {
+        assert!(
+            size <= BUDGET,
+            "minimal {} is {} bytes, over the {} byte budget",
+            WASM_FILE,
+            size,
+            BUDGET
+        );
+    }
+}
@@ -3,9 +3,15 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use ironposh_client_core::{
+    connector::active_session::UserEvent,
+    powershell::PipelineHandle,
+    psrp_record::{PsrpRecord, PsrpRecordMeta},
+};
+use ironposh_psrp::{MessageType, ProgressRecord, ProgressRecordType};
 use ironposh_web::{
-    GatewayTransport, SecurityWarning, WasmAuthMethod, WasmPowerShellClient, WasmWinRmConfig,
-    WinRmDestination,
+    GatewayTransport, SecurityWarning, WasmAuthMethod, WasmPowerShellClient, WasmPowerShellEvent,
+    WasmWinRmConfig, WinRmDestination,
 };
 use wasm_bindgen_test::*;
 
@@ -30,6 +36,55 @@ fn security_check_reports_plain_gateway_to_tls_destination() {
     assert_eq!(WasmPowerShellClient::check_security(&cfg).length(), 1);
 }
 
+#[wasm_bindgen_test]
+fn security_check_always_reports_disabled_authentication() {
+    let mut cfg = test_config("wss://localhost:7171", GatewayTransport::Tcp, None);
+    cfg.auth = WasmAuthMethod::None;
+
+    assert_eq!(
+        cfg.check_security(),
+        vec![
+            SecurityWarning::AuthenticationDisabled,
+            SecurityWarning::DestinationChannelInsecure
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn progress_records_become_progress_events() {
+    let id = uuid::Uuid::new_v4();
+    let event = UserEvent::PipelineRecord {
+        pipeline: PipelineHandle::new(id),
+        record: PsrpRecord::Progress {
+            meta: PsrpRecordMeta {
+                message_type: MessageType::ProgressRecord,
+                message_type_value: 0x00041010,
+                stream: String::new(),
+                command_id: None,
+                data_len: 0,
+            },
+            record: ProgressRecord::builder()
+                .activity("Copying".to_string())
+                .activity_id(1)
+                .percent_complete(40)
+                .progress_type(ProgressRecordType::Completed)
+                .build(),
+        },
+    };
+
+    let WasmPowerShellEvent::Progress {
+        pipeline_id,
+        progress,
+    } = WasmPowerShellEvent::try_from(&event).unwrap()
+    else {
+        panic!("expected a Progress event");
+    };
+    assert_eq!(pipeline_id, id.to_string());
+    assert_eq!(progress.activity, "Copying");
+    assert_eq!(progress.percent_complete, 40);
+    assert!(progress.completed);
+}
+
 fn test_config(
     gateway_url: impl Into<String>,
     transport: GatewayTransport,
@@ -55,5 +110,12 @@ fn test_config(
         raw_ui_enabled: Some(true),
         force_insecure,
         configuration_name: None,
+        service_principal: None,
+        application_arguments: None,
+        idle_policy: None,
+        heartbeat_interval_ms: None,
+        host_call_policy: None,
+        max_concurrent_receives: None,
+        max_pending_output_bytes: None,
     }
 }
@@ -0,0 +1,21 @@
+// Worker entry for worker mode: load the module and serve the page.
+//
+//   new Worker(new URL("ironposh-worker.ts", import.meta.url), { type: "module" })
+//
+// The page posts `init` with the wasm URL first (bundlers resolve it on the
+// page side), then talks to `WorkerPowerShellClient` (worker-client.ts).
+import init, { runWorker, set_panic_hook } from "../pkg/ironposh_web";
+
+self.addEventListener(
+  "message",
+  async (event: MessageEvent<{ type: "init"; wasmUrl?: string }>) => {
+    if (event.data?.type !== "init") {
+      return;
+    }
+    await init(event.data.wasmUrl);
+    set_panic_hook();
+    // Installs the request handler and posts `ready`.
+    runWorker();
+  },
+  { once: true },
+);
@@ -0,0 +1,154 @@
+// Page side of worker mode: a promise-based client over the postMessage
+// protocol of `runWorker()`. Only types are imported from the package, so
+// the page never instantiates the wasm module itself.
+import type {
+  IronPoshError,
+  JsHostCall,
+  JsRunCommandEvent,
+  JsSessionEvent,
+  SecurityWarning,
+  WasmWinRmConfig,
+  WorkerMessage,
+  WorkerRequest,
+} from "../pkg/ironposh_web";
+
+export interface WorkerClientHandlers {
+  onHostCall: (call: JsHostCall) => unknown | Promise<unknown>;
+  onSessionEvent: (event: JsSessionEvent) => void;
+  // Asked when the configuration is insecure; resolve `true` to connect anyway.
+  onSecurityWarning?: (warnings: SecurityWarning[]) => Promise<boolean>;
+}
+
+type Pending = {
+  request: WorkerRequest;
+  resolve: () => void;
+  reject: (error: IronPoshError | Error) => void;
+  onEvent?: (event: JsRunCommandEvent) => void;
+};
+
+export class WorkerPowerShellClient {
+  private nextId = 1;
+  private readonly pending = new Map<number, Pending>();
+
+  private constructor(
+    private readonly worker: Worker,
+    private readonly handlers: WorkerClientHandlers,
+  ) {
+    worker.addEventListener("message", (event: MessageEvent<WorkerMessage>) =>
+      this.onMessage(event.data),
+    );
+  }
+
+  // Start `worker` (running ironposh-worker.ts), load `wasmUrl` in it and
+  // connect. Resolves once the session is being opened; session progress
+  // arrives through `onSessionEvent`.
+  static async connect(
+    worker: Worker,
+    wasmUrl: string | undefined,
+    config: WasmWinRmConfig,
+    handlers: WorkerClientHandlers,
+  ): Promise<WorkerPowerShellClient> {
+    const ready = new Promise<void>((resolve) => {
+      const onReady = (event: MessageEvent<WorkerMessage>) => {
+        if (event.data.type === "ready") {
+          worker.removeEventListener("message", onReady);
+          resolve();
+        }
+      };
+      worker.addEventListener("message", onReady);
+    });
+    worker.postMessage({ type: "init", wasmUrl });
+    await ready;
+
+    const client = new WorkerPowerShellClient(worker, handlers);
+    await client.request({ type: "connect", id: 0, config, acceptWarnings: false });
+    return client;
+  }
+
+  // Run `script` raw; `onEvent` receives its events, as with `runCommand`.
+  runCommand(
+    script: string,
+    onEvent: (event: JsRunCommandEvent) => void,
+    timeoutMs?: number,
+  ): Promise<void> {
+    return this.request({ type: "runCommand", id: 0, script, timeoutMs }, onEvent);
+  }
+
+  stopPipeline(pipelineId: string): Promise<void> {
+    return this.request({ type: "stopPipeline", id: 0, pipelineId });
+  }
+
+  // Close the session, then stop the worker.
+  async close(): Promise<void> {
+    try {
+      await this.request({ type: "close", id: 0 });
+    } finally {
+      this.worker.terminate();
+    }
+  }
+
+  private request(
+    request: Exclude<WorkerRequest, { type: "hostCallReply" }>,
+    onEvent?: (event: JsRunCommandEvent) => void,
+  ): Promise<void> {
+    const id = this.nextId++;
+    return new Promise((resolve, reject) => {
+      const message = { ...request, id };
+      this.pending.set(id, { request: message, resolve, reject, onEvent });
+      this.worker.postMessage(message);
+    });
+  }
+
+  private onMessage(message: WorkerMessage): void {
+    switch (message.type) {
+      case "done":
+        this.pending.get(message.id)?.resolve();
+        this.pending.delete(message.id);
+        break;
+      case "failed":
+        this.pending.get(message.id)?.reject(message.error);
+        this.pending.delete(message.id);
+        break;
+      case "runEvent":
+        this.pending.get(message.id)?.onEvent?.(message.event);
+        break;
+      case "sessionEvent":
+        this.handlers.onSessionEvent(message.event);
+        break;
+      case "securityWarnings":
+        void this.confirmInsecure(message.id, message.warnings);
+        break;
+      case "hostCall":
+        void this.answerHostCall(message.callId, message.call);
+        break;
+      case "ready":
+        break;
+    }
+  }
+
+  private async confirmInsecure(id: number, warnings: SecurityWarning[]): Promise<void> {
+    const pending = this.pending.get(id);
+    if (!pending) {
+      return;
+    }
+    const accepted = (await this.handlers.onSecurityWarning?.(warnings)) ?? false;
+    if (!accepted) {
+      this.pending.delete(id);
+      pending.reject(new Error("Connection rejected: user declined insecure connection"));
+      return;
+    }
+    // Same id, so the original `connect()` promise settles on the reply.
+    this.worker.postMessage({ ...pending.request, acceptWarnings: true });
+  }
+
+  private async answerHostCall(callId: number, call: JsHostCall): Promise<void> {
+    let reply: WorkerRequest;
+    try {
+      const value = await this.handlers.onHostCall(call);
+      reply = { type: "hostCallReply", callId, value };
+    } catch (e) {
+      reply = { type: "hostCallReply", callId, error: String(e) };
+    }
+    this.worker.postMessage(reply);
+  }
+}
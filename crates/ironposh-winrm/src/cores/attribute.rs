@@ -19,7 +19,7 @@ macro_rules! define_attributes {
         impl<'a> Attribute<'a> {
             /// Convert an attribute name to the corresponding enum variant type
             /// This is automatically generated to match all enum variants
-            pub fn from_name_and_value(namespace: Option<&str>, name: &str, value: &'a str) -> Result<Option<Self>, ironposh_xml::XmlError> {
+            pub fn from_name_and_value(namespace: Option<&str>, name: &str, value: &str) -> Result<Option<Self>, ironposh_xml::XmlError> {
                 // The reserved `xml:` prefix is modeled literally (e.g. "xml:lang",
                 // no declared namespace); fold roxmltree's expanded
                 // (xml-namespace, local) form back to that spelling so it matches.
@@ -149,6 +149,9 @@ define_attributes!(
     SequenceID(u64) => (None, "SequenceID"),
         |v: &str| v.parse::<u64>().map_err(|e| e.to_string()),
         |v: u64| v.to_string(),
+    Nil(bool) => (Some(crate::cores::namespace::Namespace::XmlSchemaInstance), "nil"),
+        parse_xml_bool,
+        |v: bool| v.to_string(),
     // Add new attributes here and they automatically get handled everywhere!
 );
 
@@ -1,5 +1,7 @@
 use ironposh_xml::builder::Element;
 use ironposh_xml::mapping::{FromXml, NodeExt};
+#[cfg(feature = "streaming")]
+use ironposh_xml::stream::{FromXmlStream, PullParser, StartElement};
 
 use crate::cores::WsUuid;
 use crate::cores::namespace::{Namespace, NamespaceDeclaration};
@@ -187,29 +189,34 @@ where
                     N::TAG_NAME,
                     node.tag_name().name()
                 ))
+                .at(node)
             })?;
             if elements.next().is_some() {
                 return Err(ironposh_xml::XmlError::InvalidXml(format!(
                     "expected exactly one child element in <{}>",
                     node.tag_name().name()
-                )));
+                ))
+                .at(node));
             }
             if !only.is_element_named(N::NAMESPACE, N::TAG_NAME) {
                 return Err(ironposh_xml::XmlError::XmlInvalidTag {
                     expected: N::TAG_NAME.to_string(),
                     found: only.tag_name().name().to_string(),
-                });
+                }
+                .at(only));
             }
             only
         };
 
-        let value = V::from_xml(element)?;
+        // The innermost tag a failure passes through locates it.
+        let value = V::from_xml(element).map_err(|e| e.at(element))?;
         // Identity is (namespace-URI, local-name); a parse error on a *known*
         // attribute is propagated, while a truly unknown attribute is ignored.
         let mut attributes = Vec::new();
         for attr in element.attributes() {
             if let Some(parsed) =
-                Attribute::from_name_and_value(attr.namespace(), attr.name(), attr.value())?
+                Attribute::from_name_and_value(attr.namespace(), attr.name(), attr.value())
+                    .map_err(|e| e.at(element))?
             {
                 attributes.push(parsed);
             }
@@ -226,6 +233,90 @@ where
     }
 }
 
+#[cfg(feature = "streaming")]
+impl<'a, V, N> FromXmlStream<'a> for Tag<'a, V, N>
+where
+    V: TagValue<'a> + FromXmlStream<'a>,
+    N: TagName,
+{
+    fn from_xml_stream(
+        start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, ironposh_xml::XmlError> {
+        if !start.is_named(N::NAMESPACE, N::TAG_NAME) {
+            // Wrapper case, as in `from_xml`: exactly one child element, N.
+            let only = parser.next_child()?.ok_or_else(|| {
+                ironposh_xml::XmlError::InvalidXml(format!(
+                    "expected a <{}> child in <{}>, found none",
+                    N::TAG_NAME,
+                    start.local_name
+                ))
+            })?;
+            if !only.is_named(N::NAMESPACE, N::TAG_NAME) {
+                return Err(ironposh_xml::XmlError::XmlInvalidTag {
+                    expected: N::TAG_NAME.to_string(),
+                    found: only.local_name.to_string(),
+                });
+            }
+            let tag = Self::from_xml_stream(only, parser)?;
+            if parser.next_child()?.is_some() {
+                return Err(ironposh_xml::XmlError::InvalidXml(format!(
+                    "expected exactly one child element in <{}>",
+                    start.local_name
+                )));
+            }
+            return Ok(tag);
+        }
+
+        let mut attributes = Vec::new();
+        for attr in &start.attributes {
+            if let Some(parsed) = Attribute::from_name_and_value(
+                attr.namespace.as_deref(),
+                attr.local_name,
+                &attr.value,
+            )? {
+                attributes.push(parsed);
+            }
+        }
+        let value = V::from_xml_stream(start, parser)?;
+
+        // The pull parser resolves prefixes itself and does not report the
+        // declarations; a parsed response is never serialized again.
+        Ok(Tag {
+            value,
+            attributes,
+            namespaces_declaration: NamespaceDeclaration::new(),
+            __phantom: std::marker::PhantomData,
+            __phantom_name: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Streaming counterpart of one `#[derive(FromXml)]` field: if `child` is
+/// `T`'s element, take it and read it into `slot`.
+#[cfg(feature = "streaming")]
+pub(crate) fn stream_field<'a, T>(
+    slot: &mut Option<T>,
+    child: &mut Option<StartElement<'a>>,
+    parser: &mut PullParser<'a>,
+    owner: &str,
+) -> Result<(), ironposh_xml::XmlError>
+where
+    T: NamedTag + FromXmlStream<'a>,
+{
+    let Some(start) = child.take_if(|start| start.is_named(T::NAMESPACE, T::TAG_NAME)) else {
+        return Ok(());
+    };
+    if slot.is_some() {
+        return Err(ironposh_xml::XmlError::InvalidXml(format!(
+            "duplicate <{}> in {owner}",
+            T::TAG_NAME
+        )));
+    }
+    *slot = Some(T::from_xml_stream(start, parser)?);
+    Ok(())
+}
+
 /// A tag type's XML identity (name + namespace) exposed at the type level.
 ///
 /// `Tag<'a, V, N>` forwards to its `N: TagName`. Reading identity through this
@@ -16,7 +16,9 @@ pub trait TagName {
 
 // Leaf-valued tags live here (their value types are all in `cores`). Tags whose
 // value is a domain struct (Shell = ShellValue, Body = SoapBody, …) are defined
-// with `tag!` next to that struct instead — see the rsp/soap/ws_* modules.
+// with `tag!` next to that struct instead — see the rsp/soap/ws_* modules. The
+// enumeration and fragment-transfer headers are kept together in
+// `ws_management::header`.
 
 // ============================================================
 // PowerShell Remoting Shell (rsp namespace)
@@ -27,6 +29,9 @@ tag!(ResourceUri = Text<'a> => WsmanShell);
 tag!(Owner = Text<'a> => WsmanShell);
 tag!(ClientIP = Text<'a> => WsmanShell);
 tag!(ProcessId = Text<'a> => WsmanShell);
+tag!(Variable = Text<'a> => WsmanShell);
+tag!(WorkingDirectory = Text<'a> => WsmanShell);
+tag!(Lifetime = Time => WsmanShell);
 tag!(IdleTimeOut = Time => WsmanShell);
 tag!(InputStreams = Text<'a> => WsmanShell);
 tag!(OutputStreams = Text<'a> => WsmanShell);
@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 
+#[cfg(feature = "streaming")]
+use ironposh_xml::stream::{FromXmlStream, PullParser, StartElement};
 use ironposh_xml::{XmlError, builder::Element, mapping::FromXml, parser::Node};
 
 use crate::xml_num_value;
@@ -41,6 +43,16 @@ pub(crate) fn leaf_text<'a>(node: Node<'a, 'a>) -> Result<Cow<'a, str>, XmlError
     )
 }
 
+/// Streaming counterpart of [`leaf_text`]: the trimmed text of the element
+/// whose start tag was just read, up to and including its end tag.
+#[cfg(feature = "streaming")]
+pub(crate) fn stream_leaf_text<'a>(parser: &mut PullParser<'a>) -> Result<Cow<'a, str>, XmlError> {
+    Ok(match parser.read_text()? {
+        Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+        Cow::Owned(text) => Cow::Owned(text.trim().to_owned()),
+    })
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Text<'a>(Cow<'a, str>);
 
@@ -86,6 +98,16 @@ impl<'a> FromXml<'a> for Text<'a> {
     }
 }
 
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for Text<'a> {
+    fn from_xml_stream(
+        _start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        Ok(Self(stream_leaf_text(parser)?))
+    }
+}
+
 impl<'a> TagValue<'a> for () {
     fn append_to_element(self, element: Element<'a>) -> Element<'a> {
         element
@@ -115,6 +137,22 @@ impl<'a> FromXml<'a> for Empty {
     }
 }
 
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for Empty {
+    fn from_xml_stream(
+        start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        if !stream_leaf_text(parser)?.is_empty() {
+            return Err(XmlError::InvalidXml(format!(
+                "<{}> must be empty but has text content",
+                start.local_name
+            )));
+        }
+        Ok(Self)
+    }
+}
+
 impl From<()> for Empty {
     fn from(_value: ()) -> Self {
         Self
@@ -135,10 +173,8 @@ impl<'a> TagValue<'a> for WsUuid {
     }
 }
 
-impl<'a> FromXml<'a> for WsUuid {
-    fn from_xml(node: Node<'a, 'a>) -> Result<Self, XmlError> {
-        let text = leaf_text(node)?;
-        let text = text.as_ref();
+impl WsUuid {
+    fn parse(text: &str) -> Result<Self, XmlError> {
         // WS-Management prefixes UUIDs with "uuid:" — strip it if present.
         let raw = text.strip_prefix("uuid:").unwrap_or(text);
         uuid::Uuid::parse_str(raw)
@@ -147,6 +183,22 @@ impl<'a> FromXml<'a> for WsUuid {
     }
 }
 
+impl<'a> FromXml<'a> for WsUuid {
+    fn from_xml(node: Node<'a, 'a>) -> Result<Self, XmlError> {
+        Self::parse(&leaf_text(node)?)
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for WsUuid {
+    fn from_xml_stream(
+        _start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        Self::parse(&stream_leaf_text(parser)?)
+    }
+}
+
 impl From<uuid::Uuid> for WsUuid {
     fn from(value: uuid::Uuid) -> Self {
         Self(value)
@@ -174,10 +226,9 @@ impl<'a> TagValue<'a> for Time {
     }
 }
 
-impl<'a> FromXml<'a> for Time {
-    fn from_xml(node: Node<'a, 'a>) -> Result<Self, XmlError> {
+impl Time {
+    fn parse(text: &str) -> Result<Self, XmlError> {
         // WS-Management timeout format: "PT180.000S".
-        let text = leaf_text(node)?;
         let seconds = text
             .strip_prefix("PT")
             .and_then(|s| s.strip_suffix('S'))
@@ -188,6 +239,22 @@ impl<'a> FromXml<'a> for Time {
     }
 }
 
+impl<'a> FromXml<'a> for Time {
+    fn from_xml(node: Node<'a, 'a>) -> Result<Self, XmlError> {
+        Self::parse(&leaf_text(node)?)
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for Time {
+    fn from_xml_stream(
+        _start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        Self::parse(&stream_leaf_text(parser)?)
+    }
+}
+
 impl From<f64> for Time {
     fn from(value: f64) -> Self {
         Self(value)
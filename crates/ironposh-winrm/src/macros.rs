@@ -70,6 +70,19 @@ macro_rules! xml_num_value {
                 }
             }
 
+            #[cfg(feature = "streaming")]
+            impl<'a> ironposh_xml::stream::FromXmlStream<'a> for $name {
+                fn from_xml_stream(
+                    _start: ironposh_xml::stream::StartElement<'a>,
+                    parser: &mut ironposh_xml::stream::PullParser<'a>,
+                ) -> Result<Self, ironposh_xml::XmlError> {
+                    let text = $crate::cores::tag_value::stream_leaf_text(parser)?;
+                    Ok($name(text.parse::<$inner>().map_err(|_| {
+                        ironposh_xml::XmlError::InvalidXml(format!("invalid {} value: {}", stringify!($name), text))
+                    })?))
+                }
+            }
+
             // ------------ Conversions -------------
             impl From<$inner> for $name        { fn from(v: $inner) -> Self { Self(v) } }
             impl From<$name>  for $inner       { fn from(v: $name)  -> Self { v.0 } }
@@ -12,6 +12,86 @@ pub struct CommandLineValue {
     pub arguments: Vec<String>,
 }
 
+impl CommandLineValue {
+    /// A WinRS command line running `command` with `args`.
+    ///
+    /// The service joins `Command` and the `Arguments` with spaces into one
+    /// Windows command line, so each argument is quoted here (see
+    /// [`quote_argument`]) to reach the program as the same single argv entry.
+    pub fn new<I, S>(command: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            command: Some(command.into()),
+            arguments: args
+                .into_iter()
+                .map(|arg| quote_argument(arg.as_ref()).into_owned())
+                .collect(),
+        }
+    }
+
+    /// `powershell.exe` running `script` through `-EncodedCommand`, which
+    /// sidesteps both `cmd.exe` and PowerShell's own command-line parsing.
+    pub fn powershell(script: &str) -> Self {
+        Self::new(
+            "powershell.exe",
+            [
+                "-NoLogo",
+                "-NoProfile",
+                "-NonInteractive",
+                "-EncodedCommand",
+                &encode_command(script),
+            ],
+        )
+    }
+}
+
+/// Quote `arg` so `CommandLineToArgvW` (and the MSVC runtime) parse it back
+/// as exactly one argument.
+///
+/// Arguments without whitespace or quotes are left alone. Otherwise the
+/// argument is wrapped in double quotes, embedded quotes are escaped with a
+/// backslash, and backslashes are doubled where they precede a quote
+/// (including the closing one); other backslashes are literal.
+pub fn quote_argument(arg: &str) -> std::borrow::Cow<'_, str> {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\u{b}', '"']) {
+        return arg.into();
+    }
+
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted.into()
+}
+
+/// The `-EncodedCommand` form of a PowerShell script: base64 of its UTF-16LE
+/// encoding.
+pub fn encode_command(script: &str) -> String {
+    use base64::Engine;
+
+    let utf16: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    base64::engine::general_purpose::STANDARD.encode(utf16)
+}
+
 impl TagValue<'_> for CommandLineValue {
     fn append_to_element(
         self,
@@ -83,6 +163,31 @@ mod tests {
         assert!(CommandLineValue::from_xml(doc.root_element()).is_err());
     }
 
+    #[test]
+    fn arguments_are_quoted_for_command_line_to_argv() {
+        for (arg, quoted) in [
+            ("plain", "plain"),
+            ("C:\\dir\\", "C:\\dir\\"),
+            ("", r#""""#),
+            ("two words", r#""two words""#),
+            (r#"say "hi""#, r#""say \"hi\"""#),
+            (r"C:\Program Files\", r#""C:\Program Files\\""#),
+            (r#"a\"b"#, r#""a\\\"b""#),
+        ] {
+            assert_eq!(quote_argument(arg), quoted, "{arg}");
+        }
+    }
+
+    #[test]
+    fn powershell_command_line_carries_encoded_script() {
+        let value = CommandLineValue::powershell("Get-Date");
+        assert_eq!(value.command.as_deref(), Some("powershell.exe"));
+        assert_eq!(
+            value.arguments.last().map(String::as_str),
+            Some("RwBlAHQALQBEAGEAdABlAA==")
+        );
+    }
+
     #[test]
     fn empty_command_element_is_none() {
         let xml = format!(r#"<rsp:CommandLine xmlns:rsp="{RSP}"><rsp:Command/></rsp:CommandLine>"#);
@@ -4,3 +4,4 @@ pub mod disconnect;
 pub mod receive;
 pub mod send;
 pub mod shell_value;
+pub mod signal;
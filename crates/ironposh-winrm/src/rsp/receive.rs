@@ -4,6 +4,8 @@ use crate::cores::{
     DesiredStream, DesiredStreamTag, ExitCode, Stream, StreamTag, TagName, TagValue,
 };
 use crate::tag;
+#[cfg(feature = "streaming")]
+use ironposh_xml::stream::{FromXmlStream, PullParser, StartElement};
 use ironposh_xml::{
     XmlError,
     builder::Element,
@@ -90,6 +92,24 @@ pub struct CommandStateValue<'a> {
     pub exit_code: Option<ExitCode<'a>>,
 }
 
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for CommandStateValue<'a> {
+    fn from_xml_stream(
+        _start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        let mut exit_code = None;
+        while let Some(start) = parser.next_child()? {
+            let mut child = Some(start);
+            crate::cores::stream_field(&mut exit_code, &mut child, parser, "CommandStateValue")?;
+            if child.is_some() {
+                parser.skip_element()?;
+            }
+        }
+        Ok(CommandStateValue { exit_code })
+    }
+}
+
 // ReceiveResponse main structure
 #[derive(Debug, Clone, typed_builder::TypedBuilder)]
 pub struct ReceiveResponseValue<'a> {
@@ -132,6 +152,32 @@ impl<'a> FromXml<'a> for ReceiveResponseValue<'a> {
     }
 }
 
+#[cfg(feature = "streaming")]
+impl<'a> FromXmlStream<'a> for ReceiveResponseValue<'a> {
+    fn from_xml_stream(
+        _start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError> {
+        let mut streams = Vec::new();
+        let mut command_state = None;
+        while let Some(start) = parser.next_child()? {
+            if start.is_named(StreamTag::NAMESPACE, StreamTag::TAG_NAME) {
+                streams.push(Stream::from_xml_stream(start, parser)?);
+                continue;
+            }
+            let mut child = Some(start);
+            crate::cores::stream_field(&mut command_state, &mut child, parser, "ReceiveResponse")?;
+            if child.is_some() {
+                parser.skip_element()?;
+            }
+        }
+        Ok(ReceiveResponseValue {
+            streams,
+            command_state,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,12 +1,74 @@
 use crate::cores::{
-    BufferMode, ClientIP, CompressionMode, CreationXml, DataLocaleText, Encoding, IdleTimeOut,
-    InputStreams, LocaleText, MaxIdleTimeOut, Name, OutputStreams, Owner, ProcessId, ProfileLoaded,
-    ResourceUri, ShellId, ShellInactivity, ShellRunTime, State,
+    Attribute, BufferMode, ClientIP, CompressionMode, CreationXml, DataLocaleText, Encoding,
+    IdleTimeOut, InputStreams, Lifetime, LocaleText, MaxIdleTimeOut, Name, OutputStreams, Owner,
+    ProcessId, ProfileLoaded, ResourceUri, ShellId, ShellInactivity, ShellRunTime, State, TagName,
+    TagValue, Variable, VariableTag, WorkingDirectory,
 };
 use crate::tag;
 use ironposh_macros::{FromXml, SimpleTagValue};
+use ironposh_xml::{
+    XmlError,
+    builder::Element,
+    mapping::{FromXml, NodeExt},
+};
 
 tag!(Shell = ShellValue<'a> => WsmanShell);
+tag!(Environment = EnvironmentValue<'a> => WsmanShell);
+
+/// `rsp:Environment`: variables set in the shell's process environment, one
+/// `<rsp:Variable Name="..">value</rsp:Variable>` each.
+#[derive(Debug, Clone, Default)]
+pub struct EnvironmentValue<'a> {
+    pub variables: Vec<Variable<'a>>,
+}
+
+impl<'a> EnvironmentValue<'a> {
+    /// Build from name/value pairs, in the order given.
+    pub fn from_pairs<I, K, V>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<std::borrow::Cow<'a, str>>,
+        V: Into<Variable<'a>>,
+    {
+        let variables = pairs
+            .into_iter()
+            .map(|(name, value)| value.into().with_attribute(Attribute::Name(name.into())))
+            .collect();
+        Self { variables }
+    }
+
+    /// Name/value pairs, skipping any `Variable` without a `Name`.
+    pub fn pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.variables.iter().filter_map(|variable| {
+            variable.attributes.iter().find_map(|attr| match attr {
+                Attribute::Name(name) => Some((&**name, variable.value.as_ref())),
+                _ => None,
+            })
+        })
+    }
+}
+
+impl<'a> TagValue<'a> for EnvironmentValue<'a> {
+    fn append_to_element(self, mut element: Element<'a>) -> Element<'a> {
+        for variable in self.variables {
+            element = element.add_child(variable.into_element());
+        }
+        element
+    }
+}
+
+impl<'a> FromXml<'a> for EnvironmentValue<'a> {
+    fn from_xml(node: ironposh_xml::parser::Node<'a, 'a>) -> Result<Self, XmlError> {
+        ironposh_xml::mapping::reject_mixed_content(node)?;
+        let mut variables = Vec::new();
+        for child in node.children() {
+            if child.is_element_named(VariableTag::NAMESPACE, VariableTag::TAG_NAME) {
+                variables.push(Variable::from_xml(child)?);
+            }
+        }
+        Ok(EnvironmentValue { variables })
+    }
+}
 
 #[derive(Debug, Clone, typed_builder::TypedBuilder, SimpleTagValue, FromXml)]
 pub struct ShellValue<'a> {
@@ -23,6 +85,12 @@ pub struct ShellValue<'a> {
     #[builder(default, setter(strip_option, into))]
     pub process_id: Option<ProcessId<'a>>,
     #[builder(default, setter(strip_option(fallback_suffix = "_opt"), into))]
+    pub environment: Option<Environment<'a>>,
+    #[builder(default, setter(strip_option(fallback_suffix = "_opt"), into))]
+    pub working_directory: Option<WorkingDirectory<'a>>,
+    #[builder(default, setter(strip_option(fallback_suffix = "_opt"), into))]
+    pub lifetime: Option<Lifetime<'a>>,
+    #[builder(default, setter(strip_option(fallback_suffix = "_opt"), into))]
     pub idle_time_out: Option<IdleTimeOut<'a>>,
     #[builder(default, setter(strip_option, into))]
     pub input_streams: Option<InputStreams<'a>>,
@@ -0,0 +1,84 @@
+use ironposh_xml::XmlError;
+
+/// Signal codes carried in the `rsp:Code` element of a WinRS `Signal` request
+/// (MS-WSMV 3.1.4.12).
+///
+/// `PowerShellCtrlC` asks a PowerShell pipeline to stop gracefully (the
+/// `StopProcessing` path); `Terminate` tears the command down regardless of
+/// its state. `CtrlC` and `CtrlBreak` are the WinRS codes for `cmd` shells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalCode {
+    Terminate,
+    CtrlC,
+    CtrlBreak,
+    /// The PowerShell plugin's Ctrl+C (MS-PSRP 3.2.5.4.14), spelled `crtl_c`
+    /// by the server.
+    PowerShellCtrlC,
+}
+
+impl SignalCode {
+    pub const fn value(self) -> &'static str {
+        match self {
+            Self::Terminate => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/terminate"
+            }
+            Self::CtrlC => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/ctrl_c",
+            Self::CtrlBreak => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/ctrl_break"
+            }
+            Self::PowerShellCtrlC => "http://schemas.microsoft.com/powershell/signal/crtl_c",
+        }
+    }
+}
+
+impl TryFrom<&str> for SignalCode {
+    type Error = XmlError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/terminate" => {
+                Ok(Self::Terminate)
+            }
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/ctrl_c" => {
+                Ok(Self::CtrlC)
+            }
+            "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/signal/ctrl_break" => {
+                Ok(Self::CtrlBreak)
+            }
+            "http://schemas.microsoft.com/powershell/signal/crtl_c" => Ok(Self::PowerShellCtrlC),
+            _ => Err(XmlError::GenericError(format!(
+                "Unknown SignalCode: {value}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_code_round_trips_through_uri() {
+        for code in [
+            SignalCode::Terminate,
+            SignalCode::CtrlC,
+            SignalCode::CtrlBreak,
+            SignalCode::PowerShellCtrlC,
+        ] {
+            assert_eq!(SignalCode::try_from(code.value()).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn powershell_ctrl_c_keeps_the_server_spelling() {
+        assert_eq!(
+            SignalCode::PowerShellCtrlC.value(),
+            "http://schemas.microsoft.com/powershell/signal/crtl_c"
+        );
+    }
+
+    #[test]
+    fn unknown_signal_code_is_rejected() {
+        assert!(SignalCode::try_from("http://example.com/signal/nope").is_err());
+    }
+}
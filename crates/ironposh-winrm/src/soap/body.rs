@@ -74,3 +74,25 @@ pub struct SoapBody<'a> {
     #[builder(default, setter(into, strip_option))]
     pub fault: Option<Fault<'a>>,
 }
+
+/// Reads a ReceiveResponse body; any other body is left to the document
+/// parser.
+#[cfg(feature = "streaming")]
+impl<'a> ironposh_xml::stream::FromXmlStream<'a> for SoapBody<'a> {
+    fn from_xml_stream(
+        _start: ironposh_xml::stream::StartElement<'a>,
+        parser: &mut ironposh_xml::stream::PullParser<'a>,
+    ) -> Result<Self, ironposh_xml::XmlError> {
+        let mut body = Self::builder().build();
+        while let Some(start) = parser.next_child()? {
+            let mut child = Some(start);
+            crate::cores::stream_field(&mut body.receive_response, &mut child, parser, "SoapBody")?;
+            if let Some(unsupported) = child {
+                return Err(ironposh_xml::XmlError::StreamUnsupported(
+                    unsupported.local_name.to_owned(),
+                ));
+            }
+        }
+        Ok(body)
+    }
+}
@@ -1,6 +1,11 @@
-use crate::cores::{Detail, SoapText, SoapValue};
+use crate::cores::{Detail, Namespace, ReadOnlyUnParsed, SoapText, SoapValue};
 use crate::tag;
 use ironposh_macros::{FromXml, SimpleTagValue};
+use ironposh_xml::mapping::NodeExt as _;
+
+/// WinRM error code (`ERROR_WSMAN_...`) for a request addressed to a shell
+/// that no longer exists, e.g. after its idle timeout expired.
+pub const SHELL_NOT_FOUND: u32 = 2_150_858_843;
 
 // SOAP Fault structures for handling SOAP error responses
 
@@ -63,19 +68,21 @@ impl<'a> ironposh_xml::mapping::FromXml<'a> for SoapFaultReasonValue<'a> {
 }
 
 impl SoapFaultValue<'_> {
+    /// The fault subcode value, e.g. `w:TimedOut`.
+    pub fn subcode(&self) -> Option<&str> {
+        self.code
+            .as_ref()
+            .and_then(|code| code.as_ref().subcode.as_ref())
+            .and_then(|subcode| subcode.as_ref().value.as_ref())
+            .map(|value| <&str>::from(value.as_ref()))
+    }
+
     /// Check if this SOAP fault represents a WS-Management operation timeout.
     ///
     /// WinRM sends this fault when a Receive request times out without data.
     /// The subcode value will contain "TimedOut" (e.g. `w:TimedOut`).
     pub fn is_timeout(&self) -> bool {
-        let subcode_text = self
-            .code
-            .as_ref()
-            .and_then(|code| code.as_ref().subcode.as_ref())
-            .and_then(|subcode| subcode.as_ref().value.as_ref())
-            .map(|value| <&str>::from(value.as_ref()));
-
-        matches!(subcode_text, Some(text) if text.contains("TimedOut"))
+        matches!(self.subcode(), Some(text) if text.contains("TimedOut"))
     }
 
     /// Check if this SOAP fault represents an invalid selector error.
@@ -84,14 +91,37 @@ impl SoapFaultValue<'_> {
     /// when a request references a `CommandId` that no longer exists (e.g. a
     /// pipeline was canceled or completed while we still had a Receive in flight).
     pub fn is_invalid_selectors(&self) -> bool {
-        let subcode_text = self
-            .code
-            .as_ref()
-            .and_then(|code| code.as_ref().subcode.as_ref())
-            .and_then(|subcode| subcode.as_ref().value.as_ref())
-            .map(|value| <&str>::from(value.as_ref()));
+        matches!(self.subcode(), Some(text) if text.contains("InvalidSelectors"))
+    }
 
-        matches!(subcode_text, Some(text) if text.contains("InvalidSelectors"))
+    /// Check if this SOAP fault reports an exceeded WinRM quota.
+    ///
+    /// WinRM answers with `w:QuotaLimit` when a per-user limit such as
+    /// `MaxShellsPerUser` or `MaxConcurrentOperationsPerUser` would be
+    /// exceeded; the reason text names the limit.
+    pub fn is_quota_limit(&self) -> bool {
+        matches!(self.subcode(), Some(text) if text.contains("QuotaLimit"))
+    }
+
+    /// The WinRM error code carried by the `f:WSManFault` element in the
+    /// fault detail, if present.
+    pub fn wsman_code(&self) -> Option<u32> {
+        let nodes = match &self.detail.as_ref()?.value {
+            ReadOnlyUnParsed::Node(node) => std::slice::from_ref(node),
+            ReadOnlyUnParsed::Children(nodes) => nodes.as_slice(),
+        };
+        nodes
+            .iter()
+            .flat_map(ironposh_xml::parser::Node::descendants)
+            .find(|node| node.is_element_named(Some(Namespace::WsmanFault.uri()), "WSManFault"))
+            .and_then(|node| node.attribute("Code"))
+            .and_then(|code| code.trim().parse().ok())
+    }
+
+    /// Check if this SOAP fault reports that the addressed shell does not
+    /// exist (any more).
+    pub fn is_shell_not_found(&self) -> bool {
+        self.wsman_code() == Some(SHELL_NOT_FOUND)
     }
 
     /// Get the human-readable reason text from the fault, if available.
@@ -121,4 +151,22 @@ mod tests {
             Reason::from_xml(doc.root_element()).expect("multilingual reason should parse");
         assert!(reason.as_ref().text.is_some());
     }
+
+    #[test]
+    fn wsman_code_is_read_from_detail() {
+        let xml = format!(
+            r#"<s:Fault xmlns:s="{S}" xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd"><s:Code><s:Value>s:Sender</s:Value><s:Subcode><s:Value>w:InvalidSelectors</s:Value></s:Subcode></s:Code><s:Reason><s:Text xml:lang="en-US">The request for the Windows Remote Shell with ShellId 2D6534D0 failed because the shell was not found on the server.</s:Text></s:Reason><s:Detail><f:WSManFault xmlns:f="http://schemas.microsoft.com/wbem/wsman/1/wsmanfault" Code="2150858843" Machine="host"><f:Message>The shell was not found.</f:Message></f:WSManFault></s:Detail></s:Fault>"#
+        );
+        let doc = parse(&xml).unwrap();
+        let fault = Fault::from_xml(doc.root_element()).unwrap();
+        assert_eq!(fault.as_ref().wsman_code(), Some(SHELL_NOT_FOUND));
+        assert!(fault.as_ref().is_shell_not_found());
+        assert!(fault.as_ref().is_invalid_selectors());
+
+        let xml =
+            format!(r#"<s:Fault xmlns:s="{S}"><s:Reason><s:Text>x</s:Text></s:Reason></s:Fault>"#);
+        let doc = parse(&xml).unwrap();
+        let fault = Fault::from_xml(doc.root_element()).unwrap();
+        assert_eq!(fault.as_ref().wsman_code(), None);
+    }
 }
@@ -4,7 +4,10 @@ use crate::cores::{
 };
 use crate::tag;
 use crate::ws_addressing::ReplyTo;
-use crate::ws_management::{OptionSet, SelectorSet};
+use crate::ws_management::{
+    FragmentTransfer, OptionSet, RequestTotalItemsCountEstimate, SelectorSet,
+    TotalItemsCountEstimate,
+};
 
 tag!(Header = SoapHeaders<'a> => SoapEnvelope2003);
 
@@ -55,4 +58,55 @@ pub struct SoapHeaders<'a> {
     pub operation_timeout: Option<OperationTimeout<'a>>,
     #[builder(default, setter(into, strip_option))]
     pub compression_type: Option<CompressionType<'a>>,
+    #[builder(default, setter(into, strip_option))]
+    pub fragment_transfer: Option<FragmentTransfer<'a>>,
+    #[builder(default, setter(into, strip_option))]
+    pub request_total_items_count_estimate: Option<RequestTotalItemsCountEstimate<'a>>,
+    #[builder(default, setter(into, strip_option))]
+    pub total_items_count_estimate: Option<TotalItemsCountEstimate<'a>>,
+}
+
+/// Reads the headers a response carries. Request-only headers with structured
+/// values (`ReplyTo`, `OptionSet`, `SelectorSet`, the item count estimate) and
+/// unknown headers are left to the document parser.
+#[cfg(feature = "streaming")]
+impl<'a> ironposh_xml::stream::FromXmlStream<'a> for SoapHeaders<'a> {
+    fn from_xml_stream(
+        _start: ironposh_xml::stream::StartElement<'a>,
+        parser: &mut ironposh_xml::stream::PullParser<'a>,
+    ) -> Result<Self, ironposh_xml::XmlError> {
+        use crate::cores::stream_field;
+
+        const OWNER: &str = "SoapHeaders";
+        let mut headers = Self::builder().build();
+        while let Some(start) = parser.next_child()? {
+            let mut child = Some(start);
+            stream_field(&mut headers.to, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.action, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.message_id, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.relates_to, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.resource_uri, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.max_envelope_size, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.locale, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.data_locale, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.session_id, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.operation_id, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.sequence_id, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.operation_timeout, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.compression_type, &mut child, parser, OWNER)?;
+            stream_field(&mut headers.fragment_transfer, &mut child, parser, OWNER)?;
+            stream_field(
+                &mut headers.request_total_items_count_estimate,
+                &mut child,
+                parser,
+                OWNER,
+            )?;
+            if let Some(unsupported) = child {
+                return Err(ironposh_xml::XmlError::StreamUnsupported(
+                    unsupported.local_name.to_owned(),
+                ));
+            }
+        }
+        Ok(headers)
+    }
 }
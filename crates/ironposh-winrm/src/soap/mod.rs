@@ -7,6 +7,7 @@ use crate::cores::TagValue;
 use crate::tag;
 use crate::{soap::body::Body, soap::header::Header};
 use ironposh_xml::mapping::{FromXml, NodeExt};
+use ironposh_xml::parser::{Document, ParseLimits};
 
 tag!(Envelope = SoapEnvelope<'a> => SoapEnvelope2003);
 
@@ -30,7 +31,8 @@ impl<'a> FromXml<'a> for SoapEnvelope<'a> {
             return Err(ironposh_xml::XmlError::XmlInvalidTag {
                 expected: <EnvelopeTag as crate::cores::TagName>::TAG_NAME.to_string(),
                 found: node.tag_name().name().to_string(),
-            });
+            }
+            .at(node));
         }
         ironposh_xml::mapping::reject_mixed_content(node)?;
         let mut header = None;
@@ -70,6 +72,61 @@ impl<'a> FromXml<'a> for SoapEnvelope<'a> {
     }
 }
 
+#[cfg(feature = "streaming")]
+impl<'a> ironposh_xml::stream::FromXmlStream<'a> for SoapEnvelope<'a> {
+    fn from_xml_stream(
+        start: ironposh_xml::stream::StartElement<'a>,
+        parser: &mut ironposh_xml::stream::PullParser<'a>,
+    ) -> Result<Self, ironposh_xml::XmlError> {
+        if !start.is_named(
+            <EnvelopeTag as crate::cores::TagName>::NAMESPACE,
+            <EnvelopeTag as crate::cores::TagName>::TAG_NAME,
+        ) {
+            return Err(ironposh_xml::XmlError::XmlInvalidTag {
+                expected: <EnvelopeTag as crate::cores::TagName>::TAG_NAME.to_string(),
+                found: start.local_name.to_string(),
+            });
+        }
+        let mut header = None;
+        let mut body = None;
+        while let Some(start) = parser.next_child()? {
+            let mut child = Some(start);
+            crate::cores::stream_field(&mut header, &mut child, parser, "Envelope")?;
+            crate::cores::stream_field(&mut body, &mut child, parser, "Envelope")?;
+            if child.is_some() {
+                parser.skip_element()?;
+            }
+        }
+        Ok(SoapEnvelope {
+            header,
+            body: body.ok_or_else(|| {
+                ironposh_xml::XmlError::InvalidXml("Missing body in SoapEnvelope".into())
+            })?,
+        })
+    }
+}
+
+/// Parse the SOAP envelope `xml`.
+///
+/// With feature `streaming`, an envelope the pull parser can map entirely (a
+/// ReceiveResponse) is read without building a document. Any other envelope,
+/// and every envelope without the feature, is parsed into `document` first and
+/// borrows from it.
+pub fn parse_envelope<'d, 'a: 'd>(
+    xml: &'a str,
+    limits: ParseLimits,
+    document: &'d mut Option<Document<'a>>,
+) -> Result<SoapEnvelope<'d>, ironposh_xml::XmlError> {
+    #[cfg(feature = "streaming")]
+    match ironposh_xml::stream::from_str_with_limits(xml, limits) {
+        Err(e) if matches!(e.kind(), ironposh_xml::XmlError::StreamUnsupported(_)) => {}
+        streamed => return streamed,
+    }
+
+    let document = document.insert(ironposh_xml::parser::parse_with_limits(xml, limits)?);
+    SoapEnvelope::from_xml(document.root_element())
+}
+
 impl<'a> TagValue<'a> for SoapEnvelope<'a> {
     fn append_to_element(
         self,
@@ -102,6 +159,57 @@ mod tests {
         assert!(SoapEnvelope::from_xml(doc.root_element()).is_err());
     }
 
+    const RECEIVE_RESPONSE: &str = r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:rsp="http://schemas.microsoft.com/wbem/wsman/1/windows/shell"><s:Header><a:Action>http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReceiveResponse</a:Action><a:RelatesTo>uuid:0B4C1D6E-5E2A-4C0D-9E1F-7A8B9C0D1E2F</a:RelatesTo></s:Header><s:Body><rsp:ReceiveResponse SequenceID="3"><rsp:Stream Name="stdout" CommandId="2D6534D0-6B12-40E3-B773-CBA26459CFA8">QUJD</rsp:Stream><rsp:Stream Name="stdout" CommandId="2D6534D0-6B12-40E3-B773-CBA26459CFA8" End="true"></rsp:Stream><rsp:CommandState CommandId="2D6534D0-6B12-40E3-B773-CBA26459CFA8" State="http://schemas.microsoft.com/powershell/Microsoft.PowerShell/CommandState/Done"><rsp:ExitCode>0</rsp:ExitCode></rsp:CommandState></rsp:ReceiveResponse></s:Body></s:Envelope>"#;
+
+    #[test]
+    fn parse_envelope_reads_a_receive_response() {
+        let mut document = None;
+        let envelope =
+            parse_envelope(RECEIVE_RESPONSE, ParseLimits::default(), &mut document).unwrap();
+
+        let header = envelope.header.as_ref().expect("header").as_ref();
+        assert_eq!(
+            header.relates_to.as_ref().map(|id| id.value.0.to_string()),
+            Some("0b4c1d6e-5e2a-4c0d-9e1f-7a8b9c0d1e2f".to_owned())
+        );
+        let receive = envelope
+            .body
+            .as_ref()
+            .receive_response
+            .as_ref()
+            .expect("ReceiveResponse");
+        assert!(matches!(
+            receive.attributes.as_slice(),
+            [crate::cores::Attribute::SequenceID(3)]
+        ));
+        let streams = &receive.value.streams;
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].value.as_ref(), "QUJD");
+        assert_eq!(streams[1].value.as_ref(), "");
+        let exit_code = receive
+            .value
+            .command_state
+            .as_ref()
+            .and_then(|state| state.value.exit_code.as_ref())
+            .map(|code| code.value.0);
+        assert_eq!(exit_code, Some(0));
+
+        drop(envelope);
+        assert_eq!(document.is_some(), cfg!(not(feature = "streaming")));
+    }
+
+    #[test]
+    fn parse_envelope_falls_back_to_a_document_for_other_bodies() {
+        let xml = format!(
+            r#"<s:Envelope xmlns:s="{S}"><s:Body><s:Fault><s:Code><s:Value>s:Receiver</s:Value></s:Code></s:Fault></s:Body></s:Envelope>"#
+        );
+        let mut document = None;
+        let envelope = parse_envelope(&xml, ParseLimits::default(), &mut document).unwrap();
+        assert!(envelope.body.as_ref().fault.is_some());
+        drop(envelope);
+        assert!(document.is_some());
+    }
+
     #[test]
     fn accepts_envelope_root_regardless_of_prefix() {
         let xml = format!(r#"<x:Envelope xmlns:x="{S}"><x:Body/></x:Envelope>"#);
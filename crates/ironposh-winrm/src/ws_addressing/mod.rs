@@ -1,11 +1,139 @@
 use ironposh_macros::{FromXml, SimpleTagValue};
 
-use crate::cores::Address;
+use crate::cores::{Address, ResourceURI, Tag, Text};
 use crate::tag;
+use crate::ws_management::{OptionSet, SelectorSet, SelectorSetValue};
 
-tag!(ReplyTo = AddressValue<'a> => WsAddressing2004);
+tag!(ReplyTo = EndpointReference<'a> => WsAddressing2004);
+tag!(ReferenceProperties = ReferenceParametersValue<'a> => WsAddressing2004);
+tag!(ReferenceParameters = ReferenceParametersValue<'a> => WsAddressing2004);
 
+/// A WS-Addressing (2004/08) endpoint reference, as sent in `ReplyTo` and
+/// returned in `x:ResourceCreated`.
+///
+/// The address alone does not identify a WS-Management resource: the shell a
+/// CreateResponse refers to is named by the selectors in its reference
+/// parameters (older services send them as reference properties).
 #[derive(Debug, Clone, SimpleTagValue, FromXml)]
-pub struct AddressValue<'a> {
-    pub url: Address<'a>,
+pub struct EndpointReference<'a> {
+    pub address: Address<'a>,
+    pub reference_properties: Option<ReferenceProperties<'a>>,
+    pub reference_parameters: Option<ReferenceParameters<'a>>,
+}
+
+impl<'a> EndpointReference<'a> {
+    /// A reference with only an address, e.g. the anonymous `ReplyTo`.
+    pub fn new(address: impl Into<Text<'a>>) -> Self {
+        Self {
+            address: Tag::new(address),
+            reference_properties: None,
+            reference_parameters: None,
+        }
+    }
+
+    fn references(&self) -> impl Iterator<Item = &ReferenceParametersValue<'a>> {
+        let parameters = self.reference_parameters.iter().map(|tag| &tag.value);
+        let properties = self.reference_properties.iter().map(|tag| &tag.value);
+        parameters.chain(properties)
+    }
+
+    /// The resource URI of the referenced resource, if the reference carries one.
+    pub fn resource_uri(&self) -> Option<&str> {
+        self.references()
+            .find_map(|reference| reference.resource_uri.as_ref())
+            .map(|uri| uri.value.as_ref())
+    }
+
+    /// The selectors naming the referenced resource (e.g. `ShellId`), if any.
+    pub fn selector_set(&self) -> Option<&SelectorSetValue> {
+        self.references()
+            .find_map(|reference| reference.selector_set.as_ref())
+            .map(|selector_set| &selector_set.value)
+    }
+}
+
+/// The WS-Management content of `ReferenceParameters`/`ReferenceProperties`:
+/// what a request to the referenced resource must carry in its headers.
+#[derive(Debug, Clone, SimpleTagValue, FromXml)]
+pub struct ReferenceParametersValue<'a> {
+    pub resource_uri: Option<ResourceURI<'a>>,
+    pub selector_set: Option<SelectorSet<'a>>,
+    pub option_set: Option<OptionSet<'a>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use ironposh_xml::mapping::FromXml;
+
+    use super::*;
+    use crate::ws_management::body::ResourceCreated;
+
+    const SHELL_ID: &str = "2D6534D0-6B12-40E3-B773-CBA26459CFA8";
+
+    fn with_resource_created(references: &str, check: impl FnOnce(&EndpointReference<'_>)) {
+        let xml = format!(
+            r#"<x:ResourceCreated xmlns:x="http://schemas.xmlsoap.org/ws/2004/09/transfer" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd"><a:Address>http://host:5985/wsman</a:Address>{references}</x:ResourceCreated>"#
+        );
+        let doc = ironposh_xml::parser::parse(&xml).unwrap();
+        check(&ResourceCreated::from_xml(doc.root_element()).unwrap().value);
+    }
+
+    #[test]
+    fn selectors_are_read_from_reference_parameters_or_properties() {
+        for element in ["ReferenceParameters", "ReferenceProperties"] {
+            let references = format!(
+                r#"<a:{element}><w:ResourceURI>http://schemas.microsoft.com/powershell/Microsoft.PowerShell</w:ResourceURI><w:SelectorSet><w:Selector Name="ShellId">{SHELL_ID}</w:Selector></w:SelectorSet></a:{element}>"#
+            );
+            with_resource_created(&references, |epr| {
+                assert_eq!(
+                    epr.selector_set()
+                        .and_then(|s| s.get("ShellId"))
+                        .map(String::as_str),
+                    Some(SHELL_ID),
+                    "{element}"
+                );
+                assert_eq!(
+                    epr.resource_uri(),
+                    Some("http://schemas.microsoft.com/powershell/Microsoft.PowerShell")
+                );
+            });
+        }
+
+        with_resource_created("", |epr| {
+            assert!(epr.selector_set().is_none());
+            assert_eq!(epr.address.value.as_ref(), "http://host:5985/wsman");
+        });
+    }
+
+    #[test]
+    fn endpoint_reference_roundtrips() {
+        let mut epr = EndpointReference::new("http://host:5985/wsman");
+        epr.reference_parameters = Some(Tag::new(ReferenceParametersValue {
+            resource_uri: None,
+            selector_set: Some(Tag::new(
+                SelectorSetValue::new().add_selector("ShellId", SHELL_ID),
+            )),
+            option_set: None,
+        }));
+        let element = ReplyTo::new(epr)
+            .with_declaration(crate::cores::Namespace::WsAddressing2004)
+            .with_declaration(crate::cores::Namespace::DmtfWsmanSchema)
+            .into_element();
+        let xml = ironposh_xml::builder::Builder::new(None, element)
+            .to_xml_string()
+            .unwrap();
+
+        let doc = ironposh_xml::parser::parse(&xml).unwrap();
+        let parsed = ReplyTo::from_xml(doc.root_element()).unwrap().value;
+        assert_eq!(parsed.address.value.as_ref(), "http://host:5985/wsman");
+        assert!(parsed.reference_properties.is_none());
+        assert_eq!(
+            parsed
+                .selector_set()
+                .and_then(|s| s.get("ShellId"))
+                .map(String::as_str),
+            Some(SHELL_ID),
+            "{xml}"
+        );
+    }
 }
@@ -1,12 +1,10 @@
-use ironposh_macros::{FromXml, SimpleTagValue};
 use ironposh_xml::builder::Element;
 
-use crate::cores::{Address, ResourceURI, TagValue, tag_value::Text};
+use crate::cores::{TagValue, tag_value::Text};
 use crate::tag;
-use crate::ws_management::SelectorSet;
+use crate::ws_addressing::EndpointReference;
 
-tag!(ReferenceParameters = ReferenceParametersValue<'a> => WsAddressing2004);
-tag!(ResourceCreated = ResourceCreatedValue<'a> => WsTransfer2004);
+tag!(ResourceCreated = EndpointReference<'a> => WsTransfer2004);
 
 // Enumeration operations
 #[derive(Debug, Clone, Default)]
@@ -127,18 +125,6 @@ impl<'a> TagValue<'a> for GetStatusValue<'a> {
     }
 }
 
-#[derive(Debug, Clone, SimpleTagValue, FromXml)]
-pub struct ReferenceParametersValue<'a> {
-    pub resource_uri: ResourceURI<'a>,
-    pub selector_set: SelectorSet<'a>,
-}
-
-#[derive(Debug, Clone, SimpleTagValue, FromXml)]
-pub struct ResourceCreatedValue<'a> {
-    pub address: Address<'a>,
-    pub reference_parameters: ReferenceParameters<'a>,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,14 +164,16 @@ mod tests {
             value.address.value,
             "http://10.10.0.3:5985/wsman?PSVersion=7.4.10".into()
         );
+        assert_eq!(
+            value.resource_uri(),
+            Some("http://schemas.microsoft.com/powershell/Microsoft.PowerShell")
+        );
         assert_eq!(
             value
-                .reference_parameters
-                .as_ref()
-                .resource_uri
-                .as_ref()
-                .as_ref(),
-            "http://schemas.microsoft.com/powershell/Microsoft.PowerShell"
+                .selector_set()
+                .and_then(|s| s.get("ShellId"))
+                .map(String::as_str),
+            Some("2D6534D0-6B12-40E3-B773-CBA26459CFA8")
         );
     }
 }
@@ -6,12 +6,25 @@ use ironposh_xml::{
 };
 
 use crate::cores::tag_value::leaf_text;
-use crate::cores::{self, OptionTagNameTag, Selector, SelectorTag, TagName, TagValue, Text};
+use crate::cores::{
+    self, Empty, OptionTagNameTag, Selector, SelectorTag, TagName, TagValue, Text, U32,
+};
 use crate::tag;
 
 tag!(SelectorSet = SelectorSetValue => DmtfWsmanSchema);
 tag!(OptionSet = OptionSetValue => DmtfWsmanSchema);
 
+// Fragment-level transfer (DSP0226 7.7): the fragment expression selecting
+// part of a resource. Requests send it with `s:mustUnderstand="true"`.
+tag!(FragmentTransfer = Text<'a> => DmtfWsmanSchema);
+// Enumeration (DSP0226 8): the count estimate is requested and returned as
+// headers; `OptimizeEnumeration` and `MaxElements` go in the `n:Enumerate`
+// body, asking for the first items in the Enumerate response itself.
+tag!(RequestTotalItemsCountEstimate = Empty => DmtfWsmanSchema);
+tag!(TotalItemsCountEstimate = ItemsCountEstimate => DmtfWsmanSchema);
+tag!(OptimizeEnumeration = Empty => DmtfWsmanSchema);
+tag!(MaxElements = U32 => DmtfWsmanSchema);
+
 #[derive(Debug, Clone, Default)]
 pub struct SelectorSetValue {
     pub selectors: HashMap<String, String>,
@@ -43,7 +56,11 @@ impl SelectorSetValue {
 
 impl<'a> TagValue<'a> for SelectorSetValue {
     fn append_to_element(self, mut element: Element<'a>) -> Element<'a> {
-        for (name, value) in self.selectors {
+        // Sorted so the same set always serialises identically.
+        let mut selectors: Vec<_> = self.selectors.into_iter().collect();
+        selectors.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, value) in selectors {
             let selector = Selector::new(Text::from(value))
                 .with_attribute(crate::cores::Attribute::Name(name.into()));
 
@@ -83,9 +100,140 @@ impl<'a> FromXml<'a> for SelectorSetValue {
     }
 }
 
+/// Value of a `<w:Option>`.
+///
+/// Typed values are rendered the way winrs does (`TRUE`/`FALSE`, decimal
+/// integers). Parsed options are always [`OptionValue::Text`]; use
+/// [`OptionValue::as_bool`]/[`OptionValue::as_int`] to read them back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Text(String),
+    Bool(bool),
+    Int(i64),
+}
+
+impl OptionValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            Self::Text(text) if text.eq_ignore_ascii_case("true") => Some(true),
+            Self::Text(text) if text.eq_ignore_ascii_case("false") => Some(false),
+            Self::Text(_) | Self::Int(_) => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            Self::Text(text) => text.trim().parse().ok(),
+            Self::Bool(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Bool(value) => f.write_str(bool_text(*value)),
+            Self::Int(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+fn bool_text(value: bool) -> &'static str {
+    if value { "TRUE" } else { "FALSE" }
+}
+
+impl From<&str> for OptionValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
+impl From<String> for OptionValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<bool> for OptionValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for OptionValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u32> for OptionValue {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+/// One `<w:Option>`: its value and its `MustComply` attribute (`None` omits it).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsOption {
+    pub value: OptionValue,
+    pub must_comply: Option<bool>,
+}
+
+impl PartialEq<str> for WsOption {
+    fn eq(&self, other: &str) -> bool {
+        match &self.value {
+            OptionValue::Text(text) => text == other,
+            OptionValue::Bool(value) => other == bool_text(*value),
+            OptionValue::Int(value) => other.parse::<i64>() == Ok(*value),
+        }
+    }
+}
+
+/// Options with a fixed meaning to the WinRM shell host, rendered the way
+/// native winrs and PowerShell send them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WellKnownOption {
+    /// `WINRS_NOPROFILE`: do not load the user profile for the shell.
+    NoProfile(bool),
+    /// `WINRS_CODEPAGE`: console code page of the shell (e.g. 65001 for UTF-8).
+    Codepage(u32),
+    /// `protocolversion`: PSRP protocol version, sent with `MustComply="true"`.
+    ProtocolVersion(String),
+}
+
+impl WellKnownOption {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NoProfile(_) => "WINRS_NOPROFILE",
+            Self::Codepage(_) => "WINRS_CODEPAGE",
+            Self::ProtocolVersion(_) => "protocolversion",
+        }
+    }
+
+    fn into_option(self) -> WsOption {
+        match self {
+            Self::NoProfile(no_profile) => WsOption {
+                value: no_profile.into(),
+                must_comply: None,
+            },
+            Self::Codepage(codepage) => WsOption {
+                value: codepage.into(),
+                must_comply: None,
+            },
+            Self::ProtocolVersion(version) => WsOption {
+                value: version.into(),
+                must_comply: Some(true),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct OptionSetValue {
-    pub options: HashMap<String, String>,
+    pub options: HashMap<String, WsOption>,
 }
 
 impl OptionSetValue {
@@ -93,31 +241,78 @@ impl OptionSetValue {
         Self::default()
     }
 
-    /// Add an option as a key-value pair
+    /// Add an option as a key-value pair, marked `MustComply="true"`
     /// Example:
     /// option_set.add_option("WINRS_CONSOLEMODE_STDIN", "TRUE")
-    /// Generates: <w:Option Name="WINRS_CONSOLEMODE_STDIN">TRUE</w:Option>
-    pub fn add_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.options.insert(name.into(), value.into());
+    /// Generates: <w:Option Name="WINRS_CONSOLEMODE_STDIN" MustComply="true">TRUE</w:Option>
+    pub fn add_option(mut self, name: impl Into<String>, value: impl Into<OptionValue>) -> Self {
+        self.insert_option(name, value);
         self
     }
 
     /// Add an option using a mutable reference for chaining
-    pub fn insert_option(&mut self, name: impl Into<String>, value: impl Into<String>) {
-        self.options.insert(name.into(), value.into());
+    pub fn insert_option(&mut self, name: impl Into<String>, value: impl Into<OptionValue>) {
+        self.insert_option_with(name, value, Some(true));
+    }
+
+    /// Add an option with an explicit `MustComply` attribute (`None` omits it)
+    pub fn add_option_with(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<OptionValue>,
+        must_comply: Option<bool>,
+    ) -> Self {
+        self.insert_option_with(name, value, must_comply);
+        self
+    }
+
+    pub fn insert_option_with(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<OptionValue>,
+        must_comply: Option<bool>,
+    ) {
+        self.options.insert(
+            name.into(),
+            WsOption {
+                value: value.into(),
+                must_comply,
+            },
+        );
+    }
+
+    /// Add a well-known option with its canonical name, rendering and `MustComply`
+    /// Example:
+    /// option_set.add_well_known(WellKnownOption::Codepage(65001))
+    /// Generates: <w:Option Name="WINRS_CODEPAGE">65001</w:Option>
+    pub fn add_well_known(mut self, option: WellKnownOption) -> Self {
+        let name = option.name();
+        self.options.insert(name.to_owned(), option.into_option());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WsOption> {
+        self.options.get(name)
     }
 }
 
 impl<'a> TagValue<'a> for OptionSetValue {
     fn append_to_element(self, mut element: Element<'a>) -> Element<'a> {
-        for (name, value) in self.options {
-            let option_element = Element::new("Option")
+        // Sorted so the same set always serialises identically.
+        let mut options: Vec<_> = self.options.into_iter().collect();
+        options.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, option) in options {
+            let mut option_element = Element::new("Option")
                 .set_namespace(ironposh_xml::builder::Namespace::from(
                     OptionTagNameTag::NAMESPACE.expect("OptionTagName definately has a namespace"),
                 ))
-                .set_text(value)
-                .add_attribute(cores::Attribute::Name(name.into()).into())
-                .add_attribute(cores::Attribute::MustComply(true).into());
+                .set_text(option.value.to_string())
+                .add_attribute(cores::Attribute::Name(name.into()).into());
+            if let Some(must_comply) = option.must_comply {
+                option_element =
+                    option_element.add_attribute(cores::Attribute::MustComply(must_comply).into());
+            }
             element = element.add_child(option_element);
         }
 
@@ -143,13 +338,82 @@ impl<'a> FromXml<'a> for OptionSetValue {
                         "duplicate option {name:?}"
                     )));
                 }
-                options.insert(name, leaf_text(child)?.to_string());
+                let must_comply = child
+                    .attributes()
+                    .find(|attr| attr.namespace().is_none() && attr.name() == "MustComply")
+                    .map(|attr| {
+                        cores::Attribute::from_name_and_value(None, "MustComply", attr.value())
+                    })
+                    .transpose()?
+                    .flatten()
+                    .and_then(|attr| match attr {
+                        cores::Attribute::MustComply(must_comply) => Some(must_comply),
+                        _ => None,
+                    });
+                options.insert(
+                    name,
+                    WsOption {
+                        value: OptionValue::Text(leaf_text(child)?.to_string()),
+                        must_comply,
+                    },
+                );
             }
         }
         Ok(Self { options })
     }
 }
 
+/// Value of a `<w:TotalItemsCountEstimate>`: `None` when the service cannot
+/// estimate, sent as `xsi:nil="true"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemsCountEstimate(pub Option<u64>);
+
+impl<'a> TagValue<'a> for ItemsCountEstimate {
+    fn append_to_element(self, element: Element<'a>) -> Element<'a> {
+        match self.0 {
+            Some(count) => element.set_text(count.to_string()),
+            None => element.add_attribute(cores::Attribute::Nil(true).into()),
+        }
+    }
+}
+
+impl<'a> FromXml<'a> for ItemsCountEstimate {
+    fn from_xml(node: ironposh_xml::parser::Node<'a, 'a>) -> Result<Self, ironposh_xml::XmlError> {
+        let nil = node
+            .attributes()
+            .find(|attr| {
+                attr.namespace() == Some(cores::Namespace::XmlSchemaInstance.uri())
+                    && attr.name() == "nil"
+            })
+            .map(|attr| {
+                cores::Attribute::from_name_and_value(attr.namespace(), attr.name(), attr.value())
+            })
+            .transpose()?
+            .flatten()
+            .is_some_and(|attr| matches!(attr, cores::Attribute::Nil(true)));
+        let text = leaf_text(node)?;
+        if nil {
+            if !text.is_empty() {
+                return Err(ironposh_xml::XmlError::InvalidXml(
+                    "nil <TotalItemsCountEstimate> has text content".into(),
+                ));
+            }
+            return Ok(Self(None));
+        }
+        text.parse().map(|count| Self(Some(count))).map_err(|_| {
+            ironposh_xml::XmlError::InvalidXml(format!(
+                "invalid TotalItemsCountEstimate value: {text}"
+            ))
+        })
+    }
+}
+
+impl From<u64> for ItemsCountEstimate {
+    fn from(count: u64) -> Self {
+        Self(Some(count))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +439,52 @@ mod tests {
         assert!(OptionSetValue::from_xml(doc.root_element()).is_err());
     }
 
+    #[test]
+    fn well_known_options_render_like_winrs_and_roundtrip() {
+        let option_set = OptionSetValue::new()
+            .add_well_known(WellKnownOption::NoProfile(true))
+            .add_well_known(WellKnownOption::Codepage(65001))
+            .add_well_known(WellKnownOption::ProtocolVersion("2.3".to_owned()));
+        let element = OptionSet::from(option_set)
+            .with_declaration(crate::cores::Namespace::DmtfWsmanSchema)
+            .into_element();
+        let xml = ironposh_xml::builder::Builder::new(None, element)
+            .to_xml_string()
+            .unwrap();
+
+        let codepage = xml.find("WINRS_CODEPAGE").unwrap();
+        let no_profile = xml.find("WINRS_NOPROFILE").unwrap();
+        let protocol = xml.find("protocolversion").unwrap();
+        assert!(
+            codepage < no_profile && no_profile < protocol,
+            "sorted: {xml}"
+        );
+        assert_eq!(xml.matches("MustComply").count(), 1, "{xml}");
+
+        let doc = parse(&xml).unwrap();
+        let parsed = OptionSetValue::from_xml(doc.root_element()).unwrap();
+        let no_profile = parsed.get("WINRS_NOPROFILE").unwrap();
+        assert_eq!(no_profile.value, OptionValue::Text("TRUE".to_owned()));
+        assert_eq!(no_profile.value.as_bool(), Some(true));
+        assert_eq!(no_profile.must_comply, None);
+        assert_eq!(
+            parsed.get("WINRS_CODEPAGE").unwrap().value.as_int(),
+            Some(65001)
+        );
+        let protocol = parsed.get("protocolversion").unwrap();
+        assert_eq!(protocol, "2.3");
+        assert_eq!(protocol.must_comply, Some(true));
+    }
+
+    #[test]
+    fn rejects_invalid_must_comply() {
+        let xml = format!(
+            r#"<w:OptionSet xmlns:w="{W}"><w:Option Name="x" MustComply="maybe">v</w:Option></w:OptionSet>"#
+        );
+        let doc = parse(&xml).unwrap();
+        assert!(OptionSetValue::from_xml(doc.root_element()).is_err());
+    }
+
     #[test]
     fn rejects_selector_without_name() {
         let xml =
@@ -183,6 +493,54 @@ mod tests {
         assert!(SelectorSetValue::from_xml(doc.root_element()).is_err());
     }
 
+    fn items_count_estimate(xml: &str) -> Result<ItemsCountEstimate, ironposh_xml::XmlError> {
+        let doc = parse(xml).unwrap();
+        TotalItemsCountEstimate::from_xml(doc.root_element()).map(|tag| tag.value)
+    }
+
+    #[test]
+    fn total_items_count_estimate_roundtrips_count_and_nil() {
+        for estimate in [ItemsCountEstimate(Some(42)), ItemsCountEstimate(None)] {
+            let element = TotalItemsCountEstimate::new(estimate)
+                .with_declaration(crate::cores::Namespace::DmtfWsmanSchema)
+                .with_declaration(crate::cores::Namespace::XmlSchemaInstance)
+                .into_element();
+            let xml = ironposh_xml::builder::Builder::new(None, element)
+                .to_xml_string()
+                .unwrap();
+            assert_eq!(items_count_estimate(&xml).unwrap(), estimate, "{xml}");
+        }
+
+        let xsi = "http://www.w3.org/2001/XMLSchema-instance";
+        let nil_with_text = format!(
+            r#"<w:TotalItemsCountEstimate xmlns:w="{W}" xmlns:xsi="{xsi}" xsi:nil="true">3</w:TotalItemsCountEstimate>"#
+        );
+        assert!(items_count_estimate(&nil_with_text).is_err());
+        let not_a_count =
+            format!(r#"<w:TotalItemsCountEstimate xmlns:w="{W}">many</w:TotalItemsCountEstimate>"#);
+        assert!(items_count_estimate(&not_a_count).is_err());
+    }
+
+    #[test]
+    fn enumeration_and_fragment_headers_parse_from_soap_headers() {
+        let xml = format!(
+            r#"<s:Header xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:w="{W}"><w:FragmentTransfer s:mustUnderstand="true">Config/MaxEnvelopeSizekb</w:FragmentTransfer><w:TotalItemsCountEstimate>7</w:TotalItemsCountEstimate></s:Header>"#
+        );
+        let doc = parse(&xml).unwrap();
+        let headers = crate::soap::header::SoapHeaders::from_xml(doc.root_element()).unwrap();
+
+        let fragment = headers.fragment_transfer.unwrap();
+        assert_eq!(fragment.value.as_ref(), "Config/MaxEnvelopeSizekb");
+        assert!(matches!(
+            fragment.attributes.as_slice(),
+            [cores::Attribute::MustUnderstand(true)]
+        ));
+        assert_eq!(
+            headers.total_items_count_estimate.unwrap().value,
+            ItemsCountEstimate(Some(7))
+        );
+    }
+
     #[test]
     fn rejects_selectorset_with_stray_text() {
         let xml = format!(
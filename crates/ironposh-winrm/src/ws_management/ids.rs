@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of the UUIDs stamped into outgoing envelopes (MessageID,
+/// OperationID, SessionID) and of client-chosen shell/runspace pool ids.
+///
+/// Production code uses [`RandomIds`]; tests inject [`SequentialIds`] so the
+/// same call sequence yields byte-identical envelopes for golden files.
+pub trait IdGenerator: std::fmt::Debug + Send + Sync {
+    fn next_id(&self) -> uuid::Uuid;
+}
+
+/// Random v4 UUIDs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> uuid::Uuid {
+        uuid::Uuid::new_v4()
+    }
+}
+
+/// Deterministic ids: `00000000-0000-4000-8000-000000000001`, `...0002`, ...
+///
+/// Shaped as v4 UUIDs so anything validating the version still accepts them.
+#[derive(Debug, Default)]
+pub struct SequentialIds {
+    issued: AtomicU64,
+}
+
+impl SequentialIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> uuid::Uuid {
+        let n = self.issued.fetch_add(1, Ordering::Relaxed) + 1;
+        uuid::Uuid::from_u128(0x0000_0000_0000_4000_8000_0000_0000_0000 | u128::from(n))
+    }
+}
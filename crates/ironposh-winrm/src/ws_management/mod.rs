@@ -1,11 +1,17 @@
 pub mod body;
 pub mod header;
+pub mod ids;
 pub use header::*;
+pub use ids::{IdGenerator, RandomIds, SequentialIds};
+
+use std::sync::Arc;
+
+use ironposh_xml::builder::SerializationScratch;
 
 use crate::{
     cores::{Action, Attribute, Tag, Time, WsUuid, namespace::Namespace, tag_value::Text},
     soap::{Envelope, SoapEnvelope, body::SoapBody, header::SoapHeaders},
-    ws_addressing::AddressValue,
+    ws_addressing::EndpointReference,
 };
 
 #[derive(Debug, Clone, typed_builder::TypedBuilder)]
@@ -29,7 +35,11 @@ pub struct WsMan {
     #[builder(default = "http://schemas.microsoft.com/powershell/Microsoft.PowerShell".to_string())]
     resource_uri: String,
 
-    #[builder(default = uuid::Uuid::new_v4())]
+    /// Source of MessageID/OperationID values and the default SessionID.
+    #[builder(default = Arc::new(RandomIds))]
+    ids: Arc<dyn IdGenerator>,
+
+    #[builder(default = ids.next_id())]
     session_id: uuid::Uuid,
 
     to: String,
@@ -43,32 +53,45 @@ impl WsMan {
     pub fn resource_uri(&self) -> &str {
         &self.resource_uri
     }
+
+    /// The id generator, shared with the runspace pool built on this connection.
+    pub fn ids(&self) -> &Arc<dyn IdGenerator> {
+        &self.ids
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum WsAction {
     Create,
+    CreateResponse,
     Delete,
+    DeleteResponse,
     Get,
     Put,
     Command,
     CommandResponse,
     ShellReceive,
+    ReceiveResponse,
     ShellCreate,
     Send,
+    SendResponse,
     Signal,
+    SignalResponse,
     Disconnect,
     DisconnectResponse,
     Reconnect,
     ReconnectResponse,
     Connect,
+    ConnectResponse,
 }
 
 impl WsAction {
     pub fn as_str(&self) -> &str {
         match self {
             Self::Create => "http://schemas.xmlsoap.org/ws/2004/09/transfer/Create",
+            Self::CreateResponse => "http://schemas.xmlsoap.org/ws/2004/09/transfer/CreateResponse",
             Self::Delete => "http://schemas.xmlsoap.org/ws/2004/09/transfer/Delete",
+            Self::DeleteResponse => "http://schemas.xmlsoap.org/ws/2004/09/transfer/DeleteResponse",
             Self::Get => "http://schemas.xmlsoap.org/ws/2004/09/transfer/Get",
             Self::Put => "http://schemas.xmlsoap.org/ws/2004/09/transfer/Put",
             Self::Command => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Command",
@@ -76,9 +99,18 @@ impl WsAction {
                 "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/CommandResponse"
             }
             Self::ShellReceive => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Receive", // See note below
+            Self::ReceiveResponse => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReceiveResponse"
+            }
             Self::ShellCreate => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/create",
             Self::Send => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Send",
+            Self::SendResponse => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/SendResponse"
+            }
             Self::Signal => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Signal",
+            Self::SignalResponse => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/SignalResponse"
+            }
             Self::Disconnect => {
                 "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Disconnect"
             }
@@ -90,30 +122,51 @@ impl WsAction {
                 "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ReconnectResponse"
             }
             Self::Connect => "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Connect",
+            Self::ConnectResponse => {
+                "http://schemas.microsoft.com/wbem/wsman/1/windows/shell/ConnectResponse"
+            }
         }
     }
+
+    /// Whether `action` is one of the `a:Action` values a server answers a
+    /// failed request with (WS-Management or WS-Addressing fault), whatever
+    /// the request was.
+    pub fn is_fault(action: &str) -> bool {
+        matches!(
+            action,
+            "http://schemas.dmtf.org/wbem/wsman/1/wsman/fault"
+                | "http://schemas.xmlsoap.org/ws/2004/08/addressing/fault"
+                | "http://www.w3.org/2005/08/addressing/soap/fault"
+        )
+    }
 }
 
 impl WsMan {
-    pub fn invoke<'a>(
-        &'a self,
+    /// Build a request and render it into `scratch`. The XML borrows the
+    /// scratch buffer until its next render.
+    pub fn invoke<'s>(
+        &self,
+        scratch: &'s mut SerializationScratch,
         action: &WsAction,
-        resource_uri: Option<&'a str>,
-        resource_body: SoapBody<'a>,
+        resource_uri: Option<&str>,
+        resource_body: SoapBody<'_>,
         option_set: Option<header::OptionSetValue>,
         selector_set: Option<header::SelectorSetValue>,
-    ) -> Envelope<'a> {
-        self.invoke_with_operation_timeout(
+    ) -> Result<&'s str, ironposh_xml::XmlError> {
+        let envelope = self.envelope(
             action,
             resource_uri,
             resource_body,
             option_set,
             selector_set,
             None,
-        )
+        );
+        scratch.render(&envelope.into_element())
     }
 
-    pub fn invoke_with_operation_timeout<'a>(
+    /// The request envelope [`Self::invoke`] renders, for callers that need
+    /// its headers (e.g. the MessageID) or a per-request OperationTimeout.
+    pub fn envelope<'a>(
         &'a self,
         action: &WsAction,
         resource_uri: Option<&'a str>,
@@ -123,16 +176,16 @@ impl WsMan {
         operation_timeout_secs: Option<f64>,
     ) -> Envelope<'a> {
         // Generate a unique message ID and operation ID for this request
-        let message_id = uuid::Uuid::new_v4();
-        let operation_id = uuid::Uuid::new_v4();
+        let message_id = self.ids.next_id();
+        let operation_id = self.ids.next_id();
 
         let resource_uri = resource_uri.unwrap_or(self.resource_uri.as_str());
         let operation_timeout_secs = operation_timeout_secs.unwrap_or(self.operation_timeout);
 
         // Create reply-to address value
-        let reply_to_addr = AddressValue {
-            url: Tag::new("http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous"),
-        };
+        let reply_to_addr = EndpointReference::new(
+            "http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous",
+        );
 
         // Create the SOAP header with all required fields
         let header = SoapHeaders::builder()
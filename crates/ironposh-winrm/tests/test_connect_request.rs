@@ -4,7 +4,7 @@ use ironposh_winrm::{
     soap::{SoapEnvelope, body::SoapBody},
     ws_management::{SelectorSetValue, WsAction, WsMan},
 };
-use ironposh_xml::mapping::FromXml;
+use ironposh_xml::{builder::SerializationScratch, mapping::FromXml};
 
 const SHELL_ID: &str = "2D6534D0-6B12-40E3-B773-CBA26459CFA8";
 const RESOURCE_URI: &str = "http://schemas.microsoft.com/powershell/Microsoft.PowerShell";
@@ -35,17 +35,16 @@ fn test_build_connect_envelope() {
 
     let selector_set = SelectorSetValue::new().add_selector("ShellId", SHELL_ID);
 
-    let envelope = ws_man.invoke(
-        &WsAction::Connect,
-        Some(RESOURCE_URI),
-        SoapBody::builder().connect(connect_tag).build(),
-        None,
-        Some(selector_set),
-    );
-
-    let xml_string = envelope
-        .into_element()
-        .to_xml_string()
+    let mut scratch = SerializationScratch::new();
+    let xml_string = ws_man
+        .invoke(
+            &mut scratch,
+            &WsAction::Connect,
+            Some(RESOURCE_URI),
+            SoapBody::builder().connect(connect_tag).build(),
+            None,
+            Some(selector_set),
+        )
         .expect("Failed to build XML");
 
     assert!(
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use ironposh_winrm::{
+    soap::body::SoapBody,
+    ws_management::{SequentialIds, WsAction, WsMan},
+};
+use ironposh_xml::builder::SerializationScratch;
+
+fn get_envelope_xml(wsman: &WsMan) -> String {
+    wsman
+        .invoke(
+            &mut SerializationScratch::new(),
+            &WsAction::Get,
+            None,
+            SoapBody::builder().build(),
+            None,
+            None,
+        )
+        .expect("failed to build XML")
+        .to_owned()
+}
+
+fn sequential_wsman() -> WsMan {
+    WsMan::builder()
+        .to("http://example.local/wsman".to_string())
+        .ids(Arc::new(SequentialIds::new()))
+        .build()
+}
+
+#[test]
+fn sequential_ids_produce_byte_identical_envelopes() {
+    let first = sequential_wsman();
+    let second = sequential_wsman();
+
+    assert_eq!(get_envelope_xml(&first), get_envelope_xml(&second));
+    assert_eq!(get_envelope_xml(&first), get_envelope_xml(&second));
+}
+
+#[test]
+fn sequential_ids_fill_session_message_and_operation_ids_in_order() {
+    let xml = get_envelope_xml(&sequential_wsman());
+
+    // SessionID is drawn at build time, then MessageID and OperationID per request.
+    for (element, n) in [("SessionId", 1), ("MessageID", 2), ("OperationID", 3)] {
+        let start = xml
+            .find(&format!(":{element}"))
+            .unwrap_or_else(|| panic!("missing {element}. xml={xml}"));
+        let end = start + xml[start..].find("</").expect("closing tag");
+        assert!(
+            xml[start..end].ends_with(&format!(">uuid:00000000-0000-4000-8000-{n:012x}")),
+            "expected {element} to carry id {n}. xml={xml}"
+        );
+    }
+}
@@ -4,7 +4,7 @@ use ironposh_winrm::{
     soap::{SoapEnvelope, body::SoapBody},
     ws_management::{SelectorSetValue, WsAction, WsMan},
 };
-use ironposh_xml::mapping::FromXml;
+use ironposh_xml::{builder::SerializationScratch, mapping::FromXml};
 
 const SHELL_ID: &str = "2D6534D0-6B12-40E3-B773-CBA26459CFA8";
 const RESOURCE_URI: &str = "http://schemas.microsoft.com/powershell/Microsoft.PowerShell";
@@ -41,17 +41,16 @@ fn test_build_disconnect_envelope() {
 
     let selector_set = SelectorSetValue::new().add_selector("ShellId", SHELL_ID);
 
-    let envelope = ws_man.invoke(
-        &WsAction::Disconnect,
-        Some(RESOURCE_URI),
-        SoapBody::builder().disconnect(disconnect_tag).build(),
-        None,
-        Some(selector_set),
-    );
-
-    let xml_string = envelope
-        .into_element()
-        .to_xml_string()
+    let mut scratch = SerializationScratch::new();
+    let xml_string = ws_man
+        .invoke(
+            &mut scratch,
+            &WsAction::Disconnect,
+            Some(RESOURCE_URI),
+            SoapBody::builder().disconnect(disconnect_tag).build(),
+            None,
+            Some(selector_set),
+        )
         .expect("Failed to build XML");
 
     assert!(
@@ -84,17 +83,16 @@ fn test_build_disconnect_envelope_without_idle_timeout() {
 
     let selector_set = SelectorSetValue::new().add_selector("ShellId", SHELL_ID);
 
-    let envelope = ws_man.invoke(
-        &WsAction::Disconnect,
-        Some(RESOURCE_URI),
-        SoapBody::builder().disconnect(disconnect_tag).build(),
-        None,
-        Some(selector_set),
-    );
-
-    let xml_string = envelope
-        .into_element()
-        .to_xml_string()
+    let mut scratch = SerializationScratch::new();
+    let xml_string = ws_man
+        .invoke(
+            &mut scratch,
+            &WsAction::Disconnect,
+            Some(RESOURCE_URI),
+            SoapBody::builder().disconnect(disconnect_tag).build(),
+            None,
+            Some(selector_set),
+        )
         .expect("Failed to build XML");
 
     assert!(xml_string.contains("<rsp:Disconnect"));
@@ -113,17 +111,16 @@ fn test_build_reconnect_envelope() {
 
     let selector_set = SelectorSetValue::new().add_selector("ShellId", SHELL_ID);
 
-    let envelope = ws_man.invoke(
-        &WsAction::Reconnect,
-        Some(RESOURCE_URI),
-        SoapBody::builder().reconnect(reconnect_tag).build(),
-        None,
-        Some(selector_set),
-    );
-
-    let xml_string = envelope
-        .into_element()
-        .to_xml_string()
+    let mut scratch = SerializationScratch::new();
+    let xml_string = ws_man
+        .invoke(
+            &mut scratch,
+            &WsAction::Reconnect,
+            Some(RESOURCE_URI),
+            SoapBody::builder().reconnect(reconnect_tag).build(),
+            None,
+            Some(selector_set),
+        )
         .expect("Failed to build XML");
 
     assert!(
@@ -2,7 +2,7 @@ use ironposh_winrm::{
     cores::{Tag, tag_name::*, tag_value::Text},
     rsp::shell_value::{ShellTag, ShellValue},
     soap::{Envelope, SoapEnvelope, body::SoapBody, header::SoapHeaders},
-    ws_addressing::{AddressValue, ReplyToTag},
+    ws_addressing::{EndpointReference, ReplyToTag},
     ws_management::header::{OptionSetTag, OptionSetValue},
 };
 
@@ -38,12 +38,14 @@ mod tests {
             .add_option("protocolversion", "2.3");
 
         // Build ReplyTo with Address
-        let reply_to_address = Tag::new(AddressValue {
-            url: Tag::new(Text::from(
+        let reply_to_address = Tag::new(EndpointReference {
+            address: Tag::new(Text::from(
                 "http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous",
             ))
             .with_name(AddressTag)
             .with_attribute(ironposh_winrm::cores::Attribute::MustUnderstand(true)),
+            reference_properties: None,
+            reference_parameters: None,
         })
         .with_name(ReplyToTag)
         .with_attribute(ironposh_winrm::cores::Attribute::MustUnderstand(true));
@@ -242,4 +242,28 @@ mod tests {
             parse_result.unwrap_err()
         );
     }
+
+    /// Test: A bad value deep in the envelope
+    /// Expected: The error names the element path and its byte range
+    #[test]
+    fn test_invalid_value_is_located() {
+        let path = "tests/resources/malformed/empty_body.xml";
+        let xml_content = fs::read_to_string(path)
+            .expect("Failed to read file")
+            .replace("uuid:12345678-1234", "uuid:not-a-uuid");
+
+        let document = ironposh_xml::parser::parse(&xml_content).expect("Valid XML should parse");
+        let err = SoapEnvelope::from_xml(document.root_element()).unwrap_err();
+
+        let location = err.location().expect("error should be located");
+        assert_eq!(location.path, "/Envelope/Header/MessageID");
+        assert!(
+            xml_content[location.range.clone()].starts_with("<a:MessageID>uuid:not-a-uuid"),
+            "range should cover the element: {location}"
+        );
+        assert!(
+            err.to_string()
+                .contains("/Envelope/Header/MessageID, bytes ")
+        );
+    }
 }
@@ -2,7 +2,7 @@ use ironposh_winrm::{
     soap::body::SoapBody,
     ws_management::{WsAction, WsMan},
 };
-use ironposh_xml::builder::Builder;
+use ironposh_xml::builder::SerializationScratch;
 
 #[test]
 fn wsman_operation_timeout_supports_fractional_seconds() {
@@ -11,16 +11,16 @@ fn wsman_operation_timeout_supports_fractional_seconds() {
         .operation_timeout(0.5)
         .build();
 
-    let envelope = wsman.invoke(
-        &WsAction::Get,
-        None,
-        SoapBody::builder().build(),
-        None,
-        None,
-    );
-    let element = envelope.into_element();
-    let xml_string = Builder::new(None, element)
-        .to_xml_string()
+    let mut scratch = SerializationScratch::new();
+    let xml_string = wsman
+        .invoke(
+            &mut scratch,
+            &WsAction::Get,
+            None,
+            SoapBody::builder().build(),
+            None,
+            None,
+        )
         .expect("failed to build XML");
 
     // WS-Management OperationTimeout format is `PT{seconds:.3}S`.
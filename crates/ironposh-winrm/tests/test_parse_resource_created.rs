@@ -58,17 +58,13 @@ mod tests {
         );
         let resource_created = body.resource_created.as_ref().unwrap().as_ref();
 
-        // ResourceCreated now has required fields (Tag<..> not Option<Tag<..>>)
-        // So we can access them directly without checking is_some()
-        // Just verify they exist by referencing them
-        let _ = &resource_created.address;
-        let _ = &resource_created.reference_parameters;
-
-        // Validate ReferenceParameters content
-        // ReferenceParametersValue also has required fields now
-        let ref_params = &resource_created.reference_parameters.value;
-        let _ = &ref_params.resource_uri;
-        let _ = &ref_params.selector_set;
+        // The shell is named by the selectors in its ReferenceParameters.
+        assert!(resource_created.resource_uri().is_some());
+        assert!(
+            resource_created
+                .selector_set()
+                .is_some_and(|selectors| selectors.get("ShellId").is_some())
+        );
 
         // Validate that Shell element is also present (it should be ignored by ResourceCreated parser)
         assert!(body.shell.is_some(), "Body should also have Shell element");
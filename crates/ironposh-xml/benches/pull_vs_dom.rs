@@ -0,0 +1,111 @@
+//! Latency and peak heap of extracting every `<rsp:Stream>` payload from a
+//! ~1 MB ReceiveResponse: roxmltree DOM vs the streaming pull parser.
+//!
+//!     cargo bench -p ironposh-xml --features streaming --bench pull_vs_dom
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use ironposh_xml::mapping::NodeExt;
+use ironposh_xml::stream::{PullEvent, PullParser};
+
+const RSP: &str = "http://schemas.microsoft.com/wbem/wsman/1/windows/shell";
+const TARGET_SIZE: usize = 1024 * 1024;
+const ITERATIONS: u32 = 50;
+
+/// System allocator that tracks live and peak heap usage.
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+fn receive_response() -> String {
+    // 76-char base64 lines are what PSRP fragments look like on the wire.
+    let payload =
+        "AAAAAAAAAAMAAAAAAAAAAAMAAABnAQAAAAUQAgBsDPVb/zVH8pB752JScmEJAAAAAAAAAAAAAAAA".repeat(20);
+    let mut xml = format!(
+        r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:rsp="{RSP}"><s:Body><rsp:ReceiveResponse>"#
+    );
+    while xml.len() < TARGET_SIZE {
+        xml.push_str(
+            r#"<rsp:Stream Name="stdout" CommandId="5F4B2C1A-0D3E-4F5A-9B8C-7D6E5F4A3B2C">"#,
+        );
+        xml.push_str(&payload);
+        xml.push_str("</rsp:Stream>");
+    }
+    xml.push_str("</rsp:ReceiveResponse></s:Body></s:Envelope>");
+    xml
+}
+
+fn dom_streams(xml: &str) -> usize {
+    let document = ironposh_xml::parser::parse(xml).expect("valid document");
+    document
+        .descendants()
+        .filter(|node| node.is_element_named(Some(RSP), "Stream"))
+        .filter_map(|node| node.text())
+        .map(str::len)
+        .sum()
+}
+
+fn pull_streams(xml: &str) -> usize {
+    let mut parser = PullParser::new(xml);
+    let mut total = 0;
+    while let Some(event) = parser.next_event().expect("valid document") {
+        if let PullEvent::Start(start) = event {
+            if start.is_named(Some(RSP), "Stream") {
+                total += parser.read_text().expect("stream text").len();
+            }
+        }
+    }
+    total
+}
+
+fn measure(name: &str, xml: &str, run: fn(&str) -> usize) -> usize {
+    let mut elapsed = Duration::ZERO;
+    let mut peak = 0;
+    let mut result = 0;
+    for _ in 0..ITERATIONS {
+        let baseline = LIVE.load(Ordering::Relaxed);
+        PEAK.store(baseline, Ordering::Relaxed);
+        let start = Instant::now();
+        result = black_box(run(black_box(xml)));
+        elapsed += start.elapsed();
+        peak = peak.max(PEAK.load(Ordering::Relaxed) - baseline);
+    }
+    println!(
+        "{name:>5}: {:>9.3?} / parse, peak heap {:>8} KiB",
+        elapsed / ITERATIONS,
+        peak / 1024
+    );
+    result
+}
+
+fn main() {
+    let xml = receive_response();
+    println!("input: {} KiB, {ITERATIONS} iterations", xml.len() / 1024);
+
+    let dom = measure("dom", &xml, dom_streams);
+    let pull = measure("pull", &xml, pull_streams);
+    assert_eq!(dom, pull, "both parsers must extract the same payload");
+}
@@ -0,0 +1,145 @@
+//! Allocations per render of a Receive-sized request envelope:
+//! `Element::to_xml_string` vs a reused `SerializationScratch`.
+//!
+//! Criterion reports allocator calls (allocations plus reallocations) per
+//! iteration instead of wall time:
+//!
+//!     cargo bench -p ironposh-xml --bench scratch_render
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use ironposh_xml::builder::{Element, SerializationScratch};
+
+/// System allocator that counts allocation calls (including reallocations).
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// Criterion measurement: allocator calls made by the routine.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        ALLOCATIONS.load(Ordering::SeqCst)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        ALLOCATIONS.load(Ordering::SeqCst) - start
+    }
+
+    fn add(&self, v1: &usize, v2: &usize) -> usize {
+        v1 + v2
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    #[allow(clippy::cast_precision_loss)] // counts stay far below 2^52
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+/// Roughly the shape and size of a WinRS Receive request.
+fn envelope() -> Element<'static> {
+    let header = (0..12).fold(Element::new("Header"), |header, i| {
+        header.add_child(
+            Element::new(
+                [
+                    "Action",
+                    "To",
+                    "ResourceURI",
+                    "ReplyTo",
+                    "MessageID",
+                    "SessionId",
+                ][i % 6],
+            )
+            .set_text("http://schemas.microsoft.com/wbem/wsman/1/windows/shell/Receive"),
+        )
+    });
+    let body = Element::new("Body").add_child(
+        Element::new("Receive").add_child(Element::new("DesiredStream").set_text("stdout")),
+    );
+    Element::new("Envelope").add_child(header).add_child(body)
+}
+
+fn scratch_render(c: &mut Criterion<Allocations>) {
+    let element = envelope();
+    let mut group = c.benchmark_group("render_receive");
+
+    group.bench_function("to_xml_string", |b| {
+        b.iter(|| black_box(element.to_xml_string().expect("render")));
+    });
+
+    // Borrowed output: what is left is the writer's namespace bookkeeping.
+    let mut scratch = SerializationScratch::new();
+    group.bench_function("scratch", |b| {
+        b.iter(|| black_box(scratch.render(&element).expect("render").len()));
+    });
+
+    // What the connection pays when it has to own the request.
+    group.bench_function("scratch_to_owned", |b| {
+        b.iter(|| black_box(scratch.render(&element).expect("render").to_owned()));
+    });
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = scratch_render
+}
+criterion_main!(benches);
@@ -52,6 +52,12 @@ impl<'a> Attribute<'a> {
         self
     }
 
+    /// Where C14N puts this attribute: unqualified ones (empty URL) first,
+    /// then by namespace URL, then by name.
+    pub(crate) fn canonical_key(&self) -> (&str, &str) {
+        (self.namespace.as_ref().map_or("", |ns| ns.url), self.name)
+    }
+
     pub fn get_namespaces(
         &self,
         namespaces_set: &mut std::collections::HashSet<crate::builder::Namespace<'a>>,
@@ -67,6 +73,7 @@ impl<'a> crate::builder::NamespaceWrite<'a> for Attribute<'a> {
         &self,
         w: &mut W,
         alias_map: Option<&crate::builder::AliasMap<'a>>,
+        _mode: crate::builder::OutputMode,
     ) -> Result<(), crate::builder::XmlBuilderError> {
         let ns_alias = if let Some(map) = alias_map {
             self.namespace.as_ref().and_then(|ns| map.get(ns)).copied()
@@ -1,4 +1,4 @@
-use crate::builder::{Declaration, Element, NamespaceWrite, XmlBuilderError};
+use crate::builder::{Declaration, Element, NamespaceWrite, OutputMode, XmlBuilderError};
 
 /// Represents a builder for constructing an XML document.
 pub struct Builder<'a> {
@@ -6,6 +6,7 @@ pub struct Builder<'a> {
     declaration: Option<Declaration<'a>>,
     /// The root element of the XML document.
     element: Element<'a>,
+    mode: OutputMode,
 }
 
 impl<'a> Builder<'a> {
@@ -28,15 +29,33 @@ impl<'a> Builder<'a> {
         Builder {
             declaration,
             element,
+            mode: OutputMode::AsBuilt,
         }
     }
 
+    /// Render in `mode`; [`OutputMode::AsBuilt`] unless set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ironposh_xml::builder::{Attribute, Builder, Element, OutputMode};
+    /// let element = Element::new("root")
+    ///     .add_attribute(Attribute::new("b", "2"))
+    ///     .add_attribute(Attribute::new("a", "1"));
+    /// let builder = Builder::new(None, element).with_output_mode(OutputMode::Canonical);
+    /// assert_eq!(builder.to_xml_string().unwrap(), r#"<root a="1" b="2"/>"#);
+    /// ```
+    pub fn with_output_mode(mut self, mode: OutputMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     pub fn write_to<W: std::io::Write>(&self, mut w: W) -> Result<(), XmlBuilderError> {
         if let Some(decl) = &self.declaration {
             decl.write(&mut w)?; // converts to XmlError via From
             w.write_all(b" \n")?;
         }
-        self.element.ns_write(&mut w, None)
+        self.element.ns_write(&mut w, None, self.mode)
     }
 
     pub fn to_xml_string(&self) -> Result<String, XmlBuilderError> {
@@ -3,10 +3,28 @@ use std::{borrow::Cow, collections::HashMap};
 use tracing::error;
 
 use crate::builder::{
-    escape_text, write_escaped_text, AliasMap, Attribute, Namespace, NamespaceWrite,
+    escape_text, write_escaped_text, AliasMap, Attribute, Namespace, NamespaceWrite, OutputMode,
     XmlBuilderError,
 };
 
+/// The declarations of `map` in URL order, or by prefix for
+/// [`OutputMode::Canonical`], so an element renders the same bytes every time
+/// (the map's own order varies from run to run).
+fn sorted_declarations<'m, 'a>(
+    map: &'m HashMap<Namespace<'a>, Option<&'a str>>,
+    mode: OutputMode,
+) -> Vec<(&'m Namespace<'a>, Option<&'a str>)> {
+    let mut declarations: Vec<_> = map.iter().map(|(ns, alias)| (ns, *alias)).collect();
+    match mode {
+        OutputMode::AsBuilt => declarations.sort_unstable_by_key(|(ns, _)| ns.url),
+        // `None` (the default namespace) sorts first, as C14N wants.
+        OutputMode::Canonical => {
+            declarations.sort_unstable_by_key(|(ns, alias)| (*alias, ns.url));
+        }
+    }
+    declarations
+}
+
 #[derive(Debug, Clone)]
 pub enum Content<'a> {
     /// Represents a text content within an XML element.
@@ -190,8 +208,17 @@ impl<'a> Element<'a> {
     }
 
     pub fn to_xml_string(&self) -> Result<String, crate::XmlError> {
+        self.to_xml_string_in(OutputMode::AsBuilt)
+    }
+
+    /// Like [`Self::to_xml_string`], in [`OutputMode::Canonical`] order.
+    pub fn to_canonical_xml_string(&self) -> Result<String, crate::XmlError> {
+        self.to_xml_string_in(OutputMode::Canonical)
+    }
+
+    pub fn to_xml_string_in(&self, mode: OutputMode) -> Result<String, crate::XmlError> {
         let mut buf = Vec::new();
-        self.ns_write(&mut buf, None)?;
+        self.ns_write(&mut buf, None, mode)?;
         Ok(String::from_utf8(buf).map_err(XmlBuilderError::from)?)
     }
 }
@@ -210,6 +237,7 @@ impl<'a> crate::builder::NamespaceWrite<'a> for Element<'a> {
         &self,
         w: &mut W,
         parent_decl_map: Option<&AliasMap<'a>>,
+        mode: OutputMode,
     ) -> Result<(), XmlBuilderError> {
         // Merge alias maps (child overrides parent) – same logic as before:
         let decl_map = match (parent_decl_map, &self.namespaces_declaration) {
@@ -250,7 +278,7 @@ impl<'a> crate::builder::NamespaceWrite<'a> for Element<'a> {
         // Write start tag + namespace declarations (unchanged behavior)
         w.write_fmt(format_args!("<{name}"))?;
         if let Some(this_ns) = &self.namespaces_declaration {
-            for (url, alias) in this_ns {
+            for (url, alias) in sorted_declarations(this_ns, mode) {
                 if let Some(alias) = alias {
                     w.write_fmt(format_args!(" xmlns:{alias}=\"{url}\""))?;
                 } else {
@@ -260,8 +288,16 @@ impl<'a> crate::builder::NamespaceWrite<'a> for Element<'a> {
         }
 
         // Attributes
-        for a in &self.attributes {
-            a.ns_write(w, decl_map.as_deref())?;
+        if mode == OutputMode::Canonical {
+            let mut attributes: Vec<_> = self.attributes.iter().collect();
+            attributes.sort_by_key(|a| a.canonical_key());
+            for a in attributes {
+                a.ns_write(w, decl_map.as_deref(), mode)?;
+            }
+        } else {
+            for a in &self.attributes {
+                a.ns_write(w, decl_map.as_deref(), mode)?;
+            }
         }
 
         // Content
@@ -279,7 +315,7 @@ impl<'a> crate::builder::NamespaceWrite<'a> for Element<'a> {
             Content::Elements(children) => {
                 w.write_all(b">")?;
                 for c in children {
-                    c.ns_write(w, decl_map.as_deref())?;
+                    c.ns_write(w, decl_map.as_deref(), mode)?;
                 }
                 w.write_all(b"</")?;
                 w.write_all(name.as_bytes())?;
@@ -378,7 +414,7 @@ impl crate::builder::NamespaceFmt for Element<'_> {
         write!(f, "<{name}")?;
 
         if let Some(this_namespaces) = &self.namespaces_declaration {
-            for (url, alias) in this_namespaces {
+            for (url, alias) in sorted_declarations(this_namespaces, OutputMode::AsBuilt) {
                 if let Some(alias) = alias {
                     write!(f, " xmlns:{alias}=\"{url}\"")?;
                 } else {
@@ -12,6 +12,7 @@ mod builder_impl;
 mod declaration;
 mod element;
 mod namespace;
+mod scratch;
 
 use std::collections::HashMap;
 
@@ -20,13 +21,29 @@ pub use self::builder_impl::*;
 pub use self::declaration::*;
 pub use self::element::*;
 pub use self::namespace::*;
+pub use self::scratch::*;
 
 pub type AliasMap<'a> = HashMap<Namespace<'a>, Option<&'a str>>;
 
-#[derive(Debug, thiserror::Error)]
+/// How the builders order what XML leaves them free to order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Attributes in the order they were added, namespace declarations by
+    /// URL.
+    #[default]
+    AsBuilt,
+    /// The order of Canonical XML (C14N): namespace declarations by prefix,
+    /// the default namespace first, then attributes without a namespace by
+    /// name, then the rest by namespace URL and name. Two elements that
+    /// differ only in that order render to the same bytes, for golden tests
+    /// and signatures.
+    Canonical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum XmlBuilderError {
     #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[source] crate::Shared<std::io::Error>),
     #[error("UTF-8 error: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
     #[error("Missing alias map for element '{tag}' in namespace '{ns}'")]
@@ -39,11 +56,18 @@ pub enum XmlBuilderError {
     NamespaceHasNoAlias { tag: String, ns: String },
 }
 
+impl From<std::io::Error> for XmlBuilderError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(crate::Shared::new(error))
+    }
+}
+
 pub trait NamespaceWrite<'a> {
     fn ns_write<W: std::io::Write>(
         &self,
         w: &mut W,
         aliases: Option<&AliasMap<'a>>,
+        mode: OutputMode,
     ) -> Result<(), XmlBuilderError>;
 }
 
@@ -183,15 +207,15 @@ mod tests {
         let builder = Builder::new(Some(declaration), element);
         let xml_string = builder.to_xml_string().unwrap();
         // The declaration includes a space after "?>" and before the root element
-        assert!(
-            xml_string.starts_with(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?> "#)
+        assert_eq!(
+            xml_string,
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?> "#,
+                "\n",
+                r#"<ns1:root xmlns:ns1="http://example.com/ns1" xmlns:ns2="http://example.com/ns2" attr1="value1">"#,
+                r#"<ns2:child attr2="value2"/></ns1:root>"#
+            )
         );
-        assert!(xml_string.contains(r"<ns1:root"));
-        assert!(xml_string.contains(r#"xmlns:ns1="http://example.com/ns1""#));
-        assert!(xml_string.contains(r#"xmlns:ns2="http://example.com/ns2""#));
-        assert!(xml_string.contains(r#"attr1="value1""#));
-        assert!(xml_string.contains(r#"<ns2:child attr2="value2"/>"#));
-        assert!(xml_string.ends_with(r"</ns1:root>"));
     }
 
     #[test]
@@ -338,12 +362,11 @@ mod tests {
 
         let builder = Builder::new(None, element);
         let xml_string = builder.to_xml_string().unwrap();
-        // Check that all namespace declarations are present (order may vary due to HashMap)
-        assert!(xml_string.contains(r#"xmlns:ns1="http://example.com/ns1""#));
-        assert!(xml_string.contains(r#"xmlns:ns2="http://example.com/ns2""#));
-        assert!(xml_string.contains(r#"xmlns="http://example.com/default""#));
-        assert!(xml_string.starts_with("<root "));
-        assert!(xml_string.ends_with("/>"));
+        // Declarations come out in URL order.
+        assert_eq!(
+            xml_string,
+            r#"<root xmlns="http://example.com/default" xmlns:ns1="http://example.com/ns1" xmlns:ns2="http://example.com/ns2"/>"#
+        );
     }
 
     // Attribute tests
@@ -509,13 +532,13 @@ mod tests {
 
         let builder = Builder::new(None, root);
         let xml_string = builder.to_xml_string().unwrap();
-        // Check components due to HashMap ordering
-        assert!(xml_string.starts_with("<root"));
-        assert!(xml_string.contains(r#"xmlns:ns1="http://example.com/ns1""#));
-        assert!(xml_string.contains(r#"xmlns:ns2="http://example.com/ns2""#));
-        assert!(xml_string.contains("<ns2:child>"));
-        assert!(xml_string.contains("<ns1:grandchild/>"));
-        assert!(xml_string.ends_with("</ns2:child></root>"));
+        assert_eq!(
+            xml_string,
+            concat!(
+                r#"<root xmlns:ns1="http://example.com/ns1" xmlns:ns2="http://example.com/ns2">"#,
+                "<ns2:child><ns1:grandchild/></ns2:child></root>"
+            )
+        );
     }
 
     #[test]
@@ -531,12 +554,55 @@ mod tests {
 
         let builder = Builder::new(None, root);
         let xml_string = builder.to_xml_string().unwrap();
-        // Check key components due to HashMap ordering
-        assert!(xml_string.contains(r#"<root xmlns:ns1="http://example.com/ns1""#));
-        assert!(xml_string.contains(r"<ns2:child"));
-        assert!(xml_string.contains(r#"xmlns:override="http://example.com/ns1""#));
-        assert!(xml_string.contains(r#"xmlns:ns2="http://example.com/ns2""#));
-        assert!(xml_string.ends_with("/></root>"));
+        assert_eq!(
+            xml_string,
+            concat!(
+                r#"<root xmlns:ns1="http://example.com/ns1">"#,
+                r#"<ns2:child xmlns:override="http://example.com/ns1" xmlns:ns2="http://example.com/ns2"/>"#,
+                "</root>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_canonical_mode_orders_declarations_and_attributes() {
+        let ns = Namespace::new("http://example.com/a");
+        let build = |reversed: bool| {
+            let mut element = Element::new("root")
+                .add_namespace_declaration("http://example.com/z", None)
+                .add_namespace_declaration("http://example.com/b", Some("p2"))
+                .add_namespace_declaration("http://example.com/a", Some("p1"));
+            let mut attributes = vec![
+                Attribute::new("b", "2"),
+                Attribute::new("z", "3").set_namespace(ns.clone()),
+                Attribute::new("a", "1"),
+            ];
+            if reversed {
+                attributes.reverse();
+            }
+            for attribute in attributes {
+                element = element.add_attribute(attribute);
+            }
+            element
+        };
+
+        let expected = concat!(
+            r#"<root xmlns="http://example.com/z" xmlns:p1="http://example.com/a" xmlns:p2="http://example.com/b""#,
+            r#" a="1" b="2" p1:z="3"/>"#
+        );
+        assert_eq!(build(false).to_canonical_xml_string().unwrap(), expected);
+        assert_eq!(build(true).to_canonical_xml_string().unwrap(), expected);
+        let builder = Builder::new(None, build(true)).with_output_mode(OutputMode::Canonical);
+        assert_eq!(builder.to_xml_string().unwrap(), expected);
+
+        // As built: attributes as added, declarations by URL.
+        assert_eq!(
+            build(false).to_xml_string().unwrap(),
+            concat!(
+                r#"<root xmlns:p1="http://example.com/a" xmlns:p2="http://example.com/b" xmlns="http://example.com/z""#,
+                r#" b="2" p1:z="3" a="1"/>"#
+            )
+        );
     }
 
     #[test]
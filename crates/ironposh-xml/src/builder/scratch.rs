@@ -0,0 +1,57 @@
+use crate::builder::{Element, NamespaceWrite, OutputMode};
+
+/// Reusable output buffer for rendering request envelopes.
+///
+/// [`Element::to_xml_string`] starts from an empty `Vec` and grows it by
+/// doubling for every request. A long-lived scratch keeps its capacity across
+/// requests and hands out the rendered XML in place; a caller that has to own
+/// the request pays one exact-size copy instead of the growth reallocations.
+#[derive(Debug, Default)]
+pub struct SerializationScratch {
+    buf: Vec<u8>,
+}
+
+impl SerializationScratch {
+    /// Capacity kept between renders; a one-off huge request (e.g. a large
+    /// Send) does not pin its buffer for the rest of the session.
+    pub const MAX_RETAINED_CAPACITY: usize = 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current capacity of the reusable buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Render `element` into the scratch buffer. The XML stays valid until the
+    /// next render.
+    pub fn render(&mut self, element: &Element<'_>) -> Result<&str, crate::XmlError> {
+        self.buf.clear();
+        self.buf.shrink_to(Self::MAX_RETAINED_CAPACITY);
+        element.ns_write(&mut self.buf, None, OutputMode::AsBuilt)?;
+        Ok(std::str::from_utf8(&self.buf).expect("XML must be UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_matches_to_xml_string_and_keeps_capacity() {
+        let element = Element::new("root")
+            .add_child(Element::new("child").set_text("a < b"))
+            .add_child(Element::new("empty"));
+
+        let mut scratch = SerializationScratch::new();
+        let first = scratch.render(&element).unwrap().to_owned();
+        let capacity = scratch.capacity();
+
+        assert_eq!(first, element.to_xml_string().unwrap());
+        assert!(capacity >= first.len());
+        assert_eq!(scratch.render(&element).unwrap(), first);
+        assert_eq!(scratch.capacity(), capacity);
+    }
+}
@@ -1,10 +1,16 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
 use roxmltree::NodeType;
 
 pub mod builder;
 pub mod mapping;
 pub mod parser;
+#[cfg(feature = "streaming")]
+pub mod stream;
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum XmlError {
     #[error("Invalid XML: {0}")]
     ParserError(#[from] crate::parser::Error),
@@ -36,6 +42,158 @@ pub enum XmlError {
     #[error("Invalid node type: expected '{expected:?}', found {found:?}")]
     InvalidNodeType { expected: NodeType, found: NodeType },
 
+    #[error("XML resource limit exceeded: {resource} is limited to {limit}")]
+    ResourceLimitExceeded {
+        resource: &'static str,
+        limit: usize,
+    },
+
+    /// The streaming parser has no mapping for this element; the document
+    /// has to go through [`parser::parse`] instead.
+    #[error("not supported by the streaming parser: <{0}>")]
+    StreamUnsupported(String),
+
     #[error("This code path is not supposed to be called: {extra_info}")]
     NotSupposeToBeCalled { extra_info: String },
+
+    /// Any of the above, with where in the document it was found.
+    #[error("{source} [{location}]")]
+    Located {
+        source: Box<Self>,
+        location: XmlLocation,
+    },
+}
+
+impl XmlError {
+    /// Attach the location of `node`, unless the error already has one: the
+    /// innermost node an error passes through is the most precise.
+    #[must_use]
+    pub fn at(self, node: parser::Node<'_, '_>) -> Self {
+        self.located(|| XmlLocation::of(node))
+    }
+
+    #[must_use]
+    pub(crate) fn located(self, location: impl FnOnce() -> XmlLocation) -> Self {
+        match self {
+            Self::Located { .. } => self,
+            _ => Self::Located {
+                source: Box::new(self),
+                location: location(),
+            },
+        }
+    }
+
+    pub fn location(&self) -> Option<&XmlLocation> {
+        match self {
+            Self::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location, for matching on what went wrong.
+    pub fn kind(&self) -> &Self {
+        match self {
+            Self::Located { source, .. } => source,
+            _ => self,
+        }
+    }
+}
+
+/// Where in a document an [`XmlError`] was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlLocation {
+    /// Byte range in the source text: the offending node's, or an empty range
+    /// where a malformed document stopped parsing.
+    pub range: Range<usize>,
+    /// Local names of the elements from the root down, e.g.
+    /// `/Envelope/Body/ReceiveResponse/Stream`. Empty when unknown.
+    pub path: String,
+}
+
+impl XmlLocation {
+    /// The location of `node` in its document.
+    pub fn of(node: parser::Node<'_, '_>) -> Self {
+        let mut names: Vec<&str> = node
+            .ancestors()
+            .filter(parser::Node::is_element)
+            .map(|element| element.tag_name().name())
+            .collect();
+        names.reverse();
+        Self {
+            range: node.range(),
+            path: Self::path(names),
+        }
+    }
+
+    /// `/a/b/c` from local names, outermost first.
+    pub(crate) fn path<'a>(names: impl IntoIterator<Item = &'a str>) -> String {
+        names.into_iter().fold(String::new(), |mut path, name| {
+            path.push('/');
+            path.push_str(name);
+            path
+        })
+    }
+
+    /// A position in text that may not have parsed into a tree.
+    pub fn at_byte(offset: usize) -> Self {
+        Self {
+            range: offset..offset,
+            path: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for XmlLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}, ", self.path)?;
+        }
+        if self.range.is_empty() {
+            write!(f, "byte {}", self.range.start)
+        } else {
+            write!(f, "bytes {}..{}", self.range.start, self.range.end)
+        }
+    }
+}
+
+/// An error payload that is not `Clone` itself (e.g. [`std::io::Error`]),
+/// shared so the error carrying it can be. Two are equal when they print the
+/// same.
+#[derive(Debug)]
+pub struct Shared<E>(Arc<E>);
+
+impl<E> Shared<E> {
+    pub fn new(error: E) -> Self {
+        Self(Arc::new(error))
+    }
+
+    pub fn get(&self) -> &E {
+        &self.0
+    }
+}
+
+impl<E> Clone for Shared<E> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<E: fmt::Display> PartialEq for Shared<E> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl<E: fmt::Display> Eq for Shared<E> {}
+
+impl<E: fmt::Display> fmt::Display for Shared<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for Shared<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
 }
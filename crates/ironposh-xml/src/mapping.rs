@@ -52,7 +52,8 @@ pub fn reject_mixed_content(node: Node<'_, '_>) -> Result<(), XmlError> {
             return Err(XmlError::InvalidXml(format!(
                 "<{}> contains unexpected text content",
                 node.tag_name().name()
-            )));
+            ))
+            .at(child));
         }
     }
     Ok(())
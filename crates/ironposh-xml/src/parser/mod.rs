@@ -1,6 +1,6 @@
 pub use roxmltree::*;
 
-use crate::XmlError;
+use crate::{XmlError, XmlLocation};
 
 impl<'a> TryFrom<crate::parser::Node<'a, 'a>> for crate::builder::Element<'a> {
     type Error = crate::XmlError;
@@ -10,7 +10,8 @@ impl<'a> TryFrom<crate::parser::Node<'a, 'a>> for crate::builder::Element<'a> {
             return Err(crate::XmlError::InvalidNodeType {
                 expected: NodeType::Element,
                 found: value.node_type(),
-            });
+            }
+            .at(value));
         }
 
         let tag_name = value.tag_name();
@@ -26,8 +27,146 @@ impl<'a> TryFrom<crate::parser::Node<'a, 'a>> for crate::builder::Element<'a> {
     }
 }
 
+/// Resource limits enforced on every parsed document.
+///
+/// Server payloads are untrusted: a pathological (or buggy) response could nest
+/// deep enough to blow the stack of the recursive CLIXML deserializer, or fan out
+/// into millions of siblings. The size and node limits are enforced before and
+/// while the tree is built, so an oversized document never reaches memory as a
+/// DOM. The defaults are far above anything a real WinRM or PSRP message produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum element nesting depth (the root element is depth 1).
+    pub max_depth: usize,
+    /// Maximum number of child nodes under a single node.
+    pub max_children: usize,
+    /// Maximum size of the document, in bytes.
+    pub max_document_bytes: usize,
+    /// Maximum number of nodes (elements, text, comments) in the document.
+    pub max_nodes: usize,
+}
+
+impl ParseLimits {
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+    pub const DEFAULT_MAX_CHILDREN: usize = 1 << 20;
+    pub const DEFAULT_MAX_DOCUMENT_BYTES: usize = 128 * 1024 * 1024;
+    pub const DEFAULT_MAX_NODES: usize = 1 << 24;
+
+    /// Fail with [`XmlError::ResourceLimitExceeded`] if `xml` is over
+    /// `max_document_bytes`.
+    pub fn check_document_size(&self, xml: &str) -> Result<(), XmlError> {
+        if xml.len() > self.max_document_bytes {
+            return Err(XmlError::ResourceLimitExceeded {
+                resource: "document size",
+                limit: self.max_document_bytes,
+            }
+            .located(|| XmlLocation::at_byte(self.max_document_bytes)));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_children: Self::DEFAULT_MAX_CHILDREN,
+            max_document_bytes: Self::DEFAULT_MAX_DOCUMENT_BYTES,
+            max_nodes: Self::DEFAULT_MAX_NODES,
+        }
+    }
+}
+
 pub fn parse(xml: &str) -> Result<Document<'_>, crate::XmlError> {
-    roxmltree::Document::parse(xml).map_err(crate::XmlError::ParserError)
+    parse_with_limits(xml, ParseLimits::default())
+}
+
+/// Parse `xml`, rejecting documents that exceed `limits` with
+/// [`XmlError::ResourceLimitExceeded`].
+///
+/// Errors carry an [`XmlLocation`]: the byte offset where a malformed
+/// document stopped parsing, or the node that broke a limit.
+pub fn parse_with_limits(xml: &str, limits: ParseLimits) -> Result<Document<'_>, crate::XmlError> {
+    limits.check_document_size(xml)?;
+    let options = ParsingOptions {
+        nodes_limit: u32::try_from(limits.max_nodes).unwrap_or(u32::MAX),
+        ..ParsingOptions::default()
+    };
+    let document = roxmltree::Document::parse_with_options(xml, options).map_err(|error| {
+        if matches!(error, roxmltree::Error::NodesLimitReached) {
+            return crate::XmlError::ResourceLimitExceeded {
+                resource: "nodes",
+                limit: limits.max_nodes,
+            };
+        }
+        let offset = byte_offset(xml, error.pos());
+        crate::XmlError::ParserError(error).located(|| XmlLocation::at_byte(offset))
+    })?;
+    check_limits(&document, limits)?;
+    Ok(document)
+}
+
+/// The byte offset of a 1-based row/column (in characters) position.
+fn byte_offset(xml: &str, pos: TextPos) -> usize {
+    let row_start = match pos.row as usize {
+        0 | 1 => 0,
+        row => xml
+            .match_indices('\n')
+            .nth(row - 2)
+            .map_or(xml.len(), |(newline, _)| newline + 1),
+    };
+    row_start
+        + xml[row_start..]
+            .chars()
+            .take((pos.col as usize).saturating_sub(1))
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+/// Single iterative pass over the tree: `children` holds, per open node, how
+/// many children have been seen so far, so its length is the current depth.
+fn check_limits(document: &Document<'_>, limits: ParseLimits) -> Result<(), XmlError> {
+    // The (non-element) document root is open from the start, so an element
+    // at depth `d` is visited with `d` entries on the stack.
+    let mut children: Vec<usize> = vec![0];
+    let mut next = document.root().first_child();
+    while let Some(node) = next {
+        if let Some(count) = children.last_mut() {
+            *count += 1;
+            if *count > limits.max_children {
+                return Err(XmlError::ResourceLimitExceeded {
+                    resource: "children per node",
+                    limit: limits.max_children,
+                }
+                .at(node));
+            }
+        }
+        if children.len() > limits.max_depth {
+            return Err(XmlError::ResourceLimitExceeded {
+                resource: "nesting depth",
+                limit: limits.max_depth,
+            }
+            .at(node));
+        }
+
+        if let Some(child) = node.first_child() {
+            children.push(0);
+            next = Some(child);
+            continue;
+        }
+        // Close the finished ancestors until one has a next sibling.
+        next = node.next_sibling();
+        let mut ancestor = node;
+        while next.is_none() {
+            let Some(parent) = ancestor.parent() else {
+                break;
+            };
+            children.pop();
+            next = parent.next_sibling();
+            ancestor = parent;
+        }
+    }
+    Ok(())
 }
 
 /// Legacy visitor-based deserialization.
@@ -71,8 +210,11 @@ impl<'a> NodeDeserializer<'a> {
     where
         V: XmlVisitor<'a>,
     {
-        visitor.visit_node(self.root)?;
-        visitor.finish()
+        let root = self.root;
+        visitor
+            .visit_node(root)
+            .and_then(|()| visitor.finish())
+            .map_err(|error| error.at(root))
     }
 }
 
@@ -85,3 +227,131 @@ pub trait XmlDeserialize<'a>: Sized {
         NodeDeserializer::new(node).deserialize(Self::visitor())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested(depth: usize) -> String {
+        format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth))
+    }
+
+    #[test]
+    fn depth_at_limit_is_accepted() {
+        let limits = ParseLimits {
+            max_depth: 4,
+            ..ParseLimits::default()
+        };
+        assert!(parse_with_limits(&nested(4), limits).is_ok());
+    }
+
+    #[test]
+    fn depth_over_limit_is_rejected() {
+        let limits = ParseLimits {
+            max_depth: 4,
+            ..ParseLimits::default()
+        };
+        let err = parse_with_limits(&nested(5), limits).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "nesting depth",
+                limit: 4
+            }
+        ));
+        let location = err.location().expect("located");
+        assert_eq!(location.path, "/a/a/a/a/a");
+        assert_eq!(location.range, 12..19);
+    }
+
+    #[test]
+    fn too_many_children_is_rejected() {
+        let limits = ParseLimits {
+            max_children: 3,
+            ..ParseLimits::default()
+        };
+        assert!(parse_with_limits("<r><c/><c/><c/></r>", limits).is_ok());
+        let err = parse_with_limits("<r><c/><c/><c/><c/></r>", limits).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "children per node",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn oversized_document_is_rejected_before_parsing() {
+        let limits = ParseLimits {
+            max_document_bytes: 8,
+            ..ParseLimits::default()
+        };
+        assert!(parse_with_limits("<r></r>", limits).is_ok());
+        // Not even well-formed: the size check must fire before the parser runs.
+        let err = parse_with_limits("<r><unclosed>", limits).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "document size",
+                limit: 8
+            }
+        ));
+    }
+
+    #[test]
+    fn node_limit_stops_the_parser() {
+        let limits = ParseLimits {
+            max_nodes: 4,
+            ..ParseLimits::default()
+        };
+        // Document root + <r> + two children.
+        assert!(parse_with_limits("<r><c/><c/></r>", limits).is_ok());
+        let err = parse_with_limits("<r><c/><c/><c/></r>", limits).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "nodes",
+                limit: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn syntax_error_reports_its_byte_offset() {
+        let xml = "<Envelope>\n  <Body>\u{e9}\u{e9}</Bod>\n</Envelope>";
+        let err = parse(xml).unwrap_err();
+        assert!(matches!(err.kind(), XmlError::ParserError(_)));
+        let location = err.location().expect("located");
+        assert_eq!(&xml[location.range.start..], "</Bod>\n</Envelope>");
+        assert!(location.path.is_empty());
+        assert!(err.to_string().ends_with("[byte 23]"), "{err}");
+    }
+
+    #[test]
+    fn errors_are_clone_and_comparable() {
+        let err = parse("<a>").unwrap_err();
+        assert_eq!(err.clone(), err);
+
+        let io = crate::builder::XmlBuilderError::from(std::io::Error::other("disk full"));
+        assert_eq!(io.clone(), io);
+        assert_eq!(io.to_string(), "IO error: disk full");
+    }
+
+    #[test]
+    fn at_keeps_the_innermost_location() {
+        let doc = parse("<Envelope><Body><Stream>x</Stream></Body></Envelope>").unwrap();
+        let stream = doc
+            .descendants()
+            .find(|n| n.has_tag_name("Stream"))
+            .unwrap();
+        let err = XmlError::InvalidXml("bad stream".into())
+            .at(stream)
+            .at(doc.root_element());
+        assert_eq!(
+            err.to_string(),
+            "Invalid XML: bad stream [/Envelope/Body/Stream, bytes 16..34]"
+        );
+        assert_eq!(err.kind(), &XmlError::InvalidXml("bad stream".into()));
+    }
+}
@@ -0,0 +1,632 @@
+//! Streaming pull parser (feature `streaming`).
+//!
+//! [`crate::parser::parse`] builds a full roxmltree document, which is the right
+//! trade-off for SOAP envelopes but wasteful when a multi-megabyte Receive
+//! response is scanned once for its `<rsp:Stream>` payloads. [`PullParser`]
+//! walks the input front to back and hands out events borrowing from it, so
+//! memory use is bounded by the nesting depth rather than the document size.
+//!
+//! Supported subset: elements, attributes, namespaces, text, CDATA, the five
+//! predefined entities and character references. Comments and processing
+//! instructions are skipped; DTDs are rejected.
+
+use std::borrow::Cow;
+
+use crate::parser::ParseLimits;
+use crate::{XmlError, XmlLocation};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// An element start tag, with its name resolved against the in-scope namespaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartElement<'a> {
+    pub namespace: Option<Cow<'a, str>>,
+    pub local_name: &'a str,
+    /// Attributes other than namespace declarations.
+    pub attributes: Vec<Attribute<'a>>,
+}
+
+impl StartElement<'_> {
+    /// `true` iff the expanded name equals `(ns, local)`; the prefix is irrelevant.
+    pub fn is_named(&self, ns: Option<&str>, local: &str) -> bool {
+        self.local_name == local && self.namespace.as_deref() == ns
+    }
+
+    /// Value of the un-namespaced attribute `local`.
+    pub fn attribute(&self, local: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.namespace.is_none() && attribute.local_name == local)
+            .map(|attribute| attribute.value.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub namespace: Option<Cow<'a, str>>,
+    pub local_name: &'a str,
+    pub value: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullEvent<'a> {
+    Start(StartElement<'a>),
+    /// Closes the most recent open element (also emitted for `<empty/>`).
+    End,
+    Text(Cow<'a, str>),
+}
+
+/// Streaming counterpart of [`crate::mapping::FromXml`]: build `Self` from the
+/// element whose start tag was just read, consuming it up to its end tag.
+pub trait FromXmlStream<'a>: Sized {
+    fn from_xml_stream(
+        start: StartElement<'a>,
+        parser: &mut PullParser<'a>,
+    ) -> Result<Self, XmlError>;
+}
+
+/// Read the document `xml` as a `T`, the streaming counterpart of
+/// `T::from_xml(parse_with_limits(xml, limits)?.root_element())`.
+pub fn from_str_with_limits<'a, T: FromXmlStream<'a>>(
+    xml: &'a str,
+    limits: ParseLimits,
+) -> Result<T, XmlError> {
+    let mut parser = PullParser::with_limits(xml, limits);
+    let Some(PullEvent::Start(root)) = parser.next_event()? else {
+        return Err(parser.error("document has no root element"));
+    };
+    let value = T::from_xml_stream(root, &mut parser)?;
+    if parser.depth() > 0 {
+        return Err(parser.error("root element was not read to its end"));
+    }
+    match parser.next_event()? {
+        None => Ok(value),
+        Some(_) => Err(parser.error("content after the root element")),
+    }
+}
+
+#[derive(Debug)]
+struct Scope<'a> {
+    qname: &'a str,
+    /// Namespace declarations on this element: prefix (`""` for the default) → URI.
+    bindings: Vec<(&'a str, Cow<'a, str>)>,
+    children: usize,
+}
+
+/// Pull parser over an in-memory document.
+#[derive(Debug)]
+pub struct PullParser<'a> {
+    input: &'a str,
+    pos: usize,
+    limits: ParseLimits,
+    scopes: Vec<Scope<'a>>,
+    root_children: usize,
+    /// A self-closing tag was just reported; its `End` is still owed.
+    pending_end: bool,
+    seen_root: bool,
+}
+
+impl<'a> PullParser<'a> {
+    pub fn new(xml: &'a str) -> Self {
+        Self::with_limits(xml, ParseLimits::default())
+    }
+
+    pub fn with_limits(xml: &'a str, limits: ParseLimits) -> Self {
+        Self {
+            input: xml.strip_prefix('\u{feff}').unwrap_or(xml),
+            pos: 0,
+            limits,
+            scopes: Vec::new(),
+            root_children: 0,
+            pending_end: false,
+            seen_root: false,
+        }
+    }
+
+    /// Number of currently open elements.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// The next event, or `None` once the root element has been closed and
+    /// only trailing whitespace/comments remain.
+    pub fn next_event(&mut self) -> Result<Option<PullEvent<'a>>, XmlError> {
+        if self.pos == 0 {
+            self.limits.check_document_size(self.input)?;
+        }
+        if self.pending_end {
+            self.pending_end = false;
+            self.scopes.pop();
+            return Ok(Some(PullEvent::End));
+        }
+
+        let input = self.input;
+        loop {
+            let rest = &input[self.pos..];
+            if rest.is_empty() {
+                if let Some(scope) = self.scopes.last() {
+                    return Err(
+                        self.error(&format!("unexpected end of input inside <{}>", scope.qname))
+                    );
+                }
+                if !self.seen_root {
+                    return Err(self.error("document has no root element"));
+                }
+                return Ok(None);
+            }
+
+            let Some(tag) = rest.strip_prefix('<') else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let raw = &rest[..end];
+                if self.scopes.is_empty() {
+                    if !raw.trim().is_empty() {
+                        return Err(self.error("text outside the root element"));
+                    }
+                    self.pos += end;
+                    continue;
+                }
+                self.pos += end;
+                self.note_child()?;
+                return Ok(Some(PullEvent::Text(unescape(raw)?)));
+            };
+
+            if tag.starts_with('?') {
+                self.skip_past("?>")?;
+            } else if tag.starts_with("!--") {
+                self.skip_past("-->")?;
+            } else if let Some(cdata) = tag.strip_prefix("![CDATA[") {
+                let end = cdata
+                    .find("]]>")
+                    .ok_or_else(|| self.error("unterminated CDATA section"))?;
+                if self.scopes.is_empty() {
+                    return Err(self.error("CDATA outside the root element"));
+                }
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+                self.note_child()?;
+                return Ok(Some(PullEvent::Text(Cow::Borrowed(&cdata[..end]))));
+            } else if tag.starts_with('!') {
+                return Err(self.error("DTDs are not supported"));
+            } else if let Some(end_tag) = tag.strip_prefix('/') {
+                return self.read_end_tag(end_tag).map(Some);
+            } else {
+                return self.read_start_tag(tag).map(Some);
+            }
+        }
+    }
+
+    /// Collect the text content of the element whose start tag was just read,
+    /// consuming its end tag. Child elements are an error.
+    pub fn read_text(&mut self) -> Result<Cow<'a, str>, XmlError> {
+        let mut text = Cow::Borrowed("");
+        loop {
+            match self.next_event()? {
+                Some(PullEvent::Text(chunk)) => {
+                    if text.is_empty() {
+                        text = chunk;
+                    } else {
+                        text.to_mut().push_str(&chunk);
+                    }
+                }
+                Some(PullEvent::End) => return Ok(text),
+                Some(PullEvent::Start(start)) => {
+                    return Err(self.error(&format!(
+                        "unexpected element <{}> in text content",
+                        start.local_name
+                    )));
+                }
+                None => return Err(self.error("unexpected end of input in text content")),
+            }
+        }
+    }
+
+    /// The next child element of the element whose start tag was just read,
+    /// or `None` once its end tag has been consumed. Whitespace between
+    /// children is skipped; other text is mixed content and an error.
+    pub fn next_child(&mut self) -> Result<Option<StartElement<'a>>, XmlError> {
+        loop {
+            match self.next_event()? {
+                Some(PullEvent::Start(start)) => return Ok(Some(start)),
+                Some(PullEvent::End) => return Ok(None),
+                Some(PullEvent::Text(text)) if text.trim().is_empty() => {}
+                Some(PullEvent::Text(_)) => {
+                    return Err(self.error("unexpected text content in a container element"));
+                }
+                None => return Err(self.error("unexpected end of input")),
+            }
+        }
+    }
+
+    /// Skip the rest of the element whose start tag was just read.
+    pub fn skip_element(&mut self) -> Result<(), XmlError> {
+        let target = self.depth().saturating_sub(1);
+        while self.depth() > target {
+            if self.next_event()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_start_tag(&mut self, tag: &'a str) -> Result<PullEvent<'a>, XmlError> {
+        let close = tag_end(tag).ok_or_else(|| self.error("unterminated start tag"))?;
+        let self_closing = tag[..close].ends_with('/');
+        let body = &tag[..close - usize::from(self_closing)];
+
+        let name_end = body
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(body.len());
+        let qname = &body[..name_end];
+        if qname.is_empty() {
+            return Err(self.error("empty element name"));
+        }
+        let raw_attributes = self.parse_attributes(&body[name_end..])?;
+
+        if self.scopes.is_empty() && self.seen_root {
+            return Err(self.error("multiple root elements"));
+        }
+        if self.scopes.len() >= self.limits.max_depth {
+            return Err(self.locate(XmlError::ResourceLimitExceeded {
+                resource: "nesting depth",
+                limit: self.limits.max_depth,
+            }));
+        }
+        self.note_child()?;
+
+        let mut bindings = Vec::new();
+        let mut plain = Vec::new();
+        for (name, value) in raw_attributes {
+            if name == "xmlns" {
+                bindings.push(("", value));
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                bindings.push((prefix, value));
+            } else {
+                plain.push((name, value));
+            }
+        }
+        self.scopes.push(Scope {
+            qname,
+            bindings,
+            children: 0,
+        });
+        self.seen_root = true;
+        self.pos += 1 + close + 1;
+
+        let (prefix, local_name) = split_qname(qname);
+        let namespace = self.resolve(prefix.unwrap_or(""))?;
+        let attributes = plain
+            .into_iter()
+            .map(|(name, value)| {
+                let (prefix, local_name) = split_qname(name);
+                // Un-prefixed attributes are never in the default namespace.
+                let namespace = match prefix {
+                    Some(prefix) => self.resolve(prefix)?,
+                    None => None,
+                };
+                Ok(Attribute {
+                    namespace,
+                    local_name,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, XmlError>>()?;
+
+        self.pending_end = self_closing;
+        Ok(PullEvent::Start(StartElement {
+            namespace,
+            local_name,
+            attributes,
+        }))
+    }
+
+    fn read_end_tag(&mut self, tag: &'a str) -> Result<PullEvent<'a>, XmlError> {
+        let close = tag
+            .find('>')
+            .ok_or_else(|| self.error("unterminated end tag"))?;
+        let qname = tag[..close].trim_end();
+        match self.scopes.last() {
+            Some(scope) if scope.qname == qname => {}
+            Some(scope) => {
+                return Err(self.error(&format!("expected </{}>, found </{qname}>", scope.qname)));
+            }
+            None => return Err(self.error(&format!("unexpected </{qname}>"))),
+        }
+        self.scopes.pop();
+        self.pos += "</".len() + close + 1;
+        Ok(PullEvent::End)
+    }
+
+    fn parse_attributes(
+        &self,
+        mut rest: &'a str,
+    ) -> Result<Vec<(&'a str, Cow<'a, str>)>, XmlError> {
+        let mut attributes = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return Ok(attributes);
+            }
+            let eq = rest
+                .find('=')
+                .ok_or_else(|| self.error("attribute without a value"))?;
+            let name = rest[..eq].trim_end();
+            let value_part = rest[eq + 1..].trim_start();
+            let quote = value_part
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| self.error("unquoted attribute value"))?;
+            let value_end = value_part[1..]
+                .find(quote)
+                .ok_or_else(|| self.error("unterminated attribute value"))?;
+            attributes.push((name, unescape(&value_part[1..=value_end])?));
+            rest = &value_part[value_end + 2..];
+        }
+    }
+
+    fn resolve(&self, prefix: &str) -> Result<Option<Cow<'a, str>>, XmlError> {
+        if prefix == "xml" {
+            return Ok(Some(Cow::Borrowed(XML_NAMESPACE)));
+        }
+        let binding = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.bindings.iter())
+            .find(|(bound, _)| *bound == prefix);
+        match binding {
+            // `xmlns=""` undeclares the default namespace.
+            Some((_, uri)) if uri.is_empty() => Ok(None),
+            Some((_, uri)) => Ok(Some(uri.clone())),
+            None if prefix.is_empty() => Ok(None),
+            None => Err(self.error(&format!("unbound namespace prefix '{prefix}'"))),
+        }
+    }
+
+    fn note_child(&mut self) -> Result<(), XmlError> {
+        let children = self
+            .scopes
+            .last_mut()
+            .map_or(&mut self.root_children, |scope| &mut scope.children);
+        *children += 1;
+        if *children > self.limits.max_children {
+            return Err(self.locate(XmlError::ResourceLimitExceeded {
+                resource: "children per node",
+                limit: self.limits.max_children,
+            }));
+        }
+        Ok(())
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<(), XmlError> {
+        let end = self.input[self.pos..]
+            .find(terminator)
+            .ok_or_else(|| self.error(&format!("missing '{terminator}'")))?;
+        self.pos += end + terminator.len();
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> XmlError {
+        self.locate(XmlError::InvalidXml(message.to_owned()))
+    }
+
+    /// Attach the current byte offset and the open elements' path.
+    fn locate(&self, error: XmlError) -> XmlError {
+        error.located(|| XmlLocation {
+            range: self.pos..self.pos,
+            path: XmlLocation::path(self.scopes.iter().map(|scope| split_qname(scope.qname).1)),
+        })
+    }
+}
+
+/// Offset of the `>` closing a tag, skipping over quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_qname(qname: &str) -> (Option<&str>, &str) {
+    match qname.split_once(':') {
+        Some((prefix, local)) => (Some(prefix), local),
+        None => (None, qname),
+    }
+}
+
+/// Resolve entity and character references; borrows when there are none.
+fn unescape(raw: &str) -> Result<Cow<'_, str>, XmlError> {
+    if !raw.contains('&') {
+        return Ok(Cow::Borrowed(raw));
+    }
+
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let after = &rest[amp + 1..];
+        let semi = after
+            .find(';')
+            .ok_or_else(|| XmlError::InvalidXml("unterminated entity reference".to_string()))?;
+        let entity = &after[..semi];
+        let resolved = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32)
+                .ok_or_else(|| XmlError::InvalidXml(format!("unknown entity '&{entity};'")))?,
+        };
+        out.push(resolved);
+        rest = &after[semi + 1..];
+    }
+    out.push_str(rest);
+    Ok(Cow::Owned(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSP: &str = "http://schemas.microsoft.com/wbem/wsman/1/windows/shell";
+
+    fn start(event: Option<PullEvent<'_>>) -> StartElement<'_> {
+        match event {
+            Some(PullEvent::Start(start)) => start,
+            other => panic!("expected a start tag, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn resolves_prefixes_and_reads_text() {
+        let xml = format!(
+            r#"<?xml version="1.0"?><s:Body xmlns:s="urn:s" xmlns:rsp="{RSP}"><rsp:Stream Name="stdout" CommandId="A">QUJD</rsp:Stream></s:Body>"#
+        );
+        let mut parser = PullParser::new(&xml);
+
+        assert!(start(parser.next_event().unwrap()).is_named(Some("urn:s"), "Body"));
+        let stream = start(parser.next_event().unwrap());
+        assert!(stream.is_named(Some(RSP), "Stream"));
+        assert_eq!(stream.attribute("Name"), Some("stdout"));
+        assert_eq!(stream.attribute("CommandId"), Some("A"));
+        assert_eq!(parser.read_text().unwrap(), "QUJD");
+        assert_eq!(parser.next_event().unwrap(), Some(PullEvent::End));
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn self_closing_tag_emits_end() {
+        let mut parser = PullParser::new(r#"<a xmlns="urn:d"><b/></a>"#);
+        assert!(start(parser.next_event().unwrap()).is_named(Some("urn:d"), "a"));
+        assert!(start(parser.next_event().unwrap()).is_named(Some("urn:d"), "b"));
+        assert_eq!(parser.next_event().unwrap(), Some(PullEvent::End));
+        assert_eq!(parser.next_event().unwrap(), Some(PullEvent::End));
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn unescapes_entities_and_cdata() {
+        let mut parser = PullParser::new("<a>x &lt;&#65;&#x42;&gt; <![CDATA[<raw>]]></a>");
+        start(parser.next_event().unwrap());
+        assert_eq!(parser.read_text().unwrap(), "x <AB> <raw>");
+    }
+
+    #[test]
+    fn skip_element_consumes_subtree() {
+        let mut parser = PullParser::new("<r><skip><x>1</x><y/></skip><keep/></r>");
+        start(parser.next_event().unwrap());
+        start(parser.next_event().unwrap());
+        parser.skip_element().unwrap();
+        assert_eq!(start(parser.next_event().unwrap()).local_name, "keep");
+    }
+
+    #[test]
+    fn next_child_walks_children_and_rejects_mixed_content() {
+        let mut parser = PullParser::new("<r>\n  <a>1</a>\n  <b/>\n</r>");
+        start(parser.next_event().unwrap());
+        let a = parser.next_child().unwrap().expect("first child");
+        assert_eq!(a.local_name, "a");
+        parser.skip_element().unwrap();
+        assert_eq!(
+            parser
+                .next_child()
+                .unwrap()
+                .expect("second child")
+                .local_name,
+            "b"
+        );
+        parser.skip_element().unwrap();
+        assert_eq!(parser.next_child().unwrap(), None);
+
+        let mut parser = PullParser::new("<r>text<a/></r>");
+        start(parser.next_event().unwrap());
+        assert!(parser.next_child().is_err());
+    }
+
+    #[test]
+    fn from_str_reads_the_root_element() {
+        struct Names(Vec<String>);
+        impl<'a> FromXmlStream<'a> for Names {
+            fn from_xml_stream(
+                _start: StartElement<'a>,
+                parser: &mut PullParser<'a>,
+            ) -> Result<Self, XmlError> {
+                let mut names = Vec::new();
+                while let Some(child) = parser.next_child()? {
+                    names.push(child.local_name.to_owned());
+                    parser.skip_element()?;
+                }
+                Ok(Self(names))
+            }
+        }
+
+        let names: Names =
+            from_str_with_limits("<r><a/><b>x</b></r>", ParseLimits::default()).unwrap();
+        assert_eq!(names.0, ["a", "b"]);
+        assert!(
+            from_str_with_limits::<Names>("<r/><r/>", ParseLimits::default()).is_err(),
+            "a second root element"
+        );
+    }
+
+    #[test]
+    fn mismatched_end_tag_is_rejected() {
+        let mut parser = PullParser::new("<a><b></a>");
+        start(parser.next_event().unwrap());
+        start(parser.next_event().unwrap());
+        let err = parser.next_event().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid XML: expected </b>, found </a> [/a/b, byte 6]"
+        );
+    }
+
+    #[test]
+    fn document_size_limit_is_enforced() {
+        let limits = ParseLimits {
+            max_document_bytes: 4,
+            ..ParseLimits::default()
+        };
+        let err = PullParser::with_limits("<a>text</a>", limits)
+            .next_event()
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "document size",
+                limit: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn depth_limit_is_enforced() {
+        let limits = ParseLimits {
+            max_depth: 2,
+            ..ParseLimits::default()
+        };
+        let mut parser = PullParser::with_limits("<a><b><c/></b></a>", limits);
+        start(parser.next_event().unwrap());
+        start(parser.next_event().unwrap());
+        let err = parser.next_event().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            XmlError::ResourceLimitExceeded {
+                resource: "nesting depth",
+                ..
+            }
+        ));
+        assert_eq!(err.location().map(|at| at.path.as_str()), Some("/a/b"));
+    }
+}
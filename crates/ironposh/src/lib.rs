@@ -0,0 +1,127 @@
+//! PowerShell Remoting over WinRM.
+//!
+//! This crate is the supported way to depend on ironposh. It re-exports the
+//! types an application needs from the internal crates (`ironposh-async`,
+//! `ironposh-client-core`, `ironposh-psrp`, ...) under one namespace:
+//!
+//! - [`config`]: where and how to connect ([`WinRmConfig`]).
+//! - [`auth`]: credentials and the authentication method.
+//! - [`client`]: the async client, its session hooks and the `HttpClient`
+//!   seam it runs on.
+//! - [`pipeline`]: what to run and the events a running pipeline produces.
+//! - [`host`]: host calls made by the server into the client.
+//! - [`value`]: PowerShell objects as they come over the wire.
+//! - [`error`]: the errors the session reports.
+//!
+//! Paths in this crate follow semver: a re-export is only removed or moved in
+//! a breaking release. The internal crates make no such promise, and their
+//! module layout changes as the implementation does. [`WinRmConfig`] and
+//! [`PwshCoreError`] are `#[non_exhaustive]` so settings and error variants
+//! can be added in minor releases: build the config with
+//! [`WinRmConfig::builder`], and keep a wildcard arm when matching errors.
+//!
+//! ```no_run
+//! use futures::StreamExt as _;
+//! use ironposh::client::{HttpClient, RemoteAsyncPowershellClient, SessionHooks};
+//! use ironposh::pipeline::UserEvent;
+//! use ironposh::WinRmConfig;
+//!
+//! async fn get_date(config: WinRmConfig, http: impl HttpClient) -> anyhow::Result<()> {
+//!     let session = RemoteAsyncPowershellClient::open_task(config, None, http, SessionHooks::default());
+//!     let mut client = session.client;
+//!     // Poll `session.connection_task` on the runtime in use, then:
+//!     let mut events = client.send_script_raw("Get-Date".to_owned()).await?;
+//!     while let Some(event) = events.next().await {
+//!         if let UserEvent::PipelineOutput { output, .. } = event {
+//!             println!("{}", output.data);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use config::WinRmConfig;
+pub use error::PwshCoreError;
+
+/// Connection settings.
+pub mod config {
+    pub use ironposh_client_core::TransportSecurity;
+    pub use ironposh_client_core::connector::WinRmConfig;
+    pub use ironposh_client_core::connector::config::{
+        AffinityConfig, DefragmentLimits, HostMode, IdleAction, IdlePolicy, ParseLimits, ParseMode,
+        ReceiveFlowConfig, ServicePrincipal, TlsOptions,
+    };
+    pub use ironposh_client_core::connector::http::{AddressPreference, ServerAddress};
+    pub use ironposh_psrp::{
+        ApplicationArguments, HostDefaultData, HostDefaultDataError, HostInfo, Size,
+    };
+}
+
+/// Credentials and authentication methods.
+pub mod auth {
+    #[cfg(feature = "pass-the-hash")]
+    pub use ironposh_client_core::NtHash;
+    pub use ironposh_client_core::connector::config::Delegation;
+    pub use ironposh_client_core::credentials::ClientUserName;
+    #[cfg(feature = "smartcard")]
+    pub use ironposh_client_core::credentials::{
+        DEFAULT_SMART_CARD_CSP, SmartCardIdentity, SmartCardLogon,
+    };
+    pub use ironposh_client_core::{
+        AuthenticatorConfig, ClientAuthIdentity, KdcEndpoint, KerberosConfig, SspiAuthConfig,
+    };
+}
+
+/// The async client and the hooks installed on its session.
+pub mod client {
+    pub use ironposh_async::client::OpenedSession;
+    pub use ironposh_async::{
+        AuditEntry, AuditSink, AuditStatus, HostCallDecision, HostCallPolicy, HostCallRules,
+        HostIo, HostResponse, HostSubmitter, HttpClient, InvokeDecision, InvokeInterceptor,
+        InvokeOutput, MemoryAuditLog, PoolLifecycleEvent, RemoteAsyncPowershellClient,
+        SessionEvent, SessionHooks, StopPolicy,
+    };
+    pub use ironposh_client_core::connector::{HttpResponseTargeted, TrySend};
+    #[cfg(feature = "tokio")]
+    pub use ironposh_client_tokio::http_client::ReqwestHttpClient;
+}
+
+/// Pipelines and the events they produce.
+pub mod pipeline {
+    pub use ironposh_client_core::connector::active_session::UserEvent;
+    pub use ironposh_client_core::pipeline::{
+        Parameter, PipelineCommand, PipelineOptions, PipelineSpec,
+    };
+    pub use ironposh_client_core::powershell::PipelineHandle;
+    pub use ironposh_client_core::psrp_record::PsrpRecord;
+}
+
+/// Host calls from the server.
+pub mod host {
+    pub use ironposh_client_core::host::{
+        BufferCell, ChoiceDescription, Coordinates, FieldDescription, HostCall, HostCallScope,
+        HostError, KeyInfo, PSCredential, ProgressRecord, Rectangle, Size, Submission,
+    };
+    pub use ironposh_psrp::{PipelineHostResponse, RemoteHostMethodId};
+}
+
+/// PowerShell objects.
+pub mod value {
+    pub use ironposh_psrp::ps_value::{
+        ComplexObject, ComplexObjectContent, Container, PsPrimitiveValue, PsValue,
+    };
+    pub use ironposh_psrp::{ErrorRecord, InformationMessageData, PipelineOutput};
+}
+
+/// Errors reported by the session.
+pub mod error {
+    pub use ironposh_client_core::user_message::{EnglishCatalog, UserMessage};
+    pub use ironposh_client_core::{ErrorCode, Phase, PwshCoreError, Quota, RemoteError};
+}
+
+/// The blocking facade, for callers that want results without an async
+/// runtime.
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    pub use ironposh_client_sync::{PowerShellSession, ScriptOutput};
+}
@@ -0,0 +1,19 @@
+//! The CLIXML deserializer (`PsValue`) over arbitrary documents.
+
+#![no_main]
+
+use ironposh_psrp::ps_value::{DeserializationContext, PsValue, PsXmlDeserialize};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(xml) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(document) = ironposh_xml::parser::parse(xml) else {
+        return;
+    };
+    let mut context = DeserializationContext::default();
+    if let Ok(value) = PsValue::from_node_with_context(document.root_element(), &mut context) {
+        let _ = value.to_string();
+    }
+});
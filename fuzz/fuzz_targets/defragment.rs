@@ -0,0 +1,28 @@
+//! The defragmenter fed with arbitrary packets, then every reassembled
+//! message pushed through the typed PSRP parser.
+//!
+//! The first input byte picks the packet size so fragments of one object
+//! straddle `defragment` calls the way they do across Receive responses.
+
+#![no_main]
+
+use ironposh_psrp::fragmentation::{DefragmentResult, Defragmenter};
+use ironposh_psrp::messages::PsrpMessage;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk, data)) = data.split_first() else {
+        return;
+    };
+    let chunk = usize::from(chunk).max(1) * 16;
+
+    let mut defragmenter = Defragmenter::new();
+    for packet in data.chunks(chunk) {
+        let Ok(DefragmentResult::Complete(messages)) = defragmenter.defragment(packet) else {
+            continue;
+        };
+        for message in &messages {
+            let _ = PsrpMessage::parse(message);
+        }
+    }
+});
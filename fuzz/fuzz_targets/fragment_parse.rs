@@ -0,0 +1,19 @@
+//! `PowerShellFragment::parse` over arbitrary bytes, consuming as many
+//! fragments as the input holds.
+
+#![no_main]
+
+use std::io::Cursor;
+
+use ironposh_psrp::PowerShellFragment;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut cursor = Cursor::new(data);
+    while (cursor.position() as usize) < data.len() {
+        let Ok(fragment) = PowerShellFragment::parse(&mut cursor) else {
+            break;
+        };
+        let _ = fragment.into_vec();
+    }
+});
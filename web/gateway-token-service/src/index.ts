@@ -103,7 +103,11 @@ export function getDestinationScheme(transport: GatewayTransport): string {
   return transport === 'Tls' ? 'tls' : 'tcp';
 }
 
-export type SecurityWarning = 'GatewayChannelInsecure' | 'DestinationChannelInsecure' | 'BothChannelsInsecure';
+export type SecurityWarning =
+  | 'GatewayChannelInsecure'
+  | 'DestinationChannelInsecure'
+  | 'BothChannelsInsecure'
+  | 'AuthenticationDisabled';
 
 export function checkSecurity(
   gatewayUrl: string,
@@ -21,7 +21,8 @@ import {
 type SecurityWarning =
   | 'GatewayChannelInsecure'
   | 'DestinationChannelInsecure'
-  | 'BothChannelsInsecure';
+  | 'BothChannelsInsecure'
+  | 'AuthenticationDisabled';
 
 type TableRow = Record<string, string>;
 
@@ -167,6 +168,8 @@ const App: React.FC = () => {
           return '- Destination channel has no encryption (HTTP without SSPI)';
         case 'BothChannelsInsecure':
           return '- BOTH channels are unencrypted! This is extremely dangerous!';
+        case 'AuthenticationDisabled':
+          return '- WinRM authentication is disabled! Only safe if the gateway authenticates the user';
         default:
           return `- Unknown warning: ${w}`;
       }
@@ -398,6 +401,8 @@ const App: React.FC = () => {
           setStatus(`Command error: ${event.error?.message ?? 'unknown error'}`);
         } else if (event.type === 'pipelineFinished') {
           setStatus('Command execution finished.');
+        } else if (event.type === 'pipelineTimedOut') {
+          setStatus(`Command timed out after ${event.timeoutMs} ms and was stopped.`);
         }
       });
     } catch (error) {
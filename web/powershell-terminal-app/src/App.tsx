@@ -58,6 +58,8 @@ function formatSecurityWarnings(warnings: SecurityWarning[]): string {
         return '⚠️ Destination channel is not encrypted (no TLS and SSPI encryption disabled)';
       case 'BothChannelsInsecure':
         return '🚨 BOTH channels are insecure! Gateway uses WS/HTTP and destination has no encryption';
+      case 'AuthenticationDisabled':
+        return '🚨 WinRM authentication is disabled! Only safe if the gateway authenticates the user';
       default:
         return `⚠️ Unknown warning: ${w}`;
     }
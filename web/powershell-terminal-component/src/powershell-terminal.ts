@@ -240,6 +240,18 @@ export class PowerShellTerminalElement extends HTMLElement {
               return;
             }
 
+            // The server broke the runspace pool (service restart, policy kill).
+            if (typeof event === "object" && "broken" in event) {
+              this.handleSessionLost(event.broken.reason);
+              return;
+            }
+
+            // The idle policy is about to disconnect/close the session.
+            if (typeof event === "object" && "idle" in event) {
+              this.handleIdle(event.idle.action);
+              return;
+            }
+
             // The session ended after it was established (task exited / closed).
             if (
               this.state === "connected" &&
@@ -415,6 +427,25 @@ export class PowerShellTerminalElement extends HTMLElement {
     this.emitEvent({ type: "error", detail: new Error(reason) });
   }
 
+  // Idle shutdown is expected, not a failure: say so and close quietly
+  // instead of reporting the session end that follows as a lost connection.
+  private handleIdle(action: "disconnect" | "close"): void {
+    if (this.state === "closed") return;
+    this.isRunning = false;
+    this.runningController = null;
+    this.cancelHostCallInput?.("Session idle");
+    if (this.terminal) {
+      this.terminal.writeln("");
+      this.terminal.writeln(
+        action === "disconnect"
+          ? "\x1b[33mSession idle: disconnected (the shell is kept on the server)\x1b[0m"
+          : "\x1b[33mSession idle: closed\x1b[0m",
+      );
+    }
+    this.setState("closed");
+    this.emitEvent({ type: "disconnected", detail: undefined });
+  }
+
   disconnect(): void {
     if (this.state === "connected" && this.terminal) {
       this.setState("closed");